@@ -102,6 +102,10 @@ impl<'a> FieldInfo<'a> {
             Some(def) => quote!(Some(|| #def.to_dynamic())),
             None => quote!(None),
         };
+        let deprecated = match &self.deprecated {
+            Some(reason) => quote!(Some(#reason)),
+            None => quote!(None),
+        };
         quote!(
             crate::meta::ConfigOption {
                 name: #name,
@@ -110,6 +114,7 @@ impl<'a> FieldInfo<'a> {
                 container: crate::meta::ConfigContainer::#container_type,
                 type_name: #type_name,
                 default_value: #get_default,
+                deprecated: #deprecated,
                 possible_values: &[],
                 fields: &[],
             }
@@ -301,6 +301,27 @@ pub enum SystemBackdrop {
     Tabbed,
 }
 
+/// The system material shown behind the translucent parts of the
+/// window by `macos_window_vibrancy`.  These correspond to the cases
+/// of `NSVisualEffectMaterial`.
+#[derive(Debug, Copy, Clone, FromDynamic, ToDynamic, PartialEq, Eq)]
+pub enum MacOsVibrancy {
+    Titlebar,
+    Selection,
+    Menu,
+    Popover,
+    Sidebar,
+    HeaderView,
+    Sheet,
+    WindowBackground,
+    HudWindow,
+    FullScreenUI,
+    ToolTip,
+    ContentBackground,
+    UnderWindowBackground,
+    UnderPageBackground,
+}
+
 pub fn default_win32_acrylic_accent_color() -> RgbaColor {
     SrgbaTuple(0.156863, 0.156863, 0.156863, 0.003922).into()
 }
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use wezterm_dynamic::{FromDynamic, ToDynamic};
 
 /// <https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function>
@@ -62,6 +63,8 @@ pub struct VisualBell {
 pub enum VisualBellTarget {
     BackgroundColor,
     CursorColor,
+    /// Outline the pane that rang the bell, leaving its content as-is
+    Border,
 }
 
 impl Default for VisualBellTarget {
@@ -81,3 +84,50 @@ impl Default for AudibleBell {
         Self::SystemBeep
     }
 }
+
+/// The events that can be associated with a sound via `event_sounds`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SoundEvent {
+    /// The bell was rung in a pane
+    Bell,
+    /// A command finished running in a pane, as reported by the
+    /// shell integration (OSC 133;D)
+    CommandComplete,
+    /// A toast notification was shown
+    Notification,
+}
+
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct EventSound {
+    /// The sound file to play, in any format supported by the system
+    pub path: PathBuf,
+    /// From 0.0 (silent) to 1.0 (full volume)
+    #[dynamic(default = "default_sound_volume")]
+    pub volume: f32,
+}
+
+fn default_sound_volume() -> f32 {
+    1.0
+}
+
+#[derive(Default, Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct EventSounds {
+    /// Played in place of the system beep when `audible_bell` is
+    /// set to `SystemBeep`
+    #[dynamic(default)]
+    pub bell: Option<EventSound>,
+    #[dynamic(default)]
+    pub command_complete: Option<EventSound>,
+    #[dynamic(default)]
+    pub notification: Option<EventSound>,
+}
+
+impl EventSounds {
+    pub fn sound_for_event(&self, event: SoundEvent) -> Option<&EventSound> {
+        match event {
+            SoundEvent::Bell => self.bell.as_ref(),
+            SoundEvent::CommandComplete => self.command_complete.as_ref(),
+            SoundEvent::Notification => self.notification.as_ref(),
+        }
+    }
+}
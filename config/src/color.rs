@@ -153,6 +153,10 @@ pub struct Palette {
     /// The color of the "thumb" of the scrollbar; the segment that
     /// represents the current viewable area
     pub scrollbar_thumb: Option<RgbaColor>,
+    /// The color of scroll bar markers for search matches
+    pub scrollbar_search_marker: Option<RgbaColor>,
+    /// The color of scroll bar markers for shell prompts
+    pub scrollbar_prompt_marker: Option<RgbaColor>,
     /// The color of the split line between panes
     pub split: Option<RgbaColor>,
     /// The color of the visual bell. If unspecified, the foreground
@@ -160,6 +164,10 @@ pub struct Palette {
     pub visual_bell: Option<RgbaColor>,
     /// The color to use for the cursor when a dead key or leader state is active
     pub compose_cursor: Option<RgbaColor>,
+    /// The colors of the text being composed by the input method.
+    /// If unspecified, the colors of the cell at the cursor are used.
+    pub ime_preedit_fg: Option<RgbaColor>,
+    pub ime_preedit_bg: Option<RgbaColor>,
 
     pub copy_mode_active_highlight_fg: Option<ColorSpec>,
     pub copy_mode_active_highlight_bg: Option<ColorSpec>,
@@ -214,9 +222,13 @@ impl Palette {
                 map
             },
             scrollbar_thumb: overlay!(scrollbar_thumb),
+            scrollbar_search_marker: overlay!(scrollbar_search_marker),
+            scrollbar_prompt_marker: overlay!(scrollbar_prompt_marker),
             split: overlay!(split),
             visual_bell: overlay!(visual_bell),
             compose_cursor: overlay!(compose_cursor),
+            ime_preedit_fg: overlay!(ime_preedit_fg),
+            ime_preedit_bg: overlay!(ime_preedit_bg),
             copy_mode_active_highlight_fg: overlay!(copy_mode_active_highlight_fg),
             copy_mode_active_highlight_bg: overlay!(copy_mode_active_highlight_bg),
             copy_mode_inactive_highlight_fg: overlay!(copy_mode_inactive_highlight_fg),
@@ -754,6 +766,18 @@ impl ColorSchemeFile {
         Self::from_toml_value(&scheme)
     }
 
+    pub fn from_yaml_str(s: &str) -> anyhow::Result<Self> {
+        let scheme: serde_json::Value = serde_yaml::from_str(s)?;
+        let scheme = Self::from_json_value(&scheme)?;
+
+        anyhow::ensure!(
+            scheme.colors.ansi.is_some(),
+            "scheme is missing ANSI colors"
+        );
+
+        Ok(scheme)
+    }
+
     pub fn to_toml_value(&self) -> anyhow::Result<toml::Value> {
         let value = self.to_dynamic();
         Ok(dynamic_to_toml(value)?)
@@ -1,5 +1,5 @@
 use crate::background::{BackgroundLayer, Gradient};
-use crate::bell::{AudibleBell, EasingFunction, VisualBell};
+use crate::bell::{AudibleBell, EasingFunction, EventSounds, VisualBell};
 use crate::color::{
     ColorSchemeFile, HsbTransform, Palette, SrgbaTuple, TabBarStyle, WindowFrameConfig,
 };
@@ -11,27 +11,36 @@ use crate::font::{
 };
 use crate::frontend::FrontEndSelection;
 use crate::keyassignment::{
-    KeyAssignment, KeyTable, KeyTableEntry, KeyTables, MouseEventTrigger, SpawnCommand,
+    KeyAssignment, KeyTable, KeyTableEntry, KeyTables, LaunchMenuItem, MouseEventTrigger,
+    QuickSelectPattern, SearchDirection,
 };
-use crate::keys::{Key, LeaderKey, Mouse};
+use crate::keys::{Key, KeyNoAction, LeaderKeys, Mouse, MouseEventZone};
+use crate::layout::LayoutPane;
 use crate::lua::make_lua_context;
+use crate::output_filter::OutputFilter;
+use crate::profile::validate_profiles;
+use crate::proxy::{
+    resolve_proxy, validate_http_proxy, validate_socks5_proxy, ProxyOverride, ProxyServer,
+};
 use crate::ssh::{SshBackend, SshDomain};
 use crate::tls::{TlsDomainClient, TlsDomainServer};
-use crate::units::Dimension;
+use crate::units::{Dimension, GuiGeometry};
 use crate::unix::UnixDomain;
-use crate::wsl::WslDomain;
+use crate::window::{MacOsWindowButtonPosition, SpawnWindowOn, TabBarButton, WindowPlacementRule};
+use crate::workspace::WorkspaceSpawnDefaults;
+use crate::wsl::{WslDistroDefaults, WslDomain};
 use crate::{
     default_config_with_overrides_applied, default_one_point_oh, default_one_point_oh_f64,
     default_true, default_win32_acrylic_accent_color, CellWidth, GpuInfo,
-    IntegratedTitleButtonColor, KeyMapPreference, LoadedConfig, MouseEventTriggerMods, RgbaColor,
-    SerialDomain, SystemBackdrop, WebGpuPowerPreference, CONFIG_DIRS, CONFIG_FILE_OVERRIDE,
-    CONFIG_OVERRIDES, CONFIG_SKIP,
+    IntegratedTitleButtonColor, KeyMapPreference, LoadedConfig, MacOsVibrancy,
+    MouseEventTriggerMods, RgbaColor, SerialDomain, SystemBackdrop, WebGpuPowerPreference,
+    CONFIG_DIRS, CONFIG_FILE_OVERRIDE, CONFIG_OVERRIDES, CONFIG_SKIP,
 };
 use anyhow::Context;
 use luahelper::impl_lua_conversion_dynamic;
 use mlua::FromLua;
 use portable_pty::CommandBuilder;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
 use std::io::Read;
 use std::path::{Path, PathBuf};
@@ -96,6 +105,17 @@ pub struct Config {
     #[dynamic(default)]
     pub integrated_title_button_color: IntegratedTitleButtonColor,
 
+    /// Additional buttons to show in the tab bar, each bound to
+    /// a key assignment
+    #[dynamic(default)]
+    pub tab_bar_buttons: Vec<TabBarButton>,
+
+    /// When using integrated buttons with the MacOsNative style,
+    /// moves the traffic light buttons to this position rather
+    /// than the system default
+    #[dynamic(default)]
+    pub macos_window_button_position: Option<MacOsWindowButtonPosition>,
+
     /// When using FontKitXXX font systems, a set of directories to
     /// search ahead of the standard font locations for fonts.
     /// Relative paths are taken to be relative to the directory
@@ -169,6 +189,11 @@ pub struct Config {
     #[dynamic(default = "default_command_palette_bg_color")]
     pub command_palette_bg_color: RgbaColor,
 
+    /// The color of the characters in the command palette that
+    /// match the text typed to filter it
+    #[dynamic(default = "default_command_palette_match_fg_color")]
+    pub command_palette_match_fg_color: RgbaColor,
+
     /// Font to use for PaneSelect
     #[dynamic(default)]
     pub pane_select_font: Option<TextStyle>,
@@ -182,6 +207,24 @@ pub struct Config {
     #[dynamic(default = "default_pane_select_bg_color")]
     pub pane_select_bg_color: RgbaColor,
 
+    /// Controls when each pane shows a small badge with the number of
+    /// the key that activates it, eg: 1 for `ActivatePaneByIndex(0)`,
+    /// which is bound to SUPER|ALT+1.  Badges are only shown when
+    /// the tab has more than one pane.
+    #[dynamic(default)]
+    pub pane_index_badges: PaneIndexBadges,
+
+    /// The modifiers that must be held to show the pane index badges
+    /// when `pane_index_badges = "WhileModifiersHeld"`
+    #[dynamic(default = "default_pane_index_badge_modifiers")]
+    pub pane_index_badge_modifiers: Modifiers,
+
+    /// How many cells each press of hjkl or an arrow key moves the
+    /// split while the `resize_pane` key table is active.  Holding
+    /// SHIFT moves it five times as far.
+    #[dynamic(default = "default_resize_pane_step")]
+    pub resize_pane_step: usize,
+
     #[dynamic(default)]
     pub tab_bar_style: TabBarStyle,
 
@@ -192,6 +235,14 @@ pub struct Config {
     /// by the colors setting.
     pub color_scheme: Option<String>,
 
+    /// The color scheme to use in place of `color_scheme` while the
+    /// system appearance is dark
+    pub color_scheme_dark: Option<String>,
+
+    /// The color scheme to use in place of `color_scheme` while the
+    /// system appearance is light
+    pub color_scheme_light: Option<String>,
+
     /// Named color schemes
     #[dynamic(default)]
     pub color_schemes: HashMap<String, Palette>,
@@ -231,6 +282,11 @@ pub struct Config {
     #[dynamic(default)]
     pub exit_behavior_messaging: ExitBehaviorMessaging,
 
+    /// When the program spawned into a pane exits, print a line with
+    /// its exit status, elapsed time, CPU time and peak memory usage
+    #[dynamic(default)]
+    pub pane_exit_summary: bool,
+
     #[dynamic(default = "default_clean_exits")]
     pub clean_exit_codes: Vec<u32>,
 
@@ -264,6 +320,50 @@ pub struct Config {
     #[dynamic(default = "default_initial_cols", validate = "validate_row_or_col")]
     pub initial_cols: u16,
 
+    /// Specifies the size of a new window in pixels or as a percentage
+    /// of a monitor, such as `"80%x60%@primary"` or `"1400x900"`.
+    /// When set, this takes precedence over `initial_cols` and
+    /// `initial_rows`.
+    #[dynamic(default)]
+    pub initial_geometry: Option<GuiGeometry>,
+
+    /// Rules that place new windows on a particular monitor, optionally
+    /// only for windows of a given workspace.  The first matching rule
+    /// whose monitor is connected is applied when a window is created
+    /// and again whenever the set of monitors changes.
+    #[dynamic(default)]
+    pub window_placement_rules: Vec<WindowPlacementRule>,
+
+    /// The monitor on which new windows are centered when neither
+    /// their position nor `window_placement_rules` say otherwise
+    #[dynamic(default)]
+    pub spawn_window_on: SpawnWindowOn,
+
+    /// When true, the size and position of windows is remembered for
+    /// each window class and workspace, and new windows of the same
+    /// class and workspace open with that geometry rather than on the
+    /// `spawn_window_on` monitor.  A geometry saved on a display that
+    /// is no longer connected is ignored.  Only supported on macOS.
+    #[dynamic(default)]
+    pub remember_window_geometry: bool,
+
+    /// A key combination that toggles the dropdown window from
+    /// anywhere, even while another application has focus.
+    /// The dropdown window slides down from the top of the active
+    /// screen and is created the first time that it is toggled.
+    #[dynamic(default)]
+    pub dropdown_hotkey: Option<KeyNoAction>,
+
+    /// The height of the dropdown window as a percentage of the
+    /// height of its screen
+    #[dynamic(default = "default_dropdown_height_percent")]
+    pub dropdown_height_percent: u8,
+
+    /// How long the dropdown window takes to slide in or out.
+    /// 0 shows and hides it without animation.
+    #[dynamic(default = "default_dropdown_animation_duration_ms")]
+    pub dropdown_animation_duration_ms: u64,
+
     #[dynamic(default = "default_hyperlink_rules")]
     pub hyperlink_rules: Vec<hyperlink::Rule>,
 
@@ -352,6 +452,12 @@ pub struct Config {
     #[dynamic(default)]
     pub wsl_domains: Option<Vec<WslDomain>>,
 
+    /// Per-distribution defaults for the domains that are created
+    /// automatically for each installed WSL distribution when
+    /// `wsl_domains` is not set, keyed by the distribution name
+    #[dynamic(default)]
+    pub wsl_distro_defaults: HashMap<String, WslDistroDefaults>,
+
     #[dynamic(default)]
     pub exec_domains: Vec<ExecDomain>,
 
@@ -365,6 +471,16 @@ pub struct Config {
     #[dynamic(default)]
     pub ssh_domains: Option<Vec<SshDomain>>,
 
+    /// If true, `SSH:<host>` and `SSHMUX:<host>` domains are added for
+    /// each Host listed in `~/.ssh/config`, alongside any `ssh_domains`
+    #[dynamic(default)]
+    pub auto_ssh_domains: bool,
+
+    /// Hosts from `~/.ssh/config` matching any of these patterns, in
+    /// which `*` and `?` are wildcards, are left out of `auto_ssh_domains`
+    #[dynamic(default)]
+    pub auto_ssh_domains_exclude: Vec<String>,
+
     #[dynamic(default)]
     pub ssh_backend: SshBackend,
 
@@ -377,6 +493,15 @@ pub struct Config {
     #[dynamic(default)]
     pub tls_clients: Vec<TlsDomainClient>,
 
+    /// Browse the local network via mDNS for mux servers that advertise
+    /// the `_kaku-mux._tcp` service, and add an `mdns:NAME` tls domain
+    /// for each of them, so that they can be attached from the launcher.
+    /// Kaku doesn't advertise servers itself; publish them with the
+    /// system mDNS responder.
+    /// Disabled by default.  Enabling it takes effect on the next start.
+    #[dynamic(default)]
+    pub mdns_discovery: bool,
+
     /// Constrains the rate at which the multiplexer client will
     /// speculatively fetch line data.
     /// This helps to avoid saturating the link between the client
@@ -392,12 +517,60 @@ pub struct Config {
     #[dynamic(default = "default_mux_output_parser_buffer_size")]
     pub mux_output_parser_buffer_size: usize,
 
+    /// Filters applied, in order, to the output of each pane before
+    /// it is stored in the terminal model.
+    #[dynamic(default)]
+    pub output_filters: Vec<OutputFilter>,
+
+    /// If applying `output_filters` to a batch of output takes longer
+    /// than this many milliseconds, filtering is disabled for that
+    /// pane so that a pathological pattern cannot stall its output.
+    #[dynamic(default = "default_output_filter_time_budget_ms")]
+    pub output_filter_time_budget_ms: u64,
+
+    /// The `Mask` and `Progress` output filters match within runs of
+    /// text of up to this many bytes; longer runs are split, so that
+    /// the cost of matching each chunk stays bounded.  A match that
+    /// straddles a split is not found.
+    #[dynamic(default = "default_output_filter_max_text_bytes")]
+    pub output_filter_max_text_bytes: usize,
+
     #[dynamic(default = "default_true")]
     pub mux_enable_ssh_agent: bool,
 
     #[dynamic(default)]
     pub default_ssh_auth_sock: Option<String>,
 
+    /// The address on which to serve read-only views of panes
+    /// shared via `pane:share_read_only()`, eg: "127.0.0.1:0".
+    /// Pane sharing is disabled unless this is set.  The views are
+    /// served over plain HTTP; use a TLS terminating proxy to share
+    /// beyond the local machine.
+    #[dynamic(default)]
+    pub pane_share_bind_address: Option<String>,
+
+    /// Propagates clipboard copies between GUI clients that are
+    /// attached to the same mux server.  Disabled by default.
+    #[dynamic(default)]
+    pub clipboard_sync: ClipboardSync,
+
+    /// How many recent copies `ShowClipboardHistory` offers to
+    /// paste again.  Set to 0 to stop remembering copies.
+    #[dynamic(default = "default_clipboard_history_size")]
+    pub clipboard_history_size: usize,
+
+    /// If true, the clipboard history is saved, encrypted, beneath
+    /// the data directory so that it survives a restart
+    #[dynamic(default)]
+    pub clipboard_history_persist: bool,
+
+    /// If true, text that programs place on the clipboard with OSC 52
+    /// is remembered in the clipboard history along with the text
+    /// copied in the gui.  Disabled by default, as a program can set
+    /// the clipboard without the user being aware of it.
+    #[dynamic(default)]
+    pub clipboard_history_include_osc52: bool,
+
     /// How many ms to delay after reading a chunk of output
     /// in order to try to coalesce fragmented writes into
     /// a single bigger chunk of output and reduce the chances
@@ -416,6 +589,11 @@ pub struct Config {
     #[dynamic(default = "default_bypass_mouse_reporting_modifiers")]
     pub bypass_mouse_reporting_modifiers: Modifiers,
 
+    /// Whether applications may request that mouse events are
+    /// reported with pixel rather than cell coordinates
+    #[dynamic(default)]
+    pub mouse_reporting_granularity: MouseReportingGranularity,
+
     #[dynamic(default)]
     pub debug_key_events: bool,
 
@@ -424,7 +602,14 @@ pub struct Config {
 
     #[dynamic(default)]
     pub disable_default_key_bindings: bool,
-    pub leader: Option<LeaderKey>,
+    /// The leader chord, or an array of alternative chords
+    #[dynamic(default)]
+    pub leader: LeaderKeys,
+    /// Shown at the start of the tab bar while the leader is active;
+    /// an empty string hides it.  Escapes as produced by
+    /// `wezterm.format` are honored.
+    #[dynamic(default = "default_leader_indicator")]
+    pub leader_indicator: String,
 
     #[dynamic(default = "default_num_alphabet")]
     pub launcher_alphabet: String,
@@ -432,12 +617,16 @@ pub struct Config {
     #[dynamic(default)]
     pub disable_default_quick_select_patterns: bool,
     #[dynamic(default)]
-    pub quick_select_patterns: Vec<String>,
+    pub quick_select_patterns: Vec<QuickSelectPattern>,
     #[dynamic(default = "default_alphabet")]
     pub quick_select_alphabet: String,
     #[dynamic(default)]
     pub quick_select_remove_styling: bool,
 
+    /// Which way Enter moves through the matches of a search
+    #[dynamic(default)]
+    pub search_direction: SearchDirection,
+
     #[dynamic(default)]
     pub mouse_bindings: Vec<Mouse>,
     #[dynamic(default)]
@@ -446,9 +635,16 @@ pub struct Config {
     #[dynamic(default)]
     pub daemon_options: DaemonOptions,
 
+    /// When true, Left Option produces the character composed by the
+    /// keyboard layout, such as `å` for Option-a.  When false, it acts
+    /// as Alt/Meta and the unmodified key is sent with the modifier.
     #[dynamic(default)]
     pub send_composed_key_when_left_alt_is_pressed: bool,
 
+    /// Like `send_composed_key_when_left_alt_is_pressed`, for Right
+    /// Option.  Right Option composing while Left Option acts as Alt
+    /// is the macOS counterpart of distinguishing AltGr from Ctrl+Alt;
+    /// there is no separate AltGr handling as Kaku only runs on macOS.
     #[dynamic(default = "default_true")]
     pub send_composed_key_when_right_alt_is_pressed: bool,
 
@@ -483,6 +679,16 @@ pub struct Config {
     #[dynamic(default = "default_true")]
     pub show_tab_index_in_tab_bar: bool,
 
+    /// If true, a thin bar along the bottom of each tab shows the
+    /// progress reported by its active pane with OSC 9;4
+    #[dynamic(default = "default_true")]
+    pub show_progress_in_tab_bar: bool,
+
+    /// If true, the combined progress of all panes is shown as a
+    /// badge on the dock icon
+    #[dynamic(default = "default_true")]
+    pub show_progress_in_dock: bool,
+
     #[dynamic(default = "default_true")]
     pub show_tabs_in_tab_bar: bool,
 
@@ -512,6 +718,23 @@ pub struct Config {
     #[dynamic(try_from = "crate::units::PixelUnit", default = "default_half_cell")]
     pub min_scroll_bar_height: Dimension,
 
+    /// If true, clicking in the scroll bar track above or below the
+    /// thumb jumps the thumb to that position and begins dragging it,
+    /// rather than scrolling by a page.
+    #[dynamic(default)]
+    pub scroll_bar_click_to_jump: bool,
+
+    /// If true, the scroll bar shows markers for the rows of shell
+    /// prompts and, while a search is active, for the rows of its
+    /// matches.
+    #[dynamic(default)]
+    pub scroll_bar_markers: bool,
+
+    /// If true, the scroll bar track renders a condensed preview of
+    /// the colors in the scrollback, with the thumb drawn over it.
+    #[dynamic(default)]
+    pub scroll_bar_minimap: bool,
+
     /// If false, do not try to use a Wayland protocol connection
     /// when starting the gui frontend, and instead use X11.
     /// This option is only considered on X11/Wayland systems and
@@ -534,6 +757,14 @@ pub struct Config {
     #[dynamic(default = "default_true")]
     pub anti_alias_custom_block_glyphs: bool,
 
+    /// Overrides how individual characters are drawn, keyed by the
+    /// character.  Each entry can force a character to come from the
+    /// font even though a custom glyph exists for it, force the custom
+    /// glyph to be drawn even if `custom_block_glyphs` is disabled, or
+    /// supply an SVG path to draw for a private use area character.
+    #[dynamic(default, validate = "validate_custom_glyphs")]
+    pub custom_glyphs: HashMap<char, CustomGlyph>,
+
     /// Controls the amount of padding to use around the terminal cell area
     #[dynamic(default)]
     pub window_padding: WindowPadding,
@@ -571,6 +802,14 @@ pub struct Config {
     #[dynamic(default)]
     pub macos_window_background_blur: i64,
 
+    /// Only works on MacOS. Shows a system vibrancy material through
+    /// the parts of the window made translucent by
+    /// `window_background_opacity` or the background layers.  When set,
+    /// `macos_window_background_blur` is not applied, as the material
+    /// provides its own blur.
+    #[dynamic(default)]
+    pub macos_window_vibrancy: Option<MacOsVibrancy>,
+
     /// Only works on KDE Wayland
     #[dynamic(default)]
     pub kde_window_background_blur: bool,
@@ -595,6 +834,14 @@ pub struct Config {
     #[dynamic(default = "default_one_point_oh")]
     pub window_background_opacity: f32,
 
+    /// Used in place of window_background_opacity while the system
+    /// appearance is dark
+    pub window_background_opacity_dark: Option<f32>,
+
+    /// Used in place of window_background_opacity while the system
+    /// appearance is light
+    pub window_background_opacity_light: Option<f32>,
+
     /// inactive_pane_hue, inactive_pane_saturation and
     /// inactive_pane_brightness allow for transforming the color
     /// of inactive panes.
@@ -715,15 +962,46 @@ pub struct Config {
     pub xim_im_name: Option<String>,
     #[dynamic(default)]
     pub ime_preedit_rendering: ImePreeditRendering,
+    /// How the text being composed by the input method is underlined
+    /// when `ime_preedit_rendering = "Builtin"`.  Its colors are set by
+    /// `ime_preedit_fg` and `ime_preedit_bg` in the `colors` table.
+    #[dynamic(default = "default_ime_preedit_underline")]
+    pub ime_preedit_underline: wezterm_term::Underline,
 
+    /// Controls which OSC 9 and OSC 777 notifications are shown,
+    /// based on whether the pane that raised them has focus
     #[dynamic(default)]
     pub notification_handling: NotificationHandling,
 
+    /// How notifications that are shown are delivered
+    #[dynamic(default)]
+    pub notification_delivery: NotificationDelivery,
+
+    /// When true, a pane watched with `TogglePaneMonitor` also shows
+    /// a system notification when its monitor fires, unless the
+    /// `pane-monitor-triggered` event handler returns false
+    #[dynamic(default)]
+    pub pane_monitor_notifications: bool,
+
+    /// When true, a dead key waits for the next key press and the two
+    /// are composed into one character, such as `é` for `´` then `e`;
+    /// the pending accent is shown at the cursor in the meantime.
+    /// When false, a dead key produces its accent immediately.
+    /// Dead keys pressed along with Option only wait when the
+    /// corresponding `send_composed_key_when_*_alt_is_pressed` is true.
     #[dynamic(default = "default_true")]
     pub use_dead_keys: bool,
 
+    /// Entries for the launcher, which may be nested with `children`.
+    /// The `augment-launch-menu` event can add more entries each time
+    /// that the launcher is opened.
+    #[dynamic(default)]
+    pub launch_menu: Vec<LaunchMenuItem>,
+
+    /// Named layout presets of split panes and the commands to run in
+    /// them, applied with `ApplyLayout` or `kaku cli apply-layout`
     #[dynamic(default)]
-    pub launch_menu: Vec<SpawnCommand>,
+    pub layouts: HashMap<String, LayoutPane>,
 
     #[dynamic(default)]
     pub use_box_model_render: bool,
@@ -754,9 +1032,35 @@ pub struct Config {
     #[dynamic(default)]
     pub window_close_confirmation: WindowCloseConfirmation,
 
+    /// When the system is about to sleep, or the user is logging out,
+    /// save the window state that is restored on the next launch
+    #[dynamic(default = "default_true")]
+    pub checkpoint_state_on_sleep: bool,
+
+    /// When the system is about to sleep, or the user is logging out,
+    /// detach from remote mux domains so that the servers see a clean
+    /// disconnect rather than a connection that times out.  Domains
+    /// detached for sleep are re-attached when the system wakes.
+    #[dynamic(default)]
+    pub detach_remote_domains_on_sleep: bool,
+
+    /// Stop (SIGSTOP) the processes running in local panes while the
+    /// system is asleep, and continue them on wake.  This prevents
+    /// busy programs from waking the machine, at the cost of any
+    /// timers they have running.
+    #[dynamic(default)]
+    pub pause_local_panes_on_sleep: bool,
+
     #[dynamic(default)]
     pub native_macos_fullscreen_mode: bool,
 
+    /// When true, tabs are shown as native macOS window tabs rather
+    /// than in the tab bar drawn by Kaku.  Each tab is then a window
+    /// of its own within a window tab group, which Mission Control and
+    /// the standard Window menu commands understand.
+    #[dynamic(default)]
+    pub use_native_macos_tabs: bool,
+
     #[dynamic(default)]
     pub macos_fullscreen_extend_behind_notch: bool,
 
@@ -769,18 +1073,100 @@ pub struct Config {
     #[dynamic(default)]
     pub adjust_window_size_when_changing_font_size: Option<bool>,
 
+    /// When set, resizing the window changes the font size so that
+    /// the terminal keeps exactly this many columns and rows.  Any
+    /// space left over is added to the padding.
+    #[dynamic(default)]
+    pub adjust_font_size_to_fit: Option<FontSizeFit>,
+
+    /// When true, font size changes made with IncreaseFontSize and
+    /// friends apply to the active tab and are restored when
+    /// switching back to it, rather than applying to the whole window
+    #[dynamic(default)]
+    pub per_tab_font_size: bool,
+
     #[dynamic(default = "default_tiling_desktop_environments")]
     pub tiling_desktop_environments: Vec<String>,
 
     #[dynamic(default)]
     pub use_resize_increments: bool,
 
+    /// How many cursor key presses each wheel report sends to a
+    /// program in the alternate screen that doesn't capture the mouse
     #[dynamic(default = "default_alternate_buffer_wheel_scroll_speed")]
     pub alternate_buffer_wheel_scroll_speed: u8,
 
+    /// Scales the number of lines scrolled by each step of a mouse wheel
+    #[dynamic(default = "default_one_point_oh_f64")]
+    pub mouse_wheel_scroll_multiplier: f64,
+
+    /// Scales the number of lines scrolled by trackpad gestures, or
+    /// any other device that reports precise scrolling deltas
+    #[dynamic(default = "default_one_point_oh_f64")]
+    pub trackpad_scroll_multiplier: f64,
+
+    /// Used in place of the wheel and trackpad multipliers when the
+    /// pane is in the alternate screen.  It scales the number of wheel
+    /// reports sent to the program; each of those is turned into
+    /// `alternate_buffer_wheel_scroll_speed` cursor key presses for
+    /// programs that don't capture the mouse.
+    #[dynamic(default = "default_one_point_oh_f64")]
+    pub alternate_screen_scroll_multiplier: f64,
+
+    /// How scrolling speeds up when wheel events arrive in quick
+    /// succession
+    #[dynamic(default)]
+    pub scroll_acceleration: ScrollAcceleration,
+
+    /// The largest factor that `scroll_acceleration` may apply
+    #[dynamic(default = "default_scroll_acceleration_max")]
+    pub scroll_acceleration_max: f64,
+
+    /// How many frames per second are rendered while the viewport
+    /// glides to its new position after scrolling.  0 disables smooth
+    /// scrolling, so that the viewport jumps by whole lines.
+    /// How far the mouse wheel and the trackpad scroll is set separately
+    /// by `mouse_wheel_scroll_multiplier` and `trackpad_scroll_multiplier`,
+    /// the device being told apart by whether the scroll event reports
+    /// precise deltas.
+    #[dynamic(default)]
+    pub scroll_animation_fps: u8,
+
+    /// How long, in milliseconds, smooth scrolling takes to reach
+    /// the new position
+    #[dynamic(default = "default_scroll_animation_duration_ms")]
+    pub scroll_animation_duration_ms: u64,
+
+    /// The easing function applied to smooth scrolling
+    #[dynamic(default = "linear_ease")]
+    pub scroll_animation_easing: EasingFunction,
+
+    /// How long, in milliseconds, the outline of the active pane takes
+    /// to glide to its new extent when the pane is zoomed or unzoomed.
+    /// 0 disables the animation.
+    #[dynamic(default = "default_zoom_animation_duration_ms")]
+    pub zoom_animation_duration_ms: u64,
+
+    /// If true, a zoomed pane is outlined, and marked in the tab bar,
+    /// as a reminder that the other panes of its tab are hidden
+    #[dynamic(default = "default_true")]
+    pub show_zoom_indicator: bool,
+
+    /// How often, in milliseconds, the `update-status` event is emitted
+    /// so that the left and right status areas of the tab bar can be
+    /// refreshed.  The event is also emitted whenever the window title
+    /// is updated, such as when the active tab changes.  Set to 0 to
+    /// only emit it then.
     #[dynamic(default = "default_status_update_interval")]
     pub status_update_interval: u64,
 
+    /// How often, in seconds, the layout of the windows, tabs and panes
+    /// is saved so that it can be rebuilt after a restart by
+    /// `kaku start --restore-session` or the RestoreSession action.
+    /// 0 disables saving.
+    #[dynamic(default = "default_session_save_interval")]
+    pub session_save_interval_seconds: u64,
+
     #[dynamic(default)]
     pub experimental_pixel_positioning: bool,
 
@@ -802,6 +1188,11 @@ pub struct Config {
     #[dynamic(default = "default_true")]
     pub warn_about_missing_glyphs: bool,
 
+    /// How to draw a character that none of the configured or
+    /// fallback fonts can display
+    #[dynamic(default)]
+    pub missing_glyph_style: MissingGlyphStyle,
+
     #[dynamic(default)]
     pub sort_fallback_fonts_by_coverage: bool,
 
@@ -826,8 +1217,22 @@ pub struct Config {
     #[dynamic(default = "default_max_fps")]
     pub max_fps: u64,
 
+    /// Limits the frame rate once there has been no input, and no
+    /// output in a visible pane, for `idle_fps_after_secs` seconds.
+    /// Input and output restore `max_fps` immediately.
+    /// Set to 0 to always paint at up to `max_fps`.
+    #[dynamic(default = "default_idle_max_fps")]
+    pub idle_max_fps: u64,
+    #[dynamic(default = "default_idle_fps_after_secs")]
+    pub idle_fps_after_secs: u64,
+
     #[dynamic(default = "default_shape_cache_size")]
     pub shape_cache_size: usize,
+    /// The maximum number of shaped text runs per font that are
+    /// persisted in the cache directory, so that they needn't be
+    /// shaped again on the next start.  0 disables persistence.
+    #[dynamic(default = "default_persistent_shape_cache_size")]
+    pub persistent_shape_cache_size: usize,
     #[dynamic(default = "default_line_state_cache_size")]
     pub line_state_cache_size: usize,
     #[dynamic(default = "default_line_quad_cache_size")]
@@ -843,9 +1248,24 @@ pub struct Config {
     #[dynamic(default)]
     pub audible_bell: AudibleBell,
 
+    /// Sound files to play for the bell and other events
+    #[dynamic(default)]
+    pub event_sounds: EventSounds,
+
     #[dynamic(default)]
     pub canonicalize_pasted_newlines: Option<NewlineCanon>,
 
+    /// Removes trailing newlines from pasted text, so that a paste
+    /// doesn't run a command before it can be reviewed
+    #[dynamic(default)]
+    pub paste_strip_trailing_newline: bool,
+
+    /// Asks for confirmation before pasting text that contains
+    /// newlines or control characters into a pane that hasn't
+    /// enabled bracketed paste mode
+    #[dynamic(default = "default_true")]
+    pub paste_confirm_unsafe: bool,
+
     #[dynamic(default = "default_unicode_version")]
     pub unicode_version: u8,
 
@@ -867,9 +1287,44 @@ pub struct Config {
     #[dynamic(default)]
     pub default_mux_server_domain: Option<String>,
 
+    /// An HTTP proxy, in the form `host:port`, that is used for the
+    /// update check, ssh domains and TLS mux connections
+    #[dynamic(default, validate = "validate_http_proxy")]
+    pub http_proxy: Option<String>,
+
+    /// A SOCKS5 proxy, in the form `host:port`, used in the same way
+    /// as `http_proxy`.  It takes precedence when both are set.
+    #[dynamic(default, validate = "validate_socks5_proxy")]
+    pub socks5_proxy: Option<String>,
+
+    /// Per-domain replacements for `http_proxy` and `socks5_proxy`.
+    /// An override that sets neither connects to its domain directly.
+    #[dynamic(default)]
+    pub proxy_overrides: Vec<ProxyOverride>,
+
     #[dynamic(default)]
     pub default_workspace: Option<String>,
 
+    /// Per-workspace overrides for `default_domain`, `default_prog`
+    /// and `default_cwd`, keyed by workspace name.
+    /// Superseded by `workspaces`, which takes precedence.
+    #[dynamic(default)]
+    pub workspace_spawn_defaults: HashMap<String, WorkspaceSpawnDefaults>,
+
+    /// Per-workspace spawn defaults, environment and colors, keyed by
+    /// workspace name, eg:
+    /// `config.workspaces = { work = { default_cwd = "~/work",
+    /// color_scheme = "Builtin Solarized Dark", env = { FOO = "bar" } } }`
+    #[dynamic(default)]
+    pub workspaces: HashMap<String, WorkspaceSpawnDefaults>,
+
+    /// Named sets of config overrides that can be switched between at
+    /// runtime with `ActivateConfigProfile` or
+    /// `kaku cli activate-profile`, eg: a "presentation" profile with
+    /// a larger `font_size`
+    #[dynamic(default, validate = "validate_profiles")]
+    pub profiles: HashMap<String, wezterm_dynamic::Value>,
+
     #[dynamic(default)]
     pub xcursor_theme: Option<String>,
 
@@ -941,10 +1396,20 @@ impl Config {
         if let Some(domains) = &self.wsl_domains {
             domains.clone()
         } else {
-            WslDomain::default_domains()
+            WslDomain::default_domains(&self.wsl_distro_defaults)
         }
     }
 
+    /// Returns the proxy that should be used to connect to `host`
+    pub fn proxy_for_host(&self, host: &str) -> Option<ProxyServer> {
+        resolve_proxy(
+            host,
+            self.http_proxy.as_deref(),
+            self.socks5_proxy.as_deref(),
+            &self.proxy_overrides,
+        )
+    }
+
     pub fn update_ulimit(&self) -> anyhow::Result<()> {
         #[cfg(unix)]
         {
@@ -1308,6 +1773,7 @@ impl Config {
                 (key, mods),
                 KeyTableEntry {
                     action: k.action.clone(),
+                    metadata: k.metadata.clone(),
                 },
             );
         }
@@ -1324,6 +1790,7 @@ impl Config {
                     (key, mods),
                     KeyTableEntry {
                         action: k.action.clone(),
+                        metadata: k.metadata.clone(),
                     },
                 );
             }
@@ -1339,7 +1806,24 @@ impl Config {
         let mut map = HashMap::new();
 
         for m in &self.mouse_bindings {
-            map.insert((m.event.clone(), m.mods), m.action.clone());
+            if m.zone == MouseEventZone::Pane {
+                map.insert((m.event.clone(), m.mods), m.action.clone());
+            }
+        }
+
+        map
+    }
+
+    /// Returns the mouse bindings for zones other than the pane content
+    pub fn zone_mouse_bindings(
+        &self,
+    ) -> HashMap<(MouseEventZone, MouseEventTrigger, Modifiers), KeyAssignment> {
+        let mut map = HashMap::new();
+
+        for m in &self.mouse_bindings {
+            if m.zone != MouseEventZone::Pane {
+                map.insert((m.zone, m.event.clone(), m.mods.mods), m.action.clone());
+            }
         }
 
         map
@@ -1366,6 +1850,16 @@ impl Config {
             }
         }
 
+        // Resolve the hosts from the ssh config files once, here,
+        // rather than each time the domains are enumerated
+        if self.auto_ssh_domains {
+            cfg.ssh_domains = Some(SshDomain::merge_auto_domains(
+                self.ssh_domains.as_deref().unwrap_or_default(),
+                SshDomain::default_domains(),
+                &self.auto_ssh_domains_exclude,
+            ));
+        }
+
         // Add some reasonable default font rules
         let reduced = self.font.reduce_first_font_to_family();
 
@@ -1416,17 +1910,32 @@ impl Config {
         // references a scheme not already defined inline.  This avoids
         // directory enumeration + TOML parsing on every startup for users
         // who don't use custom .toml color scheme files.
-        let need_disk_schemes = cfg
-            .color_scheme
-            .as_ref()
-            .map_or(false, |name| !cfg.color_schemes.contains_key(name.as_str()));
+        let need_disk_schemes = [
+            &cfg.color_scheme,
+            &cfg.color_scheme_dark,
+            &cfg.color_scheme_light,
+        ]
+        .iter()
+        .filter_map(|name| name.as_ref())
+        .any(|name| !cfg.color_schemes.contains_key(name.as_str()));
         if need_disk_schemes {
             cfg.load_color_schemes(&cfg.compute_color_scheme_dirs())
                 .ok();
         }
 
-        if let Some(scheme) = cfg.color_scheme.as_ref() {
-            match cfg.resolve_color_scheme() {
+        cfg.resolve_palette();
+
+        if let Some(bg) = BackgroundLayer::with_legacy(self) {
+            cfg.background.insert(0, bg);
+        }
+
+        cfg
+    }
+
+    /// Computes resolved_palette from color_scheme and colors
+    fn resolve_palette(&mut self) {
+        if let Some(scheme) = self.color_scheme.as_ref() {
+            match self.resolve_color_scheme() {
                 None => {
                     log::error!(
                         "Your configuration specifies color_scheme=\"{}\" \
@@ -1435,93 +1944,92 @@ impl Config {
                     );
                 }
                 Some(p) => {
-                    cfg.resolved_palette = p;
+                    self.resolved_palette = p;
                 }
             }
         }
 
-        if let Some(colors) = &cfg.colors {
-            cfg.resolved_palette = cfg.resolved_palette.overlay_with(colors);
+        if let Some(colors) = &self.colors {
+            self.resolved_palette = self.resolved_palette.overlay_with(colors);
         }
+    }
 
-        if let Some(bg) = BackgroundLayer::with_legacy(self) {
-            cfg.background.insert(0, bg);
+    /// Returns a copy of this config with color_scheme_dark or
+    /// color_scheme_light, and the corresponding background opacity,
+    /// applied according to whether the system appearance is `dark`.
+    /// Returns None if there are no settings specific to that appearance.
+    pub fn for_appearance(&self, dark: bool) -> Option<Self> {
+        let (scheme, opacity) = if dark {
+            (&self.color_scheme_dark, self.window_background_opacity_dark)
+        } else {
+            (
+                &self.color_scheme_light,
+                self.window_background_opacity_light,
+            )
+        };
+        if scheme.is_none() && opacity.is_none() {
+            return None;
         }
 
-        cfg
-    }
-
-    fn compute_color_scheme_dirs(&self) -> Vec<PathBuf> {
-        let mut paths = self.color_scheme_dirs.clone();
-        for dir in CONFIG_DIRS.iter() {
-            paths.push(dir.join("colors"));
+        let mut cfg = self.clone();
+        if let Some(scheme) = scheme {
+            cfg.color_scheme = Some(scheme.clone());
+            cfg.resolved_palette = Palette::default();
+            cfg.resolve_palette();
         }
-        if cfg!(windows) {
-            // See commentary re: portable tools above!
-            if let Ok(exe_name) = std::env::current_exe() {
-                if let Some(exe_dir) = exe_name.parent() {
-                    paths.insert(0, exe_dir.join("colors"));
+        if let Some(opacity) = opacity {
+            // The layer derived from the legacy window_background_* options
+            // was given the opacity when the config was loaded
+            if BackgroundLayer::with_legacy(self).is_some() {
+                if let Some(layer) = cfg.background.first_mut() {
+                    layer.opacity = opacity;
                 }
             }
+            cfg.window_background_opacity = opacity;
         }
-        paths
+        Some(cfg)
     }
 
-    fn load_color_schemes(&mut self, paths: &[PathBuf]) -> anyhow::Result<()> {
-        fn extract_scheme_name(name: &str) -> Option<&str> {
-            if name.ends_with(".toml") {
-                let len = name.len();
-                Some(&name[..len - 5])
-            } else {
-                None
-            }
-        }
+    /// Returns the settings configured for `workspace`, if any
+    pub fn workspace_settings(&self, workspace: &str) -> Option<&WorkspaceSpawnDefaults> {
+        self.workspaces
+            .get(workspace)
+            .or_else(|| self.workspace_spawn_defaults.get(workspace))
+    }
 
-        fn load_scheme(path: &Path) -> anyhow::Result<ColorSchemeFile> {
-            let s = std::fs::read_to_string(path)?;
-            ColorSchemeFile::from_toml_str(&s).context("parsing TOML")
+    /// Returns a copy of this config with the `color_scheme` and
+    /// `accent_color` of `workspace` applied.
+    /// Returns None if the workspace has no colors of its own.
+    pub fn for_workspace(&self, workspace: &str) -> Option<Self> {
+        let settings = self.workspace_settings(workspace)?;
+        if settings.color_scheme.is_none() && settings.accent_color.is_none() {
+            return None;
         }
 
-        for colors_dir in paths {
-            if let Ok(dir) = std::fs::read_dir(colors_dir) {
-                for entry in dir {
-                    if let Ok(entry) = entry {
-                        if let Some(name) = entry.file_name().to_str() {
-                            if let Some(scheme_name) = extract_scheme_name(name) {
-                                if self.color_schemes.contains_key(scheme_name) {
-                                    // This scheme has already been defined
-                                    continue;
-                                }
-
-                                let path = entry.path();
-                                match load_scheme(&path) {
-                                    Ok(scheme) => {
-                                        let name = scheme
-                                            .metadata
-                                            .name
-                                            .unwrap_or_else(|| scheme_name.to_string());
-                                        log::trace!(
-                                            "Loaded color scheme `{}` from {}",
-                                            name,
-                                            path.display()
-                                        );
-                                        self.color_schemes.insert(name, scheme.colors);
-                                    }
-                                    Err(err) => {
-                                        log::error!(
-                                            "Color scheme in `{}` failed to load: {:#}",
-                                            path.display(),
-                                            err
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        let mut cfg = self.clone();
+        if let Some(scheme) = &settings.color_scheme {
+            cfg.color_scheme = Some(scheme.clone());
+            cfg.resolved_palette = Palette::default();
+            cfg.resolve_palette();
+        }
+        if let Some(accent) = settings.accent_color {
+            let tab_bar = cfg
+                .resolved_palette
+                .tab_bar
+                .get_or_insert_with(Default::default);
+            let mut active_tab = tab_bar.active_tab();
+            active_tab.bg_color = accent;
+            tab_bar.active_tab.replace(active_tab);
         }
+        Some(cfg)
+    }
 
+    fn compute_color_scheme_dirs(&self) -> Vec<PathBuf> {
+        color_scheme_dirs(&self.color_scheme_dirs)
+    }
+
+    fn load_color_schemes(&mut self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        load_color_scheme_files(paths, &mut self.color_schemes);
         Ok(())
     }
 
@@ -1535,6 +2043,12 @@ impl Config {
         }
     }
 
+    /// Whether tabs are native macOS window tabs, which is only
+    /// possible on macOS
+    pub fn uses_native_macos_tabs(&self) -> bool {
+        cfg!(target_os = "macos") && self.use_native_macos_tabs
+    }
+
     pub fn initial_size(&self, dpi: u32, cell_pixel_dims: Option<(usize, usize)>) -> TerminalSize {
         // If we aren't passed the actual values, guess at a plausible
         // default set of pixel dimensions.
@@ -1620,7 +2134,25 @@ impl Config {
         cmd.env_remove("APPDIR");
         cmd.env_remove("OWD");
 
+        // Variables that were set explicitly on the command, such as
+        // from the `set_environment_variables` of a SpawnCommand, take
+        // precedence over the defaults from the config
+        let explicit_env: HashSet<String> = cmd
+            .iter_extra_env_as_str()
+            .map(|(k, _)| k.to_string())
+            .collect();
+
+        if explicit_env.contains("WSLENV") {
+            wsl_env = cmd
+                .get_env("WSLENV")
+                .and_then(|v| v.to_str())
+                .map(|v| v.to_string());
+        }
+
         for (k, v) in &self.set_environment_variables {
+            if explicit_env.contains(k) {
+                continue;
+            }
             if k == "WSLENV" {
                 wsl_env.replace(v.clone());
             } else {
@@ -1661,6 +2193,98 @@ fn default_check_for_updates() -> bool {
     cfg!(not(feature = "distro-defaults"))
 }
 
+/// Returns `color_scheme_dirs` followed by the `colors` directory of
+/// each config directory, in the order in which they are searched for
+/// color scheme files
+pub(crate) fn color_scheme_dirs(color_scheme_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths = color_scheme_dirs.to_vec();
+    for dir in CONFIG_DIRS.iter() {
+        paths.push(dir.join("colors"));
+    }
+    if cfg!(windows) {
+        // See commentary re: portable tools above!
+        if let Ok(exe_name) = std::env::current_exe() {
+            if let Some(exe_dir) = exe_name.parent() {
+                paths.insert(0, exe_dir.join("colors"));
+            }
+        }
+    }
+    paths
+}
+
+/// Loads the `.toml`, `.yaml` and `.yml` color scheme files in `paths`
+/// into `schemes`, keyed by both their name and their aliases.  A scheme
+/// that doesn't specify its name in its metadata is named after its
+/// file.  Names that are already present are left alone, so the first
+/// of `paths` to define a scheme wins.
+pub(crate) fn load_color_scheme_files(paths: &[PathBuf], schemes: &mut HashMap<String, Palette>) {
+    fn extract_scheme_name(
+        name: &str,
+    ) -> Option<(&str, fn(&str) -> anyhow::Result<ColorSchemeFile>)> {
+        if let Some(name) = name.strip_suffix(".toml") {
+            Some((name, ColorSchemeFile::from_toml_str))
+        } else if let Some(name) = name
+            .strip_suffix(".yaml")
+            .or_else(|| name.strip_suffix(".yml"))
+        {
+            Some((name, ColorSchemeFile::from_yaml_str))
+        } else {
+            None
+        }
+    }
+
+    for colors_dir in paths {
+        let mut entries: Vec<_> = match std::fs::read_dir(colors_dir) {
+            Ok(dir) => dir.filter_map(|entry| entry.ok()).collect(),
+            Err(_) => continue,
+        };
+        // Make the winner deterministic if two files claim the same name
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let file_name = entry.file_name();
+            let (scheme_name, parse) = match file_name.to_str().and_then(extract_scheme_name) {
+                Some(found) => found,
+                None => continue,
+            };
+            if schemes.contains_key(scheme_name) {
+                // This scheme has already been defined
+                continue;
+            }
+
+            let path = entry.path();
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|s| parse(&s))
+            {
+                Ok(scheme) => {
+                    let name = scheme
+                        .metadata
+                        .name
+                        .unwrap_or_else(|| scheme_name.to_string());
+                    if schemes.contains_key(&name) {
+                        continue;
+                    }
+                    log::trace!("Loaded color scheme `{}` from {}", name, path.display());
+                    for alias in scheme.metadata.aliases {
+                        schemes
+                            .entry(alias)
+                            .or_insert_with(|| scheme.colors.clone());
+                    }
+                    schemes.insert(name, scheme.colors);
+                }
+                Err(err) => {
+                    log::error!(
+                        "Color scheme in `{}` failed to load: {:#}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+    }
+}
+
 fn default_pane_select_fg_color() -> RgbaColor {
     SrgbaTuple(0.75, 0.75, 0.75, 1.0).into()
 }
@@ -1702,6 +2326,10 @@ fn default_command_palette_bg_color() -> RgbaColor {
     (0x33, 0x33, 0x33).into()
 }
 
+fn default_command_palette_match_fg_color() -> RgbaColor {
+    (0xd7, 0x87, 0x00).into()
+}
+
 fn default_swallow_mouse_click_on_window_focus() -> bool {
     cfg!(target_os = "macos")
 }
@@ -1710,6 +2338,14 @@ fn default_mux_output_parser_coalesce_delay_ms() -> u64 {
     3
 }
 
+fn default_output_filter_time_budget_ms() -> u64 {
+    50
+}
+
+fn default_output_filter_max_text_bytes() -> usize {
+    64 * 1024
+}
+
 fn default_mux_output_parser_buffer_size() -> usize {
     128 * 1024
 }
@@ -1736,6 +2372,10 @@ fn default_swap_backspace_and_delete() -> bool {
     false
 }
 
+fn default_clipboard_history_size() -> usize {
+    50
+}
+
 fn default_scrollback_lines() -> usize {
     3500
 }
@@ -1892,6 +2532,14 @@ fn default_max_fps() -> u64 {
     60
 }
 
+fn default_idle_max_fps() -> u64 {
+    5
+}
+
+fn default_idle_fps_after_secs() -> u64 {
+    10
+}
+
 fn default_tiling_desktop_environments() -> Vec<String> {
     [
         "X11 LG3D",
@@ -1929,6 +2577,10 @@ fn default_status_update_interval() -> u64 {
     1_000
 }
 
+fn default_session_save_interval() -> u64 {
+    30
+}
+
 fn default_quit_when_all_windows_are_closed() -> bool {
     #[cfg(target_os = "macos")]
     {
@@ -1944,6 +2596,18 @@ fn default_alternate_buffer_wheel_scroll_speed() -> u8 {
     3
 }
 
+fn default_scroll_acceleration_max() -> f64 {
+    4.0
+}
+
+fn default_scroll_animation_duration_ms() -> u64 {
+    120
+}
+
+fn default_zoom_animation_duration_ms() -> u64 {
+    150
+}
+
 fn default_num_alphabet() -> String {
     // Note: vi motion keys are intentionally excluded from this alphabet
     "1234567890abcdefghilmnopqrstuvwxyz".to_string()
@@ -2078,6 +2742,15 @@ pub enum NewlineCanon {
     CarriageReturnAndLineFeed,
 }
 
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MouseReportingGranularity {
+    /// Honor requests for SGR-Pixels (1016) mouse reporting
+    #[default]
+    Pixel,
+    /// Decline requests for SGR-Pixels (1016) mouse reporting
+    Cell,
+}
+
 #[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, Default)]
 pub enum WindowCloseConfirmation {
     #[default]
@@ -2087,6 +2760,121 @@ pub enum WindowCloseConfirmation {
     // running programs are stateful
 }
 
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PaneIndexBadges {
+    #[default]
+    Never,
+    Always,
+    WhileModifiersHeld,
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FontSizeFit {
+    #[dynamic(validate = "validate_font_size_fit_dimension")]
+    pub cols: usize,
+    #[dynamic(validate = "validate_font_size_fit_dimension")]
+    pub rows: usize,
+}
+
+fn validate_font_size_fit_dimension(value: &usize) -> Result<(), String> {
+    if *value == 0 {
+        Err("adjust_font_size_to_fit cols and rows must be greater than zero".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MissingGlyphStyle {
+    /// Use the placeholder (`.notdef`) glyph from the primary font
+    #[default]
+    FontDefault,
+    /// A box showing the hexadecimal codepoint
+    HexBox,
+    /// U+FFFD REPLACEMENT CHARACTER
+    ReplacementCharacter,
+    /// Leave the cell empty
+    Blank,
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CustomGlyphRender {
+    /// Draw the built-in glyph, or `svg_path` if set
+    #[default]
+    Draw,
+    /// Always use the glyph from the font
+    Font,
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Debug, PartialEq, Default)]
+pub struct CustomGlyph {
+    #[dynamic(default)]
+    pub render: CustomGlyphRender,
+    /// SVG path data to fill, such as `"M0 0 L1 0.5 L0 1 Z"`.
+    /// Only permitted for private use area characters.
+    #[dynamic(default)]
+    pub svg_path: Option<String>,
+    /// The `min-x`, `min-y`, `width` and `height` of the coordinate
+    /// space used by `svg_path`, which is stretched to fill the cell
+    #[dynamic(default = "default_svg_view_box")]
+    pub svg_view_box: [f32; 4],
+}
+
+fn default_svg_view_box() -> [f32; 4] {
+    [0., 0., 1., 1.]
+}
+
+/// Returns true if `c` is in one of the private use areas, which
+/// is where symbol fonts for prompts and status lines put their glyphs
+pub fn is_private_use_char(c: char) -> bool {
+    matches!(c as u32, 0xe000..=0xf8ff | 0xf0000..=0xffffd | 0x100000..=0x10fffd)
+}
+
+fn validate_custom_glyphs(glyphs: &HashMap<char, CustomGlyph>) -> Result<(), String> {
+    for (c, glyph) in glyphs {
+        if glyph.svg_path.is_some() && !is_private_use_char(*c) {
+            return Err(format!(
+                "custom_glyphs: svg_path can only be used for private use area \
+                 characters, but U+{:04X} is not one",
+                *c as u32
+            ));
+        }
+        if glyph.svg_view_box[2] <= 0. || glyph.svg_view_box[3] <= 0. {
+            return Err(format!(
+                "custom_glyphs: the svg_view_box width and height for U+{:04X} \
+                 must be greater than zero",
+                *c as u32
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(FromDynamic, ToDynamic, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ScrollAcceleration {
+    #[default]
+    None,
+    /// Each event in a burst adds 10% to the speed
+    Linear,
+    /// Starts slower than Linear, but keeps speeding up for longer
+    /// bursts
+    Quadratic,
+}
+
+impl ScrollAcceleration {
+    /// Returns the factor to apply to the `streak`th event in a burst
+    /// of wheel events, which is at least 1.0 and no more than `max`
+    pub fn factor(self, streak: u32, max: f64) -> f64 {
+        let streak = streak as f64;
+        let factor = match self {
+            Self::None => 1.0,
+            Self::Linear => 1.0 + 0.1 * streak,
+            Self::Quadratic => 1.0 + 0.01 * streak * streak,
+        };
+        factor.min(max).max(1.0)
+    }
+}
+
 struct PathPossibility {
     path: PathBuf,
     is_required: bool,
@@ -2189,6 +2977,10 @@ fn default_line_quad_cache_size() -> usize {
     1024
 }
 
+fn default_persistent_shape_cache_size() -> usize {
+    16384
+}
+
 fn default_line_to_ele_shape_cache_size() -> usize {
     1024
 }
@@ -2227,25 +3019,97 @@ impl FromDynamic for BoldBrightening {
     }
 }
 
+#[derive(Debug, FromDynamic, ToDynamic, Clone, PartialEq, Eq)]
+pub struct ClipboardSync {
+    /// Send text copied in this client to the mux server, so that
+    /// other attached clients can paste it
+    #[dynamic(default)]
+    pub send: bool,
+    /// Place text copied in other attached clients into the local
+    /// clipboard
+    #[dynamic(default)]
+    pub receive: bool,
+    /// Copies larger than this many bytes are neither sent nor
+    /// relayed nor accepted
+    #[dynamic(default = "default_clipboard_sync_max_bytes")]
+    pub max_bytes: usize,
+}
+
+impl Default for ClipboardSync {
+    fn default() -> Self {
+        Self {
+            send: false,
+            receive: false,
+            max_bytes: default_clipboard_sync_max_bytes(),
+        }
+    }
+}
+
+impl ClipboardSync {
+    pub fn allows_size(&self, len: usize) -> bool {
+        len <= self.max_bytes
+    }
+
+    /// Whether a copy of `len` bytes made locally is sent to the mux server
+    pub fn sends(&self, len: usize) -> bool {
+        self.send && self.allows_size(len)
+    }
+
+    /// Whether a copy of `len` bytes made by another client is placed
+    /// into the local clipboard
+    pub fn receives(&self, len: usize) -> bool {
+        self.receive && self.allows_size(len)
+    }
+
+    /// Whether a mux server accepts a copy of `len` bytes from one of
+    /// its clients and relays it to the others.  The server relays
+    /// when either direction is enabled in its own configuration.
+    pub fn relays(&self, len: usize) -> bool {
+        (self.send || self.receive) && self.allows_size(len)
+    }
+}
+
+fn default_clipboard_sync_max_bytes() -> usize {
+    1024 * 1024
+}
+
 #[derive(Debug, FromDynamic, ToDynamic, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ImePreeditRendering {
-    /// IME preedit is rendered by WezTerm itself
+    /// IME preedit is rendered by WezTerm itself, inline at the cursor
     #[default]
     Builtin,
-    /// IME preedit is rendered by system
+    /// IME preedit is rendered by system; it is not drawn in the
+    /// terminal, leaving the input method to display it
     System,
 }
 
+fn default_ime_preedit_underline() -> wezterm_term::Underline {
+    wezterm_term::Underline::Single
+}
+
 #[derive(Debug, FromDynamic, ToDynamic, Clone, Copy, PartialEq, Eq, Default)]
 pub enum NotificationHandling {
     #[default]
     AlwaysShow,
     NeverShow,
+    /// Suppress notifications from the active pane of the window
+    /// that has keyboard focus, as the user can already see them
     SuppressFromFocusedPane,
     SuppressFromFocusedTab,
     SuppressFromFocusedWindow,
 }
 
+#[derive(Debug, FromDynamic, ToDynamic, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationDelivery {
+    /// Show a desktop notification; clicking it focuses the pane
+    #[default]
+    Toast,
+    /// Ring the bell of the pane, as though it had sent BEL
+    Bell,
+    /// Emit the `pane-notification` event and leave the rest to Lua
+    Event,
+}
+
 fn validate_row_or_col(value: &u16) -> Result<(), String> {
     if *value < 1 {
         Err("initial_cols and initial_rows must be non-zero".to_string())
@@ -2276,6 +3140,27 @@ pub(crate) fn validate_domain_name(name: &str) -> Result<(), String> {
     }
 }
 
+fn default_pane_index_badge_modifiers() -> Modifiers {
+    Modifiers::SUPER | Modifiers::ALT
+}
+
+fn default_resize_pane_step() -> usize {
+    1
+}
+
+fn default_dropdown_height_percent() -> u8 {
+    40
+}
+
+fn default_dropdown_animation_duration_ms() -> u64 {
+    150
+}
+
+fn default_leader_indicator() -> String {
+    // md_keyboard
+    "\u{f030c} LEADER ".to_string()
+}
+
 /// <https://github.com/wezterm/wezterm/pull/2435>
 /// <https://github.com/wezterm/wezterm/issues/2771>
 /// <https://github.com/wezterm/wezterm/issues/2630>
@@ -2286,3 +3171,86 @@ fn default_macos_forward_mods() -> Modifiers {
 fn default_colr_rasterizer() -> FontRasterizerSelection {
     FontRasterizerSelection::Harfbuzz
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sync(send: bool, receive: bool) -> ClipboardSync {
+        ClipboardSync {
+            send,
+            receive,
+            max_bytes: 8,
+        }
+    }
+
+    #[test]
+    fn clipboard_sync_is_off_by_default() {
+        let sync = ClipboardSync::default();
+        assert!(!sync.sends(1));
+        assert!(!sync.receives(1));
+        assert!(!sync.relays(1));
+    }
+
+    #[test]
+    fn clipboard_sync_directions() {
+        let send_only = sync(true, false);
+        assert!(send_only.sends(8));
+        assert!(!send_only.receives(8));
+        assert!(send_only.relays(8));
+
+        let receive_only = sync(false, true);
+        assert!(!receive_only.sends(8));
+        assert!(receive_only.receives(8));
+        assert!(receive_only.relays(8));
+    }
+
+    #[test]
+    fn clipboard_sync_rejects_large_copies() {
+        let both = sync(true, true);
+        assert!(!both.sends(9));
+        assert!(!both.receives(9));
+        assert!(!both.relays(9));
+    }
+
+    #[test]
+    fn font_size_fit_rejects_empty_grid() {
+        assert!(validate_font_size_fit_dimension(&80).is_ok());
+        assert!(validate_font_size_fit_dimension(&0).is_err());
+    }
+
+    #[test]
+    fn zone_mouse_bindings_exclude_pane() {
+        let middle_click = MouseEventTrigger::Down {
+            streak: 1,
+            button: wezterm_term::input::MouseButton::Middle,
+        };
+        let binding = |zone, action| Mouse {
+            event: middle_click.clone(),
+            mods: MouseEventTriggerMods {
+                mods: Modifiers::SHIFT,
+                ..Default::default()
+            },
+            action,
+            zone,
+        };
+        let config = Config {
+            mouse_bindings: vec![
+                binding(MouseEventZone::Pane, KeyAssignment::Nop),
+                binding(MouseEventZone::Tab, KeyAssignment::ActivateTab(0)),
+            ],
+            ..Config::default_config()
+        };
+
+        let zones = config.zone_mouse_bindings();
+        assert_eq!(zones.len(), 1);
+        assert_eq!(
+            zones.get(&(MouseEventZone::Tab, middle_click.clone(), Modifiers::SHIFT)),
+            Some(&KeyAssignment::ActivateTab(0))
+        );
+
+        let panes = config.mouse_bindings();
+        assert_eq!(panes.len(), 1);
+        assert_eq!(panes.values().next(), Some(&KeyAssignment::Nop));
+    }
+}
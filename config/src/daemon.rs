@@ -8,6 +8,64 @@ pub struct DaemonOptions {
     pub pid_file: Option<PathBuf>,
     pub stdout: Option<PathBuf>,
     pub stderr: Option<PathBuf>,
+    /// Where to deliver notifications about noteworthy mux server
+    /// events, such as clients attaching or a pane process exiting.
+    #[dynamic(default)]
+    pub notification_sinks: Vec<MuxNotificationSink>,
+}
+
+/// An event that the mux server can report to a notification sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum MuxServerEvent {
+    ClientAttached,
+    ClientDetached,
+    /// The bell rang in a pane while no clients were attached
+    BellInDetachedPane,
+    PaneProcessExited,
+}
+
+#[derive(Debug, Clone, FromDynamic, ToDynamic)]
+pub enum MuxNotificationTarget {
+    /// POST a JSON description of the event to `url`
+    Webhook { url: String },
+    /// Publish the event to an ntfy topic
+    Ntfy {
+        #[dynamic(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+        #[dynamic(default)]
+        token: Option<String>,
+    },
+    /// Pipe the event as a mail message through a sendmail
+    /// compatible program
+    Email {
+        to: String,
+        #[dynamic(default = "default_sendmail")]
+        sendmail: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, FromDynamic, ToDynamic)]
+pub struct MuxNotificationSink {
+    pub target: MuxNotificationTarget,
+    /// The events to deliver to this sink.
+    /// An empty list means all events.
+    #[dynamic(default)]
+    pub events: Vec<MuxServerEvent>,
+}
+
+impl MuxNotificationSink {
+    pub fn wants(&self, event: MuxServerEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_sendmail() -> PathBuf {
+    PathBuf::from("/usr/sbin/sendmail")
 }
 
 /// Set the sticky bit on path.
@@ -0,0 +1,99 @@
+use crate::Config;
+use wezterm_dynamic::{ToDynamic, Value};
+
+/// Describes which parts of the configuration changed on a reload.
+/// Each path starts with the name of a top level config field; when
+/// the field is a table, the path continues into the keys that
+/// changed beneath it, eg: `colors.background`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDelta {
+    paths: Vec<String>,
+}
+
+impl ConfigDelta {
+    pub fn between(old: &Config, new: &Config) -> Self {
+        let mut paths = vec![];
+        diff_values(&old.to_dynamic(), &new.to_dynamic(), None, &mut paths);
+        Self { paths }
+    }
+
+    /// True if no field changed, which is also the case when the
+    /// reload failed and the prior configuration was retained
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Returns true if the top level field `name`, or anything
+    /// beneath it, changed
+    pub fn field_changed(&self, name: &str) -> bool {
+        self.paths.iter().any(|path| top_level_field(path) == name)
+    }
+
+    /// Returns true if every change is to one of `fields`
+    pub fn only_changed(&self, fields: &[&str]) -> bool {
+        self.paths
+            .iter()
+            .all(|path| fields.contains(&top_level_field(path)))
+    }
+}
+
+fn top_level_field(path: &str) -> &str {
+    path.split('.').next().unwrap_or(path)
+}
+
+fn diff_values(old: &Value, new: &Value, path: Option<&str>, paths: &mut Vec<String>) {
+    if old == new {
+        return;
+    }
+    match (old, new, path) {
+        (Value::Object(old), Value::Object(new), _) => {
+            let keys = old
+                .keys()
+                .chain(new.keys().filter(|k| !old.contains_key(k)));
+            for key in keys {
+                let name = match key {
+                    Value::String(s) => s.to_string(),
+                    other => format!("{other:?}"),
+                };
+                let child = match path {
+                    Some(path) => format!("{path}.{name}"),
+                    None => name,
+                };
+                diff_values(
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    Some(&child),
+                    paths,
+                );
+            }
+        }
+        (_, _, Some(path)) => paths.push(path.to_string()),
+        (_, _, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delta() {
+        let old = Config::default_config();
+        assert!(ConfigDelta::between(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.font_size += 1.0;
+        new.disable_default_key_bindings = !old.disable_default_key_bindings;
+        let delta = ConfigDelta::between(&old, &new);
+        assert_eq!(
+            delta.paths(),
+            &["disable_default_key_bindings", "font_size"]
+        );
+        assert!(delta.field_changed("font_size"));
+        assert!(!delta.only_changed(&["disable_default_key_bindings"]));
+    }
+}
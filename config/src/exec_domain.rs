@@ -15,5 +15,9 @@ pub struct ExecDomain {
     pub name: String,
     pub fixup_command: String,
     pub label: Option<ValueOrFunc>,
+    /// The event that maps a cwd reported via OSC 7 by a process
+    /// running inside the domain to the equivalent path on the host,
+    /// used when spawning into a different domain from such a pane
+    pub map_cwd: Option<String>,
 }
 impl_lua_conversion_dynamic!(ExecDomain);
@@ -3,6 +3,7 @@ use crate::*;
 use bitflags::*;
 use enum_display_derive::Display;
 use luahelper::impl_lua_conversion_dynamic;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt::Display;
 use wezterm_dynamic::{FromDynamic, FromDynamicOptions, ToDynamic, Value};
@@ -384,6 +385,10 @@ pub struct FontAttributes {
     pub scale: Option<NotNan<f64>>,
     #[dynamic(default)]
     pub assume_emoji_presentation: Option<bool>,
+    /// Coordinates for the axes of a variable font, keyed by the
+    /// four character axis tag, such as `wght` or `slnt`
+    #[dynamic(default)]
+    pub variation: Option<BTreeMap<String, NotNan<f64>>>,
 }
 impl_lua_conversion_dynamic!(FontAttributes);
 
@@ -412,6 +417,7 @@ impl FontAttributes {
             freetype_load_flags: None,
             scale: None,
             assume_emoji_presentation: None,
+            variation: None,
         }
     }
 
@@ -429,6 +435,7 @@ impl FontAttributes {
             freetype_load_flags: None,
             scale: None,
             assume_emoji_presentation: None,
+            variation: None,
         }
     }
 }
@@ -448,6 +455,7 @@ impl Default for FontAttributes {
             freetype_load_flags: None,
             scale: None,
             assume_emoji_presentation: None,
+            variation: None,
         }
     }
 }
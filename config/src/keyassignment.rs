@@ -1,6 +1,8 @@
+use crate::bell::SoundEvent;
 use crate::default_true;
 use crate::keys::KeyNoAction;
 use crate::window::WindowLevel;
+use crate::SerialLineEnding;
 use luahelper::impl_lua_conversion_dynamic;
 use ordered_float::NotNan;
 use portable_pty::CommandBuilder;
@@ -136,6 +138,24 @@ impl Default for Pattern {
     }
 }
 
+/// How the text of a search is matched; see `SetMatchType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SearchMatchType {
+    CaseSensitive,
+    CaseInsensitive,
+    Regex,
+}
+
+/// Which way `AdvanceMatch` moves through the matches of a search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, FromDynamic, ToDynamic)]
+pub enum SearchDirection {
+    /// Towards older output, further up the scrollback
+    #[default]
+    Backward,
+    /// Towards newer output, further down the scrollback
+    Forward,
+}
+
 /// A mouse event that can trigger an action
 #[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, FromDynamic, ToDynamic)]
 pub enum MouseEventTrigger {
@@ -266,6 +286,35 @@ impl SpawnCommand {
     }
 }
 
+/// An entry of the `launch_menu`.  When `children` is empty, choosing
+/// it spawns the command in a new tab; otherwise it opens a submenu
+/// of `children` and the command fields are ignored.
+#[derive(Debug, Default, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct LaunchMenuItem {
+    #[dynamic(flatten)]
+    pub spawn: SpawnCommand,
+    #[dynamic(default)]
+    pub children: Vec<LaunchMenuItem>,
+}
+impl_lua_conversion_dynamic!(LaunchMenuItem);
+
+impl LaunchMenuItem {
+    pub fn is_submenu(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    /// The text shown for this entry in the launcher and the palette
+    pub fn label(&self) -> String {
+        match self.spawn.label.as_ref() {
+            Some(label) => label.to_string(),
+            None => match self.spawn.args.as_ref() {
+                Some(args) => args.join(" "),
+                None => "(default shell)".to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, FromDynamic, ToDynamic)]
 pub enum PaneDirection {
     Up,
@@ -315,6 +364,67 @@ pub enum ClipboardCopyDestination {
 }
 impl_lua_conversion_dynamic!(ClipboardCopyDestination);
 
+/// The set of panes that `ToggleBroadcastInput` mirrors input to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum BroadcastInputScope {
+    /// All of the panes in the active tab
+    Tab,
+    /// All of the panes in all of the tabs in the window
+    Window,
+    /// All of the panes in all of the windows in the active workspace
+    Workspace,
+}
+impl_lua_conversion_dynamic!(BroadcastInputScope);
+
+/// What `TogglePaneMonitor` watches a pane for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum PaneMonitor {
+    /// Alert when the pane produces any output
+    Activity,
+    /// Alert when the pane hasn't produced output for this many seconds
+    Silence(u64),
+}
+impl_lua_conversion_dynamic!(PaneMonitor);
+
+/// The format produced by `CopySelectionAsTable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum TableFormat {
+    Tsv,
+    Csv,
+    Markdown,
+}
+impl_lua_conversion_dynamic!(TableFormat);
+
+/// The format produced by `ExportScrollback` and `kaku cli export-pane`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic, Serialize, Deserialize)]
+pub enum ScrollbackExportFormat {
+    /// The text without any colors or styling
+    PlainText,
+    /// The text with escape sequences that reproduce its colors
+    /// and styling when it is printed to a terminal
+    Ansi,
+    /// An HTML document with the colors of the pane's palette inlined
+    Html,
+}
+impl_lua_conversion_dynamic!(ScrollbackExportFormat);
+
+impl Default for ScrollbackExportFormat {
+    fn default() -> Self {
+        Self::PlainText
+    }
+}
+
+impl ScrollbackExportFormat {
+    /// The file name extension used for exports in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::PlainText => "txt",
+            Self::Ansi => "ans",
+            Self::Html => "html",
+        }
+    }
+}
+
 impl Default for ClipboardCopyDestination {
     fn default() -> Self {
         Self::ClipboardAndPrimarySelection
@@ -446,7 +556,7 @@ pub struct QuickSelectArguments {
     pub alphabet: String,
     /// Overrides the main quick_select_patterns config
     #[dynamic(default)]
-    pub patterns: Vec<String>,
+    pub patterns: Vec<QuickSelectPattern>,
     #[dynamic(default)]
     pub action: Option<Box<KeyAssignment>>,
     /// Skip triggering `action` after paste is performed (capital selection)
@@ -460,6 +570,112 @@ pub struct QuickSelectArguments {
     pub scope_lines: Option<usize>,
 }
 
+/// What QuickSelect does with the text selected from the matches of
+/// a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuickSelectAction {
+    /// Copy to the clipboard and primary selection
+    Copy,
+    /// Paste into the pane
+    Paste,
+    /// Open with the default handler for urls
+    OpenUrl,
+    /// Send to the pane as though it had been typed, rather than pasted
+    SendToPane,
+    /// Perform a key assignment.  Callbacks defined by
+    /// `wezterm.action_callback` are passed the selected text and a
+    /// table of the named captures after the window and pane.
+    KeyAssignment(Box<KeyAssignment>),
+}
+
+impl FromDynamic for QuickSelectAction {
+    fn from_dynamic(
+        value: &Value,
+        options: FromDynamicOptions,
+    ) -> Result<Self, wezterm_dynamic::Error> {
+        match value {
+            Value::String(s) => match s.as_str() {
+                "Copy" => Ok(Self::Copy),
+                "Paste" => Ok(Self::Paste),
+                "OpenUrl" => Ok(Self::OpenUrl),
+                "SendToPane" => Ok(Self::SendToPane),
+                s => Err(wezterm_dynamic::Error::Message(format!(
+                    "`{s}` is not valid, use one of `Copy`, `Paste`, `OpenUrl`, \
+                     `SendToPane` or a key assignment"
+                ))),
+            },
+            _ => Ok(Self::KeyAssignment(Box::new(KeyAssignment::from_dynamic(
+                value, options,
+            )?))),
+        }
+    }
+}
+
+impl ToDynamic for QuickSelectAction {
+    fn to_dynamic(&self) -> Value {
+        match self {
+            Self::Copy => "Copy".to_dynamic(),
+            Self::Paste => "Paste".to_dynamic(),
+            Self::OpenUrl => "OpenUrl".to_dynamic(),
+            Self::SendToPane => "SendToPane".to_dynamic(),
+            Self::KeyAssignment(assignment) => assignment.to_dynamic(),
+        }
+    }
+}
+
+/// A regex for QuickSelect to match, either as a plain string or as
+/// `{ regex = "...", action = ... }`.  As for the built-in patterns,
+/// the text that is selected is that of the last capture group that
+/// participated in the match, or the whole match if there are none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickSelectPattern {
+    pub regex: String,
+    /// What to do with the selected text, in place of the `action`
+    /// of the QuickSelect or copying it
+    pub action: Option<QuickSelectAction>,
+}
+
+#[derive(FromDynamic, ToDynamic)]
+struct QuickSelectPatternTable {
+    regex: String,
+    #[dynamic(default)]
+    action: Option<QuickSelectAction>,
+}
+
+impl FromDynamic for QuickSelectPattern {
+    fn from_dynamic(
+        value: &Value,
+        options: FromDynamicOptions,
+    ) -> Result<Self, wezterm_dynamic::Error> {
+        match value {
+            Value::String(regex) => Ok(Self {
+                regex: regex.to_string(),
+                action: None,
+            }),
+            _ => {
+                let table = QuickSelectPatternTable::from_dynamic(value, options)?;
+                Ok(Self {
+                    regex: table.regex,
+                    action: table.action,
+                })
+            }
+        }
+    }
+}
+
+impl ToDynamic for QuickSelectPattern {
+    fn to_dynamic(&self) -> Value {
+        match &self.action {
+            None => self.regex.to_dynamic(),
+            Some(action) => QuickSelectPatternTable {
+                regex: self.regex.clone(),
+                action: Some(action.clone()),
+            }
+            .to_dynamic(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
 pub struct PromptInputLine {
     pub action: Box<KeyAssignment>,
@@ -539,12 +755,25 @@ pub enum KeyAssignment {
     ToggleAlwaysOnTop,
     ToggleAlwaysOnBottom,
     SetWindowLevel(WindowLevel),
+    MoveWindowToMonitor(usize),
     CopyTo(ClipboardCopyDestination),
+    /// Copies the selection to the clipboard after converting the
+    /// columns that it spans into the given format
+    CopySelectionAsTable(TableFormat),
+    /// Parses the selection, or the output of the most recent command
+    /// if nothing is selected, as JSON and shows it as a foldable tree
+    ViewSelectionAsJson,
     CopyTextTo {
         text: String,
         destination: ClipboardCopyDestination,
     },
     PasteFrom(ClipboardPasteSource),
+    /// Shows the recent copies, letting one be picked to paste
+    /// into the active pane
+    ShowClipboardHistory,
+    /// Pastes the text that was yanked into a named register in
+    /// copy mode
+    PasteFromRegister(String),
     ActivateTabRelative(isize),
     ActivateTabRelativeNoWrap(isize),
     IncreaseFontSize,
@@ -563,16 +792,37 @@ pub enum KeyAssignment {
         confirm: bool,
     },
     ReloadConfiguration,
+    /// Activates the named entry of the `profiles` config table;
+    /// `default` returns to the config without a profile
+    ActivateConfigProfile(String),
+    /// Rebuilds the windows, tabs and panes saved by the previous run,
+    /// other than those that are already open
+    RestoreSession,
     MoveTabRelative(isize),
     MoveTab(usize),
+    /// Moves the active tab out into a new window of its own
+    MoveTabToNewWindow,
+    /// Shows the dropdown window, creating it if needed, or hides it
+    /// if it is already visible and focused
+    ToggleDropdownWindow,
     ScrollByPage(NotNan<f64>),
     ScrollByLine(isize),
     ScrollByCurrentEventWheelDelta,
     ScrollToPrompt(isize),
+    /// Selects the output of the command at the top of the viewport,
+    /// or of the most recent command if the viewport isn't scrolled back
+    SelectCommandOutput,
+    /// Copies the output of the most recent command to the clipboard
+    CopyLastCommandOutput,
     ScrollToTop,
     ScrollToBottom,
+    ToggleViewportSplit(SplitSize),
     ShowTabNavigator,
     ShowDebugOverlay,
+    ShowKeyDebugger,
+    /// Shows or hides frame timing and cache statistics over the window
+    ToggleRenderHud,
+    PreviewEventSound(SoundEvent),
     HideApplication,
     QuitApplication,
     SpawnCommandInNewTab(SpawnCommand),
@@ -582,7 +832,26 @@ pub enum KeyAssignment {
     ShowLauncher,
     ShowLauncherArgs(LauncherActionArgs),
     ClearScrollback(ScrollbackEraseMode),
+    /// Saves the scrollback of the active pane to the downloads folder
+    ExportScrollback {
+        #[dynamic(default)]
+        format: ScrollbackExportFormat,
+    },
+    /// Starts or stops recording the output of the active pane to an
+    /// asciinema `.cast` file
+    TogglePaneRecording,
+    /// Replays an asciinema `.cast` file in a read-only overlay over
+    /// the active pane.  Without a path, the most recent recording
+    /// made by `TogglePaneRecording` is played.
+    PlayRecording {
+        #[dynamic(default)]
+        path: Option<String>,
+    },
+    /// Changes what the Enter key sends in a serial port domain
+    SetSerialLineEnding(SerialLineEnding),
     Search(Pattern),
+    /// Searches the scrollback of every pane in the active workspace
+    SearchWorkspace(Pattern),
     ActivateCopyMode,
 
     SelectTextAtMouseCursor(SelectionMode),
@@ -594,10 +863,27 @@ pub enum KeyAssignment {
     StartWindowDrag,
 
     AdjustPaneSize(PaneDirection, usize),
+    /// Resizes the splits around the active pane so that it has an
+    /// exact size in cells, as far as the tab allows
+    SetPaneSize(PaneSize),
+    /// Spawns the named preset from the `layouts` config in a new tab
+    ApplyLayout(String),
     ActivatePaneDirection(PaneDirection),
     ActivatePaneByIndex(usize),
     TogglePaneZoomState,
     SetPaneZoomState(bool),
+    /// Makes the active pane read-only, discarding keystrokes and
+    /// pastes, or makes it writable again
+    TogglePaneInputLock,
+    /// Mirrors keystrokes and pastes sent to the active pane to all
+    /// of the other panes in the scope, or stops doing so
+    ToggleBroadcastInput(BroadcastInputScope),
+    /// Stops the active pane from sending or receiving broadcast
+    /// input, or allows it to again
+    TogglePaneBroadcastExclusion,
+    /// Starts watching the active pane for output activity or for
+    /// silence, flagging its tab when that happens, or stops watching
+    TogglePaneMonitor(PaneMonitor),
     CloseCurrentPane {
         confirm: bool,
     },
@@ -650,6 +936,16 @@ pub enum KeyAssignment {
 }
 impl_lua_conversion_dynamic!(KeyAssignment);
 
+#[derive(Debug, Clone, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub struct PaneSize {
+    /// The width in cells; the width is unchanged if omitted
+    #[dynamic(default)]
+    pub cols: Option<usize>,
+    /// The height in cells; the height is unchanged if omitted
+    #[dynamic(default)]
+    pub rows: Option<usize>,
+}
+
 #[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
 pub struct SplitPane {
     pub direction: PaneDirection,
@@ -710,6 +1006,8 @@ pub enum CopyModeAssignment {
     PriorMatchPage,
     NextMatchPage,
     CycleMatchType,
+    SetMatchType(SearchMatchType),
+    AdvanceMatch { reverse: bool },
     ClearPattern,
     EditPattern,
     AcceptPattern,
@@ -721,6 +1019,9 @@ pub enum CopyModeAssignment {
     JumpBackward { prev_char: bool },
     JumpAgain,
     JumpReverse,
+    SelectTextObject { inner: bool },
+    MoveToMatchingBracket,
+    SelectRegister,
 }
 
 pub type KeyTable = HashMap<(KeyCode, Modifiers), KeyTableEntry>;
@@ -734,4 +1035,39 @@ pub struct KeyTables {
 #[derive(Debug, Clone, PartialEq)]
 pub struct KeyTableEntry {
     pub action: KeyAssignment,
+    pub metadata: CommandMetadata,
+}
+
+/// Optional labels that can be attached to a key binding to describe
+/// its action in the command palette and menubar, which is primarily
+/// useful for `EmitEvent` bindings whose event names would otherwise
+/// be shown.
+#[derive(Debug, Clone, Default, PartialEq, FromDynamic, ToDynamic)]
+pub struct CommandMetadata {
+    /// Replaces the brief description of the action
+    pub brief: Option<String>,
+    /// Replaces the longer description of the action
+    pub doc: Option<String>,
+    /// Where to place the action in the menubar, eg: `{"Tools", "Git"}`
+    #[dynamic(default)]
+    pub menubar: Vec<String>,
+    /// The name of a Nerd Font glyph to show alongside the action
+    pub icon: Option<String>,
+}
+
+/// A command added to the command palette, and optionally the
+/// menubar, by `wezterm.register_command`
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct UserCommand {
+    pub brief: String,
+    #[dynamic(default)]
+    pub doc: Option<String>,
+    pub action: KeyAssignment,
+    /// Where to place the command in the menubar, eg: `{"Shell"}`.
+    /// If empty, the command only appears in the command palette.
+    #[dynamic(default)]
+    pub menubar: Vec<String>,
+    /// The name of a Nerd Font glyph to show alongside the command
+    #[dynamic(default)]
+    pub icon: Option<String>,
 }
@@ -1,4 +1,4 @@
-use crate::keyassignment::{KeyAssignment, MouseEventTrigger};
+use crate::keyassignment::{CommandMetadata, KeyAssignment, MouseEventTrigger};
 use std::convert::TryFrom;
 use wezterm_dynamic::{Error as DynError, FromDynamic, FromDynamicOptions, ToDynamic, Value};
 use wezterm_input_types::{KeyCode, Modifiers, PhysKeyCode};
@@ -123,6 +123,8 @@ pub struct Key {
     #[dynamic(flatten)]
     pub key: KeyNoAction,
     pub action: KeyAssignment,
+    #[dynamic(flatten)]
+    pub metadata: CommandMetadata,
 }
 
 #[derive(Debug, Clone, FromDynamic, ToDynamic)]
@@ -137,12 +139,61 @@ fn default_leader_timeout() -> u64 {
     1000
 }
 
+/// The `leader` setting: either a single chord, or an array of
+/// alternative chords, any of which activates the leader
+#[derive(Debug, Clone, Default)]
+pub struct LeaderKeys(pub Vec<LeaderKey>);
+
+impl FromDynamic for LeaderKeys {
+    fn from_dynamic(value: &Value, options: FromDynamicOptions) -> Result<Self, DynError> {
+        match value {
+            Value::Array(chords) => Ok(Self(
+                chords
+                    .iter()
+                    .map(|chord| LeaderKey::from_dynamic(chord, options))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            _ => Ok(Self(vec![LeaderKey::from_dynamic(value, options)?])),
+        }
+    }
+}
+
+impl ToDynamic for LeaderKeys {
+    fn to_dynamic(&self) -> Value {
+        match self.0.as_slice() {
+            [chord] => chord.to_dynamic(),
+            chords => Value::Array(chords.iter().map(|chord| chord.to_dynamic()).collect()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, FromDynamic, ToDynamic)]
 pub struct Mouse {
     pub event: MouseEventTrigger,
     #[dynamic(flatten)]
     pub mods: MouseEventTriggerMods,
     pub action: KeyAssignment,
+    #[dynamic(default)]
+    pub zone: MouseEventZone,
+}
+
+/// The part of the window that a mouse binding applies to.
+/// `mouse_reporting` and `alt_screen` only apply to `Pane`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, FromDynamic, ToDynamic)]
+pub enum MouseEventZone {
+    /// The content of a pane
+    #[default]
+    Pane,
+    /// A tab in the tab bar; the tab is activated before the
+    /// action is performed
+    Tab,
+    /// The new tab button in the tab bar
+    NewTabButton,
+    /// The divider between two panes
+    PaneBorder,
+    /// The parts of the tab bar that are not covered by a tab
+    /// or a button, which also serve to drag the window
+    WindowBackground,
 }
 
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -192,3 +243,47 @@ pub struct MouseEventTriggerMods {
     #[dynamic(default)]
     pub alt_screen: MouseEventAltScreen,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn leader_keys(json: &str) -> Result<LeaderKeys, DynError> {
+        let value = crate::json_to_dynamic(&serde_json::from_str(json).unwrap());
+        LeaderKeys::from_dynamic(&value, FromDynamicOptions::default())
+    }
+
+    #[test]
+    fn single_leader_chord() {
+        let leader = leader_keys(r#"{"key": "a", "mods": "CTRL"}"#).unwrap();
+        assert_eq!(leader.0.len(), 1);
+        assert_eq!(leader.0[0].key.mods, Modifiers::CTRL);
+        assert_eq!(leader.0[0].timeout_milliseconds, 1000);
+        // A single chord is written back as an object, not an array
+        assert!(matches!(leader.to_dynamic(), Value::Object(_)));
+    }
+
+    #[test]
+    fn alternative_leader_chords() {
+        let leader = leader_keys(
+            r#"[
+                {"key": "a", "mods": "CTRL"},
+                {"key": "b", "mods": "SUPER", "timeout_milliseconds": 500}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(leader.0.len(), 2);
+        assert_eq!(leader.0[1].key.mods, Modifiers::SUPER);
+        assert_eq!(leader.0[1].timeout_milliseconds, 500);
+
+        let round_trip =
+            LeaderKeys::from_dynamic(&leader.to_dynamic(), FromDynamicOptions::default()).unwrap();
+        assert_eq!(round_trip.0.len(), 2);
+        assert_eq!(round_trip.0[1].key, leader.0[1].key);
+    }
+
+    #[test]
+    fn invalid_leader_chord() {
+        assert!(leader_keys(r#"[{"key": "a", "mods": "CTRL"}, {"mods": "CTRL"}]"#).is_err());
+    }
+}
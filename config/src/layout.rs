@@ -0,0 +1,52 @@
+use crate::keyassignment::{PaneDirection, SpawnCommand, SplitSize};
+use luahelper::impl_lua_conversion_dynamic;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+/// A pane of a layout preset from the `layouts` config table, along
+/// with the panes that are split off from it.  For example:
+///
+/// ```lua
+/// config.layouts = {
+///   dev = {
+///     args = { 'nvim' },
+///     cwd = '/home/me/src/app',
+///     splits = {
+///       {
+///         direction = 'Right',
+///         size = { Percent = 30 },
+///         args = { 'npm', 'run', 'dev' },
+///         splits = {
+///           { direction = 'Down', args = { 'tail', '-f', 'app.log' } },
+///         },
+///       },
+///     },
+///   },
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct LayoutPane {
+    /// What to run in the pane.  When the cwd is omitted, the pane
+    /// inherits the cwd of the pane that it was split off from.
+    #[dynamic(flatten)]
+    pub command: SpawnCommand,
+    /// Where this pane is placed relative to the pane that it is split
+    /// off from.  Ignored for the top level pane.
+    #[dynamic(default = "default_layout_direction")]
+    pub direction: PaneDirection,
+    /// The size of this pane relative to the space that it is split
+    /// off from
+    #[dynamic(default)]
+    pub size: SplitSize,
+    /// Panes to split off from this one, in order
+    #[dynamic(default)]
+    pub splits: Vec<LayoutPane>,
+    /// Focus this pane once the layout has been applied, rather than
+    /// the top level pane
+    #[dynamic(default)]
+    pub active: bool,
+}
+impl_lua_conversion_dynamic!(LayoutPane);
+
+fn default_layout_direction() -> PaneDirection {
+    PaneDirection::Right
+}
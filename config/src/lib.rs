@@ -27,14 +27,20 @@ mod cell;
 mod color;
 mod config;
 mod daemon;
+mod delta;
 mod exec_domain;
 mod font;
 mod frontend;
 pub mod keyassignment;
 mod keys;
+mod layout;
 pub mod lua;
 pub mod meta;
+mod output_filter;
+mod profile;
+mod proxy;
 mod scheme_data;
+mod scheme_metadata;
 mod serial;
 mod ssh;
 mod terminal;
@@ -42,7 +48,9 @@ mod tls;
 mod units;
 mod unix;
 mod version;
+mod watch;
 pub mod window;
+mod workspace;
 mod wsl;
 
 pub use crate::config::*;
@@ -51,10 +59,16 @@ pub use bell::*;
 pub use cell::*;
 pub use color::*;
 pub use daemon::*;
+pub use delta::*;
 pub use exec_domain::*;
 pub use font::*;
 pub use frontend::*;
 pub use keys::*;
+pub use layout::*;
+pub use output_filter::*;
+pub use profile::*;
+pub use proxy::*;
+pub use scheme_metadata::*;
 pub use serial::*;
 pub use ssh::*;
 pub use terminal::*;
@@ -62,6 +76,8 @@ pub use tls::*;
 pub use units::*;
 pub use unix::*;
 pub use version::*;
+pub use watch::*;
+pub use workspace::*;
 pub use wsl::*;
 
 type ErrorCallback = fn(&str);
@@ -173,18 +189,44 @@ pub fn build_default_schemes() -> HashMap<String, Palette> {
 }
 
 /// Lazy-loading color scheme registry
-/// Loads color schemes on-demand instead of eagerly loading all 1001 schemes at startup
+/// Loads color schemes on-demand instead of eagerly loading all 1001 schemes at startup.
+/// Schemes found in the `colors` directory of the config directories are
+/// loaded up front and take precedence over built-in schemes with the
+/// same name.
 pub struct ColorSchemeRegistry {
     loaded: RwLock<HashMap<String, Palette>>,
+    user_dirs: Vec<PathBuf>,
+    user: RwLock<HashMap<String, Palette>>,
+}
+
+fn load_user_schemes(dirs: &[PathBuf]) -> HashMap<String, Palette> {
+    let mut schemes = HashMap::new();
+    crate::config::load_color_scheme_files(dirs, &mut schemes);
+    schemes
 }
 
 impl ColorSchemeRegistry {
     pub fn new() -> Self {
+        Self::with_user_dirs(crate::config::color_scheme_dirs(&[]))
+    }
+
+    fn with_user_dirs(user_dirs: Vec<PathBuf>) -> Self {
+        let user = load_user_schemes(&user_dirs);
         Self {
             loaded: RwLock::new(HashMap::new()),
+            user_dirs,
+            user: RwLock::new(user),
         }
     }
 
+    /// Re-reads the user's colors directories so that added, removed
+    /// or edited scheme files are reflected by subsequent lookups.
+    /// Called when the config is reloaded.
+    pub fn invalidate(&self) {
+        let user = load_user_schemes(&self.user_dirs);
+        *self.user.write() = user;
+    }
+
     /// Get a color scheme by name, loading it on-demand if not already cached
     /// Returns an owned Palette for API compatibility
     pub fn get(&self, name: &str) -> Option<Palette> {
@@ -192,6 +234,10 @@ impl ColorSchemeRegistry {
     }
 
     fn get_internal(&self, name: &str) -> Option<Palette> {
+        if let Some(palette) = self.user.read().get(name) {
+            return Some(palette.clone());
+        }
+
         // Fast path: check if already loaded
         {
             let loaded = self.loaded.read();
@@ -240,12 +286,26 @@ impl ColorSchemeRegistry {
         None
     }
 
-    /// Get all available scheme names (without loading them)
+    /// Get all available built-in scheme names (without loading them)
     pub fn available_schemes() -> Vec<&'static str> {
         scheme_data::SCHEMES.iter().map(|(name, _)| *name).collect()
     }
 
-    /// Clone all loaded schemes (for backward compatibility with .clone())
+    /// Get the names of the user schemes followed by those of the
+    /// built-in schemes that they don't override
+    pub fn scheme_names(&self) -> Vec<String> {
+        let user = self.user.read();
+        let mut names: Vec<String> = user.keys().cloned().collect();
+        names.extend(
+            Self::available_schemes()
+                .into_iter()
+                .filter(|name| !user.contains_key(*name))
+                .map(|name| name.to_string()),
+        );
+        names
+    }
+
+    /// Clone all loaded built-in schemes (for backward compatibility with .clone())
     /// Note: This will eagerly load ALL schemes if called before any are cached
     pub fn clone(&self) -> HashMap<String, Palette> {
         let loaded = self.loaded.read();
@@ -332,6 +392,16 @@ impl Drop for ConfigSubscription {
 pub fn subscribe_to_config_reload<F>(subscriber: F) -> ConfigSubscription
 where
     F: Fn() -> bool + 'static + Send,
+{
+    ConfigSubscription(CONFIG.subscribe(move |_: &ConfigDelta| subscriber()))
+}
+
+/// Like `subscribe_to_config_reload`, but the subscriber is also told
+/// which fields changed, so that it can skip work that the changed
+/// fields don't affect
+pub fn subscribe_to_config_reload_with_delta<F>(subscriber: F) -> ConfigSubscription
+where
+    F: Fn(&ConfigDelta) -> bool + 'static + Send,
 {
     ConfigSubscription(CONFIG.subscribe(subscriber))
 }
@@ -455,6 +525,11 @@ pub fn show_error(err: &str) {
     }
 }
 
+/// The directory in which shaped text runs are persisted across runs
+pub fn shape_cache_dir() -> PathBuf {
+    CACHE_DIR.join("shape-cache")
+}
+
 pub fn create_user_owned_dirs(p: &Path) -> anyhow::Result<()> {
     let mut builder = DirBuilder::new();
     builder.recursive(true);
@@ -532,6 +607,12 @@ pub fn configuration() -> ConfigHandle {
     CONFIG.get()
 }
 
+/// Returns the commands that the config added to the command
+/// palette and menubar with `wezterm.register_command`
+pub fn user_commands() -> Arc<Vec<keyassignment::UserCommand>> {
+    CONFIG.user_commands()
+}
+
 /// Returns a version of the config (loaded from the config file)
 /// with some field overridden based on the supplied overrides object.
 pub fn overridden_config(overrides: &wezterm_dynamic::Value) -> Result<ConfigHandle, Error> {
@@ -563,7 +644,10 @@ struct ConfigInner {
     warnings: Vec<String>,
     generation: usize,
     watcher: Option<notify::RecommendedWatcher>,
-    subscribers: HashMap<usize, Box<dyn Fn() -> bool + Send>>,
+    watch_list: Arc<Mutex<WatchList>>,
+    /// Registered by `wezterm.register_command` in the current config
+    user_commands: Arc<Vec<keyassignment::UserCommand>>,
+    subscribers: HashMap<usize, Box<dyn Fn(&ConfigDelta) -> bool + Send>>,
 }
 
 impl ConfigInner {
@@ -574,13 +658,15 @@ impl ConfigInner {
             warnings: vec![],
             generation: 0,
             watcher: None,
+            watch_list: Arc::new(Mutex::new(WatchList::default())),
+            user_commands: Arc::new(vec![]),
             subscribers: HashMap::new(),
         }
     }
 
     fn subscribe<F>(&mut self, subscriber: F) -> usize
     where
-        F: Fn() -> bool + 'static + Send,
+        F: Fn(&ConfigDelta) -> bool + 'static + Send,
     {
         static SUB_ID: AtomicUsize = AtomicUsize::new(0);
         let sub_id = SUB_ID.fetch_add(1, Ordering::Relaxed);
@@ -592,16 +678,16 @@ impl ConfigInner {
         self.subscribers.remove(&sub_id);
     }
 
-    fn notify(&mut self) {
-        self.subscribers.retain(|_, notify| notify());
+    fn notify(&mut self, delta: &ConfigDelta) {
+        self.subscribers.retain(|_, notify| notify(delta));
     }
 
-    fn watch_path(&mut self, path: PathBuf) {
+    fn watcher(&mut self) -> &mut notify::RecommendedWatcher {
         if self.watcher.is_none() {
             let (tx, rx) = std::sync::mpsc::channel();
             const DELAY: Duration = Duration::from_millis(200);
             let watcher = notify::recommended_watcher(tx).unwrap();
-            let path = path.clone();
+            let watch_list = Arc::clone(&self.watch_list);
 
             std::thread::spawn(move || {
                 // block until we get an event
@@ -621,16 +707,17 @@ impl ConfigInner {
                     match event {
                         Ok(event) => {
                             let mut paths = extract_path(event);
-                            if !paths.is_empty() {
+                            let delay = watch_list.lock().unwrap().reload_delay(&paths, DELAY);
+                            if let Some(delay) = delay {
                                 // Grace period to allow events to settle
-                                std::thread::sleep(DELAY);
+                                std::thread::sleep(delay);
                                 // Drain any other immediately ready events
                                 while let Ok(Ok(event)) = rx.try_recv() {
                                     paths.append(&mut extract_path(event));
                                 }
                                 paths.sort();
                                 paths.dedup();
-                                log::debug!("paths {:?} changed, reload config", path);
+                                log::debug!("paths {:?} changed, reload config", paths);
                                 reload();
                             }
                         }
@@ -642,12 +729,70 @@ impl ConfigInner {
             });
             self.watcher.replace(watcher);
         }
-        if let Some(watcher) = self.watcher.as_mut() {
-            use notify::Watcher;
-            watcher
-                .watch(&path, notify::RecursiveMode::NonRecursive)
-                .ok();
+        self.watcher.as_mut().unwrap()
+    }
+
+    fn watch_path(&mut self, path: PathBuf) {
+        use notify::Watcher;
+        self.watcher()
+            .watch(&path, notify::RecursiveMode::NonRecursive)
+            .ok();
+        let mut watch_list = self.watch_list.lock().unwrap();
+        if !watch_list.files.contains(&path) {
+            watch_list.files.push(path);
+        }
+    }
+
+    /// Replaces the set of watched directories with `dirs`
+    fn watch_dirs(&mut self, dirs: Vec<WatchDir>) {
+        use notify::Watcher;
+        let watch_list = Arc::clone(&self.watch_list);
+        let mut watch_list = watch_list.lock().unwrap();
+        let (removed, added) = watch_list.dir_changes(&dirs);
+        for dir in removed {
+            self.watcher().unwatch(&dir.path).ok();
+        }
+        for dir in added {
+            let mode = if dir.recursive {
+                notify::RecursiveMode::Recursive
+            } else {
+                notify::RecursiveMode::NonRecursive
+            };
+            if let Err(err) = self.watcher().watch(&dir.path, mode) {
+                log::warn!("unable to watch {}: {:#}", dir.path.display(), err);
+            }
+        }
+        watch_list.dirs = dirs;
+    }
+
+    fn accumulate_watch_dirs(lua: &Lua) -> Vec<WatchDir> {
+        let mut dirs = vec![];
+        if let Ok(mlua::Value::Table(tbl)) = lua.named_registry_value("wezterm-watch-dirs") {
+            for dir in tbl.sequence_values::<mlua::Value>().flatten() {
+                if let Ok(dir) = luahelper::from_lua_value_dynamic::<WatchDir>(dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+
+    /// Returns the commands registered by the config, keeping only the
+    /// most recent registration of each brief description
+    fn accumulate_user_commands(lua: &Lua) -> Vec<keyassignment::UserCommand> {
+        let mut commands: Vec<keyassignment::UserCommand> = vec![];
+        if let Ok(mlua::Value::Table(tbl)) = lua.named_registry_value("wezterm-user-commands") {
+            for command in tbl.sequence_values::<mlua::Value>().flatten() {
+                match luahelper::from_lua_value_dynamic::<keyassignment::UserCommand>(command) {
+                    Ok(command) => {
+                        commands.retain(|existing| existing.brief != command.brief);
+                        commands.push(command);
+                    }
+                    Err(err) => log::error!("register_command: {err:#}"),
+                }
+            }
         }
+        commands
     }
 
     fn accumulate_watch_paths(lua: &Lua, watch_paths: &mut Vec<PathBuf>) {
@@ -684,13 +829,22 @@ impl ConfigInner {
             // triggering reload loops.
             watch_paths.push(path);
         }
+        let mut watch_dirs = None;
         if let Some(lua) = &lua {
             ConfigInner::accumulate_watch_paths(lua, &mut watch_paths);
+            watch_dirs = Some(ConfigInner::accumulate_watch_dirs(lua));
         }
+        let user_commands = lua
+            .as_ref()
+            .map(ConfigInner::accumulate_user_commands)
+            .unwrap_or_default();
 
+        let mut delta = ConfigDelta::default();
         match config {
             Ok(config) => {
+                delta = ConfigDelta::between(&self.config, &config);
                 self.config = Arc::new(config);
+                self.user_commands = Arc::new(user_commands);
                 self.error.take();
                 self.generation += 1;
 
@@ -714,11 +868,16 @@ impl ConfigInner {
             }
         }
 
-        self.notify();
+        self.notify(&delta);
         if self.config.automatically_reload_config {
             for path in watch_paths {
                 self.watch_path(path);
             }
+            // A failed load doesn't tell us which directories to watch,
+            // so keep watching the ones from the last successful load
+            if let Some(dirs) = watch_dirs {
+                self.watch_dirs(dirs);
+            }
         }
     }
 
@@ -727,6 +886,7 @@ impl ConfigInner {
     /// configuration
     fn use_defaults(&mut self) {
         self.config = Arc::new(Config::default_config());
+        self.user_commands = Arc::new(vec![]);
         self.error.take();
         self.generation += 1;
     }
@@ -781,10 +941,16 @@ impl Configuration {
         }
     }
 
+    /// Returns the commands registered by `wezterm.register_command`
+    pub fn user_commands(&self) -> Arc<Vec<keyassignment::UserCommand>> {
+        let inner = self.inner.lock().unwrap();
+        Arc::clone(&inner.user_commands)
+    }
+
     /// Subscribe to config reload events
     fn subscribe<F>(&self, subscriber: F) -> usize
     where
-        F: Fn() -> bool + 'static + Send,
+        F: Fn(&ConfigDelta) -> bool + 'static + Send,
     {
         let mut inner = self.inner.lock().unwrap();
         inner.subscribe(subscriber)
@@ -812,7 +978,10 @@ impl Configuration {
             inner.generation
         };
 
-        let config = Config::load_with_overrides(overrides);
+        let config = match active_profile_overrides(&self.get()) {
+            Some(profile) => Config::load_with_overrides(&merge_overrides(profile, overrides)),
+            None => Config::load_with_overrides(overrides),
+        };
         Ok(ConfigHandle {
             config: Arc::new(config.config?),
             generation,
@@ -829,7 +998,8 @@ impl Configuration {
     /// Reload the configuration
     pub fn reload(&self) {
         let reload_id = self.reload_epoch.fetch_add(1, Ordering::Relaxed) + 1;
-        let loaded = Config::load();
+        COLOR_SCHEMES.invalidate();
+        let loaded = load_with_active_profile();
         if self.reload_epoch.load(Ordering::Relaxed) != reload_id {
             return;
         }
@@ -890,6 +1060,30 @@ impl ConfigHandle {
         }
     }
 
+    /// Applies the colors configured for `workspace`; see
+    /// `Config::for_workspace`
+    pub fn with_workspace(&self, workspace: &str) -> Self {
+        match self.config.for_workspace(workspace) {
+            Some(config) => Self {
+                config: Arc::new(config),
+                generation: self.generation,
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns this config with the settings specific to the dark or
+    /// light system appearance applied; see [Config::for_appearance]
+    pub fn with_appearance(&self, dark: bool) -> Self {
+        match self.config.for_appearance(dark) {
+            Some(config) => Self {
+                config: Arc::new(config),
+                generation: self.generation,
+            },
+            None => self.clone(),
+        }
+    }
+
     pub fn unicode_version(&self) -> UnicodeVersion {
         UnicodeVersion {
             version: self.config.unicode_version,
@@ -924,3 +1118,91 @@ fn default_one_point_oh() -> f32 {
 fn default_true() -> bool {
     true
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn user_schemes_override_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Dracula.toml"),
+            "[colors]\nforeground = \"#ffffff\"\nansi = [\"#000000\", \"#800000\", \"#008000\", \
+             \"#808000\", \"#000080\", \"#800080\", \"#008080\", \"#c0c0c0\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("mine.yaml"),
+            "metadata:\n  name: My Scheme\n  aliases: [mine]\ncolors:\n  \
+             ansi: ['#000000', '#800000', '#008000', '#808000', '#000080', '#800080', \
+             '#008080', '#c0c0c0']\n",
+        )
+        .unwrap();
+
+        let registry = ColorSchemeRegistry::with_user_dirs(vec![dir.path().to_path_buf()]);
+        let white = RgbaColor::try_from("#ffffff".to_string()).unwrap();
+        assert_eq!(registry.get("Dracula").unwrap().foreground, Some(white));
+        assert!(registry.get("My Scheme").is_some());
+        assert!(registry.get("mine").is_some());
+        let names = registry.scheme_names();
+        assert_eq!(names.iter().filter(|name| *name == "Dracula").count(), 1);
+        // The lua get_builtin_color_schemes only ever sees built-in schemes
+        assert_ne!(registry.clone()["Dracula"].foreground, Some(white));
+        assert!(!registry.clone().contains_key("My Scheme"));
+
+        std::fs::remove_file(dir.path().join("Dracula.toml")).unwrap();
+        registry.invalidate();
+        assert_ne!(registry.get("Dracula").unwrap().foreground, Some(white));
+    }
+
+    #[test]
+    fn register_command() -> anyhow::Result<()> {
+        let lua = lua::make_lua_context(Path::new("testing"))?;
+        lua.load(
+            r#"
+local wezterm = require 'wezterm'
+wezterm.register_command {
+  brief = 'Deploy',
+  action = wezterm.action.EmitEvent 'deploy',
+  menubar = { 'Shell' },
+}
+wezterm.register_command {
+  brief = 'Deploy',
+  doc = 'Ship it',
+  action = wezterm.action.EmitEvent 'deploy-v2',
+}
+"#,
+        )
+        .exec()?;
+
+        let commands = ConfigInner::accumulate_user_commands(&lua);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].doc.as_deref(), Some("Ship it"));
+        assert!(commands[0].menubar.is_empty());
+        assert_eq!(
+            commands[0].action,
+            keyassignment::KeyAssignment::EmitEvent("deploy-v2".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn appearance_specific_settings() {
+        let mut config = Config::default_config();
+        assert!(config.for_appearance(true).is_none());
+
+        config.color_scheme_dark = Some("Dracula".to_string());
+        config.window_background_opacity_dark = Some(0.9);
+        assert!(config.for_appearance(false).is_none());
+
+        let dark = config.for_appearance(true).unwrap();
+        assert_eq!(dark.color_scheme.as_deref(), Some("Dracula"));
+        assert_eq!(dark.window_background_opacity, 0.9);
+        assert_eq!(
+            dark.resolved_palette.foreground,
+            COLOR_SCHEMES.get("Dracula").unwrap().foreground
+        );
+    }
+}
@@ -1,14 +1,15 @@
 use crate::exec_domain::{ExecDomain, ValueOrFunc};
-use crate::keyassignment::KeyAssignment;
+use crate::keyassignment::{KeyAssignment, UserCommand};
 use crate::{
     Config, FontAttributes, FontStretch, FontStyle, FontWeight, FreeTypeLoadTarget, RgbaColor,
-    TextStyle,
+    TextStyle, WatchDir,
 };
 use anyhow::{anyhow, Context};
 use luahelper::{from_lua_value_dynamic, lua_value_to_dynamic, to_lua};
 use mlua::{FromLua, IntoLuaMulti, Lua, Table, Value, Variadic};
 use ordered_float::NotNan;
 use portable_pty::CommandBuilder;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::path::Path;
 use std::sync::Mutex;
@@ -322,6 +323,13 @@ end
             "add_to_config_reload_watch_list",
             lua.create_function(add_to_config_reload_watch_list)?,
         )?;
+        lua.set_named_registry_value("wezterm-watch-dirs", lua.create_table()?)?;
+        wezterm_mod.set(
+            "add_dir_to_config_reload_watch_list",
+            lua.create_function(add_dir_to_config_reload_watch_list)?,
+        )?;
+        lua.set_named_registry_value("wezterm-user-commands", lua.create_table()?)?;
+        wezterm_mod.set("register_command", lua.create_function(register_command)?)?;
 
         wezterm_mod.set("target_triple", crate::wezterm_target_triple())?;
         wezterm_mod.set("version", crate::wezterm_version())?;
@@ -333,7 +341,13 @@ end
 
         wezterm_mod.set(
             "default_wsl_domains",
-            lua.create_function(|_, ()| Ok(crate::WslDomain::default_domains()))?,
+            lua.create_function(
+                |_, distro_defaults: Option<HashMap<String, crate::WslDistroDefaults>>| {
+                    Ok(crate::WslDomain::default_domains(
+                        &distro_defaults.unwrap_or_default(),
+                    ))
+                },
+            )?,
         )?;
 
         wezterm_mod.set("font", lua.create_function(font)?)?;
@@ -498,6 +512,8 @@ struct LuaFontAttributes {
     pub scale: Option<NotNan<f64>>,
     #[dynamic(default)]
     pub assume_emoji_presentation: Option<bool>,
+    #[dynamic(default)]
+    pub variation: Option<BTreeMap<String, NotNan<f64>>>,
 }
 impl<'lua> FromLua<'lua> for LuaFontAttributes {
     fn from_lua(value: Value<'lua>, _lua: &'lua Lua) -> Result<Self, mlua::Error> {
@@ -585,6 +601,7 @@ fn font<'lua>(
             },
             scale: attrs.scale,
             assume_emoji_presentation: attrs.assume_emoji_presentation,
+            variation: attrs.variation,
         }));
 
     Ok(text_style)
@@ -634,6 +651,7 @@ fn font_with_fallback<'lua>(
                 },
                 scale: attrs.scale,
                 assume_emoji_presentation: attrs.assume_emoji_presentation,
+                variation: attrs.variation,
             }));
     }
 
@@ -655,7 +673,12 @@ fn action_callback<'lua>(lua: &'lua Lua, callback: mlua::Function) -> mlua::Resu
 
 fn exec_domain<'lua>(
     lua: &'lua Lua,
-    (name, fixup_command, label): (String, mlua::Function, Option<mlua::Value>),
+    (name, fixup_command, label, map_cwd): (
+        String,
+        mlua::Function,
+        Option<mlua::Value>,
+        Option<mlua::Function>,
+    ),
 ) -> mlua::Result<ExecDomain> {
     let fixup_command = {
         let event_name = format!("exec-domain-{name}");
@@ -679,10 +702,21 @@ fn exec_domain<'lua>(
         }
         None => None,
     };
+
+    let map_cwd = match map_cwd {
+        Some(callback) => {
+            let event_name = format!("exec-domain-{name}-map-cwd");
+            register_event(lua, (event_name.clone(), callback))?;
+            Some(event_name)
+        }
+        None => None,
+    };
+
     Ok(ExecDomain {
         name,
         fixup_command,
         label,
+        map_cwd,
     })
 }
 
@@ -862,6 +896,34 @@ pub fn add_to_config_reload_watch_list<'lua>(
     Ok(())
 }
 
+/// Watches the directory `path`, in which a leading `~` is expanded
+/// to the home directory, and reloads the config when it changes.
+/// `options` is a table with the optional fields of `WatchDir`
+pub fn add_dir_to_config_reload_watch_list<'lua>(
+    lua: &'lua Lua,
+    (path, options): (String, Option<Table<'lua>>),
+) -> mlua::Result<()> {
+    let options = match options {
+        Some(options) => options,
+        None => lua.create_table()?,
+    };
+    let mut dir: WatchDir = from_lua_value_dynamic(Value::Table(options))?;
+    dir.path = crate::watch::expand_home_dir(Path::new(&path), &crate::HOME_DIR);
+    let dirs: Table = lua.named_registry_value("wezterm-watch-dirs")?;
+    dirs.raw_set(dirs.raw_len() + 1, to_lua(lua, dir)?)?;
+    Ok(())
+}
+
+/// Adds an entry to the command palette, and to the menubar if
+/// its `menubar` field is set.  Registering another command with
+/// the same `brief` replaces the earlier one.
+pub fn register_command<'lua>(lua: &'lua Lua, command: Value<'lua>) -> mlua::Result<()> {
+    let command: UserCommand = from_lua_value_dynamic(command)?;
+    let commands: Table = lua.named_registry_value("wezterm-user-commands")?;
+    commands.raw_set(commands.raw_len() + 1, to_lua(lua, command)?)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -26,6 +26,8 @@ pub struct ConfigOption {
     pub type_name: &'static str,
     /// call this to get the default value
     pub default_value: Option<fn() -> Value>,
+    /// If the option is deprecated, the reason given for that
+    pub deprecated: Option<&'static str>,
     /// TODO: For enum types, the set of possible values
     pub possible_values: &'static [&'static Value],
     /// TODO: For struct types, the fields in the child struct
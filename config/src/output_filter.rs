@@ -0,0 +1,41 @@
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+/// A transformation applied to the output of a pane before it is
+/// applied to the terminal model.
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub enum OutputFilter {
+    /// Discard escape sequences, leaving only printable text and
+    /// basic control characters (newline, carriage return, tab,
+    /// backspace).  Useful for panes that tail colorized logs.
+    StripEscapes,
+    /// Replace text matching `regex` with `replacement`.
+    /// Matching happens within each run of printable text, so a
+    /// secret that is split by an escape sequence or that arrives
+    /// across separate reads may not be masked.
+    Mask {
+        regex: String,
+        #[dynamic(default = "default_mask_replacement")]
+        replacement: String,
+    },
+    /// Prefix each line with the local time, formatted using
+    /// strftime-style `format`.
+    Timestamp {
+        #[dynamic(default = "default_timestamp_format")]
+        format: String,
+    },
+    /// Report progress, as though the program had emitted OSC 9;4,
+    /// when text matches `regex`.  If the regex has capture groups
+    /// named `done` and `total`, such as `^\[(?P<done>\d+)/(?P<total>\d+)\]`
+    /// for ninja, their ratio is used; otherwise the first capture
+    /// group is taken to be a percentage.  Progress is cleared once
+    /// it reaches 100%.
+    Progress { regex: String },
+}
+
+fn default_mask_replacement() -> String {
+    "********".to_string()
+}
+
+fn default_timestamp_format() -> String {
+    "[%H:%M:%S] ".to_string()
+}
@@ -0,0 +1,156 @@
+//! Config profiles are named sets of overrides, defined in the
+//! `profiles` config table, that can be switched between at runtime.
+//! The active profile is remembered across restarts.
+use crate::*;
+
+/// Activating this name returns to the config without any profile
+/// applied, unless there is a profile with this name
+pub const DEFAULT_CONFIG_PROFILE: &str = "default";
+
+lazy_static! {
+    static ref ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(read_active_profile());
+}
+
+fn active_profile_file() -> PathBuf {
+    DATA_DIR.join("active-config-profile")
+}
+
+fn read_active_profile() -> Option<String> {
+    let name = std::fs::read_to_string(active_profile_file()).ok()?;
+    let name = name.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+fn write_active_profile(name: Option<&str>) -> anyhow::Result<()> {
+    let path = active_profile_file();
+    match name {
+        Some(name) => {
+            create_user_owned_dirs(&DATA_DIR)?;
+            std::fs::write(&path, name).with_context(|| format!("writing {}", path.display()))?;
+        }
+        None => match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("removing {}", path.display()));
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Returns the name of the active config profile, if any
+pub fn active_config_profile() -> Option<String> {
+    ACTIVE_PROFILE.lock().unwrap().clone()
+}
+
+/// Makes `name` the active config profile and reloads the config.
+/// `None` or [DEFAULT_CONFIG_PROFILE] deactivates the current profile.
+pub fn activate_config_profile(name: Option<&str>) -> anyhow::Result<()> {
+    let config = configuration();
+    let name = match name {
+        Some(name) if config.profiles.contains_key(name) => Some(name),
+        None | Some(DEFAULT_CONFIG_PROFILE) => None,
+        Some(name) => bail!("there is no config profile named `{name}`"),
+    };
+    if ACTIVE_PROFILE.lock().unwrap().as_deref() == name {
+        return Ok(());
+    }
+
+    write_active_profile(name)?;
+    *ACTIVE_PROFILE.lock().unwrap() = name.map(|name| name.to_string());
+    log::info!(
+        "activated config profile {}",
+        name.unwrap_or(DEFAULT_CONFIG_PROFILE)
+    );
+    reload();
+    Ok(())
+}
+
+/// Returns the overrides of the active profile, if it is defined by `config`
+pub(crate) fn active_profile_overrides(config: &Config) -> Option<&Value> {
+    let name = active_config_profile()?;
+    let overrides = config.profiles.get(&name);
+    if overrides.is_none() {
+        log::warn!("config profile `{name}` is not defined; using the config without it");
+    }
+    overrides
+}
+
+/// Loads the config, and then, if a profile is active, loads it again
+/// with the overrides from that profile applied
+pub(crate) fn load_with_active_profile() -> LoadedConfig {
+    let loaded = Config::load();
+    let overrides = match &loaded.config {
+        Ok(config) => match active_profile_overrides(config) {
+            Some(overrides) => overrides.clone(),
+            None => return loaded,
+        },
+        Err(_) => return loaded,
+    };
+    Config::load_with_overrides(&overrides)
+}
+
+/// Combines the overrides of the active profile with `overrides`,
+/// with the latter taking precedence
+pub(crate) fn merge_overrides(profile: &Value, overrides: &Value) -> Value {
+    match (profile, overrides) {
+        (Value::Object(profile), Value::Object(overrides)) => {
+            let mut merged = profile.clone();
+            for (key, value) in overrides.iter() {
+                merged.insert(key.clone(), value.clone());
+            }
+            Value::Object(merged)
+        }
+        (profile, Value::Null) => profile.clone(),
+        (_, overrides) => overrides.clone(),
+    }
+}
+
+pub(crate) fn validate_profiles(profiles: &HashMap<String, Value>) -> Result<(), String> {
+    for (name, overrides) in profiles {
+        match overrides {
+            Value::Object(obj) if obj.get_by_str("profiles").is_some() => {
+                return Err(format!("profile `{name}` cannot override `profiles`"));
+            }
+            Value::Object(_) => {}
+            _ => return Err(format!("profile `{name}` must be a table")),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let profile = Value::Object(
+            vec![
+                ("font_size".to_dynamic(), 20.0.to_dynamic()),
+                ("color_scheme".to_dynamic(), "Light".to_dynamic()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let overrides = Value::Object(
+            vec![("font_size".to_dynamic(), 12.0.to_dynamic())]
+                .into_iter()
+                .collect(),
+        );
+
+        let merged = merge_overrides(&profile, &overrides);
+        let merged = match &merged {
+            Value::Object(obj) => obj,
+            _ => panic!("merged overrides are not an object"),
+        };
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged.get_by_str("font_size"), Some(&12.0.to_dynamic()));
+        assert_eq!(merge_overrides(&profile, &Value::Null), profile);
+    }
+}
@@ -0,0 +1,196 @@
+use luahelper::impl_lua_conversion_dynamic;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// An HTTP proxy that supports the CONNECT method
+    Http,
+    Socks5,
+}
+
+/// A proxy server resolved from `http_proxy`, `socks5_proxy`
+/// or `proxy_overrides`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyServer {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+}
+
+impl ProxyServer {
+    /// Parses a proxy of the form `[scheme://]host:port`.  `kind`
+    /// applies when no scheme is given; otherwise the scheme must be
+    /// one of `http`, `socks5` or `socks5h`.
+    pub fn parse(spec: &str, kind: ProxyKind) -> anyhow::Result<Self> {
+        let (kind, address) = match spec.split_once("://") {
+            Some(("http", address)) => (ProxyKind::Http, address),
+            Some(("socks5" | "socks5h", address)) => (ProxyKind::Socks5, address),
+            Some((scheme, _)) => anyhow::bail!("unsupported proxy scheme `{scheme}` in {spec}"),
+            None => (kind, spec),
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = address
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow::anyhow!("proxy {spec} must have the form host:port"))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            anyhow::bail!("proxy {spec} has no host");
+        }
+        let port = port
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid port in proxy {spec}: {err}"))?;
+        Ok(Self {
+            kind,
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// The proxy in the url form understood by curl.  SOCKS5 proxies
+    /// resolve host names on the proxy side.
+    pub fn url(&self) -> String {
+        let scheme = match self.kind {
+            ProxyKind::Http => "http",
+            ProxyKind::Socks5 => "socks5h",
+        };
+        format!("{scheme}://{}", self.address())
+    }
+
+    pub fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// An ssh `ProxyCommand` that tunnels to `host:port` through
+    /// this proxy using the BSD netcat that ships with macOS
+    pub fn netcat_proxy_command(&self, host: &str, port: u16) -> String {
+        let protocol = match self.kind {
+            ProxyKind::Http => "connect",
+            ProxyKind::Socks5 => "5",
+        };
+        format!("nc -X {protocol} -x {} {host} {port}", self.address())
+    }
+}
+
+/// Overrides the proxy used for connections to a particular domain
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct ProxyOverride {
+    /// The host name that this override applies to.  Subdomains
+    /// also match, and a leading `*.` is ignored, so `example.com`
+    /// and `*.example.com` both match `ssh.example.com`.
+    pub domain: String,
+    #[dynamic(default, validate = "validate_http_proxy")]
+    pub http_proxy: Option<String>,
+    #[dynamic(default, validate = "validate_socks5_proxy")]
+    pub socks5_proxy: Option<String>,
+}
+impl_lua_conversion_dynamic!(ProxyOverride);
+
+impl ProxyOverride {
+    pub fn matches_host(&self, host: &str) -> bool {
+        let domain = self.domain.trim_start_matches("*.").trim_start_matches('.');
+        let host = host.trim_end_matches('.');
+        if host.eq_ignore_ascii_case(domain) {
+            return true;
+        }
+        host.len() > domain.len()
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+            && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+    }
+}
+
+/// Picks the proxy for `host`.  The first matching override wins, and
+/// an override that specifies neither proxy means a direct connection.
+/// A SOCKS5 proxy is preferred over an HTTP proxy when both are set,
+/// as it can carry any kind of connection.
+pub(crate) fn resolve_proxy(
+    host: &str,
+    http_proxy: Option<&str>,
+    socks5_proxy: Option<&str>,
+    overrides: &[ProxyOverride],
+) -> Option<ProxyServer> {
+    let (http_proxy, socks5_proxy) = match overrides.iter().find(|o| o.matches_host(host)) {
+        Some(o) => (o.http_proxy.as_deref(), o.socks5_proxy.as_deref()),
+        None => (http_proxy, socks5_proxy),
+    };
+    let (spec, kind) = match (socks5_proxy, http_proxy) {
+        (Some(spec), _) => (spec, ProxyKind::Socks5),
+        (None, Some(spec)) => (spec, ProxyKind::Http),
+        (None, None) => return None,
+    };
+    match ProxyServer::parse(spec, kind) {
+        Ok(proxy) => Some(proxy),
+        Err(err) => {
+            log::error!("ignoring proxy for {host}: {err:#}");
+            None
+        }
+    }
+}
+
+pub(crate) fn validate_http_proxy(value: &Option<String>) -> Result<(), String> {
+    match value {
+        Some(spec) => ProxyServer::parse(spec, ProxyKind::Http)
+            .map(|_| ())
+            .map_err(|err| format!("{err:#}")),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn validate_socks5_proxy(value: &Option<String>) -> Result<(), String> {
+    match value {
+        Some(spec) => ProxyServer::parse(spec, ProxyKind::Socks5)
+            .map(|_| ())
+            .map_err(|err| format!("{err:#}")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_proxy() {
+        assert_eq!(
+            ProxyServer::parse("proxy.corp:3128", ProxyKind::Http).unwrap(),
+            ProxyServer {
+                kind: ProxyKind::Http,
+                host: "proxy.corp".to_string(),
+                port: 3128,
+            }
+        );
+        let socks = ProxyServer::parse("socks5://127.0.0.1:1080/", ProxyKind::Http).unwrap();
+        assert_eq!(socks.kind, ProxyKind::Socks5);
+        assert_eq!(socks.url(), "socks5h://127.0.0.1:1080");
+        assert_eq!(
+            socks.netcat_proxy_command("bastion", 22),
+            "nc -X 5 -x 127.0.0.1:1080 bastion 22"
+        );
+        assert!(ProxyServer::parse("proxy.corp", ProxyKind::Http).is_err());
+        assert!(ProxyServer::parse("ftp://proxy.corp:21", ProxyKind::Http).is_err());
+    }
+
+    #[test]
+    fn overrides() {
+        let overrides = vec![
+            ProxyOverride {
+                domain: "*.internal.corp".to_string(),
+                http_proxy: None,
+                socks5_proxy: None,
+            },
+            ProxyOverride {
+                domain: "github.com".to_string(),
+                http_proxy: Some("gh-proxy:8080".to_string()),
+                socks5_proxy: None,
+            },
+        ];
+        let resolve = |host| resolve_proxy(host, None, Some("socks:1080"), &overrides);
+
+        assert_eq!(resolve("ssh.internal.corp"), None);
+        assert_eq!(resolve("api.github.com").unwrap().host, "gh-proxy");
+        assert_eq!(resolve("notgithub.com").unwrap().kind, ProxyKind::Socks5);
+    }
+}
@@ -0,0 +1,126 @@
+//! An index of metadata for the built-in color schemes.
+//! Only the `[metadata]` table and the background color of each
+//! scheme are parsed, so answering questions such as "which schemes
+//! are dark?" doesn't require loading all of the palettes.
+use crate::scheme_data::SCHEMES;
+use crate::{ColorSchemeMetaData, SrgbaTuple};
+use lazy_static::lazy_static;
+use luahelper::impl_lua_conversion_dynamic;
+use std::str::FromStr;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+lazy_static! {
+    static ref BUILTIN_SCHEME_METADATA: Vec<SchemeMetadata> = SCHEMES
+        .iter()
+        .map(|(name, data)| SchemeMetadata::from_scheme_data(name, data))
+        .collect();
+}
+
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct SchemeMetadata {
+    pub name: String,
+    pub author: Option<String>,
+    pub origin_url: Option<String>,
+    #[dynamic(default)]
+    pub aliases: Vec<String>,
+    /// true if the background color of the scheme is dark
+    pub is_dark: bool,
+    /// Lowercase words taken from the name and aliases, such as
+    /// `solarized` or `gogh`, along with either `dark` or `light`
+    #[dynamic(default)]
+    pub tags: Vec<String>,
+}
+impl_lua_conversion_dynamic!(SchemeMetadata);
+
+impl SchemeMetadata {
+    fn from_scheme_data(name: &str, data: &str) -> Self {
+        let metadata = data
+            .find("[metadata]")
+            .and_then(|idx| toml::from_str::<toml::Value>(&data[idx..]).ok())
+            .and_then(|value| value.get("metadata").cloned())
+            .and_then(|value| {
+                ColorSchemeMetaData::from_dynamic(
+                    &crate::toml_to_dynamic(&value),
+                    Default::default(),
+                )
+                .ok()
+            });
+        let (author, origin_url, aliases) = match metadata {
+            Some(m) => (m.author, m.origin_url, m.aliases),
+            None => (None, None, vec![]),
+        };
+
+        let is_dark = background_color(data)
+            .map(|bg| bg.to_linear().relative_luminance() < 0.2)
+            .unwrap_or(true);
+
+        let mut tags = vec![];
+        for text in std::iter::once(name).chain(aliases.iter().map(|s| s.as_str())) {
+            for word in text
+                .split(|c: char| !c.is_alphanumeric() && c != '.')
+                .map(|w| w.trim_matches('.').to_lowercase())
+                .filter(|w| !w.is_empty())
+            {
+                if !tags.contains(&word) {
+                    tags.push(word);
+                }
+            }
+        }
+        let brightness = if is_dark { "dark" } else { "light" };
+        if !tags.iter().any(|t| t == brightness) {
+            tags.push(brightness.to_string());
+        }
+
+        Self {
+            name: name.to_string(),
+            author,
+            origin_url,
+            aliases,
+            is_dark,
+            tags,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.to_lowercase();
+        self.tags.iter().any(|t| *t == tag)
+    }
+}
+
+/// Extracts the top level `background = "..."` entry from the
+/// `[colors]` table of a scheme
+fn background_color(data: &str) -> Option<SrgbaTuple> {
+    let colors = match data.find("\n[") {
+        Some(end) => &data[..end],
+        None => data,
+    };
+    colors.lines().find_map(|line| {
+        let value = line.strip_prefix("background = ")?;
+        SrgbaTuple::from_str(value.trim().trim_matches('"')).ok()
+    })
+}
+
+/// Returns metadata for all of the built-in color schemes
+pub fn builtin_scheme_metadata() -> &'static [SchemeMetadata] {
+    &BUILTIN_SCHEME_METADATA
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metadata_without_palette() {
+        let data = "[colors]\nansi = []\nbackground = \"#fdf6e3\"\nforeground = \"#657b83\"\n\n\
+                    [colors.indexed]\n\n[metadata]\naliases = [\"Solarized Light (Gogh)\"]\n\
+                    author = \"Ethan Schoonover\"\nname = \"Solarized (light) (terminal.sexy)\"\n";
+        let meta = SchemeMetadata::from_scheme_data("Solarized (light) (terminal.sexy)", data);
+        assert!(!meta.is_dark);
+        assert_eq!(meta.author.as_deref(), Some("Ethan Schoonover"));
+        assert!(meta.has_tag("solarized"));
+        assert!(meta.has_tag("Gogh"));
+        assert!(meta.has_tag("terminal.sexy"));
+        assert!(meta.has_tag("light"));
+        assert!(!meta.has_tag("dark"));
+    }
+}
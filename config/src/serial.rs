@@ -1,6 +1,59 @@
 use crate::config::validate_domain_name;
 use wezterm_dynamic::{FromDynamic, ToDynamic};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SerialFlowControl {
+    None,
+    XonXoff,
+    RtsCts,
+}
+
+impl Default for SerialFlowControl {
+    fn default() -> Self {
+        Self::XonXoff
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SerialParity {
+    None,
+    Odd,
+    Even,
+}
+
+impl Default for SerialParity {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SerialStopBits {
+    One,
+    Two,
+}
+
+impl Default for SerialStopBits {
+    fn default() -> Self {
+        Self::One
+    }
+}
+
+/// What is sent to the serial port in place of the carriage return
+/// produced by the Enter key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromDynamic, ToDynamic)]
+pub enum SerialLineEnding {
+    CR,
+    LF,
+    CRLF,
+}
+
+impl Default for SerialLineEnding {
+    fn default() -> Self {
+        Self::CR
+    }
+}
+
 #[derive(Default, Debug, Clone, FromDynamic, ToDynamic)]
 pub struct SerialDomain {
     /// The name of this specific domain.  Must be unique amongst
@@ -16,4 +69,22 @@ pub struct SerialDomain {
 
     /// Set the baud rate.  The default is 9600 baud.
     pub baud: Option<usize>,
+
+    /// Set the flow control.  The default is XonXoff.
+    #[dynamic(default)]
+    pub flow_control: SerialFlowControl,
+
+    /// Set the parity.  The default is None.
+    #[dynamic(default)]
+    pub parity: SerialParity,
+
+    /// Set the number of stop bits.  The default is One.
+    #[dynamic(default)]
+    pub stop_bits: SerialStopBits,
+
+    /// What to send when Enter is pressed.  The default is CR.
+    /// Can be changed while connected with the SetSerialLineEnding
+    /// key assignment.
+    #[dynamic(default)]
+    pub line_ending: SerialLineEnding,
 }
@@ -61,6 +61,18 @@ pub struct SshDomain {
     #[dynamic(default)]
     pub no_agent_auth: bool,
 
+    /// If true, forward the local ssh agent to the remote host.
+    /// Otherwise, ForwardAgent from the ssh config files applies.
+    /// Agent forwarding is only supported by the LibSsh ssh_backend.
+    #[dynamic(default)]
+    pub forward_agent: bool,
+
+    /// Private keys to authenticate with, in place of the IdentityFile
+    /// entries from the ssh config files.  A leading `~` is expanded
+    /// to the home directory.
+    #[dynamic(default)]
+    pub identity_files: Vec<PathBuf>,
+
     /// The username to use for authenticating with the remote host
     pub username: Option<String>,
 
@@ -111,10 +123,17 @@ impl SshDomain {
     pub fn default_domains() -> Vec<Self> {
         let mut config = wezterm_ssh::Config::new();
         config.add_default_config_files();
+        Self::domains_for_hosts(&config.enumerate_hosts())
+    }
 
+    /// Returns an `SSH:<host>` and an `SSHMUX:<host>` domain for each
+    /// of `hosts`.  The remote address is the host alias itself, so that
+    /// HostName, User, Port, ProxyJump and the other options for the
+    /// host are taken from the ssh config files when connecting.
+    fn domains_for_hosts(hosts: &[String]) -> Vec<Self> {
         let mut plain_ssh = vec![];
         let mut mux_ssh = vec![];
-        for host in config.enumerate_hosts() {
+        for host in hosts {
             plain_ssh.push(Self {
                 name: format!("SSH:{host}"),
                 remote_address: host.to_string(),
@@ -135,6 +154,26 @@ impl SshDomain {
         plain_ssh.append(&mut mux_ssh);
         plain_ssh
     }
+
+    /// Returns `explicit` followed by those of the `default_domains`
+    /// whose host doesn't match any of the `exclude` patterns and
+    /// whose name isn't already taken by one of `explicit`
+    pub fn merge_auto_domains(
+        explicit: &[Self],
+        default_domains: Vec<Self>,
+        exclude: &[String],
+    ) -> Vec<Self> {
+        let mut domains = explicit.to_vec();
+        for domain in default_domains {
+            let excluded = exclude
+                .iter()
+                .any(|pattern| crate::watch::wildcard_match(pattern, &domain.remote_address));
+            if !excluded && !domains.iter().any(|d| d.name == domain.name) {
+                domains.push(domain);
+            }
+        }
+        domains
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -183,3 +222,36 @@ impl FromStr for SshParameters {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge_auto_domains() {
+        let explicit = vec![SshDomain {
+            name: "SSH:build".to_string(),
+            remote_address: "build.example.com".to_string(),
+            ..SshDomain::default()
+        }];
+        let hosts = vec![
+            "build".to_string(),
+            "work-db".to_string(),
+            "work-web".to_string(),
+            "home".to_string(),
+        ];
+        let domains = SshDomain::merge_auto_domains(
+            &explicit,
+            SshDomain::domains_for_hosts(&hosts),
+            &["work-*".to_string()],
+        );
+        let names: Vec<&str> = domains.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["SSH:build", "SSH:home", "SSHMUX:build", "SSHMUX:home"]
+        );
+        // The explicit domain wins over the one from the ssh config
+        assert_eq!(domains[0].remote_address, "build.example.com");
+        assert_eq!(domains[3].multiplexing, SshMultiplexing::WezTerm);
+    }
+}
@@ -1,6 +1,6 @@
 //! Bridge our gui config into the terminal crate configuration
 
-use crate::{configuration, ConfigHandle, NewlineCanon};
+use crate::{configuration, ConfigHandle, MouseReportingGranularity, NewlineCanon};
 use std::sync::Mutex;
 use termwiz::cell::UnicodeVersion;
 use wezterm_term::color::ColorPalette;
@@ -113,6 +113,17 @@ impl wezterm_term::TerminalConfiguration for TermConfig {
         self.configuration().log_unknown_escape_sequences
     }
 
+    fn mouse_reporting_granularity(&self) -> wezterm_term::config::MouseReportingGranularity {
+        match self.configuration().mouse_reporting_granularity {
+            MouseReportingGranularity::Pixel => {
+                wezterm_term::config::MouseReportingGranularity::Pixel
+            }
+            MouseReportingGranularity::Cell => {
+                wezterm_term::config::MouseReportingGranularity::Cell
+            }
+        }
+    }
+
     fn normalize_output_to_unicode_nfc(&self) -> bool {
         self.configuration().normalize_output_to_unicode_nfc
     }
@@ -94,9 +94,43 @@ pub struct TlsDomainClient {
     /// instead.
     #[dynamic(default)]
     pub overlay_lag_indicator: bool,
+
+    /// If true, `pem_cert`, `pem_private_key` and `pem_ca` are watched
+    /// and the domain reconnects once they have been replaced, so that
+    /// rotated certificates are used without restarting
+    #[dynamic(default = "default_true")]
+    pub reconnect_on_cert_change: bool,
+
+    /// Warn, with a toast notification and the `tls-certificate-expiring`
+    /// event, when the client certificate expires within this many
+    /// days.  0 disables the warning.
+    #[dynamic(default = "default_cert_expiry_warning_days")]
+    pub cert_expiry_warning_days: u64,
+
+    /// Instead of verifying the server certificate against a CA, pin
+    /// the SHA-256 fingerprint of the certificate that the server
+    /// presents the first time, in `DATA_DIR/known_mux_servers`, and
+    /// refuse to connect if it presents a different one later.
+    /// A client certificate is only presented if one is configured.
+    /// This is used by the domains created for servers found by
+    /// `mdns_discovery`.
+    #[dynamic(default)]
+    pub trust_on_first_use: bool,
+}
+
+fn default_cert_expiry_warning_days() -> u64 {
+    14
 }
 
 impl TlsDomainClient {
+    /// Returns the configured certificate and key files
+    pub fn cert_files(&self) -> Vec<PathBuf> {
+        [&self.pem_cert, &self.pem_private_key, &self.pem_ca]
+            .into_iter()
+            .filter_map(|path| path.clone())
+            .collect()
+    }
+
     pub fn ssh_parameters(&self) -> Option<anyhow::Result<SshParameters>> {
         self.bootstrap_via_ssh
             .as_ref()
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::str::FromStr;
 use wezterm_dynamic::{FromDynamic, FromDynamicOptions, ToDynamic, Value};
 
@@ -250,6 +251,79 @@ impl FromStr for GuiPosition {
     }
 }
 
+/// The size of a window, expressed as `WIDTHxHEIGHT[@SCREEN]`.
+/// Each dimension is either a number of pixels, such as `1200` or
+/// `1200px`, or a percentage of the size of the screen, such as `80%`.
+/// SCREEN is one of `main` (or `primary`), `active`, `screen` or the
+/// name of a monitor, and defaults to `main`.
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+#[dynamic(try_from = "String", into = "String")]
+pub struct GuiGeometry {
+    pub width: Dimension,
+    pub height: Dimension,
+    pub origin: GeometryOrigin,
+}
+
+impl FromStr for GuiGeometry {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<GuiGeometry> {
+        let (size, origin) = match s.split_once('@') {
+            Some((size, "primary")) => (size, GeometryOrigin::MainScreen),
+            Some((size, screen)) => (size, GuiPosition::parse_origin(screen)),
+            None => (s, GeometryOrigin::MainScreen),
+        };
+        let (width, height) = size
+            .split_once('x')
+            .ok_or_else(|| anyhow::anyhow!("invalid geometry spec {}; expected WIDTHxHEIGHT", s))?;
+        let width = GuiPosition::parse_dim(width)?;
+        let height = GuiPosition::parse_dim(height)?;
+        if width.is_zero() || height.is_zero() {
+            anyhow::bail!(
+                "invalid geometry spec {}; width and height must be non-zero",
+                s
+            );
+        }
+        Ok(GuiGeometry {
+            width,
+            height,
+            origin,
+        })
+    }
+}
+
+impl TryFrom<String> for GuiGeometry {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> anyhow::Result<GuiGeometry> {
+        GuiGeometry::from_str(&s)
+    }
+}
+
+impl From<&GuiGeometry> for String {
+    fn from(geometry: &GuiGeometry) -> String {
+        fn dim(d: &Dimension) -> String {
+            match d {
+                Dimension::Percent(n) => format!("{}%", n * 100.),
+                Dimension::Pixels(n) | Dimension::Points(n) | Dimension::Cells(n) => {
+                    format!("{}", n)
+                }
+            }
+        }
+        let size = format!("{}x{}", dim(&geometry.width), dim(&geometry.height));
+        match &geometry.origin {
+            GeometryOrigin::MainScreen => size,
+            GeometryOrigin::ActiveScreen => format!("{size}@active"),
+            GeometryOrigin::ScreenCoordinateSystem => format!("{size}@screen"),
+            GeometryOrigin::Named(name) => format!("{size}@{name}"),
+        }
+    }
+}
+
+impl From<GuiGeometry> for String {
+    fn from(geometry: GuiGeometry) -> String {
+        (&geometry).into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -310,4 +384,28 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn geometry() {
+        assert_eq!(
+            GuiGeometry::from_str("80%x60%@primary").unwrap(),
+            GuiGeometry {
+                width: Dimension::Percent(0.8),
+                height: Dimension::Percent(0.6),
+                origin: GeometryOrigin::MainScreen
+            }
+        );
+        assert_eq!(
+            GuiGeometry::from_str("1200x800px@HDMI-1").unwrap(),
+            GuiGeometry {
+                width: Dimension::Pixels(1200.),
+                height: Dimension::Pixels(800.),
+                origin: GeometryOrigin::Named("HDMI-1".to_string())
+            }
+        );
+        let geometry = GuiGeometry::from_str("50%x900@active").unwrap();
+        assert_eq!(String::from(&geometry), "50%x900@active");
+        assert!(GuiGeometry::from_str("80%").is_err());
+        assert!(GuiGeometry::from_str("0x600").is_err());
+    }
 }
@@ -0,0 +1,250 @@
+use crate::*;
+use std::path::Component;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+/// A directory registered by `wezterm.add_dir_to_config_reload_watch_list`,
+/// changes within which cause the config to be reloaded
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct WatchDir {
+    #[dynamic(default)]
+    pub path: PathBuf,
+    /// Whether changes in subdirectories are also considered
+    #[dynamic(default = "default_true")]
+    pub recursive: bool,
+    /// If non-empty, only changes to files whose names match one of
+    /// these patterns trigger a reload.  `*` matches any sequence
+    /// of characters and `?` matches any single character.
+    #[dynamic(default)]
+    pub filter: Vec<String>,
+    /// How long to wait for changes to settle before reloading
+    #[dynamic(default = "default_watch_dir_debounce")]
+    pub debounce_milliseconds: u64,
+}
+
+fn default_watch_dir_debounce() -> u64 {
+    200
+}
+
+impl WatchDir {
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_milliseconds)
+    }
+
+    /// Returns true if a change to `path` should cause a reload
+    pub fn matches(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.path) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        let mut components = relative.components();
+        let name = match components.next_back() {
+            Some(Component::Normal(name)) => name.to_string_lossy(),
+            // The directory itself
+            _ => return true,
+        };
+        if !self.recursive && components.next().is_some() {
+            return false;
+        }
+        self.filter.is_empty()
+            || self
+                .filter
+                .iter()
+                .any(|pattern| wildcard_match(pattern, &name))
+    }
+}
+
+/// The files and directories that the config reload watcher acts on
+#[derive(Default, Debug)]
+pub(crate) struct WatchList {
+    pub files: Vec<PathBuf>,
+    pub dirs: Vec<WatchDir>,
+}
+
+impl WatchList {
+    /// Compares the watched directories with `dirs`, which are to
+    /// replace them.  Returns those that are no longer watched and
+    /// those that are newly watched; a directory whose recursive mode
+    /// changes appears in both.  Directories that are watched the
+    /// same way in both are left alone, so that a reload doesn't
+    /// re-register every directory with the watcher.
+    pub fn dir_changes<'a>(
+        &'a self,
+        dirs: &'a [WatchDir],
+    ) -> (Vec<&'a WatchDir>, Vec<&'a WatchDir>) {
+        fn same_watch(a: &WatchDir, b: &WatchDir) -> bool {
+            a.path == b.path && a.recursive == b.recursive
+        }
+        let removed = self
+            .dirs
+            .iter()
+            .filter(|dir| !dirs.iter().any(|d| same_watch(d, dir)))
+            .collect();
+        let added = dirs
+            .iter()
+            .filter(|dir| !self.dirs.iter().any(|d| same_watch(d, dir)))
+            .collect();
+        (removed, added)
+    }
+
+    /// Returns how long to wait before reloading in response to
+    /// changes to `paths`, or None if none of them are of interest.
+    /// Changes to files outside of the watched directories come from
+    /// the individually watched files.
+    pub fn reload_delay(&self, paths: &[PathBuf], file_delay: Duration) -> Option<Duration> {
+        let mut delay = None;
+        for path in paths {
+            let in_dir = self.dirs.iter().any(|dir| path.starts_with(&dir.path));
+            let path_delay = if !in_dir || self.files.contains(path) {
+                Some(file_delay)
+            } else {
+                self.dirs
+                    .iter()
+                    .filter(|dir| dir.matches(path))
+                    .map(WatchDir::debounce)
+                    .max()
+            };
+            delay = delay.max(path_delay);
+        }
+        delay
+    }
+}
+
+/// Returns `path` with a leading `~` expanded to `home`
+pub(crate) fn expand_home_dir(path: &Path, home: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home.join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any sequence
+/// of characters and `?` matches any single character
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` in the pattern, and the position
+    // in the text that it was matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wildcard() {
+        assert!(wildcard_match("*.toml", "Dracula.toml"));
+        assert!(!wildcard_match("*.toml", "Dracula.toml.swp"));
+        assert!(wildcard_match("theme-?.lua", "theme-a.lua"));
+        assert!(wildcard_match("*a*b", "xaxxab"));
+        assert!(!wildcard_match("?", ""));
+    }
+
+    #[test]
+    fn watch_dir_matches() {
+        let dir = WatchDir {
+            path: PathBuf::from("/home/me/.config/kaku/colors"),
+            recursive: false,
+            filter: vec!["*.toml".to_string()],
+            debounce_milliseconds: 200,
+        };
+        assert!(dir.matches(Path::new("/home/me/.config/kaku/colors/new.toml")));
+        assert!(!dir.matches(Path::new("/home/me/.config/kaku/colors/.new.toml.swp")));
+        assert!(!dir.matches(Path::new("/home/me/.config/kaku/colors/a/b.toml")));
+        assert!(!dir.matches(Path::new("/home/me/.config/kaku/kaku.lua")));
+
+        let dir = WatchDir {
+            recursive: true,
+            ..dir
+        };
+        assert!(dir.matches(Path::new("/home/me/.config/kaku/colors/a/b.toml")));
+    }
+
+    fn watch_dir(path: &str, recursive: bool) -> WatchDir {
+        WatchDir {
+            path: PathBuf::from(path),
+            recursive,
+            filter: vec![],
+            debounce_milliseconds: 200,
+        }
+    }
+
+    #[test]
+    fn dir_changes() {
+        let list = WatchList {
+            files: vec![],
+            dirs: vec![
+                watch_dir("/colors", true),
+                watch_dir("/themes", true),
+                watch_dir("/gone", false),
+            ],
+        };
+        let dirs = vec![
+            watch_dir("/colors", true),
+            watch_dir("/themes", false),
+            watch_dir("/new", true),
+        ];
+        let (removed, added) = list.dir_changes(&dirs);
+        let paths = |dirs: Vec<&WatchDir>| -> Vec<PathBuf> {
+            dirs.into_iter().map(|d| d.path.clone()).collect()
+        };
+        assert_eq!(
+            paths(removed),
+            vec![PathBuf::from("/themes"), PathBuf::from("/gone")]
+        );
+        assert_eq!(
+            paths(added),
+            vec![PathBuf::from("/themes"), PathBuf::from("/new")]
+        );
+
+        // Reloading with the same set changes nothing
+        let list = WatchList {
+            files: vec![],
+            dirs: dirs.clone(),
+        };
+        let (removed, added) = list.dir_changes(&dirs);
+        assert!(removed.is_empty());
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn expand_home() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            expand_home_dir(Path::new("~/.config/kaku/colors"), home),
+            PathBuf::from("/home/me/.config/kaku/colors")
+        );
+        assert_eq!(expand_home_dir(Path::new("~"), home), home);
+        assert_eq!(
+            expand_home_dir(Path::new("/etc/kaku"), home),
+            PathBuf::from("/etc/kaku")
+        );
+        assert_eq!(
+            expand_home_dir(Path::new("~other/x"), home),
+            PathBuf::from("~other/x")
+        );
+    }
+}
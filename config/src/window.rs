@@ -1,4 +1,5 @@
-use wezterm_dynamic::{FromDynamic, ToDynamic};
+use crate::keyassignment::KeyAssignment;
+use wezterm_dynamic::{FromDynamic, FromDynamicOptions, ToDynamic, Value};
 
 #[derive(Debug, Default, Clone, ToDynamic, PartialEq, Eq, FromDynamic)]
 pub enum WindowLevel {
@@ -7,3 +8,112 @@ pub enum WindowLevel {
     Normal = 0,
     AlwaysOnTop = 3,
 }
+
+/// A user-defined button shown in the tab bar, to the right of the
+/// new tab button, that performs `action` when clicked.
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct TabBarButton {
+    /// The text of the button; may include escape sequences
+    /// generated by `wezterm.format`
+    pub label: String,
+    /// The text to show while the mouse is over the button.
+    /// Defaults to `label`.
+    #[dynamic(default)]
+    pub hover_label: Option<String>,
+    pub action: KeyAssignment,
+}
+
+/// Where the macOS window buttons ("traffic lights") are placed,
+/// measured in points from the top left corner of the window to
+/// the top left corner of the close button.
+#[derive(Debug, Clone, Copy, PartialEq, FromDynamic, ToDynamic)]
+pub struct MacOsWindowButtonPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Identifies a monitor either by its position in the list of
+/// monitors, which are numbered from left to right starting at 0,
+/// or by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl FromDynamic for MonitorSelector {
+    fn from_dynamic(
+        value: &Value,
+        options: FromDynamicOptions,
+    ) -> Result<Self, wezterm_dynamic::Error> {
+        match value {
+            Value::String(name) => Ok(Self::Name(name.to_string())),
+            _ => match usize::from_dynamic(value, options) {
+                Ok(idx) => Ok(Self::Index(idx)),
+                Err(_) => Err(wezterm_dynamic::Error::Message(format!(
+                    "expected either a monitor index or name, but got {}",
+                    value.variant_name()
+                ))),
+            },
+        }
+    }
+}
+
+impl ToDynamic for MonitorSelector {
+    fn to_dynamic(&self) -> Value {
+        match self {
+            Self::Index(idx) => idx.to_dynamic(),
+            Self::Name(name) => name.to_dynamic(),
+        }
+    }
+}
+
+/// Where a window is placed on its monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, FromDynamic, ToDynamic)]
+pub enum WindowPlacement {
+    /// Keep the size of the window and center it
+    #[default]
+    Centered,
+    LeftHalf,
+    RightHalf,
+    TopHalf,
+    BottomHalf,
+    /// Cover the whole of the monitor
+    Fill,
+}
+
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct WindowPlacementRule {
+    /// Only apply this rule to windows whose active workspace has
+    /// this name.  If omitted, the rule applies to all windows.
+    #[dynamic(default)]
+    pub workspace: Option<String>,
+    pub monitor: MonitorSelector,
+    #[dynamic(default)]
+    pub position: WindowPlacement,
+}
+
+impl WindowPlacementRule {
+    pub fn matches_workspace(&self, workspace: &str) -> bool {
+        match &self.workspace {
+            Some(w) => w == workspace,
+            None => true,
+        }
+    }
+}
+
+/// The monitor on which a new window opens when neither its position
+/// nor `window_placement_rules` place it elsewhere
+#[derive(Debug, Clone, PartialEq, Eq, Default, FromDynamic, ToDynamic)]
+pub enum SpawnWindowOn {
+    /// Leave it to the system, which usually picks the monitor of
+    /// the focused window
+    #[default]
+    ActiveScreen,
+    /// The monitor that contains the mouse pointer
+    CursorScreen,
+    /// The monitor with the menu bar
+    PrimaryScreen,
+    /// The monitor with this name, as listed by `wezterm.gui.screens()`
+    ScreenNamed(String),
+}
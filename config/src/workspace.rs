@@ -0,0 +1,34 @@
+use crate::*;
+use luahelper::impl_lua_conversion_dynamic;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+/// Settings that apply to a particular workspace: overrides for the
+/// spawn defaults of new tabs, windows and splits created in it, and
+/// the colors of the windows that show it
+#[derive(Default, Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct WorkspaceSpawnDefaults {
+    /// The domain used in place of `default_domain` when a spawn
+    /// requests the default domain
+    #[dynamic(default)]
+    pub default_domain: Option<String>,
+    /// Used in place of `default_prog` when no command is given
+    #[dynamic(default)]
+    pub default_prog: Option<Vec<String>>,
+    /// Used in place of `default_cwd` when neither the spawn nor the
+    /// current pane provide a working directory
+    #[dynamic(default)]
+    pub default_cwd: Option<PathBuf>,
+    /// Environment variables set for programs spawned in the workspace
+    /// by the local domain.  They take precedence over those from
+    /// `set_environment_variables`, but not over those set by the spawn.
+    #[dynamic(default)]
+    pub env: HashMap<String, String>,
+    /// Used in place of `color_scheme` by windows showing the workspace
+    #[dynamic(default)]
+    pub color_scheme: Option<String>,
+    /// The background color of the active tab in the tab bar, to tell
+    /// at a glance which workspace a window is showing
+    #[dynamic(default)]
+    pub accent_color: Option<RgbaColor>,
+}
+impl_lua_conversion_dynamic!(WorkspaceSpawnDefaults);
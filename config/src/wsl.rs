@@ -12,28 +12,70 @@ pub struct WslDomain {
     pub username: Option<String>,
     pub default_cwd: Option<PathBuf>,
     pub default_prog: Option<Vec<String>>,
+    /// When true, SSH_AUTH_SOCK in the distro is set to a socket
+    /// that relays requests to the Windows OpenSSH agent.
+    /// The relay needs `socat` to be installed in the distro.
+    #[dynamic(default)]
+    pub forward_ssh_agent: bool,
+    /// The named pipe of the Windows agent that `forward_ssh_agent`
+    /// relays to.  Defaults to `\\.\pipe\openssh-ssh-agent`.
+    pub ssh_agent_pipe: Option<String>,
+    /// When true and SSH_AUTH_SOCK is set in the Windows environment,
+    /// pass it through to the distro via WSLENV, translating the path.
+    /// Has no effect when `forward_ssh_agent` is enabled.
+    #[dynamic(default)]
+    pub share_ssh_auth_sock: bool,
 }
 impl_lua_conversion_dynamic!(WslDomain);
 
-impl WslDomain {
-    pub fn default_domains() -> Vec<WslDomain> {
-        #[allow(unused_mut)]
-        let mut domains = vec![];
+/// Defaults applied to the domain that is automatically created
+/// for a WSL distribution, see `wsl_distro_defaults`
+#[derive(Default, Debug, Clone, FromDynamic, ToDynamic)]
+pub struct WslDistroDefaults {
+    pub username: Option<String>,
+    pub default_cwd: Option<PathBuf>,
+    pub default_prog: Option<Vec<String>>,
+}
+impl_lua_conversion_dynamic!(WslDistroDefaults);
 
+impl WslDomain {
+    pub fn default_domains(distro_defaults: &HashMap<String, WslDistroDefaults>) -> Vec<WslDomain> {
         #[cfg(windows)]
         if let Ok(distros) = WslDistro::load_distro_list() {
-            for distro in distros {
-                domains.push(WslDomain {
-                    name: format!("WSL:{}", distro.name),
-                    distribution: Some(distro.name.clone()),
-                    username: None,
-                    default_cwd: Some("~".into()),
-                    default_prog: None,
-                });
-            }
+            return Self::domains_for_distros(&distros, distro_defaults);
         }
 
-        domains
+        #[cfg(not(windows))]
+        let _ = distro_defaults;
+
+        vec![]
+    }
+
+    /// Builds a domain for each distribution in `distros`, skipping
+    /// the internal ones used by Docker Desktop, which have no shell
+    #[cfg(any(windows, test))]
+    fn domains_for_distros(
+        distros: &[WslDistro],
+        distro_defaults: &HashMap<String, WslDistroDefaults>,
+    ) -> Vec<WslDomain> {
+        distros
+            .iter()
+            .filter(|distro| !distro.name.starts_with("docker-desktop"))
+            .map(|distro| {
+                let defaults = distro_defaults
+                    .get(&distro.name)
+                    .cloned()
+                    .unwrap_or_default();
+                WslDomain {
+                    name: format!("WSL:{}", distro.name),
+                    distribution: Some(distro.name.clone()),
+                    username: defaults.username,
+                    default_cwd: Some(defaults.default_cwd.unwrap_or_else(|| "~".into())),
+                    default_prog: defaults.default_prog,
+                    ..Default::default()
+                }
+            })
+            .collect()
     }
 }
 
@@ -209,3 +251,41 @@ fn test_parse_wsl_distro_list() {
         ]
     );
 }
+
+#[cfg(test)]
+#[test]
+fn test_domains_for_distros() {
+    let distros = parse_wsl_distro_list(
+        "  NAME                   STATE           VERSION
+* Arch                   Running         2
+  docker-desktop-data    Stopped         2
+  docker-desktop         Stopped         2
+  Ubuntu                 Stopped         2",
+    );
+
+    let mut distro_defaults = HashMap::new();
+    distro_defaults.insert(
+        "Ubuntu".to_string(),
+        WslDistroDefaults {
+            username: Some("dev".to_string()),
+            default_cwd: Some("/work".into()),
+            default_prog: None,
+        },
+    );
+
+    let domains = WslDomain::domains_for_distros(&distros, &distro_defaults);
+    assert_eq!(
+        domains
+            .iter()
+            .map(|d| (
+                d.name.as_str(),
+                d.username.as_deref(),
+                d.default_cwd.clone()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("WSL:Arch", None, Some(PathBuf::from("~"))),
+            ("WSL:Ubuntu", Some("dev"), Some(PathBuf::from("/work"))),
+        ]
+    );
+}
@@ -12,8 +12,9 @@
 #![allow(clippy::range_plus_one)]
 
 use anyhow::{bail, Context as _, Error};
-use config::keyassignment::{PaneDirection, ScrollbackEraseMode};
+use config::keyassignment::{PaneDirection, ScrollbackEraseMode, ScrollbackExportFormat};
 use mux::client::{ClientId, ClientInfo};
+use mux::domain::DomainId;
 use mux::pane::PaneId;
 use mux::renderable::{RenderableDimensions, StableCursorPosition};
 use mux::tab::{PaneNode, SerdeUrl, SplitRequest, TabId};
@@ -29,6 +30,7 @@ use std::io::Cursor;
 use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
+use termwiz::cell::SemanticType;
 use termwiz::hyperlink::Hyperlink;
 use termwiz::image::{ImageData, TextureCoordinate};
 use termwiz::surface::{Line, SequenceNo};
@@ -441,7 +443,7 @@ macro_rules! pdu {
 /// The overall version of the codec.
 /// This must be bumped when backwards incompatible changes
 /// are made to the types and protocol.
-pub const CODEC_VERSION: usize = 45;
+pub const CODEC_VERSION: usize = 56;
 
 // Defines the Pdu enum.
 // Each struct has an explicit identifying number.
@@ -502,6 +504,22 @@ pdu! {
     GetPaneDirection: 60,
     GetPaneDirectionResponse: 61,
     AdjustPaneSize: 62,
+    GetPaneSemanticText: 63,
+    GetPaneSemanticTextResponse: 64,
+    GetPaneRegionText: 65,
+    GetPaneRegionTextResponse: 66,
+    WaitForPaneText: 67,
+    WaitForPaneTextResponse: 68,
+    SyncClipboard: 69,
+    ActivateConfigProfile: 70,
+    ExportScrollback: 71,
+    ExportScrollbackResponse: 72,
+    SetPaneRecording: 73,
+    SetPaneRecordingResponse: 74,
+    SetPaneSize: 75,
+    ApplyLayout: 76,
+    ListDomains: 77,
+    ListDomainsResponse: 78,
 }
 
 impl Pdu {
@@ -849,6 +867,24 @@ pub struct GetClientListResponse {
     pub clients: Vec<ClientInfo>,
 }
 
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ListDomains;
+
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct DomainInfo {
+    pub domain_id: DomainId,
+    pub name: String,
+    pub attached: bool,
+    pub detachable: bool,
+    /// True for the domain that new tabs are spawned into by default
+    pub is_default: bool,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ListDomainsResponse {
+    pub domains: Vec<DomainInfo>,
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct Resize {
     pub containing_tab_id: TabId,
@@ -876,6 +912,108 @@ pub struct AdjustPaneSize {
     pub amount: usize,
 }
 
+/// Resizes the splits around a pane so that it has an exact size.
+/// A dimension that is None is left unchanged.
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct SetPaneSize {
+    pub pane_id: PaneId,
+    pub cols: Option<usize>,
+    pub rows: Option<usize>,
+}
+
+/// Spawns a layout preset from the `layouts` config of the server
+/// in a new tab.  Responds with a SpawnResponse for the active pane
+/// of the layout.
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ApplyLayout {
+    pub name: String,
+    /// The window to add the tab to; a new window is created if None
+    pub window_id: Option<WindowId>,
+    pub size: TerminalSize,
+    /// The workspace of the new window, if one is created
+    pub workspace: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct GetPaneSemanticText {
+    pub pane_id: PaneId,
+    pub semantic_type: Option<SemanticType>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct GetPaneSemanticTextResponse {
+    pub zones: Vec<mux::pane_text::SemanticText>,
+}
+
+/// Requests the text in a rectangle of cells, relative to the
+/// top left of the viewport
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct GetPaneRegionText {
+    pub pane_id: PaneId,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct GetPaneRegionTextResponse {
+    pub text: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct WaitForPaneText {
+    pub pane_id: PaneId,
+    pub regex: String,
+    pub timeout_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct WaitForPaneTextResponse {
+    /// The matched text, or None if the timeout elapsed first
+    pub matched: Option<String>,
+}
+
+/// Text copied in one attached client.  Sent by a client to the
+/// server, which relays it to the other attached clients.
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct SyncClipboard {
+    pub clipboard: String,
+    pub selection: ClipboardSelection,
+}
+
+/// Activates an entry of the `profiles` config table in the server
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ActivateConfigProfile {
+    pub name: String,
+}
+
+/// Requests the scrollback and viewport of a pane, rendered in `format`
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ExportScrollback {
+    pub pane_id: PaneId,
+    pub format: ScrollbackExportFormat,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ExportScrollbackResponse {
+    pub text: String,
+}
+
+/// Starts or stops recording the output of a pane to an asciinema
+/// `.cast` file on the server
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct SetPaneRecording {
+    pub pane_id: PaneId,
+    pub recording: bool,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct SetPaneRecordingResponse {
+    /// The file the pane is, or was, being recorded to
+    pub path: Option<PathBuf>,
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct GetPaneDirectionResponse {
     pub pane_id: Option<PaneId>,
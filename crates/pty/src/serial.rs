@@ -12,16 +12,46 @@ use crate::{
 use anyhow::{ensure, Context};
 use filedescriptor::FileDescriptor;
 use serial2::{CharSize, FlowControl, Parity, SerialPort, StopBits};
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::ffi::{OsStr, OsString};
 use std::io::{Read, Result as IoResult, Write};
 #[cfg(unix)]
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 type Handle = Arc<SerialPort>;
 
+/// What is sent to the port in place of each carriage return written
+/// to the pty, which is what the Enter key produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Cr,
+    Lf,
+    CrLf,
+}
+
+fn translate_line_endings(buf: &[u8], line_ending: LineEnding) -> Cow<'_, [u8]> {
+    let replacement: &[u8] = match line_ending {
+        LineEnding::Cr => return Cow::Borrowed(buf),
+        LineEnding::Lf => b"\n",
+        LineEnding::CrLf => b"\r\n",
+    };
+    if !buf.contains(&b'\r') {
+        return Cow::Borrowed(buf);
+    }
+    let mut translated = Vec::with_capacity(buf.len() * 2);
+    for &b in buf {
+        if b == b'\r' {
+            translated.extend_from_slice(replacement);
+        } else {
+            translated.push(b);
+        }
+    }
+    Cow::Owned(translated)
+}
+
 pub struct SerialTty {
     port: OsString,
     baud: u32,
@@ -29,6 +59,7 @@ pub struct SerialTty {
     parity: Parity,
     stop_bits: StopBits,
     flow_control: FlowControl,
+    line_ending: Arc<Mutex<LineEnding>>,
 }
 
 impl SerialTty {
@@ -40,6 +71,7 @@ impl SerialTty {
             parity: Parity::None,
             stop_bits: StopBits::One,
             flow_control: FlowControl::XonXoff,
+            line_ending: Arc::new(Mutex::new(LineEnding::Cr)),
         }
     }
 
@@ -62,6 +94,12 @@ impl SerialTty {
     pub fn set_flow_control(&mut self, flow_control: FlowControl) {
         self.flow_control = flow_control;
     }
+
+    /// Changes the line ending translation, including for ports that
+    /// are already open
+    pub fn set_line_ending(&self, line_ending: LineEnding) {
+        *self.line_ending.lock().unwrap() = line_ending;
+    }
 }
 
 impl PtySystem for SerialTty {
@@ -96,6 +134,7 @@ impl PtySystem for SerialTty {
             master: Box::new(Master {
                 port,
                 took_writer: RefCell::new(false),
+                line_ending: Arc::clone(&self.line_ending),
             }),
         })
     }
@@ -192,15 +231,26 @@ impl ChildKiller for SerialChildKiller {
 struct Master {
     port: Handle,
     took_writer: RefCell<bool>,
+    line_ending: Arc<Mutex<LineEnding>>,
 }
 
 struct MasterWriter {
     port: Handle,
+    line_ending: Arc<Mutex<LineEnding>>,
 }
 
 impl Write for MasterWriter {
     fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
-        self.port.write(buf)
+        let line_ending = *self.line_ending.lock().unwrap();
+        match translate_line_endings(buf, line_ending) {
+            Cow::Borrowed(buf) => self.port.write(buf),
+            Cow::Owned(translated) => {
+                // Report the length of the untranslated data, as that
+                // is what the caller asked us to write
+                self.port.write_all(&translated)?;
+                Ok(buf.len())
+            }
+        }
     }
 
     fn flush(&mut self) -> Result<(), std::io::Error> {
@@ -233,7 +283,8 @@ impl MasterPty for Master {
         }
         *self.took_writer.borrow_mut() = true;
         let port = Arc::clone(&self.port);
-        Ok(Box::new(MasterWriter { port }))
+        let line_ending = Arc::clone(&self.line_ending);
+        Ok(Box::new(MasterWriter { port, line_ending }))
     }
 
     #[cfg(unix)]
@@ -303,3 +354,18 @@ impl Read for Reader {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_endings() {
+        assert_eq!(&*translate_line_endings(b"ls\r", LineEnding::Cr), b"ls\r");
+        assert_eq!(&*translate_line_endings(b"ls\r", LineEnding::Lf), b"ls\n");
+        assert_eq!(
+            &*translate_line_endings(b"a\rb\r", LineEnding::CrLf),
+            b"a\r\nb\r\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Keeps long-lived TLS client domains healthy: the certificate files
+//! are polled so that the domain can reconnect once they have been
+//! rotated, and the client certificate is checked for impending expiry.
+use crate::client::ReaderMessage;
+use config::TlsDomainClient;
+use mux::{Mux, MuxNotification};
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::x509::X509;
+use smol::channel::Sender;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+lazy_static::lazy_static! {
+    static ref EXPIRY_WARNINGS: Mutex<ExpiryWarnings> = Mutex::new(ExpiryWarnings::default());
+}
+
+/// Remembers when each certificate was last warned about, so that a
+/// domain that keeps reconnecting doesn't post a toast each time
+#[derive(Default)]
+struct ExpiryWarnings {
+    /// Keyed by the fingerprint of the certificate, so that a
+    /// renewed certificate in the same file is warned about afresh
+    last_warned: HashMap<Vec<u8>, Instant>,
+}
+
+impl ExpiryWarnings {
+    /// Returns true if the certificate with `fingerprint` hasn't
+    /// been warned about in the last EXPIRY_CHECK_INTERVAL, and
+    /// records that it is being warned about at `now`
+    fn should_warn(&mut self, fingerprint: &[u8], now: Instant) -> bool {
+        match self.last_warned.get(fingerprint) {
+            Some(last) if now.saturating_duration_since(*last) < EXPIRY_CHECK_INTERVAL => false,
+            _ => {
+                self.last_warned.insert(fingerprint.to_vec(), now);
+                true
+            }
+        }
+    }
+}
+
+/// Returns the number of whole days until `cert` expires,
+/// which is negative if it already has
+fn days_until_expiry(cert: &X509) -> anyhow::Result<i64> {
+    let now = Asn1Time::days_from_now(0)?;
+    Ok(now.diff(cert.not_after())?.days as i64)
+}
+
+/// Notifies the mux if the certificate in `cert_path` expires within
+/// the `cert_expiry_warning_days` of `tls_client`.  Each certificate
+/// is warned about at most once a day.
+pub(crate) fn check_expiry(tls_client: &TlsDomainClient, cert_path: &Path) {
+    if tls_client.cert_expiry_warning_days == 0 {
+        return;
+    }
+    let check = || -> anyhow::Result<Option<i64>> {
+        let cert = X509::from_pem(&std::fs::read(cert_path)?)?;
+        let days = days_until_expiry(&cert)?;
+        if days >= tls_client.cert_expiry_warning_days as i64 {
+            return Ok(None);
+        }
+        let fingerprint = cert.digest(MessageDigest::sha256())?;
+        let should_warn = EXPIRY_WARNINGS
+            .lock()
+            .unwrap()
+            .should_warn(&fingerprint, Instant::now());
+        Ok(if should_warn { Some(days) } else { None })
+    };
+    match check() {
+        Ok(Some(days)) => {
+            Mux::notify_from_any_thread(MuxNotification::TlsCertificateExpiring {
+                domain_name: tls_client.name.clone(),
+                days_remaining: days,
+            });
+        }
+        Ok(None) => {}
+        Err(err) => log::warn!("checking expiry of {}: {:#}", cert_path.display(), err),
+    }
+}
+
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .collect()
+}
+
+/// Spawns a thread that watches the certificate files of `tls_client`
+/// on behalf of the client whose reader thread receives from `sender`.
+/// The reader is told once the files have changed and then stayed the
+/// same for a poll interval, so that a certificate and key that are
+/// replaced one after the other cause a single reconnect.
+/// The thread ends along with the client.
+pub(crate) fn spawn_watcher(
+    tls_client: TlsDomainClient,
+    cert_path: PathBuf,
+    sender: Sender<ReaderMessage>,
+) {
+    let files = if tls_client.reconnect_on_cert_change {
+        tls_client.cert_files()
+    } else {
+        vec![]
+    };
+    if files.is_empty() && tls_client.cert_expiry_warning_days == 0 {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let mut last_modified = modified_times(&files);
+        let mut changed = false;
+        let mut last_expiry_check = Instant::now();
+
+        while !sender.is_closed() {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let modified = modified_times(&files);
+            if modified != last_modified {
+                last_modified = modified;
+                changed = true;
+            } else if changed {
+                changed = false;
+                log::info!("certificates for {} changed; reconnecting", tls_client.name);
+                if sender.try_send(ReaderMessage::CertificatesChanged).is_err() {
+                    break;
+                }
+            }
+
+            if last_expiry_check.elapsed() >= EXPIRY_CHECK_INTERVAL {
+                last_expiry_check = Instant::now();
+                check_expiry(&tls_client, &cert_path);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509Builder;
+
+    fn certificate(days_valid: u32) -> X509 {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(days_valid).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn days_until_expiry_of_generated_certificate() {
+        // A second may tick over between building the certificate
+        // and checking it, which takes the count below a whole day
+        let days = days_until_expiry(&certificate(10)).unwrap();
+        assert!((9..=10).contains(&days), "{days}");
+        let days = days_until_expiry(&certificate(0)).unwrap();
+        assert!((-1..=0).contains(&days), "{days}");
+    }
+
+    #[test]
+    fn warn_once_a_day() {
+        let mut warnings = ExpiryWarnings::default();
+        let now = Instant::now();
+        assert!(warnings.should_warn(b"cert", now));
+        assert!(!warnings.should_warn(b"cert", now + Duration::from_secs(60)));
+        // A renewed certificate is warned about straight away
+        assert!(warnings.should_warn(b"renewed", now + Duration::from_secs(60)));
+        assert!(warnings.should_warn(b"cert", now + EXPIRY_CHECK_INTERVAL));
+    }
+}
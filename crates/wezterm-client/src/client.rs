@@ -12,8 +12,8 @@ use mux::connui::ConnectionUI;
 use mux::domain::DomainId;
 use mux::pane::PaneId;
 use mux::ssh::ssh_connect_with_ui;
-use mux::Mux;
-use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
+use mux::{Mux, MuxNotification};
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod, SslVerifyMode};
 use openssl::x509::X509;
 use portable_pty::Child;
 use smol::channel::{bounded, unbounded, Receiver, Sender};
@@ -43,12 +43,14 @@ struct Timeout;
 #[error("ChannelSendError")]
 struct ChannelSendError;
 
-enum ReaderMessage {
+pub(crate) enum ReaderMessage {
     SendPdu {
         pdu: Pdu,
         promise: Sender<anyhow::Result<Pdu>>,
     },
     Readable,
+    /// The TLS certificate files were replaced
+    CertificatesChanged,
 }
 
 #[derive(Clone)]
@@ -209,6 +211,35 @@ fn process_unilateral(
         }
     };
     match &decoded.pdu {
+        Pdu::SyncClipboard(SyncClipboard {
+            clipboard,
+            selection,
+        }) => {
+            let config = configuration();
+            let sync = &config.clipboard_sync;
+            if !sync.receive {
+                log::trace!("ignoring SyncClipboard; clipboard_sync.receive is disabled");
+            } else if !sync.receives(clipboard.len()) {
+                log::warn!(
+                    "ignoring SyncClipboard of {} bytes; it exceeds clipboard_sync.max_bytes",
+                    clipboard.len()
+                );
+            } else {
+                let clipboard = clipboard.to_string();
+                let selection = *selection;
+                promise::spawn::spawn_into_main_thread(async move {
+                    let mux = Mux::try_get().ok_or_else(|| anyhow!("no more mux"))?;
+                    mux.notify(MuxNotification::ClipboardSynced {
+                        origin: None,
+                        selection,
+                        clipboard,
+                    });
+                    anyhow::Result::<()>::Ok(())
+                })
+                .detach();
+            }
+            return Ok(());
+        }
         Pdu::WindowWorkspaceChanged(WindowWorkspaceChanged {
             window_id,
             workspace,
@@ -337,6 +368,11 @@ enum NotReconnectableError {
     ClientWasDestroyed,
 }
 
+/// Ends the client thread so that it reconnects using the new certificates
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("TLS certificate files changed")]
+struct CertificatesChangedError;
+
 fn client_thread(
     reconnectable: &mut Reconnectable,
     local_domain_id: Option<DomainId>,
@@ -393,6 +429,9 @@ async fn client_thread_async(
                     .context("encoding a PDU to send to the server")?;
                 stream.flush().await.context("flushing PDU to server")?;
             }
+            Ok(ReaderMessage::CertificatesChanged) => {
+                return Err(CertificatesChangedError.into());
+            }
             Ok(ReaderMessage::Readable) => {
                 match Pdu::decode_async(&mut stream, Some(next_serial)).await {
                     Ok(decoded) => {
@@ -681,8 +720,9 @@ impl Reconnectable {
         ui: &mut ConnectionUI,
     ) -> anyhow::Result<()> {
         let ssh_config = mux::ssh::ssh_domain_to_ssh_config(&ssh_dom)?;
+        let problems = mux::ssh::ssh_domain_problems(&ssh_dom, &ssh_config);
 
-        let sess = ssh_connect_with_ui(ssh_config, ui)?;
+        let sess = ssh_connect_with_ui(ssh_config, &problems, ui)?;
         let proxy_bin = Self::wezterm_bin_path(&ssh_dom.remote_wezterm_path);
 
         let cmd = if let Some(cmd) = ssh_dom.override_proxy_command.clone() {
@@ -831,6 +871,7 @@ impl Reconnectable {
             match self.try_connect(&tls_client, ui, &remote_address, remote_host_name) {
                 Ok(stream) => {
                     self.stream.replace(stream);
+                    self.check_cert_expiry(&tls_client);
                     return Ok(());
                 }
                 Err(err) => {
@@ -877,7 +918,7 @@ impl Reconnectable {
                     ssh_config.insert("port".to_string(), port.to_string());
                 }
 
-                let sess = ssh_connect_with_ui(ssh_config, ui)?;
+                let sess = ssh_connect_with_ui(ssh_config, &[], ui)?;
 
                 let creds = ui.run_and_log_error(|| {
                     // The `tlscreds` command will start the server if needed and then
@@ -937,9 +978,23 @@ impl Reconnectable {
             || self.try_connect(&tls_client, ui, &remote_address, remote_host_name)
         })?;
         self.stream.replace(stream);
+        self.check_cert_expiry(&tls_client);
         Ok(())
     }
 
+    fn tls_client_cert_path(&self, tls_client: &TlsDomainClient) -> anyhow::Result<PathBuf> {
+        match tls_client.pem_cert.clone() {
+            Some(cert) => Ok(cert),
+            None => self.tls_creds_cert_path(),
+        }
+    }
+
+    fn check_cert_expiry(&self, tls_client: &TlsDomainClient) {
+        if let Ok(cert_path) = self.tls_client_cert_path(tls_client) {
+            crate::certwatch::check_expiry(tls_client, &cert_path);
+        }
+    }
+
     fn try_connect(
         &mut self,
         tls_client: &TlsDomainClient,
@@ -949,37 +1004,48 @@ impl Reconnectable {
     ) -> anyhow::Result<Box<dyn AsyncReadAndWrite>> {
         let mut connector = SslConnector::builder(SslMethod::tls())?;
 
-        let cert_file = match tls_client.pem_cert.clone() {
-            Some(cert) => cert,
-            None => self.tls_creds_cert_path()?,
-        };
+        let cert_file = self.tls_client_cert_path(tls_client);
 
-        connector
-            .set_certificate_file(&cert_file, SslFiletype::PEM)
-            .context(format!(
-                "set_certificate_file to {} for TLS client",
-                cert_file.display()
-            ))?;
+        // A pinned server may not want a client certificate, so only
+        // present one if we have it
+        let present_cert =
+            !tls_client.trust_on_first_use || matches!(&cert_file, Ok(path) if path.exists());
 
-        if let Some(chain_file) = tls_client.pem_ca.as_ref() {
+        if present_cert {
+            let cert_file = cert_file?;
             connector
-                .set_certificate_chain_file(&chain_file)
+                .set_certificate_file(&cert_file, SslFiletype::PEM)
                 .context(format!(
-                    "set_certificate_chain_file to {} for TLS client",
-                    chain_file.display()
+                    "set_certificate_file to {} for TLS client",
+                    cert_file.display()
+                ))?;
+
+            if let Some(chain_file) = tls_client.pem_ca.as_ref() {
+                connector
+                    .set_certificate_chain_file(&chain_file)
+                    .context(format!(
+                        "set_certificate_chain_file to {} for TLS client",
+                        chain_file.display()
+                    ))?;
+            }
+
+            let key_file = match tls_client.pem_private_key.clone() {
+                Some(key) => key,
+                None => self.tls_creds_cert_path()?,
+            };
+            connector
+                .set_private_key_file(&key_file, SslFiletype::PEM)
+                .context(format!(
+                    "set_private_key_file to {} for TLS client",
+                    key_file.display()
                 ))?;
         }
 
-        let key_file = match tls_client.pem_private_key.clone() {
-            Some(key) => key,
-            None => self.tls_creds_cert_path()?,
-        };
-        connector
-            .set_private_key_file(&key_file, SslFiletype::PEM)
-            .context(format!(
-                "set_private_key_file to {} for TLS client",
-                key_file.display()
-            ))?;
+        if tls_client.trust_on_first_use {
+            // The certificate is checked against the pinned
+            // fingerprint once the handshake is complete
+            connector.set_verify(SslVerifyMode::NONE);
+        }
 
         fn load_cert(name: &Path) -> anyhow::Result<X509> {
             let cert_bytes = std::fs::read(name)?;
@@ -1009,29 +1075,55 @@ impl Reconnectable {
             .configure()?
             .verify_hostname(!tls_client.accept_invalid_hostnames);
 
-        ui.output_str(&format!("Connecting to {} using TLS\n", remote_address));
-        let stream = TcpStream::connect(remote_address)
-            .with_context(|| format!("connecting to {}", remote_address))?;
+        let proxy = configuration().proxy_for_host(remote_host_name);
+        let stream = match proxy {
+            Some(proxy) => {
+                ui.output_str(&format!(
+                    "Connecting to {} using TLS via proxy {}\n",
+                    remote_address,
+                    proxy.address()
+                ));
+                let port = remote_address
+                    .rsplit(':')
+                    .next()
+                    .and_then(|port| port.parse::<u16>().ok())
+                    .ok_or_else(|| anyhow!("no port in {}", remote_address))?;
+                crate::proxy::connect_via_proxy(&proxy, remote_host_name, port)?
+            }
+            None => {
+                ui.output_str(&format!("Connecting to {} using TLS\n", remote_address));
+                TcpStream::connect(remote_address)
+                    .with_context(|| format!("connecting to {}", remote_address))?
+            }
+        };
         stream.set_nodelay(true)?;
         stream.set_write_timeout(Some(tls_client.write_timeout))?;
         stream.set_read_timeout(Some(tls_client.read_timeout))?;
 
-        let stream = Box::new(Async::new(AsyncSslStream::new(
-            connector
-                .connect(
-                    tls_client
-                        .expected_cn
-                        .as_deref()
-                        .unwrap_or(remote_host_name),
-                    stream,
+        let stream = connector
+            .connect(
+                tls_client
+                    .expected_cn
+                    .as_deref()
+                    .unwrap_or(remote_host_name),
+                stream,
+            )
+            .with_context(|| {
+                format!(
+                    "SslConnector for {} with host name {}",
+                    remote_address, remote_host_name,
                 )
-                .with_context(|| {
-                    format!(
-                        "SslConnector for {} with host name {}",
-                        remote_address, remote_host_name,
-                    )
-                })?,
-        ))?);
+            })?;
+
+        if tls_client.trust_on_first_use {
+            let cert = stream
+                .ssl()
+                .peer_certificate()
+                .ok_or_else(|| anyhow!("{} presented no certificate", remote_address))?;
+            crate::known_servers::verify_pinned_certificate(&tls_client.name, &cert)?;
+        }
+
+        let stream = Box::new(Async::new(AsyncSslStream::new(stream))?);
         ui.output_str("TLS Connected!\n");
         Ok(stream)
     }
@@ -1045,6 +1137,12 @@ impl Client {
         let (sender, mut receiver) = unbounded();
         let client_id = ClientId::new();
 
+        if let ClientDomainConfig::Tls(tls_client) = &client_domain_config {
+            if let Ok(cert_path) = reconnectable.tls_client_cert_path(tls_client) {
+                crate::certwatch::spawn_watcher(tls_client.clone(), cert_path, sender.clone());
+            }
+        }
+
         thread::spawn(move || {
             const BASE_INTERVAL: Duration = Duration::from_secs(1);
             const MAX_INTERVAL: Duration = Duration::from_secs(10);
@@ -1059,6 +1157,20 @@ impl Client {
 
                     let local_domain_id = local_domain_id.expect("checked above");
 
+                    if e.root_cause().is::<CertificatesChangedError>() {
+                        // Reconnect quietly; if that doesn't work out,
+                        // fall back to reconnecting with a ConnectionUI
+                        let mut ui = ConnectionUI::new_headless();
+                        if reconnectable.connect(false, &mut ui, true).is_ok() {
+                            log::info!("Reconnected with new TLS certificates");
+                            promise::spawn::spawn_into_main_thread(async move {
+                                ClientDomain::reattach(local_domain_id, ui).await.ok();
+                            })
+                            .detach();
+                            continue;
+                        }
+                    }
+
                     if let Some(ioerr) = e.root_cause().downcast_ref::<std::io::Error>() {
                         if let std::io::ErrorKind::UnexpectedEof = ioerr.kind() {
                             // Don't reconnect for a simple EOF
@@ -1389,4 +1501,30 @@ impl Client {
         GetPaneDirectionResponse
     );
     rpc!(adjust_pane_size, AdjustPaneSize, UnitResponse);
+    rpc!(set_pane_size, SetPaneSize, UnitResponse);
+    rpc!(apply_layout, ApplyLayout, SpawnResponse);
+    rpc!(
+        get_pane_semantic_text,
+        GetPaneSemanticText,
+        GetPaneSemanticTextResponse
+    );
+    rpc!(
+        get_pane_region_text,
+        GetPaneRegionText,
+        GetPaneRegionTextResponse
+    );
+    rpc!(wait_for_pane_text, WaitForPaneText, WaitForPaneTextResponse);
+    rpc!(sync_clipboard, SyncClipboard, UnitResponse);
+    rpc!(activate_config_profile, ActivateConfigProfile, UnitResponse);
+    rpc!(
+        export_scrollback,
+        ExportScrollback,
+        ExportScrollbackResponse
+    );
+    rpc!(
+        set_pane_recording,
+        SetPaneRecording,
+        SetPaneRecordingResponse
+    );
+    rpc!(list_domains, ListDomains = (), ListDomainsResponse);
 }
@@ -15,7 +15,7 @@ use portable_pty::CommandBuilder;
 use promise::spawn::spawn_into_new_thread;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use wezterm_term::TerminalSize;
+use wezterm_term::{ClipboardSelection, TerminalSize};
 
 pub struct ClientInner {
     pub client: Client,
@@ -481,6 +481,23 @@ impl ClientDomain {
         Ok(())
     }
 
+    /// Sends text that was copied in this client to the server,
+    /// which relays it to the other attached clients
+    pub fn sync_clipboard(&self, selection: ClipboardSelection, clipboard: String) {
+        if let Some(inner) = self.inner() {
+            promise::spawn::spawn(async move {
+                inner
+                    .client
+                    .sync_clipboard(codec::SyncClipboard {
+                        clipboard,
+                        selection,
+                    })
+                    .await
+            })
+            .detach();
+        }
+    }
+
     pub fn process_remote_window_title_change(&self, remote_window_id: WindowId, title: String) {
         if let Some(inner) = self.inner() {
             if let Some(local_window_id) = inner.remote_to_local_window(remote_window_id) {
@@ -756,6 +773,7 @@ impl Domain for ClientDomain {
         _size: TerminalSize,
         _command: Option<CommandBuilder>,
         _command_dir: Option<String>,
+        _workspace: &str,
     ) -> anyhow::Result<Arc<dyn Pane>> {
         anyhow::bail!("spawn_pane not implemented for ClientDomain")
     }
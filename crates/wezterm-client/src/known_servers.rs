@@ -0,0 +1,148 @@
+//! Pins the certificates of tls domains that use `trust_on_first_use`.
+//! The SHA-256 fingerprint of the certificate that each domain presents
+//! the first time is recorded in `DATA_DIR/known_mux_servers`, one
+//! `sha256:HEX NAME` line per domain, much like ssh's `known_hosts`.
+use anyhow::{bail, Context};
+use openssl::hash::MessageDigest;
+use openssl::x509::X509Ref;
+use std::path::{Path, PathBuf};
+
+fn known_servers_file() -> PathBuf {
+    config::DATA_DIR.join("known_mux_servers")
+}
+
+/// Formats a fingerprint the way it is stored
+pub fn format_fingerprint(digest: &[u8]) -> String {
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256:{hex}")
+}
+
+fn parse_known_servers(contents: &str) -> impl Iterator<Item = (&str, &str)> {
+    contents.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        let (fingerprint, name) = line.split_once(' ')?;
+        Some((name.trim(), fingerprint))
+    })
+}
+
+/// Returns the fingerprint pinned for the domain `name`, if any
+pub fn pinned_fingerprint(name: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(known_servers_file()).ok()?;
+    parse_known_servers(&contents)
+        .find(|(known, _)| *known == name)
+        .map(|(_, fingerprint)| fingerprint.to_string())
+}
+
+/// What to do about the certificate presented by a server
+#[derive(Debug, PartialEq, Eq)]
+enum PinCheck {
+    Trusted,
+    FirstUse,
+    Changed { pinned: String },
+}
+
+fn check_fingerprint(pinned: Option<&str>, fingerprint: &str) -> PinCheck {
+    match pinned {
+        None => PinCheck::FirstUse,
+        Some(pinned) if pinned == fingerprint => PinCheck::Trusted,
+        Some(pinned) => PinCheck::Changed {
+            pinned: pinned.to_string(),
+        },
+    }
+}
+
+/// Replaces the file at `path` with `contents` by writing a temporary
+/// file alongside it and renaming it over the original, so that a
+/// crash or a concurrent writer can't leave it truncated
+fn write_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    std::fs::write(&temp, contents).with_context(|| format!("writing {}", temp.display()))?;
+    std::fs::rename(&temp, path).with_context(|| {
+        let _ = std::fs::remove_file(&temp);
+        format!("renaming {} to {}", temp.display(), path.display())
+    })
+}
+
+/// Checks `cert`, presented by the server of the domain `name`, against
+/// the pinned fingerprint, pinning it if this is the first connection
+pub(crate) fn verify_pinned_certificate(name: &str, cert: &X509Ref) -> anyhow::Result<()> {
+    let fingerprint = format_fingerprint(&cert.digest(MessageDigest::sha256())?);
+    let path = known_servers_file();
+    match check_fingerprint(pinned_fingerprint(name).as_deref(), &fingerprint) {
+        PinCheck::Trusted => Ok(()),
+        PinCheck::FirstUse => {
+            log::info!("pinning certificate {fingerprint} for {name}");
+            config::create_user_owned_dirs(&config::DATA_DIR)?;
+            let mut contents = std::fs::read_to_string(&path).unwrap_or_default();
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            contents.push_str(&format!("{fingerprint} {name}\n"));
+            write_atomically(&path, &contents)
+        }
+        PinCheck::Changed { pinned } => bail!(
+            "The certificate presented by {name} has changed from {pinned} \
+             to {fingerprint}.  This may mean that someone is impersonating \
+             the server.  If the server was reinstalled, remove its line \
+             from {} and connect again.",
+            path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let contents = "# comment\nsha256:aa mdns:Build Box\n\nsha256:bb mdns:laptop\n";
+        assert_eq!(
+            parse_known_servers(contents).collect::<Vec<_>>(),
+            vec![
+                ("mdns:Build Box", "sha256:aa"),
+                ("mdns:laptop", "sha256:bb")
+            ]
+        );
+    }
+
+    #[test]
+    fn fingerprint() {
+        assert_eq!(format_fingerprint(&[0x0a, 0xff]), "sha256:0aff");
+    }
+
+    #[test]
+    fn atomic_write() {
+        let dir = std::env::temp_dir().join(format!("kaku-known-servers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("known_mux_servers");
+        write_atomically(&path, "sha256:aa one\n").unwrap();
+        write_atomically(&path, "sha256:aa one\nsha256:bb two\n").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "sha256:aa one\nsha256:bb two\n"
+        );
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(check_fingerprint(None, "sha256:aa"), PinCheck::FirstUse);
+        assert_eq!(
+            check_fingerprint(Some("sha256:aa"), "sha256:aa"),
+            PinCheck::Trusted
+        );
+        assert_eq!(
+            check_fingerprint(Some("sha256:aa"), "sha256:bb"),
+            PinCheck::Changed {
+                pinned: "sha256:aa".to_string()
+            }
+        );
+    }
+}
@@ -1,4 +1,8 @@
+mod certwatch;
 pub mod client;
 pub mod discovery;
 pub mod domain;
+pub mod known_servers;
+pub mod mdns;
 pub mod pane;
+mod proxy;
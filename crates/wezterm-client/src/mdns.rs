@@ -0,0 +1,436 @@
+//! Finds mux servers on the local network that advertise the
+//! `_kaku-mux._tcp` DNS-SD service via multicast DNS.
+//! We send a one-shot query (RFC 6762 section 5.1) from an ephemeral
+//! port, so responders answer us directly and we don't need to share
+//! port 5353 with the system mDNS responder.
+//! Advertising is not implemented here; the servers are expected to be
+//! published by the system responder (avahi, or dns-sd on macOS).
+use anyhow::{anyhow, bail, Context};
+use config::TlsDomainClient;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// The DNS-SD service type that mux servers advertise
+pub const SERVICE_TYPE: &str = "_kaku-mux._tcp.local";
+
+/// Domains created for discovered servers are named with this
+/// prefix followed by the instance name of the server
+pub const DOMAIN_PREFIX: &str = "mdns:";
+
+const MDNS_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+/// Set in the class of a question to ask for a unicast response
+const CLASS_UNICAST_RESPONSE: u16 = 0x8000;
+
+/// A mux server that answered our query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredServer {
+    /// The instance name that the server advertises
+    pub name: String,
+    /// The host name from the SRV record, without the trailing dot
+    pub host: String,
+    pub port: u16,
+    pub addresses: Vec<IpAddr>,
+    /// The key=value pairs from the TXT record
+    pub properties: BTreeMap<String, String>,
+}
+
+impl DiscoveredServer {
+    pub fn domain_name(&self) -> String {
+        format!("{DOMAIN_PREFIX}{}", self.name)
+    }
+
+    /// Returns the host:port to connect to, preferring an IPv4
+    /// address over the host name, which may not resolve outside
+    /// of mDNS
+    pub fn remote_address(&self) -> String {
+        match self.addresses.iter().find(|addr| addr.is_ipv4()) {
+            Some(addr) => format!("{addr}:{}", self.port),
+            None => format!("{}:{}", self.host, self.port),
+        }
+    }
+
+    /// Returns the configuration of the tls domain that connects
+    /// to this server, pinning its certificate on first use
+    pub fn tls_client(&self) -> TlsDomainClient {
+        TlsDomainClient {
+            name: self.domain_name(),
+            remote_address: self.remote_address(),
+            trust_on_first_use: true,
+            accept_invalid_hostnames: true,
+            read_timeout: config::default_read_timeout(),
+            write_timeout: config::default_write_timeout(),
+            local_echo_threshold_ms: config::default_local_echo_threshold_ms(),
+            ..TlsDomainClient::default()
+        }
+    }
+}
+
+/// Queries the local network for mux servers, collecting answers
+/// until `timeout` has elapsed
+pub fn browse(timeout: Duration) -> anyhow::Result<Vec<DiscoveredServer>> {
+    let socket =
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).context("binding socket for mDNS query")?;
+    socket.set_multicast_ttl_v4(255)?;
+    socket
+        .send_to(&build_query(SERVICE_TYPE), MDNS_ADDR)
+        .context("sending mDNS query")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut records = Records::default();
+    let mut buf = [0u8; 9000];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                if let Err(err) = records.add_packet(&buf[..len]) {
+                    log::debug!("ignoring mDNS response from {from}: {err:#}");
+                }
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                break
+            }
+            Err(err) => return Err(err).context("receiving mDNS responses"),
+        }
+    }
+    Ok(records.servers(SERVICE_TYPE))
+}
+
+fn push_name(packet: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+}
+
+/// Builds a query for the PTR records of `service`
+fn build_query(service: &str) -> Vec<u8> {
+    let mut packet = vec![];
+    // id, flags
+    packet.extend_from_slice(&[0, 0, 0, 0]);
+    // one question, no answer, authority or additional records
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    push_name(&mut packet, service);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&(CLASS_IN | CLASS_UNICAST_RESPONSE).to_be_bytes());
+    packet
+}
+
+/// A domain name as its list of labels.  Instance names may contain
+/// dots, so they can't be joined up and split again.
+type Name = Vec<String>;
+
+fn name_key(name: &[String]) -> String {
+    name.iter()
+        .map(|label| label.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+struct Reader<'a> {
+    packet: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self
+            .packet
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| anyhow!("truncated packet"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn name(&mut self) -> anyhow::Result<Name> {
+        let (name, end) = read_name(self.packet, self.pos)?;
+        self.pos = end;
+        Ok(name)
+    }
+}
+
+/// Reads the possibly compressed name at `pos` in `packet`, returning
+/// it along with the position just past it
+fn read_name(packet: &[u8], mut pos: usize) -> anyhow::Result<(Name, usize)> {
+    let mut labels = vec![];
+    let mut end = None;
+    // Each pointer must go backwards, so this bounds the number of jumps
+    let mut limit = pos;
+    loop {
+        let len = *packet.get(pos).ok_or_else(|| anyhow!("truncated name"))? as usize;
+        if len == 0 {
+            return Ok((labels, end.unwrap_or(pos + 1)));
+        }
+        if len & 0xc0 == 0xc0 {
+            let low = *packet
+                .get(pos + 1)
+                .ok_or_else(|| anyhow!("truncated name"))? as usize;
+            let target = ((len & 0x3f) << 8) | low;
+            if target >= limit {
+                bail!("name compression pointer does not point backwards");
+            }
+            end.get_or_insert(pos + 2);
+            limit = target;
+            pos = target;
+            continue;
+        }
+        let label = packet
+            .get(pos + 1..pos + 1 + len)
+            .ok_or_else(|| anyhow!("truncated label"))?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        pos += 1 + len;
+    }
+}
+
+#[derive(Default)]
+struct Records {
+    /// service -> instances, keyed by name_key
+    ptr: BTreeMap<String, BTreeSet<Name>>,
+    /// instance -> (target, port)
+    srv: BTreeMap<String, (Name, u16)>,
+    txt: BTreeMap<String, BTreeMap<String, String>>,
+    addresses: BTreeMap<String, BTreeSet<IpAddr>>,
+}
+
+impl Records {
+    /// Records the answers and additional records of a response
+    fn add_packet(&mut self, packet: &[u8]) -> anyhow::Result<()> {
+        let mut reader = Reader { packet, pos: 0 };
+        let _id = reader.u16()?;
+        let flags = reader.u16()?;
+        if flags & 0x8000 == 0 {
+            // A query from someone else
+            return Ok(());
+        }
+        let questions = reader.u16()?;
+        let records = reader.u16()? as usize + reader.u16()? as usize + reader.u16()? as usize;
+
+        for _ in 0..questions {
+            reader.name()?;
+            reader.bytes(4)?;
+        }
+
+        for _ in 0..records {
+            let owner = reader.name()?;
+            let rtype = reader.u16()?;
+            let _class = reader.u16()?;
+            let _ttl = reader.bytes(4)?;
+            let len = reader.u16()? as usize;
+            let start = reader.pos;
+            let rdata = reader.bytes(len)?;
+            let key = name_key(&owner);
+
+            match rtype {
+                TYPE_PTR => {
+                    let (instance, _) = read_name(packet, start)?;
+                    self.ptr.entry(key).or_default().insert(instance);
+                }
+                TYPE_SRV => {
+                    if rdata.len() < 7 {
+                        bail!("short SRV record");
+                    }
+                    let port = u16::from_be_bytes([rdata[4], rdata[5]]);
+                    let (target, _) = read_name(packet, start + 6)?;
+                    self.srv.insert(key, (target, port));
+                }
+                TYPE_TXT => {
+                    let mut properties = BTreeMap::new();
+                    let mut txt = rdata;
+                    while let Some((&len, rest)) = txt.split_first() {
+                        let len = (len as usize).min(rest.len());
+                        let entry = String::from_utf8_lossy(&rest[..len]);
+                        match entry.split_once('=') {
+                            Some((k, v)) => properties.insert(k.to_string(), v.to_string()),
+                            None if !entry.is_empty() => {
+                                properties.insert(entry.to_string(), String::new())
+                            }
+                            None => None,
+                        };
+                        txt = &rest[len..];
+                    }
+                    self.txt.insert(key, properties);
+                }
+                TYPE_A if rdata.len() == 4 => {
+                    let addr = Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]);
+                    self.addresses.entry(key).or_default().insert(addr.into());
+                }
+                TYPE_AAAA if rdata.len() == 16 => {
+                    let mut octets = [0u8; 16];
+                    octets.copy_from_slice(rdata);
+                    self.addresses
+                        .entry(key)
+                        .or_default()
+                        .insert(Ipv6Addr::from(octets).into());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the instances of `service` whose SRV record we have,
+    /// ordered by name
+    fn servers(&self, service: &str) -> Vec<DiscoveredServer> {
+        let service_key = service.to_ascii_lowercase();
+        let mut servers = vec![];
+        for instance in self.ptr.get(&service_key).into_iter().flatten() {
+            let key = name_key(instance);
+            let Some((target, port)) = self.srv.get(&key) else {
+                continue;
+            };
+            let name = match instance.first() {
+                Some(name) => name.clone(),
+                None => continue,
+            };
+            let target_key = name_key(target);
+            servers.push(DiscoveredServer {
+                name,
+                host: target.join("."),
+                port: *port,
+                addresses: self
+                    .addresses
+                    .get(&target_key)
+                    .map(|addrs| addrs.iter().copied().collect())
+                    .unwrap_or_default(),
+                properties: self.txt.get(&key).cloned().unwrap_or_default(),
+            });
+        }
+        servers.sort_by(|a, b| a.name.cmp(&b.name));
+        servers
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn push_record(packet: &mut Vec<u8>, owner: &[u8], rtype: u16, rdata: &[u8]) {
+        packet.extend_from_slice(owner);
+        packet.extend_from_slice(&rtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&120u32.to_be_bytes());
+        packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        packet.extend_from_slice(rdata);
+    }
+
+    /// A response to our query, using compression for the instance
+    /// name that follows the question like a typical responder
+    fn response() -> Vec<u8> {
+        let mut packet = vec![0, 0, 0x84, 0, 0, 1, 0, 1, 0, 0, 0, 3];
+        let question = packet.len() as u8;
+        push_name(&mut packet, SERVICE_TYPE);
+        packet.extend_from_slice(&[0, 12, 0, 1]);
+
+        // "Build Box" then a pointer to the service type
+        let mut instance = vec![9];
+        instance.extend_from_slice(b"Build Box");
+        instance.extend_from_slice(&[0xc0, question]);
+        let instance_pos = packet.len() + 2 + 2 + 2 + 4 + 2;
+        push_record(&mut packet, &[0xc0, question], TYPE_PTR, &instance);
+        let instance_ptr = [0xc0, instance_pos as u8];
+
+        let mut srv = vec![0, 0, 0, 0, 0x0f, 0xa0];
+        push_name(&mut srv, "buildbox.local");
+        push_record(&mut packet, &instance_ptr, TYPE_SRV, &srv);
+
+        push_record(
+            &mut packet,
+            &instance_ptr,
+            TYPE_TXT,
+            b"\x0bversion=0.3\x04beta",
+        );
+
+        let mut host = vec![];
+        push_name(&mut host, "buildbox.local");
+        push_record(&mut packet, &host, TYPE_A, &[192, 168, 1, 20]);
+        packet
+    }
+
+    #[test]
+    fn query() {
+        let query = build_query("_a._tcp.local");
+        assert_eq!(
+            query,
+            b"\0\0\0\0\0\x01\0\0\0\0\0\0\x02_a\x04_tcp\x05local\0\0\x0c\x80\x01"
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        let mut records = Records::default();
+        records.add_packet(&response()).unwrap();
+        assert_eq!(
+            records.servers(SERVICE_TYPE),
+            vec![DiscoveredServer {
+                name: "Build Box".to_string(),
+                host: "buildbox.local".to_string(),
+                port: 4000,
+                addresses: vec![Ipv4Addr::new(192, 168, 1, 20).into()],
+                properties: BTreeMap::from([
+                    ("version".to_string(), "0.3".to_string()),
+                    ("beta".to_string(), String::new()),
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn instance_without_srv_is_skipped() {
+        let mut packet = response();
+        // Only keep the header, question and PTR record
+        packet[11] = 0;
+        let mut records = Records::default();
+        records.add_packet(&packet).unwrap();
+        assert_eq!(records.servers(SERVICE_TYPE), vec![]);
+    }
+
+    #[test]
+    fn reject_forward_pointer() {
+        assert!(read_name(&[0xc0, 0x04, 0, 0, 0], 0).is_err());
+        // A pointer to itself would otherwise loop forever
+        assert!(read_name(&[0, 0xc0, 0x01], 1).is_err());
+    }
+
+    #[test]
+    fn truncated_response() {
+        let packet = response();
+        let mut records = Records::default();
+        assert!(records.add_packet(&packet[..packet.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn remote_address_prefers_ipv4() {
+        let mut server = DiscoveredServer {
+            name: "box".to_string(),
+            host: "box.local".to_string(),
+            port: 4000,
+            addresses: vec![Ipv6Addr::LOCALHOST.into()],
+            properties: BTreeMap::new(),
+        };
+        assert_eq!(server.remote_address(), "box.local:4000");
+        server.addresses.push(Ipv4Addr::new(10, 0, 0, 2).into());
+        assert_eq!(server.remote_address(), "10.0.0.2:4000");
+    }
+}
@@ -5,7 +5,7 @@ use anyhow::bail;
 use async_trait::async_trait;
 use codec::*;
 use config::configuration;
-use config::keyassignment::ScrollbackEraseMode;
+use config::keyassignment::{ScrollbackEraseMode, ScrollbackExportFormat};
 use mux::domain::DomainId;
 use mux::pane::{
     alloc_pane_id, CachePolicy, CloseReason, ForEachPaneLogicalLine, LogicalLine, Pane, PaneId,
@@ -21,6 +21,7 @@ use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
 use std::sync::Arc;
+use termwiz::hyperlink::Rule;
 use termwiz::input::KeyEvent;
 use termwiz::surface::SequenceNo;
 use url::Url;
@@ -303,6 +304,12 @@ impl Pane for ClientPane {
         mux::pane::impl_get_logical_lines_via_get_lines(self, lines)
     }
 
+    fn apply_hyperlinks(&self, lines: Range<StableRowIndex>, rules: &[Rule]) {
+        // The default implementation operates on copies of the lines,
+        // so apply the rules to our cache instead
+        self.renderable.lock().apply_hyperlinks(lines, rules);
+    }
+
     fn get_current_seqno(&self) -> SequenceNo {
         self.renderable.lock().get_current_seqno()
     }
@@ -447,6 +454,20 @@ impl Pane for ClientPane {
         }
     }
 
+    /// Only the lines that have been displayed are cached locally,
+    /// so the server renders the export from the whole scrollback
+    async fn export_scrollback(&self, format: ScrollbackExportFormat) -> anyhow::Result<String> {
+        let response = self
+            .client
+            .client
+            .export_scrollback(ExportScrollback {
+                pane_id: self.remote_pane_id,
+                format,
+            })
+            .await?;
+        Ok(response.text)
+    }
+
     fn key_down(&self, key: KeyCode, mods: KeyModifiers) -> anyhow::Result<()> {
         let input_serial;
         {
@@ -2,7 +2,7 @@ use crate::domain::ClientInner;
 use crate::pane::clientpane::ClientPane;
 use anyhow::anyhow;
 use codec::*;
-use config::{configuration, ConfigHandle};
+use config::configuration;
 use lru::LruCache;
 use mux::pane::PaneId;
 use mux::renderable::{RenderableDimensions, StableCursorPosition};
@@ -19,6 +19,7 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use termwiz::cell::{Cell, CellAttributes, Underline};
 use termwiz::color::AnsiColor;
+use termwiz::hyperlink::Rule;
 use termwiz::image::{ImageCell, ImageData};
 use termwiz::surface::{SequenceNo, SEQ_ZERO};
 use url::Url;
@@ -51,8 +52,26 @@ impl LineEntry {
             Self::Stale(_) => ("Stale", None),
         }
     }
+
+    fn line(&self) -> Option<&Line> {
+        match self {
+            Self::Line(line) | Self::LineAndFetching(line, _) | Self::Stale(line) => Some(line),
+            Self::Fetching(_) => None,
+        }
+    }
+
+    fn line_mut(&mut self) -> Option<&mut Line> {
+        match self {
+            Self::Line(line) | Self::LineAndFetching(line, _) | Self::Stale(line) => Some(line),
+            Self::Fetching(_) => None,
+        }
+    }
 }
 
+/// Mirrors the limit used when assembling logical lines elsewhere, so
+/// that a really long wrapped line doesn't get re-scanned in its entirety
+const MAX_LOGICAL_LINE_LEN: usize = 1024;
+
 pub struct RenderableInner {
     pub client: Arc<ClientInner>,
     remote_pane_id: PaneId,
@@ -358,12 +377,12 @@ impl RenderableInner {
             delta.seqno,
             self.local_pane_id
         );
+        let produced_output = delta.seqno != self.seqno;
         self.seqno = delta.seqno;
 
-        let config = configuration();
         for (stable_row, line) in bonus_lines {
             log::trace!("bonus line {} seqno={}", stable_row, line.current_seqno());
-            self.put_line(stable_row, line, &config, None);
+            self.put_line(stable_row, line, None);
             dirty.remove(stable_row);
         }
 
@@ -371,7 +390,11 @@ impl RenderableInner {
             "apply_changes_to_surface: Generate PaneOutput event for local={}",
             self.local_pane_id
         );
-        Mux::get().notify(mux::MuxNotification::PaneOutput(self.local_pane_id));
+        let mux = Mux::get();
+        if produced_output {
+            mux.record_pane_monitor_output(self.local_pane_id);
+        }
+        mux.notify(mux::MuxNotification::PaneOutput(self.local_pane_id));
 
         let mut to_fetch = RangeSet::new();
         log::trace!("dirty as of seq {} -> {:?}", delta.seqno, dirty);
@@ -449,11 +472,10 @@ impl RenderableInner {
         &mut self,
         stable_row: StableRowIndex,
         mut line: Line,
-        config: &ConfigHandle,
         fetch_start: Option<Instant>,
     ) {
-        line.scan_and_create_hyperlinks(&config.hyperlink_rules);
-
+        // Implicit hyperlinks are applied later by apply_hyperlinks,
+        // which can see the neighboring rows that this line wraps into
         let entry = if let Some(fetch_start) = fetch_start {
             // If we're completing a fetch, only replace entries that were
             // set to fetching as part of our fetch.  If they are now longer
@@ -546,11 +568,9 @@ impl RenderableInner {
 
             match result {
                 Ok(lines) => {
-                    let config = configuration();
-
                     log::trace!("fetch complete for {:?} at {:?}", to_fetch, now);
                     for (stable_row, line) in lines.into_iter() {
-                        inner.put_line(stable_row, line, &config, Some(now));
+                        inner.put_line(stable_row, line, Some(now));
                     }
                 }
                 Err(err) => {
@@ -798,6 +818,12 @@ impl RenderableState {
         (lines.start, result)
     }
 
+    /// Applies the implicit hyperlink `rules` to the cached copies of
+    /// `lines`; see `apply_hyperlinks_to_cache`
+    pub fn apply_hyperlinks(&self, lines: Range<StableRowIndex>, rules: &[Rule]) {
+        apply_hyperlinks_to_cache(&mut self.inner.borrow_mut().lines, lines, rules);
+    }
+
     pub fn get_current_seqno(&self) -> SequenceNo {
         self.inner.borrow().seqno
     }
@@ -858,3 +884,132 @@ impl RenderableState {
         self.inner.borrow().dimensions
     }
 }
+
+/// Applies the implicit hyperlink `rules` to the copies of `lines` that
+/// are held in `cache`.  Rows that are joined by soft wrapping are
+/// matched as a single logical line, so that a long URL in a narrow pane
+/// is recognized as one link rather than as several fragments.
+fn apply_hyperlinks_to_cache(
+    cache: &mut LruCache<StableRowIndex, LineEntry>,
+    lines: Range<StableRowIndex>,
+    rules: &[Rule],
+) {
+    if rules.is_empty() || lines.is_empty() {
+        return;
+    }
+    // Look backwards to find the start of the first logical line
+    let mut row = lines.start;
+    let mut back_len = 0;
+    while let Some(prior) = cache.peek(&(row - 1)).and_then(LineEntry::line) {
+        if !prior.last_cell_was_wrapped() || prior.len() + back_len > MAX_LOGICAL_LINE_LEN {
+            break;
+        }
+        back_len += prior.len();
+        row -= 1;
+    }
+
+    let mut logical: Vec<(StableRowIndex, LineEntry)> = vec![];
+    let mut logical_len = 0;
+    loop {
+        let mut entry = cache.pop(&row);
+        let wrapped = match entry.as_mut().and_then(LineEntry::line_mut) {
+            Some(line) => {
+                logical_len += line.len();
+                Some(line.last_cell_was_wrapped())
+            }
+            None => None,
+        };
+
+        // Continue the logical line while this row wraps into the
+        // next one, even if that takes us past the end of `lines`
+        let continues = wrapped == Some(true) && logical_len <= MAX_LOGICAL_LINE_LEN;
+        if let Some(entry) = entry {
+            logical.push((row, entry));
+        }
+        if !continues {
+            let mut physical: Vec<&mut Line> = logical
+                .iter_mut()
+                .filter_map(|(_, entry)| entry.line_mut())
+                .collect();
+            Line::apply_hyperlink_rules(rules, &mut physical);
+            for (idx, entry) in logical.drain(..) {
+                cache.put(idx, entry);
+            }
+            logical_len = 0;
+            if row + 1 >= lines.end {
+                break;
+            }
+        }
+        row += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cache(rows: &[(&str, bool)]) -> LruCache<StableRowIndex, LineEntry> {
+        let mut cache = LruCache::unbounded();
+        for (idx, (text, wrapped)) in rows.iter().enumerate() {
+            let mut line: Line = (*text).into();
+            line.set_last_cell_was_wrapped(*wrapped, SEQ_ZERO);
+            cache.put(idx as StableRowIndex, LineEntry::Line(line));
+        }
+        cache
+    }
+
+    fn link(
+        cache: &LruCache<StableRowIndex, LineEntry>,
+        row: StableRowIndex,
+        idx: usize,
+    ) -> Option<String> {
+        cache
+            .peek(&row)
+            .and_then(LineEntry::line)
+            .and_then(|line| line.get_cell(idx))
+            .and_then(|cell| cell.attrs().hyperlink().map(|link| link.uri().to_string()))
+    }
+
+    fn rules() -> Vec<Rule> {
+        vec![Rule::new(r"\b\w+://(?:[\w.-]+)\.[a-z]{2,15}\S*\b", "$0").unwrap()]
+    }
+
+    #[test]
+    fn wrapped_rows_are_matched_together() {
+        let mut cache = cache(&[
+            ("see https://exam", true),
+            ("ple.com/a/b ok", false),
+            ("https://other.org", false),
+        ]);
+        // Only the second row is requested, but the link starts on the first
+        apply_hyperlinks_to_cache(&mut cache, 1..2, &rules());
+
+        let expected = Some("https://example.com/a/b".to_string());
+        assert_eq!(link(&cache, 0, 3), None);
+        assert_eq!(link(&cache, 0, 4), expected);
+        assert_eq!(link(&cache, 1, 10), expected);
+        assert_eq!(link(&cache, 1, 12), None);
+        // Rows after the requested range that aren't part of its
+        // logical lines are left alone
+        assert_eq!(link(&cache, 2, 0), None);
+    }
+
+    #[test]
+    fn logical_line_continues_past_range() {
+        let mut cache = cache(&[("go to https://exam", true), ("ple.com now", false)]);
+        apply_hyperlinks_to_cache(&mut cache, 0..1, &rules());
+        let expected = Some("https://example.com".to_string());
+        assert_eq!(link(&cache, 0, 6), expected);
+        assert_eq!(link(&cache, 1, 0), expected);
+    }
+
+    #[test]
+    fn rows_being_fetched_are_skipped() {
+        let mut cache = cache(&[("https://example.com", false)]);
+        cache.put(1, LineEntry::Fetching(Instant::now()));
+        apply_hyperlinks_to_cache(&mut cache, 0..3, &rules());
+        assert_eq!(link(&cache, 0, 0), Some("https://example.com".to_string()));
+        assert!(matches!(cache.peek(&1), Some(LineEntry::Fetching(_))));
+        assert!(cache.peek(&2).is_none());
+    }
+}
@@ -0,0 +1,119 @@
+//! Tunnels TCP connections through the proxy servers configured by
+//! `http_proxy`, `socks5_proxy` and `proxy_overrides`
+use anyhow::{anyhow, bail, Context};
+use config::{ProxyKind, ProxyServer};
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+/// Connects to `host:port` through `proxy`
+pub fn connect_via_proxy(proxy: &ProxyServer, host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port))
+        .with_context(|| format!("connecting to proxy {}", proxy.address()))?;
+    match proxy.kind {
+        ProxyKind::Http => http_connect(&mut stream, host, port),
+        ProxyKind::Socks5 => socks5_connect(&mut stream, host, port),
+    }
+    .with_context(|| format!("tunneling to {host}:{port} via proxy {}", proxy.address()))?;
+    Ok(stream)
+}
+
+fn http_connect(stream: &mut TcpStream, host: &str, port: u16) -> anyhow::Result<()> {
+    let authority = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    write!(
+        stream,
+        "CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n\r\n"
+    )?;
+
+    // Read the response a byte at a time so that none of the
+    // tunneled data is consumed along with the headers
+    let mut response = vec![];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 8192 {
+            bail!("proxy response headers are too long");
+        }
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte)?;
+        response.push(byte[0]);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or("");
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => bail!("proxy refused CONNECT: {status}"),
+    }
+}
+
+fn socks5_connect(stream: &mut TcpStream, host: &str, port: u16) -> anyhow::Result<()> {
+    // Version 5, offering only the "no authentication" method
+    stream.write_all(&[5, 1, 0])?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply)?;
+    if reply != [5, 0] {
+        bail!("proxy requires an unsupported authentication method");
+    }
+
+    let mut request = vec![5, 1, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(addr)) => {
+            request.push(1);
+            request.extend_from_slice(&addr.octets());
+        }
+        Ok(IpAddr::V6(addr)) => {
+            request.push(4);
+            request.extend_from_slice(&addr.octets());
+        }
+        Err(_) => {
+            let len: u8 = host
+                .len()
+                .try_into()
+                .map_err(|_| anyhow!("host name {host} is too long"))?;
+            request.push(3);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0 {
+        bail!("proxy failed to connect: {}", socks5_error(reply[1]));
+    }
+
+    // The reply ends with the address the proxy bound for us,
+    // which we have no use for
+    let address_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8];
+            stream.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        kind => bail!("proxy replied with unknown address type {kind}"),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound)?;
+    Ok(())
+}
+
+fn socks5_error(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
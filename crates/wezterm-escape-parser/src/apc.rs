@@ -949,8 +949,10 @@ pub struct KittyImageFrame {
 
     /// Gap in milliseconds of this frame from the next one.
     /// Zero or omitted values are interpreted as 40ms.
+    /// Negative values create a gapless frame, which is composed
+    /// into the frames that follow it but never shown by itself.
     /// z=...
-    pub duration_ms: Option<u32>,
+    pub duration_ms: Option<i32>,
 
     /// Composition mode.
     /// Default is AlphaBlending
@@ -976,7 +978,7 @@ impl KittyImageFrame {
                 None | Some(0) => None,
                 n => n,
             },
-            duration_ms: match geti(keys, "Z") {
+            duration_ms: match geti(keys, "z") {
                 None | Some(0) => None,
                 n => n,
             },
@@ -994,7 +996,7 @@ impl KittyImageFrame {
         set(keys, "y", &self.y);
         set(keys, "c", &self.base_frame);
         set(keys, "r", &self.frame_number);
-        set(keys, "Z", &self.duration_ms);
+        set(keys, "z", &self.duration_ms);
         match &self.composition_mode {
             KittyFrameCompositionMode::AlphaBlending => {}
             KittyFrameCompositionMode::Overwrite => {
@@ -1265,5 +1267,31 @@ mod test {
                 },
             }
         );
+
+        assert_eq!(
+            KittyImage::parse_apc("Ga=f,i=3,r=2,z=-1,q=2;AAAA=".as_bytes()).unwrap(),
+            KittyImage::TransmitFrame {
+                transmit: KittyImageTransmit {
+                    format: None,
+                    data: KittyImageData::Direct("AAAA=".to_string()),
+                    width: None,
+                    height: None,
+                    image_id: Some(3),
+                    image_number: None,
+                    compression: KittyImageCompression::None,
+                    more_data_follows: false,
+                },
+                verbosity: KittyImageVerbosity::Quiet,
+                frame: KittyImageFrame {
+                    x: None,
+                    y: None,
+                    base_frame: None,
+                    frame_number: Some(2),
+                    composition_mode: KittyFrameCompositionMode::AlphaBlending,
+                    background_pixel: None,
+                    duration_ms: Some(-1),
+                },
+            }
+        );
     }
 }
@@ -55,6 +55,11 @@ impl Hyperlink {
         }
     }
 
+    /// Sets the parameter `key` to `value`, replacing any prior value
+    pub fn set_param<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.params.insert(key.into(), value.into());
+    }
+
     pub fn new_with_id<S: Into<String>, S2: Into<String>>(uri: S, id: S2) -> Self {
         let mut params = HashMap::new();
         params.insert("id".into(), id.into());
@@ -932,6 +932,13 @@ pub enum ITermProprietary {
     SetBadgeFormat(String),
     /// Download file data from the application.
     File(Box<ITermFileData>),
+    /// Begins a file transfer whose data is sent in a series of
+    /// FilePart sequences.  The data field is always empty.
+    MultipartFile(Box<ITermFileData>),
+    /// A chunk of the data for the file started by MultipartFile
+    FilePart(Vec<u8>),
+    /// Completes the file started by MultipartFile
+    FileEnd,
 
     /// Configure unicode version
     UnicodeVersion(ITermUnicodeVersionOp),
@@ -968,6 +975,14 @@ pub struct ITermFileData {
 
 impl ITermFileData {
     fn parse(osc: &[&[u8]]) -> Result<Self> {
+        Self::parse_args(osc, "File=", true)
+    }
+
+    fn parse_multipart(osc: &[&[u8]]) -> Result<Self> {
+        Self::parse_args(osc, "MultipartFile=", false)
+    }
+
+    fn parse_args(osc: &[&[u8]], keyword: &str, has_data: bool) -> Result<Self> {
         let mut params = HashMap::new();
 
         // Unfortunately, the encoding for the file download data is
@@ -982,17 +997,17 @@ impl ITermFileData {
         let last = osc.len() - 1;
         for (idx, s) in osc.iter().enumerate().skip(1) {
             let param = if idx == 1 {
-                if s.len() >= 5 {
-                    // skip over File=
-                    &s[5..]
+                if s.len() >= keyword.len() {
+                    // skip over File= or MultipartFile=
+                    &s[keyword.len()..]
                 } else {
-                    bail!("failed to parse file data; File= not found");
+                    bail!("failed to parse file data; {} not found", keyword);
                 }
             } else {
                 s
             };
 
-            let param = if idx == last {
+            let param = if idx == last && has_data {
                 // The final argument contains `:base64`, so look for that
                 if let Some(colon) = param.iter().position(|c| *c == b':') {
                     data = Some(base64_decode(&param[colon + 1..])?);
@@ -1043,7 +1058,11 @@ impl ITermFileData {
             .get("doNotMoveCursor")
             .map(|s| *s != "0")
             .unwrap_or(false);
-        let data = data.ok_or_else(|| format!("didn't set data"))?;
+        let data = if has_data {
+            data.ok_or_else(|| format!("didn't set data"))?
+        } else {
+            vec![]
+        };
         Ok(Self {
             name,
             size,
@@ -1057,9 +1076,10 @@ impl ITermFileData {
     }
 }
 
-impl Display for ITermFileData {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "File")?;
+impl ITermFileData {
+    /// Emits the `=key=value;...` portion that is shared by File
+    /// and MultipartFile
+    fn fmt_params(&self, f: &mut Formatter) -> FmtResult {
         let mut sep = "=";
         let emit_sep = |sep, f: &mut Formatter| -> core::result::Result<&str, FmtError> {
             write!(f, "{}", sep)?;
@@ -1098,6 +1118,14 @@ impl Display for ITermFileData {
         if sep == "=" {
             write!(f, "=")?;
         }
+        Ok(())
+    }
+}
+
+impl Display for ITermFileData {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "File")?;
+        self.fmt_params(f)?;
         write!(f, ":{}", base64_encode(&self.data))?;
         Ok(())
     }
@@ -1217,6 +1245,7 @@ impl ITermProprietary {
         single!(ClearScrollback, "ClearScrollback");
         single!(EndCopy, "EndCopy");
         single!(RequestCellSize, "ReportCellSize");
+        single!(FileEnd, "FileEnd");
         const_arg!(HighlightCursorLine, "HighlightCursorLine", "yes", true);
         const_arg!(HighlightCursorLine, "HighlightCursorLine", "no", false);
         one_str!(CurrentDir, "CurrentDir");
@@ -1308,6 +1337,18 @@ impl ITermProprietary {
             return Ok(ITermProprietary::File(Box::new(ITermFileData::parse(osc)?)));
         }
 
+        if keyword == "MultipartFile" {
+            return Ok(ITermProprietary::MultipartFile(Box::new(
+                ITermFileData::parse_multipart(osc)?,
+            )));
+        }
+
+        if osc.len() == 2 && keyword == "FilePart" {
+            if let Some(p1) = p1 {
+                return Ok(ITermProprietary::FilePart(base64_decode(p1)?));
+            }
+        }
+
         bail!("ITermProprietary {:?}", osc);
     }
 }
@@ -1364,6 +1405,12 @@ impl Display for ITermProprietary {
             }
             SetBadgeFormat(s) => write!(f, "SetBadgeFormat={}", base64_encode(s))?,
             File(file) => file.fmt(f)?,
+            MultipartFile(file) => {
+                write!(f, "MultipartFile")?;
+                file.fmt_params(f)?
+            }
+            FilePart(data) => write!(f, "FilePart={}", base64_encode(data))?,
+            FileEnd => write!(f, "FileEnd")?,
             UnicodeVersion(ITermUnicodeVersionOp::Set(n)) => write!(f, "UnicodeVersion={}", n)?,
             UnicodeVersion(ITermUnicodeVersionOp::Push(Some(label))) => {
                 write!(f, "UnicodeVersion=push {}", label)?
@@ -1997,4 +2044,39 @@ mod test {
             )))
         );
     }
+
+    #[test]
+    fn iterm_multipart_file() {
+        assert_eq!(
+            parse(
+                &["1337", "MultipartFile=size=5", "inline=1"],
+                "\x1b]1337;MultipartFile=size=5;inline=1\x1b\\"
+            ),
+            OperatingSystemCommand::ITermProprietary(ITermProprietary::MultipartFile(Box::new(
+                ITermFileData {
+                    name: None,
+                    size: Some(5),
+                    width: ITermDimension::Automatic,
+                    height: ITermDimension::Automatic,
+                    preserve_aspect_ratio: true,
+                    inline: true,
+                    do_not_move_cursor: false,
+                    data: vec![],
+                }
+            )))
+        );
+
+        assert_eq!(
+            parse(
+                &["1337", "FilePart=aGVsbG8="],
+                "\x1b]1337;FilePart=aGVsbG8=\x1b\\"
+            ),
+            OperatingSystemCommand::ITermProprietary(ITermProprietary::FilePart(b"hello".to_vec()))
+        );
+
+        assert_eq!(
+            parse(&["1337", "FileEnd"], "\x1b]1337;FileEnd\x1b\\"),
+            OperatingSystemCommand::ITermProprietary(ITermProprietary::FileEnd)
+        );
+    }
 }
@@ -183,7 +183,15 @@ impl Face {
         let mut mm = std::ptr::null_mut();
 
         unsafe {
-            ft_result(FT_Get_MM_Var(self.face, &mut mm), ()).context("FT_Get_MM_Var")?;
+            if FT_Get_MM_Var(self.face, &mut mm) != 0 {
+                // Not a variable font; the same axes apply to every
+                // font in the fallback list, so this isn't an error
+                log::warn!(
+                    "font {} has no variation axes; ignoring {axes:?}",
+                    self.source.diagnostic_string()
+                );
+                return Ok(());
+            }
 
             let mut res = vec![];
             let num_styles = (*mm).num_namedstyles;
@@ -208,6 +216,79 @@ impl Face {
         }
     }
 
+    /// Sets the design coordinates of the named axes of a variable
+    /// font, clamped to the range supported by each axis.  Axes that
+    /// aren't named keep their current coordinates.
+    pub fn set_variation_axes(&mut self, axes: &[(String, f64)]) -> anyhow::Result<()> {
+        if axes.is_empty() {
+            return Ok(());
+        }
+
+        let mut mm = std::ptr::null_mut();
+        unsafe {
+            if FT_Get_MM_Var(self.face, &mut mm) != 0 {
+                // Not a variable font; the same axes apply to every
+                // font in the fallback list, so this isn't an error
+                log::warn!(
+                    "font {} has no variation axes; ignoring {axes:?}",
+                    self.source.diagnostic_string()
+                );
+                return Ok(());
+            }
+
+            let result = (|| {
+                let mm = &*mm;
+                let ft_axes = from_raw_parts(mm.axis, mm.num_axis as usize);
+
+                let mut coords: Vec<FT_Fixed> = ft_axes.iter().map(|axis| axis.def).collect();
+                ft_result(
+                    FT_Get_Var_Design_Coordinates(
+                        self.face,
+                        coords.len() as FT_UInt,
+                        coords.as_mut_ptr(),
+                    ),
+                    (),
+                )
+                .context("FT_Get_Var_Design_Coordinates")?;
+
+                for (tag, value) in axes {
+                    let bytes = tag.as_bytes();
+                    if bytes.len() != 4 {
+                        log::warn!("ignoring invalid font variation axis tag {tag:?}");
+                        continue;
+                    }
+                    let ft_tag = ft_make_tag(bytes[0], bytes[1], bytes[2], bytes[3]);
+                    match ft_axes.iter().position(|axis| axis.tag == ft_tag) {
+                        Some(idx) => {
+                            let axis = &ft_axes[idx];
+                            let value = value
+                                .max(axis.minimum.to_num::<f64>())
+                                .min(axis.maximum.to_num::<f64>());
+                            coords[idx] = FT_Fixed::from_num(value);
+                        }
+                        None => log::warn!(
+                            "font {} has no variation axis {tag:?}",
+                            self.source.diagnostic_string()
+                        ),
+                    }
+                }
+
+                ft_result(
+                    FT_Set_Var_Design_Coordinates(
+                        self.face,
+                        coords.len() as FT_UInt,
+                        coords.as_mut_ptr(),
+                    ),
+                    (),
+                )
+                .context("FT_Set_Var_Design_Coordinates")
+            })();
+
+            FT_Done_MM_Var(self.lib, mm);
+            result
+        }
+    }
+
     pub fn get_glyph_name(&self, glyph_index: u32) -> Option<String> {
         let mut buf = [0u8; 128];
         let res = unsafe {
@@ -25,6 +25,13 @@ pub const IS_PNG: hb_tag_t = hb_tag(b'p', b'n', b'g', b' ');
 pub const IS_SVG: hb_tag_t = hb_tag(b's', b'v', b'g', b' ');
 pub const IS_BGRA: hb_tag_t = hb_tag(b'B', b'G', b'R', b'A');
 
+/// Returns the version of the harfbuzz library in use
+pub fn version_string() -> String {
+    unsafe { CStr::from_ptr(hb_version_string()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
 pub fn language_from_string(s: &str) -> Result<hb_language_t, Error> {
     unsafe {
         let lang = hb_language_from_string(s.as_ptr() as *const c_char, s.len() as i32);
@@ -29,6 +29,7 @@ pub mod ftwrap;
 pub mod locator;
 pub mod parser;
 pub mod rasterizer;
+pub mod report;
 pub mod shaper;
 pub mod units;
 
@@ -48,8 +49,68 @@ pub fn alloc_font_id() -> LoadedFontId {
     FONT_ID.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed)
 }
 
+type MissingGlyphHandler = Box<dyn Fn(Vec<char>) + Send + Sync>;
+
 lazy_static::lazy_static! {
     static ref LAST_WARNING: Mutex<Option<(Instant, usize)>> = Mutex::new(None);
+    /// The codepoints already passed to the missing glyph handler,
+    /// along with the config generation they were reported under
+    static ref REPORTED_MISSING: Mutex<(usize, HashSet<char>)> = Mutex::new((0, HashSet::new()));
+    static ref MISSING_GLYPH_HANDLER: Mutex<Option<MissingGlyphHandler>> = Mutex::new(None);
+}
+
+/// Registers a function that is called from a background thread with
+/// the codepoints that no font could provide.  Each codepoint is only
+/// reported once per config generation.
+pub fn set_missing_glyph_handler<F: Fn(Vec<char>) + Send + Sync + 'static>(handler: F) {
+    MISSING_GLYPH_HANDLER
+        .lock()
+        .unwrap()
+        .replace(Box::new(handler));
+}
+
+/// Formats codepoints in the style of `U+1F600 😀, U+E0B0`, omitting
+/// the character itself for control and private use codepoints
+pub fn describe_codepoints(codepoints: &[char]) -> String {
+    codepoints
+        .iter()
+        .map(|&c| {
+            let cp = c as u32;
+            let private_use = (0xE000..=0xF8FF).contains(&cp)
+                || (0xF0000..=0xFFFFD).contains(&cp)
+                || (0x100000..=0x10FFFD).contains(&cp);
+            if c.is_control() || private_use {
+                format!("U+{cp:04X}")
+            } else {
+                format!("U+{cp:04X} {c}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn report_missing_glyphs(codepoints: &[char], generation: usize) {
+    let handler = MISSING_GLYPH_HANDLER.lock().unwrap();
+    let handler = match handler.as_ref() {
+        Some(handler) => handler,
+        None => return,
+    };
+
+    let new_codepoints: Vec<char> = {
+        let mut reported = REPORTED_MISSING.lock().unwrap();
+        if reported.0 != generation {
+            reported.0 = generation;
+            reported.1.clear();
+        }
+        codepoints
+            .iter()
+            .copied()
+            .filter(|&c| reported.1.insert(c))
+            .collect()
+    };
+    if !new_codepoints.is_empty() {
+        handler(new_codepoints);
+    }
 }
 
 pub struct LoadedFont {
@@ -67,6 +128,11 @@ pub struct LoadedFont {
     /// Glyphs for which no font was found and for which we should
     /// stop searching
     tried_glyphs: RefCell<HashSet<char>>,
+    /// Memoized result of shaping_fingerprint
+    shaping_fingerprint: RefCell<Option<u64>>,
+    /// How many of `handles` came from the configuration, rather than
+    /// being discovered later to supply missing glyphs
+    configured_handles: usize,
 }
 
 impl std::fmt::Debug for LoadedFont {
@@ -95,6 +161,50 @@ impl LoadedFont {
         self.id
     }
 
+    /// Returns a value that identifies the output of `shape` for this
+    /// font, so that shaped results can be reused across runs.  It covers
+    /// the fonts in the fallback list (and the modification time of those
+    /// loaded from disk), the size, the shaper options and the harfbuzz
+    /// version, and so changes when fallback fonts are added.
+    pub fn shaping_fingerprint(&self) -> u64 {
+        if let Some(fingerprint) = *self.shaping_fingerprint.borrow() {
+            return fingerprint;
+        }
+
+        // DefaultHasher may change between Rust releases, so use a hash
+        // whose output is stable for the fingerprint to outlive the run
+        use std::hash::{Hash, Hasher};
+        let mut hasher = fnv::FnvHasher::default();
+        hbwrap::version_string().hash(&mut hasher);
+        self.font_size.to_bits().hash(&mut hasher);
+        self.dpi.hash(&mut hasher);
+        if let Some(font_config) = self.font_config.upgrade() {
+            let config = font_config.config.borrow();
+            format!("{:?}", config.font_shaper).hash(&mut hasher);
+            config.harfbuzz_features.hash(&mut hasher);
+        }
+        for parsed in self.handles.borrow().iter() {
+            let handle = &parsed.handle;
+            handle.source.name_or_path_str().hash(&mut hasher);
+            handle.index.hash(&mut hasher);
+            handle.variation.hash(&mut hasher);
+            parsed.harfbuzz_features.hash(&mut hasher);
+            format!("{:?}", parsed.variation).hash(&mut hasher);
+            if let Some(meta) = handle
+                .source
+                .path_str()
+                .and_then(|path| std::fs::metadata(&*path).ok())
+            {
+                meta.len().hash(&mut hasher);
+                meta.modified().ok().hash(&mut hasher);
+            }
+        }
+
+        let fingerprint = hasher.finish();
+        self.shaping_fingerprint.borrow_mut().replace(fingerprint);
+        fingerprint
+    }
+
     fn insert_fallback_handles(&self, extra_handles: Vec<ParsedFont>) -> anyhow::Result<bool> {
         let mut loaded = false;
         {
@@ -107,6 +217,7 @@ impl LoadedFont {
             }
             if loaded {
                 log::trace!("revised fallback: {:#?}", handles);
+                self.shaping_fingerprint.borrow_mut().take();
             }
         }
         if loaded {
@@ -394,10 +505,12 @@ impl FallbackResolveInfo {
 
         if !wanted.is_empty() {
             // There were some glyphs we couldn't resolve!
-            let fallback_str = wanted
+            let missing: Vec<char> = wanted
                 .iter_values()
-                .map(|c| std::char::from_u32(c).unwrap_or(' '))
-                .collect::<String>();
+                .filter_map(std::char::from_u32)
+                .collect();
+            report_missing_glyphs(&missing, self.config.generation());
+            let fallback_str = describe_codepoints(&missing);
 
             let current_gen = self.config.generation();
             let show_warning = self.config.warn_about_missing_glyphs
@@ -423,7 +536,7 @@ impl FallbackResolveInfo {
                      configuration so that it can find them.\n\
                      {} has more information about configuring fonts.\n\
                      Set warn_about_missing_glyphs=false to suppress this message.",
-                    fallback_str.escape_unicode(),
+                    fallback_str,
                     url,
                 );
 
@@ -436,16 +549,17 @@ impl FallbackResolveInfo {
                             your configuration so that it can find them.\n\
                             Set warn_about_missing_glyphs=false to suppress this\n\
                             message.",
-                        fallback_str.escape_unicode()
+                        fallback_str
                     ),
                     url: Some(url.to_string()),
                     timeout: Some(Duration::from_secs(15)),
+                    activation_token: None,
                 }
                 .show();
             } else {
                 log::debug!(
                     "No fonts contain glyphs for these codepoints: {}",
-                    fallback_str.escape_unicode()
+                    fallback_str
                 );
             }
         }
@@ -635,6 +749,7 @@ impl FontConfigInner {
             )
         })?;
 
+        let configured_handles = handles.len();
         let loaded = Rc::new(LoadedFont {
             rasterizers: RefCell::new(HashMap::new()),
             handles: RefCell::new(handles),
@@ -647,6 +762,8 @@ impl FontConfigInner {
             text_style: text_style.clone(),
             id: alloc_font_id(),
             tried_glyphs: RefCell::new(HashSet::new()),
+            shaping_fingerprint: RefCell::new(None),
+            configured_handles,
             pixel_geometry: config.display_pixel_geometry,
         });
 
@@ -926,6 +1043,7 @@ impl FontConfigInner {
             }
         }
 
+        let configured_handles = handles.len();
         let loaded = Rc::new(LoadedFont {
             rasterizers: RefCell::new(HashMap::new()),
             handles: RefCell::new(handles),
@@ -938,6 +1056,8 @@ impl FontConfigInner {
             text_style: style.clone(),
             id: alloc_font_id(),
             tried_glyphs: RefCell::new(HashSet::new()),
+            shaping_fingerprint: RefCell::new(None),
+            configured_handles,
             pixel_geometry: config.display_pixel_geometry,
         });
 
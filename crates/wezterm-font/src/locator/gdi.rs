@@ -327,6 +327,7 @@ impl FontLocator for GdiFontLocator {
                         freetype_load_flags: None,
                         scale: None,
                         assume_emoji_presentation: None,
+                        variation: None,
                     };
 
                     if !resolved.contains(&attr) {
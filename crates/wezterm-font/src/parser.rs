@@ -42,6 +42,8 @@ pub struct ParsedFont {
     pub freetype_render_target: Option<FreeTypeLoadTarget>,
     pub freetype_load_flags: Option<FreeTypeLoadFlags>,
     pub scale: Option<f64>,
+    /// Axis tags and coordinates for a variable font
+    pub variation: Option<Vec<(String, f64)>>,
 }
 
 impl std::fmt::Debug for ParsedFont {
@@ -63,6 +65,7 @@ impl std::fmt::Debug for ParsedFont {
             .field("freetype_render_target", &self.freetype_render_target)
             .field("freetype_load_flags", &self.freetype_load_flags)
             .field("scale", &self.scale)
+            .field("variation", &self.variation)
             .finish()
     }
 }
@@ -88,6 +91,7 @@ impl Clone for ParsedFont {
             freetype_load_flags: self.freetype_load_flags,
             is_built_in_fallback: self.is_built_in_fallback,
             scale: self.scale,
+            variation: self.variation.clone(),
             palettes: self.palettes.clone(),
         }
     }
@@ -337,6 +341,7 @@ impl ParsedFont {
                 && p.freetype_load_flags.is_none()
                 && p.harfbuzz_features.is_none()
                 && p.scale.is_none()
+                && p.variation.is_none()
             {
                 code.push_str(&format!("  \"{}\",\n", p.names.family));
             } else {
@@ -362,6 +367,16 @@ impl ParsedFont {
                 if let Some(item) = p.freetype_render_target {
                     code.push_str(&format!(", freetype_render_target=\"{:?}\"", item));
                 }
+                if let Some(axes) = &p.variation {
+                    code.push_str(", variation={");
+                    for (idx, (tag, value)) in axes.iter().enumerate() {
+                        if idx > 0 {
+                            code.push_str(", ");
+                        }
+                        code.push_str(&format!("{}={}", tag, value));
+                    }
+                    code.push('}');
+                }
                 if let Some(feat) = &p.harfbuzz_features {
                     code.push_str(", harfbuzz_features={");
                     for (idx, f) in feat.iter().enumerate() {
@@ -529,6 +544,7 @@ impl ParsedFont {
             freetype_load_target: None,
             freetype_load_flags: None,
             scale: None,
+            variation: None,
             palettes,
         })
     }
@@ -781,6 +797,11 @@ impl ParsedFont {
         self.freetype_load_target = attr.freetype_load_target;
         self.freetype_load_flags = attr.freetype_load_flags;
         self.scale = attr.scale.map(|f| *f);
+        self.variation = attr.variation.as_ref().map(|axes| {
+            axes.iter()
+                .map(|(tag, value)| (tag.clone(), **value))
+                .collect()
+        });
 
         self.synthesize_italic = self.style == FontStyle::Normal && attr.style != FontStyle::Normal;
         self.synthesize_bold = attr.weight >= FontWeight::DEMIBOLD
@@ -0,0 +1,200 @@
+//! Explains which font supplied each glyph of some text, and why;
+//! used by `kaku cli font-report` and the debug overlay.
+use crate::shaper::PresentationWidth;
+use crate::{FontConfiguration, LoadedFont};
+use std::fmt::Write;
+use termwiz::cell::CellAttributes;
+use termwiz::surface::{Line, SEQ_ZERO};
+
+/// How the font that supplied a glyph came to be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphSource {
+    /// The first font of the style
+    Primary,
+    /// A later font in the configured fallback list
+    ConfiguredFallback,
+    /// A font bundled with the application
+    BuiltInFallback,
+    /// A font found on the system because no configured font
+    /// had the glyph
+    DiscoveredFallback,
+    /// No font had the glyph, so a placeholder is drawn instead
+    LastResort,
+}
+
+impl GlyphSource {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Primary => "primary font",
+            Self::ConfiguredFallback => "configured fallback",
+            Self::BuiltInFallback => "built-in fallback",
+            Self::DiscoveredFallback => "discovered fallback",
+            Self::LastResort => "LAST RESORT: no font has this glyph",
+        }
+    }
+}
+
+/// Describes a single glyph produced by the shaper
+#[derive(Debug, Clone)]
+pub struct GlyphReport {
+    /// The text that the glyph represents
+    pub text: String,
+    /// Index into the fallback list of the font that supplied the glyph
+    pub font_idx: usize,
+    pub font_name: String,
+    pub glyph_pos: u32,
+    pub num_cells: u8,
+    pub x_advance: f64,
+    pub source: GlyphSource,
+}
+
+/// Describes a run of text that was shaped together
+#[derive(Debug, Clone)]
+pub struct ClusterReport {
+    pub text: String,
+    pub presentation: String,
+    pub direction: String,
+    pub width: usize,
+    pub glyphs: Vec<GlyphReport>,
+}
+
+fn classify(font: &LoadedFont, font_idx: usize, glyph_pos: u32, is_space: bool) -> GlyphSource {
+    let handles = font.handles.borrow();
+    let parsed = handles.get(font_idx);
+    let is_last_resort = parsed
+        .map(|p| p.names().family == "Last Resort High-Efficiency")
+        .unwrap_or(true);
+
+    if (glyph_pos == 0 && !is_space) || is_last_resort {
+        GlyphSource::LastResort
+    } else if font_idx >= font.configured_handles {
+        GlyphSource::DiscoveredFallback
+    } else if parsed.map(|p| p.is_built_in_fallback).unwrap_or(false) {
+        GlyphSource::BuiltInFallback
+    } else if font_idx == 0 {
+        GlyphSource::Primary
+    } else {
+        GlyphSource::ConfiguredFallback
+    }
+}
+
+/// Shapes `text` with the default font, resolving fallback fonts as
+/// needed, and reports where each glyph came from
+pub fn shape_report(fonts: &FontConfiguration, text: &str) -> anyhow::Result<Vec<ClusterReport>> {
+    let font = fonts.default_font()?;
+    let line = Line::from_text(text, &CellAttributes::default(), SEQ_ZERO, None);
+    let mut reports = vec![];
+
+    for cluster in line.cluster(None) {
+        let presentation_width = PresentationWidth::with_cluster(&cluster);
+        let infos = font.blocking_shape(
+            &cluster.text,
+            Some(cluster.presentation),
+            cluster.direction,
+            None,
+            Some(&presentation_width),
+        )?;
+
+        let mut starts: Vec<usize> = infos.iter().map(|info| info.cluster as usize).collect();
+        starts.sort_unstable();
+        starts.dedup();
+
+        let glyphs = infos
+            .iter()
+            .map(|info| {
+                let start = info.cluster as usize;
+                let end = starts
+                    .iter()
+                    .copied()
+                    .find(|&s| s > start)
+                    .unwrap_or(cluster.text.len());
+                let font_name = font
+                    .handles
+                    .borrow()
+                    .get(info.font_idx)
+                    .map(|p| p.names().full_name.clone())
+                    .unwrap_or_else(|| "?".to_string());
+                GlyphReport {
+                    text: cluster.text.get(start..end).unwrap_or("").to_string(),
+                    font_idx: info.font_idx,
+                    font_name,
+                    glyph_pos: info.glyph_pos,
+                    num_cells: info.num_cells,
+                    x_advance: info.x_advance.get(),
+                    source: classify(&font, info.font_idx, info.glyph_pos, info.is_space),
+                }
+            })
+            .collect();
+
+        reports.push(ClusterReport {
+            text: cluster.text.clone(),
+            presentation: format!("{:?}", cluster.presentation),
+            direction: format!("{:?}", cluster.direction),
+            width: cluster.width,
+            glyphs,
+        });
+    }
+
+    Ok(reports)
+}
+
+/// Produces a human readable report of how `text` is rendered:
+/// the fallback list of the default font, followed by the font
+/// and shaping decisions for each glyph
+pub fn font_report(fonts: &FontConfiguration, text: &str) -> anyhow::Result<String> {
+    let clusters = shape_report(fonts, text)?;
+    let font = fonts.default_font()?;
+    let mut out = String::new();
+
+    writeln!(out, "Fallback list for the default font:")?;
+    for (idx, parsed) in font.handles.borrow().iter().enumerate() {
+        let origin = if idx >= font.configured_handles {
+            "discovered"
+        } else if parsed.is_built_in_fallback {
+            "built-in"
+        } else {
+            "configured"
+        };
+        writeln!(
+            out,
+            "  {idx:>2}: {} ({origin}) {}",
+            parsed.names().full_name,
+            parsed.handle.diagnostic_string()
+        )?;
+    }
+
+    for cluster in clusters {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{:?}: {} presentation, {}, {} cells",
+            cluster.text, cluster.presentation, cluster.direction, cluster.width
+        )?;
+        for glyph in cluster.glyphs {
+            let codepoints: Vec<String> = glyph
+                .text
+                .chars()
+                .map(|c| format!("U+{:04X}", c as u32))
+                .collect();
+            let ligature = if glyph.text.chars().count() > 1 {
+                " (ligature/combined)"
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "  {:?} {} -> font {} {}, glyph {}, {} cell(s), advance {:.1}px: {}{ligature}",
+                glyph.text,
+                codepoints.join(" "),
+                glyph.font_idx,
+                glyph.font_name,
+                glyph.glyph_pos,
+                glyph.num_cells,
+                glyph.x_advance,
+                glyph.source.describe(),
+            )?;
+        }
+    }
+
+    Ok(out)
+}
@@ -157,7 +157,10 @@ impl HarfbuzzShaper {
                 if opt_pair.is_none() {
                     let handle = &self.handles[font_idx];
                     log::trace!("shaper wants {} {:?}", font_idx, handle);
-                    let face = self.lib.face_from_locator(&handle.handle)?;
+                    let mut face = self.lib.face_from_locator(&handle.handle)?;
+                    if let Some(axes) = &handle.variation {
+                        face.set_variation_axes(axes)?;
+                    }
 
                     let font = if USE_OT_FACE {
                         harfbuzz::Font::from_locator(&handle.handle)?
@@ -862,6 +865,7 @@ mod test {
                     harfbuzz_features: None,
                     scale: None,
                     assume_emoji_presentation: None,
+                    variation: None,
                 },
                 14,
             )
@@ -1,6 +1,6 @@
 use clap::builder::ValueParser;
 use clap::{Parser, ValueHint};
-use config::{GuiPosition, SshParameters};
+use config::{GuiGeometry, GuiPosition, SshParameters};
 use std::ffi::OsString;
 use std::path::PathBuf;
 
@@ -88,6 +88,17 @@ pub struct StartCommand {
     #[arg(long, verbatim_doc_comment)]
     pub position: Option<GuiPosition>,
 
+    /// Override the size for the initial window launched by this process.
+    ///
+    /// --geometry 1400x900             to set the size in pixels
+    /// --geometry 80%x60%              to set the size relative to the main monitor
+    /// --geometry 80%x60%@active       to set the size relative to the active monitor
+    /// --geometry 80%x60%@HDMI-1       to set the size relative to the monitor named HDMI-1
+    ///
+    /// This takes precedence over the initial_geometry configuration option.
+    #[arg(long, verbatim_doc_comment)]
+    pub geometry: Option<GuiGeometry>,
+
     /// Name of the multiplexer domain section from the configuration
     /// to which you'd like to connect. If omitted, the default domain
     /// will be used.
@@ -101,6 +112,13 @@ pub struct StartCommand {
     #[arg(long, requires = "domain")]
     pub attach: bool,
 
+    /// Rebuild the windows, tabs and panes that were open when
+    /// kaku last ran, rather than spawning PROG.  The gui-startup
+    /// event is still emitted.
+    /// See the `session_save_interval_seconds` config option.
+    #[arg(long, conflicts_with_all = ["attach", "prog"])]
+    pub restore_session: bool,
+
     /// Instead of executing your shell, run PROG.
     /// For example: `wezterm start -- bash -l` will spawn bash
     /// as if it were a login shell. [aliases: -e]
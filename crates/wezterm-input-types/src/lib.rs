@@ -1278,6 +1278,9 @@ pub struct MouseEvent {
     pub screen_coords: crate::ScreenPoint,
     pub mouse_buttons: MouseButtons,
     pub modifiers: Modifiers,
+    /// For wheel events, true if the device reports precise deltas,
+    /// as trackpads do, rather than discrete steps like a mouse wheel
+    pub precise_scroll: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,84 @@
+//! Adds a tls domain for each mux server that is found on the local
+//! network via mDNS, while `mdns_discovery` is enabled.  The domains are
+//! named `mdns:NAME` and pin the certificate of their server on first use.
+//! Domains are never removed, as a server that stops answering may
+//! still have panes attached.
+//! This is the browsing side only: kaku doesn't advertise its own mux
+//! server, so servers must be published by the system mDNS responder,
+//! for example `avahi-publish -s NAME _kaku-mux._tcp PORT`.
+use anyhow::Context;
+use config::configuration;
+use mux::domain::Domain;
+use mux::Mux;
+use std::sync::Arc;
+use std::time::Duration;
+use wezterm_client::domain::{ClientDomain, ClientDomainConfig};
+use wezterm_client::mdns::{browse, DiscoveredServer, DOMAIN_PREFIX};
+
+/// How long to collect answers to each query
+const BROWSE_TIMEOUT: Duration = Duration::from_secs(2);
+const BROWSE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Must be called on the mux thread
+fn add_domains(servers: Vec<DiscoveredServer>) {
+    let mux = Mux::get();
+    for server in servers {
+        if mux.get_domain_by_name(&server.domain_name()).is_some() {
+            continue;
+        }
+        log::info!(
+            "discovered mux server {} at {}",
+            server.name,
+            server.remote_address()
+        );
+        let domain: Arc<dyn Domain> = Arc::new(ClientDomain::new(ClientDomainConfig::Tls(
+            server.tls_client(),
+        )));
+        mux.add_domain(&domain);
+    }
+}
+
+/// Spawns a thread that periodically browses for mux servers, if
+/// `mdns_discovery` is enabled.  Nothing is spawned otherwise, so
+/// enabling it takes effect on the next start; disabling it pauses
+/// browsing straight away.
+pub fn start() -> anyhow::Result<()> {
+    if !configuration().mdns_discovery {
+        return Ok(());
+    }
+    std::thread::Builder::new()
+        .name("mdns-discovery".into())
+        .spawn(|| loop {
+            if configuration().mdns_discovery {
+                match browse(BROWSE_TIMEOUT) {
+                    Ok(servers) => {
+                        promise::spawn::spawn_into_main_thread(async move {
+                            add_domains(servers);
+                        })
+                        .detach();
+                    }
+                    Err(err) => log::warn!("browsing for mux servers: {:#}", err),
+                }
+            }
+            std::thread::sleep(BROWSE_INTERVAL);
+        })
+        .context("spawning mdns-discovery thread")?;
+    Ok(())
+}
+
+/// Resolves `name` to the domain of a discovered server, browsing for
+/// it if it hasn't been found yet.  This happens regardless of
+/// `mdns_discovery`, as naming such a domain asks for it explicitly.
+/// Must be called on the mux thread.
+pub async fn discover_domain(name: &str) -> anyhow::Result<Option<Arc<dyn Domain>>> {
+    if !name.starts_with(DOMAIN_PREFIX) {
+        return Ok(None);
+    }
+    let mux = Mux::get();
+    if let Some(domain) = mux.get_domain_by_name(name) {
+        return Ok(Some(domain));
+    }
+    let servers = smol::unblock(|| browse(BROWSE_TIMEOUT)).await?;
+    add_domains(servers);
+    Ok(mux.get_domain_by_name(name))
+}
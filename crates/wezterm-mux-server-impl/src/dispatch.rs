@@ -141,6 +141,26 @@ where
                 .await?;
                 stream.flush().await.context("flushing PDU to client")?;
             }
+            Ok(Item::Notif(MuxNotification::ClipboardSynced {
+                origin,
+                selection,
+                clipboard,
+            })) => {
+                if !mux::client::should_relay_clipboard(origin.as_ref(), handler.client_id())
+                    || !config::configuration()
+                        .clipboard_sync
+                        .relays(clipboard.len())
+                {
+                    continue;
+                }
+                Pdu::SyncClipboard(codec::SyncClipboard {
+                    clipboard,
+                    selection,
+                })
+                .encode_async(&mut stream, 0)
+                .await?;
+                stream.flush().await.context("flushing PDU to client")?;
+            }
             Ok(Item::Notif(MuxNotification::TabAddedToWindow { tab_id, window_id })) => {
                 Pdu::TabAddedToWindow(codec::TabAddedToWindow { tab_id, window_id })
                     .encode_async(&mut stream, 0)
@@ -204,6 +224,11 @@ where
             }
             Ok(Item::Notif(MuxNotification::ActiveWorkspaceChanged(_))) => {}
             Ok(Item::Notif(MuxNotification::Empty)) => {}
+            Ok(Item::Notif(MuxNotification::ClientAttached(_)))
+            | Ok(Item::Notif(MuxNotification::ClientDetached(_)))
+            | Ok(Item::Notif(MuxNotification::PaneProcessExited { .. }))
+            | Ok(Item::Notif(MuxNotification::PaneMonitorTriggered { .. }))
+            | Ok(Item::Notif(MuxNotification::TlsCertificateExpiring { .. })) => {}
             Err(err) => {
                 log::error!("process_async Err {}", err);
                 return Ok(());
@@ -5,8 +5,10 @@ use mux::Mux;
 use std::sync::Arc;
 use wezterm_client::domain::{ClientDomain, ClientDomainConfig};
 
+pub mod discovered_domains;
 pub mod dispatch;
 pub mod local;
+pub mod notifier;
 pub mod pki;
 pub mod sessionhandler;
 
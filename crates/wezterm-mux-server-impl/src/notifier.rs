@@ -0,0 +1,277 @@
+//! Delivers noteworthy mux server events to the notification sinks
+//! configured via `daemon_options.notification_sinks`.
+//! Events are collected from the mux notification stream on the main
+//! thread and handed off to a dedicated thread for delivery, so that
+//! a slow or unreachable sink cannot stall the mux.
+use anyhow::{anyhow, Context};
+use config::{configuration, MuxNotificationSink, MuxNotificationTarget, MuxServerEvent};
+use http_req::request::{Method, Request};
+use http_req::uri::Uri;
+use mux::{Mux, MuxNotification};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use wezterm_term::Alert;
+
+#[derive(Debug, Clone)]
+struct EventReport {
+    event: MuxServerEvent,
+    title: String,
+    message: String,
+}
+
+impl EventReport {
+    fn from_notification(notif: &MuxNotification) -> Option<Self> {
+        match notif {
+            MuxNotification::ClientAttached(client_id) => Some(Self {
+                event: MuxServerEvent::ClientAttached,
+                title: "Client attached".to_string(),
+                message: format!(
+                    "{}@{} (pid {}) attached",
+                    client_id.username, client_id.hostname, client_id.pid
+                ),
+            }),
+            MuxNotification::ClientDetached(client_id) => Some(Self {
+                event: MuxServerEvent::ClientDetached,
+                title: "Client detached".to_string(),
+                message: format!(
+                    "{}@{} (pid {}) detached",
+                    client_id.username, client_id.hostname, client_id.pid
+                ),
+            }),
+            MuxNotification::Alert {
+                pane_id,
+                alert: Alert::Bell,
+            } if no_clients_attached() => Some(Self {
+                event: MuxServerEvent::BellInDetachedPane,
+                title: "Bell".to_string(),
+                message: format!("Bell rang in {}", describe_pane(*pane_id)),
+            }),
+            MuxNotification::PaneProcessExited { pane_id, status } => Some(Self {
+                event: MuxServerEvent::PaneProcessExited,
+                title: "Process exited".to_string(),
+                message: format!("Process in {} exited: {}", describe_pane(*pane_id), status),
+            }),
+            _ => None,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::json!({
+            "event": format!("{:?}", self.event),
+            "hostname": hostname(),
+            "title": self.title,
+            "message": self.message,
+        })
+        .to_string()
+    }
+}
+
+fn hostname() -> String {
+    hostname::get()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// Returns true if the only client known to the mux is the
+/// mux itself, which is the case for a headless server that
+/// nobody is currently attached to.
+fn no_clients_attached() -> bool {
+    let mux = Mux::get();
+    let identity = mux.active_identity();
+    mux.iter_clients()
+        .iter()
+        .all(|info| Some(&info.client_id) == identity.as_ref())
+}
+
+fn describe_pane(pane_id: mux::pane::PaneId) -> String {
+    match Mux::get().get_pane(pane_id) {
+        Some(pane) => format!("pane {} ({})", pane_id, pane.get_title()),
+        None => format!("pane {}", pane_id),
+    }
+}
+
+fn post(
+    url: &str,
+    content_type: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> anyhow::Result<()> {
+    let uri = Uri::try_from(url).map_err(|e| anyhow!("invalid url {url}: {e}"))?;
+    let mut response_body = Vec::new();
+    let mut request = Request::new(&uri);
+    request
+        .method(Method::POST)
+        .header("User-Agent", &format!("kaku/{}", config::wezterm_version()))
+        .header("Content-Type", content_type)
+        .header("Content-Length", &body.len());
+    for (name, value) in headers {
+        request.header(name, value);
+    }
+    let response = request
+        .body(body)
+        .send(&mut response_body)
+        .map_err(|e| anyhow!("POST {url} failed: {e}"))?;
+    if !response.status_code().is_success() {
+        anyhow::bail!(
+            "POST {url} failed: {} {}",
+            response.status_code(),
+            response.reason()
+        );
+    }
+    Ok(())
+}
+
+fn sendmail(sendmail: &std::path::Path, to: &str, report: &EventReport) -> anyhow::Result<()> {
+    let mut child = Command::new(sendmail)
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("spawning {}", sendmail.display()))?;
+    {
+        let mut stdin = child.stdin.take().context("sendmail stdin")?;
+        write!(
+            stdin,
+            "To: {to}\nSubject: [kaku@{}] {}\n\n{}\n",
+            hostname(),
+            report.title,
+            report.message
+        )?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {status}", sendmail.display());
+    }
+    Ok(())
+}
+
+fn ntfy_url(server: &str, topic: &str) -> String {
+    format!("{}/{}", server.trim_end_matches('/'), topic)
+}
+
+fn deliver(sink: &MuxNotificationSink, report: &EventReport) -> anyhow::Result<()> {
+    match &sink.target {
+        MuxNotificationTarget::Webhook { url } => {
+            post(url, "application/json", &[], report.to_json().as_bytes())
+        }
+        MuxNotificationTarget::Ntfy {
+            server,
+            topic,
+            token,
+        } => {
+            let url = ntfy_url(server, topic);
+            let auth = token.as_ref().map(|token| format!("Bearer {token}"));
+            let mut headers = vec![("Title", report.title.as_str())];
+            if let Some(auth) = &auth {
+                headers.push(("Authorization", auth.as_str()));
+            }
+            post(&url, "text/plain", &headers, report.message.as_bytes())
+        }
+        MuxNotificationTarget::Email { to, sendmail: prog } => sendmail(prog, to, report),
+    }
+}
+
+/// Subscribe to the mux and start delivering events to the
+/// configured notification sinks.
+/// The set of sinks is re-read from the configuration for each
+/// event, so changes are picked up on config reload.
+pub fn start() -> anyhow::Result<()> {
+    let (tx, rx) = smol::channel::unbounded::<EventReport>();
+
+    std::thread::Builder::new()
+        .name("mux-notifier".into())
+        .spawn(move || {
+            while let Ok(report) = smol::block_on(rx.recv()) {
+                let config = configuration();
+                for sink in &config.daemon_options.notification_sinks {
+                    if !sink.wants(report.event) {
+                        continue;
+                    }
+                    if let Err(err) = deliver(sink, &report) {
+                        log::warn!("delivering {:?} notification: {:#}", report.event, err);
+                    }
+                }
+            }
+        })
+        .context("spawning mux-notifier thread")?;
+
+    Mux::get().subscribe(move |notif| {
+        if configuration().daemon_options.notification_sinks.is_empty() {
+            return true;
+        }
+        match EventReport::from_notification(&notif) {
+            Some(report) => tx.try_send(report).is_ok(),
+            None => true,
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mux::client::ClientId;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn client_id() -> Arc<ClientId> {
+        Arc::new(ClientId {
+            hostname: "laptop".to_string(),
+            username: "wez".to_string(),
+            pid: 42,
+            epoch: 0,
+            id: 0,
+            ssh_auth_sock: None,
+        })
+    }
+
+    #[test]
+    fn client_reports() {
+        let report =
+            EventReport::from_notification(&MuxNotification::ClientAttached(client_id())).unwrap();
+        assert_eq!(report.event, MuxServerEvent::ClientAttached);
+        assert_eq!(report.message, "wez@laptop (pid 42) attached");
+
+        let report =
+            EventReport::from_notification(&MuxNotification::ClientDetached(client_id())).unwrap();
+        assert_eq!(report.event, MuxServerEvent::ClientDetached);
+        assert_eq!(report.title, "Client detached");
+
+        assert!(EventReport::from_notification(&MuxNotification::Empty).is_none());
+    }
+
+    #[test]
+    fn report_json() {
+        let report =
+            EventReport::from_notification(&MuxNotification::ClientAttached(client_id())).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["event"], "ClientAttached");
+        assert_eq!(json["title"], "Client attached");
+        assert_eq!(json["message"], "wez@laptop (pid 42) attached");
+        assert_eq!(json["hostname"], hostname());
+    }
+
+    #[test]
+    fn sink_event_filter() {
+        let mut sink = MuxNotificationSink {
+            target: MuxNotificationTarget::Email {
+                to: "ops@example.com".to_string(),
+                sendmail: PathBuf::from("/usr/sbin/sendmail"),
+            },
+            events: vec![],
+        };
+        assert!(sink.wants(MuxServerEvent::PaneProcessExited));
+
+        sink.events = vec![MuxServerEvent::BellInDetachedPane];
+        assert!(sink.wants(MuxServerEvent::BellInDetachedPane));
+        assert!(!sink.wants(MuxServerEvent::ClientAttached));
+    }
+
+    #[test]
+    fn ntfy_topic_url() {
+        assert_eq!(ntfy_url("https://ntfy.sh", "kaku"), "https://ntfy.sh/kaku");
+        assert_eq!(ntfy_url("https://ntfy.sh/", "kaku"), "https://ntfy.sh/kaku");
+    }
+}
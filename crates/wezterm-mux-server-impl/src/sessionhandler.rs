@@ -1,9 +1,10 @@
 use crate::PKI;
 use anyhow::{anyhow, Context};
 use codec::*;
+use config::keyassignment::ScrollbackExportFormat;
 use config::TermConfig;
 use mux::client::ClientId;
-use mux::domain::SplitSource;
+use mux::domain::{DomainState, SplitSource};
 use mux::pane::{CachePolicy, Pane, PaneId};
 use mux::renderable::{RenderableDimensions, StableCursorPosition};
 use mux::tab::TabId;
@@ -222,6 +223,10 @@ impl SessionHandler {
         }
     }
 
+    pub(crate) fn client_id(&self) -> Option<&Arc<ClientId>> {
+        self.client_id.as_ref()
+    }
+
     pub(crate) fn per_pane(&mut self, pane_id: PaneId) -> Arc<Mutex<PerPane>> {
         Arc::clone(
             self.per_pane
@@ -385,6 +390,30 @@ impl SessionHandler {
                 })
                 .detach();
             }
+            Pdu::ListDomains(ListDomains) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let mux = Mux::get();
+                            let default_domain_id = mux.default_domain().domain_id();
+                            let domains = mux
+                                .iter_domains()
+                                .into_iter()
+                                .map(|domain| DomainInfo {
+                                    domain_id: domain.domain_id(),
+                                    name: domain.domain_name().to_string(),
+                                    attached: domain.state() == DomainState::Attached,
+                                    detachable: domain.detachable(),
+                                    is_default: domain.domain_id() == default_domain_id,
+                                })
+                                .collect();
+                            Ok(Pdu::ListDomainsResponse(ListDomainsResponse { domains }))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
             Pdu::ListPanes(ListPanes {}) => {
                 spawn_into_main_thread(async move {
                     catch(
@@ -725,6 +754,14 @@ impl SessionHandler {
                 .detach();
             }
 
+            Pdu::ApplyLayout(request) => {
+                let client_id = self.client_id.clone();
+                spawn_into_main_thread(async move {
+                    schedule_apply_layout(request, send_response, client_id);
+                })
+                .detach();
+            }
+
             Pdu::MovePaneToNewTab(request) => {
                 let client_id = self.client_id.clone();
                 spawn_into_main_thread(async move {
@@ -987,6 +1024,194 @@ impl SessionHandler {
                 .detach();
             }
 
+            Pdu::SetPaneSize(SetPaneSize {
+                pane_id,
+                cols,
+                rows,
+            }) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let mux = Mux::get();
+                            let (_pane_domain_id, _window_id, tab_id) = mux
+                                .resolve_pane_id(pane_id)
+                                .ok_or_else(|| anyhow!("pane_id {} invalid", pane_id))?;
+                            let tab = mux
+                                .get_tab(tab_id)
+                                .ok_or_else(|| anyhow!("no such tab {}", tab_id))?;
+                            tab.set_pane_size(pane_id, cols, rows);
+                            Ok(Pdu::UnitResponse(UnitResponse {}))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
+
+            Pdu::GetPaneSemanticText(GetPaneSemanticText {
+                pane_id,
+                semantic_type,
+            }) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let mux = Mux::get();
+                            let pane = mux
+                                .get_pane(pane_id)
+                                .ok_or_else(|| anyhow!("no such pane {}", pane_id))?;
+                            let zones = mux::pane_text::get_semantic_text(&*pane, semantic_type)?;
+                            Ok(Pdu::GetPaneSemanticTextResponse(
+                                GetPaneSemanticTextResponse { zones },
+                            ))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
+
+            Pdu::GetPaneRegionText(GetPaneRegionText {
+                pane_id,
+                x,
+                y,
+                width,
+                height,
+            }) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let mux = Mux::get();
+                            let pane = mux
+                                .get_pane(pane_id)
+                                .ok_or_else(|| anyhow!("no such pane {}", pane_id))?;
+                            let text = mux::pane_text::get_region_text(&*pane, x, y, width, height);
+                            Ok(Pdu::GetPaneRegionTextResponse(GetPaneRegionTextResponse {
+                                text,
+                            }))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
+
+            Pdu::WaitForPaneText(WaitForPaneText {
+                pane_id,
+                regex,
+                timeout_ms,
+            }) => {
+                spawn_into_main_thread(async move {
+                    promise::spawn::spawn(async move {
+                        let result = mux::pane_text::wait_for_text(
+                            pane_id,
+                            &regex,
+                            std::time::Duration::from_millis(timeout_ms),
+                        )
+                        .await
+                        .map(|matched| {
+                            Pdu::WaitForPaneTextResponse(WaitForPaneTextResponse { matched })
+                        });
+                        send_response(result);
+                    })
+                    .detach();
+                })
+                .detach();
+            }
+
+            Pdu::SyncClipboard(SyncClipboard {
+                clipboard,
+                selection,
+            }) => {
+                let origin = self.client_id.clone();
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let config = config::configuration();
+                            let sync = &config.clipboard_sync;
+                            if !sync.send && !sync.receive {
+                                anyhow::bail!("clipboard_sync is disabled on this server");
+                            }
+                            if !sync.relays(clipboard.len()) {
+                                anyhow::bail!(
+                                    "clipboard of {} bytes exceeds clipboard_sync.max_bytes",
+                                    clipboard.len()
+                                );
+                            }
+                            Mux::get().notify(MuxNotification::ClipboardSynced {
+                                origin,
+                                selection,
+                                clipboard,
+                            });
+                            Ok(Pdu::UnitResponse(UnitResponse {}))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
+
+            Pdu::ActivateConfigProfile(ActivateConfigProfile { name }) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            config::activate_config_profile(Some(&name))?;
+                            Ok(Pdu::UnitResponse(UnitResponse {}))
+                        },
+                        send_response,
+                    );
+                })
+                .detach();
+            }
+
+            Pdu::ExportScrollback(ExportScrollback { pane_id, format }) => {
+                async fn do_export(
+                    pane_id: PaneId,
+                    format: ScrollbackExportFormat,
+                ) -> anyhow::Result<Pdu> {
+                    let mux = Mux::get();
+                    let pane = mux
+                        .get_pane(pane_id)
+                        .ok_or_else(|| anyhow!("no such pane {}", pane_id))?;
+
+                    let text = pane.export_scrollback(format).await?;
+                    Ok(Pdu::ExportScrollbackResponse(ExportScrollbackResponse {
+                        text,
+                    }))
+                }
+
+                spawn_into_main_thread(async move {
+                    promise::spawn::spawn(async move {
+                        let result = do_export(pane_id, format).await;
+                        send_response(result);
+                    })
+                    .detach();
+                })
+                .detach();
+            }
+
+            Pdu::SetPaneRecording(SetPaneRecording { pane_id, recording }) => {
+                spawn_into_main_thread(async move {
+                    catch(
+                        move || {
+                            let mux = Mux::get();
+                            let pane = mux
+                                .get_pane(pane_id)
+                                .ok_or_else(|| anyhow!("no such pane {}", pane_id))?;
+                            let path = if recording {
+                                Some(mux::recording::start_recording(&*pane)?)
+                            } else {
+                                mux::recording::stop_recording(pane_id)
+                            };
+                            Ok(Pdu::SetPaneRecordingResponse(SetPaneRecordingResponse {
+                                path,
+                            }))
+                        },
+                        send_response,
+                    )
+                })
+                .detach();
+            }
+
             Pdu::Invalid { .. } => send_response(Err(anyhow!("invalid PDU {:?}", decoded.pdu))),
             Pdu::Pong { .. }
             | Pdu::ListPanesResponse { .. }
@@ -998,11 +1223,17 @@ impl SessionHandler {
             | Pdu::LivenessResponse { .. }
             | Pdu::GetPaneDirectionResponse { .. }
             | Pdu::SearchScrollbackResponse { .. }
+            | Pdu::GetPaneSemanticTextResponse { .. }
+            | Pdu::GetPaneRegionTextResponse { .. }
+            | Pdu::ExportScrollbackResponse { .. }
+            | Pdu::SetPaneRecordingResponse { .. }
+            | Pdu::WaitForPaneTextResponse { .. }
             | Pdu::GetLinesResponse { .. }
             | Pdu::GetCodecVersionResponse { .. }
             | Pdu::WindowWorkspaceChanged { .. }
             | Pdu::GetTlsCredsResponse { .. }
             | Pdu::GetClientListResponse { .. }
+            | Pdu::ListDomainsResponse { .. }
             | Pdu::PaneRemoved { .. }
             | Pdu::PaneFocused { .. }
             | Pdu::TabResized { .. }
@@ -1069,6 +1300,37 @@ async fn split_pane(split: SplitPane, client_id: Option<Arc<ClientId>>) -> anyho
     }))
 }
 
+fn schedule_apply_layout<SND>(
+    request: ApplyLayout,
+    send_response: SND,
+    client_id: Option<Arc<ClientId>>,
+) where
+    SND: Fn(anyhow::Result<Pdu>) + 'static,
+{
+    promise::spawn::spawn(async move { send_response(apply_layout(request, client_id).await) })
+        .detach();
+}
+
+async fn apply_layout(
+    request: ApplyLayout,
+    client_id: Option<Arc<ClientId>>,
+) -> anyhow::Result<Pdu> {
+    let mux = Mux::get();
+    let _identity = mux.with_identity(client_id);
+
+    let layout = mux::layout::get_layout(&request.name)?;
+    let (tab, pane_id, window_id) =
+        mux::layout::apply_layout(&layout, request.window_id, request.size, request.workspace)
+            .await?;
+
+    Ok::<Pdu, anyhow::Error>(Pdu::SpawnResponse(SpawnResponse {
+        pane_id,
+        tab_id: tab.tab_id(),
+        window_id,
+        size: tab.get_size(),
+    }))
+}
+
 async fn domain_spawn_v2(spawn: SpawnV2, client_id: Option<Arc<ClientId>>) -> anyhow::Result<Pdu> {
     let mux = Mux::get();
     let _identity = mux.with_identity(client_id);
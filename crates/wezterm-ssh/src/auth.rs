@@ -63,9 +63,9 @@ impl crate::sessioninner::SessionInner {
         use std::path::{Path, PathBuf};
 
         if let Some(files) = self.config.get("identityfile") {
-            for file in files.split_whitespace() {
+            for file in crate::config::split_identity_files(files) {
                 let pubkey: PathBuf = format!("{}.pub", file).into();
-                let file = Path::new(file);
+                let file = Path::new(&file);
 
                 if !file.exists() {
                     continue;
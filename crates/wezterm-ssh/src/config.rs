@@ -5,6 +5,41 @@ use std::path::{Path, PathBuf};
 
 pub type ConfigMap = BTreeMap<String, String>;
 
+/// Returns the list of files held in the whitespace separated
+/// `identityfile` value of a ConfigMap.  Entries that were added by
+/// `join_identity_files` may be double quoted, to allow for paths
+/// containing spaces.
+pub fn split_identity_files(value: &str) -> Vec<String> {
+    let mut files = vec![];
+    let mut rest = value.trim_start();
+    while !rest.is_empty() {
+        let (file, remainder) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        files.push(file.to_string());
+        rest = remainder.trim_start();
+    }
+    files
+}
+
+/// Produces an `identityfile` value holding `files`, quoting any that
+/// contain whitespace so that `split_identity_files` returns them intact
+pub fn join_identity_files<S: AsRef<str>>(files: &[S]) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let file = file.as_ref();
+            if file.contains(char::is_whitespace) {
+                format!("\"{}\"", file)
+            } else {
+                file.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// A Pattern in a `Host` list
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Pattern {
@@ -1114,6 +1149,25 @@ Config {
         );
     }
 
+    #[test]
+    fn identity_file_lists() {
+        let files = vec![
+            "/home/me/.ssh/id_rsa".to_string(),
+            "/Users/me/Library/Mobile Documents/id_ed25519".to_string(),
+        ];
+        let joined = join_identity_files(&files);
+        assert_eq!(
+            joined,
+            "/home/me/.ssh/id_rsa \"/Users/me/Library/Mobile Documents/id_ed25519\""
+        );
+        assert_eq!(split_identity_files(&joined), files);
+        assert_eq!(
+            split_identity_files(" /a  /b\t/c "),
+            vec!["/a".to_string(), "/b".to_string(), "/c".to_string()]
+        );
+        assert!(split_identity_files("").is_empty());
+    }
+
     #[test]
     fn multiple_identityfile() {
         let mut config = Config::new();
@@ -185,8 +185,8 @@ impl SessionInner {
             sess.set_option(libssh_rs::SshOption::IdentityAgent(Some(agent.clone())))?;
         }
         if let Some(files) = self.config.get("identityfile") {
-            for file in files.split_whitespace() {
-                sess.set_option(libssh_rs::SshOption::AddIdentity(file.to_string()))?;
+            for file in crate::config::split_identity_files(files) {
+                sess.set_option(libssh_rs::SshOption::AddIdentity(file))?;
             }
         }
         if let Some(kh) = self.config.get("userknownhostsfile") {
@@ -10,6 +10,7 @@ use fancy_regex::{Captures, Regex};
 #[cfg(feature = "use_serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use wezterm_dynamic::{FromDynamic, FromDynamicOptions, ToDynamic, Value};
+use wezterm_input_types::Modifiers;
 
 extern crate alloc;
 use crate::alloc::borrow::ToOwned;
@@ -59,6 +60,60 @@ pub struct Rule {
     /// Which capture to highlight
     #[dynamic(default)]
     pub highlight: usize,
+
+    /// The modifiers that must be held when clicking to activate the
+    /// links made by this rule.  By default, any click activates them.
+    /// The default mouse bindings only open links on a click without
+    /// modifiers, so a rule that sets this needs a mouse binding that
+    /// performs `OpenLinkAtMouseCursor` with the same modifiers.
+    #[cfg_attr(feature = "use_serde", serde(skip))]
+    #[dynamic(default, into = "String", try_from = "String")]
+    pub mods: Modifiers,
+
+    /// An event to emit in place of opening the links made by this
+    /// rule when they are activated
+    #[cfg_attr(feature = "use_serde", serde(skip))]
+    #[dynamic(default)]
+    pub handler: Option<RuleHandler>,
+}
+
+/// Links made by rules with `mods` or a `handler` carry them in these
+/// parameters, along with the matched text, so that they are known
+/// when the link is clicked
+pub const RULE_MODS_PARAM: &str = "rule_mods";
+pub const RULE_HANDLER_PARAM: &str = "rule_handler";
+pub const RULE_TEXT_PARAM: &str = "rule_text";
+
+/// The name of the event emitted by a rule's handler.  It can be
+/// given either as the name of an event registered with `wezterm.on`,
+/// or as the action returned by `wezterm.action_callback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleHandler(pub String);
+
+impl FromDynamic for RuleHandler {
+    fn from_dynamic(
+        value: &Value,
+        options: FromDynamicOptions,
+    ) -> Result<RuleHandler, wezterm_dynamic::Error> {
+        if let Value::Object(obj) = value {
+            if let Some(name) = obj.get_by_str("EmitEvent") {
+                return Ok(RuleHandler(String::from_dynamic(name, options)?));
+            }
+        }
+        match String::from_dynamic(value, options) {
+            Ok(name) => Ok(RuleHandler(name)),
+            Err(_) => Err(wezterm_dynamic::Error::Message(format!(
+                "expected an event name or wezterm.action_callback, but got {}",
+                value.variant_name()
+            ))),
+        }
+    }
+}
+
+impl ToDynamic for RuleHandler {
+    fn to_dynamic(&self) -> Value {
+        self.0.to_dynamic()
+    }
 }
 
 struct RegexWrap(Regex);
@@ -179,6 +234,8 @@ impl Rule {
             regex: Regex::new(regex)?,
             format: format.to_owned(),
             highlight,
+            mods: Modifiers::NONE,
+            handler: None,
         })
     }
 
@@ -205,9 +262,18 @@ impl Rule {
             .into_iter()
             .map(|m| {
                 let url = m.expand();
-                let link = Arc::new(Hyperlink::new_implicit(url));
+                let mut link = Hyperlink::new_implicit(url);
+                if m.rule.mods != Modifiers::NONE {
+                    link.set_param(RULE_MODS_PARAM, m.rule.mods.to_string());
+                }
+                if let Some(handler) = &m.rule.handler {
+                    link.set_param(RULE_HANDLER_PARAM, handler.0.clone());
+                    if let Some(text) = m.captures.get(0) {
+                        link.set_param(RULE_TEXT_PARAM, text.as_str());
+                    }
+                }
                 RuleMatch {
-                    link,
+                    link: Arc::new(link),
                     range: m.range(),
                 }
             })
@@ -250,6 +316,19 @@ mod test {
         );
     }
 
+    #[test]
+    fn rule_params() {
+        let mut rule = Rule::new(r"(\w+\.rs):(\d+)", "file://$1").unwrap();
+        rule.mods = Modifiers::SUPER;
+        rule.handler = Some(RuleHandler("open-in-editor".to_string()));
+
+        let link = &Rule::match_hyperlinks("at main.rs:12", &[rule])[0].link;
+        assert_eq!(link.uri(), "file://main.rs");
+        assert_eq!(link.params()[RULE_MODS_PARAM], "SUPER");
+        assert_eq!(link.params()[RULE_HANDLER_PARAM], "open-in-editor");
+        assert_eq!(link.params()[RULE_TEXT_PARAM], "main.rs:12");
+    }
+
     #[test]
     fn parse_with_parentheses() {
         fn assert_helper(test_uri: &str, expected_uri: &str, msg: &str) {
@@ -99,6 +99,29 @@ fn hyperlinks() {
     );
 }
 
+#[test]
+fn wrapped_hyperlink() {
+    let rules = vec![Rule::new(r"\b\w+://(?:[\w.-]+)\.[a-z]{2,15}\S*\b", "$0").unwrap()];
+
+    let mut line1: Line = "see https://exam".into();
+    line1.set_last_cell_was_wrapped(true, SEQ_ZERO);
+    let mut line2: Line = "ple.com/a/b ok".into();
+
+    Line::apply_hyperlink_rules(&rules, &mut [&mut line1, &mut line2]);
+
+    let link = |line: &Line, idx: usize| {
+        line.get_cell(idx)
+            .and_then(|cell| cell.attrs().hyperlink().map(|link| link.uri().to_string()))
+    };
+    let expected = Some("https://example.com/a/b".to_string());
+    assert_eq!(link(&line1, 3), None);
+    assert_eq!(link(&line1, 4), expected);
+    assert_eq!(link(&line2, 10), expected);
+    assert_eq!(link(&line2, 12), None);
+    assert!(line1.last_cell_was_wrapped());
+    assert!(!line2.last_cell_was_wrapped());
+}
+
 #[test]
 fn double_click_range_bounds() {
     let line: Line = "hello".into();
@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 mod macos;
 
 #[derive(Debug, Clone)]
@@ -6,6 +8,25 @@ pub struct ToastNotification {
     pub message: String,
     pub url: Option<String>,
     pub timeout: Option<std::time::Duration>,
+    /// Passed to the handler registered by `set_activation_handler`
+    /// when the notification is clicked
+    pub activation_token: Option<String>,
+}
+
+static ACTIVATION_HANDLER: OnceLock<fn(String)> = OnceLock::new();
+
+/// Sets the function that is called with the `activation_token` of a
+/// notification when it is clicked.  It may be called on any thread.
+pub fn set_activation_handler(handler: fn(String)) {
+    if ACTIVATION_HANDLER.set(handler).is_err() {
+        log::warn!("notification activation handler was already set");
+    }
+}
+
+fn activate(token: String) {
+    if let Some(handler) = ACTIVATION_HANDLER.get() {
+        handler(token);
+    }
 }
 
 impl ToastNotification {
@@ -28,6 +49,7 @@ pub fn persistent_toast_notification_with_click_to_open_url(title: &str, message
         message: message.to_string(),
         url: Some(url.to_string()),
         timeout: None,
+        activation_token: None,
     });
 }
 
@@ -37,6 +59,7 @@ pub fn persistent_toast_notification(title: &str, message: &str) {
         message: message.to_string(),
         url: None,
         timeout: None,
+        activation_token: None,
     });
 }
 
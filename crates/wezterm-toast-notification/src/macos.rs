@@ -10,8 +10,9 @@ use objc2_foundation::{ns_string, NSArray, NSDictionary, NSError, NSSet, NSStrin
 use objc2_user_notifications::{
     UNAuthorizationOptions, UNMutableNotificationContent, UNNotification, UNNotificationAction,
     UNNotificationActionOptions, UNNotificationCategory, UNNotificationCategoryOptions,
-    UNNotificationPresentationOptions, UNNotificationRequest, UNNotificationResponse,
-    UNUserNotificationCenter, UNUserNotificationCenterDelegate,
+    UNNotificationDismissActionIdentifier, UNNotificationPresentationOptions,
+    UNNotificationRequest, UNNotificationResponse, UNUserNotificationCenter,
+    UNUserNotificationCenterDelegate,
 };
 use std::sync::{LazyLock, Once};
 
@@ -75,6 +76,15 @@ define_class!(
                 }
             }
 
+            let token = user_info.valueForKey(ns_string!("activate"));
+            if let Some(token) = token {
+                if let Ok(token) = token.downcast::<NSString>() {
+                    if *action != *UNNotificationDismissActionIdentifier {
+                        crate::activate(token.to_string());
+                    }
+                }
+            }
+
             completion_handler.call(());
         }
     }
@@ -158,13 +168,26 @@ pub fn show_notif(toast: ToastNotification) -> Result<(), Box<dyn std::error::Er
         notif.setTitle(&NSString::from_str(&toast.title));
         notif.setBody(&NSString::from_str(&toast.message));
 
-        if let Some(url) = &toast.url {
-            let info =
-                NSDictionary::from_slices(&[ns_string!("url")], &[&*NSString::from_str(url)]);
+        let url = toast.url.as_deref().map(NSString::from_str);
+        let token = toast.activation_token.as_deref().map(NSString::from_str);
+        let mut keys: Vec<&NSString> = vec![];
+        let mut values: Vec<&NSString> = vec![];
+        if let Some(url) = &url {
+            keys.push(ns_string!("url"));
+            values.push(url);
+        }
+        if let Some(token) = &token {
+            keys.push(ns_string!("activate"));
+            values.push(token);
+        }
+        if !keys.is_empty() {
+            let info = NSDictionary::from_slices(&keys, &values);
             notif.setUserInfo(
                 info.downcast_ref::<NSDictionary>()
                     .expect("is NSDictionary"),
             );
+        }
+        if url.is_some() {
             notif.setCategoryIdentifier(ns_string!("SHOW_URL_ACTION"));
         }
 
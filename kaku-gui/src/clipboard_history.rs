@@ -0,0 +1,238 @@
+//! Remembers the text most recently copied in any window, so that it
+//! can be pasted again via `ShowClipboardHistory`.
+//!
+//! When `clipboard_history_persist` is enabled, the history is also
+//! saved beneath `DATA_DIR`, encrypted with AES-256-GCM using a key
+//! that is kept in the login keychain.
+use anyhow::{anyhow, Context};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Copies larger than this are not remembered
+const MAX_ENTRY_BYTES: usize = 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub text: String,
+    /// Seconds since the epoch when the text was copied
+    pub copied_at: u64,
+}
+
+#[derive(Default)]
+struct ClipboardHistory {
+    /// The most recent copy first
+    entries: VecDeque<HistoryEntry>,
+    loaded: bool,
+}
+
+lazy_static! {
+    static ref HISTORY: Mutex<ClipboardHistory> = Mutex::new(ClipboardHistory::default());
+    /// Serializes writes, so that an older snapshot can't replace a newer one
+    static ref SAVE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn file_name() -> PathBuf {
+    config::DATA_DIR.join("clipboard-history")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl ClipboardHistory {
+    fn load_if_needed(&mut self, persist: bool) {
+        if std::mem::replace(&mut self.loaded, true) {
+            return;
+        }
+        if !persist {
+            // Don't leave a history behind once persistence is turned off
+            let _ = std::fs::remove_file(file_name());
+            return;
+        }
+        match load() {
+            Ok(entries) => self.entries = entries,
+            Err(err) => log::warn!("failed to load clipboard history: {err:#}"),
+        }
+    }
+
+    /// Puts `text` at the front, removing any earlier copy of the same
+    /// text, and keeps at most `limit` entries.  Returns false if the
+    /// text isn't worth remembering.
+    fn push(&mut self, text: &str, limit: usize) -> bool {
+        if limit == 0 || text.trim().is_empty() || text.len() > MAX_ENTRY_BYTES {
+            return false;
+        }
+        self.entries.retain(|entry| entry.text != text);
+        self.entries.push_front(HistoryEntry {
+            text: text.to_string(),
+            copied_at: now_secs(),
+        });
+        self.entries.truncate(limit);
+        true
+    }
+}
+
+/// Remembers text that was copied to the clipboard
+pub fn record(text: &str) {
+    let config = config::configuration();
+    let mut history = HISTORY.lock();
+    history.load_if_needed(config.clipboard_history_persist);
+    let recorded = history.push(text, config.clipboard_history_size);
+    drop(history);
+
+    if recorded && config.clipboard_history_persist {
+        std::thread::spawn(|| {
+            let _guard = SAVE_LOCK.lock();
+            let entries = HISTORY.lock().entries.clone();
+            if let Err(err) = save(&entries) {
+                log::warn!("failed to save clipboard history: {err:#}");
+            }
+        });
+    }
+}
+
+/// Returns the remembered copies, the most recent first
+pub fn entries() -> Vec<HistoryEntry> {
+    let config = config::configuration();
+    let mut history = HISTORY.lock();
+    history.load_if_needed(config.clipboard_history_persist);
+    history.entries.truncate(config.clipboard_history_size);
+    history.entries.iter().cloned().collect()
+}
+
+#[cfg(target_os = "macos")]
+fn load_key_bytes() -> anyhow::Result<Vec<u8>> {
+    use security_framework::passwords::{get_generic_password, set_generic_password};
+
+    const SERVICE: &str = "Kaku Clipboard History";
+    const ACCOUNT: &str = "encryption-key";
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    match get_generic_password(SERVICE, ACCOUNT) {
+        Ok(bytes) => Ok(bytes),
+        Err(err) if err.code() == ERR_SEC_ITEM_NOT_FOUND => {
+            let mut bytes = vec![0u8; AES_256_GCM.key_len()];
+            SystemRandom::new()
+                .fill(&mut bytes)
+                .map_err(|_| anyhow!("failed to generate a key"))?;
+            set_generic_password(SERVICE, ACCOUNT, &bytes)
+                .context("storing the key in the keychain")?;
+            Ok(bytes)
+        }
+        Err(err) => Err(err).context("reading the key from the keychain"),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn load_key_bytes() -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("persisting the clipboard history requires the macOS keychain")
+}
+
+fn key() -> anyhow::Result<&'static LessSafeKey> {
+    static KEY: OnceLock<LessSafeKey> = OnceLock::new();
+    if let Some(key) = KEY.get() {
+        return Ok(key);
+    }
+    let bytes = load_key_bytes()?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| anyhow!("the clipboard history key is invalid"))?;
+    Ok(KEY.get_or_init(|| LessSafeKey::new(key)))
+}
+
+/// The file holds a random nonce followed by the sealed JSON
+fn load() -> anyhow::Result<VecDeque<HistoryEntry>> {
+    let data = match std::fs::read(file_name()) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(VecDeque::new()),
+        Err(err) => return Err(err).context("reading clipboard history"),
+    };
+    if data.len() < NONCE_LEN {
+        anyhow::bail!("clipboard history file is truncated");
+    }
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid nonce"))?;
+    let mut sealed = sealed.to_vec();
+    let plain = key()?
+        .open_in_place(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| anyhow!("clipboard history could not be decrypted"))?;
+    Ok(serde_json::from_slice(plain)?)
+}
+
+fn save(entries: &VecDeque<HistoryEntry>) -> anyhow::Result<()> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("failed to generate a nonce"))?;
+    let mut sealed = serde_json::to_vec(entries)?;
+    key()?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut sealed,
+        )
+        .map_err(|_| anyhow!("clipboard history could not be encrypted"))?;
+
+    let mut data = nonce.to_vec();
+    data.append(&mut sealed);
+
+    let path = file_name();
+    config::create_user_owned_dirs(&config::DATA_DIR)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path).context("opening clipboard history")?;
+    std::io::Write::write_all(&mut file, &data).context("writing clipboard history")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(history: &ClipboardHistory) -> Vec<&str> {
+        history
+            .entries
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn push() {
+        let mut history = ClipboardHistory::default();
+        assert!(history.push("one", 3));
+        assert!(history.push("two", 3));
+        assert!(history.push("three", 3));
+        assert_eq!(texts(&history), vec!["three", "two", "one"]);
+
+        // Copying the same text again moves it to the front
+        assert!(history.push("one", 3));
+        assert_eq!(texts(&history), vec!["one", "three", "two"]);
+
+        assert!(history.push("four", 3));
+        assert_eq!(texts(&history), vec!["four", "one", "three"]);
+    }
+
+    #[test]
+    fn push_ignores_unwanted_text() {
+        let mut history = ClipboardHistory::default();
+        assert!(!history.push("  \n", 3));
+        assert!(!history.push("text", 0));
+        assert!(!history.push(&"x".repeat(MAX_ENTRY_BYTES + 1), 3));
+        assert!(history.entries.is_empty());
+    }
+}
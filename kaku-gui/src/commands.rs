@@ -74,10 +74,29 @@ pub struct ExpandedCommand {
     pub doc: Cow<'static, str>,
     pub action: KeyAssignment,
     pub keys: Vec<(Modifiers, KeyCode)>,
-    pub menubar: &'static [&'static str],
+    pub menubar: Vec<Cow<'static, str>>,
     pub icon: Option<Cow<'static, str>>,
 }
 
+impl ExpandedCommand {
+    /// Overrides the derived labels with any that the user attached
+    /// to the key binding for this command
+    fn apply_metadata(&mut self, metadata: &CommandMetadata) {
+        if let Some(brief) = &metadata.brief {
+            self.brief = brief.clone().into();
+        }
+        if let Some(doc) = &metadata.doc {
+            self.doc = doc.clone().into();
+        }
+        if !metadata.menubar.is_empty() {
+            self.menubar = metadata.menubar.iter().cloned().map(Cow::Owned).collect();
+        }
+        if let Some(icon) = &metadata.icon {
+            self.icon = Some(icon.clone().into());
+        }
+    }
+}
+
 impl std::fmt::Debug for CommandDef {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("CommandDef")
@@ -181,7 +200,7 @@ impl CommandDef {
                     doc: def.doc.into(),
                     keys,
                     action,
-                    menubar: def.menubar,
+                    menubar: def.menubar.iter().map(|&s| s.into()).collect(),
                     icon: def.icon.map(Cow::Borrowed),
                 })
             }
@@ -204,22 +223,54 @@ impl CommandDef {
     pub fn actions_for_palette_and_menubar(config: &ConfigHandle) -> Vec<ExpandedCommand> {
         let mut result = Self::expanded_commands(config);
 
-        // Generate some stuff based on the config
-        for cmd in &config.launch_menu {
-            let label = match cmd.label.as_ref() {
-                Some(label) => label.to_string(),
-                None => match cmd.args.as_ref() {
-                    Some(args) => args.join(" "),
-                    None => "(default shell)".to_string(),
-                },
-            };
+        // Generate some stuff based on the config.  Submenus of the
+        // launch_menu become submenus of the Shell menu, and their
+        // entries are prefixed with the submenu labels in the palette.
+        fn push_launch_menu(
+            result: &mut Vec<ExpandedCommand>,
+            items: &[LaunchMenuItem],
+            parents: &[String],
+        ) {
+            for item in items {
+                let label = item.label();
+                let mut path = parents.to_vec();
+                path.push(label.clone());
+                if item.is_submenu() {
+                    push_launch_menu(result, &item.children, &path);
+                    continue;
+                }
+                let mut menubar: Vec<Cow<'static, str>> = vec!["Shell".into()];
+                menubar.extend(parents.iter().map(|label| label.clone().into()));
+                result.push(ExpandedCommand {
+                    brief: format!("{} (New Tab)", path.join(" / ")).into(),
+                    doc: "".into(),
+                    keys: vec![],
+                    action: KeyAssignment::SpawnCommandInNewTab(item.spawn.clone()),
+                    menubar,
+                    icon: Some("md_tab_plus".into()),
+                });
+            }
+        }
+        push_launch_menu(&mut result, &config.launch_menu, &[]);
+
+        let active_profile = config::active_config_profile();
+        let mut profiles: Vec<&str> = config.profiles.keys().map(|name| name.as_str()).collect();
+        profiles.sort();
+        if active_profile.is_some() && !config.profiles.contains_key(config::DEFAULT_CONFIG_PROFILE)
+        {
+            profiles.insert(0, config::DEFAULT_CONFIG_PROFILE);
+        }
+        for name in profiles {
+            if Some(name) == active_profile.as_deref() {
+                continue;
+            }
             result.push(ExpandedCommand {
-                brief: format!("{label} (New Tab)").into(),
+                brief: format!("Activate config profile {name}").into(),
                 doc: "".into(),
                 keys: vec![],
-                action: KeyAssignment::SpawnCommandInNewTab(cmd.clone()),
-                menubar: &["Shell"],
-                icon: Some("md_tab_plus".into()),
+                action: KeyAssignment::ActivateConfigProfile(name.to_string()),
+                menubar: vec![],
+                icon: None,
             });
         }
 
@@ -253,7 +304,7 @@ impl CommandDef {
                                 domain: SpawnTabDomain::DomainName(name.to_string()),
                                 ..SpawnCommand::default()
                             }),
-                            menubar: &["Shell"],
+                            menubar: vec!["Shell".into()],
                             icon: Some("md_tab_plus".into()),
                         });
                     } else {
@@ -262,7 +313,7 @@ impl CommandDef {
                             doc: "".into(),
                             keys: vec![],
                             action: KeyAssignment::AttachDomain(name.to_string()),
-                            menubar: &["Shell", "Attach"],
+                            menubar: vec!["Shell".into(), "Attach".into()],
                             icon: Some("md_pipe".into()),
                         });
                     }
@@ -284,7 +335,7 @@ impl CommandDef {
                         action: KeyAssignment::DetachDomain(SpawnTabDomain::DomainName(
                             name.to_string(),
                         )),
-                        menubar: &["Shell", "Detach"],
+                        menubar: vec!["Shell".into(), "Detach".into()],
                         icon: Some("md_pipe_disconnected".into()),
                     });
                 }
@@ -301,7 +352,7 @@ impl CommandDef {
                             name: Some(workspace.clone()),
                             spawn: None,
                         },
-                        menubar: &["Window", "Workspace"],
+                        menubar: vec!["Window".into(), "Workspace".into()],
                         icon: None,
                     });
                 }
@@ -314,50 +365,69 @@ impl CommandDef {
                     name: None,
                     spawn: None,
                 },
-                menubar: &["Window", "Workspace"],
+                menubar: vec!["Window".into(), "Workspace".into()],
                 icon: None,
             });
         }
 
-        // And sweep to pick up stuff from their key assignments
         let inputmap = InputMap::new(config);
-        for ((keycode, mods), entry) in inputmap.keys.default.iter() {
-            if result
+
+        // Commands registered with wezterm.register_command, shown
+        // with any keys that are bound to the same action
+        for command in config::user_commands().iter() {
+            let keys = inputmap
+                .keys
+                .default
                 .iter()
-                .position(|cmd| cmd.action == entry.action)
-                .is_some()
-            {
+                .filter(|(_, entry)| entry.action == command.action)
+                .map(|((keycode, mods), _)| (*mods, keycode.clone()))
+                .collect();
+            result.push(ExpandedCommand {
+                brief: command.brief.clone().into(),
+                doc: command.doc.clone().unwrap_or_default().into(),
+                keys,
+                action: command.action.clone(),
+                menubar: command.menubar.iter().cloned().map(Cow::Owned).collect(),
+                icon: command.icon.clone().map(Cow::Owned),
+            });
+        }
+
+        // And sweep to pick up stuff from their key assignments
+        for ((keycode, mods), entry) in inputmap.keys.default.iter() {
+            if let Some(cmd) = result.iter_mut().find(|cmd| cmd.action == entry.action) {
+                cmd.apply_metadata(&entry.metadata);
                 continue;
             }
             if let Some(cmd) = derive_command_from_key_assignment(&entry.action) {
-                result.push(ExpandedCommand {
+                let mut cmd = ExpandedCommand {
                     brief: cmd.brief.into(),
                     doc: cmd.doc.into(),
                     keys: vec![(*mods, keycode.clone())],
                     action: entry.action.clone(),
-                    menubar: cmd.menubar,
+                    menubar: cmd.menubar.iter().map(|&s| s.into()).collect(),
                     icon: cmd.icon.map(Cow::Borrowed),
-                });
+                };
+                cmd.apply_metadata(&entry.metadata);
+                result.push(cmd);
             }
         }
         for table in inputmap.keys.by_name.values() {
             for entry in table.values() {
-                if result
-                    .iter()
-                    .position(|cmd| cmd.action == entry.action)
-                    .is_some()
-                {
+                if let Some(cmd) = result.iter_mut().find(|cmd| cmd.action == entry.action) {
+                    cmd.apply_metadata(&entry.metadata);
                     continue;
                 }
                 if let Some(cmd) = derive_command_from_key_assignment(&entry.action) {
-                    result.push(ExpandedCommand {
+                    let mut cmd = ExpandedCommand {
                         brief: cmd.brief.into(),
                         doc: cmd.doc.into(),
                         keys: vec![],
                         action: entry.action.clone(),
-                        menubar: cmd.menubar,
+                        menubar: cmd.menubar.iter().map(|&s| s.into()).collect(),
                         icon: cmd.icon.map(Cow::Borrowed),
-                    });
+                    };
+                    cmd.apply_metadata(&entry.metadata);
+                    result.push(cmd);
                 }
             }
         }
@@ -420,11 +490,11 @@ impl CommandDef {
         commands.retain(|cmd| !cmd.menubar.is_empty());
 
         // Prefer to put the menus in this order
-        let mut order: Vec<&'static str> = vec!["Kaku", "Shell", "Edit", "View", "Window"];
+        let mut order: Vec<&str> = vec!["Kaku", "Shell", "Edit", "View", "Window"];
         // Add any other menus on the end
         for cmd in &commands {
-            if !order.contains(&cmd.menubar[0]) {
-                order.push(cmd.menubar[0]);
+            if !order.contains(&&*cmd.menubar[0]) {
+                order.push(&cmd.menubar[0]);
             }
         }
 
@@ -699,6 +769,55 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Edit"],
             icon: Some("md_content_copy"),
         },
+        CopySelectionAsTable(format) => {
+            let name = match format {
+                TableFormat::Tsv => "TSV",
+                TableFormat::Csv => "CSV",
+                TableFormat::Markdown => "Markdown table",
+            };
+            CommandDef {
+                brief: format!("Copy selection as {name}").into(),
+                doc: format!(
+                    "Copies the columns spanned by the selection to the clipboard as {name}"
+                )
+                .into(),
+                keys: vec![],
+                args: &[ArgType::ActivePane],
+                menubar: &["Edit", "Copy as Table"],
+                icon: Some("md_table"),
+            }
+        }
+        ViewSelectionAsJson => CommandDef {
+            brief: "View selection as JSON".into(),
+            doc: "Shows the selection, or the output of the last command, \
+                  as a foldable JSON tree"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit"],
+            icon: Some("md_code_json"),
+        },
+        SelectCommandOutput => CommandDef {
+            brief: "Select command output".into(),
+            doc: "Selects the output of the command at the top of the \
+                  viewport, or of the last command, using the semantic \
+                  zones reported by shell integration"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit"],
+            icon: Some("md_select"),
+        },
+        CopyLastCommandOutput => CommandDef {
+            brief: "Copy last command output".into(),
+            doc: "Copies the output of the last command to the clipboard, \
+                  using the semantic zones reported by shell integration"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit"],
+            icon: Some("md_content_copy"),
+        },
         PasteFrom(ClipboardPasteSource::Clipboard) => CommandDef {
             brief: "Paste from clipboard".into(),
             doc: "Pastes text from the clipboard".into(),
@@ -710,6 +829,23 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Edit"],
             icon: Some("md_content_paste"),
         },
+        ShowClipboardHistory => CommandDef {
+            brief: "Paste from clipboard history".into(),
+            doc: "Shows the recent copies, and pastes the one that is picked".into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit"],
+            icon: Some("md_clipboard_text_clock"),
+        },
+        PasteFromRegister(name) => CommandDef {
+            brief: format!("Paste from register {name}").into(),
+            doc: format!("Pastes the text that was yanked into register {name} in copy mode")
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: Some("md_content_paste"),
+        },
         ToggleFullScreen => CommandDef {
             brief: "Toggle full screen mode".into(),
             doc: "Switch between normal and full screen mode".into(),
@@ -759,6 +895,20 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Window", "Level"],
             icon: None,
         },
+        MoveWindowToMonitor(n) => {
+            let ordinal = english_ordinal(*n as isize + 1);
+            CommandDef {
+                brief: format!("Move Window to {ordinal} Monitor").into(),
+                doc: format!(
+                    "Moves the window to the {ordinal} monitor, counting from the left"
+                )
+                .into(),
+                keys: vec![],
+                args: &[ArgType::ActiveWindow],
+                menubar: &["Window", "Move to Monitor"],
+                icon: None,
+            }
+        }
         Hide => CommandDef {
             brief: "Hide/Minimize Window".into(),
             doc: "Hides/Mimimizes the current window".into(),
@@ -811,6 +961,44 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Edit"],
             icon: Some("cod_clear_all"),
         },
+        ExportScrollback {
+            format: ScrollbackExportFormat::PlainText,
+        } => CommandDef {
+            brief: "Export scrollback as text".into(),
+            doc: "Saves the scrollback of the current pane to the \
+              downloads folder as plain text"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit", "Export Scrollback"],
+            icon: Some("cod_save_as"),
+        },
+        ExportScrollback {
+            format: ScrollbackExportFormat::Ansi,
+        } => CommandDef {
+            brief: "Export scrollback with colors".into(),
+            doc: "Saves the scrollback of the current pane to the \
+              downloads folder as text with escape sequences that \
+              reproduce its colors and styling"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit", "Export Scrollback"],
+            icon: Some("cod_save_as"),
+        },
+        ExportScrollback {
+            format: ScrollbackExportFormat::Html,
+        } => CommandDef {
+            brief: "Export scrollback as HTML".into(),
+            doc: "Saves the scrollback of the current pane to the \
+              downloads folder as an HTML document using the colors \
+              of the current color scheme"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Edit", "Export Scrollback"],
+            icon: Some("cod_save_as"),
+        },
         Search(Pattern::CurrentSelectionOrEmptyString) => CommandDef {
             brief: "Search pane output".into(),
             doc: "Enters the search mode UI for the current pane".into(),
@@ -827,6 +1015,26 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &[],
             icon: Some("oct_search"),
         },
+        SearchWorkspace(Pattern::CurrentSelectionOrEmptyString) => CommandDef {
+            brief: "Search all panes in workspace".into(),
+            doc: "Searches the output of every pane in the current workspace \
+            and lists the matches, grouped by pane"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &["Edit"],
+            icon: Some("oct_search"),
+        },
+        SearchWorkspace(_) => CommandDef {
+            brief: "Search all panes in workspace".into(),
+            doc: "Searches the output of every pane in the current workspace \
+            and lists the matches, grouped by pane"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &[],
+            icon: Some("oct_search"),
+        },
         ShowDebugOverlay => CommandDef {
             brief: "Show debug overlay".into(),
             doc: "Activates the debug overlay and Lua REPL".into(),
@@ -835,6 +1043,34 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Help"],
             icon: Some("cod_debug"),
         },
+        ShowKeyDebugger => CommandDef {
+            brief: "Show key debugger".into(),
+            doc: "Shows how key presses are received, mapped \
+                and encoded, to help diagnose keyboard layout issues"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Help"],
+            icon: Some("md_keyboard"),
+        },
+        ToggleRenderHud => CommandDef {
+            brief: "Toggle render statistics".into(),
+            doc: "Shows or hides an overlay with frame times, cache hit \
+                rates, shaping time and pty throughput"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveWindow],
+            menubar: &["Help"],
+            icon: Some("md_speedometer"),
+        },
+        PreviewEventSound(event) => CommandDef {
+            brief: format!("Preview {event:?} sound").into(),
+            doc: "Plays the sound configured for the event in `event_sounds`".into(),
+            keys: vec![],
+            args: &[],
+            menubar: &[],
+            icon: Some("md_volume_high"),
+        },
         InputSelector(_) => CommandDef {
             brief: "Prompt the user to choose from a list".into(),
             doc: "Activates the selector overlay and wait for input".into(),
@@ -1058,13 +1294,18 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
         }
         ActivatePaneByIndex(n) => {
             let n = *n;
-            let ordinal = english_ordinal(n as isize);
+            let ordinal = english_ordinal(n as isize + 1);
+            let keys = if n <= 7 {
+                vec![(Modifiers::SUPER.union(Modifiers::ALT), (n + 1).to_string())]
+            } else {
+                vec![]
+            };
             CommandDef {
                 brief: format!("Activate {ordinal} Pane").into(),
                 doc: format!("Activates the {ordinal} Pane").into(),
-                keys: vec![],
+                keys,
                 args: &[ArgType::ActiveWindow],
-                menubar: &[],
+                menubar: &["Window", "Select Pane"],
                 icon: None,
             }
         }
@@ -1309,6 +1550,24 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &[],
             icon: None,
         },
+        ActivateConfigProfile(name) => CommandDef {
+            brief: format!("Activate config profile `{name}`").into(),
+            doc: format!("Applies the overrides of the `{name}` config profile").into(),
+            keys: vec![],
+            args: &[],
+            menubar: &[],
+            icon: None,
+        },
+        RestoreSession => CommandDef {
+            brief: "Restore previous session".into(),
+            doc: "Reopens the windows, tabs and panes that were open \
+            when Kaku last ran"
+                .into(),
+            keys: vec![],
+            args: &[],
+            menubar: &["Window"],
+            icon: None,
+        },
         QuitApplication => CommandDef {
             brief: "Quit Kaku".into(),
             doc: "Quits Kaku".into(),
@@ -1372,6 +1631,26 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 icon: None,
             }
         }
+        MoveTabToNewWindow => CommandDef {
+            brief: "Move tab to a new window".into(),
+            doc: "Moves the current tab out of this window and into \
+            a new window of its own"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActiveTab],
+            menubar: &["Window", "Move Tab"],
+            icon: Some("md_open_in_new"),
+        },
+        ToggleDropdownWindow => CommandDef {
+            brief: "Toggle dropdown window".into(),
+            doc: "Slides the dropdown window down from the top of the \
+            active screen, or hides it if it already has focus"
+                .into(),
+            keys: vec![],
+            args: &[],
+            menubar: &["Window"],
+            icon: Some("md_arrow_collapse_down"),
+        },
         ScrollByPage(amount) => {
             let amount = amount.into_inner();
             if amount == -1.0 {
@@ -1476,6 +1755,16 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["View"],
             icon: Some("md_format_align_top"),
         },
+        ToggleViewportSplit(_) => CommandDef {
+            brief: "Toggle split viewport".into(),
+            doc: "Splits the current pane into a scrollback viewport \
+                above and a live viewport below"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["View"],
+            icon: Some("md_view_split_horizontal"),
+        },
         ActivateCopyMode => CommandDef {
             brief: "Activate Copy Mode".into(),
             doc: "Enter mouse-less copy mode to select text using only \
@@ -1595,6 +1884,28 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             icon: None,
         },
         AdjustPaneSize(PaneDirection::Next | PaneDirection::Prev, _) => return None,
+        ApplyLayout(name) => CommandDef {
+            brief: format!("Open layout {name}").into(),
+            doc: format!("Spawns the `{name}` preset from the layouts config in a new tab").into(),
+            keys: vec![],
+            args: &[],
+            menubar: &[],
+            icon: Some("md_view_dashboard"),
+        },
+        SetPaneSize(size) => CommandDef {
+            brief: format!(
+                "Resize Pane to {}x{} cells",
+                size.cols.map_or("-".to_string(), |cols| cols.to_string()),
+                size.rows.map_or("-".to_string(), |rows| rows.to_string()),
+            )
+            .into(),
+            doc: "Moves the split dividers around the active pane so that it has the given size"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: None,
+        },
         ActivatePaneDirection(PaneDirection::Next | PaneDirection::Prev) => return None,
         ActivatePaneDirection(PaneDirection::Left) => CommandDef {
             brief: "Activate Pane Left".into(),
@@ -1636,6 +1947,71 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
             menubar: &["Window"],
             icon: Some("md_fullscreen"),
         },
+        TogglePaneInputLock => CommandDef {
+            brief: "Toggle Pane Input Lock".into(),
+            doc: "Makes the current pane read-only, so that keystrokes and \
+                  pastes are ignored, or makes it writable again"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Window"],
+            icon: Some("md_lock"),
+        },
+        ToggleBroadcastInput(scope) => {
+            let scope = match scope {
+                BroadcastInputScope::Tab => "Tab",
+                BroadcastInputScope::Window => "Window",
+                BroadcastInputScope::Workspace => "Workspace",
+            };
+            CommandDef {
+                brief: format!("Toggle Broadcast Input to {scope}").into(),
+                doc: format!(
+                    "Mirrors keystrokes and pastes to all of the panes in the \
+                     current {}, or stops doing so",
+                    scope.to_lowercase()
+                )
+                .into(),
+                keys: vec![],
+                args: &[ArgType::ActivePane],
+                menubar: &["Window", "Broadcast Input"],
+                icon: Some("md_broadcast"),
+            }
+        }
+        TogglePaneBroadcastExclusion => CommandDef {
+            brief: "Toggle Pane Broadcast Exclusion".into(),
+            doc: "Stops the current pane from sending or receiving broadcast \
+                  input, or allows it to again"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Window", "Broadcast Input"],
+            icon: Some("md_broadcast_off"),
+        },
+        TogglePaneMonitor(monitor) => {
+            let (brief, doc) = match monitor {
+                PaneMonitor::Activity => (
+                    "Toggle Pane Activity Monitor".to_string(),
+                    "Flags the tab when the current pane produces output, \
+                     or stops doing so"
+                        .to_string(),
+                ),
+                PaneMonitor::Silence(seconds) => (
+                    format!("Toggle Pane Silence Monitor ({seconds}s)"),
+                    format!(
+                        "Flags the tab when the current pane hasn't produced \
+                         output for {seconds} seconds, or stops doing so"
+                    ),
+                ),
+            };
+            CommandDef {
+                brief: brief.into(),
+                doc: doc.into(),
+                keys: vec![],
+                args: &[ArgType::ActivePane],
+                menubar: &["Window", "Monitor Pane"],
+                icon: Some("md_bell_ring"),
+            }
+        }
         ActivateLastTab => CommandDef {
             brief: "Activate the last active tab".into(),
             doc: "If there was no prior active tab, has no effect.".into(),
@@ -1975,6 +2351,21 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 icon: None,
             }
         }
+        ActivateKeyTable { name, .. }
+            if name == crate::termwindow::resizemode::RESIZE_PANE_TABLE =>
+        {
+            CommandDef {
+                brief: "Resize Pane with the Keyboard".into(),
+                doc: "Activates the `resize_pane` key table, in which hjkl or the \
+                      arrow keys resize the active pane until Escape or Enter \
+                      is pressed"
+                    .into(),
+                keys: vec![],
+                args: &[ArgType::ActivePane],
+                menubar: &["Window", "Resize Pane"],
+                icon: Some("md_resize"),
+            }
+        }
         ActivateKeyTable { name, .. } => CommandDef {
             brief: format!("Activate key table `{name}`").into(),
             doc: format!("Activate key table `{name}`").into(),
@@ -2041,6 +2432,47 @@ pub fn derive_command_from_key_assignment(action: &KeyAssignment) -> Option<Comm
                 },
             }
         }
+        TogglePaneRecording => CommandDef {
+            brief: "Toggle recording of the current pane".into(),
+            doc: "Starts or stops recording the output of the current \
+              pane to an asciinema .cast file in the recordings folder"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Shell"],
+            icon: Some("md_record_rec"),
+        },
+        PlayRecording { path: None } => CommandDef {
+            brief: "Play the latest recording".into(),
+            doc: "Replays the most recent pane recording in an overlay \
+              over the current pane, with controls to pause, seek and \
+              change the speed"
+                .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &["Shell"],
+            icon: Some("md_play"),
+        },
+        PlayRecording { path: Some(path) } => CommandDef {
+            brief: format!("Play recording {path}").into(),
+            doc: format!("Replays {path} in an overlay over the current pane").into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: Some("md_play"),
+        },
+        SetSerialLineEnding(line_ending) => CommandDef {
+            brief: format!("Send {line_ending:?} for Enter on the serial port").into(),
+            doc: format!(
+                "Changes the line ending sent by the Enter key in the \
+                 serial port domain of the current pane to {line_ending:?}"
+            )
+            .into(),
+            keys: vec![],
+            args: &[ArgType::ActivePane],
+            menubar: &[],
+            icon: None,
+        },
         ResetTerminal => CommandDef {
             brief: "Reset the terminal emulation state in the current pane".into(),
             doc: "Reset the terminal emulation state in the current pane".into(),
@@ -2091,9 +2523,27 @@ fn compute_default_actions() -> Vec<KeyAssignment> {
         #[cfg(not(target_os = "macos"))]
         CopyTo(ClipboardCopyDestination::PrimarySelection),
         CopyTo(ClipboardCopyDestination::Clipboard),
+        CopySelectionAsTable(TableFormat::Tsv),
+        CopySelectionAsTable(TableFormat::Csv),
+        CopySelectionAsTable(TableFormat::Markdown),
+        ViewSelectionAsJson,
+        SelectCommandOutput,
+        CopyLastCommandOutput,
         PasteFrom(ClipboardPasteSource::Clipboard),
+        ShowClipboardHistory,
         ClearScrollback(ScrollbackEraseMode::ScrollbackOnly),
         ClearScrollback(ScrollbackEraseMode::ScrollbackAndViewport),
+        ExportScrollback {
+            format: ScrollbackExportFormat::PlainText,
+        },
+        ExportScrollback {
+            format: ScrollbackExportFormat::Ansi,
+        },
+        ExportScrollback {
+            format: ScrollbackExportFormat::Html,
+        },
+        TogglePaneRecording,
+        PlayRecording { path: None },
         QuickSelect,
         CharSelect(CharSelectArguments::default()),
         ActivateCopyMode,
@@ -2108,6 +2558,7 @@ fn compute_default_actions() -> Vec<KeyAssignment> {
         ScrollByPage(NotNan::new(1.0).unwrap()),
         ScrollToTop,
         ScrollToBottom,
+        ToggleViewportSplit(SplitSize::Percent(50)),
         // ----------------- Window
         ToggleFullScreen,
         ToggleAlwaysOnTop,
@@ -2117,6 +2568,7 @@ fn compute_default_actions() -> Vec<KeyAssignment> {
         SetWindowLevel(WindowLevel::AlwaysOnTop),
         Hide,
         Search(Pattern::CurrentSelectionOrEmptyString),
+        SearchWorkspace(Pattern::CurrentSelectionOrEmptyString),
         PaneSelect(PaneSelectArguments {
             alphabet: String::new(),
             mode: PaneSelectMode::Activate,
@@ -2170,22 +2622,43 @@ fn compute_default_actions() -> Vec<KeyAssignment> {
         ActivateWindowRelative(1),
         MoveTabRelative(-1),
         MoveTabRelative(1),
+        MoveTabToNewWindow,
+        ToggleDropdownWindow,
         AdjustPaneSize(PaneDirection::Left, 1),
         AdjustPaneSize(PaneDirection::Right, 1),
         AdjustPaneSize(PaneDirection::Up, 1),
         AdjustPaneSize(PaneDirection::Down, 1),
+        crate::termwindow::resizemode::activate_resize_mode(),
         ActivatePaneDirection(PaneDirection::Left),
         ActivatePaneDirection(PaneDirection::Right),
         ActivatePaneDirection(PaneDirection::Up),
         ActivatePaneDirection(PaneDirection::Down),
+        ActivatePaneByIndex(0),
+        ActivatePaneByIndex(1),
+        ActivatePaneByIndex(2),
+        ActivatePaneByIndex(3),
+        ActivatePaneByIndex(4),
+        ActivatePaneByIndex(5),
+        ActivatePaneByIndex(6),
+        ActivatePaneByIndex(7),
         TogglePaneZoomState,
+        TogglePaneInputLock,
+        ToggleBroadcastInput(BroadcastInputScope::Tab),
+        ToggleBroadcastInput(BroadcastInputScope::Window),
+        ToggleBroadcastInput(BroadcastInputScope::Workspace),
+        TogglePaneBroadcastExclusion,
+        TogglePaneMonitor(PaneMonitor::Activity),
+        TogglePaneMonitor(PaneMonitor::Silence(30)),
         ActivateLastTab,
         ShowLauncher,
         ShowTabNavigator,
+        RestoreSession,
         // ----------------- Help
         OpenUri("https://github.com/tw93/Kaku".to_string()),
         OpenUri("https://github.com/tw93/Kaku/issues/".to_string()),
         ShowDebugOverlay,
+        ShowKeyDebugger,
+        ToggleRenderHud,
         // ----------------- Misc
         OpenLinkAtMouseCursor,
     ];
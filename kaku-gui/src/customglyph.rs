@@ -2,11 +2,12 @@ use crate::glyphcache::{GlyphCache, SizedBlockKey};
 use crate::utilsprites::RenderMetrics;
 use ::window::bitmaps::atlas::Sprite;
 use ::window::color::SrgbaPixel;
-use config::DimensionContext;
+use config::{Config, CustomGlyphRender, DimensionContext, MissingGlyphStyle};
 use std::ops::Range;
 use termwiz::surface::CursorShape;
 use tiny_skia::{BlendMode, FillRule, Paint, Path, PathBuilder, PixmapMut, Stroke, Transform};
 use wezterm_font::units::{IntPixelLength, PixelLength};
+use wezterm_font::GlyphInfo;
 use window::{BitmapImage, Image, Point, Rect, Size};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -231,6 +232,15 @@ pub enum BlockKey {
     /// A graph branch pattern
     Branches(Branch),
     Spinner(u8),
+    /// A box showing the codepoint of a character that no font can
+    /// display; see `missing_glyph_style`
+    HexBox(u32),
+    /// Stands in for U+FFFD REPLACEMENT CHARACTER when it is used in
+    /// place of a character that no font can display
+    ReplacementCharacter,
+    /// A private use area character drawn from the `svg_path`
+    /// set for it in `custom_glyphs`
+    UserPath(char),
 
     Poly(&'static [Poly]),
 
@@ -678,11 +688,45 @@ impl PolyStyle {
 }
 
 impl BlockKey {
+    /// Returns the key used to draw `info` in place of the font's
+    /// placeholder glyph if no font could provide it, according to
+    /// `missing_glyph_style`
+    pub fn for_missing_glyph(info: &GlyphInfo, style: MissingGlyphStyle) -> Option<Self> {
+        if !Self::is_missing(info) {
+            return None;
+        }
+        match style {
+            MissingGlyphStyle::FontDefault | MissingGlyphStyle::Blank => None,
+            MissingGlyphStyle::HexBox => info.only_char.map(|c| Self::HexBox(c as u32)),
+            MissingGlyphStyle::ReplacementCharacter => Some(Self::ReplacementCharacter),
+        }
+    }
+
+    /// Returns true if the shaper had to use the font's placeholder
+    /// for `info`.  Glyph 0 is the .notdef glyph that the shaper falls
+    /// back to once it has run out of fonts to try.
+    pub fn is_missing(info: &GlyphInfo) -> bool {
+        info.glyph_pos == 0 && !info.is_space
+    }
+
+    /// Returns the key to draw `c` with in place of the glyph from
+    /// the font, taking `custom_block_glyphs` and any override for
+    /// `c` in `custom_glyphs` into account
+    pub fn for_char(c: char, config: &Config) -> Option<Self> {
+        match config.custom_glyphs.get(&c) {
+            Some(glyph) => match glyph.render {
+                CustomGlyphRender::Font => None,
+                CustomGlyphRender::Draw if glyph.svg_path.is_some() => Some(Self::UserPath(c)),
+                CustomGlyphRender::Draw => Self::from_char(c),
+            },
+            None if config.custom_block_glyphs => Self::from_char(c),
+            None => None,
+        }
+    }
+
     pub fn filter_out_synthetic(glyphs: &mut Vec<char>) {
         let config = config::configuration();
-        if config.custom_block_glyphs {
-            glyphs.retain(|&c| Self::from_char(c).is_none());
-        }
+        glyphs.retain(|&c| Self::for_char(c, &config).is_none());
     }
 
     pub fn from_str(s: &str) -> Option<Self> {
@@ -5980,6 +6024,83 @@ impl GlyphCache {
                     _ => {}
                 }
             }
+            BlockKey::HexBox(codepoint) => {
+                let width = metrics.cell_size.width as f32;
+                let height = metrics.cell_size.height as f32;
+                let line = (metrics.underline_height as f32).max(1.);
+                fill_rect(&mut buffer, 0.0..width, 0.0..line, BlockAlpha::Full);
+                fill_rect(
+                    &mut buffer,
+                    0.0..width,
+                    height - line..height,
+                    BlockAlpha::Full,
+                );
+                fill_rect(&mut buffer, 0.0..line, 0.0..height, BlockAlpha::Full);
+                fill_rect(
+                    &mut buffer,
+                    width - line..width,
+                    0.0..height,
+                    BlockAlpha::Full,
+                );
+
+                let digits = hex_box_digits(codepoint);
+                let (top, bottom) = digits.split_at(digits.len() / 2);
+                let inset = line + 1.;
+                fill_pixel_glyphs(
+                    &mut buffer,
+                    &[top, bottom],
+                    inset..width - inset,
+                    inset..height - inset,
+                    BlendMode::default(),
+                );
+            }
+            BlockKey::ReplacementCharacter => {
+                self.draw_polys(
+                    &metrics,
+                    &[Poly {
+                        path: &[
+                            PolyCommand::MoveTo(BlockCoord::Frac(1, 2), BlockCoord::Frac(1, 8)),
+                            PolyCommand::LineTo(BlockCoord::One, BlockCoord::Frac(1, 2)),
+                            PolyCommand::LineTo(BlockCoord::Frac(1, 2), BlockCoord::Frac(7, 8)),
+                            PolyCommand::LineTo(BlockCoord::Zero, BlockCoord::Frac(1, 2)),
+                            PolyCommand::Close,
+                        ],
+                        intensity: BlockAlpha::Full,
+                        style: PolyStyle::Fill,
+                    }],
+                    &mut buffer,
+                    PolyAA::AntiAlias,
+                    BlendMode::default(),
+                );
+                let width = metrics.cell_size.width as f32;
+                let height = metrics.cell_size.height as f32;
+                fill_pixel_glyphs(
+                    &mut buffer,
+                    &[&[QUESTION_MARK]],
+                    width * 0.3..width * 0.7,
+                    height * 0.32..height * 0.68,
+                    BlendMode::Clear,
+                );
+            }
+            BlockKey::UserPath(c) => {
+                let config = config::configuration();
+                if let Some(glyph) = config.custom_glyphs.get(&c) {
+                    if let Some(data) = &glyph.svg_path {
+                        if let Err(err) = fill_svg_path(
+                            &mut buffer,
+                            data,
+                            glyph.svg_view_box,
+                            config.anti_alias_custom_block_glyphs,
+                        ) {
+                            log::error!(
+                                "custom_glyphs: invalid svg_path for U+{:04X}: {:#}",
+                                c as u32,
+                                err
+                            );
+                        }
+                    }
+                }
+            }
             BlockKey::Poly(polys) | BlockKey::PolyWithCustomMetrics { polys, .. } => {
                 self.draw_polys(
                     &metrics,
@@ -6034,3 +6155,463 @@ fn fill_rect(buffer: &mut Image, x: Range<f32>, y: Range<f32>, intensity: BlockA
         None,
     );
 }
+
+/// A 3x5 pixel font for the hexadecimal digits.  Each row is a set of
+/// three bits, with the leftmost pixel in the most significant bit.
+const HEX_DIGITS: [[u8; 5]; 16] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b111, 0b101, 0b111, 0b101, 0b101],
+    [0b110, 0b101, 0b110, 0b101, 0b110],
+    [0b111, 0b100, 0b100, 0b100, 0b111],
+    [0b110, 0b101, 0b101, 0b101, 0b110],
+    [0b111, 0b100, 0b111, 0b100, 0b111],
+    [0b111, 0b100, 0b111, 0b100, 0b100],
+];
+
+const QUESTION_MARK: [u8; 5] = [0b111, 0b001, 0b011, 0b000, 0b010];
+
+/// Returns the glyphs for the hex digits of `codepoint`; four digits
+/// for the BMP and six otherwise, so that they split evenly into the
+/// two rows of a hex box
+fn hex_box_digits(codepoint: u32) -> Vec<[u8; 5]> {
+    let num_digits = if codepoint > 0xffff { 6 } else { 4 };
+    (0..num_digits)
+        .rev()
+        .map(|idx| HEX_DIGITS[((codepoint >> (idx * 4)) & 0xf) as usize])
+        .collect()
+}
+
+/// Fill the shape described by the SVG path `data` into `buffer`,
+/// stretching `view_box` (min-x, min-y, width, height) to cover it
+fn fill_svg_path(
+    buffer: &mut Image,
+    data: &str,
+    view_box: [f32; 4],
+    anti_alias: bool,
+) -> anyhow::Result<()> {
+    let path = parse_svg_path(data)?;
+    let (width, height) = buffer.image_dimensions();
+    let [min_x, min_y, vb_width, vb_height] = view_box;
+    let sx = width as f32 / vb_width;
+    let sy = height as f32 / vb_height;
+    let transform = Transform::from_row(sx, 0., 0., sy, -min_x * sx, -min_y * sy);
+
+    let mut pixmap =
+        PixmapMut::from_bytes(buffer.pixel_data_slice_mut(), width as u32, height as u32)
+            .expect("make pixmap from existing bitmap");
+    let mut paint = Paint::default();
+    paint.set_color(tiny_skia::Color::WHITE);
+    paint.anti_alias = anti_alias;
+    paint.force_hq_pipeline = true;
+    pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+    Ok(())
+}
+
+struct SvgPathTokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl SvgPathTokens<'_> {
+    fn skip_separators(&mut self) {
+        while matches!(
+            self.data.get(self.pos),
+            Some(b' ' | b'\t' | b'\r' | b'\n' | b',')
+        ) {
+            self.pos += 1;
+        }
+    }
+
+    fn is_digit(&self, idx: usize) -> bool {
+        self.data.get(idx).map_or(false, u8::is_ascii_digit)
+    }
+
+    fn number(&mut self) -> anyhow::Result<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        let mut end = start;
+        if matches!(self.data.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let mut seen_digit = false;
+        let mut seen_dot = false;
+        loop {
+            if self.is_digit(end) {
+                seen_digit = true;
+            } else if self.data.get(end) == Some(&b'.') && !seen_dot {
+                seen_dot = true;
+            } else {
+                break;
+            }
+            end += 1;
+        }
+        if !seen_digit {
+            anyhow::bail!("expected a number at offset {start}");
+        }
+        if matches!(self.data.get(end), Some(b'e' | b'E')) {
+            let mut exp = end + 1;
+            if matches!(self.data.get(exp), Some(b'+' | b'-')) {
+                exp += 1;
+            }
+            if self.is_digit(exp) {
+                end = exp;
+                while self.is_digit(end) {
+                    end += 1;
+                }
+            }
+        }
+        self.pos = end;
+        Ok(std::str::from_utf8(&self.data[start..end])?.parse()?)
+    }
+
+    /// Arc flags are a single digit and need not be separated
+    /// from whatever follows them
+    fn flag(&mut self) -> anyhow::Result<bool> {
+        self.skip_separators();
+        let flag = match self.data.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => anyhow::bail!("expected an arc flag at offset {}", self.pos),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+
+    fn point(&mut self, origin: (f32, f32)) -> anyhow::Result<(f32, f32)> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok((origin.0 + x, origin.1 + y))
+    }
+}
+
+/// Parse SVG path data.  All of the path commands are supported;
+/// elliptical arcs are approximated using cubic curves.
+fn parse_svg_path(data: &str) -> anyhow::Result<Path> {
+    let mut tokens = SvgPathTokens {
+        data: data.as_bytes(),
+        pos: 0,
+    };
+    let mut pb = PathBuilder::new();
+    let mut current = (0f32, 0f32);
+    let mut start = (0f32, 0f32);
+    let mut command: Option<char> = None;
+    // The kind and final control point of the previous curve,
+    // which the smooth curve commands reflect
+    let mut last_control: Option<(char, (f32, f32))> = None;
+
+    let reflect = |last: Option<(char, (f32, f32))>, kind: char, current: (f32, f32)| match last {
+        Some((k, (x, y))) if k == kind => (2. * current.0 - x, 2. * current.1 - y),
+        _ => current,
+    };
+
+    loop {
+        tokens.skip_separators();
+        let Some(&b) = tokens.data.get(tokens.pos) else {
+            break;
+        };
+        let cmd = if b.is_ascii_alphabetic() {
+            tokens.pos += 1;
+            b as char
+        } else {
+            // Coordinates following a command repeat it, except
+            // that those following a move are lines
+            match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) if !c.eq_ignore_ascii_case(&'z') => c,
+                _ => anyhow::bail!("expected a command at offset {}", tokens.pos),
+            }
+        };
+        command = Some(cmd);
+
+        let origin = if cmd.is_ascii_lowercase() {
+            current
+        } else {
+            (0., 0.)
+        };
+        let mut control = None;
+
+        match cmd.to_ascii_uppercase() {
+            'M' => {
+                current = tokens.point(origin)?;
+                start = current;
+                pb.move_to(current.0, current.1);
+            }
+            'L' => {
+                current = tokens.point(origin)?;
+                pb.line_to(current.0, current.1);
+            }
+            'H' => {
+                current.0 = origin.0 + tokens.number()?;
+                pb.line_to(current.0, current.1);
+            }
+            'V' => {
+                current.1 = origin.1 + tokens.number()?;
+                pb.line_to(current.0, current.1);
+            }
+            'C' | 'S' => {
+                let c1 = if cmd.eq_ignore_ascii_case(&'c') {
+                    tokens.point(origin)?
+                } else {
+                    reflect(last_control, 'C', current)
+                };
+                let c2 = tokens.point(origin)?;
+                current = tokens.point(origin)?;
+                pb.cubic_to(c1.0, c1.1, c2.0, c2.1, current.0, current.1);
+                control = Some(('C', c2));
+            }
+            'Q' | 'T' => {
+                let c = if cmd.eq_ignore_ascii_case(&'q') {
+                    tokens.point(origin)?
+                } else {
+                    reflect(last_control, 'Q', current)
+                };
+                current = tokens.point(origin)?;
+                pb.quad_to(c.0, c.1, current.0, current.1);
+                control = Some(('Q', c));
+            }
+            'A' => {
+                let rx = tokens.number()?;
+                let ry = tokens.number()?;
+                let x_axis_rotation = tokens.number()?;
+                let large_arc = tokens.flag()?;
+                let sweep = tokens.flag()?;
+                let to = tokens.point(origin)?;
+                svg_arc_to(
+                    &mut pb,
+                    current,
+                    (rx, ry),
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                );
+                current = to;
+            }
+            'Z' => {
+                pb.close();
+                current = start;
+            }
+            _ => anyhow::bail!("unknown path command '{cmd}'"),
+        }
+        last_control = control;
+    }
+
+    pb.finish()
+        .ok_or_else(|| anyhow::anyhow!("the path doesn't describe a shape"))
+}
+
+/// Append an elliptical arc from `from` to `to`, using one cubic curve
+/// per quarter turn; the conversion to center parameterization follows
+/// <https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes>
+fn svg_arc_to(
+    pb: &mut PathBuilder,
+    from: (f32, f32),
+    radii: (f32, f32),
+    x_axis_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: (f32, f32),
+) {
+    use std::f32::consts::{FRAC_PI_2, TAU};
+
+    if from == to {
+        return;
+    }
+    let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+    if rx == 0. || ry == 0. {
+        pb.line_to(to.0, to.1);
+        return;
+    }
+
+    let (sin_phi, cos_phi) = x_axis_rotation.to_radians().sin_cos();
+    let dx = (from.0 - to.0) / 2.;
+    let dy = (from.1 - to.1) / 2.;
+    let x1 = cos_phi * dx + sin_phi * dy;
+    let y1 = -sin_phi * dx + cos_phi * dy;
+
+    // Radii that are too small to span the endpoints are scaled up
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1. {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = (num / den).max(0.).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let cx1 = coef * rx * y1 / ry;
+    let cy1 = -coef * ry * x1 / rx;
+    let cx = cos_phi * cx1 - sin_phi * cy1 + (from.0 + to.0) / 2.;
+    let cy = sin_phi * cx1 + cos_phi * cy1 + (from.1 + to.1) / 2.;
+
+    let angle = |ux: f32, uy: f32, vx: f32, vy: f32| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let theta = angle(1., 0., (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle(
+        (x1 - cx1) / rx,
+        (y1 - cy1) / ry,
+        (-x1 - cx1) / rx,
+        (-y1 - cy1) / ry,
+    );
+    if !sweep && delta > 0. {
+        delta -= TAU;
+    } else if sweep && delta < 0. {
+        delta += TAU;
+    }
+
+    let point = |t: f32| {
+        let (sin, cos) = t.sin_cos();
+        (
+            cx + rx * cos * cos_phi - ry * sin * sin_phi,
+            cy + rx * cos * sin_phi + ry * sin * cos_phi,
+        )
+    };
+    let tangent = |t: f32| {
+        let (sin, cos) = t.sin_cos();
+        (
+            -rx * sin * cos_phi - ry * cos * sin_phi,
+            -rx * sin * sin_phi + ry * cos * cos_phi,
+        )
+    };
+
+    let segments = (delta.abs() / FRAC_PI_2).ceil().max(1.) as usize;
+    let step = delta / segments as f32;
+    let k = 4. / 3. * (step / 4.).tan();
+    for i in 0..segments {
+        let t1 = theta + step * i as f32;
+        let t2 = t1 + step;
+        let (p1, d1) = (point(t1), tangent(t1));
+        let d2 = tangent(t2);
+        let p2 = if i + 1 == segments { to } else { point(t2) };
+        pb.cubic_to(
+            p1.0 + k * d1.0,
+            p1.1 + k * d1.1,
+            p2.0 - k * d2.0,
+            p2.1 - k * d2.1,
+            p2.0,
+            p2.1,
+        );
+    }
+}
+
+/// Draws rows of pixel font glyphs centered within the x and y ranges,
+/// scaled up by the largest whole number that fits
+fn fill_pixel_glyphs(
+    buffer: &mut Image,
+    rows: &[&[[u8; 5]]],
+    x: Range<f32>,
+    y: Range<f32>,
+    blend_mode: BlendMode,
+) {
+    let cols = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if cols == 0 {
+        return;
+    }
+    // Glyphs are 3x5 with a one pixel gap between them
+    let grid_width = (cols * 4 - 1) as f32;
+    let grid_height = (rows.len() * 6 - 1) as f32;
+    let unit = ((x.end - x.start) / grid_width)
+        .min((y.end - y.start) / grid_height)
+        .floor()
+        .max(1.);
+    let left = x.start + ((x.end - x.start) - grid_width * unit).max(0.) / 2.;
+    let top = y.start + ((y.end - y.start) - grid_height * unit).max(0.) / 2.;
+
+    let (width, height) = buffer.image_dimensions();
+    let mut pixmap =
+        PixmapMut::from_bytes(buffer.pixel_data_slice_mut(), width as u32, height as u32)
+            .expect("make pixmap from existing bitmap");
+    let mut paint = Paint::default();
+    paint.set_color(tiny_skia::Color::WHITE);
+    paint.anti_alias = false;
+    paint.blend_mode = blend_mode;
+
+    let mut path = PathBuilder::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, glyph) in row.iter().enumerate() {
+            for (y_idx, bits) in glyph.iter().enumerate() {
+                for x_idx in 0..3 {
+                    if bits & (0b100 >> x_idx) == 0 {
+                        continue;
+                    }
+                    let px = left + (col_idx * 4 + x_idx) as f32 * unit;
+                    let py = top + (row_idx * 6 + y_idx) as f32 * unit;
+                    if let Some(rect) = tiny_skia::Rect::from_xywh(px, py, unit, unit) {
+                        path.push_rect(rect);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(path) = path.finish() {
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hex_digits() {
+        assert_eq!(
+            hex_box_digits(0xe0b0),
+            vec![
+                HEX_DIGITS[0xe],
+                HEX_DIGITS[0],
+                HEX_DIGITS[0xb],
+                HEX_DIGITS[0]
+            ]
+        );
+        assert_eq!(hex_box_digits(0x1f600).len(), 6);
+        assert_eq!(hex_box_digits(0x1f600)[0], HEX_DIGITS[0]);
+    }
+
+    fn bounds(data: &str) -> (f32, f32, f32, f32) {
+        let rect = parse_svg_path(data).unwrap().bounds();
+        (rect.left(), rect.top(), rect.right(), rect.bottom())
+    }
+
+    #[test]
+    fn svg_path_commands() {
+        assert_eq!(bounds("M0 0 L1 0.5 L0 1 Z"), (0., 0., 1., 1.));
+        // Relative coordinates, with an implicit line after the move
+        assert_eq!(bounds("m0,0 1,.5-1,.5z"), (0., 0., 1., 1.));
+        assert_eq!(bounds("M0 0H2V3h-2z"), (0., 0., 2., 3.));
+        assert_eq!(bounds("M0 0Q1 0 1 1T2 2L0 2Z"), (0., 0., 2., 2.));
+    }
+
+    #[test]
+    fn svg_path_arcs() {
+        let (left, top, right, bottom) = bounds("M0 0.5A0.5 0.5 0 1 1 1 0.5A.5.5 0 1 1 0 .5Z");
+        for (value, expected) in [(left, 0.), (top, 0.), (right, 1.), (bottom, 1.)] {
+            assert!((value - expected).abs() < 0.01, "{value} != {expected}");
+        }
+    }
+
+    #[test]
+    fn svg_path_errors() {
+        assert!(parse_svg_path("").is_err());
+        assert!(parse_svg_path("L").is_err());
+        assert!(parse_svg_path("M0 0 X1 1").is_err());
+        assert!(parse_svg_path("1 1").is_err());
+    }
+}
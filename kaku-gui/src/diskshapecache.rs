@@ -0,0 +1,430 @@
+//! Persists the output of the font shaper beneath `CACHE_DIR`, so that
+//! text runs shaped by an earlier session don't need to be shaped again
+//! before the first paint.
+//!
+//! There is one file per font fingerprint (see
+//! `LoadedFont::shaping_fingerprint`), holding the glyphs for each text
+//! run keyed by a hash of the run.  Files are loaded in the background
+//! on first use, so that the render path never waits on the disk, and
+//! are written back periodically from a background thread.
+use fnv::FnvHasher;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use termwiz::cellcluster::CellCluster;
+use wezterm_font::units::PixelLength;
+use wezterm_font::GlyphInfo;
+
+/// Entries, and whole files, that haven't been used for this long
+/// are discarded
+const MAX_AGE: Duration = Duration::from_secs(30 * 86400);
+
+/// How often newly shaped runs are written out
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref CACHE: Mutex<DiskShapeCache> = Mutex::new(DiskShapeCache::new());
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StoredGlyph {
+    only_char: Option<char>,
+    is_space: bool,
+    num_cells: u8,
+    cluster: u32,
+    font_idx: usize,
+    glyph_pos: u32,
+    x_advance: f64,
+    y_advance: f64,
+    x_offset: f64,
+    y_offset: f64,
+}
+
+impl StoredGlyph {
+    fn from_info(info: &GlyphInfo) -> Self {
+        Self {
+            only_char: info.only_char,
+            is_space: info.is_space,
+            num_cells: info.num_cells,
+            cluster: info.cluster,
+            font_idx: info.font_idx,
+            glyph_pos: info.glyph_pos,
+            x_advance: info.x_advance.get(),
+            y_advance: info.y_advance.get(),
+            x_offset: info.x_offset.get(),
+            y_offset: info.y_offset.get(),
+        }
+    }
+
+    fn to_info(&self, _text: &str) -> GlyphInfo {
+        GlyphInfo {
+            #[cfg(any(debug_assertions, test))]
+            text: _text.to_string(),
+            only_char: self.only_char,
+            is_space: self.is_space,
+            num_cells: self.num_cells,
+            cluster: self.cluster,
+            font_idx: self.font_idx,
+            glyph_pos: self.glyph_pos,
+            x_advance: PixelLength::new(self.x_advance),
+            y_advance: PixelLength::new(self.y_advance),
+            x_offset: PixelLength::new(self.x_offset),
+            y_offset: PixelLength::new(self.y_offset),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Entry {
+    /// The text of the run, to guard against hash collisions
+    text: String,
+    /// Seconds since the epoch when this entry was last used
+    last_used: u64,
+    glyphs: Vec<StoredGlyph>,
+}
+
+#[derive(Default)]
+struct FontShapes {
+    entries: HashMap<u64, Entry>,
+    dirty: bool,
+    /// Whether the entries from the file have been merged in; until
+    /// then, saving would clobber the file
+    loaded: bool,
+}
+
+struct DiskShapeCache {
+    fonts: HashMap<u64, FontShapes>,
+    last_save: Instant,
+    pruned_files: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn file_name(fingerprint: u64) -> PathBuf {
+    config::shape_cache_dir().join(format!("{fingerprint:016x}.json"))
+}
+
+/// Computes the key for the text run of a cluster.  This is persisted,
+/// so it uses a hash that is stable across Rust releases.
+pub fn run_key(cluster: &CellCluster) -> u64 {
+    let mut hasher = FnvHasher::default();
+    cluster.text.hash(&mut hasher);
+    (cluster.presentation as u8).hash(&mut hasher);
+    (cluster.direction as u8).hash(&mut hasher);
+    cluster.width.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl DiskShapeCache {
+    fn new() -> Self {
+        Self {
+            fonts: HashMap::new(),
+            last_save: Instant::now(),
+            pruned_files: false,
+        }
+    }
+
+    /// Returns true the first time that `fingerprint` is seen, when
+    /// its file needs to be loaded
+    fn start_loading(&mut self, fingerprint: u64) -> bool {
+        if self.fonts.contains_key(&fingerprint) {
+            return false;
+        }
+        self.fonts.insert(fingerprint, FontShapes::default());
+        true
+    }
+
+    /// Merges in the entries read from the file for `fingerprint`.
+    /// Those stored since loading started are newer, and are kept.
+    fn finish_loading(&mut self, fingerprint: u64, entries: HashMap<u64, Entry>) {
+        let font = self.fonts.entry(fingerprint).or_default();
+        for (key, entry) in entries {
+            font.entries.entry(key).or_insert(entry);
+        }
+        font.loaded = true;
+    }
+
+    fn lookup(&mut self, fingerprint: u64, key: u64, text: &str) -> Option<Vec<GlyphInfo>> {
+        let font = self.fonts.get_mut(&fingerprint)?;
+        let entry = font.entries.get_mut(&key)?;
+        if entry.text != text {
+            return None;
+        }
+
+        // Only bother rewriting the file for coarse changes in usage
+        let now = now_secs();
+        if now.saturating_sub(entry.last_used) > 86400 {
+            entry.last_used = now;
+            font.dirty = true;
+        }
+
+        Some(entry.glyphs.iter().map(|g| g.to_info(text)).collect())
+    }
+
+    fn store(&mut self, fingerprint: u64, key: u64, text: &str, infos: &[GlyphInfo]) {
+        if infos
+            .iter()
+            .any(|info| info.glyph_pos == 0 && !info.is_space)
+        {
+            return;
+        }
+        let font = self.fonts.entry(fingerprint).or_default();
+        font.entries.insert(
+            key,
+            Entry {
+                text: text.to_string(),
+                last_used: now_secs(),
+                glyphs: infos.iter().map(StoredGlyph::from_info).collect(),
+            },
+        );
+        font.dirty = true;
+    }
+
+    /// Serializes the fonts with unsaved changes, applying the
+    /// eviction policy, and returns the data to write
+    fn take_pending(&mut self, max_entries: usize) -> Vec<(PathBuf, String)> {
+        self.last_save = Instant::now();
+        let oldest = now_secs().saturating_sub(MAX_AGE.as_secs());
+        let mut pending = vec![];
+
+        for (fingerprint, font) in self.fonts.iter_mut() {
+            if !font.dirty || !font.loaded {
+                continue;
+            }
+            font.dirty = false;
+
+            font.entries.retain(|_, entry| entry.last_used >= oldest);
+            if font.entries.len() > max_entries {
+                let mut by_age: Vec<(u64, u64)> = font
+                    .entries
+                    .iter()
+                    .map(|(key, entry)| (entry.last_used, *key))
+                    .collect();
+                by_age.sort_unstable();
+                let excess = font.entries.len() - max_entries;
+                for (_, key) in by_age.into_iter().take(excess) {
+                    font.entries.remove(&key);
+                }
+            }
+
+            match serde_json::to_string(&font.entries) {
+                Ok(json) => pending.push((file_name(*fingerprint), json)),
+                Err(err) => log::warn!("failed to serialize shape cache: {err:#}"),
+            }
+        }
+
+        pending
+    }
+}
+
+/// Removes files for fonts that haven't been used in a long while,
+/// such as those left behind by a font upgrade
+fn prune_stale_files() {
+    let Ok(dir) = std::fs::read_dir(config::shape_cache_dir()) else {
+        return;
+    };
+    let oldest = SystemTime::now() - MAX_AGE;
+    for entry in dir.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .map(|modified| modified < oldest)
+            .unwrap_or(false);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn write_pending(pending: Vec<(PathBuf, String)>, prune: bool) -> anyhow::Result<()> {
+    config::create_user_owned_dirs(&config::shape_cache_dir())?;
+    if prune {
+        prune_stale_files();
+    }
+    for (path, json) in pending {
+        std::fs::write(&path, json)?;
+    }
+    Ok(())
+}
+
+/// Reads the file for `fingerprint` on a background thread
+fn spawn_load(fingerprint: u64) {
+    std::thread::spawn(move || {
+        let entries = std::fs::read(file_name(fingerprint))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        CACHE.lock().finish_loading(fingerprint, entries);
+    });
+}
+
+/// Returns the previously shaped glyphs for a run, if any.  Nothing is
+/// found for a font until its file has been loaded.
+pub fn lookup(
+    fingerprint: u64,
+    key: u64,
+    text: &str,
+    max_entries: usize,
+) -> Option<Vec<GlyphInfo>> {
+    if max_entries == 0 {
+        return None;
+    }
+    let mut cache = CACHE.lock();
+    if cache.start_loading(fingerprint) {
+        spawn_load(fingerprint);
+    }
+    cache.lookup(fingerprint, key, text)
+}
+
+/// Records the glyphs produced by shaping a run.  Runs that include
+/// missing glyphs are not recorded, as a fallback font may be found
+/// for them later.
+pub fn store(fingerprint: u64, key: u64, text: &str, infos: &[GlyphInfo], max_entries: usize) {
+    if max_entries == 0 {
+        return;
+    }
+    let mut cache = CACHE.lock();
+    if cache.start_loading(fingerprint) {
+        spawn_load(fingerprint);
+    }
+    cache.store(fingerprint, key, text, infos);
+
+    if cache.last_save.elapsed() >= SAVE_INTERVAL {
+        let pending = cache.take_pending(max_entries);
+        let prune = !std::mem::replace(&mut cache.pruned_files, true);
+        drop(cache);
+        std::thread::spawn(move || {
+            if let Err(err) = write_pending(pending, prune) {
+                log::warn!("failed to save shape cache: {err:#}");
+            }
+        });
+    }
+}
+
+/// Writes out any unsaved changes; called as the gui shuts down
+pub fn save(max_entries: usize) {
+    let pending = CACHE.lock().take_pending(max_entries);
+    if pending.is_empty() {
+        return;
+    }
+    if let Err(err) = write_pending(pending, false) {
+        log::warn!("failed to save shape cache: {err:#}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::cell::CellAttributes;
+    use termwiz::surface::{Line, SEQ_ZERO};
+
+    fn glyph(glyph_pos: u32) -> GlyphInfo {
+        StoredGlyph {
+            only_char: Some('a'),
+            is_space: false,
+            num_cells: 1,
+            cluster: 0,
+            font_idx: 0,
+            glyph_pos,
+            x_advance: 8.,
+            y_advance: 0.,
+            x_offset: 0.,
+            y_offset: 0.,
+        }
+        .to_info("a")
+    }
+
+    fn entry(text: &str, last_used: u64) -> Entry {
+        Entry {
+            text: text.to_string(),
+            last_used,
+            glyphs: vec![],
+        }
+    }
+
+    fn loaded_cache() -> DiskShapeCache {
+        let mut cache = DiskShapeCache::new();
+        cache.finish_loading(1, HashMap::new());
+        cache
+    }
+
+    #[test]
+    fn run_key_is_deterministic() {
+        let attrs = CellAttributes::default();
+        let key = |text: &str| {
+            let line = Line::from_text(text, &attrs, SEQ_ZERO, None);
+            run_key(&line.cluster(None)[0])
+        };
+        assert_eq!(key("->"), key("->"));
+        assert_ne!(key("->"), key("=>"));
+    }
+
+    #[test]
+    fn lookup_checks_text() {
+        let mut cache = loaded_cache();
+        cache.store(1, 42, "a", &[glyph(7)]);
+        let glyphs = cache.lookup(1, 42, "a").unwrap();
+        assert_eq!(glyphs.len(), 1);
+        assert_eq!(glyphs[0].glyph_pos, 7);
+        assert_eq!(glyphs[0].x_advance.get(), 8.);
+        // A hash collision with another run isn't a hit
+        assert!(cache.lookup(1, 42, "b").is_none());
+        assert!(cache.lookup(2, 42, "a").is_none());
+    }
+
+    #[test]
+    fn missing_glyphs_are_not_stored() {
+        let mut cache = loaded_cache();
+        cache.store(1, 42, "a", &[glyph(7), glyph(0)]);
+        assert!(cache.lookup(1, 42, "a").is_none());
+    }
+
+    #[test]
+    fn loading_keeps_newer_entries() {
+        let mut cache = DiskShapeCache::new();
+        assert!(cache.start_loading(1));
+        assert!(!cache.start_loading(1));
+        cache.store(1, 42, "a", &[glyph(7)]);
+
+        // Nothing is written until the file has been merged in
+        assert!(cache.take_pending(100).is_empty());
+
+        let mut from_disk = HashMap::new();
+        from_disk.insert(42, entry("a", 0));
+        from_disk.insert(43, entry("b", now_secs()));
+        cache.finish_loading(1, from_disk);
+
+        assert_eq!(cache.lookup(1, 42, "a").unwrap()[0].glyph_pos, 7);
+        assert!(cache.lookup(1, 43, "b").is_some());
+        assert_eq!(cache.take_pending(100).len(), 1);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut cache = loaded_cache();
+        let now = now_secs();
+        let font = cache.fonts.get_mut(&1).unwrap();
+        font.entries.insert(1, entry("stale", 0));
+        font.entries.insert(2, entry("old", now - 10));
+        font.entries.insert(3, entry("newer", now - 5));
+        font.entries.insert(4, entry("newest", now));
+        font.dirty = true;
+
+        assert_eq!(cache.take_pending(2).len(), 1);
+        let mut kept: Vec<u64> = cache.fonts[&1].entries.keys().copied().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, vec![3, 4]);
+
+        // Only fonts with changes are written
+        assert!(cache.take_pending(2).is_empty());
+    }
+}
@@ -70,3 +70,22 @@ pub fn save_to_downloads(orig_name: Option<String>, data: &[u8]) -> anyhow::Resu
 
     Ok(())
 }
+
+/// Saves the scrollback exported by `ExportScrollback` to the user's
+/// download folder
+pub fn save_scrollback_export(name: &str, data: &[u8]) -> anyhow::Result<()> {
+    let (name, mut file) = resolve_file_name(Some(name))?;
+    file.write_all(data)
+        .with_context(|| format!("writing {} of data to {}", data.len(), name.display()))?;
+
+    let url = format!("file://{}", name.display());
+    wezterm_toast_notification::persistent_toast_notification_with_click_to_open_url(
+        "Scrollback exported",
+        &format!("Saved {}", name.display()),
+        &url,
+    );
+
+    log::info!("Exported scrollback to {}", name.display());
+
+    Ok(())
+}
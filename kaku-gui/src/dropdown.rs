@@ -0,0 +1,184 @@
+//! The dropdown window: a terminal window that slides down from the top
+//! of the active screen when `dropdown_hotkey` is pressed, from any
+//! application, and slides back up when it is pressed again.
+//!
+//! The window is an ordinary mux window that is created the first time
+//! it is toggled; hiding it keeps its tabs and panes running.
+use crate::frontend::front_end;
+use config::keyassignment::SpawnTabDomain;
+use config::keys::KeyMapPreference;
+use config::window::WindowLevel;
+use config::ConfigHandle;
+use mux::window::WindowId as MuxWindowId;
+use mux::Mux;
+use std::cell::RefCell;
+use std::time::Duration;
+use window::{Connection, ConnectionOps, ScreenRect, Window, WindowOps};
+
+#[derive(Default)]
+struct DropdownState {
+    mux_window_id: Option<MuxWindowId>,
+    visible: bool,
+    /// Set while the window is being spawned, so that pressing the
+    /// hotkey again in the meantime doesn't spawn a second one
+    spawning: bool,
+}
+
+thread_local! {
+    static STATE: RefCell<DropdownState> = RefCell::new(DropdownState::default());
+}
+
+/// Registers `dropdown_hotkey` with the system, replacing the hotkey
+/// of the previous configuration
+pub fn update_hotkey(config: &ConfigHandle) {
+    let hotkey = match &config.dropdown_hotkey {
+        Some(key) => match key.key.resolve(KeyMapPreference::Physical).to_phys() {
+            Some(phys) => Some((phys, key.mods)),
+            None => {
+                log::error!(
+                    "dropdown_hotkey: {:?} has no physical key code",
+                    key.key.resolve(KeyMapPreference::Physical)
+                );
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(conn) = Connection::get() {
+        if let Err(err) = conn.set_global_hotkey(hotkey) {
+            log::error!("dropdown_hotkey: {:#}", err);
+        }
+    }
+}
+
+/// Returns true if `mux_window_id` is the dropdown window
+pub fn is_dropdown_window(mux_window_id: MuxWindowId) -> bool {
+    STATE.with(|state| state.borrow().mux_window_id == Some(mux_window_id))
+}
+
+/// Returns the frame of the dropdown window on the active screen.
+/// The visible frame is used so that the window hangs below the
+/// menu bar and the camera housing rather than sliding under them.
+fn dropdown_frame(config: &ConfigHandle) -> anyhow::Result<ScreenRect> {
+    let screens = Connection::get()
+        .ok_or_else(|| anyhow::anyhow!("no connection"))?
+        .screens()?;
+    Ok(frame_within(
+        screens.active.visible_rect,
+        config.dropdown_height_percent,
+    ))
+}
+
+/// Returns the top `height_percent` of `visible`, which is clamped
+/// to between 10 and 100 percent
+fn frame_within(visible: ScreenRect, height_percent: u8) -> ScreenRect {
+    let percent = height_percent.clamp(10, 100) as isize;
+    euclid::rect(
+        visible.min_x(),
+        visible.min_y(),
+        visible.width(),
+        visible.height() * percent / 100,
+    )
+}
+
+/// Slides `window` down into view.  Called in place of `show` for the
+/// dropdown window.
+pub fn show(window: &Window, config: &ConfigHandle) {
+    let frame = match dropdown_frame(config) {
+        Ok(frame) => frame,
+        Err(err) => {
+            log::error!("dropdown window: {:#}", err);
+            window.show();
+            return;
+        }
+    };
+    window.set_window_level(WindowLevel::AlwaysOnTop);
+    window.show_dropdown(
+        frame,
+        Duration::from_millis(config.dropdown_animation_duration_ms),
+    );
+}
+
+/// Shows the dropdown window, creating it if needed, or hides it if
+/// it is already visible and focused
+pub fn toggle() {
+    let config = config::configuration();
+    let fe = front_end();
+    let (mux_window_id, visible, spawning) = STATE.with(|state| {
+        let state = state.borrow();
+        (state.mux_window_id, state.visible, state.spawning)
+    });
+    if spawning {
+        return;
+    }
+
+    if let Some(gui) = mux_window_id.and_then(|id| fe.gui_window_for_mux_window(id)) {
+        let visible = if !visible {
+            show(&gui.window, &config);
+            true
+        } else if fe.focused_window() == Some(gui.mux_window_id) {
+            gui.window
+                .hide_dropdown(Duration::from_millis(config.dropdown_animation_duration_ms));
+            false
+        } else {
+            gui.window.focus();
+            true
+        };
+        STATE.with(|state| state.borrow_mut().visible = visible);
+        return;
+    }
+
+    STATE.with(|state| state.borrow_mut().spawning = true);
+    promise::spawn::spawn(async move {
+        if let Err(err) = spawn_dropdown_window(&config).await {
+            log::error!("dropdown window: {:#}", err);
+            STATE.with(|state| state.borrow_mut().spawning = false);
+        }
+    })
+    .detach();
+}
+
+async fn spawn_dropdown_window(config: &ConfigHandle) -> anyhow::Result<()> {
+    let mux = Mux::get();
+    let dpi = config.dpi.unwrap_or_else(|| ::window::default_dpi());
+    let size = config.initial_size(dpi as u32, None);
+    let workspace = mux.active_workspace();
+    let (_tab, _pane, window_id) = mux
+        .spawn_tab_or_window(
+            None,
+            SpawnTabDomain::DefaultDomain,
+            None,
+            None,
+            size,
+            None,
+            workspace,
+            None,
+        )
+        .await?;
+    // The gui window is created once the mux reports the new window,
+    // after this returns, and consults this to slide itself into view
+    STATE.with(|state| {
+        *state.borrow_mut() = DropdownState {
+            mux_window_id: Some(window_id),
+            visible: true,
+            spawning: false,
+        };
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frame_is_within_the_visible_area() {
+        // A 1512x982 point screen at 2x, with a 37 point menu bar
+        // and the dock on the left
+        let visible = euclid::rect(140, 74, 2884, 1890);
+        assert_eq!(frame_within(visible, 50), euclid::rect(140, 74, 2884, 945));
+        assert_eq!(frame_within(visible, 100), visible);
+        assert_eq!(frame_within(visible, 200), visible);
+        assert_eq!(frame_within(visible, 0), euclid::rect(140, 74, 2884, 189));
+    }
+}
@@ -5,22 +5,49 @@ use crate::TermWindow;
 use ::window::*;
 use anyhow::{Context, Error};
 use config::keyassignment::{KeyAssignment, SpawnCommand};
-use config::{ConfigSubscription, NotificationHandling};
+use config::{ConfigSubscription, NotificationDelivery, NotificationHandling, SoundEvent};
 use mux::client::ClientId;
+use mux::connui::PromptRequest;
+use mux::domain::{DomainId, DomainState};
+use mux::localpane::LocalPane;
+use mux::pane::PaneId;
+use mux::tab::TabId;
 use mux::window::WindowId as MuxWindowId;
 use mux::{Mux, MuxNotification};
+use mux_lua::MuxPane;
 use promise::{Future, Promise};
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::process::{Command, Stdio};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use wezterm_term::{Alert, ClipboardSelection};
+use wezterm_client::domain::ClientDomain;
+use wezterm_term::{Alert, ClipboardSelection, Progress};
 use wezterm_toast_notification::*;
 
 pub const SET_DEFAULT_TERMINAL_EVENT: &str = "set-default-terminal";
 
+/// Called when a notification raised by a pane is clicked
+fn focus_pane_for_notification(token: String) {
+    let Ok(pane_id) = token.parse::<PaneId>() else {
+        return;
+    };
+    promise::spawn::spawn_into_main_thread(async move {
+        let mux = Mux::get();
+        if let Err(err) = mux.focus_pane_and_containing_tab(pane_id) {
+            log::error!("Failed to focus pane for notification: {err:#}");
+            return;
+        }
+        if let Some((_domain, window_id, _tab_id)) = mux.resolve_pane_id(pane_id) {
+            if let Some(gui_win) = front_end().gui_window_for_mux_window(window_id) {
+                gui_win.window.focus();
+            }
+        }
+    })
+    .detach();
+}
+
 pub struct GuiFrontEnd {
     connection: Rc<Connection>,
     switching_workspaces: RefCell<bool>,
@@ -28,6 +55,14 @@ pub struct GuiFrontEnd {
     known_windows: RefCell<BTreeMap<Window, MuxWindowId>>,
     client_id: Arc<ClientId>,
     config_subscription: RefCell<Option<ConfigSubscription>>,
+    /// The mux window whose gui window has keyboard focus, if any
+    focused_window: RefCell<Option<MuxWindowId>>,
+    /// The label most recently shown on the dock icon
+    dock_badge: RefCell<Option<String>>,
+    /// Mux windows that were spawned as native tabs, and the mux
+    /// window to whose native tab group each should be added once
+    /// its gui window has been created
+    native_tab_parents: RefCell<HashMap<MuxWindowId, MuxWindowId>>,
 }
 
 impl Drop for GuiFrontEnd {
@@ -38,6 +73,9 @@ impl Drop for GuiFrontEnd {
 
 lazy_static::lazy_static! {
     static ref FAST_CONFIG_SNAPSHOT: Mutex<Option<config::ConfigHandle>> = Mutex::new(None);
+    /// Remote domains that were detached when the system went to sleep,
+    /// and which should be re-attached when it wakes
+    static ref DETACHED_FOR_SLEEP: Mutex<Vec<DomainId>> = Mutex::new(vec![]);
 }
 
 fn fast_config_snapshot() -> config::ConfigHandle {
@@ -174,7 +212,11 @@ impl GuiFrontEnd {
             known_windows: RefCell::new(BTreeMap::new()),
             client_id: client_id.clone(),
             config_subscription: RefCell::new(None),
+            focused_window: RefCell::new(None),
+            dock_badge: RefCell::new(None),
+            native_tab_parents: RefCell::new(HashMap::new()),
         });
+        set_activation_handler(focus_pane_for_notification);
 
         mux.subscribe(move |n| {
             match n {
@@ -217,51 +259,65 @@ impl GuiFrontEnd {
                 MuxNotification::WindowTitleChanged { .. } => {}
                 MuxNotification::TabResized(_) => {}
                 MuxNotification::TabAddedToWindow { .. } => {}
-                MuxNotification::PaneRemoved(_) => {}
+                MuxNotification::PaneRemoved(_) => {
+                    // It may have been the pane that was reporting progress
+                    promise::spawn::spawn_into_main_thread(async move {
+                        front_end().update_dock_progress();
+                    })
+                    .detach();
+                }
                 MuxNotification::WindowInvalidated(_) => {}
                 MuxNotification::PaneOutput(_) => {}
                 MuxNotification::PaneAdded(_) => {}
+                MuxNotification::PaneMonitorTriggered { .. } => {
+                    // Handled by the window containing the pane
+                }
+                MuxNotification::ClientAttached(_) | MuxNotification::ClientDetached(_) => {}
+                MuxNotification::TlsCertificateExpiring {
+                    domain_name,
+                    days_remaining,
+                } => {
+                    promise::spawn::spawn_into_main_thread(async move {
+                        warn_certificate_expiring(domain_name, days_remaining);
+                    })
+                    .detach();
+                }
+                MuxNotification::PaneProcessExited { .. } => {}
+                MuxNotification::Alert {
+                    pane_id: _,
+                    alert: Alert::CommandComplete { .. },
+                } => {
+                    promise::spawn::spawn_into_main_thread(async move {
+                        crate::sound::play_event_sound(
+                            &config::configuration(),
+                            SoundEvent::CommandComplete,
+                        );
+                    })
+                    .detach();
+                }
                 MuxNotification::Alert {
                     pane_id,
-                    alert:
-                        Alert::ToastNotification {
-                            title,
-                            body,
-                            focus: _,
-                        },
+                    alert: Alert::ToastNotification { title, body, focus },
                 } => {
-                    let mux = Mux::get();
-
-                    if let Some((_domain, window_id, tab_id)) = mux.resolve_pane_id(pane_id) {
-                        let config = config::configuration();
-
-                        if let Some((_fdomain, f_window, f_tab, f_pane)) =
-                            mux.resolve_focused_pane(&client_id)
-                        {
-                            let show = match config.notification_handling {
-                                NotificationHandling::NeverShow => false,
-                                NotificationHandling::AlwaysShow => true,
-                                NotificationHandling::SuppressFromFocusedPane => f_pane != pane_id,
-                                NotificationHandling::SuppressFromFocusedTab => f_tab != tab_id,
-                                NotificationHandling::SuppressFromFocusedWindow => {
-                                    f_window != window_id
-                                }
-                            };
-
-                            if show {
-                                let message = if title.is_none() { "" } else { &body };
-                                let title = title.as_ref().unwrap_or(&body);
-                                // FIXME: if notification.focus is true, we should do
-                                // something here to arrange to focus pane_id when the
-                                // notification is clicked
-                                persistent_toast_notification(title, message);
-                            }
-                        }
-                    }
+                    let client_id = Arc::clone(&client_id);
+                    promise::spawn::spawn_into_main_thread(async move {
+                        front_end().deliver_notification(&client_id, pane_id, title, body, focus);
+                    })
+                    .detach();
+                }
+                MuxNotification::Alert {
+                    pane_id: _,
+                    alert: Alert::Progress(_),
+                } => {
+                    // The tab bar is updated via TermWindowNotif
+                    promise::spawn::spawn_into_main_thread(async move {
+                        front_end().update_dock_progress();
+                    })
+                    .detach();
                 }
                 MuxNotification::Alert {
                     pane_id: _,
-                    alert: Alert::Bell | Alert::Progress(_),
+                    alert: Alert::Bell,
                 } => {
                     // Handled via TermWindowNotif; NOP it here.
                 }
@@ -306,6 +362,35 @@ impl GuiFrontEnd {
                         log::error!("save_to_downloads: {:#}", err);
                     }
                 }
+                MuxNotification::ClipboardSynced {
+                    origin,
+                    selection,
+                    clipboard,
+                } => {
+                    if origin.as_ref() == Some(&client_id)
+                        || !config::configuration()
+                            .clipboard_sync
+                            .receives(clipboard.len())
+                    {
+                        return true;
+                    }
+                    promise::spawn::spawn_into_main_thread(async move {
+                        let fe = crate::frontend::front_end();
+                        log::trace!("clipboard synced from another client {:?}", selection);
+                        if let Some(window) = fe.known_windows.borrow().keys().next() {
+                            window.set_clipboard(
+                                match selection {
+                                    ClipboardSelection::Clipboard => Clipboard::Clipboard,
+                                    ClipboardSelection::PrimarySelection => {
+                                        Clipboard::PrimarySelection
+                                    }
+                                },
+                                clipboard,
+                            );
+                        }
+                    })
+                    .detach();
+                }
                 MuxNotification::AssignClipboard {
                     pane_id,
                     selection,
@@ -319,6 +404,13 @@ impl GuiFrontEnd {
                             selection,
                             clipboard
                         );
+                        if let (ClipboardSelection::Clipboard, Some(text)) =
+                            (selection, clipboard.as_ref())
+                        {
+                            if config::configuration().clipboard_history_include_osc52 {
+                                crate::clipboard_history::record(text);
+                            }
+                        }
                         if let Some(window) = fe.known_windows.borrow().keys().next() {
                             window.set_clipboard(
                                 match selection {
@@ -344,6 +436,7 @@ impl GuiFrontEnd {
             config::reload();
         }
         refresh_fast_config_snapshot();
+        crate::session::start_autosave();
 
         // Build the initial menu bar synchronously during startup.
         // AppKit may inspect menu item selectors during reopen events,
@@ -434,6 +527,36 @@ impl GuiFrontEnd {
             ApplicationEvent::OpenCommandScriptInTab(file_name) => {
                 Self::spawn_open_command_script(file_name, true);
             }
+            ApplicationEvent::ScreensChanged => {
+                if let Some(fe) = try_front_end() {
+                    for gui_win in fe.gui_windows() {
+                        gui_win.window.notify(TermWindowNotif::Apply(Box::new(|tw| {
+                            tw.apply_window_placement_rules()
+                        })));
+                    }
+                }
+            }
+            ApplicationEvent::WillSleep => {
+                let config = config::configuration();
+                if config.detach_remote_domains_on_sleep {
+                    detach_remote_domains(true);
+                }
+                if config.pause_local_panes_on_sleep {
+                    set_local_panes_paused(true);
+                }
+            }
+            ApplicationEvent::DidWake => {
+                // Resume unconditionally; the config may have changed
+                // while we were asleep
+                set_local_panes_paused(false);
+                reattach_remote_domains();
+            }
+            ApplicationEvent::WillPowerOff => {
+                if config::configuration().detach_remote_domains_on_sleep {
+                    detach_remote_domains(false);
+                }
+            }
+            ApplicationEvent::GlobalHotKeyPressed => crate::dropdown::toggle(),
             ApplicationEvent::PerformKeyAssignment(action) => {
                 // We should only get here when there are no windows open
                 // and the user picks an action from the menubar.
@@ -482,6 +605,8 @@ impl GuiFrontEnd {
                     KeyAssignment::ReloadConfiguration => {
                         // Manual reload is intentionally disabled.
                     }
+                    KeyAssignment::RestoreSession => crate::session::spawn_restore_previous(),
+                    KeyAssignment::ToggleDropdownWindow => crate::dropdown::toggle(),
                     KeyAssignment::QuitApplication => {
                         // If we get here, there are no windows that could have received
                         // the QuitApplication command, therefore it must be ok to quit
@@ -666,6 +791,145 @@ impl GuiFrontEnd {
         *self.switching_workspaces.borrow()
     }
 
+    /// Called by windows as they gain or lose keyboard focus
+    pub fn window_focus_changed(&self, mux_window_id: MuxWindowId, focused: bool) {
+        let mut current = self.focused_window.borrow_mut();
+        if focused {
+            current.replace(mux_window_id);
+        } else if *current == Some(mux_window_id) {
+            current.take();
+        }
+    }
+
+    /// Returns the mux window whose gui window has keyboard focus
+    pub fn focused_window(&self) -> Option<MuxWindowId> {
+        *self.focused_window.borrow()
+    }
+
+    /// Arranges for the gui window of `mux_window_id`, which has yet
+    /// to be created, to become a native tab of the window of `parent`
+    pub fn add_as_native_tab(&self, mux_window_id: MuxWindowId, parent: MuxWindowId) {
+        self.native_tab_parents
+            .borrow_mut()
+            .insert(mux_window_id, parent);
+    }
+
+    /// Returns the gui window whose native tab group the newly created
+    /// gui window of `mux_window_id` should join, if any
+    pub fn take_native_tab_parent(&self, mux_window_id: MuxWindowId) -> Option<Window> {
+        let parent = self
+            .native_tab_parents
+            .borrow_mut()
+            .remove(&mux_window_id)?;
+        self.gui_window_for_mux_window(parent).map(|gui| gui.window)
+    }
+
+    /// Shows the combined progress reported by the panes with
+    /// OSC 9;4 as the badge of the dock icon
+    fn update_dock_progress(&self) {
+        let label = if config::configuration().show_progress_in_dock {
+            match Mux::get().overall_progress() {
+                Progress::None => None,
+                Progress::Percentage(pct) => Some(format!("{pct}%")),
+                Progress::Error(pct) => Some(format!("{pct}% !")),
+                Progress::Indeterminate => Some("\u{2026}".to_string()),
+            }
+        } else {
+            None
+        };
+        let mut current = self.dock_badge.borrow_mut();
+        if *current != label {
+            self.connection.set_dock_badge(label.as_deref());
+            *current = label;
+        }
+    }
+
+    /// Delivers a notification raised by a pane with OSC 9 or OSC 777,
+    /// subject to `notification_handling` and `notification_delivery`
+    fn deliver_notification(
+        &self,
+        client_id: &ClientId,
+        pane_id: PaneId,
+        title: Option<String>,
+        body: String,
+        focus: bool,
+    ) {
+        let mux = Mux::get();
+        let Some((_domain, window_id, tab_id)) = mux.resolve_pane_id(pane_id) else {
+            return;
+        };
+        let config = config::configuration();
+
+        // The mux knows which pane is active, but only the gui knows
+        // whether the window containing it has keyboard focus
+        let window_focused = *self.focused_window.borrow() == Some(window_id);
+        let focused = match mux.resolve_focused_pane(client_id) {
+            Some((_fdomain, _f_window, f_tab, f_pane)) if window_focused => Some((f_tab, f_pane)),
+            _ => None,
+        };
+        if !notification_is_shown(
+            config.notification_handling,
+            window_focused,
+            focused,
+            tab_id,
+            pane_id,
+        ) {
+            return;
+        }
+
+        match config.notification_delivery {
+            NotificationDelivery::Toast => {
+                let message = if title.is_none() { "" } else { &body };
+                let title = title.as_ref().unwrap_or(&body);
+                ToastNotification {
+                    title: title.to_string(),
+                    message: message.to_string(),
+                    url: None,
+                    timeout: None,
+                    activation_token: focus.then(|| pane_id.to_string()),
+                }
+                .show();
+                crate::sound::play_event_sound(&config, SoundEvent::Notification);
+            }
+            NotificationDelivery::Bell => {
+                mux.notify(MuxNotification::Alert {
+                    pane_id,
+                    alert: Alert::Bell,
+                });
+            }
+            NotificationDelivery::Event => {
+                let Some(window) = self.gui_window_for_mux_window(window_id) else {
+                    return;
+                };
+
+                async fn emit_pane_notification(
+                    lua: Option<Rc<mlua::Lua>>,
+                    window: GuiWin,
+                    pane: MuxPane,
+                    title: Option<String>,
+                    body: String,
+                ) -> anyhow::Result<()> {
+                    if let Some(lua) = lua {
+                        let args = lua.pack_multi((window, pane, title, body))?;
+                        if let Err(err) =
+                            config::lua::emit_event(&lua, ("pane-notification".to_string(), args))
+                                .await
+                        {
+                            log::error!("while processing pane-notification event: {:#}", err);
+                        }
+                    }
+                    Ok(())
+                }
+
+                let pane = MuxPane(pane_id);
+                promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+                    emit_pane_notification(lua, window, pane, title, body)
+                }))
+                .detach();
+            }
+        }
+    }
+
     pub fn gui_window_for_mux_window(&self, mux_window_id: MuxWindowId) -> Option<GuiWin> {
         let windows = self.known_windows.borrow();
         for (window, v) in windows.iter() {
@@ -731,6 +995,7 @@ pub fn try_new() -> Result<Rc<GuiFrontEnd>, Error> {
             // refresh asynchronously to avoid re-locking config here.
             promise::spawn::spawn_into_main_thread(async {
                 refresh_fast_config_snapshot();
+                crate::dropdown::update_hotkey(&config::configuration());
             })
             .detach();
             // TODO(macos): AppKit does not allow safe async menubar reconstruction
@@ -752,5 +1017,195 @@ pub fn try_new() -> Result<Rc<GuiFrontEnd>, Error> {
         .borrow_mut()
         .replace(config_subscription);
 
+    wezterm_font::set_missing_glyph_handler(emit_missing_glyphs_event);
+    mux::connui::set_prompt_handler(show_connection_prompt);
+    crate::dropdown::update_hotkey(&config::configuration());
+
     Ok(front_end)
 }
+
+/// Shows the prompts of a connection, such as a password or the
+/// one-time code of two factor authentication, as an overlay in the
+/// focused window, or in the first window if none has the focus
+fn show_connection_prompt(request: PromptRequest) {
+    promise::spawn::spawn_into_main_thread(async move {
+        let fe = front_end();
+        let gui = fe
+            .focused_window()
+            .and_then(|mux_window_id| fe.gui_window_for_mux_window(mux_window_id))
+            .or_else(|| fe.gui_windows().into_iter().next());
+        match gui {
+            Some(gui) => {
+                gui.window.focus();
+                gui.window
+                    .notify(TermWindowNotif::Apply(Box::new(move |tw| {
+                        tw.show_connection_prompt(request);
+                    })));
+            }
+            None => {
+                let mut respond = request.respond;
+                respond.result(Err(anyhow::anyhow!("there is no window to prompt in")));
+            }
+        }
+    })
+    .detach();
+}
+
+/// Shows a toast and emits the `tls-certificate-expiring` event with
+/// the name of the domain and the number of days until its client
+/// certificate expires
+fn warn_certificate_expiring(domain_name: String, days_remaining: i64) {
+    let message = if days_remaining < 0 {
+        format!("The client certificate for {domain_name} has expired")
+    } else {
+        format!("The client certificate for {domain_name} expires in {days_remaining} days")
+    };
+    log::warn!("{message}");
+    persistent_toast_notification("TLS Certificate", &message);
+
+    promise::spawn::spawn(async move {
+        let result = config::with_lua_config_on_main_thread(move |lua| async move {
+            if let Some(lua) = lua {
+                let args = lua.pack_multi((domain_name, days_remaining))?;
+                config::lua::emit_event(&lua, ("tls-certificate-expiring".to_string(), args))
+                    .await?;
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(err) = result {
+            log::error!("while processing tls-certificate-expiring event: {err:#}");
+        }
+    })
+    .detach();
+}
+
+/// Emits the `missing-glyphs` event with a list of the codepoints, in
+/// `U+XXXX` form, that no font could display
+fn emit_missing_glyphs_event(codepoints: Vec<char>) {
+    promise::spawn::spawn_into_main_thread(async move {
+        let result = config::with_lua_config_on_main_thread(move |lua| async move {
+            if let Some(lua) = lua {
+                let codepoints: Vec<String> = codepoints
+                    .iter()
+                    .map(|&c| format!("U+{:04X}", c as u32))
+                    .collect();
+                let args = lua.pack_multi(codepoints)?;
+                config::lua::emit_event(&lua, ("missing-glyphs".to_string(), args)).await?;
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(err) = result {
+            log::error!("while processing missing-glyphs event: {err:#}");
+        }
+    })
+    .detach();
+}
+
+/// Detaches the attached client domains so that their mux servers see
+/// the connection close cleanly.  When `reattach_on_wake` is true they
+/// will be re-attached by `reattach_remote_domains`.
+fn detach_remote_domains(reattach_on_wake: bool) {
+    let mut detached = DETACHED_FOR_SLEEP.lock().unwrap();
+    for domain in Mux::get().iter_domains() {
+        if domain.downcast_ref::<ClientDomain>().is_none()
+            || domain.state() != DomainState::Attached
+        {
+            continue;
+        }
+        log::info!("detaching domain {} for sleep", domain.domain_name());
+        match domain.detach() {
+            Ok(()) if reattach_on_wake => detached.push(domain.domain_id()),
+            Ok(()) => {}
+            Err(err) => log::error!("detaching domain {}: {err:#}", domain.domain_name()),
+        }
+    }
+}
+
+fn reattach_remote_domains() {
+    let domains = std::mem::take(&mut *DETACHED_FOR_SLEEP.lock().unwrap());
+    if domains.is_empty() {
+        return;
+    }
+    promise::spawn::spawn(async move {
+        let mux = Mux::get();
+        for domain_id in domains {
+            let Some(domain) = mux.get_domain(domain_id) else {
+                continue;
+            };
+            if domain.state() == DomainState::Attached {
+                continue;
+            }
+            if let Err(err) = domain.attach(None).await {
+                log::error!(
+                    "re-attaching domain {} after wake: {err:#}",
+                    domain.domain_name()
+                );
+            }
+        }
+    })
+    .detach();
+}
+
+/// Stops or continues the processes running in local panes
+fn set_local_panes_paused(paused: bool) {
+    for pane in Mux::get().iter_panes() {
+        if let Some(local) = pane.downcast_ref::<LocalPane>() {
+            if paused {
+                local.pause_processes();
+            } else {
+                local.resume_processes();
+            }
+        }
+    }
+}
+
+/// Returns true if `handling` shows a notification from `pane_id` in
+/// `tab_id`, given whether the window containing them has keyboard
+/// focus and, if it does, which tab and pane are active in it
+fn notification_is_shown(
+    handling: NotificationHandling,
+    window_focused: bool,
+    focused: Option<(TabId, PaneId)>,
+    tab_id: TabId,
+    pane_id: PaneId,
+) -> bool {
+    match handling {
+        NotificationHandling::NeverShow => false,
+        NotificationHandling::AlwaysShow => true,
+        NotificationHandling::SuppressFromFocusedPane => {
+            focused.map(|(_, pane)| pane) != Some(pane_id)
+        }
+        NotificationHandling::SuppressFromFocusedTab => focused.map(|(tab, _)| tab) != Some(tab_id),
+        NotificationHandling::SuppressFromFocusedWindow => !window_focused,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn notification_handling() {
+        use NotificationHandling::*;
+        // Pane 2 in tab 1 raises the notification
+        let shown = |handling, window_focused, focused| {
+            notification_is_shown(handling, window_focused, focused, 1, 2)
+        };
+
+        assert!(shown(AlwaysShow, true, Some((1, 2))));
+        assert!(!shown(NeverShow, false, None));
+
+        assert!(!shown(SuppressFromFocusedPane, true, Some((1, 2))));
+        assert!(shown(SuppressFromFocusedPane, true, Some((1, 3))));
+        assert!(shown(SuppressFromFocusedPane, false, None));
+
+        assert!(!shown(SuppressFromFocusedTab, true, Some((1, 3))));
+        assert!(shown(SuppressFromFocusedTab, true, Some((4, 5))));
+        assert!(shown(SuppressFromFocusedTab, false, None));
+
+        assert!(!shown(SuppressFromFocusedWindow, true, Some((4, 5))));
+        assert!(shown(SuppressFromFocusedWindow, false, None));
+    }
+}
@@ -531,12 +531,9 @@ impl DecodedImage {
                 }
             },
             ImageDataType::AnimRgba8 { durations, .. } => {
-                let current_frame = if durations.len() > 1 && durations[0].as_millis() == 0 {
-                    // Skip possible 0-duration root frame
-                    1
-                } else {
-                    0
-                };
+                // Skip the possible 0-duration root frame and any other
+                // gapless frames
+                let current_frame = durations.iter().position(|d| !d.is_zero()).unwrap_or(0);
                 Self {
                     frame_start: RefCell::new(Instant::now()),
                     current_frame: RefCell::new(current_frame),
@@ -695,6 +692,9 @@ impl GlyphCache {
         let config = self.fonts.config();
         self.image_cache.update_config(&config);
         self.cursor_glyphs.clear();
+        // The paths for these may have changed
+        self.block_glyphs
+            .retain(|key, _| !matches!(key.block, BlockKey::UserPath(_)));
     }
 
     /// Perform the load and render of a glyph
@@ -973,13 +973,14 @@ impl GlyphCache {
                     let mut next_due = *decoded_frame_start
                         + durations[*decoded_current_frame].max(min_frame_duration);
                     if now >= next_due {
-                        // Advance to next frame
-                        *decoded_current_frame = *decoded_current_frame + 1;
-                        if *decoded_current_frame >= frames.len() {
-                            *decoded_current_frame = 0;
-                            // Skip potential 0-duration root frame
-                            if durations[0].as_millis() == 0 && frames.len() > 1 {
-                                *decoded_current_frame = *decoded_current_frame + 1;
+                        // Advance to next frame, skipping over the
+                        // potential 0-duration root frame and any other
+                        // gapless frames; those only exist to be composed
+                        // into the frames that follow them.
+                        for _ in 0..frames.len() {
+                            *decoded_current_frame = (*decoded_current_frame + 1) % frames.len();
+                            if !durations[*decoded_current_frame].is_zero() {
+                                break;
                             }
                         }
                         *decoded_frame_start = now;
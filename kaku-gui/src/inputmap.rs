@@ -3,7 +3,7 @@ use config::keyassignment::{
     ClipboardCopyDestination, ClipboardPasteSource, KeyAssignment, KeyTableEntry, KeyTables,
     MouseEventTrigger, SelectionMode,
 };
-use config::{ConfigHandle, MouseEventAltScreen, MouseEventTriggerMods};
+use config::{ConfigHandle, MouseEventAltScreen, MouseEventTriggerMods, MouseEventZone};
 use std::collections::HashMap;
 use std::time::Duration;
 use wezterm_term::input::MouseButton;
@@ -12,7 +12,9 @@ use window::{KeyCode, Modifiers, PhysKeyCode, UIKeyCapRendering};
 pub struct InputMap {
     pub keys: KeyTables,
     pub mouse: HashMap<(MouseEventTrigger, MouseEventTriggerMods), KeyAssignment>,
-    leader: Option<(KeyCode, Modifiers, Duration)>,
+    /// Bindings for the tab bar and the pane borders
+    zone_mouse: HashMap<(MouseEventZone, MouseEventTrigger, Modifiers), KeyAssignment>,
+    leader: Vec<(KeyCode, Modifiers, Duration)>,
 }
 
 impl InputMap {
@@ -26,13 +28,18 @@ impl InputMap {
 
         let mut keys = config.key_bindings();
 
-        let leader = config.leader.as_ref().map(|leader| {
-            (
-                leader.key.key.resolve(config.key_map_preference).clone(),
-                leader.key.mods,
-                Duration::from_millis(leader.timeout_milliseconds),
-            )
-        });
+        let leader = config
+            .leader
+            .0
+            .iter()
+            .map(|leader| {
+                (
+                    leader.key.key.resolve(config.key_map_preference).clone(),
+                    leader.key.mods,
+                    Duration::from_millis(leader.timeout_milliseconds),
+                )
+            })
+            .collect();
 
         let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
 
@@ -79,9 +86,10 @@ impl InputMap {
                 {
                     continue;
                 }
-                keys.default
-                    .entry((code, mods))
-                    .or_insert(KeyTableEntry { action });
+                keys.default.entry((code, mods)).or_insert(KeyTableEntry {
+                    action,
+                    metadata: Default::default(),
+                });
             }
         }
 
@@ -377,6 +385,8 @@ impl InputMap {
             .retain(|_, v| v.action != KeyAssignment::DisableDefaultAssignment);
 
         mouse.retain(|_, v| *v != KeyAssignment::DisableDefaultAssignment);
+        let mut zone_mouse = config.zone_mouse_bindings();
+        zone_mouse.retain(|_, v| *v != KeyAssignment::DisableDefaultAssignment);
         // Expand MouseEventAltScreen::Any to individual True/False entries
         let mut expanded_mouse = vec![];
         for ((code, mods), v) in &mouse {
@@ -405,11 +415,15 @@ impl InputMap {
         keys.by_name
             .entry("search_mode".to_string())
             .or_insert_with(crate::overlay::copy::search_key_table);
+        keys.by_name
+            .entry(crate::termwindow::resizemode::RESIZE_PANE_TABLE.to_string())
+            .or_insert_with(|| crate::termwindow::resizemode::resize_pane_key_table(config));
 
         Self {
             keys,
             leader,
             mouse,
+            zone_mouse,
         }
     }
 
@@ -450,12 +464,11 @@ impl InputMap {
     }
 
     pub fn is_leader(&self, key: &KeyCode, mods: Modifiers) -> Option<std::time::Duration> {
-        if let Some((leader_key, leader_mods, timeout)) = self.leader.as_ref() {
-            if *leader_key == *key && *leader_mods == mods.remove_positional_mods() {
-                return Some(*timeout);
-            }
-        }
-        None
+        let mods = mods.remove_positional_mods();
+        self.leader
+            .iter()
+            .find(|(leader_key, leader_mods, _)| *leader_key == *key && *leader_mods == mods)
+            .map(|(_, _, timeout)| *timeout)
     }
 
     pub fn has_table(&self, name: &str) -> bool {
@@ -486,6 +499,17 @@ impl InputMap {
         mods.mods = mods.mods.remove_positional_mods();
         self.mouse.get(&(event, mods)).cloned()
     }
+
+    pub fn lookup_zone_mouse(
+        &self,
+        zone: MouseEventZone,
+        event: MouseEventTrigger,
+        mods: Modifiers,
+    ) -> Option<KeyAssignment> {
+        self.zone_mouse
+            .get(&(zone, event, mods.remove_positional_mods()))
+            .cloned()
+    }
 }
 
 pub fn ui_key(key: &KeyCode, ui_key_cap_rendering: UIKeyCapRendering) -> String {
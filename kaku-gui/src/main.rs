@@ -27,10 +27,13 @@ use wezterm_gui_subcommands::{name_equals_value, StartCommand};
 use wezterm_mux_server_impl::update_mux_domains;
 use wezterm_toast_notification::*;
 
+mod clipboard_history;
 mod colorease;
 mod commands;
 mod customglyph;
+mod diskshapecache;
 mod download;
+mod dropdown;
 mod frontend;
 mod glyphcache;
 mod inputmap;
@@ -41,7 +44,9 @@ mod resize_increment_calculator;
 mod scripting;
 mod scrollbar;
 mod selection;
+mod session;
 mod shapecache;
+mod sound;
 mod spawn;
 mod stats;
 mod tabbar;
@@ -56,7 +61,9 @@ mod utilsprites;
 static ALLOC: dhat::Alloc = dhat::Alloc;
 
 pub use selection::SelectionMode;
-pub use termwindow::{set_window_class, set_window_position, TermWindow, ICON_DATA};
+pub use termwindow::{
+    set_window_class, set_window_geometry, set_window_position, TermWindow, ICON_DATA,
+};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -284,6 +291,12 @@ async fn async_run_terminal_gui(
     if let Err(err) = spawn_mux_server(unix_socket_path, should_publish) {
         log::warn!("{:#}", err);
     }
+    if let Err(err) = wezterm_mux_server_impl::notifier::start() {
+        log::warn!("{:#}", err);
+    }
+    if let Err(err) = wezterm_mux_server_impl::discovered_domains::start() {
+        log::warn!("{:#}", err);
+    }
 
     let default_domain_is_local = Mux::get().default_domain().domain_name() == "local";
     if default_domain_is_local {
@@ -329,17 +342,36 @@ async fn async_run_terminal_gui(
     let mux = Mux::get();
 
     let domain = if let Some(name) = &opts.domain {
-        let domain = mux
-            .get_domain_by_name(name)
-            .ok_or_else(|| anyhow!("invalid domain {name}"))?;
+        let domain = match mux.get_domain_by_name(name) {
+            Some(domain) => domain,
+            None => wezterm_mux_server_impl::discovered_domains::discover_domain(name)
+                .await?
+                .ok_or_else(|| anyhow!("invalid domain {name}"))?,
+        };
         Some(domain)
     } else {
         None
     };
 
+    let restored = opts.restore_session
+        && match session::restore_previous().await {
+            Ok(true) => true,
+            Ok(false) => {
+                log::warn!("There is no saved session to restore");
+                false
+            }
+            Err(err) => {
+                log::error!("Failed to restore the session: {:#}", err);
+                false
+            }
+        };
+
     if !opts.attach {
         trigger_and_log_gui_startup(spawn_command).await;
     }
+    if restored {
+        return Ok(());
+    }
 
     let is_connecting = opts.attach;
 
@@ -625,6 +657,9 @@ fn run_terminal_gui(opts: StartCommand, default_domain_name: Option<String>) ->
     if let Some(pos) = opts.position.as_ref() {
         set_window_position(pos.clone());
     }
+    if let Some(geometry) = opts.geometry.as_ref() {
+        set_window_geometry(geometry.clone());
+    }
 
     let config = config::configuration();
 
@@ -700,7 +735,9 @@ fn run_terminal_gui(opts: StartCommand, default_domain_name: Option<String>) ->
     .detach();
 
     maybe_show_configuration_error_window();
-    gui.run_forever()
+    let result = gui.run_forever();
+    crate::diskshapecache::save(config::configuration().persistent_shape_cache_size);
+    result
 }
 
 fn fatal_toast_notification(title: &str, message: &str) {
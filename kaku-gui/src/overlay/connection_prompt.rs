@@ -0,0 +1,54 @@
+//! Presents the prompts of a connection, such as the password and the
+//! one-time codes that an ssh server asks for, over the active tab of a
+//! window rather than in a separate connection window.
+use mux::connui::{PasswordPromptHost, PromptField};
+use mux::termwiztermtab::TermWizTerminal;
+use termwiz::lineedit::*;
+use termwiz::surface::Change;
+use termwiz::terminal::Terminal;
+
+fn render_lines(term: &mut TermWizTerminal, text: &str) -> anyhow::Result<()> {
+    let mut text = text.replace("\r\n", "\n").replace("\n", "\r\n");
+    text.push_str("\r\n");
+    term.render(&[Change::Text(text)])?;
+    Ok(())
+}
+
+/// Reads an answer for each of `fields` in turn, obscuring those that
+/// are not echoed.  Fails if any of them is cancelled.
+pub fn show_connection_prompt_overlay(
+    mut term: TermWizTerminal,
+    instructions: String,
+    fields: Vec<PromptField>,
+) -> anyhow::Result<Vec<String>> {
+    term.no_grab_mouse_in_raw_mode();
+    if !instructions.is_empty() {
+        render_lines(&mut term, &instructions)?;
+    }
+
+    let mut answers = vec![];
+    for field in fields {
+        // The line editor only handles a single line prompt, so any
+        // preceding lines are output above it
+        let (preamble, prompt) = match field.prompt.rsplit_once('\n') {
+            Some((preamble, prompt)) => (Some(preamble), prompt),
+            None => (None, field.prompt.as_str()),
+        };
+        if let Some(preamble) = preamble {
+            render_lines(&mut term, preamble)?;
+        }
+
+        let mut editor = LineEditor::new(&mut term);
+        editor.set_prompt(prompt);
+        let line = if field.echo {
+            editor.read_line(&mut NopLineEditorHost::default())?
+        } else {
+            editor.read_line(&mut PasswordPromptHost::default())?
+        };
+        match line {
+            Some(line) => answers.push(line),
+            None => anyhow::bail!("prompt was cancelled"),
+        }
+    }
+    Ok(answers)
+}
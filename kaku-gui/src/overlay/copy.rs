@@ -3,7 +3,7 @@ use crate::termwindow::keyevent::KeyTableArgs;
 use crate::termwindow::{TermWindow, TermWindowNotif};
 use config::keyassignment::{
     ClipboardCopyDestination, CopyModeAssignment, KeyAssignment, KeyTable, KeyTableEntry,
-    ScrollbackEraseMode, SelectionMode,
+    ScrollbackEraseMode, SearchDirection, SearchMatchType, SelectionMode,
 };
 use mux::domain::DomainId;
 use mux::pane::{
@@ -19,7 +19,7 @@ use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
-use termwiz::cell::{Cell, CellAttributes};
+use termwiz::cell::{Cell, CellAttributes, Underline};
 use termwiz::color::AnsiColor;
 use termwiz::lineedit::{LineEditBuffer, Movement};
 use termwiz::surface::{CursorVisibility, SequenceNo, SEQ_ZERO};
@@ -34,9 +34,31 @@ use window::{KeyCode as WKeyCode, Modifiers, WindowOps};
 
 lazy_static::lazy_static! {
     static ref SAVED_PATTERN: Mutex<HashMap<TabId, Pattern>> = Mutex::new(HashMap::new());
+    /// Text yanked into named registers; see `SelectRegister`
+    static ref REGISTERS: Mutex<HashMap<char, String>> = Mutex::new(HashMap::new());
 }
 
 const SEARCH_CHUNK_SIZE: StableRowIndex = 1000;
+/// How far `%` and the bracket text objects look for a matching bracket
+const MAX_BRACKET_ROWS: StableRowIndex = 500;
+const BRACKETS: [(&str, &str); 4] = [("(", ")"), ("[", "]"), ("{", "}"), ("<", ">")];
+
+/// Stores text that was yanked into a register.  As in vim, an
+/// uppercase name appends to the register of the lowercase name.
+pub fn store_register(name: char, text: String) {
+    let mut registers = REGISTERS.lock();
+    if name.is_uppercase() {
+        let name = name.to_ascii_lowercase();
+        registers.entry(name).or_default().push_str(&text);
+    } else {
+        registers.insert(name, text);
+    }
+}
+
+/// Returns the text stored in a register
+pub fn register_text(name: char) -> Option<String> {
+    REGISTERS.lock().get(&name.to_ascii_lowercase()).cloned()
+}
 
 pub struct CopyOverlay {
     delegate: Arc<dyn Pane>,
@@ -50,6 +72,38 @@ struct PendingJump {
     prev_char: bool,
 }
 
+/// What `SelectTextObject` selects, named by the key that follows it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TextObject {
+    /// `w`: a run of word characters, or of other punctuation
+    Word,
+    /// `W`: a run of non-whitespace
+    BigWord,
+    /// `l`: the current line
+    Line,
+    /// `(`, `[`, `{` or `<`, or their closing brackets; also `b` and `B`
+    Bracket(&'static str, &'static str),
+}
+
+impl TextObject {
+    fn from_key(c: char) -> Option<Self> {
+        match c {
+            'w' => Some(Self::Word),
+            'W' => Some(Self::BigWord),
+            'l' => Some(Self::Line),
+            'b' => Some(Self::Bracket("(", ")")),
+            'B' => Some(Self::Bracket("{", "}")),
+            c => {
+                let c = c.to_string();
+                BRACKETS
+                    .iter()
+                    .find(|(open, close)| *open == c || *close == c)
+                    .map(|&(open, close)| Self::Bracket(open, close))
+            }
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Jump {
     forward: bool,
@@ -85,6 +139,22 @@ struct CopyRenderable {
     searching: Option<Searching>,
     pending_jump: Option<PendingJump>,
     last_jump: Option<Jump>,
+    /// The start of a match to select once the search finds it,
+    /// in place of the one nearest the bottom
+    wanted_match: Option<(StableRowIndex, usize)>,
+    /// Set when the pattern is a regex with capture groups.  The
+    /// results are then the captured text, and the rest of each match
+    /// is underlined around them.
+    capture_regex: Option<fancy_regex::Regex>,
+    /// Set by `SelectTextObject` until the key naming the object is
+    /// pressed; true for the inner variant
+    pending_text_object: Option<bool>,
+    /// Set by `SelectRegister` until the key naming the register is
+    /// pressed
+    pending_register: bool,
+    /// The register that the next yank stores into, in place of
+    /// the clipboard
+    register: Option<char>,
 }
 
 struct Searching {
@@ -162,6 +232,11 @@ impl CopyOverlay {
             searching: None,
             pending_jump: None,
             last_jump: None,
+            wanted_match: None,
+            capture_regex: None,
+            pending_text_object: None,
+            pending_register: false,
+            register: None,
         };
 
         let search_row = render.compute_search_row();
@@ -202,6 +277,36 @@ impl CopyOverlay {
         render.dirty_results.add(search_row);
     }
 
+    /// Selects the match that starts at `x`, `y` rather than the
+    /// one nearest the bottom, waiting for the search to reach it
+    /// if necessary
+    pub fn activate_match_at(&self, y: StableRowIndex, x: usize) {
+        let mut render = self.render.lock();
+        render.wanted_match.replace((y, x));
+        render.activate_wanted_match();
+    }
+
+    /// Returns the starting rows of the current search matches
+    pub fn search_result_rows(&self) -> Vec<StableRowIndex> {
+        let render = self.render.lock();
+        render.results.iter().map(|res| res.start_y).collect()
+    }
+
+    /// Returns the register chosen for the next yank, if any,
+    /// clearing the choice
+    pub fn take_register(&self) -> Option<char> {
+        self.render.lock().register.take()
+    }
+
+    /// Returns the starting row of the selected search match
+    pub fn active_search_result_row(&self) -> Option<StableRowIndex> {
+        let render = self.render.lock();
+        render
+            .result_pos
+            .and_then(|pos| render.results.get(pos))
+            .map(|res| res.start_y)
+    }
+
     pub fn viewport_changed(&self, viewport: Option<StableRowIndex>) {
         let mut render = self.render.lock();
         if render.viewport != viewport {
@@ -308,6 +413,12 @@ impl CopyRenderable {
         self.results.clear();
         self.by_line.clear();
         self.result_pos.take();
+        self.capture_regex = match &self.get_pattern() {
+            Pattern::Regex(re) => fancy_regex::Regex::new(re)
+                .ok()
+                .filter(|re| re.captures_len() > 1),
+            _ => None,
+        };
 
         SAVED_PATTERN.lock().insert(self.tab_id, self.get_pattern());
 
@@ -370,7 +481,9 @@ impl CopyRenderable {
         let is_first = self.results.is_empty();
         self.incrementally_recompute_results(results);
 
-        if is_first {
+        if self.wanted_match.is_some() {
+            self.activate_wanted_match();
+        } else if is_first {
             if !self.results.is_empty() {
                 self.activate_match_number(0);
             } else {
@@ -382,6 +495,10 @@ impl CopyRenderable {
         let dims = self.delegate.get_dimensions();
         if range.start == dims.scrollback_top {
             self.searching.take();
+            if self.wanted_match.take().is_some() && !self.results.is_empty() {
+                // It has scrolled away or the output has changed
+                self.activate_match_number(0);
+            }
             return;
         }
 
@@ -429,6 +546,21 @@ impl CopyRenderable {
             })));
     }
 
+    fn activate_wanted_match(&mut self) {
+        let (y, x) = match self.wanted_match {
+            Some(wanted) => wanted,
+            None => return,
+        };
+        if let Some(n) = self
+            .results
+            .iter()
+            .position(|result| result.start_y == y && result.start_x == x)
+        {
+            self.wanted_match.take();
+            self.activate_match_number(n);
+        }
+    }
+
     fn activate_match_number(&mut self, n: usize) {
         self.result_pos.replace(n);
         let result = self.results[n].clone();
@@ -640,6 +772,104 @@ impl CopyRenderable {
         }
     }
 
+    /// Describes the pattern and the selected match for the search bar
+    fn search_bar_text(&self, pattern: &Pattern) -> String {
+        let mode = match pattern {
+            Pattern::CaseSensitiveString(_) => "case-sensitive",
+            Pattern::CaseInSensitiveString(_) => "ignore-case",
+            Pattern::Regex(_) => "regex",
+        };
+        let counter = if self.results.is_empty() {
+            "no matches".to_string()
+        } else {
+            format!(
+                "match {}/{}",
+                self.result_pos.map(|x| x + 1).unwrap_or(0),
+                self.results.len()
+            )
+        };
+        let remain = match &self.searching {
+            Some(Searching { remain, .. }) => format!(", searching {remain} lines"),
+            None => String::new(),
+        };
+        format!("Search: {} ({counter}, {mode}{remain})", **pattern)
+    }
+
+    /// Colors the search matches on the line at `stable_idx`
+    fn highlight_matches(
+        &self,
+        line: &mut Line,
+        stable_idx: StableRowIndex,
+        colors: &config::Palette,
+    ) {
+        if let Some(re) = &self.capture_regex {
+            let mut text = String::new();
+            let mut cells = vec![];
+            for cell in line.visible_cells() {
+                cells.push((
+                    text.len(),
+                    cell.cell_index()..cell.cell_index() + cell.width(),
+                ));
+                text.push_str(cell.str());
+            }
+            for captures in re.captures_iter(&text).flatten() {
+                let whole = match captures.get(0) {
+                    Some(whole) => whole,
+                    None => continue,
+                };
+                for (byte_idx, cell_range) in &cells {
+                    if !(whole.start()..whole.end()).contains(byte_idx) {
+                        continue;
+                    }
+                    for cell_idx in cell_range.clone() {
+                        if let Some(cell) = line.cells_mut_for_attr_changes_only().get_mut(cell_idx)
+                        {
+                            cell.attrs_mut().set_underline(Underline::Single);
+                        }
+                    }
+                }
+            }
+        }
+
+        let matches = match self.by_line.get(&stable_idx) {
+            Some(matches) => matches,
+            None => return,
+        };
+        for m in matches {
+            for cell_idx in m.range.clone() {
+                if let Some(cell) = line.cells_mut_for_attr_changes_only().get_mut(cell_idx) {
+                    if Some(m.result_index) == self.result_pos {
+                        cell.attrs_mut()
+                            .set_background(
+                                colors
+                                    .copy_mode_active_highlight_bg
+                                    .unwrap_or(AnsiColor::Yellow.into()),
+                            )
+                            .set_foreground(
+                                colors
+                                    .copy_mode_active_highlight_fg
+                                    .unwrap_or(AnsiColor::Black.into()),
+                            )
+                            .set_reverse(false);
+                    } else {
+                        cell.attrs_mut()
+                            .set_background(
+                                colors
+                                    .copy_mode_inactive_highlight_bg
+                                    .unwrap_or(AnsiColor::Fuchsia.into()),
+                            )
+                            .set_foreground(
+                                colors
+                                    .copy_mode_inactive_highlight_fg
+                                    .unwrap_or(AnsiColor::Black.into()),
+                            )
+                            .set_reverse(false);
+                    }
+                }
+            }
+        }
+    }
+
     fn get_pattern(&self) -> Pattern {
         let pattern = self.search_line.get_line().to_string();
         match self.pattern_type {
@@ -701,6 +931,29 @@ impl CopyRenderable {
         self.schedule_update_search();
     }
 
+    fn set_match_type(&mut self, match_type: SearchMatchType) {
+        let pattern_type = match match_type {
+            SearchMatchType::CaseSensitive => PatternType::CaseSensitiveString,
+            SearchMatchType::CaseInsensitive => PatternType::CaseInSensitiveString,
+            SearchMatchType::Regex => PatternType::Regex,
+        };
+        if self.pattern_type != pattern_type {
+            self.pattern_type = pattern_type;
+            self.schedule_update_search();
+        }
+    }
+
+    /// Moves through the matches in the direction of `search_direction`,
+    /// or the opposite way if `reverse` is true
+    fn advance_match(&mut self, reverse: bool) {
+        let forward = config::configuration().search_direction == SearchDirection::Forward;
+        if forward != reverse {
+            self.next_match();
+        } else {
+            self.prior_match();
+        }
+    }
+
     fn move_to_viewport_middle(&mut self) {
         let dims = self.dimensions();
         self.cursor.y = dims.top + (dims.dims.viewport_rows as isize) / 2;
@@ -1098,6 +1351,242 @@ impl CopyRenderable {
         self.start.take();
         self.clear_selection();
     }
+
+    /// Selects from `start` to `end`, leaving the cursor at `end`
+    fn select_range(
+        &mut self,
+        mode: SelectionMode,
+        start: (usize, StableRowIndex),
+        end: (usize, StableRowIndex),
+    ) {
+        self.selection_mode = mode;
+        self.start
+            .replace(SelectionCoordinate::x_y(start.0, start.1));
+        self.cursor.x = end.0;
+        self.cursor.y = end.1;
+        self.select_to_cursor_pos();
+    }
+
+    /// Returns the cells of `rows` as (x, y, text), in order, along
+    /// with the index of the cell under the cursor
+    fn cells_around_cursor(
+        &self,
+        rows: Range<StableRowIndex>,
+    ) -> (Vec<(usize, StableRowIndex, String)>, Option<usize>) {
+        let (top, lines) = self.delegate.get_lines(rows);
+        let mut cells = vec![];
+        for (idx, line) in lines.iter().enumerate() {
+            let y = top + idx as StableRowIndex;
+            for cell in line.visible_cells() {
+                cells.push((cell.cell_index(), y, cell.str().to_string()));
+            }
+        }
+        let cursor = cells.iter().rposition(|(x, y, _)| {
+            *y < self.cursor.y || (*y == self.cursor.y && *x <= self.cursor.x)
+        });
+        let cursor = cursor.filter(|&idx| cells[idx].1 == self.cursor.y);
+        (cells, cursor)
+    }
+
+    /// The rows that are searched for a matching bracket
+    fn bracket_rows(&self) -> Range<StableRowIndex> {
+        let dims = self.delegate.get_dimensions();
+        let end = dims.scrollback_top + dims.scrollback_rows as StableRowIndex;
+        let y = self.cursor.y;
+        y.saturating_sub(MAX_BRACKET_ROWS).max(dims.scrollback_top)
+            ..(y + MAX_BRACKET_ROWS + 1).min(end)
+    }
+
+    fn select_text_object(&mut self, object: TextObject, inner: bool) -> bool {
+        match object {
+            TextObject::Word => self.select_word_object(inner, false),
+            TextObject::BigWord => self.select_word_object(inner, true),
+            TextObject::Line => self.select_line_object(inner),
+            TextObject::Bracket(open, close) => self.select_bracket_object(open, close, inner),
+        }
+    }
+
+    fn select_word_object(&mut self, inner: bool, big: bool) -> bool {
+        let y = self.cursor.y;
+        let (cells, cursor) = self.cells_around_cursor(y..y + 1);
+        let idx = match cursor {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        // 0 for whitespace, otherwise the kind of word
+        let class = |idx: usize| -> u8 {
+            let c = cells[idx].2.chars().next().unwrap_or(' ');
+            if c.is_whitespace() {
+                0
+            } else if big || c.is_alphanumeric() || c == '_' {
+                1
+            } else {
+                2
+            }
+        };
+        let run = |idx: usize| -> (usize, usize) {
+            let class_at = class(idx);
+            let mut start = idx;
+            while start > 0 && class(start - 1) == class_at {
+                start -= 1;
+            }
+            let mut end = idx;
+            while end + 1 < cells.len() && class(end + 1) == class_at {
+                end += 1;
+            }
+            (start, end)
+        };
+
+        let (mut start, mut end) = run(idx);
+        if !inner {
+            // Take in the whitespace that follows the word, or that
+            // precedes it if it ends the line.  Around whitespace is
+            // the whitespace and the word after it.
+            if end + 1 < cells.len() && (class(idx) == 0 || class(end + 1) == 0) {
+                end = run(end + 1).1;
+            } else if start > 0 && class(start - 1) == 0 {
+                start = run(start - 1).0;
+            }
+        }
+        self.select_range(SelectionMode::Cell, (cells[start].0, y), (cells[end].0, y));
+        true
+    }
+
+    fn select_line_object(&mut self, inner: bool) -> bool {
+        let y = self.cursor.y;
+        if !inner {
+            self.select_range(SelectionMode::Line, (0, y), (0, y));
+            return true;
+        }
+        let (cells, _) = self.cells_around_cursor(y..y + 1);
+        let is_content = |(_, _, text): &&(usize, StableRowIndex, String)| !text.trim().is_empty();
+        match (
+            cells.iter().find(is_content),
+            cells.iter().rev().find(is_content),
+        ) {
+            (Some(first), Some(last)) => {
+                self.select_range(SelectionMode::Cell, (first.0, y), (last.0, y));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn select_bracket_object(&mut self, open: &str, close: &str, inner: bool) -> bool {
+        let (cells, cursor) = self.cells_around_cursor(self.bracket_rows());
+        let idx = match cursor {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let open_idx = if cells[idx].2 == open {
+            Some(idx)
+        } else {
+            find_bracket(&cells, idx, open, close, false)
+        };
+        let (open_idx, close_idx) = match open_idx.and_then(|open_idx| {
+            Some((open_idx, find_bracket(&cells, open_idx, open, close, true)?))
+        }) {
+            Some(pair) => pair,
+            None => return false,
+        };
+
+        let (start, end) = if inner {
+            if close_idx - open_idx < 2 {
+                // There is nothing between the brackets
+                return false;
+            }
+            (open_idx + 1, close_idx - 1)
+        } else {
+            (open_idx, close_idx)
+        };
+        let (start, end) = (&cells[start], &cells[end]);
+        self.select_range(SelectionMode::Cell, (start.0, start.1), (end.0, end.1));
+        true
+    }
+
+    /// Moves to the bracket that matches the first bracket at or after
+    /// the cursor on its line
+    fn move_to_matching_bracket(&mut self) -> bool {
+        let (cells, cursor) = self.cells_around_cursor(self.bracket_rows());
+        let idx = match cursor {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let y = self.cursor.y;
+        let found = cells[idx..]
+            .iter()
+            .take_while(|(_, cell_y, _)| *cell_y == y)
+            .enumerate()
+            .find_map(|(offset, (_, _, text))| {
+                BRACKETS.iter().find_map(|&(open, close)| {
+                    if text == open {
+                        Some((idx + offset, open, close, true))
+                    } else if text == close {
+                        Some((idx + offset, open, close, false))
+                    } else {
+                        None
+                    }
+                })
+            });
+        let target = found.and_then(|(bracket_idx, open, close, forward)| {
+            find_bracket(&cells, bracket_idx, open, close, forward)
+        });
+        match target {
+            Some(target) => {
+                self.cursor.x = cells[target].0;
+                self.cursor.y = cells[target].1;
+                self.select_to_cursor_pos();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn select_register(&mut self, name: char) -> bool {
+        match name {
+            // The clipboard, which is where yanks go anyway
+            '+' | '*' => self.register = None,
+            c if c.is_ascii_alphanumeric() => self.register = Some(c),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Finds the bracket that pairs with the one at `idx`, skipping over
+/// nested pairs: the closing bracket after it when `forward`, else the
+/// opening bracket before it.  `idx` itself may be any cell.
+fn find_bracket(
+    cells: &[(usize, StableRowIndex, String)],
+    idx: usize,
+    open: &str,
+    close: &str,
+    forward: bool,
+) -> Option<usize> {
+    let (seek, nest) = if forward {
+        (close, open)
+    } else {
+        (open, close)
+    };
+    let mut depth = 0;
+    let candidates: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new(idx + 1..cells.len())
+    } else {
+        Box::new((0..idx).rev())
+    };
+    for i in candidates {
+        let text = cells[i].2.as_str();
+        if text == seek {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        } else if text == nest {
+            depth += 1;
+        }
+    }
+    None
 }
 
 impl Pane for CopyOverlay {
@@ -1139,6 +1628,37 @@ impl Pane for CopyOverlay {
     fn key_down(&self, key: KeyCode, mods: KeyModifiers) -> anyhow::Result<()> {
         let mut render = self.render.lock();
         let mods = mods.remove_positional_mods();
+        if let Some(inner) = render.pending_text_object.take() {
+            let selected = match (key, mods) {
+                (KeyCode::Char(c), KeyModifiers::NONE)
+                | (KeyCode::Char(c), KeyModifiers::SHIFT) => TextObject::from_key(c)
+                    .map(|object| render.select_text_object(object, inner))
+                    .unwrap_or(false),
+                _ => false,
+            };
+            if !selected {
+                self.delegate
+                    .perform_actions(vec![termwiz::escape::Action::Control(
+                        termwiz::escape::ControlCode::Bell,
+                    )]);
+            }
+            return Ok(());
+        }
+        if render.pending_register {
+            render.pending_register = false;
+            let selected = match (key, mods) {
+                (KeyCode::Char(c), KeyModifiers::NONE)
+                | (KeyCode::Char(c), KeyModifiers::SHIFT) => render.select_register(c),
+                _ => false,
+            };
+            if !selected {
+                self.delegate
+                    .perform_actions(vec![termwiz::escape::Action::Control(
+                        termwiz::escape::ControlCode::Bell,
+                    )]);
+            }
+            return Ok(());
+        }
         if let Some(jump) = render.pending_jump.take() {
             match (key, mods) {
                 (KeyCode::Char(c), KeyModifiers::NONE)
@@ -1241,7 +1761,10 @@ impl Pane for CopyOverlay {
     fn perform_assignment(&self, assignment: &KeyAssignment) -> PerformAssignmentResult {
         use CopyModeAssignment::*;
         let mut render = self.render.lock();
-        if render.pending_jump.is_some() {
+        if render.pending_jump.is_some()
+            || render.pending_text_object.is_some()
+            || render.pending_register
+        {
             // Block key assignments until key_down is called
             // and resolves the next state
             return PerformAssignmentResult::BlockAssignmentAndRouteToKeyDown;
@@ -1276,6 +1799,8 @@ impl Pane for CopyOverlay {
                     PriorMatchPage => render.prior_match_page(),
                     NextMatchPage => render.next_match_page(),
                     CycleMatchType => render.cycle_match_type(),
+                    SetMatchType(match_type) => render.set_match_type(*match_type),
+                    AdvanceMatch { reverse } => render.advance_match(*reverse),
                     ClearPattern => render.clear_pattern(),
                     EditPattern => render.edit_pattern(),
                     AcceptPattern => render.accept_pattern(),
@@ -1289,6 +1814,18 @@ impl Pane for CopyOverlay {
                     JumpBackward { prev_char } => render.jump(false, *prev_char),
                     JumpAgain => render.jump_again(false),
                     JumpReverse => render.jump_again(true),
+                    SelectTextObject { inner } => {
+                        render.pending_text_object.replace(*inner);
+                    }
+                    MoveToMatchingBracket => {
+                        if !render.move_to_matching_bracket() {
+                            self.delegate
+                                .perform_actions(vec![termwiz::escape::Action::Control(
+                                    termwiz::escape::ControlCode::Bell,
+                                )]);
+                        }
+                    }
+                    SelectRegister => render.pending_register = true,
                 }
                 PerformAssignmentResult::Handled
             }
@@ -1430,70 +1967,17 @@ impl Pane for CopyOverlay {
                         // Replace with search UI
                         let rev = CellAttributes::default().set_reverse(true).clone();
                         line.fill_range(0..self.dims.cols, &Cell::new(' ', rev.clone()), SEQ_ZERO);
-                        let mode = &match pattern {
-                            Pattern::CaseSensitiveString(_) => "case-sensitive",
-                            Pattern::CaseInSensitiveString(_) => "ignore-case",
-                            Pattern::Regex(_) => "regex",
-                        };
-
-                        let remain = match &self.renderer.searching {
-                            Some(Searching { remain, .. }) => {
-                                format!(" searching {remain} lines")
-                            }
-                            None => String::new(),
-                        };
-
                         line.overlay_text_with_attribute(
                             0,
-                            &format!(
-                                "Search: {} ({}/{} matches. {}{remain})",
-                                *pattern,
-                                self.renderer.result_pos.map(|x| x + 1).unwrap_or(0),
-                                self.renderer.results.len(),
-                                mode
-                            ),
+                            &self.renderer.search_bar_text(&pattern),
                             rev,
                             SEQ_ZERO,
                         );
                         self.renderer.last_bar_pos = Some(self.search_row);
                         line.clear_appdata();
-                    } else if let Some(matches) = self.renderer.by_line.get(&stable_idx) {
-                        for m in matches {
-                            // highlight
-                            for cell_idx in m.range.clone() {
-                                if let Some(cell) =
-                                    line.cells_mut_for_attr_changes_only().get_mut(cell_idx)
-                                {
-                                    if Some(m.result_index) == self.renderer.result_pos {
-                                        cell.attrs_mut()
-                                            .set_background(
-                                                colors
-                                                    .copy_mode_active_highlight_bg
-                                                    .unwrap_or(AnsiColor::Yellow.into()),
-                                            )
-                                            .set_foreground(
-                                                colors
-                                                    .copy_mode_active_highlight_fg
-                                                    .unwrap_or(AnsiColor::Black.into()),
-                                            )
-                                            .set_reverse(false);
-                                    } else {
-                                        cell.attrs_mut()
-                                            .set_background(
-                                                colors
-                                                    .copy_mode_inactive_highlight_bg
-                                                    .unwrap_or(AnsiColor::Fuchsia.into()),
-                                            )
-                                            .set_foreground(
-                                                colors
-                                                    .copy_mode_inactive_highlight_fg
-                                                    .unwrap_or(AnsiColor::Black.into()),
-                                            )
-                                            .set_reverse(false);
-                                    }
-                                }
-                            }
-                        }
+                    } else if self.renderer.by_line.contains_key(&stable_idx) {
+                        self.renderer
+                            .highlight_matches(&mut line, stable_idx, colors);
                         line.clear_appdata();
                     }
                     overlay_lines.push(line);
@@ -1531,60 +2015,15 @@ impl Pane for CopyOverlay {
                 // Replace with search UI
                 let rev = CellAttributes::default().set_reverse(true).clone();
                 line.fill_range(0..dims.cols, &Cell::new(' ', rev.clone()), SEQ_ZERO);
-                let mode = &match pattern {
-                    Pattern::CaseSensitiveString(_) => "case-sensitive",
-                    Pattern::CaseInSensitiveString(_) => "ignore-case",
-                    Pattern::Regex(_) => "regex",
-                };
                 line.overlay_text_with_attribute(
                     0,
-                    &format!(
-                        "Search: {} ({}/{} matches. {})",
-                        *pattern,
-                        renderer.result_pos.map(|x| x + 1).unwrap_or(0),
-                        renderer.results.len(),
-                        mode
-                    ),
+                    &renderer.search_bar_text(&pattern),
                     rev,
                     SEQ_ZERO,
                 );
                 renderer.last_bar_pos = Some(search_row);
-            } else if let Some(matches) = renderer.by_line.get(&stable_idx) {
-                for m in matches {
-                    // highlight
-                    for cell_idx in m.range.clone() {
-                        if let Some(cell) = line.cells_mut_for_attr_changes_only().get_mut(cell_idx)
-                        {
-                            if Some(m.result_index) == renderer.result_pos {
-                                cell.attrs_mut()
-                                    .set_background(
-                                        colors
-                                            .copy_mode_active_highlight_bg
-                                            .unwrap_or(AnsiColor::Yellow.into()),
-                                    )
-                                    .set_foreground(
-                                        colors
-                                            .copy_mode_active_highlight_fg
-                                            .unwrap_or(AnsiColor::Black.into()),
-                                    )
-                                    .set_reverse(false);
-                            } else {
-                                cell.attrs_mut()
-                                    .set_background(
-                                        colors
-                                            .copy_mode_inactive_highlight_bg
-                                            .unwrap_or(AnsiColor::Fuchsia.into()),
-                                    )
-                                    .set_foreground(
-                                        colors
-                                            .copy_mode_inactive_highlight_fg
-                                            .unwrap_or(AnsiColor::Black.into()),
-                                    )
-                                    .set_reverse(false);
-                            }
-                        }
-                    }
-                }
+            } else if renderer.by_line.contains_key(&stable_idx) {
+                renderer.highlight_matches(line, stable_idx, colors);
             }
         }
 
@@ -1640,7 +2079,12 @@ pub fn search_key_table() -> KeyTable {
         (
             WKeyCode::Char('\r'),
             Modifiers::NONE,
-            KeyAssignment::CopyMode(CopyModeAssignment::PriorMatch),
+            KeyAssignment::CopyMode(CopyModeAssignment::AdvanceMatch { reverse: false }),
+        ),
+        (
+            WKeyCode::Char('\r'),
+            Modifiers::SHIFT,
+            KeyAssignment::CopyMode(CopyModeAssignment::AdvanceMatch { reverse: true }),
         ),
         (
             WKeyCode::Char('p'),
@@ -1672,13 +2116,38 @@ pub fn search_key_table() -> KeyTable {
             Modifiers::CTRL,
             KeyAssignment::CopyMode(CopyModeAssignment::CycleMatchType),
         ),
+        (
+            WKeyCode::Char('c'),
+            Modifiers::ALT,
+            KeyAssignment::CopyMode(CopyModeAssignment::SetMatchType(
+                SearchMatchType::CaseSensitive,
+            )),
+        ),
+        (
+            WKeyCode::Char('i'),
+            Modifiers::ALT,
+            KeyAssignment::CopyMode(CopyModeAssignment::SetMatchType(
+                SearchMatchType::CaseInsensitive,
+            )),
+        ),
+        (
+            WKeyCode::Char('r'),
+            Modifiers::ALT,
+            KeyAssignment::CopyMode(CopyModeAssignment::SetMatchType(SearchMatchType::Regex)),
+        ),
         (
             WKeyCode::Char('u'),
             Modifiers::CTRL,
             KeyAssignment::CopyMode(CopyModeAssignment::ClearPattern),
         ),
     ] {
-        table.insert((key, mods), KeyTableEntry { action });
+        table.insert(
+            (key, mods),
+            KeyTableEntry {
+                action,
+                metadata: Default::default(),
+            },
+        );
     }
     table
 }
@@ -1971,6 +2440,36 @@ pub fn copy_key_table() -> KeyTable {
             Modifiers::NONE,
             KeyAssignment::CopyMode(CopyModeAssignment::JumpAgain),
         ),
+        (
+            WKeyCode::Char('i'),
+            Modifiers::NONE,
+            KeyAssignment::CopyMode(CopyModeAssignment::SelectTextObject { inner: true }),
+        ),
+        (
+            WKeyCode::Char('a'),
+            Modifiers::NONE,
+            KeyAssignment::CopyMode(CopyModeAssignment::SelectTextObject { inner: false }),
+        ),
+        (
+            WKeyCode::Char('%'),
+            Modifiers::SHIFT,
+            KeyAssignment::CopyMode(CopyModeAssignment::MoveToMatchingBracket),
+        ),
+        (
+            WKeyCode::Char('%'),
+            Modifiers::NONE,
+            KeyAssignment::CopyMode(CopyModeAssignment::MoveToMatchingBracket),
+        ),
+        (
+            WKeyCode::Char('"'),
+            Modifiers::SHIFT,
+            KeyAssignment::CopyMode(CopyModeAssignment::SelectRegister),
+        ),
+        (
+            WKeyCode::Char('"'),
+            Modifiers::NONE,
+            KeyAssignment::CopyMode(CopyModeAssignment::SelectRegister),
+        ),
         (
             WKeyCode::Char(','),
             Modifiers::NONE,
@@ -2017,7 +2516,83 @@ pub fn copy_key_table() -> KeyTable {
             KeyAssignment::CopyMode(CopyModeAssignment::MoveToEndOfLineContent),
         ),
     ] {
-        table.insert((key, mods), KeyTableEntry { action });
+        table.insert(
+            (key, mods),
+            KeyTableEntry {
+                action,
+                metadata: Default::default(),
+            },
+        );
     }
     table
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Lays `rows` out as the cells returned by `cells_around_cursor`
+    fn cells(rows: &[&str]) -> Vec<(usize, StableRowIndex, String)> {
+        let mut cells = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                cells.push((x, y as StableRowIndex, c.to_string()));
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn find_bracket_skips_nested_pairs() {
+        let cells = cells(&["f(a, (b), c)"]);
+        assert_eq!(find_bracket(&cells, 1, "(", ")", true), Some(11));
+        assert_eq!(find_bracket(&cells, 11, "(", ")", false), Some(1));
+        assert_eq!(find_bracket(&cells, 5, "(", ")", true), Some(7));
+        // From inside the pair, backwards finds the enclosing opener
+        assert_eq!(find_bracket(&cells, 9, "(", ")", false), Some(1));
+        assert_eq!(find_bracket(&cells, 0, "[", "]", true), None);
+    }
+
+    #[test]
+    fn find_bracket_spans_rows() {
+        let cells = cells(&["if {", "  {x}", "}"]);
+        assert_eq!(find_bracket(&cells, 3, "{", "}", true), Some(9));
+        assert_eq!(cells[9].1, 2);
+    }
+
+    #[test]
+    fn text_object_keys() {
+        assert_eq!(TextObject::from_key('w'), Some(TextObject::Word));
+        assert_eq!(TextObject::from_key('W'), Some(TextObject::BigWord));
+        assert_eq!(TextObject::from_key('l'), Some(TextObject::Line));
+        assert_eq!(
+            TextObject::from_key('b'),
+            Some(TextObject::Bracket("(", ")"))
+        );
+        assert_eq!(
+            TextObject::from_key('B'),
+            Some(TextObject::Bracket("{", "}"))
+        );
+        assert_eq!(
+            TextObject::from_key(']'),
+            Some(TextObject::Bracket("[", "]"))
+        );
+        assert_eq!(
+            TextObject::from_key('<'),
+            Some(TextObject::Bracket("<", ">"))
+        );
+        assert_eq!(TextObject::from_key('x'), None);
+    }
+
+    #[test]
+    fn uppercase_register_appends() {
+        store_register('q', "one".to_string());
+        store_register('Q', " two".to_string());
+        assert_eq!(register_text('q'), Some("one two".to_string()));
+        assert_eq!(register_text('Q'), Some("one two".to_string()));
+
+        store_register('q', "three".to_string());
+        assert_eq!(register_text('q'), Some("three".to_string()));
+        assert_eq!(register_text('z'), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::scripting::guiwin::GuiWin;
+use crate::termwindow::TermWindowNotif;
 use chrono::prelude::*;
 use futures::FutureExt;
 use log::Level;
@@ -148,6 +149,7 @@ pub fn show_debug_overlay(
     };
 
     lua.load("wezterm = require 'wezterm'").exec()?;
+    let window = gui_win.window.clone();
     lua.globals().set("window", gui_win)?;
     let lua_version: String = lua.globals().get("_VERSION")?;
 
@@ -203,6 +205,7 @@ pub fn show_debug_overlay(
          Lua Version: {lua_version}\r\n\
          {opengl_info}\r\n\
          Enter lua statements or expressions and hit Enter.\r\n\
+         Enter `:font-report TEXT` to explain which fonts render TEXT.\r\n\
          Press ESC or CTRL-D to exit\r\n",
     ))])?;
 
@@ -216,6 +219,12 @@ pub fn show_debug_overlay(
             }
             host.as_mut().unwrap().add_history(&line);
 
+            if let Some(text) = line.strip_prefix(":font-report ") {
+                let report = font_report(&window, text.to_string());
+                term.render(&[Change::Text(report.replace("\n", "\r\n"))])?;
+                continue;
+            }
+
             let passed_host = host.take().unwrap();
 
             let (host_res, text) =
@@ -238,6 +247,20 @@ pub fn show_debug_overlay(
     }
 }
 
+/// Asks the window for its font report, as that is where the fonts
+/// are loaded
+fn font_report(window: &::window::Window, text: String) -> String {
+    let (tx, rx) = smol::channel::bounded(1);
+    window.notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+        let report = match term_window.font_report(&text) {
+            Ok(report) => report,
+            Err(err) => format!("{err:#}\n"),
+        };
+        tx.try_send(report).ok();
+    })));
+    smol::block_on(rx.recv()).unwrap_or_else(|err| format!("{err:#}\n"))
+}
+
 // A bit of indirection because spawn_into_main_thread wants the
 // overall future to be Send but mlua::Value, mlua::Chunk are not
 // Send.  We need to split off the actual evaluation future to
@@ -0,0 +1,532 @@
+//! A foldable, searchable tree view of a JSON document, shown by
+//! the `ViewSelectionAsJson` key assignment.
+use crate::scripting::guiwin::GuiWin;
+use crate::termwindow::TermWindowNotif;
+use config::keyassignment::ClipboardCopyDestination;
+use mux::termwiztermtab::TermWizTerminal;
+use serde_json::Value;
+use std::collections::HashSet;
+use termwiz::cell::{AttributeChange, CellAttributes, Intensity};
+use termwiz::color::ColorAttribute;
+use termwiz::input::{InputEvent, KeyCode, KeyEvent, Modifiers};
+use termwiz::surface::{Change, CursorVisibility, Position};
+use termwiz::terminal::Terminal;
+use termwiz_funcs::truncate_right;
+
+const ROW_OVERHEAD: usize = 2;
+
+/// One step along the path from the root of the document to a node
+#[derive(Debug, Clone, PartialEq)]
+enum PathElement {
+    Key(String),
+    Index(usize),
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Formats a path in the style accepted by `jq`, such as
+/// `.items[0].name` or `.headers["content-type"]`
+fn format_path(path: &[PathElement]) -> String {
+    if path.is_empty() {
+        return ".".to_string();
+    }
+    let mut result = String::new();
+    for element in path {
+        match element {
+            PathElement::Key(key) if is_identifier(key) => {
+                result.push('.');
+                result.push_str(key);
+            }
+            PathElement::Key(key) => {
+                result.push_str(&format!("[{}]", Value::String(key.clone())));
+            }
+            PathElement::Index(idx) => result.push_str(&format!("[{idx}]")),
+        }
+    }
+    result
+}
+
+fn lookup<'a>(value: &'a Value, path: &[PathElement]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, element| match element {
+        PathElement::Key(key) => value.get(key.as_str()),
+        PathElement::Index(idx) => value.get(*idx),
+    })
+}
+
+fn children(value: &Value) -> Vec<(PathElement, &Value)> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (PathElement::Key(key.clone()), value))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (PathElement::Index(idx), value))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+/// A compact, single line description of a value
+fn summarize(value: &Value) -> String {
+    match value {
+        Value::Object(map) => format!("{{…}} {} keys", map.len()),
+        Value::Array(items) => format!("[…] {} items", items.len()),
+        scalar => scalar.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    path: Vec<PathElement>,
+    depth: usize,
+}
+
+/// Returns the visible rows of the tree.  Without a filter, the
+/// children of collapsed nodes are omitted.  With a filter, every
+/// node whose key or scalar value contains the filter text is
+/// returned, regardless of whether its ancestors are collapsed.
+fn visible_rows(root: &Value, collapsed: &HashSet<String>, filter: &str) -> Vec<Row> {
+    fn visit(
+        value: &Value,
+        path: &mut Vec<PathElement>,
+        collapsed: &HashSet<String>,
+        filter: &str,
+        rows: &mut Vec<Row>,
+    ) {
+        if filter.is_empty() {
+            rows.push(Row {
+                path: path.clone(),
+                depth: path.len(),
+            });
+            if collapsed.contains(&format_path(path)) {
+                return;
+            }
+        } else {
+            let key_matches = match path.last() {
+                Some(PathElement::Key(key)) => key.to_lowercase().contains(filter),
+                _ => false,
+            };
+            let value_matches =
+                !is_container(value) && value.to_string().to_lowercase().contains(filter);
+            if key_matches || value_matches {
+                rows.push(Row {
+                    path: path.clone(),
+                    depth: 0,
+                });
+            }
+        }
+        for (element, child) in children(value) {
+            path.push(element);
+            visit(child, path, collapsed, filter, rows);
+            path.pop();
+        }
+    }
+
+    let mut rows = vec![];
+    visit(
+        root,
+        &mut vec![],
+        collapsed,
+        &filter.to_lowercase(),
+        &mut rows,
+    );
+    rows
+}
+
+struct JsonViewerState {
+    root: Value,
+    collapsed: HashSet<String>,
+    rows: Vec<Row>,
+    active_idx: usize,
+    top_row: usize,
+    max_items: usize,
+    filter_term: String,
+    filtering: bool,
+    window: GuiWin,
+}
+
+impl JsonViewerState {
+    fn update_rows(&mut self) {
+        let active_path = self.rows.get(self.active_idx).map(|row| row.path.clone());
+        self.rows = visible_rows(&self.root, &self.collapsed, &self.filter_term);
+        self.active_idx = active_path
+            .and_then(|path| self.rows.iter().position(|row| row.path == path))
+            .unwrap_or(0);
+        self.scroll_to_active();
+    }
+
+    fn scroll_to_active(&mut self) {
+        if self.active_idx < self.top_row {
+            self.top_row = self.active_idx;
+        } else if self.max_items > 0 && self.active_idx >= self.top_row + self.max_items {
+            self.top_row = self.active_idx + 1 - self.max_items;
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.rows.len().saturating_sub(1) as isize;
+        self.active_idx = (self.active_idx as isize + delta).max(0).min(last) as usize;
+        self.scroll_to_active();
+    }
+
+    fn active_path(&self) -> Option<Vec<PathElement>> {
+        self.rows.get(self.active_idx).map(|row| row.path.clone())
+    }
+
+    fn active_value(&self) -> Option<&Value> {
+        let row = self.rows.get(self.active_idx)?;
+        lookup(&self.root, &row.path)
+    }
+
+    fn set_collapsed(&mut self, collapse: bool) {
+        if !self.active_value().map(is_container).unwrap_or(false) {
+            return;
+        }
+        if let Some(path) = self.active_path() {
+            let key = format_path(&path);
+            if collapse {
+                self.collapsed.insert(key);
+            } else {
+                self.collapsed.remove(&key);
+            }
+            self.update_rows();
+        }
+    }
+
+    fn toggle_collapsed(&mut self) {
+        if let Some(path) = self.active_path() {
+            let collapse = !self.collapsed.contains(&format_path(&path));
+            self.set_collapsed(collapse);
+        }
+    }
+
+    /// Collapses the active node, or moves to its parent if it is
+    /// already collapsed or isn't a container
+    fn collapse_or_parent(&mut self) {
+        let path = match self.active_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let expanded = self.active_value().map(is_container).unwrap_or(false)
+            && !self.collapsed.contains(&format_path(&path));
+        if expanded && self.filter_term.is_empty() {
+            self.set_collapsed(true);
+        } else if let Some(parent) = path.split_last().map(|(_, parent)| parent) {
+            if let Some(idx) = self.rows.iter().position(|row| row.path == parent) {
+                self.active_idx = idx;
+                self.scroll_to_active();
+            }
+        }
+    }
+
+    fn copy(&self, text: String) {
+        self.window
+            .window
+            .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                term_window.copy_to_clipboard(ClipboardCopyDestination::Clipboard, text);
+                term_window.show_copy_toast();
+            })));
+    }
+
+    fn copy_path(&self) {
+        if let Some(path) = self.active_path() {
+            self.copy(format_path(&path));
+        }
+    }
+
+    fn copy_value(&self) {
+        let text = match self.active_value() {
+            Some(Value::String(s)) => s.clone(),
+            Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+            None => return,
+        };
+        self.copy(text);
+    }
+
+    fn row_text(&self, row: &Row) -> String {
+        let value = match lookup(&self.root, &row.path) {
+            Some(value) => value,
+            None => return String::new(),
+        };
+        if !self.filter_term.is_empty() {
+            return format!("{} = {}", format_path(&row.path), summarize(value));
+        }
+        let marker = if !is_container(value) {
+            "  "
+        } else if self.collapsed.contains(&format_path(&row.path)) {
+            "▸ "
+        } else {
+            "▾ "
+        };
+        let label = match row.path.last() {
+            None => String::new(),
+            Some(PathElement::Key(key)) => format!("{}: ", Value::String(key.clone())),
+            Some(PathElement::Index(idx)) => format!("[{idx}]: "),
+        };
+        format!(
+            "{}{marker}{label}{}",
+            "  ".repeat(row.depth),
+            summarize(value)
+        )
+    }
+
+    fn render(&mut self, term: &mut TermWizTerminal) -> termwiz::Result<()> {
+        let size = term.get_screen_size()?;
+        let max_width = size.cols.saturating_sub(1);
+        self.max_items = size.rows.saturating_sub(ROW_OVERHEAD);
+        self.scroll_to_active();
+
+        let header = if self.filtering {
+            format!("Search: {}", self.filter_term)
+        } else {
+            "JSON: Enter/←/→ fold, / search, p copy path, y copy value, Esc close".to_string()
+        };
+        let status = self
+            .active_path()
+            .map(|path| format_path(&path))
+            .unwrap_or_default();
+
+        let mut changes = vec![
+            Change::ClearScreen(ColorAttribute::Default),
+            Change::CursorVisibility(CursorVisibility::Hidden),
+            Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(0),
+            },
+            AttributeChange::Intensity(Intensity::Bold).into(),
+            Change::Text(truncate_right(&header, max_width)),
+            Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(1),
+            },
+            Change::AllAttributes(CellAttributes::default()),
+            Change::Text(truncate_right(&status, max_width)),
+        ];
+
+        for (row_num, (idx, row)) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(self.top_row)
+            .take(self.max_items)
+            .enumerate()
+        {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(row_num + ROW_OVERHEAD),
+            });
+            if idx == self.active_idx {
+                changes.push(AttributeChange::Reverse(true).into());
+            }
+            changes.push(Change::Text(truncate_right(&self.row_text(row), max_width)));
+            if idx == self.active_idx {
+                changes.push(AttributeChange::Reverse(false).into());
+            }
+        }
+
+        term.render(&changes)?;
+        term.flush()
+    }
+
+    fn run_loop(&mut self, term: &mut TermWizTerminal) -> anyhow::Result<()> {
+        self.render(term)?;
+        while let Ok(Some(event)) = term.poll_input(None) {
+            match event {
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('G' | 'C'),
+                    modifiers: Modifiers::CTRL,
+                }) => break,
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Escape,
+                    ..
+                }) => {
+                    if self.filtering || !self.filter_term.is_empty() {
+                        self.filtering = false;
+                        self.filter_term.clear();
+                        self.update_rows();
+                    } else {
+                        break;
+                    }
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Enter,
+                    ..
+                }) if self.filtering => {
+                    self.filtering = false;
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Backspace,
+                    ..
+                }) if self.filtering => {
+                    if self.filter_term.pop().is_none() {
+                        self.filtering = false;
+                    }
+                    self.update_rows();
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char(c),
+                    ..
+                }) if self.filtering => {
+                    self.filter_term.push(c);
+                    self.update_rows();
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('q'),
+                    ..
+                }) => break,
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('/'),
+                    ..
+                }) => {
+                    self.filtering = true;
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('j'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::DownArrow,
+                    ..
+                }) => self.move_by(1),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('k'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::UpArrow,
+                    ..
+                }) => self.move_by(-1),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::PageDown,
+                    ..
+                }) => self.move_by(self.max_items.max(1) as isize),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::PageUp,
+                    ..
+                }) => self.move_by(-(self.max_items.max(1) as isize)),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('g'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::Home, ..
+                }) => self.move_by(-(self.rows.len() as isize)),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('G'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::End, ..
+                }) => self.move_by(self.rows.len() as isize),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Enter | KeyCode::Char(' '),
+                    ..
+                }) if self.filter_term.is_empty() => self.toggle_collapsed(),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('h'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::LeftArrow,
+                    ..
+                }) => self.collapse_or_parent(),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('l'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::RightArrow,
+                    ..
+                }) if self.filter_term.is_empty() => self.set_collapsed(false),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('p'),
+                    ..
+                }) => self.copy_path(),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('y' | 'c'),
+                    ..
+                }) => self.copy_value(),
+                _ => {}
+            }
+            self.render(term)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows `value` as a tree.  Objects and arrays can be folded,
+/// `/` searches keys and values, and the path or value of the
+/// selected node can be copied to the clipboard.
+pub fn json_viewer(mut term: TermWizTerminal, value: Value, window: GuiWin) -> anyhow::Result<()> {
+    term.set_raw_mode()?;
+    term.no_grab_mouse_in_raw_mode();
+
+    let mut state = JsonViewerState {
+        rows: visible_rows(&value, &HashSet::new(), ""),
+        root: value,
+        collapsed: HashSet::new(),
+        active_idx: 0,
+        top_row: 0,
+        max_items: 0,
+        filter_term: String::new(),
+        filtering: false,
+        window,
+    };
+    state.run_loop(&mut term)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        assert_eq!(format_path(&[]), ".");
+        assert_eq!(
+            format_path(&[
+                PathElement::Key("items".to_string()),
+                PathElement::Index(0),
+                PathElement::Key("content-type".to_string()),
+            ]),
+            ".items[0][\"content-type\"]"
+        );
+    }
+
+    #[test]
+    fn folding_and_filtering() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": {"b": 1, "c": [true, "x"]}, "d": null}"#).unwrap();
+        let paths = |rows: Vec<Row>| -> Vec<String> {
+            rows.iter().map(|row| format_path(&row.path)).collect()
+        };
+
+        assert_eq!(
+            paths(visible_rows(&value, &HashSet::new(), "")),
+            vec![".", ".a", ".a.b", ".a.c", ".a.c[0]", ".a.c[1]", ".d"]
+        );
+
+        let collapsed: HashSet<String> = vec![".a.c".to_string()].into_iter().collect();
+        assert_eq!(
+            paths(visible_rows(&value, &collapsed, "")),
+            vec![".", ".a", ".a.b", ".a.c", ".d"]
+        );
+
+        assert_eq!(
+            paths(visible_rows(&value, &collapsed, "X")),
+            vec![".a.c[1]"]
+        );
+    }
+}
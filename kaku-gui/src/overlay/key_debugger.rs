@@ -0,0 +1,112 @@
+use config::ConfigHandle;
+use mux::termwiztermtab::TermWizTerminal;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use termwiz::cell::{AttributeChange, Intensity};
+use termwiz::input::{InputEvent, KeyCode, KeyEvent};
+use termwiz::surface::{Change, CursorVisibility, Position};
+use termwiz::terminal::Terminal;
+
+/// Shows a log of key events as they pass through the GUI, in
+/// their raw, mapped and encoded forms.  The reports are produced
+/// by the key event handlers in `TermWindow` while the debugger
+/// is active and delivered via `reports`.
+/// Pressing Escape twice in a row closes the overlay.
+pub fn show_key_debugger(
+    mut term: TermWizTerminal,
+    config: ConfigHandle,
+    reports: Receiver<String>,
+) -> anyhow::Result<()> {
+    term.set_raw_mode()?;
+
+    let header = vec![
+        "Key Debugger: press keys to see how they are processed.".to_string(),
+        "Press Escape twice to close.".to_string(),
+        format!(
+            "use_dead_keys={} ({})",
+            config.use_dead_keys,
+            if config.use_dead_keys {
+                "dead keys wait for the next key"
+            } else {
+                "dead keys are sent immediately"
+            }
+        ),
+        format!(
+            "send_composed_key_when_left_alt_is_pressed={} \
+             send_composed_key_when_right_alt_is_pressed={}",
+            config.send_composed_key_when_left_alt_is_pressed,
+            config.send_composed_key_when_right_alt_is_pressed,
+        ),
+        format!(
+            "macos_forward_to_ime_modifier_mask={:?} enable_kitty_keyboard={}",
+            config.macos_forward_to_ime_modifier_mask, config.enable_kitty_keyboard,
+        ),
+    ];
+
+    let mut lines: Vec<String> = vec![];
+    let mut last_was_escape = false;
+
+    let render = |term: &mut TermWizTerminal, lines: &[String]| -> termwiz::Result<()> {
+        let size = term.get_screen_size()?;
+        let mut changes = vec![
+            Change::ClearScreen(Default::default()),
+            Change::CursorVisibility(CursorVisibility::Hidden),
+            AttributeChange::Intensity(Intensity::Bold).into(),
+        ];
+        for (y, line) in header.iter().enumerate() {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(y),
+            });
+            changes.push(line.as_str().into());
+        }
+        changes.push(AttributeChange::Intensity(Intensity::Normal).into());
+
+        let first_row = header.len() + 1;
+        let available = size.rows.saturating_sub(first_row);
+        let skip = lines.len().saturating_sub(available);
+        for (y, line) in lines.iter().skip(skip).enumerate() {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(first_row + y),
+            });
+            changes.push(line.as_str().into());
+        }
+        term.render(&changes)
+    };
+
+    render(&mut term, &lines)?;
+
+    loop {
+        match term.poll_input(Some(Duration::from_millis(50)))? {
+            Some(InputEvent::Key(KeyEvent {
+                key: KeyCode::Escape,
+                ..
+            })) => {
+                if last_was_escape {
+                    break;
+                }
+                last_was_escape = true;
+            }
+            Some(InputEvent::Key(_)) => {
+                last_was_escape = false;
+            }
+            _ => {}
+        }
+
+        let mut changed = false;
+        for report in reports.try_iter() {
+            lines.extend(report.lines().map(|s| s.to_string()));
+            changed = true;
+        }
+        // Keep the history bounded
+        if lines.len() > 1000 {
+            lines.drain(0..lines.len() - 1000);
+        }
+        if changed {
+            render(&mut term, &lines)?;
+        }
+    }
+
+    Ok(())
+}
@@ -5,20 +5,29 @@
 //! be rendered as a popup/context menu if the system supports it; at the
 //! time of writing our window layer doesn't provide an API for context
 //! menus.
+//!
+//! Entries of the `launch_menu` may open submenus of their own, and the
+//! `augment-launch-menu` event can add entries after the launcher has
+//! opened, while a loading indicator is shown.
 use crate::commands::derive_command_from_key_assignment;
 use crate::inputmap::InputMap;
 use crate::overlay::quickselect;
 use crate::overlay::selector::{matcher_pattern, matcher_score};
+use crate::scripting::guiwin::GuiWin;
 use crate::termwindow::TermWindowNotif;
 use config::configuration;
-use config::keyassignment::{KeyAssignment, SpawnCommand, SpawnTabDomain};
+use config::keyassignment::{KeyAssignment, LaunchMenuItem, SpawnCommand, SpawnTabDomain};
+use mlua::FromLua;
 use mux::domain::{DomainId, DomainState};
 use mux::pane::PaneId;
 use mux::termwiztermtab::TermWizTerminal;
 use mux::window::WindowId;
 use mux::Mux;
+use mux_lua::MuxPane;
 use rayon::prelude::*;
+use smol::channel::{Receiver, TryRecvError};
 use std::collections::BTreeMap;
+use std::time::Duration;
 use termwiz::cell::{AttributeChange, CellAttributes};
 use termwiz::color::ColorAttribute;
 use termwiz::input::{InputEvent, KeyCode, KeyEvent, Modifiers, MouseButtons, MouseEvent};
@@ -33,6 +42,71 @@ pub use config::keyassignment::LauncherFlags;
 struct Entry {
     pub label: String,
     pub action: KeyAssignment,
+    /// The entries of the submenu that this entry opens, if any
+    pub children: Vec<Entry>,
+}
+
+impl Entry {
+    fn from_launch_menu_item(item: &LaunchMenuItem) -> Self {
+        if item.is_submenu() {
+            Self {
+                label: item.label(),
+                action: KeyAssignment::Nop,
+                children: item
+                    .children
+                    .iter()
+                    .map(Self::from_launch_menu_item)
+                    .collect(),
+            }
+        } else {
+            Self {
+                label: item.label(),
+                action: KeyAssignment::SpawnCommandInNewTab(item.spawn.clone()),
+                children: vec![],
+            }
+        }
+    }
+}
+
+/// A menu that was left to open one of its submenus
+struct ParentMenu {
+    /// The label of the submenu that was opened
+    label: String,
+    entries: Vec<Entry>,
+    filter_term: String,
+    active_idx: usize,
+    top_row: usize,
+}
+
+/// How often the launcher checks for entries from the
+/// `augment-launch-menu` event while they are loading
+const PENDING_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Emits the `augment-launch-menu` event, whose handler may take a
+/// while to list additional entries for the launcher.
+/// The channel is closed without a value if there is no handler.
+pub fn augment_launch_menu(window: GuiWin, pane: MuxPane) -> Receiver<Vec<LaunchMenuItem>> {
+    let (tx, rx) = smol::channel::bounded(1);
+    promise::spawn::spawn(config::with_lua_config_on_main_thread(
+        move |lua| async move {
+            if let Some(lua) = lua {
+                let args = ("augment-launch-menu".to_string(), (window, pane));
+                match config::lua::emit_async_callback(&lua, args).await {
+                    Ok(mlua::Value::Nil) => {}
+                    Ok(value) => match Vec::<LaunchMenuItem>::from_lua(value, &lua) {
+                        Ok(items) => {
+                            tx.try_send(items).ok();
+                        }
+                        Err(err) => log::error!("augment-launch-menu: {err:#}"),
+                    },
+                    Err(err) => log::error!("augment-launch-menu: {err:#}"),
+                }
+            }
+            Ok(())
+        },
+    ))
+    .detach();
+    rx
 }
 
 pub struct LauncherTabEntry {
@@ -61,6 +135,7 @@ pub struct LauncherArgs {
     help_text: String,
     fuzzy_help_text: String,
     alphabet: String,
+    augmented: Option<Receiver<Vec<LaunchMenuItem>>>,
 }
 
 impl LauncherArgs {
@@ -165,8 +240,16 @@ impl LauncherArgs {
             help_text: help_text.to_string(),
             fuzzy_help_text: fuzzy_help_text.to_string(),
             alphabet: alphabet.to_string(),
+            augmented: None,
         }
     }
+
+    /// Adds the entries received from `augmented` to the launch menu
+    /// items once they arrive
+    pub fn with_augmented_launch_menu(mut self, augmented: Receiver<Vec<LaunchMenuItem>>) -> Self {
+        self.augmented.replace(augmented);
+        self
+    }
 }
 
 const ROW_OVERHEAD: usize = 3;
@@ -187,6 +270,13 @@ struct LauncherState {
     alphabet: String,
     selection: String,
     always_fuzzy: bool,
+    /// The menus above the submenu that is shown, outermost first
+    parents: Vec<ParentMenu>,
+    /// Entries from `augment-launch-menu` that are still loading
+    pending: Option<Receiver<Vec<LaunchMenuItem>>>,
+    /// Where the entries from `augment-launch-menu` go in the top
+    /// level menu
+    augment_idx: usize,
 }
 
 impl LauncherState {
@@ -232,18 +322,10 @@ impl LauncherState {
         // section of the configuration.
         if args.flags.contains(LauncherFlags::LAUNCH_MENU_ITEMS) {
             for item in &config.launch_menu {
-                self.entries.push(Entry {
-                    label: match item.label.as_ref() {
-                        Some(label) => label.to_string(),
-                        None => match item.args.as_ref() {
-                            Some(args) => args.join(" "),
-                            None => "(default shell)".to_string(),
-                        },
-                    },
-                    action: KeyAssignment::SpawnCommandInNewTab(item.clone()),
-                });
+                self.entries.push(Entry::from_launch_menu_item(item));
             }
         }
+        self.augment_idx = self.entries.len();
 
         for domain in &args.domains {
             let entry = if domain.state == DomainState::Attached {
@@ -253,11 +335,13 @@ impl LauncherState {
                         domain: SpawnTabDomain::DomainName(domain.name.to_string()),
                         ..SpawnCommand::default()
                     }),
+                    children: vec![],
                 }
             } else {
                 Entry {
                     label: format!("Attach {}", domain.label),
                     action: KeyAssignment::AttachDomain(domain.name.to_string()),
+                    children: vec![],
                 }
             };
 
@@ -279,6 +363,7 @@ impl LauncherState {
                             name: Some(ws.clone()),
                             spawn: None,
                         },
+                        children: vec![],
                     });
                 }
             }
@@ -291,6 +376,7 @@ impl LauncherState {
                     name: None,
                     spawn: None,
                 },
+                children: vec![],
             });
         }
 
@@ -301,6 +387,7 @@ impl LauncherState {
                     None => format!("{}.", tab.title),
                 },
                 action: KeyAssignment::ActivateTab(tab.tab_idx as isize),
+                children: vec![],
             });
         }
 
@@ -317,6 +404,7 @@ impl LauncherState {
                 self.entries.push(Entry {
                     label: format!("{}. {}", cmd.brief, cmd.doc),
                     action: cmd.action,
+                    children: vec![],
                 });
             }
         }
@@ -357,6 +445,7 @@ impl LauncherState {
                 key_entries.push(Entry {
                     label,
                     action: entry.action,
+                    children: vec![],
                 });
             }
             key_entries.sort_by(|a, b| a.label.cmp(&b.label));
@@ -376,16 +465,23 @@ impl LauncherState {
             self.max_items = max_items;
         }
 
+        let mut header = String::new();
+        if !self.parents.is_empty() {
+            let path: Vec<&str> = self.parents.iter().map(|p| p.label.as_str()).collect();
+            header = format!("{}  Left=back  ", path.join(" \u{25b8} "));
+        }
+        header.push_str(&self.help_text);
+        if self.pending.is_some() {
+            header.push_str("  Loading\u{2026}");
+        }
+
         let mut changes = vec![
             Change::ClearScreen(ColorAttribute::Default),
             Change::CursorPosition {
                 x: Position::Absolute(0),
                 y: Position::Absolute(0),
             },
-            Change::Text(format!(
-                "{}\r\n",
-                truncate_right(&self.help_text, max_width)
-            )),
+            Change::Text(format!("{}\r\n", truncate_right(&header, max_width))),
             Change::AllAttributes(CellAttributes::default()),
         ];
 
@@ -443,7 +539,11 @@ impl LauncherState {
                 changes.push(Change::Text("    ".to_string()));
             }
 
-            let mut line = crate::tabbar::parse_status_text(&entry.label, attr.clone());
+            let mut line = if entry.children.is_empty() {
+                crate::tabbar::parse_status_text(&entry.label, attr.clone())
+            } else {
+                crate::tabbar::parse_status_text(&format!("{} \u{25b8}", entry.label), attr.clone())
+            };
             if line.len() > max_width {
                 line.resize(max_width, termwiz::surface::SEQ_ZERO);
             }
@@ -474,8 +574,84 @@ impl LauncherState {
         term.render(&changes)
     }
 
-    fn launch(&self, active_idx: usize) -> bool {
+    /// Replaces the shown entries with those of a submenu
+    fn enter_submenu(&mut self, label: String, children: Vec<Entry>) {
+        self.parents.push(ParentMenu {
+            label,
+            entries: std::mem::replace(&mut self.entries, children),
+            filter_term: std::mem::take(&mut self.filter_term),
+            active_idx: self.active_idx,
+            top_row: self.top_row,
+        });
+        self.active_idx = 0;
+        self.top_row = 0;
+        self.selection.clear();
+        self.filtering = self.always_fuzzy;
+        // Force the labels to be computed for the new entries
+        self.max_items = 0;
+        self.update_filter();
+    }
+
+    /// Returns to the menu that opened the current submenu.
+    /// Returns false if this is the top level menu.
+    fn leave_submenu(&mut self) -> bool {
+        let parent = match self.parents.pop() {
+            Some(parent) => parent,
+            None => return false,
+        };
+        self.entries = parent.entries;
+        self.filter_term = parent.filter_term;
+        self.filtering = self.always_fuzzy || !self.filter_term.is_empty();
+        self.selection.clear();
+        self.max_items = 0;
+        self.update_filter();
+        self.active_idx = parent.active_idx;
+        self.top_row = parent.top_row;
+        true
+    }
+
+    /// Adds the entries from `augment-launch-menu` to the top level
+    /// menu once they have arrived
+    fn receive_pending(&mut self) {
+        let items = match self.pending.as_ref().map(|rx| rx.try_recv()) {
+            Some(Ok(items)) => items,
+            Some(Err(TryRecvError::Empty)) | None => return,
+            Some(Err(TryRecvError::Closed)) => {
+                self.pending = None;
+                return;
+            }
+        };
+        self.pending = None;
+
+        let entries: Vec<Entry> = items.iter().map(Entry::from_launch_menu_item).collect();
+        let count = entries.len();
+        let idx = self.augment_idx;
+        match self.parents.first_mut() {
+            Some(top) => {
+                top.entries.splice(idx..idx, entries);
+                if top.filter_term.is_empty() && top.active_idx >= idx {
+                    top.active_idx += count;
+                }
+            }
+            None => {
+                self.entries.splice(idx..idx, entries);
+                let active_idx = self.active_idx;
+                self.max_items = 0;
+                self.update_filter();
+                if self.filter_term.is_empty() && active_idx >= idx {
+                    self.active_idx = active_idx + count;
+                }
+            }
+        }
+    }
+
+    fn launch(&mut self, active_idx: usize) -> bool {
         if let Some(entry) = self.filtered_entries.get(active_idx) {
+            if !entry.children.is_empty() {
+                let (label, children) = (entry.label.clone(), entry.children.clone());
+                self.enter_submenu(label, children);
+                return false;
+            }
             let assignment = entry.action.clone();
             self.window.notify(TermWindowNotif::PerformAssignment {
                 pane_id: self.pane_id,
@@ -503,7 +679,17 @@ impl LauncherState {
     }
 
     fn run_loop(&mut self, term: &mut TermWizTerminal) -> anyhow::Result<()> {
-        while let Ok(Some(event)) = term.poll_input(None) {
+        loop {
+            let wait = self.pending.as_ref().map(|_| PENDING_POLL_INTERVAL);
+            let event = match term.poll_input(wait) {
+                Ok(Some(event)) => event,
+                Ok(None) => {
+                    self.receive_pending();
+                    self.render(term)?;
+                    continue;
+                }
+                Err(_) => break,
+            };
             match event {
                 InputEvent::Key(KeyEvent {
                     key: KeyCode::Char(c),
@@ -571,7 +757,27 @@ impl LauncherState {
                     key: KeyCode::Escape,
                     ..
                 }) => {
-                    break;
+                    if !self.leave_submenu() {
+                        break;
+                    }
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::LeftArrow,
+                    ..
+                }) => {
+                    self.leave_submenu();
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::RightArrow,
+                    ..
+                }) => {
+                    let is_submenu = self
+                        .filtered_entries
+                        .get(self.active_idx)
+                        .map_or(false, |entry| !entry.children.is_empty());
+                    if is_submenu {
+                        self.launch(self.active_idx);
+                    }
                 }
                 InputEvent::Key(KeyEvent {
                     key: KeyCode::Char(c),
@@ -620,6 +826,9 @@ impl LauncherState {
                             if self.launch(self.active_idx) {
                                 break;
                             }
+                            // A submenu was opened
+                            self.render(term)?;
+                            continue;
                         }
                     }
                     if mouse_buttons != MouseButtons::NONE {
@@ -637,6 +846,7 @@ impl LauncherState {
                 }
                 _ => {}
             }
+            self.receive_pending();
             self.render(term)?;
         }
 
@@ -645,7 +855,7 @@ impl LauncherState {
 }
 
 pub fn launcher(
-    args: LauncherArgs,
+    mut args: LauncherArgs,
     mut term: TermWizTerminal,
     window: ::window::Window,
     initial_choice_idx: usize,
@@ -667,10 +877,14 @@ pub fn launcher(
         selection: String::new(),
         alphabet: args.alphabet.clone(),
         always_fuzzy: filtering,
+        parents: vec![],
+        pending: None,
+        augment_idx: 0,
     };
 
     term.set_raw_mode()?;
     term.render(&[Change::Title(args.title.to_string())])?;
+    state.pending = args.augmented.take();
     state.build_entries(args);
     state.update_filter();
     state.render(&mut term)?;
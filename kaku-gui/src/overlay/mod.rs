@@ -8,12 +8,17 @@ use wezterm_term::{TerminalConfiguration, TerminalSize};
 
 pub mod confirm;
 pub mod confirm_close_pane;
+pub mod connection_prompt;
 pub mod copy;
 pub mod debug;
+pub mod json_viewer;
+pub mod key_debugger;
 pub mod launcher;
+pub mod playback;
 pub mod prompt;
 pub mod quickselect;
 pub mod selector;
+pub mod workspace_search;
 
 pub use confirm_close_pane::{
     confirm_close_pane, confirm_close_tab, confirm_close_window, confirm_quit_program,
@@ -0,0 +1,287 @@
+use mux::recording::CastFile;
+use mux::termwiztermtab::TermWizTerminal;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use termwiz::cell::{AttributeChange, CellAttributes};
+use termwiz::color::AnsiColor;
+use termwiz::input::{InputEvent, KeyCode, KeyEvent};
+use termwiz::surface::{Change, CursorVisibility, Position};
+use termwiz::terminal::Terminal as _;
+use wezterm_term::{Terminal, TerminalSize};
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.;
+/// How far the arrow keys seek, in seconds of the recording
+const SEEK_STEP: f32 = 5.;
+/// How often the status line is refreshed while nothing is output
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where playback of a recording is up to
+struct Playback {
+    output: Vec<(f32, String)>,
+    /// The number of events that have been written to the terminal
+    next: usize,
+    /// The position in the recording, in seconds
+    position: f32,
+    speed: f32,
+    paused: bool,
+}
+
+impl Playback {
+    fn new(output: Vec<(f32, String)>) -> Self {
+        Self {
+            output,
+            next: 0,
+            position: 0.,
+            speed: 1.,
+            paused: false,
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        self.output.last().map(|event| event.0).unwrap_or(0.)
+    }
+
+    fn finished(&self) -> bool {
+        self.next >= self.output.len()
+    }
+
+    /// Moves the position on by `elapsed` seconds of real time and
+    /// returns the events that are now due
+    fn advance(&mut self, elapsed: Duration) -> Range<usize> {
+        if !self.paused {
+            self.position =
+                (self.position + elapsed.as_secs_f32() * self.speed).min(self.duration());
+        }
+        let end = self.end_for(self.position);
+        let due = self.next..end.max(self.next);
+        self.next = due.end;
+        due
+    }
+
+    /// Moves the position to `target`.  Returns the events that must
+    /// be written to show it, and whether the terminal must be reset
+    /// first because the seek goes backwards.
+    fn seek(&mut self, target: f32) -> (Range<usize>, bool) {
+        self.position = target.clamp(0., self.duration());
+        let end = self.end_for(self.position);
+        let result = if end < self.next {
+            (0..end, true)
+        } else {
+            (self.next..end, false)
+        };
+        self.next = end;
+        result
+    }
+
+    fn end_for(&self, position: f32) -> usize {
+        self.output.partition_point(|event| event.0 <= position)
+    }
+
+    /// How long to wait, in real time, for the next event
+    fn time_to_next_event(&self) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let (time, _) = self.output.get(self.next)?;
+        Some(Duration::from_secs_f32(
+            ((time - self.position) / self.speed).max(0.),
+        ))
+    }
+}
+
+fn new_terminal(cast: &CastFile) -> Terminal {
+    Terminal::new(
+        TerminalSize {
+            rows: cast.height.max(1),
+            cols: cast.width.max(1),
+            ..Default::default()
+        },
+        Arc::new(config::TermConfig::new()),
+        "Kaku",
+        config::wezterm_version(),
+        // Answerbacks to queries made by the recording go nowhere
+        Box::new(std::io::sink()),
+    )
+}
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Draws the screen of `terminal`, clipped to the overlay, with a
+/// status line and the playback controls at the bottom
+fn render(
+    term: &mut TermWizTerminal,
+    terminal: &Terminal,
+    playback: &Playback,
+) -> termwiz::Result<()> {
+    let size = term.get_screen_size()?;
+    let rows = size.rows.saturating_sub(1);
+    let mut changes = vec![
+        Change::ClearScreen(Default::default()),
+        Change::CursorVisibility(CursorVisibility::Hidden),
+    ];
+
+    for (y, line) in terminal
+        .screen()
+        .visible_lines()
+        .iter()
+        .take(rows)
+        .enumerate()
+    {
+        for cluster in line.cluster(None) {
+            if cluster.first_cell_idx >= size.cols {
+                break;
+            }
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(cluster.first_cell_idx),
+                y: Position::Absolute(y),
+            });
+            changes.push(Change::AllAttributes(cluster.attrs.clone()));
+            changes.push(cluster.text.into());
+        }
+    }
+
+    let state = if playback.paused {
+        "Paused"
+    } else if playback.finished() {
+        "Finished"
+    } else {
+        "Playing"
+    };
+    changes.push(Change::AllAttributes(CellAttributes::default()));
+    changes.push(Change::CursorPosition {
+        x: Position::Absolute(0),
+        y: Position::Absolute(rows),
+    });
+    changes.push(AttributeChange::Reverse(true).into());
+    changes.push(AttributeChange::Foreground(AnsiColor::Silver.into()).into());
+    let status = format!(
+        " {state} {} / {} at {}x  \
+         Space: pause  Left/Right: seek  0-9: jump  +/-: speed  q: close ",
+        format_time(playback.position),
+        format_time(playback.duration()),
+        playback.speed,
+    );
+    changes.push(format!("{status:<width$}", width = size.cols).into());
+
+    term.render(&changes)
+}
+
+/// Replays `cast` into a terminal that belongs to the overlay, which
+/// is read-only: keys control the playback rather than being sent to
+/// any program.
+pub fn play_recording(mut term: TermWizTerminal, cast: CastFile) -> anyhow::Result<()> {
+    term.set_raw_mode()?;
+
+    let mut terminal = new_terminal(&cast);
+    let mut playback = Playback::new(cast.output.clone());
+    let mut last_tick = Instant::now();
+
+    loop {
+        let now = Instant::now();
+        let due = playback.advance(now - last_tick);
+        last_tick = now;
+        for (_, data) in &playback.output[due] {
+            terminal.advance_bytes(data);
+        }
+        render(&mut term, &terminal, &playback)?;
+
+        let wait = playback
+            .time_to_next_event()
+            .map_or(STATUS_INTERVAL, |wait| wait.min(STATUS_INTERVAL));
+        let key = match term.poll_input(Some(wait))? {
+            Some(InputEvent::Key(KeyEvent { key, .. })) => key,
+            _ => continue,
+        };
+
+        let target = match key {
+            KeyCode::Escape | KeyCode::Char('q') | KeyCode::Char('Q') => break,
+            KeyCode::Char(' ') => {
+                if playback.finished() {
+                    // Start over
+                    Some(0.)
+                } else {
+                    playback.paused = !playback.paused;
+                    None
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                playback.speed = (playback.speed * 2.).min(MAX_SPEED);
+                None
+            }
+            KeyCode::Char('-') | KeyCode::Char('_') => {
+                playback.speed = (playback.speed / 2.).max(MIN_SPEED);
+                None
+            }
+            KeyCode::RightArrow => Some(playback.position + SEEK_STEP),
+            KeyCode::LeftArrow => Some(playback.position - SEEK_STEP),
+            KeyCode::Char(c @ '0'..='9') => {
+                Some(playback.duration() * c.to_digit(10).unwrap_or(0) as f32 / 10.)
+            }
+            _ => None,
+        };
+
+        if let Some(target) = target {
+            let (events, reset) = playback.seek(target);
+            if reset {
+                terminal = new_terminal(&cast);
+            }
+            for (_, data) in &playback.output[events] {
+                terminal.advance_bytes(data);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn playback() -> Playback {
+        Playback::new(
+            [1., 2., 3., 4.]
+                .iter()
+                .map(|&t| (t, String::new()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn advance() {
+        let mut playback = playback();
+        assert_eq!(playback.advance(Duration::from_millis(500)), 0..0);
+        assert_eq!(playback.advance(Duration::from_millis(1500)), 0..2);
+        assert_eq!(playback.time_to_next_event(), Some(Duration::from_secs(1)));
+
+        playback.speed = 2.;
+        assert_eq!(playback.advance(Duration::from_secs(1)), 2..4);
+        assert!(playback.finished());
+        assert_eq!(playback.position, 4.);
+    }
+
+    #[test]
+    fn paused() {
+        let mut playback = playback();
+        playback.paused = true;
+        assert_eq!(playback.advance(Duration::from_secs(10)), 0..0);
+        assert_eq!(playback.position, 0.);
+        assert_eq!(playback.time_to_next_event(), None);
+    }
+
+    #[test]
+    fn seek() {
+        let mut playback = playback();
+        playback.advance(Duration::from_millis(1500));
+        assert_eq!(playback.seek(3.5), (1..3, false));
+        assert_eq!(playback.seek(1.5), (0..1, true));
+        assert_eq!(playback.seek(-5.), (0..0, true));
+        assert_eq!(playback.seek(10.), (0..4, false));
+        assert_eq!(playback.position, 4.);
+    }
+}
@@ -1,6 +1,10 @@
+use crate::scripting::guiwin::GuiWin;
 use crate::selection::{SelectionCoordinate, SelectionRange};
 use crate::termwindow::{TermWindow, TermWindowNotif};
-use config::keyassignment::{ClipboardCopyDestination, QuickSelectArguments, ScrollbackEraseMode};
+use config::keyassignment::{
+    ClipboardCopyDestination, KeyAssignment, QuickSelectAction, QuickSelectArguments,
+    QuickSelectPattern, ScrollbackEraseMode,
+};
 use config::ConfigHandle;
 use mux::domain::DomainId;
 use mux::pane::{
@@ -8,10 +12,13 @@ use mux::pane::{
     WithPaneLines,
 };
 use mux::renderable::*;
+use mux_lua::MuxPane;
 use parking_lot::{MappedMutexGuard, Mutex};
 use rangeset::RangeSet;
 use std::collections::HashMap;
+use std::io::Write;
 use std::ops::Range;
+use std::rc::Rc;
 use std::sync::Arc;
 use termwiz::cell::{Cell, CellAttributes};
 use termwiz::color::AnsiColor;
@@ -227,6 +234,8 @@ struct QuickSelectRenderable {
 
     config: ConfigHandle,
     args: QuickSelectArguments,
+    /// The user-provided patterns, which may have their own actions
+    patterns: Vec<QuickSelectPattern>,
 }
 
 impl QuickSelectOverlay {
@@ -240,38 +249,22 @@ impl QuickSelectOverlay {
 
         let config = term_window.config.clone();
 
-        let mut pattern = "(?m)(".to_string();
-        let mut have_patterns = false;
-        if !args.patterns.is_empty() {
-            for p in &args.patterns {
-                if have_patterns {
-                    pattern.push('|');
-                }
-                pattern.push_str(p);
-                have_patterns = true;
-            }
+        let patterns = if !args.patterns.is_empty() {
+            args.patterns.clone()
         } else {
-            // User-provided patterns take precedence over built-ins
-            for p in &config.quick_select_patterns {
-                if have_patterns {
-                    pattern.push('|');
-                }
-                pattern.push_str(p);
-                have_patterns = true;
-            }
-            if !config.disable_default_quick_select_patterns {
-                for p in &PATTERNS {
-                    if have_patterns {
-                        pattern.push('|');
-                    }
-                    pattern.push_str(p);
-                    have_patterns = true;
-                }
-            }
+            config.quick_select_patterns.clone()
+        };
+
+        // User-provided patterns take precedence over built-ins
+        let mut alternatives: Vec<String> = patterns
+            .iter()
+            .map(|p| strip_capture_names(&p.regex))
+            .collect();
+        if args.patterns.is_empty() && !config.disable_default_quick_select_patterns {
+            alternatives.extend(PATTERNS.iter().map(|p| p.to_string()));
         }
-        pattern.push(')');
 
-        let pattern = Pattern::Regex(pattern);
+        let pattern = Pattern::Regex(format!("(?m)({})", alternatives.join("|")));
 
         let window = term_window.window.clone().unwrap();
         let mut renderer = QuickSelectRenderable {
@@ -290,6 +283,7 @@ impl QuickSelectOverlay {
             height: dims.viewport_rows,
             config,
             args: args.clone(),
+            patterns,
         };
 
         let search_row = renderer.compute_search_row();
@@ -933,6 +927,7 @@ impl QuickSelectRenderable {
         let pane_id = self.delegate.pane_id();
         let action = self.args.action.clone();
         let skip_action_on_paste = self.args.skip_action_on_paste;
+        let patterns = self.patterns.clone();
         self.window
             .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
                 let mux = mux::Mux::get();
@@ -960,7 +955,26 @@ impl QuickSelectRenderable {
                         if paste {
                             let _ = pane.send_paste(&text);
                         }
-                        if let Some(action) = action {
+                        let found = find_pattern(&patterns, &pane, result.start_y, &text);
+                        if let Some((
+                            QuickSelectPattern {
+                                action: Some(action),
+                                ..
+                            },
+                            captures,
+                        )) = found
+                        {
+                            if !paste || !skip_action_on_paste {
+                                perform_pattern_action(
+                                    term_window,
+                                    &pane,
+                                    action,
+                                    paste,
+                                    text,
+                                    captures,
+                                );
+                            }
+                        } else if let Some(action) = action {
                             if !paste || !skip_action_on_paste {
                                 let _ = term_window.perform_key_assignment(&pane, &action);
                             }
@@ -981,3 +995,151 @@ impl QuickSelectRenderable {
         self.set_viewport(Some(result.start_y));
     }
 }
+
+/// Turns named capture groups into plain capture groups, so that
+/// patterns that use the same names can be combined into one regex
+fn strip_capture_names(regex: &str) -> String {
+    let mut result = String::with_capacity(regex.len());
+    let mut rest = regex;
+    while let Some(idx) = rest.find(|c: char| c == '\\' || c == '(') {
+        result.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if rest.starts_with('\\') {
+            // Keep escaped characters, including `\(`, as they are
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            result.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        let name_start = if rest.starts_with("(?P<") {
+            Some(4)
+        } else if rest.starts_with("(?<") && !rest.starts_with("(?<=") && !rest.starts_with("(?<!")
+        {
+            Some(3)
+        } else {
+            None
+        };
+        result.push('(');
+        rest = match name_start.and_then(|start| rest[start..].find('>').map(|end| start + end + 1))
+        {
+            Some(end) => &rest[end..],
+            None => &rest[1..],
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Finds the first of `patterns` that selects `text` from the logical
+/// line that contains `row`, returning it along with its named captures
+fn find_pattern<'a>(
+    patterns: &'a [QuickSelectPattern],
+    pane: &Arc<dyn Pane>,
+    row: StableRowIndex,
+    text: &str,
+) -> Option<(&'a QuickSelectPattern, HashMap<String, String>)> {
+    let line = pane.get_logical_lines(row..row + 1).into_iter().next()?;
+    let haystack = line.logical.as_str();
+    for pattern in patterns {
+        let re = match fancy_regex::Regex::new(&format!("(?m){}", pattern.regex)) {
+            Ok(re) => re,
+            Err(err) => {
+                log::error!("Invalid quick_select pattern {}: {:#}", pattern.regex, err);
+                continue;
+            }
+        };
+        for caps in re.captures_iter(&haystack).filter_map(Result::ok) {
+            // The search selects the last capture that participated
+            // in the match, so do the same here
+            let selected = (0..caps.len()).rev().find_map(|idx| caps.get(idx));
+            if selected.map(|m| m.as_str()) != Some(text) {
+                continue;
+            }
+            let captures = re
+                .capture_names()
+                .enumerate()
+                .filter_map(|(idx, name)| {
+                    Some((name?.to_string(), caps.get(idx)?.as_str().to_string()))
+                })
+                .collect();
+            return Some((pattern, captures));
+        }
+    }
+    None
+}
+
+fn perform_pattern_action(
+    term_window: &mut TermWindow,
+    pane: &Arc<dyn Pane>,
+    action: &QuickSelectAction,
+    pasted: bool,
+    text: String,
+    captures: HashMap<String, String>,
+) {
+    match action {
+        QuickSelectAction::Copy => term_window
+            .copy_to_clipboard(ClipboardCopyDestination::ClipboardAndPrimarySelection, text),
+        QuickSelectAction::Paste => {
+            if !pasted {
+                let _ = pane.send_paste(&text);
+            }
+        }
+        QuickSelectAction::OpenUrl => wezterm_open_url::open_url(&text),
+        QuickSelectAction::SendToPane => {
+            if let Err(err) = pane.writer().write_all(text.as_bytes()) {
+                log::error!("Failed to send quick select text to pane: {:#}", err);
+            }
+        }
+        QuickSelectAction::KeyAssignment(assignment) => match &**assignment {
+            KeyAssignment::EmitEvent(name) => {
+                let name = name.clone();
+                let window = GuiWin::new(term_window);
+                let pane = MuxPane(pane.pane_id());
+                promise::spawn::spawn(async move {
+                    config::with_lua_config_on_main_thread(move |lua| {
+                        do_event(lua, name, window, pane, text, captures)
+                    })
+                    .await
+                })
+                .detach();
+            }
+            assignment => {
+                let _ = term_window.perform_key_assignment(pane, assignment);
+            }
+        },
+    }
+}
+
+async fn do_event(
+    lua: Option<Rc<mlua::Lua>>,
+    name: String,
+    window: GuiWin,
+    pane: MuxPane,
+    text: String,
+    captures: HashMap<String, String>,
+) -> anyhow::Result<()> {
+    if let Some(lua) = lua {
+        let args = lua.pack_multi((window, pane, text, captures))?;
+
+        if let Err(err) = config::lua::emit_event(&lua, (name.clone(), args)).await {
+            log::error!("while processing {} event: {:#}", name, err);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod pattern_test {
+    use super::*;
+
+    #[test]
+    fn strip_names() {
+        assert_eq!(
+            strip_capture_names(r"commit (?P<sha>[0-9a-f]+) \(?<x>(?<=a)(?:b)"),
+            r"commit ([0-9a-f]+) \(?<x>(?<=a)(?:b)"
+        );
+        assert_eq!(strip_capture_names(r"(?<a>x)|(?<a>y)\"), r"(x)|(y)\");
+    }
+}
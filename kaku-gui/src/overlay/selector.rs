@@ -29,6 +29,19 @@ pub fn matcher_score(pattern: &Pattern, s: &str) -> Option<u32> {
     })
 }
 
+/// Returns the positions of the graphemes in `s` that match `pattern`,
+/// in ascending order
+pub fn matcher_indices(pattern: &Pattern, s: &str) -> Option<Vec<u32>> {
+    MATCHER.with_borrow_mut(|matcher| {
+        let mut buf = vec![];
+        let mut indices = vec![];
+        pattern.indices(Utf32Str::new(s, &mut buf), matcher, &mut indices)?;
+        indices.sort_unstable();
+        indices.dedup();
+        Some(indices)
+    })
+}
+
 pub fn matcher_pattern(s: &str) -> Pattern {
     nucleo_matcher::pattern::Pattern::parse(
         s,
@@ -37,6 +50,15 @@ pub fn matcher_pattern(s: &str) -> Pattern {
     )
 }
 
+/// What happens once an entry has been picked, or the selector
+/// has been cancelled
+enum Completion {
+    /// Emit the event registered by `action_callback`
+    Event(String),
+    /// Call a function on the main thread
+    Callback(Box<dyn FnOnce(Option<InputSelectorEntry>) + Send>),
+}
+
 struct SelectorState {
     active_idx: usize,
     max_items: usize,
@@ -48,7 +70,7 @@ struct SelectorState {
     filtering: bool,
     always_fuzzy: bool,
     args: InputSelector,
-    event_name: String,
+    completion: Option<Completion>,
     selection: String,
     labels: Vec<String>,
 }
@@ -204,19 +226,30 @@ impl SelectorState {
         term.render(&changes)
     }
 
-    fn trigger_event(&self, entry: Option<InputSelectorEntry>) {
-        let name = self.event_name.clone();
+    fn trigger_event(&mut self, entry: Option<InputSelectorEntry>) {
         let window = self.window.clone();
         let pane = self.pane.clone();
 
-        promise::spawn::spawn_into_main_thread(async move {
-            trampoline(name, window, pane, entry);
-            anyhow::Result::<()>::Ok(())
-        })
-        .detach();
+        match self.completion.take() {
+            Some(Completion::Event(name)) => {
+                promise::spawn::spawn_into_main_thread(async move {
+                    trampoline(name, window, pane, entry);
+                    anyhow::Result::<()>::Ok(())
+                })
+                .detach();
+            }
+            Some(Completion::Callback(func)) => {
+                promise::spawn::spawn_into_main_thread(async move {
+                    func(entry);
+                    anyhow::Result::<()>::Ok(())
+                })
+                .detach();
+            }
+            None => {}
+        }
     }
 
-    fn launch(&self, active_idx: usize) -> bool {
+    fn launch(&mut self, active_idx: usize) -> bool {
         if let Some(entry) = self.filtered_entries.get(active_idx).cloned() {
             self.trigger_event(Some(entry));
             true
@@ -413,7 +446,7 @@ async fn do_event(
 }
 
 pub fn selector(
-    mut term: TermWizTerminal,
+    term: TermWizTerminal,
     args: InputSelector,
     window: GuiWin,
     pane: MuxPane,
@@ -424,6 +457,35 @@ pub fn selector(
             anyhow::bail!("InputSelector requires action to be defined by action_callback")
         }
     };
+    run_selector(term, args, window, pane, Completion::Event(event_name))
+}
+
+/// Like `selector`, but passes the picked entry, or None if the
+/// selector was cancelled, to `callback` rather than emitting an
+/// event.  `args.action` is ignored.
+pub fn selector_with_callback(
+    term: TermWizTerminal,
+    args: InputSelector,
+    window: GuiWin,
+    pane: MuxPane,
+    callback: impl FnOnce(Option<InputSelectorEntry>) + Send + 'static,
+) -> anyhow::Result<()> {
+    run_selector(
+        term,
+        args,
+        window,
+        pane,
+        Completion::Callback(Box::new(callback)),
+    )
+}
+
+fn run_selector(
+    mut term: TermWizTerminal,
+    args: InputSelector,
+    window: GuiWin,
+    pane: MuxPane,
+    completion: Completion,
+) -> anyhow::Result<()> {
     let mut state = SelectorState {
         active_idx: 0,
         max_items: 0,
@@ -435,7 +497,7 @@ pub fn selector(
         filtering: args.fuzzy,
         always_fuzzy: args.fuzzy,
         args,
-        event_name,
+        completion: Some(completion),
         selection: String::new(),
         labels: vec![],
     };
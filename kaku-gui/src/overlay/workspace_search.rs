@@ -0,0 +1,493 @@
+//! Searches the scrollback of every pane in the workspace, shown by
+//! the `SearchWorkspace` key assignment.  Choosing a match activates
+//! the pane that contains it and highlights it in that pane's search UI.
+use crate::termwindow::TermWindowNotif;
+use mux::pane::{PaneId, Pattern, SearchResult};
+use mux::termwiztermtab::TermWizTerminal;
+use mux::Mux;
+use termwiz::cell::{AttributeChange, CellAttributes, Intensity, Underline};
+use termwiz::color::ColorAttribute;
+use termwiz::input::{InputEvent, KeyCode, KeyEvent, Modifiers};
+use termwiz::lineedit::{Action, BasicHistory, History, LineEditor, LineEditorHost};
+use termwiz::surface::{Change, CursorVisibility, Position};
+use termwiz::terminal::Terminal;
+use termwiz_funcs::truncate_right;
+use wezterm_term::{unicode_column_width, Line, StableRowIndex};
+
+const ROW_OVERHEAD: usize = 1;
+
+/// The most matches collected from a single pane
+const MAX_MATCHES_PER_PANE: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Match {
+    result: SearchResult,
+    /// The text of the line around the match
+    before: String,
+    matched: String,
+    after: String,
+}
+
+impl Match {
+    fn new(result: SearchResult, line: &Line) -> Self {
+        let len = line.len();
+        let end_x = if result.end_y == result.start_y {
+            result.end_x.min(len)
+        } else {
+            len
+        };
+        let start_x = result.start_x.min(end_x);
+        Self {
+            result,
+            before: line.columns_as_str(0..start_x).trim_start().to_string(),
+            matched: line.columns_as_str(start_x..end_x),
+            after: line.columns_as_str(end_x..len).trim_end().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PaneMatches {
+    pane_id: PaneId,
+    title: String,
+    matches: Vec<Match>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    /// The heading for `panes[idx]`
+    Pane(usize),
+    /// `panes[pane_idx].matches[match_idx]`
+    Match(usize, usize),
+}
+
+fn layout_rows(panes: &[PaneMatches]) -> Vec<Row> {
+    let mut rows = vec![];
+    for (pane_idx, pane) in panes.iter().enumerate() {
+        rows.push(Row::Pane(pane_idx));
+        for match_idx in 0..pane.matches.len() {
+            rows.push(Row::Match(pane_idx, match_idx));
+        }
+    }
+    rows
+}
+
+/// Runs on the main thread
+async fn search_panes(pane_ids: Vec<PaneId>, pattern: Pattern) -> Vec<PaneMatches> {
+    let mux = Mux::get();
+    let mut found = vec![];
+    for pane_id in pane_ids {
+        let pane = match mux.get_pane(pane_id) {
+            Some(pane) => pane,
+            None => continue,
+        };
+        let dims = pane.get_dimensions();
+        let range =
+            dims.scrollback_top..dims.scrollback_top + dims.scrollback_rows as StableRowIndex;
+        let mut results = match pane
+            .search(pattern.clone(), range, Some(MAX_MATCHES_PER_PANE))
+            .await
+        {
+            Ok(results) => results,
+            Err(err) => {
+                log::error!("searching pane {pane_id}: {err:#}");
+                continue;
+            }
+        };
+        if results.is_empty() {
+            continue;
+        }
+        results.sort();
+
+        let matches = results
+            .into_iter()
+            .filter_map(|result| {
+                let (_first_row, lines) = pane.get_lines(result.start_y..result.start_y + 1);
+                lines.first().map(|line| Match::new(result, line))
+            })
+            .collect();
+        found.push(PaneMatches {
+            pane_id,
+            title: pane.get_title(),
+            matches,
+        });
+    }
+    found
+}
+
+struct PatternHost {
+    history: BasicHistory,
+}
+
+impl LineEditorHost for PatternHost {
+    fn history(&mut self) -> &mut dyn History {
+        &mut self.history
+    }
+
+    fn resolve_action(
+        &mut self,
+        event: &InputEvent,
+        _editor: &mut LineEditor<'_>,
+    ) -> Option<Action> {
+        match event {
+            InputEvent::Key(KeyEvent {
+                key: KeyCode::Escape,
+                ..
+            }) => Some(Action::Cancel),
+            _ => None,
+        }
+    }
+}
+
+struct WorkspaceSearchState {
+    pattern: Pattern,
+    panes: Vec<PaneMatches>,
+    rows: Vec<Row>,
+    /// Always refers to a `Row::Match`, unless there are none
+    active_idx: usize,
+    top_row: usize,
+    max_items: usize,
+    window: ::window::Window,
+}
+
+impl WorkspaceSearchState {
+    fn new(pattern: Pattern, panes: Vec<PaneMatches>, window: ::window::Window) -> Self {
+        let rows = layout_rows(&panes);
+        let active_idx = rows
+            .iter()
+            .position(|row| matches!(row, Row::Match(..)))
+            .unwrap_or(0);
+        Self {
+            pattern,
+            panes,
+            rows,
+            active_idx,
+            top_row: 0,
+            max_items: 0,
+            window,
+        }
+    }
+
+    fn scroll_to_active(&mut self) {
+        // Keep the heading of the first match of a pane in view
+        let top = match self.active_idx.checked_sub(1).map(|idx| self.rows[idx]) {
+            Some(Row::Pane(_)) => self.active_idx - 1,
+            _ => self.active_idx,
+        };
+        if top < self.top_row {
+            self.top_row = top;
+        } else if self.max_items > 0 && self.active_idx >= self.top_row + self.max_items {
+            self.top_row = self.active_idx + 1 - self.max_items;
+        }
+    }
+
+    /// Moves the selection by `delta` matches, skipping the headings
+    fn move_by(&mut self, delta: isize) {
+        let match_rows: Vec<usize> = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, Row::Match(..)))
+            .map(|(idx, _)| idx)
+            .collect();
+        let pos = match match_rows.iter().position(|&idx| idx == self.active_idx) {
+            Some(pos) => pos as isize,
+            None => return,
+        };
+        let last = match_rows.len().saturating_sub(1) as isize;
+        self.active_idx = match_rows[(pos + delta).max(0).min(last) as usize];
+        self.scroll_to_active();
+    }
+
+    /// Moves the selection to the first match of the next or
+    /// previous pane
+    fn move_to_pane(&mut self, forward: bool) {
+        let current = match self.rows.get(self.active_idx) {
+            Some(Row::Match(pane_idx, _)) => *pane_idx,
+            _ => return,
+        };
+        let target = if forward {
+            (current + 1).min(self.panes.len() - 1)
+        } else {
+            current.saturating_sub(1)
+        };
+        if let Some(idx) = self
+            .rows
+            .iter()
+            .position(|row| *row == Row::Match(target, 0))
+        {
+            self.active_idx = idx;
+            self.scroll_to_active();
+        }
+    }
+
+    fn jump_to_active(&self) {
+        let (pane_idx, match_idx) = match self.rows.get(self.active_idx) {
+            Some(Row::Match(pane_idx, match_idx)) => (*pane_idx, *match_idx),
+            _ => return,
+        };
+        let pane_id = self.panes[pane_idx].pane_id;
+        let result = self.panes[pane_idx].matches[match_idx].result;
+        let pattern = self.pattern.clone();
+        self.window
+            .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                if let Err(err) = term_window.jump_to_search_match(pane_id, pattern, result) {
+                    log::error!("jumping to match in pane {pane_id}: {err:#}");
+                }
+            })));
+    }
+
+    fn render(&mut self, term: &mut TermWizTerminal) -> termwiz::Result<()> {
+        let size = term.get_screen_size()?;
+        let max_width = size.cols.saturating_sub(1);
+        self.max_items = size.rows.saturating_sub(ROW_OVERHEAD);
+        self.scroll_to_active();
+
+        let num_matches: usize = self.panes.iter().map(|pane| pane.matches.len()).sum();
+        let header = format!(
+            "Search workspace for \"{}\": {num_matches} matches in {} panes. \
+             Enter: jump, Tab: next pane, Esc: close",
+            self.pattern.as_str(),
+            self.panes.len()
+        );
+
+        let mut changes = vec![
+            Change::ClearScreen(ColorAttribute::Default),
+            Change::CursorVisibility(CursorVisibility::Hidden),
+            Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(0),
+            },
+            AttributeChange::Intensity(Intensity::Bold).into(),
+            Change::Text(truncate_right(&header, max_width)),
+            Change::AllAttributes(CellAttributes::default()),
+        ];
+
+        for (row_num, (idx, row)) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(self.top_row)
+            .take(self.max_items)
+            .enumerate()
+        {
+            changes.push(Change::CursorPosition {
+                x: Position::Absolute(0),
+                y: Position::Absolute(row_num + ROW_OVERHEAD),
+            });
+            match *row {
+                Row::Pane(pane_idx) => {
+                    let pane = &self.panes[pane_idx];
+                    changes.push(AttributeChange::Intensity(Intensity::Bold).into());
+                    changes.push(Change::Text(truncate_right(
+                        &format!(
+                            "Pane {}: {} ({})",
+                            pane.pane_id,
+                            pane.title,
+                            pane.matches.len()
+                        ),
+                        max_width,
+                    )));
+                    changes.push(Change::AllAttributes(CellAttributes::default()));
+                }
+                Row::Match(pane_idx, match_idx) => {
+                    let m = &self.panes[pane_idx].matches[match_idx];
+                    let active = idx == self.active_idx;
+                    if active {
+                        changes.push(AttributeChange::Reverse(true).into());
+                    }
+                    let mut remain = max_width;
+                    let mut push_clipped = |changes: &mut Vec<Change>, text: &str| {
+                        let text = truncate_right(text, remain);
+                        remain = remain.saturating_sub(unicode_column_width(&text, None));
+                        changes.push(Change::Text(text));
+                    };
+                    push_clipped(&mut changes, "  ");
+                    push_clipped(&mut changes, &m.before);
+                    changes.push(AttributeChange::Intensity(Intensity::Bold).into());
+                    changes.push(AttributeChange::Underline(Underline::Single).into());
+                    push_clipped(&mut changes, &m.matched);
+                    changes.push(AttributeChange::Intensity(Intensity::Normal).into());
+                    changes.push(AttributeChange::Underline(Underline::None).into());
+                    push_clipped(&mut changes, &m.after);
+                    if active {
+                        changes.push(AttributeChange::Reverse(false).into());
+                    }
+                }
+            }
+        }
+
+        term.render(&changes)?;
+        term.flush()
+    }
+
+    fn run_loop(&mut self, term: &mut TermWizTerminal) -> anyhow::Result<()> {
+        self.render(term)?;
+        while let Ok(Some(event)) = term.poll_input(None) {
+            match event {
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('G' | 'C'),
+                    modifiers: Modifiers::CTRL,
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::Escape | KeyCode::Char('q'),
+                    ..
+                }) => break,
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Enter,
+                    ..
+                }) => {
+                    self.jump_to_active();
+                    break;
+                }
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('j'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::DownArrow,
+                    ..
+                }) => self.move_by(1),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('k'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::UpArrow,
+                    ..
+                }) => self.move_by(-1),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::PageDown,
+                    ..
+                }) => self.move_by(self.max_items.max(1) as isize),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::PageUp,
+                    ..
+                }) => self.move_by(-(self.max_items.max(1) as isize)),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('g'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::Home, ..
+                }) => self.move_by(-(self.rows.len() as isize)),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Char('G'),
+                    ..
+                })
+                | InputEvent::Key(KeyEvent {
+                    key: KeyCode::End, ..
+                }) => self.move_by(self.rows.len() as isize),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Tab,
+                    modifiers: Modifiers::SHIFT,
+                }) => self.move_to_pane(false),
+                InputEvent::Key(KeyEvent {
+                    key: KeyCode::Tab, ..
+                }) => self.move_to_pane(true),
+                _ => {}
+            }
+            self.render(term)?;
+        }
+        Ok(())
+    }
+}
+
+/// Searches the scrollback of `pane_ids` for `pattern`, first
+/// prompting for the text to find if `pattern` is empty, and lists
+/// the matches grouped by pane.
+pub fn workspace_search(
+    mut term: TermWizTerminal,
+    mut pattern: Pattern,
+    pane_ids: Vec<PaneId>,
+    window: ::window::Window,
+) -> anyhow::Result<()> {
+    term.no_grab_mouse_in_raw_mode();
+
+    if pattern.is_empty() {
+        let mut host = PatternHost {
+            history: BasicHistory::default(),
+        };
+        let mut editor = LineEditor::new(&mut term);
+        editor.set_prompt("Search workspace: ");
+        match editor.read_line(&mut host)? {
+            Some(line) if !line.is_empty() => *pattern = line,
+            _ => return Ok(()),
+        }
+    }
+
+    term.render(&[Change::Text("\r\nSearching...".to_string())])?;
+
+    // Pane searches are not Send, so they are spawned as local tasks
+    // on the main thread, which report back through this channel
+    let (tx, rx) = smol::channel::bounded(1);
+    let search_pattern = pattern.clone();
+    promise::spawn::spawn_into_main_thread(async move {
+        promise::spawn::spawn(async move {
+            let panes = search_panes(pane_ids, search_pattern).await;
+            tx.send(panes).await.ok();
+        })
+        .detach();
+    })
+    .detach();
+    let panes = smol::block_on(rx.recv())?;
+
+    term.set_raw_mode()?;
+    let mut state = WorkspaceSearchState::new(pattern, panes, window);
+    state.run_loop(&mut term)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pane(pane_id: PaneId, num_matches: usize) -> PaneMatches {
+        let result = SearchResult {
+            start_y: 0,
+            start_x: 0,
+            end_y: 0,
+            end_x: 1,
+            match_id: 0,
+        };
+        let m = Match::new(
+            result,
+            &Line::from_text("x", &CellAttributes::default(), 0, None),
+        );
+        PaneMatches {
+            pane_id,
+            title: String::new(),
+            matches: vec![m; num_matches],
+        }
+    }
+
+    #[test]
+    fn rows() {
+        let panes = vec![pane(1, 2), pane(4, 1)];
+        assert_eq!(
+            layout_rows(&panes),
+            vec![
+                Row::Pane(0),
+                Row::Match(0, 0),
+                Row::Match(0, 1),
+                Row::Pane(1),
+                Row::Match(1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_text() {
+        let line = Line::from_text("  let foo = bar;  ", &CellAttributes::default(), 0, None);
+        let result = SearchResult {
+            start_y: 10,
+            start_x: 6,
+            end_y: 10,
+            end_x: 9,
+            match_id: 0,
+        };
+        let m = Match::new(result, &line);
+        assert_eq!(m.before, "let ");
+        assert_eq!(m.matched, "foo");
+        assert_eq!(m.after, " = bar;");
+    }
+}
@@ -5,7 +5,6 @@ use crate::termwindow::webgpu::{adapter_info_to_gpu_info, WebGpuState, WebGpuTex
 use ::window::bitmaps::atlas::OutOfTextureSpace;
 use ::window::bitmaps::Texture2d;
 use ::window::glium::backend::Context as GliumContext;
-use ::window::glium::buffer::{BufferMutSlice, Mapping};
 use ::window::glium::{
     CapabilitiesSource, IndexBuffer as GliumIndexBuffer, VertexBuffer as GliumVertexBuffer,
 };
@@ -13,6 +12,7 @@ use ::window::*;
 use anyhow::Context;
 use std::cell::{Ref, RefCell, RefMut};
 use std::convert::TryInto;
+use std::ops::Range;
 use std::rc::Rc;
 use wezterm_font::FontConfiguration;
 use wgpu::util::DeviceExt;
@@ -42,15 +42,6 @@ impl RenderContext {
         }
     }
 
-    pub fn allocate_vertex_buffer_initializer(&self, num_quads: usize) -> Vec<Vertex> {
-        match self {
-            Self::Glium(_) => {
-                vec![Vertex::default(); num_quads * VERTICES_PER_CELL]
-            }
-            Self::WebGpu(_) => vec![],
-        }
-    }
-
     pub fn allocate_vertex_buffer(
         &self,
         num_quads: usize,
@@ -159,46 +150,16 @@ impl VertexBuffer {
             _ => unreachable!(),
         }
     }
-    pub fn webgpu_mut(&mut self) -> &mut WebGpuVertexBuffer {
-        match self {
-            Self::WebGpu(g) => g,
-            _ => unreachable!(),
-        }
-    }
 }
 
-enum MappedVertexBuffer {
-    Glium(GliumMappedVertexBuffer),
-    WebGpu(WebGpuMappedVertexBuffer),
-}
-
-impl MappedVertexBuffer {
-    fn slice_mut(&mut self, range: std::ops::Range<usize>) -> &mut [Vertex] {
-        match self {
-            Self::Glium(g) => &mut g.mapping[range],
-            Self::WebGpu(g) => {
-                let mapping: &mut [Vertex] = bytemuck::cast_slice_mut(&mut g.mapping);
-                &mut mapping[range]
-            }
-        }
-    }
-}
-
-pub struct MappedQuads<'a> {
-    mapping: MappedVertexBuffer,
+pub struct StagedQuads<'a> {
+    staging: RefMut<'a, Vec<Vertex>>,
     next: RefMut<'a, usize>,
     capacity: usize,
 }
 
-pub struct WebGpuMappedVertexBuffer {
-    mapping: wgpu::BufferViewMut<'static>,
-    // Owner mapping, must be dropped after mapping
-    _slice: wgpu::BufferSlice<'static>,
-}
-
 pub struct WebGpuVertexBuffer {
     buf: wgpu::Buffer,
-    num_vertices: usize,
     state: Rc<WebGpuState>,
 }
 
@@ -215,35 +176,21 @@ impl WebGpuVertexBuffer {
             buf: state.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Vertex Buffer"),
                 size: (num_vertices * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
-                usage: wgpu::BufferUsages::VERTEX,
-                mapped_at_creation: true,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
             }),
-            num_vertices,
             state: Rc::clone(state),
         }
     }
 
-    pub fn map(&self) -> WebGpuMappedVertexBuffer {
-        unsafe {
-            let slice = self.buf.slice(..).extend_lifetime();
-            let mapping = slice.get_mapped_range_mut();
-
-            WebGpuMappedVertexBuffer {
-                mapping,
-                _slice: slice,
-            }
-        }
-    }
-
-    pub fn recreate(&mut self) -> wgpu::Buffer {
-        let mut new_buf = self.state.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Vertex Buffer"),
-            size: (self.num_vertices * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::VERTEX,
-            mapped_at_creation: true,
-        });
-        std::mem::swap(&mut new_buf, &mut self.buf);
-        new_buf
+    /// Queues `vertices` to be written at `first_vertex`; the write
+    /// takes effect before the next submitted command buffer runs
+    pub fn write(&self, first_vertex: usize, vertices: &[Vertex]) {
+        self.state.queue.write_buffer(
+            &self.buf,
+            (first_vertex * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+            bytemuck::cast_slice(vertices),
+        );
     }
 }
 
@@ -272,17 +219,7 @@ impl WebGpuIndexBuffer {
     }
 }
 
-/// This is a self-referential struct, but since those are not possible
-/// to create safely in unstable rust, we transmute the lifetimes away
-/// to static and store the owner (RefMut) and the derived Mapping object
-/// in this struct
-pub struct GliumMappedVertexBuffer {
-    mapping: Mapping<'static, [Vertex]>,
-    // Drop the owner after the mapping
-    _owner: RefMut<'static, VertexBuffer>,
-}
-
-impl<'a> QuadAllocator for MappedQuads<'a> {
+impl<'a> QuadAllocator for StagedQuads<'a> {
     fn allocate<'b>(&'b mut self) -> anyhow::Result<QuadImpl<'b>> {
         let idx = *self.next;
         *self.next += 1;
@@ -298,7 +235,7 @@ impl<'a> QuadAllocator for MappedQuads<'a> {
 
         let idx = idx * VERTICES_PER_CELL;
         let mut quad = Quad {
-            vert: self.mapping.slice_mut(idx..idx + VERTICES_PER_CELL),
+            vert: &mut self.staging[idx..idx + VERTICES_PER_CELL],
         };
 
         quad.set_has_color(false);
@@ -318,21 +255,64 @@ impl<'a> QuadAllocator for MappedQuads<'a> {
         let idx = idx * VERTICES_PER_CELL;
         let capacity = self.capacity * VERTICES_PER_CELL;
         if idx + len <= capacity {
-            self.mapping
-                .slice_mut(idx..idx + len)
-                .copy_from_slice(vertices);
+            self.staging[idx..idx + len].copy_from_slice(vertices);
         }
     }
 }
 
-pub struct TripleVertexBuffer {
-    pub index: RefCell<usize>,
-    pub bufs: RefCell<[VertexBuffer; 3]>,
+/// The vertices for one of the three sub-layers of a `RenderLayer`.
+/// Quads are assembled in `staging` by the render pass, and `upload`
+/// then copies only the quads that differ from those already in the
+/// GPU buffer, so that an otherwise idle window (eg: one with just a
+/// blinking cursor) transfers very little data per frame.
+pub struct LayerVertexBuffer {
+    pub buf: RefCell<VertexBuffer>,
+    staging: RefCell<Vec<Vertex>>,
+    /// The vertices that are currently in `buf`, for comparison
+    /// with `staging`
+    uploaded: RefCell<Vec<Vertex>>,
     pub indices: IndexBuffer,
     pub capacity: usize,
     pub next_quad: RefCell<usize>,
 }
 
+/// Runs of changed quads that are separated by fewer than this many
+/// unchanged quads are uploaded together, to reduce the number of
+/// individual writes
+const UPLOAD_MERGE_GAP: usize = 8;
+
+/// Returns the ranges of quads, out of `num_quads`, for which `differs`
+/// is true.  Runs that are separated by fewer than `merge_gap`
+/// unchanged quads are coalesced into a single range.
+fn damaged_quad_ranges(
+    num_quads: usize,
+    merge_gap: usize,
+    differs: impl Fn(usize) -> bool,
+) -> Vec<Range<usize>> {
+    let mut damaged = vec![];
+    let mut quad = 0;
+    while quad < num_quads {
+        if !differs(quad) {
+            quad += 1;
+            continue;
+        }
+        let start = quad;
+        let mut end = quad + 1;
+        let mut gap = 0;
+        while end + gap < num_quads && gap < merge_gap {
+            if differs(end + gap) {
+                end += gap + 1;
+                gap = 0;
+            } else {
+                gap += 1;
+            }
+        }
+        damaged.push(start..end);
+        quad = end + gap;
+    }
+    damaged
+}
+
 /// A trait to avoid broadly-scoped transmutes; we only want to
 /// transmute to extend a lifetime to static, and not to change
 /// the underlying type.
@@ -350,37 +330,14 @@ unsafe impl<'a, T: 'static> ExtendStatic for Ref<'a, T> {
     }
 }
 
-unsafe impl<'a, T: 'static> ExtendStatic for RefMut<'a, T> {
-    type T = RefMut<'static, T>;
-    unsafe fn extend_lifetime(self) -> Self::T {
-        std::mem::transmute(self)
-    }
-}
-
-unsafe impl<'a> ExtendStatic for wgpu::BufferSlice<'a> {
-    type T = wgpu::BufferSlice<'static>;
-    unsafe fn extend_lifetime(self) -> Self::T {
-        std::mem::transmute(self)
-    }
-}
-
-unsafe impl<'a> ExtendStatic for MappedQuads<'a> {
-    type T = MappedQuads<'static>;
+unsafe impl<'a> ExtendStatic for StagedQuads<'a> {
+    type T = StagedQuads<'static>;
     unsafe fn extend_lifetime(self) -> Self::T {
         std::mem::transmute(self)
     }
 }
 
-unsafe impl<'a, T: ?Sized + ::window::glium::buffer::Content + 'static> ExtendStatic
-    for BufferMutSlice<'a, T>
-{
-    type T = BufferMutSlice<'static, T>;
-    unsafe fn extend_lifetime(self) -> Self::T {
-        std::mem::transmute(self)
-    }
-}
-
-impl TripleVertexBuffer {
+impl LayerVertexBuffer {
     pub fn clear_quad_allocation(&self) {
         *self.next_quad.borrow_mut() = 0;
     }
@@ -399,60 +356,64 @@ impl TripleVertexBuffer {
         (num_quads * VERTICES_PER_CELL, num_quads * INDICES_PER_CELL)
     }
 
-    pub fn map(&self) -> MappedQuads<'_> {
-        let mut bufs = self.current_vb_mut();
-
-        // To map the vertex buffer, we need to hold a mutable reference to
-        // the buffer and hold the mapping object alive for the duration
-        // of the access.  Rust doesn't allow us to create a struct that
-        // holds both of those things, because one references the other
-        // and it doesn't permit self-referential structs.
-        // We use the very blunt instrument "transmute" to force Rust to
-        // treat the lifetimes of both of these things as static, which
-        // we can then store in the same struct.
-        // This is "safe" because we carry them around together and ensure
-        // that the owner is dropped after the derived data.
-        let mapping = match &mut *bufs {
-            VertexBuffer::Glium(vb) => {
-                let buf_slice = unsafe {
-                    vb.slice_mut(..)
-                        .expect("to map vertex buffer")
-                        .extend_lifetime()
-                };
-                let mapping = buf_slice.map();
-
-                MappedVertexBuffer::Glium(GliumMappedVertexBuffer {
-                    _owner: bufs,
-                    mapping,
-                })
-            }
-            VertexBuffer::WebGpu(vb) => MappedVertexBuffer::WebGpu(vb.map()),
-        };
-
-        MappedQuads {
-            mapping,
+    pub fn stage(&self) -> StagedQuads<'_> {
+        StagedQuads {
+            staging: self.staging.borrow_mut(),
             next: self.next_quad.borrow_mut(),
             capacity: self.capacity,
         }
     }
 
-    pub fn current_vb_mut(&self) -> RefMut<'static, VertexBuffer> {
-        let index = *self.index.borrow();
-        let bufs = self.bufs.borrow_mut();
-        unsafe { RefMut::map(bufs, |bufs| &mut bufs[index]).extend_lifetime() }
-    }
+    /// Copies the staged quads that have changed since the last upload
+    /// into the GPU buffer.  Returns true if the staged quads differ
+    /// from those last uploaded, which includes there being a
+    /// different number of them.
+    pub fn upload(&self) -> anyhow::Result<bool> {
+        let (vertex_count, _) = self.vertex_index_count();
+        let staging = self.staging.borrow();
+        let staging = &staging[..vertex_count.min(staging.len())];
+        let mut uploaded = self.uploaded.borrow_mut();
+
+        let mut changed = staging.len() != uploaded.len();
+        let buf = self.buf.borrow();
+        let num_quads = staging.len() / VERTICES_PER_CELL;
+        let quad_differs = |idx: usize| {
+            let range = idx * VERTICES_PER_CELL..(idx + 1) * VERTICES_PER_CELL;
+            match uploaded.get(range.clone()) {
+                Some(prior) => {
+                    bytemuck::cast_slice::<Vertex, u8>(prior)
+                        != bytemuck::cast_slice::<Vertex, u8>(&staging[range])
+                }
+                None => true,
+            }
+        };
 
-    pub fn next_index(&self) {
-        let mut index = self.index.borrow_mut();
-        *index += 1;
-        if *index >= 3 {
-            *index = 0;
+        let damaged = damaged_quad_ranges(num_quads, UPLOAD_MERGE_GAP, quad_differs);
+
+        let mut uploaded_quads = 0;
+        for quads in damaged {
+            changed = true;
+            uploaded_quads += quads.len();
+            let range = quads.start * VERTICES_PER_CELL..quads.end * VERTICES_PER_CELL;
+            let vertices = &staging[range.clone()];
+            match &*buf {
+                VertexBuffer::Glium(vb) => vb
+                    .slice(range)
+                    .ok_or_else(|| anyhow::anyhow!("vertex range is outside the buffer"))?
+                    .write(vertices),
+                VertexBuffer::WebGpu(vb) => vb.write(range.start, vertices),
+            }
         }
+        metrics::histogram!("quad.upload.quads.size").record(uploaded_quads as f64);
+
+        uploaded.clear();
+        uploaded.extend_from_slice(staging);
+        Ok(changed)
     }
 }
 
 pub struct RenderLayer {
-    pub vb: RefCell<[TripleVertexBuffer; 3]>,
+    pub vb: RefCell<[LayerVertexBuffer; 3]>,
     context: RenderContext,
     zindex: i8,
 }
@@ -485,9 +446,9 @@ impl RenderLayer {
         // of the owner in the returned struct.
         unsafe {
             let vbs = self.vb.borrow().extend_lifetime();
-            let layer0 = vbs[0].map().extend_lifetime();
-            let layer1 = vbs[1].map().extend_lifetime();
-            let layer2 = vbs[2].map().extend_lifetime();
+            let layer0 = vbs[0].stage().extend_lifetime();
+            let layer1 = vbs[1].stage().extend_lifetime();
+            let layer2 = vbs[2].stage().extend_lifetime();
             TripleLayerQuadAllocator::Gpu(BorrowedLayers {
                 layers: [layer0, layer1, layer2],
                 _owner: vbs,
@@ -514,12 +475,12 @@ impl RenderLayer {
     fn compute_vertices(
         context: &RenderContext,
         num_quads: usize,
-    ) -> anyhow::Result<TripleVertexBuffer> {
-        let verts = context.allocate_vertex_buffer_initializer(num_quads);
+    ) -> anyhow::Result<LayerVertexBuffer> {
+        let staging = vec![Vertex::default(); num_quads * VERTICES_PER_CELL];
         log::trace!(
             "compute_vertices num_quads={}, allocated {} bytes",
             num_quads,
-            verts.len() * std::mem::size_of::<Vertex>()
+            staging.len() * std::mem::size_of::<Vertex>()
         );
         let mut indices = vec![];
         indices.reserve(num_quads * INDICES_PER_CELL);
@@ -537,13 +498,10 @@ impl RenderLayer {
             indices.push(idx + V_BOT_RIGHT as u32);
         }
 
-        let buffer = TripleVertexBuffer {
-            index: RefCell::new(0),
-            bufs: RefCell::new([
-                context.allocate_vertex_buffer(num_quads, &verts)?,
-                context.allocate_vertex_buffer(num_quads, &verts)?,
-                context.allocate_vertex_buffer(num_quads, &verts)?,
-            ]),
+        let buffer = LayerVertexBuffer {
+            buf: RefCell::new(context.allocate_vertex_buffer(num_quads, &staging)?),
+            staging: RefCell::new(staging),
+            uploaded: RefCell::new(vec![]),
             capacity: num_quads,
             indices: context.allocate_index_buffer(&indices)?,
             next_quad: RefCell::new(0),
@@ -554,10 +512,10 @@ impl RenderLayer {
 }
 
 pub struct BorrowedLayers {
-    pub layers: [MappedQuads<'static>; 3],
+    pub layers: [StagedQuads<'static>; 3],
 
     // layers references _owner, so it must be dropped after layers.
-    _owner: Ref<'static, [TripleVertexBuffer; 3]>,
+    _owner: Ref<'static, [LayerVertexBuffer; 3]>,
 }
 
 impl TripleLayerQuadAllocatorTrait for BorrowedLayers {
@@ -669,6 +627,18 @@ impl RenderState {
         Ok(allocated)
     }
 
+    /// Uploads the quads built by the render pass, returning true if
+    /// any of them differ from those of the prior frame
+    pub fn upload_quads(&self) -> anyhow::Result<bool> {
+        let mut changed = false;
+        for layer in self.layers.borrow().iter() {
+            for vb in layer.vb.borrow().iter() {
+                changed |= vb.upload()?;
+            }
+        }
+        Ok(changed)
+    }
+
     fn compile_prog(
         context: &Rc<GliumContext>,
         fragment_shader: fn(&str) -> (String, String),
@@ -777,3 +747,35 @@ impl RenderState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ranges(changed: &[usize], num_quads: usize, merge_gap: usize) -> Vec<Range<usize>> {
+        damaged_quad_ranges(num_quads, merge_gap, |idx| changed.contains(&idx))
+    }
+
+    #[test]
+    fn nothing_changed() {
+        assert_eq!(ranges(&[], 10, 2), vec![]);
+        assert_eq!(ranges(&[], 0, 2), vec![]);
+    }
+
+    #[test]
+    fn nearby_changes_are_merged() {
+        assert_eq!(ranges(&[1, 3, 4], 10, 2), vec![1..5]);
+        assert_eq!(ranges(&[1, 3], 10, 2), vec![1..4]);
+    }
+
+    #[test]
+    fn distant_changes_are_separate() {
+        assert_eq!(ranges(&[1, 4], 10, 2), vec![1..2, 4..5]);
+        assert_eq!(ranges(&[0, 9], 10, 2), vec![0..1, 9..10]);
+    }
+
+    #[test]
+    fn adjacent_changes_are_one_range() {
+        assert_eq!(ranges(&[1, 2, 4], 10, 1), vec![1..3, 4..5]);
+    }
+}
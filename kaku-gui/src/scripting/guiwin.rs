@@ -4,16 +4,29 @@ use crate::termwindow::TermWindowNotif;
 use crate::TermWindow;
 use config::keyassignment::{ClipboardCopyDestination, KeyAssignment};
 use luahelper::*;
-use mlua::{UserData, UserDataMethods, UserDataRef};
+use mlua::{FromLua, Lua, UserData, UserDataMethods, UserDataRef, Value};
 use mux::pane::PaneId;
 use mux::window::WindowId as MuxWindowId;
 use mux::Mux;
 use mux_lua::MuxPane;
-use termwiz_funcs::lines_to_escapes;
+use termwiz_funcs::{format_as_escapes, lines_to_escapes, FormatItem};
 use wezterm_dynamic::{FromDynamic, ToDynamic};
 use wezterm_toast_notification::ToastNotification;
 use window::{Connection, ConnectionOps, DeadKeyStatus, WindowOps, WindowState};
 
+/// The status may be a string, which can include the escape sequences
+/// produced by `wezterm.format`, or a table of the FormatItems that
+/// would be passed to `wezterm.format`
+fn status_text<'lua>(lua: &'lua Lua, status: Value<'lua>) -> mlua::Result<String> {
+    match status {
+        Value::Table(_) => {
+            let items = <Vec<FormatItem>>::from_lua(status, lua)?;
+            format_as_escapes(items).map_err(luaerr)
+        }
+        _ => String::from_lua(status, lua),
+    }
+}
+
 #[derive(Clone)]
 pub struct GuiWin {
     pub mux_window_id: MuxWindowId,
@@ -29,6 +42,18 @@ impl GuiWin {
             mux_window_id,
         }
     }
+
+    pub async fn active_key_table(&self) -> mlua::Result<Option<String>> {
+        let (tx, rx) = smol::channel::bounded(1);
+        self.window
+            .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                tx.try_send(term_window.current_key_table_name()).ok();
+            })));
+        rx.recv()
+            .await
+            .map_err(|e| anyhow::anyhow!("{:#}", e))
+            .map_err(luaerr)
+    }
 }
 
 impl UserData for GuiWin {
@@ -88,7 +113,8 @@ impl UserData for GuiWin {
                     title,
                     message,
                     url,
-                    timeout: timeout.map(std::time::Duration::from_millis)
+                    timeout: timeout.map(std::time::Duration::from_millis),
+                    activation_token: None,
                 });
                 Ok(())
             },
@@ -96,11 +122,13 @@ impl UserData for GuiWin {
         methods.add_method("get_appearance", |_, _, _: ()| {
             Ok(Connection::get().unwrap().get_appearance().to_string())
         });
-        methods.add_method("set_right_status", |_, this, status: String| {
+        methods.add_method("set_right_status", |lua, this, status: Value| {
+            let status = status_text(lua, status)?;
             this.window.notify(TermWindowNotif::SetRightStatus(status));
             Ok(())
         });
-        methods.add_method("set_left_status", |_, this, status: String| {
+        methods.add_method("set_left_status", |lua, this, status: Value| {
+            let status = status_text(lua, status)?;
             this.window.notify(TermWindowNotif::SetLeftStatus(status));
             Ok(())
         });
@@ -211,6 +239,21 @@ impl UserData for GuiWin {
 
             Ok(result)
         });
+        methods.add_async_method("font_report", |_, this, text: String| async move {
+            let (tx, rx) = smol::channel::bounded(1);
+            this.window
+                .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                    tx.try_send(term_window.font_report(&text).map_err(|e| format!("{e:#}")))
+                        .ok();
+                })));
+            let result = rx
+                .recv()
+                .await
+                .map_err(|e| anyhow::anyhow!("{:#}", e))
+                .map_err(luaerr)?;
+
+            result.map_err(mlua::Error::external)
+        });
         methods.add_async_method("leader_is_active", |_, this, _: ()| async move {
             let (tx, rx) = smol::channel::bounded(1);
             this.window
@@ -244,10 +287,13 @@ impl UserData for GuiWin {
             Ok(result)
         });
         methods.add_async_method("active_key_table", |_, this, _: ()| async move {
+            this.active_key_table().await
+        });
+        methods.add_async_method("key_table_stack", |lua, this, _: ()| async move {
             let (tx, rx) = smol::channel::bounded(1);
             this.window
                 .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
-                    tx.try_send(term_window.current_key_table_name()).ok();
+                    tx.try_send(term_window.key_table_stack()).ok();
                 })));
             let result = rx
                 .recv()
@@ -255,7 +301,7 @@ impl UserData for GuiWin {
                 .map_err(|e| anyhow::anyhow!("{:#}", e))
                 .map_err(luaerr)?;
 
-            Ok(result)
+            dynamic_to_lua_value(lua, result.to_dynamic())
         });
         methods.add_async_method("keyboard_modifiers", |_, this, _: ()| async move {
             let (tx, rx) = smol::channel::bounded(1);
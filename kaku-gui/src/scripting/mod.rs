@@ -33,6 +33,22 @@ pub fn register(lua: &Lua) -> anyhow::Result<()> {
         })?,
     )?;
 
+    window_mod.set(
+        "active_key_table",
+        lua.create_async_function(|_, _: ()| async move {
+            let fe =
+                try_front_end().ok_or_else(|| mlua::Error::external("not called on gui thread"))?;
+            let win = match fe
+                .focused_window()
+                .and_then(|mux_window_id| fe.gui_window_for_mux_window(mux_window_id))
+            {
+                Some(win) => win,
+                None => return Ok(None),
+            };
+            win.active_key_table().await
+        })?,
+    )?;
+
     fn key_table_to_lua(table: &KeyTable) -> Vec<Key> {
         let mut keys = vec![];
         for ((key, mods), entry) in table {
@@ -42,6 +58,7 @@ pub fn register(lua: &Lua) -> anyhow::Result<()> {
                     mods: *mods,
                 },
                 action: entry.action.clone(),
+                metadata: entry.metadata.clone(),
             });
         }
         keys
@@ -1,4 +1,5 @@
 use mux::pane::Pane;
+use wezterm_term::color::{ColorAttribute, ColorPalette, SrgbaTuple};
 use wezterm_term::StableRowIndex;
 
 pub struct ScrollHit {
@@ -67,4 +68,56 @@ impl ScrollHit {
                 as StableRowIndex,
         )
     }
+
+    /// Compute the offset from the top of the scroll bar track that
+    /// corresponds to `row`, for placing markers.
+    pub fn row_to_track_offset(
+        pane: &dyn Pane,
+        row: StableRowIndex,
+        max_thumb_height: usize,
+    ) -> usize {
+        let render_dims = pane.get_dimensions();
+        let total_rows = render_dims.scrollback_rows.max(1) as f32;
+        let row = row.saturating_sub(render_dims.scrollback_top).max(0) as f32;
+        ((row / total_rows) * max_thumb_height as f32).min(max_thumb_height as f32) as usize
+    }
+
+    /// Produce a condensed preview of the scrollback for the minimap,
+    /// sampling one line per `bucket_height` pixels of the track.
+    /// Each entry is the most prominent color of the sampled line,
+    /// or None if the line is blank.
+    pub fn minimap(
+        pane: &dyn Pane,
+        palette: &ColorPalette,
+        max_thumb_height: usize,
+        bucket_height: usize,
+    ) -> Vec<Option<SrgbaTuple>> {
+        let render_dims = pane.get_dimensions();
+        let total_rows = render_dims.scrollback_rows.max(1);
+        let num_buckets = (max_thumb_height / bucket_height.max(1)).min(total_rows);
+        if num_buckets == 0 {
+            return vec![];
+        }
+
+        (0..num_buckets)
+            .map(|idx| {
+                let row = render_dims.scrollback_top
+                    + ((idx * total_rows) / num_buckets) as StableRowIndex;
+                let (_, lines) = pane.get_lines(row..row + 1);
+                let line = lines.first()?;
+
+                let mut text_color = None;
+                for cell in line.visible_cells() {
+                    let attrs = cell.attrs();
+                    if attrs.background() != ColorAttribute::Default {
+                        return Some(palette.resolve_bg(attrs.background()));
+                    }
+                    if text_color.is_none() && !cell.str().trim().is_empty() {
+                        text_color = Some(palette.resolve_fg(attrs.foreground()));
+                    }
+                }
+                text_color
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,544 @@
+//! Saves the layout of the windows, tabs and panes so that it can be
+//! rebuilt after a restart.  The layout is written periodically to
+//! `DATA_DIR/sessions/`, and the one left by the previous run is
+//! restored by `kaku start --restore-session` or the RestoreSession
+//! key assignment, which re-spawn the shells in their original cwds.
+use anyhow::Context;
+use config::keyassignment::SpawnTabDomain;
+use lazy_static::lazy_static;
+use mux::domain::SplitSource;
+use mux::pane::PaneId;
+use mux::tab::{PaneNode, SplitDirection, SplitRequest, SplitSize};
+use mux::Mux;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Bump this when making incompatible changes to the types below
+const SESSION_VERSION: u32 = 1;
+
+lazy_static! {
+    /// The session saved by the previous run, read before this
+    /// run saves its own
+    static ref PREVIOUS_SESSION: Option<Session> = match load() {
+        Ok(session) => session,
+        Err(err) => {
+            log::error!("Failed to load the saved session: {:#}", err);
+            None
+        }
+    };
+}
+
+/// Set once the previous session has been restored, so that it
+/// can't be restored a second time
+static RESTORED: AtomicBool = AtomicBool::new(false);
+
+fn session_file() -> PathBuf {
+    config::DATA_DIR.join("sessions").join("session.json")
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Session {
+    version: u32,
+    windows: Vec<WindowLayout>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct WindowLayout {
+    workspace: String,
+    title: String,
+    active_tab: usize,
+    tabs: Vec<TabLayout>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TabLayout {
+    title: String,
+    root: PaneLayout,
+    /// Whether the active pane is zoomed to fill the tab
+    #[serde(default)]
+    zoomed: bool,
+    /// The font scale of the tab, when `per_tab_font_size` is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    font_scale: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PaneLayout {
+    Pane {
+        domain: String,
+        cwd: Option<String>,
+        title: String,
+        active: bool,
+    },
+    Split {
+        direction: SplitDirection,
+        /// The size of each side, in cells along the direction of the split
+        first_size: usize,
+        second_size: usize,
+        first: Box<PaneLayout>,
+        second: Box<PaneLayout>,
+    },
+}
+
+impl PaneLayout {
+    fn from_node(mux: &Mux, node: PaneNode) -> Option<Self> {
+        match node {
+            PaneNode::Empty => None,
+            PaneNode::Leaf(entry) => {
+                let domain = mux
+                    .get_pane(entry.pane_id)
+                    .and_then(|pane| mux.get_domain(pane.domain_id()))
+                    .map(|domain| domain.domain_name().to_string())?;
+                Some(Self::Pane {
+                    domain,
+                    cwd: entry
+                        .working_dir
+                        .as_ref()
+                        .and_then(|url| mux::cwd_url_to_path(&url.url)),
+                    title: entry.title,
+                    active: entry.is_active_pane,
+                })
+            }
+            PaneNode::Split { left, right, node } => {
+                let first = Self::from_node(mux, *left);
+                let second = Self::from_node(mux, *right);
+                match (first, second) {
+                    (Some(first), Some(second)) => {
+                        let (first_size, second_size) = match node.direction {
+                            SplitDirection::Horizontal => (node.first.cols, node.second.cols),
+                            SplitDirection::Vertical => (node.first.rows, node.second.rows),
+                        };
+                        Some(Self::Split {
+                            direction: node.direction,
+                            first_size,
+                            second_size,
+                            first: Box::new(first),
+                            second: Box::new(second),
+                        })
+                    }
+                    (first, second) => first.or(second),
+                }
+            }
+        }
+    }
+
+    /// Appends the domain and cwd of each pane, in order
+    fn collect_panes<'a>(&'a self, panes: &mut Vec<(&'a str, Option<&'a str>)>) {
+        match self {
+            Self::Pane { domain, cwd, .. } => panes.push((domain, cwd.as_deref())),
+            Self::Split { first, second, .. } => {
+                first.collect_panes(panes);
+                second.collect_panes(panes);
+            }
+        }
+    }
+
+    /// The pane that occupies the top left of this part of the layout,
+    /// which is the one that exists before any of its splits are made
+    fn first_pane(&self) -> &Self {
+        match self {
+            Self::Pane { .. } => self,
+            Self::Split { first, .. } => first.first_pane(),
+        }
+    }
+
+    fn spawn_domain(&self) -> SpawnTabDomain {
+        match self.first_pane() {
+            Self::Pane { domain, .. } if Mux::get().get_domain_by_name(domain).is_some() => {
+                SpawnTabDomain::DomainName(domain.clone())
+            }
+            _ => SpawnTabDomain::DefaultDomain,
+        }
+    }
+
+    fn cwd(&self) -> Option<String> {
+        match self.first_pane() {
+            Self::Pane { cwd, .. } => cwd.clone(),
+            Self::Split { .. } => None,
+        }
+    }
+}
+
+impl WindowLayout {
+    /// The domain and cwd of each pane of each tab
+    fn panes(&self) -> Vec<(&str, Option<&str>)> {
+        let mut panes = vec![];
+        for tab in &self.tabs {
+            tab.root.collect_panes(&mut panes);
+        }
+        panes
+    }
+}
+
+/// Returns the windows of `saved` that aren't already open, judging
+/// by the workspace, domains and cwds of the windows in `open`
+fn windows_to_restore<'a>(saved: &'a Session, open: &Session) -> Vec<&'a WindowLayout> {
+    let mut open: Vec<(&str, Vec<(&str, Option<&str>)>)> = open
+        .windows
+        .iter()
+        .map(|window| (window.workspace.as_str(), window.panes()))
+        .collect();
+    saved
+        .windows
+        .iter()
+        .filter(|window| {
+            let key = (window.workspace.as_str(), window.panes());
+            match open.iter().position(|open| *open == key) {
+                Some(idx) => {
+                    // Each open window accounts for one saved window
+                    open.remove(idx);
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+/// Describes the current layout of the mux
+pub fn capture() -> Session {
+    let mux = Mux::get();
+    let mut windows = vec![];
+    for window_id in mux.iter_windows() {
+        let window = match mux.get_window(window_id) {
+            Some(window) => window,
+            None => continue,
+        };
+        let tabs: Vec<TabLayout> = window
+            .iter()
+            .filter_map(|tab| {
+                Some(TabLayout {
+                    title: tab.get_title(),
+                    root: PaneLayout::from_node(&mux, tab.codec_pane_tree())?,
+                    zoomed: tab.get_zoomed_pane().is_some(),
+                    font_scale: crate::termwindow::resize::tab_font_scale(tab.tab_id()),
+                })
+            })
+            .collect();
+        if tabs.is_empty() {
+            continue;
+        }
+        windows.push(WindowLayout {
+            workspace: window.get_workspace().to_string(),
+            title: window.get_title().to_string(),
+            active_tab: window.get_active_idx().min(tabs.len() - 1),
+            tabs,
+        });
+    }
+    Session {
+        version: SESSION_VERSION,
+        windows,
+    }
+}
+
+fn save(session: &Session) -> anyhow::Result<()> {
+    let path = session_file();
+    let dir = path.parent().expect("session file to have a parent");
+    config::create_user_owned_dirs(dir)?;
+    let data = serde_json::to_string_pretty(session)?;
+    // Replace the file in one step so that a crash while writing
+    // doesn't lose the previous session
+    let mut temp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("create temporary file in {}", dir.display()))?;
+    std::io::Write::write_all(&mut temp, data.as_bytes())?;
+    temp.persist(&path)
+        .with_context(|| format!("replace {}", path.display()))?;
+    Ok(())
+}
+
+fn load() -> anyhow::Result<Option<Session>> {
+    let path = session_file();
+    let data = match std::fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+    };
+    let session: Session =
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))?;
+    if session.version != SESSION_VERSION {
+        log::warn!(
+            "Ignoring {} as it was saved by an incompatible version",
+            path.display()
+        );
+        return Ok(None);
+    }
+    Ok(Some(session))
+}
+
+/// Saves the layout every `session_save_interval_seconds`.
+/// Layouts without any windows are not saved, so that the session
+/// survives the windows being closed at shutdown.
+pub fn start_autosave() {
+    lazy_static::initialize(&PREVIOUS_SESSION);
+    promise::spawn::spawn(async move {
+        loop {
+            let interval = config::configuration().session_save_interval_seconds;
+            if interval == 0 {
+                // Check again later in case the config changes
+                smol::Timer::after(Duration::from_secs(60)).await;
+                continue;
+            }
+            smol::Timer::after(Duration::from_secs(interval)).await;
+
+            let session = capture();
+            if session.windows.is_empty() {
+                continue;
+            }
+            if let Err(err) = save(&session) {
+                log::error!("Failed to save the session: {:#}", err);
+            }
+        }
+    })
+    .detach();
+}
+
+/// Rebuilds the layout saved by the previous run, if any, skipping
+/// the windows that are already open.  The layout is only restored
+/// once per run.  Returns false if there was nothing to restore.
+pub async fn restore_previous() -> anyhow::Result<bool> {
+    let session = match PREVIOUS_SESSION.as_ref() {
+        Some(session) => session,
+        None => return Ok(false),
+    };
+    if RESTORED.swap(true, Ordering::SeqCst) {
+        log::warn!("The saved session has already been restored");
+        return Ok(true);
+    }
+    let windows = windows_to_restore(session, &capture());
+    restore(&windows).await?;
+    Ok(true)
+}
+
+/// Restores the layout saved by the previous run in the background,
+/// logging the outcome
+pub fn spawn_restore_previous() {
+    promise::spawn::spawn(async move {
+        match restore_previous().await {
+            Ok(true) => {}
+            Ok(false) => log::warn!("There is no saved session to restore"),
+            Err(err) => log::error!("Failed to restore the session: {:#}", err),
+        }
+    })
+    .detach();
+}
+
+async fn restore(windows: &[&WindowLayout]) -> anyhow::Result<()> {
+    let mux = Mux::get();
+    let config = config::configuration();
+    let dpi = config.dpi.unwrap_or_else(|| ::window::default_dpi());
+    let size = config.initial_size(dpi as u32, None);
+
+    for layout in windows {
+        let mut window_id = None;
+        let mut active_panes = vec![];
+        for tab_layout in &layout.tabs {
+            let (tab, pane, id) = mux
+                .spawn_tab_or_window(
+                    window_id,
+                    tab_layout.root.spawn_domain(),
+                    None,
+                    tab_layout.root.cwd(),
+                    size,
+                    None,
+                    layout.workspace.clone(),
+                    None,
+                )
+                .await
+                .context("spawning tab")?;
+            window_id = Some(id);
+            if !tab_layout.title.is_empty() {
+                tab.set_title(&tab_layout.title);
+            }
+            if let Some(font_scale) = tab_layout.font_scale {
+                crate::termwindow::resize::set_tab_font_scale(tab.tab_id(), font_scale);
+            }
+            let active = restore_splits(&mux, &tab_layout.root, pane.pane_id()).await?;
+            active_panes.push((tab, active, tab_layout.zoomed));
+        }
+
+        for (tab, pane_id, zoomed) in active_panes {
+            if let Some(pane) = pane_id.and_then(|pane_id| mux.get_pane(pane_id)) {
+                tab.set_active_pane(&pane);
+            }
+            if zoomed {
+                tab.set_zoomed(true);
+            }
+        }
+        if let Some(window_id) = window_id {
+            if let Some(mut window) = mux.get_window_mut(window_id) {
+                if !layout.title.is_empty() {
+                    window.set_title(&layout.title);
+                }
+                if layout.active_tab < window.len() {
+                    window.set_active_without_saving(layout.active_tab);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Splits `pane_id`, which was spawned for the first pane of `root`,
+/// until it matches `root`.  Returns the pane that should be active.
+async fn restore_splits(
+    mux: &Arc<Mux>,
+    root: &PaneLayout,
+    pane_id: PaneId,
+) -> anyhow::Result<Option<PaneId>> {
+    let mut active = None;
+    let mut pending = vec![(root, pane_id)];
+    while let Some((layout, pane_id)) = pending.pop() {
+        match layout {
+            PaneLayout::Pane {
+                active: is_active, ..
+            } => {
+                if *is_active {
+                    active = Some(pane_id);
+                }
+            }
+            PaneLayout::Split {
+                direction,
+                first_size,
+                second_size,
+                first,
+                second,
+            } => {
+                let total = (first_size + second_size).max(1);
+                let percent = (second_size * 100 / total).clamp(1, 99) as u8;
+                let (new_pane, _size) = mux
+                    .split_pane(
+                        pane_id,
+                        SplitRequest {
+                            direction: *direction,
+                            target_is_second: true,
+                            top_level: false,
+                            size: SplitSize::Percent(percent),
+                        },
+                        SplitSource::Spawn {
+                            command: None,
+                            command_dir: second.cwd(),
+                        },
+                        second.spawn_domain(),
+                    )
+                    .await
+                    .context("splitting pane")?;
+                pending.push((first, pane_id));
+                pending.push((second, new_pane.pane_id()));
+            }
+        }
+    }
+    Ok(active)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn first_pane() {
+        let pane = |cwd: &str| PaneLayout::Pane {
+            domain: "local".to_string(),
+            cwd: Some(cwd.to_string()),
+            title: String::new(),
+            active: false,
+        };
+        let layout = PaneLayout::Split {
+            direction: SplitDirection::Horizontal,
+            first_size: 40,
+            second_size: 40,
+            first: Box::new(PaneLayout::Split {
+                direction: SplitDirection::Vertical,
+                first_size: 12,
+                second_size: 12,
+                first: Box::new(pane("/a")),
+                second: Box::new(pane("/b")),
+            }),
+            second: Box::new(pane("/c")),
+        };
+        assert_eq!(layout.cwd().as_deref(), Some("/a"));
+
+        let session = Session {
+            version: SESSION_VERSION,
+            windows: vec![WindowLayout {
+                workspace: "default".to_string(),
+                title: String::new(),
+                active_tab: 0,
+                tabs: vec![TabLayout {
+                    title: String::new(),
+                    root: layout,
+                    zoomed: true,
+                    font_scale: Some(1.21),
+                }],
+            }],
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(serde_json::from_str::<Session>(&json).unwrap(), session);
+    }
+
+    #[test]
+    fn tab_overrides_are_optional() {
+        // As saved before zoom and font scale were remembered
+        let tab: TabLayout = serde_json::from_str(
+            r#"{"title":"","root":{"type":"pane","domain":"local","cwd":null,"title":"","active":true}}"#,
+        )
+        .unwrap();
+        assert!(!tab.zoomed);
+        assert_eq!(tab.font_scale, None);
+    }
+
+    fn window(workspace: &str, cwds: &[&str]) -> WindowLayout {
+        WindowLayout {
+            workspace: workspace.to_string(),
+            title: String::new(),
+            active_tab: 0,
+            tabs: cwds
+                .iter()
+                .map(|cwd| TabLayout {
+                    title: "ignored".to_string(),
+                    root: PaneLayout::Pane {
+                        domain: "local".to_string(),
+                        cwd: Some(cwd.to_string()),
+                        title: String::new(),
+                        active: true,
+                    },
+                    zoomed: false,
+                    font_scale: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn session(windows: Vec<WindowLayout>) -> Session {
+        Session {
+            version: SESSION_VERSION,
+            windows,
+        }
+    }
+
+    #[test]
+    fn open_windows_are_not_restored() {
+        let saved = session(vec![
+            window("default", &["/a", "/b"]),
+            window("default", &["/a", "/b"]),
+            window("work", &["/a", "/b"]),
+            window("default", &["/c"]),
+        ]);
+
+        let restore = windows_to_restore(&saved, &session(vec![]));
+        assert_eq!(restore.len(), 4);
+
+        // One of the two identical windows is open, as is the last one
+        let mut open = window("default", &["/c"]);
+        open.title = "renamed".to_string();
+        let open = session(vec![window("default", &["/a", "/b"]), open]);
+        let restore: Vec<&WindowLayout> = windows_to_restore(&saved, &open);
+        assert_eq!(restore, vec![&saved.windows[1], &saved.windows[2]]);
+
+        // Restoring everything leaves nothing more to restore
+        assert!(windows_to_restore(&saved, &saved).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::customglyph::BlockKey;
 use crate::glyphcache::CachedGlyph;
-use config::TextStyle;
+use config::{Config, TextStyle};
 use std::rc::Rc;
 use wezterm_font::shaper::GlyphInfo;
 use wezterm_font::units::*;
@@ -30,7 +30,11 @@ pub struct ShapedInfo {
 impl ShapedInfo {
     /// Process the results from the shaper, stitching together glyph
     /// and positioning information
-    pub fn process(infos: &[GlyphInfo], glyphs: &[Rc<CachedGlyph>]) -> Vec<ShapedInfo> {
+    pub fn process(
+        infos: &[GlyphInfo],
+        glyphs: &[Rc<CachedGlyph>],
+        config: &Config,
+    ) -> Vec<ShapedInfo> {
         let mut pos: Vec<ShapedInfo> = Vec::with_capacity(infos.len());
 
         for (info, glyph) in infos.iter().zip(glyphs.iter()) {
@@ -46,7 +50,10 @@ impl ShapedInfo {
                     bearing_x: glyph.bearing_x.get() as f32,
                 },
                 glyph: Rc::clone(glyph),
-                block_key: info.only_char.and_then(BlockKey::from_char),
+                block_key: info
+                    .only_char
+                    .and_then(|c| BlockKey::for_char(c, config))
+                    .or_else(|| BlockKey::for_missing_glyph(info, config.missing_glyph_style)),
             });
         }
         pos
@@ -180,7 +187,7 @@ mod test {
 
         eprintln!("infos: {:#?}", all_infos);
         eprintln!("glyphs: {:#?}", all_glyphs);
-        ShapedInfo::process(&all_infos, &all_glyphs)
+        ShapedInfo::process(&all_infos, &all_glyphs, &config::configuration())
             .into_iter()
             .map(|p| p.pos)
             .collect()
@@ -0,0 +1,25 @@
+//! Plays the sounds configured via `event_sounds`
+use ::window::*;
+use config::{ConfigHandle, SoundEvent};
+
+/// Plays the sound configured for `event`.  Returns false if no
+/// sound is configured or it could not be played, so that the caller
+/// can fall back to some other way of signalling the event.
+/// Must be called on the main thread.
+pub fn play_event_sound(config: &ConfigHandle, event: SoundEvent) -> bool {
+    let sound = match config.event_sounds.sound_for_event(event) {
+        Some(sound) => sound,
+        None => return false,
+    };
+    let conn = match Connection::get() {
+        Some(conn) => conn,
+        None => return false,
+    };
+    match conn.play_sound(&sound.path, sound.volume) {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("Unable to play {event:?} sound: {err:#}");
+            false
+        }
+    }
+}
@@ -123,10 +123,15 @@ pub async fn spawn_command_internal(
             }
         }
         _ => {
+            // A native tab is a window of its own, which joins the
+            // tab group of the source window once it is created
+            let native_tab = spawn_where == SpawnWhere::NewTab
+                && config::configuration().uses_native_macos_tabs();
             let (_tab, pane, window_id) = mux
                 .spawn_tab_or_window(
                     match spawn_where {
                         SpawnWhere::NewWindow => None,
+                        _ if native_tab => None,
                         _ => src_window_id,
                     },
                     spawn.domain,
@@ -140,6 +145,10 @@ pub async fn spawn_command_internal(
                 .await
                 .context("spawn_tab_or_window")?;
 
+            if let (true, Some(src_window_id)) = (native_tab, src_window_id) {
+                crate::frontend::front_end().add_as_native_tab(window_id, src_window_id);
+            }
+
             // If it was created in this window, it copies our handlers.
             // Otherwise, we'll pick them up when we later respond to
             // the new window being created.
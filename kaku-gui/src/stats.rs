@@ -2,6 +2,7 @@ use config::configuration;
 use config::lua::get_or_create_sub_module;
 use config::lua::mlua::Lua;
 use hdrhistogram::Histogram;
+use luahelper::dynamic_to_lua_value;
 use metrics::{Counter, Gauge, Key, KeyName, Metadata, Recorder, SharedString, Unit};
 use parking_lot::Mutex;
 use std::collections::HashMap;
@@ -9,6 +10,7 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tabout::{tabulate_output, Alignment, Column};
+use wezterm_dynamic::ToDynamic;
 
 static ENABLE_STAT_PRINT: AtomicBool = AtomicBool::new(true);
 lazy_static::lazy_static! {
@@ -19,6 +21,10 @@ struct ThroughputInner {
     hist: Histogram<u64>,
     last: Option<Instant>,
     count: u64,
+    /// The per-second rate over the most recently completed window
+    previous: u64,
+    /// Sum of all recorded values
+    total: u64,
 }
 
 struct Throughput {
@@ -32,6 +38,8 @@ impl Throughput {
                 hist: Histogram::new(2).expect("failed to create histogram"),
                 last: None,
                 count: 0,
+                previous: 0,
+                total: 0,
             }),
         }
     }
@@ -39,6 +47,14 @@ impl Throughput {
         self.inner.lock().current()
     }
 
+    fn per_second(&self) -> u64 {
+        self.inner.lock().per_second()
+    }
+
+    fn total(&self) -> u64 {
+        self.inner.lock().total
+    }
+
     fn percentiles(&self) -> (u64, u64, u64) {
         let inner = self.inner.lock();
         let p50 = inner.hist.value_at_percentile(50.);
@@ -53,28 +69,38 @@ impl ThroughputInner {
         if let Some(ref last) = self.last {
             let elapsed = last.elapsed();
             if elapsed > Duration::from_secs(1) {
-                self.hist.record(self.count).ok();
-                self.count = 0;
-                self.last = Some(Instant::now());
+                self.end_window(elapsed);
             }
         } else {
             // Start a new window
             self.last = Some(Instant::now());
         };
         self.count += value;
+        self.total += value;
     }
 
     fn current(&mut self) -> u64 {
         if let Some(ref last) = self.last {
             let elapsed = last.elapsed();
             if elapsed > Duration::from_secs(1) {
-                self.hist.record(self.count).ok();
-                self.count = 0;
-                self.last = Some(Instant::now());
+                self.end_window(elapsed);
             }
         }
         self.count
     }
+
+    fn end_window(&mut self, elapsed: Duration) {
+        self.hist.record(self.count).ok();
+        self.previous = (self.count as f64 / elapsed.as_secs_f64()) as u64;
+        self.count = 0;
+        self.last = Some(Instant::now());
+    }
+
+    /// Returns the per-second rate over the last completed window
+    fn per_second(&mut self) -> u64 {
+        self.current();
+        self.previous
+    }
 }
 
 impl metrics::HistogramFn for Throughput {
@@ -268,6 +294,83 @@ impl Inner {
     }
 }
 
+impl Inner {
+    fn throughput(&self, name: &'static str) -> Option<&Arc<Throughput>> {
+        self.throughput.get(&Key::from_static_name(name))
+    }
+
+    fn per_second(&self, name: &'static str) -> u64 {
+        self.throughput(name).map(|t| t.per_second()).unwrap_or(0)
+    }
+
+    /// Returns the fraction of lookups that were hits over the
+    /// lifetime of the process, or None if there were no lookups
+    fn hit_ratio(&self, hit: &'static str, miss: &'static str) -> Option<f64> {
+        let hits = self.throughput(hit).map(|t| t.total()).unwrap_or(0);
+        let misses = self.throughput(miss).map(|t| t.total()).unwrap_or(0);
+        let lookups = hits + misses;
+        if lookups == 0 {
+            None
+        } else {
+            Some(hits as f64 / lookups as f64)
+        }
+    }
+
+    /// Returns the p50 and p95 of a latency histogram, in milliseconds
+    fn latency_ms(&self, name: &'static str) -> (f64, f64) {
+        match self.histograms.get(&Key::from_static_name(name)) {
+            Some(hist) => {
+                let (p50, _, p95) = hist.latency_percentiles();
+                (p50.as_secs_f64() * 1000., p95.as_secs_f64() * 1000.)
+            }
+            None => (0., 0.),
+        }
+    }
+}
+
+/// A summary of the performance of the rendering pipeline, returned
+/// by `wezterm.gui.get_stats()` and shown in the render HUD.
+/// Rates are for the most recent second and are shared by all windows.
+#[derive(Debug, Clone, Default, ToDynamic)]
+pub struct RenderStats {
+    /// Frames drawn per second
+    pub fps: u64,
+    /// Frames per second that weren't drawn because nothing changed
+    pub skipped_fps: u64,
+    pub frame_time_p50_ms: f64,
+    pub frame_time_p95_ms: f64,
+    /// Fraction of glyph lookups satisfied by the glyph cache
+    pub glyph_cache_hit_ratio: Option<f64>,
+    /// Fraction of text runs satisfied by the shape cache
+    pub shape_cache_hit_ratio: Option<f64>,
+    pub shaping_time_p50_ms: f64,
+    pub shaping_time_p95_ms: f64,
+    /// Bytes read from the ptys of all panes per second
+    pub pty_read_bytes_per_sec: u64,
+}
+
+pub fn render_stats() -> RenderStats {
+    let inner = INNER.lock();
+    let (frame_time_p50_ms, frame_time_p95_ms) = inner.latency_ms("gui.paint.impl");
+    let (shaping_time_p50_ms, shaping_time_p95_ms) = inner.latency_ms("shape.harfbuzz");
+    let painted = inner.per_second("gui.paint.impl.rate");
+    let skipped_fps = inner.per_second("gui.paint.skipped.rate");
+    RenderStats {
+        fps: painted.saturating_sub(skipped_fps),
+        skipped_fps,
+        frame_time_p50_ms,
+        frame_time_p95_ms,
+        glyph_cache_hit_ratio: inner.hit_ratio(
+            "glyph_cache.glyph_cache.hit.rate",
+            "glyph_cache.glyph_cache.miss.rate",
+        ),
+        shape_cache_hit_ratio: inner.hit_ratio("shape_cache.hit.rate", "shape_cache.miss.rate"),
+        shaping_time_p50_ms,
+        shaping_time_p95_ms,
+        pty_read_bytes_per_sec: inner.per_second("read_from_pane_pty.bytes.rate"),
+    }
+}
+
 fn make_inner() -> Arc<Mutex<Inner>> {
     Arc::new(Mutex::new(Inner {
         histograms: HashMap::new(),
@@ -355,6 +458,12 @@ impl Recorder for Stats {
 }
 
 pub fn register(lua: &Lua) -> anyhow::Result<()> {
+    let gui_mod = get_or_create_sub_module(lua, "gui")?;
+    gui_mod.set(
+        "get_stats",
+        lua.create_function(|lua, _: ()| dynamic_to_lua_value(lua, render_stats().to_dynamic()))?,
+    )?;
+
     let metrics_mod = get_or_create_sub_module(lua, "metrics")?;
     metrics_mod.set(
         "get_counters",
@@ -1,4 +1,6 @@
 use crate::termwindow::{PaneInformation, TabInformation, UIItem, UIItemType};
+use config::keyassignment::KeyAssignment;
+use config::window::TabBarButton;
 use config::{ConfigHandle, TabBarColors};
 use finl_unicode::grapheme_clusters::Graphemes;
 use mlua::FromLua;
@@ -28,7 +30,9 @@ pub enum TabBarItem {
     RightStatus,
     Tab { tab_idx: usize, active: bool },
     NewTabButton,
+    CustomButton(usize),
     WindowButton(IntegratedTitleButton),
+    TabRegion { tab_idx: usize, region: usize },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -37,12 +41,64 @@ pub struct TabEntry {
     pub title: Line,
     x: usize,
     width: usize,
+    /// The clickable regions of the title of a tab
+    pub regions: Vec<TitleRegion>,
+}
+
+/// Text returned by `format-tab-title` as `{ Text = "...", Action = ... }`,
+/// which performs `action` when clicked
+#[derive(Clone, Debug, PartialEq)]
+pub struct TitleRegion {
+    /// The column of the title at which the region starts
+    pub x: usize,
+    pub width: usize,
+    pub action: KeyAssignment,
 }
 
 #[derive(Clone, Debug)]
 struct TitleText {
     items: Vec<FormatItem>,
     len: usize,
+    regions: Vec<TitleRegion>,
+}
+
+fn title_len(items: &[FormatItem]) -> anyhow::Result<usize> {
+    let esc = format_as_escapes(items.to_vec())?;
+    Ok(parse_status_text(&esc, CellAttributes::default()).len())
+}
+
+/// Converts the table returned by `format-tab-title`.  In addition to
+/// FormatItems, it may contain `{ Text = "...", Action = ... }` entries
+/// that define clickable regions.
+fn title_from_lua<'lua>(
+    table: mlua::Table<'lua>,
+    lua: &'lua mlua::Lua,
+) -> anyhow::Result<TitleText> {
+    let mut items = vec![];
+    let mut regions = vec![];
+    for value in table.sequence_values::<mlua::Value>() {
+        let value = value?;
+        if let mlua::Value::Table(entry) = &value {
+            match entry.get::<_, mlua::Value>("Action")? {
+                mlua::Value::Nil => {}
+                action => {
+                    let action = KeyAssignment::from_lua(action, lua)?;
+                    let text: String = entry.get("Text")?;
+                    let x = title_len(&items)?;
+                    items.push(FormatItem::Text(text));
+                    let width = title_len(&items)? - x;
+                    regions.push(TitleRegion { x, width, action });
+                    continue;
+                }
+            }
+        }
+        items.push(FormatItem::from_lua(value, lua)?);
+    }
+    Ok(TitleText {
+        len: title_len(&items)?,
+        items,
+        regions,
+    })
 }
 
 fn call_format_tab_title(
@@ -74,23 +130,14 @@ fn call_format_tab_title(
             )?;
             match &v {
                 mlua::Value::Nil => Ok(None),
-                mlua::Value::Table(_) => {
-                    let items = <Vec<FormatItem>>::from_lua(v, &*lua)?;
-
-                    let esc = format_as_escapes(items.clone())?;
-                    let line = parse_status_text(&esc, CellAttributes::default());
-
-                    Ok(Some(TitleText {
-                        items,
-                        len: line.len(),
-                    }))
-                }
+                mlua::Value::Table(t) => Ok(Some(title_from_lua(t.clone(), &*lua)?)),
                 _ => {
                     let s = String::from_lua(v, &*lua)?;
                     let line = parse_status_text(&s, CellAttributes::default());
                     Ok(Some(TitleText {
                         len: line.len(),
                         items: vec![FormatItem::Text(s)],
+                        regions: vec![],
                     }))
                 }
             }
@@ -165,6 +212,7 @@ fn compute_tab_title(
                 TitleText {
                     len: unicode_column_width(" no pane ", None),
                     items: vec![FormatItem::Text(" no pane ".to_string())],
+                    regions: vec![],
                 }
             }
         }
@@ -217,6 +265,52 @@ fn build_default_title(
                 // TODO: Decide what to do here to indicate this
             }
         }
+        if pane.is_input_locked {
+            // md_lock
+            let graphic = "\u{f033e} ".to_string();
+            len += unicode_column_width(&graphic, None);
+            items.push(FormatItem::Text(graphic));
+        }
+        if pane.is_broadcasting_input {
+            // md_broadcast
+            let graphic = "\u{f1720} ".to_string();
+            len += unicode_column_width(&graphic, None);
+            items.push(FormatItem::Foreground(FormatColor::AnsiColor(
+                AnsiColor::Yellow,
+            )));
+            items.push(FormatItem::Text(graphic));
+            items.push(FormatItem::Foreground(FormatColor::Default));
+        }
+        if pane.is_zoomed && config.show_zoom_indicator {
+            // md_magnify_expand
+            let graphic = "\u{f1874} ".to_string();
+            len += unicode_column_width(&graphic, None);
+            items.push(FormatItem::Foreground(FormatColor::AnsiColor(
+                AnsiColor::Yellow,
+            )));
+            items.push(FormatItem::Text(graphic));
+            items.push(FormatItem::Foreground(FormatColor::Default));
+        }
+        if tab.has_triggered_monitor {
+            // md_bell_ring
+            let graphic = "\u{f009e} ".to_string();
+            len += unicode_column_width(&graphic, None);
+            items.push(FormatItem::Foreground(FormatColor::AnsiColor(
+                AnsiColor::Aqua,
+            )));
+            items.push(FormatItem::Text(graphic));
+            items.push(FormatItem::Foreground(FormatColor::Default));
+        }
+        if pane.is_recording {
+            // md_record_rec
+            let graphic = "\u{f044b} ".to_string();
+            len += unicode_column_width(&graphic, None);
+            items.push(FormatItem::Foreground(FormatColor::AnsiColor(
+                AnsiColor::Red,
+            )));
+            items.push(FormatItem::Text(graphic));
+            items.push(FormatItem::Foreground(FormatColor::Default));
+        }
     }
 
     if with_edge_padding {
@@ -230,7 +324,11 @@ fn build_default_title(
     len += unicode_column_width(&title, None);
     items.push(FormatItem::Text(title));
 
-    TitleText { len, items }
+    TitleText {
+        len,
+        items,
+        regions: vec![],
+    }
 }
 
 /// Detect the SSH destination for a pane, used to show the remote host in tab titles.
@@ -394,6 +492,7 @@ impl TabBarState {
                 title: Line::from_text(" ", &CellAttributes::blank(), 1, None),
                 x: 1,
                 width: 1,
+                regions: vec![],
             }],
         }
     }
@@ -406,6 +505,15 @@ impl TabBarState {
         &self.items
     }
 
+    /// Returns the action of a `TabBarItem::TabRegion`
+    pub fn region_action(&self, tab_idx: usize, region: usize) -> Option<&KeyAssignment> {
+        self.items
+            .iter()
+            .find(|entry| matches!(entry.item, TabBarItem::Tab { tab_idx: idx, .. } if idx == tab_idx))
+            .and_then(|entry| entry.regions.get(region))
+            .map(|region| &region.action)
+    }
+
     fn integrated_title_buttons(
         mouse_x: Option<usize>,
         x: &mut usize,
@@ -487,6 +595,7 @@ impl TabBarState {
                 title: title.to_owned(),
                 x: *x,
                 width,
+                regions: vec![],
             });
 
             *x += width;
@@ -532,6 +641,20 @@ impl TabBarState {
             },
         );
 
+        let custom_buttons = if config.use_fancy_tab_bar {
+            custom_button_labels(
+                &config.tab_bar_buttons,
+                &CellAttributes::default(),
+                &CellAttributes::default(),
+            )
+        } else {
+            custom_button_labels(
+                &config.tab_bar_buttons,
+                &new_tab_attrs,
+                &new_tab_hover_attrs,
+            )
+        };
+
         let use_integrated_title_buttons = config
             .window_decorations
             .contains(window::WindowDecorations::INTEGRATED_BUTTONS);
@@ -569,11 +692,12 @@ impl TabBarState {
 
         // Tab titles are rendered contiguously; only reserve width for controls
         // that are actually shown.
-        let controls_width = if config.show_new_tab_button_in_tab_bar {
+        let mut controls_width = if config.show_new_tab_button_in_tab_bar {
             new_tab.len()
         } else {
             0
         };
+        controls_width += custom_buttons.iter().map(button_width).sum::<usize>();
         let available_cells = title_width.saturating_sub(controls_width);
         let tab_width_max = if config.use_fancy_tab_bar || available_cells >= titles_len {
             // We can render each title with its full width
@@ -620,6 +744,7 @@ impl TabBarState {
                 title: left_status_line.clone(),
                 x,
                 width: left_status_line.len(),
+                regions: vec![],
             });
             x += left_status_line.len();
             line.append_line(left_status_line, SEQ_ZERO);
@@ -673,6 +798,7 @@ impl TabBarState {
                 title,
                 x: tab_start_idx,
                 width,
+                regions: tab_title.regions,
             });
 
             line.append_line(tab_line, SEQ_ZERO);
@@ -695,11 +821,14 @@ impl TabBarState {
                 title: new_tab_button.clone(),
                 x: button_start,
                 width,
+                regions: vec![],
             });
 
             x += width;
         }
 
+        append_custom_buttons(&custom_buttons, mouse_x, &mut x, &mut items, &mut line);
+
         // Reserve place for integrated title buttons
         let title_width = if use_integrated_title_buttons
             && config.integrated_title_button_style != IntegratedTitleButtonStyle::MacOsNative
@@ -757,6 +886,7 @@ impl TabBarState {
             title: right_status_line.clone(),
             x,
             width: status_space_available,
+            regions: vec![],
         });
 
         while right_status_line.len() > status_space_available {
@@ -790,6 +920,27 @@ impl TabBarState {
                 height: cell_height,
                 item_type: UIItemType::TabBar(entry.item),
             });
+
+            // Pushed after the tab so that they take precedence over it;
+            // regions that were truncated away are not clickable
+            if let TabBarItem::Tab { tab_idx, .. } = entry.item {
+                for (region_idx, region) in entry.regions.iter().enumerate() {
+                    let width = region.width.min(entry.width.saturating_sub(region.x));
+                    if width == 0 {
+                        continue;
+                    }
+                    items.push(UIItem {
+                        x: (entry.x + region.x) * cell_width,
+                        width: width * cell_width,
+                        y,
+                        height: cell_height,
+                        item_type: UIItemType::TabBar(TabBarItem::TabRegion {
+                            tab_idx,
+                            region: region_idx,
+                        }),
+                    });
+                }
+            }
         }
 
         items
@@ -901,6 +1052,60 @@ pub fn parse_status_text(text: &str, default_cell: CellAttributes) -> Line {
     Line::from_cells(cells, SEQ_ZERO)
 }
 
+/// Renders the label of each of the `tab_bar_buttons`, along with
+/// the label to show while the mouse is over it
+fn custom_button_labels(
+    buttons: &[TabBarButton],
+    attrs: &CellAttributes,
+    hover_attrs: &CellAttributes,
+) -> Vec<(Line, Line)> {
+    buttons
+        .iter()
+        .map(|button| {
+            let label = parse_status_text(&button.label, attrs.clone());
+            let hover_label = parse_status_text(
+                button.hover_label.as_deref().unwrap_or(&button.label),
+                hover_attrs.clone(),
+            );
+            (label, hover_label)
+        })
+        .collect()
+}
+
+/// The number of cells to reserve for a custom button, which is
+/// wider while hovered if its hover label is longer
+fn button_width((label, hover_label): &(Line, Line)) -> usize {
+    unicode_column_width(&label.as_str(), None)
+        .max(unicode_column_width(&hover_label.as_str(), None))
+}
+
+/// Appends the rendered `buttons` to `line`, starting at `x`
+fn append_custom_buttons(
+    buttons: &[(Line, Line)],
+    mouse_x: Option<usize>,
+    x: &mut usize,
+    items: &mut Vec<TabEntry>,
+    line: &mut Line,
+) {
+    for (idx, (label, hover_label)) in buttons.iter().enumerate() {
+        let hover = is_tab_hover(mouse_x, *x, hover_label.len());
+        let button = if hover { hover_label } else { label };
+        let width = button.len();
+
+        line.append_line(button.clone(), SEQ_ZERO);
+
+        items.push(TabEntry {
+            item: TabBarItem::CustomButton(idx),
+            title: button.clone(),
+            x: *x,
+            width,
+            regions: vec![],
+        });
+
+        *x += width;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -925,4 +1130,67 @@ mod test {
     fn ignore_non_ssh_command() {
         assert!(ssh_target_from_command("ls -la").is_none());
     }
+
+    #[test]
+    fn title_regions() {
+        let lua = mlua::Lua::new();
+        let table: mlua::Table = lua
+            .load(r#"{ { Text = "ab" }, { Text = "x", Action = { ActivateTab = 0 } } }"#)
+            .eval()
+            .unwrap();
+        let title = title_from_lua(table, &lua).unwrap();
+        assert_eq!(title.len, 3);
+        assert_eq!(
+            title.regions,
+            vec![TitleRegion {
+                x: 2,
+                width: 1,
+                action: KeyAssignment::ActivateTab(0),
+            }]
+        );
+    }
+
+    #[test]
+    fn custom_buttons() {
+        let buttons = vec![
+            TabBarButton {
+                label: " A ".to_string(),
+                hover_label: None,
+                action: KeyAssignment::ActivateTab(0),
+            },
+            TabBarButton {
+                label: " B ".to_string(),
+                hover_label: Some(" [B] ".to_string()),
+                action: KeyAssignment::ActivateTab(1),
+            },
+        ];
+        let labels = custom_button_labels(
+            &buttons,
+            &CellAttributes::default(),
+            &CellAttributes::default(),
+        );
+        assert_eq!(labels[0].1.as_str(), " A ");
+        assert_eq!(labels[1].1.as_str(), " [B] ");
+        assert_eq!(button_width(&labels[0]), 3);
+        assert_eq!(button_width(&labels[1]), 5);
+
+        let mut line = Line::with_width(0, SEQ_ZERO);
+        let mut items = vec![];
+        let mut x = 10;
+        // The mouse is over the second button
+        append_custom_buttons(&labels, Some(14), &mut x, &mut items, &mut line);
+        assert_eq!(line.as_str(), " A  [B] ");
+        assert_eq!(x, 18);
+        let layout: Vec<(TabBarItem, usize, usize)> = items
+            .iter()
+            .map(|entry| (entry.item, entry.x, entry.width))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                (TabBarItem::CustomButton(0), 10, 3),
+                (TabBarItem::CustomButton(1), 13, 5)
+            ]
+        );
+    }
 }
@@ -1,15 +1,18 @@
 use crate::color::LinearRgba;
 use crate::glyphcache::LoadState;
-use crate::quad::{QuadAllocator, QuadTrait};
+use crate::quad::{QuadAllocator, QuadTrait, TripleLayerQuadAllocator};
 use crate::termwindow::RenderState;
 use crate::utilsprites::RenderMetrics;
 use crate::Dimensions;
+use ::window::RectF;
 use anyhow::Context;
 use config::{
     BackgroundHorizontalAlignment, BackgroundLayer, BackgroundRepeat, BackgroundSize,
     BackgroundSource, BackgroundVerticalAlignment, ConfigHandle, DimensionContext, Gradient,
     GradientOrientation,
 };
+use mux::pane::PaneId;
+use mux::Mux;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
@@ -393,6 +396,54 @@ pub fn reload_background_image(
     result
 }
 
+/// The background layers of a pane, or of the tab that contains it,
+/// loaded for the size at which the pane was last painted
+pub struct PaneBackground {
+    layers: Arc<Vec<BackgroundLayer>>,
+    dimensions: Dimensions,
+    loaded: Vec<LoadedBackgroundLayer>,
+}
+
+/// A quad to be emitted for a background layer
+struct BackgroundTile {
+    /// left, top, right, bottom in window pixel coordinates
+    position: (f32, f32, f32, f32),
+    /// x1, x2, y1, y2
+    texture: (f32, f32, f32, f32),
+}
+
+impl BackgroundTile {
+    /// Clips the tile to `rect`, adjusting the texture coordinates so
+    /// that the visible part of the image is unchanged
+    fn clip(self, rect: RectF) -> Option<Self> {
+        let (left, top, right, bottom) = self.position;
+        let clipped_left = left.max(rect.min_x());
+        let clipped_top = top.max(rect.min_y());
+        let clipped_right = right.min(rect.max_x());
+        let clipped_bottom = bottom.min(rect.max_y());
+        if clipped_left >= clipped_right || clipped_top >= clipped_bottom {
+            return None;
+        }
+
+        fn lerp(a: f32, b: f32, t: f32) -> f32 {
+            a + (b - a) * t
+        }
+
+        let (x1, x2, y1, y2) = self.texture;
+        let width = right - left;
+        let height = bottom - top;
+        Some(Self {
+            position: (clipped_left, clipped_top, clipped_right, clipped_bottom),
+            texture: (
+                lerp(x1, x2, (clipped_left - left) / width),
+                lerp(x1, x2, (clipped_right - left) / width),
+                lerp(y1, y2, (clipped_top - top) / height),
+                lerp(y1, y2, (clipped_bottom - top) / height),
+            ),
+        })
+    }
+}
+
 impl crate::TermWindow {
     pub fn render_backgrounds(
         &self,
@@ -424,7 +475,134 @@ impl crate::TermWindow {
         let mut layer0 = vbs[0].map();
 
         let color = bg_color.mul_alpha(layer.def.opacity);
+        let rect = euclid::rect(
+            0.,
+            0.,
+            self.dimensions.pixel_width as f32,
+            self.dimensions.pixel_height as f32,
+        );
+        let tiles = match self.background_tiles(gl_state, layer, top, rect)? {
+            Some(tiles) => tiles,
+            None => return Ok(false),
+        };
+
+        for tile in &tiles {
+            let mut quad = layer0.allocate()?;
+            self.set_background_tile(&mut quad, tile, layer, color);
+        }
+
+        Ok(!tiles.is_empty())
+    }
+
+    /// Returns the background layers that apply to `pane_id`: its own,
+    /// or failing that, those of the tab that contains it
+    fn background_layers_for_pane(&self, pane_id: PaneId) -> Option<Arc<Vec<BackgroundLayer>>> {
+        let mux = Mux::get();
+        mux.get_pane_background(pane_id).or_else(|| {
+            let (_domain_id, _window_id, tab_id) = mux.resolve_pane_id(pane_id)?;
+            mux.get_tab_background(tab_id)
+        })
+    }
+
+    pub fn pane_has_background(&self, pane_id: PaneId) -> bool {
+        self.background_layers_for_pane(pane_id).is_some()
+    }
+
+    /// Renders the background layers set on a pane or its tab into
+    /// `rect`, which is the area of the window, in pixels, that the pane
+    /// occupies, including any padding around it.
+    /// Returns false if there are no layers or they haven't loaded yet.
+    pub fn render_pane_background(
+        &self,
+        layers: &mut TripleLayerQuadAllocator,
+        pane_id: PaneId,
+        bg_color: LinearRgba,
+        rect: RectF,
+        top: StableRowIndex,
+    ) -> anyhow::Result<bool> {
+        let mut state = self.pane_state(pane_id);
+        let wanted = match self.background_layers_for_pane(pane_id) {
+            Some(wanted) => wanted,
+            None => {
+                state.background.take();
+                return Ok(false);
+            }
+        };
+
+        let dimensions = Dimensions {
+            pixel_width: rect.width() as usize,
+            pixel_height: rect.height() as usize,
+            dpi: self.dimensions.dpi,
+        };
+        let stale = match &state.background {
+            Some(bg) => !Arc::ptr_eq(&bg.layers, &wanted) || bg.dimensions != dimensions,
+            None => true,
+        };
+        if stale {
+            let mut loaded = vec![];
+            for layer in wanted.iter() {
+                match load_background_layer(layer, &dimensions, &self.render_metrics) {
+                    Ok(layer) => loaded.push(layer),
+                    Err(err) => log::error!("Failed to load pane background: {:#}", err),
+                }
+            }
+            state.background.replace(PaneBackground {
+                layers: wanted,
+                dimensions,
+                loaded,
+            });
+        }
+
+        let gl_state = self.render_state.as_ref().unwrap();
+        let mut emitted = false;
+        if let Some(bg) = &state.background {
+            for layer in &bg.loaded {
+                let color = bg_color.mul_alpha(layer.def.opacity);
+                if let Some(tiles) = self.background_tiles(gl_state, layer, top, rect)? {
+                    for tile in &tiles {
+                        let mut quad = layers.allocate(0)?;
+                        self.set_background_tile(&mut quad, tile, layer, color);
+                        emitted = true;
+                    }
+                }
+            }
+        }
+        Ok(emitted)
+    }
 
+    fn set_background_tile(
+        &self,
+        quad: &mut impl QuadTrait,
+        tile: &BackgroundTile,
+        layer: &LoadedBackgroundLayer,
+        color: LinearRgba,
+    ) {
+        let left_offset = self.dimensions.pixel_width as f32 / 2.;
+        let top_offset = self.dimensions.pixel_height as f32 / 2.;
+        let (left, top, right, bottom) = tile.position;
+        quad.set_position(
+            left - left_offset,
+            top - top_offset,
+            right - left_offset,
+            bottom - top_offset,
+        );
+        let (x1, x2, y1, y2) = tile.texture;
+        quad.set_texture_discrete(x1, x2, y1, y2);
+        quad.set_is_background_image();
+        quad.set_hsv(Some(layer.def.hsb));
+        quad.set_fg_color(color);
+    }
+
+    /// Computes the quads needed to draw `layer` into `rect`, which is
+    /// in window pixel coordinates.
+    /// Returns None if the image hasn't finished loading.
+    fn background_tiles(
+        &self,
+        gl_state: &RenderState,
+        layer: &LoadedBackgroundLayer,
+        top: StableRowIndex,
+        rect: RectF,
+    ) -> anyhow::Result<Option<Vec<BackgroundTile>>> {
         let (sprite, next_due, load_state) = gl_state.glyph_cache.borrow_mut().cached_image(
             &layer.source,
             None,
@@ -433,11 +611,11 @@ impl crate::TermWindow {
         self.update_next_frame_time(next_due);
 
         if load_state == LoadState::Loading {
-            return Ok(false);
+            return Ok(None);
         }
 
-        let pixel_width = self.dimensions.pixel_width as f32;
-        let pixel_height = self.dimensions.pixel_height as f32;
+        let pixel_width = rect.width();
+        let pixel_height = rect.height();
         let tex_width = sprite.coords.width() as f32;
         let tex_height = sprite.coords.height() as f32;
 
@@ -480,8 +658,8 @@ impl crate::TermWindow {
             BackgroundSize::Dimension(n) => n.evaluate_as_pixels(v_context),
         };
 
-        let mut origin_x = pixel_width / -2.;
-        let top_pixel = pixel_height / -2.;
+        let mut origin_x = rect.min_x();
+        let top_pixel = rect.min_y();
         let mut origin_y = top_pixel;
 
         match layer.def.vertical_align {
@@ -540,7 +718,7 @@ impl crate::TermWindow {
 
         let limit_y = top_pixel + pixel_height;
 
-        let mut emitted = false;
+        let mut tiles = vec![];
 
         for y_step in start_tile.. {
             let offset_y = (y_step - start_tile) as f32 * repeat_y;
@@ -559,10 +737,7 @@ impl crate::TermWindow {
                     break;
                 }
                 let origin_x = origin_x + offset_x;
-                let mut quad = layer0.allocate()?;
-                emitted = true;
                 // log::info!("quad {origin_x},{origin_y} {width}x{height}");
-                quad.set_position(origin_x, origin_y, origin_x + width, origin_y + height);
 
                 let coords = sprite.texture_coords();
                 let mut x1 = coords.min_x();
@@ -576,13 +751,36 @@ impl crate::TermWindow {
                     std::mem::swap(&mut y1, &mut y2);
                 }
 
-                quad.set_texture_discrete(x1, x2, y1, y2);
-                quad.set_is_background_image();
-                quad.set_hsv(Some(layer.def.hsb));
-                quad.set_fg_color(color);
+                let tile = BackgroundTile {
+                    position: (origin_x, origin_y, origin_x + width, origin_y + height),
+                    texture: (x1, x2, y1, y2),
+                };
+                tiles.extend(tile.clip(rect));
             }
         }
 
-        Ok(emitted)
+        Ok(Some(tiles))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clip_tile() {
+        let tile = BackgroundTile {
+            position: (-10., 0., 10., 20.),
+            texture: (0., 1., 0., 1.),
+        };
+        let clipped = tile.clip(euclid::rect(0., 5., 100., 10.)).unwrap();
+        assert_eq!(clipped.position, (0., 5., 10., 15.));
+        assert_eq!(clipped.texture, (0.5, 1., 0.25, 0.75));
+
+        let outside = BackgroundTile {
+            position: (-10., 0., 0., 20.),
+            texture: (0., 1., 0., 1.),
+        };
+        assert!(outside.clip(euclid::rect(0., 0., 100., 100.)).is_none());
     }
 }
@@ -1,16 +1,30 @@
-use crate::termwindow::TermWindowNotif;
+use crate::overlay::{confirm, start_overlay, start_overlay_pane};
+use crate::termwindow::{GuiWin, TermWindowNotif};
 use crate::TermWindow;
-use config::keyassignment::{ClipboardCopyDestination, ClipboardPasteSource};
-use mux::pane::Pane;
+use config::keyassignment::{
+    ClipboardCopyDestination, ClipboardPasteSource, InputSelector, InputSelectorEntry,
+    KeyAssignment,
+};
+use mlua::FromLua;
+use mux::domain::DomainState;
+use mux::pane::{Pane, PaneId};
 use mux::Mux;
+use mux_lua::MuxPane;
 use smol::Timer;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use wezterm_client::domain::ClientDomain;
+use wezterm_term::ClipboardSelection;
 use window::{Clipboard, ClipboardData, WindowOps};
 
 impl TermWindow {
     pub fn copy_to_clipboard(&self, clipboard: ClipboardCopyDestination, text: String) {
+        self.sync_clipboard(clipboard, &text);
+        if clipboard != ClipboardCopyDestination::PrimarySelection {
+            crate::clipboard_history::record(&text);
+        }
         let clipboard = match clipboard {
             ClipboardCopyDestination::Clipboard => [Some(Clipboard::Clipboard), None],
             ClipboardCopyDestination::PrimarySelection => [Some(Clipboard::PrimarySelection), None],
@@ -26,6 +40,31 @@ impl TermWindow {
         }
     }
 
+    /// Offers text copied in this window to the other clients that
+    /// are attached to the same mux servers, when enabled by
+    /// `clipboard_sync.send`
+    fn sync_clipboard(&self, clipboard: ClipboardCopyDestination, text: &str) {
+        let sync = &self.config.clipboard_sync;
+        if !sync.sends(text.len()) {
+            return;
+        }
+        let selection = match clipboard {
+            ClipboardCopyDestination::PrimarySelection => ClipboardSelection::PrimarySelection,
+            ClipboardCopyDestination::Clipboard
+            | ClipboardCopyDestination::ClipboardAndPrimarySelection => {
+                ClipboardSelection::Clipboard
+            }
+        };
+        for domain in Mux::get().iter_domains() {
+            if domain.state() != DomainState::Attached {
+                continue;
+            }
+            if let Some(client) = domain.downcast_ref::<ClientDomain>() {
+                client.sync_clipboard(selection, text.to_string());
+            }
+        }
+    }
+
     /// Show toast notification with a message (disappears after 2.5 seconds).
     /// Rapid consecutive calls are safe: each toast stores its creation `Instant`,
     /// so only the matching toast is cleared — newer toasts naturally supersede older ones.
@@ -65,7 +104,89 @@ impl TermWindow {
         self.show_toast("Copied".to_string());
     }
 
+    /// Called when files are dropped onto the window.  The
+    /// `file-dropped` event is given the chance to handle them first;
+    /// unless it returns false, their quoted paths are then pasted
+    /// into the active pane.  If the event fails, the paths are
+    /// pasted anyway.
+    pub fn drop_files(&mut self, paths: Vec<PathBuf>) {
+        let pane = match self.get_active_pane_or_overlay() {
+            Some(pane) => pane,
+            None => return,
+        };
+
+        /// Returns whether the default action should take place
+        async fn emit_file_dropped(
+            lua: &mlua::Lua,
+            window: GuiWin,
+            pane: MuxPane,
+            paths: &[PathBuf],
+        ) -> mlua::Result<bool> {
+            let names: Vec<String> = paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            let args = lua.pack_multi((window, pane, names))?;
+            config::lua::emit_event(lua, ("file-dropped".to_string(), args)).await
+        }
+
+        async fn dispatch_file_dropped(
+            lua: Option<Rc<mlua::Lua>>,
+            window: GuiWin,
+            pane: MuxPane,
+            paths: Vec<PathBuf>,
+        ) -> anyhow::Result<()> {
+            let default_action = match lua {
+                Some(lua) => emit_file_dropped(&lua, window.clone(), pane, &paths)
+                    .await
+                    .unwrap_or_else(|err| {
+                        // A broken handler shouldn't make dropping files
+                        // do nothing at all
+                        log::error!("while processing file-dropped event: {:#}", err);
+                        true
+                    }),
+                None => true,
+            };
+            if default_action {
+                window
+                    .window
+                    .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                        term_window.paste_dropped_files(&paths);
+                    })));
+            }
+            Ok(())
+        }
+
+        let window = GuiWin::new(self);
+        let pane = MuxPane(pane.pane_id());
+        promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+            dispatch_file_dropped(lua, window, pane, paths)
+        }))
+        .detach();
+    }
+
+    fn paste_dropped_files(&mut self, paths: &[PathBuf]) {
+        let pane = match self.get_active_pane_or_overlay() {
+            Some(pane) => pane,
+            None => return,
+        };
+        if self.pane_input_locked(&pane, true) {
+            return;
+        }
+        let quoting = self.config.quote_dropped_files;
+        let text = paths
+            .iter()
+            .map(|path| quoting.escape(&path.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+            + " ";
+        self.paste_text(pane.pane_id(), text);
+    }
+
     pub fn paste_from_clipboard(&mut self, pane: &Arc<dyn Pane>, clipboard: ClipboardPasteSource) {
+        if self.pane_input_locked(pane, true) {
+            return;
+        }
         let pane_id = pane.pane_id();
         log::trace!(
             "paste_from_clipboard in pane {} {:?}",
@@ -88,22 +209,7 @@ impl TermWindow {
                             None => return,
                         };
 
-                        if let Some(pane) = myself
-                            .pane_state(pane_id)
-                            .overlay
-                            .as_ref()
-                            .map(|overlay| overlay.pane.clone())
-                            .or_else(|| {
-                                let mux = Mux::get();
-                                mux.get_pane(pane_id)
-                            })
-                        {
-                            if let Err(err) = pane.send_paste(&clip) {
-                                log::warn!(
-                                    "failed to paste clipboard content into pane {pane_id}: {err:#}"
-                                );
-                            }
-                        }
+                        myself.paste_text(pane_id, clip);
                     })));
                 }
                 Err(err) => {
@@ -116,6 +222,276 @@ impl TermWindow {
     }
 }
 
+impl TermWindow {
+    /// Runs pasted text through the paste pipeline: the
+    /// `transform-paste` event may rewrite or cancel it, a trailing
+    /// newline is removed when `paste_strip_trailing_newline` is set,
+    /// and a paste that could run commands in a pane without
+    /// bracketed paste mode must be confirmed first
+    fn paste_text(&mut self, pane_id: PaneId, text: String) {
+        async fn transform_paste(
+            lua: Option<Rc<mlua::Lua>>,
+            window: GuiWin,
+            pane: MuxPane,
+            text: String,
+        ) -> anyhow::Result<()> {
+            let text = match lua {
+                Some(lua) => {
+                    let args = (window.clone(), pane, text.clone());
+                    match config::lua::emit_async_callback(
+                        &lua,
+                        ("transform-paste".to_string(), args),
+                    )
+                    .await
+                    {
+                        Ok(mlua::Value::Nil) => text,
+                        // Returning false cancels the paste
+                        Ok(mlua::Value::Boolean(false)) => return Ok(()),
+                        Ok(value) => String::from_lua(value, &lua)?,
+                        Err(err) => {
+                            log::error!("while processing transform-paste event: {err:#}");
+                            text
+                        }
+                    }
+                }
+                None => text,
+            };
+            let pane_id = pane.0;
+            window
+                .window
+                .notify(TermWindowNotif::Apply(Box::new(move |myself| {
+                    myself.finish_paste(pane_id, text);
+                })));
+            Ok(())
+        }
+
+        let window = GuiWin::new(self);
+        let pane = MuxPane(pane_id);
+        promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+            transform_paste(lua, window, pane, text)
+        }))
+        .detach();
+    }
+
+    fn finish_paste(&mut self, pane_id: PaneId, mut text: String) {
+        if self.config.paste_strip_trailing_newline {
+            let len = text.trim_end_matches(['\r', '\n']).len();
+            text.truncate(len);
+        }
+        if text.is_empty() {
+            return;
+        }
+        let pane = match self.paste_target(pane_id) {
+            Some(pane) => pane,
+            None => return,
+        };
+
+        // Without bracketed paste, the program can't tell pasted text
+        // from typed text, so an embedded newline runs a command.
+        // That goes for any of the panes that input is broadcast to.
+        let unsafe_paste = self.config.paste_confirm_unsafe
+            && is_unsafe_paste(&text)
+            && std::iter::once(pane.clone())
+                .chain(Mux::get().input_broadcast_targets(pane_id))
+                .any(|pane| pane.is_bracketed_paste_enabled() == Some(false));
+        if !unsafe_paste {
+            self.send_paste_to_pane(pane_id, &text);
+            return;
+        }
+
+        let message = format!(
+            "\u{26a0}\u{fe0f} This paste contains newlines or control characters, and the \
+             program in this pane has not enabled bracketed paste, so it may run commands \
+             immediately. Paste it anyway?\n\n{}",
+            paste_preview(&text)
+        );
+        let window = self.window.clone().unwrap();
+        let (overlay, future) = start_overlay_pane(self, &pane, move |pane_id, mut term| {
+            let confirmed = confirm::run_confirmation(&message, &mut term)?;
+            TermWindow::schedule_cancel_overlay_for_pane(window.clone(), pane_id);
+            if confirmed {
+                window.notify(TermWindowNotif::Apply(Box::new(move |myself| {
+                    myself.send_paste_to_pane(pane_id, &text);
+                })));
+            }
+            Ok(())
+        });
+        self.assign_overlay_for_pane(pane_id, overlay);
+        promise::spawn::spawn(future).detach();
+    }
+
+    /// Returns the overlay of the pane if it has one, otherwise the
+    /// pane itself
+    fn paste_target(&mut self, pane_id: PaneId) -> Option<Arc<dyn Pane>> {
+        self.pane_state(pane_id)
+            .overlay
+            .as_ref()
+            .map(|overlay| overlay.pane.clone())
+            .or_else(|| {
+                let mux = Mux::get();
+                mux.get_pane(pane_id)
+            })
+    }
+
+    /// Pastes `text` into the pane, or its overlay, and the panes
+    /// that it broadcasts input to
+    fn send_paste_to_pane(&mut self, pane_id: PaneId, text: &str) {
+        if let Some(pane) = self.paste_target(pane_id) {
+            if let Err(err) = pane.send_paste(text) {
+                log::warn!("failed to paste clipboard content into pane {pane_id}: {err:#}");
+            }
+            for target in Mux::get().input_broadcast_targets(pane_id) {
+                target.send_paste(text).ok();
+            }
+        }
+    }
+
+    /// Pastes the text that was yanked into the register `name` in
+    /// copy mode
+    pub fn paste_from_register(&mut self, pane: &Arc<dyn Pane>, name: &str) {
+        if self.pane_input_locked(pane, true) {
+            return;
+        }
+        let text = name
+            .chars()
+            .next()
+            .and_then(crate::overlay::copy::register_text);
+        match text {
+            Some(text) => {
+                self.paste_text(pane.pane_id(), text);
+                self.maybe_scroll_to_bottom_for_input(pane);
+            }
+            None => self.show_toast(format!("Register {name} is empty")),
+        }
+    }
+
+    /// Shows the recent copies in a fuzzy searchable list; the one
+    /// that is picked is pasted into the active pane
+    pub fn show_clipboard_history(&mut self) {
+        let mux = Mux::get();
+        let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab,
+            None => return,
+        };
+        let pane = match self.get_active_pane_no_overlay() {
+            Some(pane) => pane,
+            None => return,
+        };
+        let entries = crate::clipboard_history::entries();
+        if entries.is_empty() {
+            self.show_toast("The clipboard history is empty".to_string());
+            return;
+        }
+
+        let choices = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| InputSelectorEntry {
+                label: history_label(&entry.text),
+                id: Some(idx.to_string()),
+            })
+            .collect();
+        let args = InputSelector {
+            action: Box::new(KeyAssignment::Nop),
+            title: "Clipboard History".to_string(),
+            choices,
+            fuzzy: true,
+            alphabet: "1234567890abcdefghilmnopqrstuvwxyz".to_string(),
+            description: "Select a copy and press Enter = paste,  Esc = cancel".to_string(),
+            fuzzy_description: "Paste from history: ".to_string(),
+        };
+
+        let pane_id = pane.pane_id();
+        let window = self.window.clone().unwrap();
+        let on_select = move |entry: Option<InputSelectorEntry>| {
+            let text = entry
+                .and_then(|entry| entry.id)
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|idx| entries.into_iter().nth(idx))
+                .map(|entry| entry.text);
+            if let Some(text) = text {
+                window.notify(TermWindowNotif::Apply(Box::new(move |myself| {
+                    let pane = match Mux::get().get_pane(pane_id) {
+                        Some(pane) => pane,
+                        None => return,
+                    };
+                    if myself.pane_input_locked(&pane, true) {
+                        return;
+                    }
+                    myself.paste_text(pane_id, text);
+                    myself.maybe_scroll_to_bottom_for_input(&pane);
+                })));
+            }
+        };
+
+        let gui_win = GuiWin::new(self);
+        let mux_pane = MuxPane(pane_id);
+        let (overlay, future) = start_overlay(self, &tab, move |_tab_id, term| {
+            crate::overlay::selector::selector_with_callback(
+                term, args, gui_win, mux_pane, on_select,
+            )
+        });
+        self.assign_overlay(tab.tab_id(), overlay);
+        promise::spawn::spawn(future).detach();
+    }
+}
+
+/// Summarizes copied text on a single line for the history list
+fn history_label(text: &str) -> String {
+    const MAX_CHARS: usize = 120;
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first: String = lines
+        .next()
+        .unwrap_or("")
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_CHARS)
+        .collect();
+    let more = lines.count();
+    if more > 0 {
+        format!("{first} \u{2026} (+{more} lines)")
+    } else {
+        first
+    }
+}
+
+/// Returns true if `text` includes newlines or other control characters
+/// that could run commands if pasted without bracketed paste mode
+fn is_unsafe_paste(text: &str) -> bool {
+    text.chars().any(|c| c.is_control() && c != '\t')
+}
+
+/// Shows the first few lines of a paste in the confirmation prompt,
+/// with control characters made visible
+fn paste_preview(text: &str) -> String {
+    const MAX_LINES: usize = 5;
+    const MAX_CHARS: usize = 60;
+    let lines: Vec<&str> = text.lines().collect();
+    let mut preview: Vec<String> = lines
+        .iter()
+        .take(MAX_LINES)
+        .map(|line| {
+            let chars: Vec<char> = line
+                .chars()
+                .flat_map(|c| match c {
+                    '\t' => vec![' '],
+                    c if c.is_control() => c.escape_default().collect(),
+                    c => vec![c],
+                })
+                .collect();
+            let mut shown: String = chars.iter().take(MAX_CHARS).collect();
+            if chars.len() > MAX_CHARS {
+                shown.push('\u{2026}');
+            }
+            format!("  {shown}")
+        })
+        .collect();
+    if lines.len() > MAX_LINES {
+        preview.push(format!("  \u{2026} (+{} lines)", lines.len() - MAX_LINES));
+    }
+    preview.join("\n")
+}
+
 fn data_to_paste_string(
     data: ClipboardData,
     quote_dropped_files: config::DroppedFileQuoting,
@@ -171,3 +547,52 @@ fn quote_path_for_clipboard_paste(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn history_labels() {
+        assert_eq!(history_label("ls -l"), "ls -l");
+        assert_eq!(
+            history_label("\n  first\n\nsecond\nthird\n"),
+            "first \u{2026} (+2 lines)"
+        );
+        assert_eq!(history_label("a\u{1b}b"), "a b");
+        assert_eq!(history_label(&"x".repeat(200)), "x".repeat(120));
+    }
+
+    #[test]
+    fn unsafe_paste() {
+        assert!(!is_unsafe_paste("ls -l"));
+        assert!(!is_unsafe_paste("a\tb"));
+        assert!(is_unsafe_paste("ls\n"));
+        assert!(is_unsafe_paste("ls\rrm -rf ~"));
+        assert!(is_unsafe_paste("\u{1b}[201~"));
+    }
+
+    #[test]
+    fn preview() {
+        assert_eq!(
+            paste_preview("echo hi\nrm\t-rf ~\n"),
+            "  echo hi\n  rm -rf ~"
+        );
+        assert_eq!(paste_preview("\u{1b}[201~"), "  \\u{1b}[201~");
+
+        let long = "x".repeat(70);
+        assert_eq!(
+            paste_preview(&long),
+            format!("  {}\u{2026}", "x".repeat(60))
+        );
+
+        let many = (1..=8)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            paste_preview(&many),
+            "  1\n  2\n  3\n  4\n  5\n  \u{2026} (+3 lines)"
+        );
+    }
+}
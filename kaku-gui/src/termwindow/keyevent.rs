@@ -1,14 +1,17 @@
-use crate::termwindow::InputMap;
+use crate::termwindow::{InputMap, TermWindowNotif};
 use ::window::{
     DeadKeyStatus, KeyCode, KeyEvent, KeyboardLedStatus, Modifiers, RawKeyEvent, WindowOps,
 };
 use anyhow::Context;
 use config::keyassignment::{KeyAssignment, KeyTableEntry};
-use mux::pane::{Pane, PerformAssignmentResult};
+use config::ImePreeditRendering;
+use mux::pane::{Pane, PaneId, PerformAssignmentResult};
+use mux::Mux;
 use smol::Timer;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use termwiz::input::KeyboardEncoding;
+use termwiz::input::{KeyCodeEncodeModes, KeyboardEncoding};
+use wezterm_dynamic::ToDynamic;
 
 #[derive(Debug, Clone)]
 pub struct KeyTableStateEntry {
@@ -33,9 +36,33 @@ pub struct KeyTableArgs<'a> {
     pub prevent_fallback: bool,
 }
 
+/// Describes an activation on the key table stack; returned
+/// by `window:key_table_stack()`
+#[derive(Debug, Clone, ToDynamic)]
+pub struct KeyTableStackItem {
+    pub name: String,
+    pub one_shot: bool,
+    pub until_unknown: bool,
+    pub prevent_fallback: bool,
+    pub timeout_milliseconds: Option<u64>,
+    /// How long remains until the activation expires
+    pub remaining_milliseconds: Option<u64>,
+}
+
+/// See `TermWindow::check_key_table_stack_changed`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyTableStackVersion {
+    window: usize,
+    overlay: Option<(PaneId, usize)>,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct KeyTableState {
     stack: Vec<KeyTableStateEntry>,
+    /// Incremented whenever an entry is pushed onto or popped from
+    /// the stack, so that pushing a table that is already at the top
+    /// counts as a change even though the names are the same
+    generation: usize,
 }
 
 impl KeyTableState {
@@ -53,14 +80,24 @@ impl KeyTableState {
             prevent_fallback: args.prevent_fallback,
             timeout_milliseconds: args.timeout_milliseconds,
         });
+        self.generation += 1;
     }
 
     pub fn pop(&mut self) {
-        self.stack.pop();
+        if self.stack.pop().is_some() {
+            self.generation += 1;
+        }
     }
 
     pub fn clear_stack(&mut self) {
-        self.stack.clear();
+        if !self.stack.is_empty() {
+            self.stack.clear();
+            self.generation += 1;
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
     }
 
     pub fn process_expiration(&mut self) -> bool {
@@ -95,6 +132,25 @@ impl KeyTableState {
         self.stack.last().map(|entry| entry.name.as_str())
     }
 
+    /// Returns the activations, the most recent last
+    pub fn stack_items(&mut self) -> Vec<KeyTableStackItem> {
+        while self.process_expiration() {}
+        let now = Instant::now();
+        self.stack
+            .iter()
+            .map(|entry| KeyTableStackItem {
+                name: entry.name.clone(),
+                one_shot: entry.one_shot,
+                until_unknown: entry.until_unknown,
+                prevent_fallback: entry.prevent_fallback,
+                timeout_milliseconds: entry.timeout_milliseconds,
+                remaining_milliseconds: entry
+                    .expiration
+                    .map(|deadline| deadline.saturating_duration_since(now).as_millis() as u64),
+            })
+            .collect()
+    }
+
     fn lookup_key(
         &mut self,
         input_map: &InputMap,
@@ -131,6 +187,7 @@ impl KeyTableState {
                     result = Some((
                         KeyTableEntry {
                             action: KeyAssignment::Nop,
+                            metadata: Default::default(),
                         },
                         Some(name.to_string()),
                     ));
@@ -255,12 +312,17 @@ impl super::TermWindow {
                 let target = std::time::Instant::now() + duration;
                 self.leader_is_down.replace(target);
                 self.update_title();
-                // schedule an invalidation so that the cursor or status
-                // area will be repainted at the right time
+                // schedule an update so that the cursor and the leader
+                // indicator will be repainted at the right time
                 if let Some(window) = self.window.clone() {
                     promise::spawn::spawn(async move {
                         Timer::at(target).await;
-                        window.invalidate();
+                        window.notify(TermWindowNotif::Apply(Box::new(|term_window| {
+                            // Unless the leader was pressed again meanwhile
+                            if !term_window.leader_is_active() {
+                                term_window.leader_done();
+                            }
+                        })));
                     })
                     .detach();
                 }
@@ -333,7 +395,7 @@ impl super::TermWindow {
             _ => OnlyKeyBindings::No,
         };
 
-        if only_key_bindings == OnlyKeyBindings::No {
+        if only_key_bindings == OnlyKeyBindings::No && !self.pane_input_locked(pane, false) {
             let config = &self.config;
 
             // This is a bit ugly.
@@ -402,6 +464,19 @@ impl super::TermWindow {
                     };
 
                     if did_encode {
+                        for target in Mux::get().input_broadcast_targets(pane.pane_id()) {
+                            match key_event {
+                                Some(key_event) => self.send_key_event_to_pane(
+                                    &target,
+                                    key_event,
+                                    term_key,
+                                    tw_raw_modifiers,
+                                ),
+                                None if is_down => target.key_down(term_key, tw_raw_modifiers),
+                                None => target.key_up(term_key, tw_raw_modifiers),
+                            }
+                            .ok();
+                        }
                         if is_down
                             && !keycode.is_modifier()
                             && self.pane_state(pane.pane_id()).overlay.is_none()
@@ -456,6 +531,9 @@ impl super::TermWindow {
         if self.current_modifier_and_leds != modifier_and_leds {
             self.current_modifier_and_leds = modifier_and_leds;
             self.schedule_next_status_update();
+            if self.config.pane_index_badges == config::PaneIndexBadges::WhileModifiersHeld {
+                context.invalidate();
+            }
         }
 
         let pane = match self.get_active_pane_or_overlay() {
@@ -584,10 +662,60 @@ impl super::TermWindow {
         name
     }
 
+    /// Returns the key tables of the window, followed by those of
+    /// the overlay of the active pane, which take precedence
+    pub fn key_table_stack(&mut self) -> Vec<KeyTableStackItem> {
+        let mut stack = self.key_table_state.stack_items();
+        if let Some(pane) = self.get_active_pane_or_overlay() {
+            if let Some(overlay) = self.pane_state(pane.pane_id()).overlay.as_mut() {
+                stack.extend(overlay.key_table_state.stack_items());
+            }
+        }
+        stack
+    }
+
+    /// Identifies the state of the key table stacks of the window
+    /// and of the overlay of the active pane
+    fn key_table_stack_version(&mut self) -> KeyTableStackVersion {
+        let mut version = KeyTableStackVersion {
+            window: self.key_table_state.generation(),
+            overlay: None,
+        };
+        if let Some(pane) = self.get_active_pane_or_overlay() {
+            let pane_id = pane.pane_id();
+            if let Some(overlay) = self.pane_state(pane_id).overlay.as_ref() {
+                version.overlay = Some((pane_id, overlay.key_table_state.generation()));
+            }
+        }
+        version
+    }
+
+    /// Emits `key-table-stack-changed` if any of the key table stacks
+    /// have had entries pushed or popped since the last call
+    pub fn check_key_table_stack_changed(&mut self) {
+        // Pops any expired activations, and arranges to repaint
+        // when the next one expires
+        self.current_key_table_name();
+        let version = self.key_table_stack_version();
+        if version != self.last_key_table_stack {
+            self.last_key_table_stack = version;
+            self.emit_window_event("key-table-stack-changed", None);
+        }
+    }
+
     pub fn composition_status(&self) -> &DeadKeyStatus {
         &self.dead_key_status
     }
 
+    /// Returns the text being composed by the input method when it
+    /// is to be drawn inline at the cursor of the active pane
+    pub fn inline_composition(&self) -> Option<&str> {
+        match (&self.dead_key_status, self.config.ime_preedit_rendering) {
+            (DeadKeyStatus::Composing(composing), ImePreeditRendering::Builtin) => Some(composing),
+            _ => None,
+        }
+    }
+
     fn leader_done(&mut self) {
         self.leader_is_down.take();
         self.update_title();
@@ -596,6 +724,62 @@ impl super::TermWindow {
         }
     }
 
+    /// Returns true if `pane` has been made read-only by
+    /// `TogglePaneInputLock`, in which case input must not be sent
+    /// to it.  If `notify` is true, a toast lets the user know why
+    /// their input was discarded.
+    pub fn pane_input_locked(&mut self, pane: &Arc<dyn Pane>, notify: bool) -> bool {
+        if !Mux::get().is_pane_input_locked(pane.pane_id()) {
+            return false;
+        }
+        if notify {
+            self.show_toast("Pane input is locked".to_string());
+        }
+        true
+    }
+
+    /// Sends `window_key` to `pane`, using the keyboard encoding that
+    /// the pane has selected.  `key` and `modifiers` are its termwiz
+    /// equivalent, used when no special encoding is active.
+    fn send_key_event_to_pane(
+        &self,
+        pane: &Arc<dyn Pane>,
+        window_key: &KeyEvent,
+        key: ::termwiz::input::KeyCode,
+        modifiers: Modifiers,
+    ) -> anyhow::Result<()> {
+        if let Some(encoded) = self.encode_win32_input(pane, window_key) {
+            if self.config.debug_key_events {
+                log::info!("win32: Encoded input as {:?}", encoded);
+            }
+            pane.writer()
+                .write_all(encoded.as_bytes())
+                .context("sending win32-input-mode encoded data")
+        } else if let Some(encoded) = self.encode_kitty_input(pane, window_key) {
+            if self.config.debug_key_events {
+                log::info!("kitty: Encoded input as {:?}", encoded);
+            }
+            pane.writer()
+                .write_all(encoded.as_bytes())
+                .context("sending kitty encoded data")
+        } else {
+            if self.config.debug_key_events {
+                log::info!(
+                    "send to pane {} key={:?} mods={:?}",
+                    if window_key.key_is_down { "DOWN" } else { "UP" },
+                    key,
+                    modifiers
+                );
+            }
+
+            if window_key.key_is_down {
+                pane.key_down(key, modifiers)
+            } else {
+                pane.key_up(key, modifiers)
+            }
+        }
+    }
+
     pub fn key_event_impl(&mut self, window_key: KeyEvent, context: &dyn WindowOps) {
         let pane = match self.get_active_pane_or_overlay() {
             Some(pane) => pane,
@@ -626,6 +810,8 @@ impl super::TermWindow {
             );
         }
 
+        self.report_key_to_debugger(&window_key, leader_mod);
+
         let modifiers = window_key.modifiers;
 
         if self.process_key(
@@ -671,38 +857,17 @@ impl super::TermWindow {
                     return;
                 }
 
-                let res = if let Some(encoded) = self.encode_win32_input(&pane, &window_key) {
-                    if self.config.debug_key_events {
-                        log::info!("win32: Encoded input as {:?}", encoded);
-                    }
-                    pane.writer()
-                        .write_all(encoded.as_bytes())
-                        .context("sending win32-input-mode encoded data")
-                } else if let Some(encoded) = self.encode_kitty_input(&pane, &window_key) {
-                    if self.config.debug_key_events {
-                        log::info!("kitty: Encoded input as {:?}", encoded);
-                    }
-                    pane.writer()
-                        .write_all(encoded.as_bytes())
-                        .context("sending kitty encoded data")
-                } else {
-                    if self.config.debug_key_events {
-                        log::info!(
-                            "send to pane {} key={:?} mods={:?}",
-                            if window_key.key_is_down { "DOWN" } else { "UP" },
-                            key,
-                            modifiers
-                        );
-                    }
+                if self.pane_input_locked(&pane, window_key.key_is_down && !key.is_modifier()) {
+                    return;
+                }
 
-                    if window_key.key_is_down {
-                        pane.key_down(key, modifiers)
-                    } else {
-                        pane.key_up(key, modifiers)
-                    }
-                };
+                let res = self.send_key_event_to_pane(&pane, &window_key, key, modifiers);
 
                 if res.is_ok() {
+                    for target in Mux::get().input_broadcast_targets(pane.pane_id()) {
+                        self.send_key_event_to_pane(&target, &window_key, key, modifiers)
+                            .ok();
+                    }
                     if window_key.key_is_down
                         && !key.is_modifier()
                         && self.pane_state(pane.pane_id()).overlay.is_none()
@@ -732,10 +897,16 @@ impl super::TermWindow {
                     return;
                 }
                 self.key_table_state.did_process_key();
+                if self.pane_input_locked(&pane, true) {
+                    return;
+                }
                 if self.config.debug_key_events {
                     log::info!("send to pane string={:?}", s);
                 }
                 pane.writer().write_all(s.as_bytes()).ok();
+                for target in Mux::get().input_broadcast_targets(pane.pane_id()) {
+                    target.writer().write_all(s.as_bytes()).ok();
+                }
                 self.maybe_scroll_to_bottom_for_input(&pane);
                 context.invalidate();
             }
@@ -743,6 +914,74 @@ impl super::TermWindow {
         }
     }
 
+    /// Describes `window_key` in its raw, mapped and encoded forms
+    /// to the key debugger overlay, if it is active.
+    fn report_key_to_debugger(&mut self, window_key: &KeyEvent, leader_mod: Modifiers) {
+        if self.key_debugger.is_none() {
+            return;
+        }
+
+        let mut report = format!(
+            "{} key={:?} mods={:?} repeat={}",
+            if window_key.key_is_down { "DOWN" } else { "UP" },
+            window_key.key,
+            window_key.modifiers,
+            window_key.repeat_count
+        );
+        if let Some(raw) = &window_key.raw {
+            report.push_str(&format!(
+                "\n  raw: key={:?} phys={:?} raw_code={:#x} mods={:?} leds={:?}",
+                raw.key, raw.phys_code, raw.raw_code, raw.modifiers, raw.leds
+            ));
+        }
+
+        let term_key = self.win_key_code_to_termwiz_key_code(&window_key.key);
+        let binding =
+            self.input_map
+                .lookup_key(&window_key.key, window_key.modifiers | leader_mod, None);
+        report.push_str(&format!("\n  mapped: {:?}", term_key));
+        if let Some(entry) = binding {
+            report.push_str(&format!(" -> {:?}", entry.action));
+        }
+
+        if let Some(pane) = self.get_active_pane_no_overlay() {
+            let encoded = if let Some(encoded) = self.encode_win32_input(&pane, window_key) {
+                Some(encoded)
+            } else if let Some(encoded) = self.encode_kitty_input(&pane, window_key) {
+                Some(encoded)
+            } else {
+                match &term_key {
+                    // Cursor key and newline modes are assumed to be
+                    // in their default state here
+                    Key::Code(code) => code
+                        .encode(
+                            window_key.modifiers,
+                            KeyCodeEncodeModes {
+                                encoding: pane.get_keyboard_encoding(),
+                                application_cursor_keys: false,
+                                newline_mode: false,
+                                modify_other_keys: None,
+                            },
+                            window_key.key_is_down,
+                        )
+                        .ok(),
+                    Key::Composed(s) => Some(s.clone()),
+                    Key::None => None,
+                }
+            };
+            if let Some(encoded) = encoded {
+                report.push_str(&format!("\n  encoded: {:?}", encoded));
+            }
+        }
+
+        if let Some(tx) = &self.key_debugger {
+            if tx.send(report).is_err() {
+                // The overlay was closed
+                self.key_debugger.take();
+            }
+        }
+    }
+
     pub fn win_key_code_to_termwiz_key_code(&self, key: &::window::KeyCode) -> Key {
         use ::termwiz::input::KeyCode as KC;
         use ::window::KeyCode as WK;
@@ -867,3 +1106,61 @@ impl super::TermWindow {
         Key::Code(code)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(name: &str, replace_current: bool) -> KeyTableArgs {
+        KeyTableArgs {
+            name,
+            timeout_milliseconds: None,
+            replace_current,
+            one_shot: false,
+            until_unknown: false,
+            prevent_fallback: false,
+        }
+    }
+
+    #[test]
+    fn generation_tracks_the_whole_stack() {
+        let mut state = KeyTableState::default();
+        state.clear_stack();
+        state.pop();
+        assert_eq!(state.generation(), 0);
+
+        state.activate(args("resize", false));
+        let pushed = state.generation();
+        assert_ne!(pushed, 0);
+
+        // Re-pushing the table at the top leaves the same name on top
+        state.activate(args("resize", true));
+        assert_eq!(state.current_table(), Some("resize"));
+        assert_ne!(state.generation(), pushed);
+
+        // A change below the top of the stack
+        state.activate(args("move", false));
+        let before = state.generation();
+        state.clear_stack();
+        state.activate(args("copy", false));
+        state.activate(args("move", false));
+        assert_eq!(state.current_table(), Some("move"));
+        assert_ne!(state.generation(), before);
+
+        let before = state.generation();
+        state.clear_stack();
+        assert_ne!(state.generation(), before);
+    }
+
+    #[test]
+    fn expiration_changes_generation() {
+        let mut state = KeyTableState::default();
+        state.activate(KeyTableArgs {
+            timeout_milliseconds: Some(0),
+            ..args("leader", false)
+        });
+        let before = state.generation();
+        assert_eq!(state.current_table(), None);
+        assert_ne!(state.generation(), before);
+    }
+}
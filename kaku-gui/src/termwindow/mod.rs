@@ -16,11 +16,11 @@ use crate::selection::Selection;
 use crate::shapecache::*;
 use crate::tabbar::{TabBarItem, TabBarState};
 use crate::termwindow::background::{
-    load_background_image, reload_background_image, LoadedBackgroundLayer,
+    load_background_image, reload_background_image, LoadedBackgroundLayer, PaneBackground,
 };
-use crate::termwindow::keyevent::{KeyTableArgs, KeyTableState};
+use crate::termwindow::keyevent::{KeyTableArgs, KeyTableStackVersion, KeyTableState};
 use crate::termwindow::modal::Modal;
-use crate::termwindow::render::paint::AllowImage;
+use crate::termwindow::render::paint::{AllowImage, FrameInputs};
 use crate::termwindow::render::{
     CachedLineState, LineQuadCacheKey, LineQuadCacheValue, LineToEleShapeCacheKey,
     LineToElementShapeItem,
@@ -30,18 +30,23 @@ use ::wezterm_term::input::{ClickPosition, MouseButton as TMB};
 use ::window::*;
 use anyhow::{anyhow, ensure, Context};
 use config::keyassignment::{
-    Confirmation, KeyAssignment, LauncherActionArgs, PaneDirection, Pattern, PromptInputLine,
-    QuickSelectArguments, RotationDirection, SpawnCommand, SplitSize,
+    BroadcastInputScope, ClipboardCopyDestination, Confirmation, KeyAssignment, LauncherActionArgs,
+    PaneDirection, PaneMonitor, Pattern, PromptInputLine, QuickSelectArguments, RotationDirection,
+    SpawnCommand, SplitSize,
 };
 use config::window::WindowLevel;
 use config::{
-    configuration, AudibleBell, ConfigHandle, Dimension, DimensionContext, FrontEndSelection,
-    GeometryOrigin, GuiPosition, TermConfig, WindowCloseConfirmation,
+    configuration, AudibleBell, ConfigDelta, ConfigHandle, Dimension, DimensionContext,
+    FrontEndSelection, GeometryOrigin, GuiGeometry, GuiPosition, SoundEvent, TermConfig,
+    WindowCloseConfirmation,
 };
 use lfucache::*;
 use mlua::{FromLua, LuaSerdeExt, UserData, UserDataFields};
+use mux::connui::PromptRequest;
+use mux::domain::LocalDomain;
 use mux::pane::{
     CachePolicy, CloseReason, Pane, PaneId, Pattern as MuxPattern, PerformAssignmentResult,
+    SearchResult,
 };
 use mux::renderable::RenderableDimensions;
 use mux::tab::{
@@ -49,24 +54,25 @@ use mux::tab::{
     TabId,
 };
 use mux::window::WindowId as MuxWindowId;
-use mux::{Mux, MuxNotification};
+use mux::{InputBroadcast, Mux, MuxNotification};
 use mux_lua::MuxPane;
 use smol::channel::Sender;
 use smol::Timer;
 use std::cell::{RefCell, RefMut};
 use std::collections::{HashMap, LinkedList};
+use std::convert::TryFrom;
 use std::ops::Add;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use termwiz::hyperlink::Hyperlink;
+use termwiz::hyperlink::{self, Hyperlink};
 use termwiz::surface::SequenceNo;
 use wezterm_dynamic::Value;
 use wezterm_font::units::PixelLength;
 use wezterm_font::FontConfiguration;
-use wezterm_term::color::ColorPalette;
+use wezterm_term::color::{ColorPalette, SrgbaTuple};
 use wezterm_term::input::LastMouseClick;
 use wezterm_term::{Alert, Progress, StableRowIndex, TerminalConfiguration, TerminalSize};
 
@@ -79,20 +85,38 @@ pub mod modal;
 mod mouseevent;
 pub mod palette;
 pub mod paneselect;
+mod placement;
 mod prevcursor;
 pub mod render;
 pub mod resize;
+pub mod resizemode;
+mod scrollspeed;
 mod selection;
+mod smoothscroll;
 pub mod spawn;
+mod tabdrag;
+mod table_text;
 pub mod webgpu;
+mod zoom;
 use crate::spawn::SpawnWhere;
 use prevcursor::PrevCursorPos;
 
 const ATLAS_SIZE: usize = 128;
 
+/// Config fields that only affect how input is mapped to actions
+const INPUT_BINDING_FIELDS: &[&str] = &[
+    "keys",
+    "key_tables",
+    "leader",
+    "disable_default_key_bindings",
+    "mouse_bindings",
+    "disable_default_mouse_bindings",
+];
+
 lazy_static::lazy_static! {
     static ref WINDOW_CLASS: Mutex<String> = Mutex::new(wezterm_gui_subcommands::DEFAULT_WINDOW_CLASS.to_owned());
     static ref POSITION: Mutex<Option<GuiPosition>> = Mutex::new(None);
+    static ref GEOMETRY: Mutex<Option<GuiGeometry>> = Mutex::new(None);
     static ref RENDER_METRICS_CACHE: Mutex<Option<RenderMetricsCacheEntry>> = Mutex::new(None);
 }
 
@@ -211,6 +235,10 @@ pub fn set_window_position(pos: GuiPosition) {
     POSITION.lock().unwrap().replace(pos);
 }
 
+pub fn set_window_geometry(geometry: GuiGeometry) {
+    GEOMETRY.lock().unwrap().replace(geometry);
+}
+
 pub fn set_window_class(cls: &str) {
     *WINDOW_CLASS.lock().unwrap() = cls.to_owned();
 }
@@ -297,6 +325,23 @@ pub struct SemanticZoneCache {
     zones: Vec<StableRowIndex>,
 }
 
+/// What the scroll bar minimap was computed from; it is recomputed
+/// only when one of these changes
+#[derive(Clone, PartialEq)]
+struct MinimapKey {
+    pane_id: PaneId,
+    seqno: SequenceNo,
+    scrollback_top: StableRowIndex,
+    scrollback_rows: usize,
+    max_thumb_height: usize,
+    bucket_height: usize,
+}
+
+struct MinimapCache {
+    key: MinimapKey,
+    buckets: Vec<Option<SrgbaTuple>>,
+}
+
 pub struct OverlayState {
     pub pane: Arc<dyn Pane>,
     pub key_table_state: KeyTableState,
@@ -308,6 +353,11 @@ pub struct PaneState {
     /// Otherwise, the viewport is at the bottom of the
     /// scrollback.
     viewport: Option<StableRowIndex>,
+    /// If is_some(), the pane is displayed as two viewports: the
+    /// top holds this many rows, starting at `viewport`, and stays
+    /// put while the remaining rows at the bottom follow the live
+    /// output.
+    viewport_split: Option<usize>,
     selection: Selection,
     /// If is_some(), rather than display the actual tab
     /// contents, we're overlaying a little internal application
@@ -316,6 +366,11 @@ pub struct PaneState {
 
     bell_start: Option<Instant>,
     pub mouse_terminal_coords: Option<(ClickPosition, StableRowIndex)>,
+    /// The background set on this pane or its tab, if any
+    background: Option<PaneBackground>,
+    /// If is_some(), the viewport is being drawn part way between
+    /// its prior and current positions
+    scroll_animation: Option<smoothscroll::ScrollAnimation>,
 }
 
 /// Data used when synchronously formatting pane and window titles
@@ -328,6 +383,9 @@ pub struct TabInformation {
     pub active_pane: Option<PaneInformation>,
     pub window_id: MuxWindowId,
     pub tab_title: String,
+    /// True if a pane in the tab has a monitor that fired, and the
+    /// user hasn't seen that pane since
+    pub has_triggered_monitor: bool,
 }
 
 impl UserData for TabInformation {
@@ -357,6 +415,9 @@ impl UserData for TabInformation {
         });
         fields.add_field_method_get("window_id", |_, this| Ok(this.window_id));
         fields.add_field_method_get("tab_title", |_, this| Ok(this.tab_title.clone()));
+        fields.add_field_method_get("has_triggered_monitor", |_, this| {
+            Ok(this.has_triggered_monitor)
+        });
         fields.add_field_method_get("window_title", |_, this| {
             let mux = Mux::get();
             let window = mux.get_window(this.window_id).ok_or_else(|| {
@@ -374,6 +435,9 @@ pub struct PaneInformation {
     pub pane_index: usize,
     pub is_active: bool,
     pub is_zoomed: bool,
+    pub is_input_locked: bool,
+    pub is_broadcasting_input: bool,
+    pub is_recording: bool,
     pub has_unseen_output: bool,
     pub left: usize,
     pub top: usize,
@@ -392,6 +456,11 @@ impl UserData for PaneInformation {
         fields.add_field_method_get("pane_index", |_, this| Ok(this.pane_index));
         fields.add_field_method_get("is_active", |_, this| Ok(this.is_active));
         fields.add_field_method_get("is_zoomed", |_, this| Ok(this.is_zoomed));
+        fields.add_field_method_get("is_input_locked", |_, this| Ok(this.is_input_locked));
+        fields.add_field_method_get("is_broadcasting_input", |_, this| {
+            Ok(this.is_broadcasting_input)
+        });
+        fields.add_field_method_get("is_recording", |_, this| Ok(this.is_recording));
         fields.add_field_method_get("has_unseen_output", |_, this| Ok(this.has_unseen_output));
         fields.add_field_method_get("left", |_, this| Ok(this.left));
         fields.add_field_method_get("top", |_, this| Ok(this.top));
@@ -483,6 +552,9 @@ pub struct TermWindow {
     pub window: Option<Window>,
     pub config: ConfigHandle,
     pub config_overrides: wezterm_dynamic::Value,
+    /// The system appearance, which selects between the dark and
+    /// light variants of the color scheme and background opacity
+    appearance: Appearance,
     os_parameters: Option<parameters::Parameters>,
     /// When we most recently received keyboard focus
     pub focused: Option<Instant>,
@@ -503,6 +575,12 @@ pub struct TermWindow {
     /// If is_some, the LEADER modifier is active until the specified instant.
     leader_is_down: Option<std::time::Instant>,
     dead_key_status: DeadKeyStatus,
+    /// If is_some, the key debugger overlay is active and key
+    /// events are reported to it
+    key_debugger: Option<std::sync::mpsc::Sender<String>>,
+    /// The tab whose font scale is currently applied, when
+    /// `per_tab_font_size` is enabled
+    font_scale_tab: Option<TabId>,
     key_table_state: KeyTableState,
     show_tab_bar: bool,
     show_scroll_bar: bool,
@@ -517,13 +595,16 @@ pub struct TermWindow {
     last_mouse_coords: (usize, i64),
     window_drag_position: Option<MouseEvent>,
     is_window_dragging: bool,
+    tab_drag: Option<tabdrag::TabDrag>,
     current_mouse_event: Option<MouseEvent>,
+    wheel_scroll: scrollspeed::WheelScroll,
     prev_cursor: PrevCursorPos,
     last_scroll_info: RenderableDimensions,
 
     tab_state: RefCell<HashMap<TabId, TabState>>,
     pane_state: RefCell<HashMap<PaneId, PaneState>>,
     semantic_zones: HashMap<PaneId, SemanticZoneCache>,
+    minimap: Option<MinimapCache>,
 
     window_background: Vec<LoadedBackgroundLayer>,
 
@@ -565,14 +646,28 @@ pub struct TermWindow {
     event_states: HashMap<String, EventState>,
     pub current_event: Option<Value>,
     has_animation: RefCell<Option<Instant>>,
+    zoom_indicator: RefCell<zoom::ZoomIndicator>,
+    /// The text cursor last reported to the input method, along with
+    /// the dpi at the time
+    last_text_cursor: Option<(Rect, usize)>,
+    /// The key table stacks as of the last `key-table-stack-changed`
+    last_key_table_stack: KeyTableStackVersion,
     /// We use this to attempt to do something reasonable
     /// if we run out of texture space
     allow_images: AllowImage,
     scheduled_animation: RefCell<Option<Instant>>,
+    /// What the frame that is on screen was drawn from, other than
+    /// its quads; None if there is no such frame
+    presented_frame: Option<FrameInputs>,
 
     created: Instant,
 
     pub last_frame_duration: Duration,
+    last_paint: Instant,
+    /// When there was last input, or output in a visible pane;
+    /// see `idle_max_fps`
+    last_activity: Instant,
+    idle_repaint_scheduled: bool,
     last_fps_check_time: Instant,
     num_frames: usize,
     pub fps: f32,
@@ -588,6 +683,9 @@ pub struct TermWindow {
 
     /// Toast notification: (start_time, message)
     toast: Option<(Instant, String)>,
+
+    /// Whether the render statistics HUD is shown
+    show_render_hud: bool,
 }
 
 impl TermWindow {
@@ -652,12 +750,17 @@ impl TermWindow {
         self.focused = if focused { Some(Instant::now()) } else { None };
         self.quad_generation += 1;
         self.load_os_parameters();
+        if let Some(fe) = crate::frontend::try_front_end() {
+            fe.window_focus_changed(self.mux_window_id, focused);
+        }
 
         if self.focused.is_none() {
             self.last_mouse_click = None;
             self.current_mouse_buttons.clear();
             self.current_mouse_capture = None;
             self.is_click_to_focus_window = false;
+            // The leader belongs to the window in which it was pressed
+            self.leader_is_down = None;
 
             for state in self.pane_state.borrow_mut().values_mut() {
                 state.mouse_terminal_coords.take();
@@ -708,7 +811,13 @@ impl TermWindow {
 
 impl TermWindow {
     pub async fn new_window(mux_window_id: MuxWindowId) -> anyhow::Result<()> {
-        let config = configuration();
+        let appearance = Connection::get().unwrap().get_appearance();
+        let mux = Mux::get();
+        let config = configuration().with_appearance(appearance.is_dark());
+        let config = match mux.get_window(mux_window_id) {
+            Some(window) => config.with_workspace(window.get_workspace()),
+            None => config,
+        };
         let dpi = config.dpi.unwrap_or_else(|| ::window::default_dpi()) as usize;
         let fontconfig = Rc::new(FontConfiguration::new(Some(config.clone()), dpi)?);
         let persisted_font_scale = resize::load_persisted_font_scale(&config);
@@ -716,7 +825,6 @@ impl TermWindow {
             fontconfig.change_scaling(font_scale, dpi);
         }
 
-        let mux = Mux::get();
         let size = match mux.get_active_tab_for_window(mux_window_id) {
             Some(tab) => tab.get_size(),
             None => {
@@ -733,7 +841,9 @@ impl TermWindow {
 
         // Initially we have only a single tab, so take that into account
         // for the tab bar state.
-        let show_tab_bar = config.enable_tab_bar && !config.hide_tab_bar_if_only_one_tab;
+        let show_tab_bar = config.enable_tab_bar
+            && !config.hide_tab_bar_if_only_one_tab
+            && !config.uses_native_macos_tabs();
         let tab_bar_height = if show_tab_bar {
             Self::tab_bar_pixel_height_impl(&config, &fontconfig, &render_metrics)? as usize
         } else {
@@ -810,6 +920,9 @@ impl TermWindow {
         let myself = Self {
             created: Instant::now(),
             connection_name,
+            last_paint: Instant::now(),
+            last_activity: Instant::now(),
+            idle_repaint_scheduled: false,
             last_fps_check_time: Instant::now(),
             num_frames: 0,
             last_frame_duration: Duration::ZERO,
@@ -822,6 +935,7 @@ impl TermWindow {
             window_background,
             config: config.clone(),
             config_overrides: wezterm_dynamic::Value::default(),
+            appearance,
             palette: None,
             focused: None,
             mux_window_id,
@@ -838,6 +952,8 @@ impl TermWindow {
             input_map: InputMap::new(&config),
             leader_is_down: None,
             dead_key_status: DeadKeyStatus::None,
+            key_debugger: None,
+            font_scale_tab: None,
             show_tab_bar,
             show_scroll_bar: config.enable_scroll_bar,
             tab_bar: TabBarState::default(),
@@ -847,7 +963,9 @@ impl TermWindow {
             last_mouse_coords: (0, -1),
             window_drag_position: None,
             is_window_dragging: false,
+            tab_drag: None,
             current_mouse_event: None,
+            wheel_scroll: Default::default(),
             current_modifier_and_leds: Default::default(),
             prev_cursor: PrevCursorPos::new(),
             last_scroll_info: RenderableDimensions::default(),
@@ -909,9 +1027,14 @@ impl TermWindow {
             event_states: HashMap::new(),
             current_event: None,
             has_animation: RefCell::new(None),
+            zoom_indicator: RefCell::new(Default::default()),
+            last_text_cursor: None,
+            last_key_table_stack: KeyTableStackVersion::default(),
             scheduled_animation: RefCell::new(None),
+            presented_frame: None,
             allow_images: AllowImage::Yes,
             semantic_zones: HashMap::new(),
+            minimap: None,
             ui_items: vec![],
             dragging: None,
             split_drag_state: None,
@@ -921,6 +1044,7 @@ impl TermWindow {
             modal: RefCell::new(None),
             opengl_info: None,
             toast: None,
+            show_render_hud: false,
             live_resizing: false,
         };
 
@@ -930,6 +1054,20 @@ impl TermWindow {
         let mut x = None;
         let mut y = None;
         let mut origin = GeometryOrigin::default();
+        let mut width = Dimension::Pixels(dimensions.pixel_width as f32);
+        let mut height = Dimension::Pixels(dimensions.pixel_height as f32);
+
+        let size = GEOMETRY
+            .lock()
+            .unwrap()
+            .take()
+            .or_else(|| config.initial_geometry.clone());
+        let explicit_size = size.is_some();
+        if let Some(size) = size {
+            width = size.width;
+            height = size.height;
+            origin = size.origin;
+        }
 
         if let Some(position) = mux
             .get_window(mux_window_id)
@@ -941,13 +1079,40 @@ impl TermWindow {
             origin = position.origin;
         }
 
-        let geometry = RequestedWindowGeometry {
-            width: Dimension::Pixels(dimensions.pixel_width as f32),
-            height: Dimension::Pixels(dimensions.pixel_height as f32),
+        let mut geometry = RequestedWindowGeometry {
+            width,
+            height,
             x,
             y,
             origin,
+            explicit_size,
+            persistence_key: None,
         };
+        let workspace = mux
+            .get_window(mux_window_id)
+            .map(|window| window.get_workspace().to_string())
+            .unwrap_or_else(|| mux.active_workspace());
+        if geometry.x.is_none() && !config.window_placement_rules.is_empty() {
+            if let Some(placed) = placement::geometry_for_rules(
+                &config.window_placement_rules,
+                &workspace,
+                width,
+                height,
+            ) {
+                geometry = placed;
+            }
+        }
+        if config.remember_window_geometry && !crate::dropdown::is_dropdown_window(mux_window_id) {
+            geometry.persistence_key = Some(format!("{}/{}", get_window_class(), workspace));
+        } else if geometry.x.is_none() {
+            if let Some(placed) = placement::geometry_for_spawn_window_on(
+                &config.spawn_window_on,
+                geometry.width,
+                geometry.height,
+            ) {
+                geometry = placed;
+            }
+        }
         log::trace!("{:?}", geometry);
 
         let window = Window::new_window(
@@ -968,11 +1133,12 @@ impl TermWindow {
 
         Self::apply_icon(&window)?;
 
-        let config_subscription = config::subscribe_to_config_reload({
+        let config_subscription = config::subscribe_to_config_reload_with_delta({
             let window = window.clone();
-            move || {
-                window.notify(TermWindowNotif::Apply(Box::new(|tw| {
-                    tw.config_was_reloaded()
+            move |delta| {
+                let delta = delta.clone();
+                window.notify(TermWindowNotif::Apply(Box::new(move |tw| {
+                    tw.config_was_reloaded_with_delta(&delta)
                 })));
                 true
             }
@@ -1017,7 +1183,14 @@ impl TermWindow {
                 myself.created(RenderContext::WebGpu(Rc::clone(&webgpu)))?;
             }
             myself.load_os_parameters();
-            window.show();
+            if let Some(parent) = front_end().take_native_tab_parent(mux_window_id) {
+                parent.add_native_tab(&window);
+            }
+            if crate::dropdown::is_dropdown_window(mux_window_id) {
+                crate::dropdown::show(&window, &config);
+            } else {
+                window.show();
+            }
             myself.subscribe_to_pane_updates();
             myself.emit_window_event("window-config-reloaded", None);
             myself.emit_status_event();
@@ -1035,6 +1208,12 @@ impl TermWindow {
         window: &Window,
     ) -> anyhow::Result<bool> {
         log::trace!("{event:?}");
+        if !matches!(
+            event,
+            WindowEvent::NeedRepaint | WindowEvent::Exposed | WindowEvent::Notification(_)
+        ) {
+            self.last_activity = Instant::now();
+        }
         match event {
             WindowEvent::Destroyed => {
                 self.window.take();
@@ -1054,6 +1233,7 @@ impl TermWindow {
             }
             WindowEvent::AppearanceChanged(appearance) => {
                 log::debug!("Appearance is now {:?}", appearance);
+                self.appearance = appearance;
                 // This is a bit fugly; we get per-window notifications
                 // for appearance changes which successfully updates the
                 // per-window config, but we need to explicitly tell the
@@ -1092,6 +1272,7 @@ impl TermWindow {
                 window_state,
                 live_resizing,
             } => {
+                self.presented_frame.take();
                 self.resize(dimensions, window_state, window, live_resizing);
                 Ok(true)
             }
@@ -1135,10 +1316,16 @@ impl TermWindow {
                 window.invalidate();
                 Ok(true)
             }
+            WindowEvent::Exposed => {
+                self.presented_frame.take();
+                Ok(true)
+            }
             WindowEvent::NeedRepaint => {
                 if self.resizes_pending > 0 {
                     self.is_repaint_pending = true;
                     Ok(true)
+                } else if self.defer_idle_repaint(window) {
+                    Ok(true)
                 } else if self.webgpu.is_some() {
                     self.do_paint_webgpu()
                 } else {
@@ -1157,6 +1344,9 @@ impl TermWindow {
                     Some(pane) => pane,
                     None => return Ok(true),
                 };
+                if self.pane_input_locked(&pane, true) {
+                    return Ok(true);
+                }
                 pane.send_paste(text.as_str())?;
                 Ok(true)
             }
@@ -1165,6 +1355,9 @@ impl TermWindow {
                     Some(pane) => pane,
                     None => return Ok(true),
                 };
+                if self.pane_input_locked(&pane, true) {
+                    return Ok(true);
+                }
                 let urls = urls
                     .iter()
                     .map(|url| self.config.quote_dropped_files.escape(&url.to_string()))
@@ -1175,27 +1368,55 @@ impl TermWindow {
                 Ok(true)
             }
             WindowEvent::DroppedFile(paths) => {
-                let pane = match self.get_active_pane_or_overlay() {
-                    Some(pane) => pane,
-                    None => return Ok(true),
-                };
-                let paths = paths
-                    .iter()
-                    .map(|path| {
-                        self.config
-                            .quote_dropped_files
-                            .escape(&path.to_string_lossy())
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" ")
-                    + " ";
-                pane.send_paste(&paths)?;
+                self.drop_files(paths);
                 Ok(true)
             }
             WindowEvent::DraggedFile(_) => Ok(true),
         }
     }
 
+    /// The frame rate that painting is currently limited to: `max_fps`,
+    /// or `idle_max_fps` if nothing has happened for a while
+    pub fn effective_max_fps(&self) -> u64 {
+        let idle_fps = self.config.idle_max_fps;
+        let idle_after = Duration::from_secs(self.config.idle_fps_after_secs);
+        if idle_fps > 0
+            && idle_fps < self.config.max_fps
+            && self.last_activity.elapsed() >= idle_after
+        {
+            idle_fps
+        } else {
+            self.config.max_fps.max(1)
+        }
+    }
+
+    /// Returns true if painting now would exceed the idle frame rate,
+    /// in which case the repaint is rescheduled for when it is due
+    fn defer_idle_repaint(&mut self, window: &Window) -> bool {
+        let fps = self.effective_max_fps();
+        if fps >= self.config.max_fps {
+            return false;
+        }
+        let due = self.last_paint + Duration::from_millis(1000 / fps);
+        if due <= Instant::now() {
+            return false;
+        }
+        if !self.idle_repaint_scheduled {
+            self.idle_repaint_scheduled = true;
+            let window = window.clone();
+            promise::spawn::spawn(async move {
+                Timer::at(due).await;
+                let win = window.clone();
+                window.notify(TermWindowNotif::Apply(Box::new(move |tw| {
+                    tw.idle_repaint_scheduled = false;
+                    win.invalidate();
+                })));
+            })
+            .detach();
+        }
+        true
+    }
+
     fn do_paint(&mut self, window: &Window) -> bool {
         let gl = match self.gl.as_ref() {
             Some(gl) => gl,
@@ -1209,17 +1430,24 @@ impl TermWindow {
             return false;
         }
 
-        let mut frame = glium::Frame::new(
-            Rc::clone(&gl),
-            (
-                self.dimensions.pixel_width as u32,
-                self.dimensions.pixel_height as u32,
-            ),
-        );
-        if let Err(err) = self.paint_impl(&mut RenderFrame::Glium(&mut frame)) {
+        let gl = Rc::clone(gl);
+        let mut finished = true;
+        let result = self.paint_impl(|tw| {
+            let mut frame = glium::Frame::new(
+                gl,
+                (
+                    tw.dimensions.pixel_width as u32,
+                    tw.dimensions.pixel_height as u32,
+                ),
+            );
+            let result = tw.call_draw(&mut RenderFrame::Glium(&mut frame));
+            finished = window.finish_frame(frame).is_ok();
+            result
+        });
+        if let Err(err) = result {
             log::error!("paint_impl failed: {:#}", err);
         }
-        window.finish_frame(frame).is_ok()
+        finished
     }
 
     fn do_paint_webgpu(&mut self) -> anyhow::Result<bool> {
@@ -1247,7 +1475,7 @@ impl TermWindow {
     }
 
     fn do_paint_webgpu_impl(&mut self) -> anyhow::Result<bool> {
-        self.paint_impl(&mut RenderFrame::WebGpu)?;
+        self.paint_impl(|tw| tw.call_draw(&mut RenderFrame::WebGpu))?;
         Ok(true)
     }
 
@@ -1382,7 +1610,9 @@ impl TermWindow {
 
                     match self.config.audible_bell {
                         AudibleBell::SystemBeep => {
-                            Connection::get().expect("on main thread").beep();
+                            if !crate::sound::play_event_sound(&self.config, SoundEvent::Bell) {
+                                Connection::get().expect("on main thread").beep();
+                            }
                         }
                         AudibleBell::Disabled => {}
                     }
@@ -1395,13 +1625,16 @@ impl TermWindow {
                     window.invalidate();
                 }
                 MuxNotification::Alert {
-                    alert: Alert::ToastNotification { .. },
+                    alert: Alert::ToastNotification { .. } | Alert::CommandComplete { .. },
                     ..
                 } => {}
                 MuxNotification::TabAddedToWindow {
                     window_id: _,
                     tab_id,
                 } => {
+                    if self.move_extra_tab_to_native_tab(tab_id) {
+                        return Ok(());
+                    }
                     let mux = Mux::get();
                     let mut size = self.terminal_size;
                     if let Some(tab) = mux.get_tab(tab_id) {
@@ -1456,13 +1689,23 @@ impl TermWindow {
                 MuxNotification::TabTitleChanged { .. } => {
                     self.update_title_post_status();
                 }
+                MuxNotification::WindowWorkspaceChanged(_) => {
+                    self.apply_workspace_colors();
+                }
                 MuxNotification::PaneAdded(_)
                 | MuxNotification::WorkspaceRenamed { .. }
                 | MuxNotification::PaneRemoved(_)
-                | MuxNotification::WindowWorkspaceChanged(_)
                 | MuxNotification::ActiveWorkspaceChanged(_)
                 | MuxNotification::Empty
+                | MuxNotification::ClientAttached(_)
+                | MuxNotification::ClientDetached(_)
+                | MuxNotification::PaneProcessExited { .. }
+                | MuxNotification::ClipboardSynced { .. }
+                | MuxNotification::TlsCertificateExpiring { .. }
                 | MuxNotification::WindowCreated(_) => {}
+                MuxNotification::PaneMonitorTriggered { pane_id, monitor } => {
+                    self.pane_monitor_triggered(pane_id, monitor);
+                }
             },
             TermWindowNotif::EmitStatusUpdate => {
                 self.emit_status_event();
@@ -1496,6 +1739,7 @@ impl TermWindow {
                     }
                 };
                 self.update_title();
+                self.apply_workspace_colors();
                 window.invalidate();
             }
             TermWindowNotif::SetInnerSize { width, height } => {
@@ -1582,6 +1826,7 @@ impl TermWindow {
     fn mux_pane_output_event(&mut self, pane_id: PaneId) {
         metrics::histogram!("mux.pane_output_event.rate").record(1.);
         if self.is_pane_visible(pane_id) {
+            self.last_activity = Instant::now();
             if let Some(ref win) = self.window {
                 win.invalidate();
             }
@@ -1654,6 +1899,15 @@ impl TermWindow {
                 dead.store(true, Ordering::Relaxed);
                 return false;
             }
+            MuxNotification::PaneMonitorTriggered { pane_id, .. } => {
+                let mux = Mux::get();
+                match mux.resolve_pane_id(pane_id) {
+                    Some((_domain, window_id, _tab_id)) if window_id == mux_window_id => {
+                        // fall through
+                    }
+                    _ => return true,
+                }
+            }
             MuxNotification::TabResized(tab_id)
             | MuxNotification::TabTitleChanged { tab_id, .. } => {
                 let mux = Mux::get();
@@ -1664,7 +1918,7 @@ impl TermWindow {
                 }
             }
             MuxNotification::Alert {
-                alert: Alert::ToastNotification { .. },
+                alert: Alert::ToastNotification { .. } | Alert::CommandComplete { .. },
                 ..
             }
             | MuxNotification::AssignClipboard { .. }
@@ -1673,7 +1927,16 @@ impl TermWindow {
             | MuxNotification::ActiveWorkspaceChanged(_)
             | MuxNotification::WorkspaceRenamed { .. }
             | MuxNotification::Empty
-            | MuxNotification::WindowWorkspaceChanged(_) => return true,
+            | MuxNotification::ClientAttached(_)
+            | MuxNotification::ClientDetached(_)
+            | MuxNotification::PaneProcessExited { .. }
+            | MuxNotification::ClipboardSynced { .. }
+            | MuxNotification::TlsCertificateExpiring { .. } => return true,
+            MuxNotification::WindowWorkspaceChanged(window_id) => {
+                if window_id != mux_window_id {
+                    return true;
+                }
+            }
             MuxNotification::Alert {
                 alert: Alert::PaletteChanged { .. },
                 ..
@@ -1828,7 +2091,11 @@ impl TermWindow {
         let viewport = self
             .get_viewport(pane.pane_id())
             .unwrap_or(dims.physical_top);
-        let visible_range = viewport..viewport + dims.viewport_rows as StableRowIndex;
+        let visible_range = if self.viewport_split_rows(pane.pane_id(), &dims).is_some() {
+            viewport..dims.physical_top + dims.viewport_rows as StableRowIndex
+        } else {
+            viewport..viewport + dims.viewport_rows as StableRowIndex
+        };
         let seqno = self.selection(pane.pane_id()).seqno;
         let dirty = pane.get_changed_since(visible_range, seqno);
 
@@ -1866,6 +2133,9 @@ impl TermWindow {
     /// Decide whether the tab bar should be visible based on tab count,
     /// fullscreen state, and config.
     fn should_show_tab_bar(&self, num_tabs: usize) -> bool {
+        if self.config.uses_native_macos_tabs() {
+            return false;
+        }
         let is_full_screen = self.window_state.contains(WindowState::FULL_SCREEN);
         if is_full_screen {
             // Always show tab bar in fullscreen mode to display the right status (time)
@@ -1880,7 +2150,7 @@ impl TermWindow {
     fn palette(&mut self) -> &ColorPalette {
         if self.palette.is_none() {
             self.palette
-                .replace(config::TermConfig::new().color_palette());
+                .replace(config::TermConfig::with_config(self.config.clone()).color_palette());
         }
         self.palette.as_ref().unwrap()
     }
@@ -1896,20 +2166,37 @@ impl TermWindow {
         self.config_was_reloaded_impl();
     }
 
-    fn config_was_reloaded_silently(&mut self) {
-        if self.live_resizing {
-            return;
+    /// Handles a reload of the configuration file.  When only input
+    /// bindings changed, the fonts, glyph and shaping caches are left
+    /// as they are.
+    fn config_was_reloaded_with_delta(&mut self, delta: &ConfigDelta) {
+        if !delta.is_empty() && delta.only_changed(INPUT_BINDING_FIELDS) && !self.live_resizing {
+            self.input_bindings_were_reloaded();
+        } else {
+            self.config_was_reloaded();
         }
-        self.config_was_reloaded_impl();
     }
 
-    fn config_was_reloaded_impl(&mut self) {
-        log::debug!(
-            "config was reloaded, overrides: {:?}",
-            self.config_overrides
-        );
+    fn input_bindings_were_reloaded(&mut self) {
+        log::debug!("input bindings were reloaded");
+        self.config = self.effective_config();
+        // The active tables may no longer exist, or may have different
+        // bindings, so start over from the default bindings, as with
+        // ClearKeyTableStack
         self.key_table_state.clear_stack();
-        self.connection_name = Connection::get().unwrap().name();
+        self.input_map = InputMap::new(&self.config);
+        self.leader_is_down = None;
+        self.update_title();
+        if let Some(window) = self.window.as_ref() {
+            window.invalidate();
+        }
+        self.emit_window_event("window-config-reloaded", None);
+    }
+
+    /// The global configuration with this window's overrides and the
+    /// settings for the current system appearance and for the
+    /// workspace that it shows applied
+    fn effective_config(&self) -> ConfigHandle {
         let config = if matches!(&self.config_overrides, Value::Null)
             || matches!(&self.config_overrides, Value::Object(obj) if obj.is_empty())
         {
@@ -1927,8 +2214,39 @@ impl TermWindow {
                 }
             }
         };
+        let config = config.with_appearance(self.appearance.is_dark());
+        match Mux::get().get_window(self.mux_window_id) {
+            Some(window) => config.with_workspace(window.get_workspace()),
+            None => config,
+        }
+    }
+
+    /// Reloads the configuration if the workspace now shown by this
+    /// window has different colors from the previous one
+    fn apply_workspace_colors(&mut self) {
+        if self.effective_config().resolved_palette != self.config.resolved_palette {
+            self.config_was_reloaded();
+        }
+    }
+
+    fn config_was_reloaded_silently(&mut self) {
+        if self.live_resizing {
+            return;
+        }
+        self.config_was_reloaded_impl();
+    }
+
+    fn config_was_reloaded_impl(&mut self) {
+        log::debug!(
+            "config was reloaded, overrides: {:?}",
+            self.config_overrides
+        );
+        self.key_table_state.clear_stack();
+        self.connection_name = Connection::get().unwrap().name();
+        let config = self.effective_config();
         self.config = config.clone();
         self.palette.take();
+        self.minimap.take();
 
         let mux = Mux::get();
         let window = match mux.get_window(self.mux_window_id) {
@@ -2078,6 +2396,7 @@ impl TermWindow {
     fn update_title(&mut self) {
         self.schedule_status_update();
         self.update_title_impl();
+        self.check_key_table_stack_changed();
     }
 
     fn window_contains_pane(&mut self, pane_id: PaneId) -> bool {
@@ -2091,6 +2410,91 @@ impl TermWindow {
         return window_id == self.mux_window_id;
     }
 
+    /// Called when a pane watched with `TogglePaneMonitor` sees the
+    /// activity or silence that it was watched for.  The tab is
+    /// flagged until the pane is seen, the `pane-monitor-triggered`
+    /// event is emitted and, unless that returns false, a
+    /// notification is shown if `pane_monitor_notifications` is set.
+    fn pane_monitor_triggered(&mut self, pane_id: PaneId, monitor: PaneMonitor) {
+        if !self.window_contains_pane(pane_id) {
+            return;
+        }
+        let mux = Mux::get();
+        if self.focused.is_some()
+            && self
+                .get_panes_to_render()
+                .iter()
+                .any(|pos| pos.pane.pane_id() == pane_id)
+        {
+            // The user is already looking at it
+            mux.acknowledge_pane_monitor(pane_id);
+            return;
+        }
+
+        self.update_title_post_status();
+        if let Some(window) = self.window.as_ref() {
+            window.invalidate();
+        }
+
+        async fn dispatch_pane_monitor_triggered(
+            lua: Option<Rc<mlua::Lua>>,
+            window: GuiWin,
+            pane: MuxPane,
+            monitor: PaneMonitor,
+        ) -> anyhow::Result<()> {
+            let default_action = match lua {
+                Some(lua) => {
+                    let args = lua.pack_multi((window, pane, monitor))?;
+                    config::lua::emit_event(&lua, ("pane-monitor-triggered".to_string(), args))
+                        .await
+                        .map_err(|e| {
+                            log::error!("while processing pane-monitor-triggered event: {:#}", e);
+                            e
+                        })?
+                }
+                None => true,
+            };
+            if default_action && configuration().pane_monitor_notifications {
+                let title = match monitor {
+                    PaneMonitor::Activity => "Activity in pane".to_string(),
+                    PaneMonitor::Silence(seconds) => format!("Pane silent for {seconds}s"),
+                };
+                let message = Mux::get()
+                    .get_pane(pane.0)
+                    .map(|pane| pane.get_title())
+                    .unwrap_or_default();
+                wezterm_toast_notification::ToastNotification {
+                    title,
+                    message,
+                    url: None,
+                    timeout: None,
+                    activation_token: Some(pane.0.to_string()),
+                }
+                .show();
+            }
+            Ok(())
+        }
+
+        let window = GuiWin::new(self);
+        let pane = MuxPane(pane_id);
+        promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+            dispatch_pane_monitor_triggered(lua, window, pane, monitor)
+        }))
+        .detach();
+    }
+
+    /// Clears the triggered state of the monitors of the panes that
+    /// the user can currently see
+    pub fn acknowledge_pane_monitors(&self) {
+        if self.focused.is_none() {
+            return;
+        }
+        let mux = Mux::get();
+        for pos in self.get_panes_to_render() {
+            mux.acknowledge_pane_monitor(pos.pane.pane_id());
+        }
+    }
+
     fn emit_user_var_event(&mut self, pane_id: PaneId, name: String, value: String) {
         if !self.window_contains_pane(pane_id) {
             return;
@@ -2151,6 +2555,10 @@ impl TermWindow {
         let active_tab = tabs.iter().find(|t| t.is_active).cloned();
         let active_pane = panes.iter().find(|p| p.is_active).cloned();
 
+        if let Some(tab) = &active_tab {
+            self.apply_tab_font_scale(tab.tab_id);
+        }
+
         let border = self.get_os_border();
         let tab_bar_height = self.tab_bar_pixel_height().unwrap_or(0.);
         let tab_bar_y = if self.config.tab_bar_at_bottom {
@@ -2170,6 +2578,11 @@ impl TermWindow {
             None => false,
         };
 
+        let left_status = if self.leader_is_active() {
+            format!("{}{}", self.config.leader_indicator, self.left_status)
+        } else {
+            self.left_status.clone()
+        };
         let new_tab_bar = TabBarState::new(
             self.dimensions.pixel_width / self.render_metrics.cell_size.width as usize,
             if hovering_in_tab_bar {
@@ -2181,7 +2594,7 @@ impl TermWindow {
             &panes,
             self.config.resolved_palette.tab_bar.as_ref(),
             &self.config,
-            &self.left_status,
+            &left_status,
             &self.right_status,
         );
         if new_tab_bar != self.tab_bar {
@@ -2236,16 +2649,20 @@ impl TermWindow {
             Some(title) => title,
             None => {
                 if let (Some(pos), Some(tab)) = (active_pane, active_tab) {
+                    let flags = format!(
+                        "{}{}{}",
+                        if pos.is_zoomed { "[Z] " } else { "" },
+                        if pos.is_input_locked { "[RO] " } else { "" },
+                        if pos.is_broadcasting_input {
+                            "[B] "
+                        } else {
+                            ""
+                        }
+                    );
                     if num_tabs == 1 {
-                        format!("{}{}", if pos.is_zoomed { "[Z] " } else { "" }, pos.title)
+                        format!("{flags}{}", pos.title)
                     } else {
-                        format!(
-                            "{}[{}/{}] {}",
-                            if pos.is_zoomed { "[Z] " } else { "" },
-                            tab.tab_index + 1,
-                            num_tabs,
-                            pos.title
-                        )
+                        format!("{flags}[{}/{}] {}", tab.tab_index + 1, num_tabs, pos.title)
                     }
                 } else {
                     "".to_string()
@@ -2281,7 +2698,13 @@ impl TermWindow {
         self.schedule_next_status_update();
     }
 
+    /// Arranges for `update-status` to be emitted after
+    /// `status_update_interval`; an interval of 0 disables the
+    /// periodic updates
     fn schedule_next_status_update(&mut self) {
+        if self.config.status_update_interval == 0 {
+            return;
+        }
         if let Some(window) = self.window.as_ref() {
             let now = Instant::now();
             if self.last_status_call <= now {
@@ -2309,19 +2732,29 @@ impl TermWindow {
                 0.0
             };
             let (padding_left, padding_top) = self.padding_left_top();
+            let border = self.get_os_border();
 
             let r = Rect::new(
                 Point::new(
                     (((cursor.x + pos.left) as isize).max(0) * self.render_metrics.cell_size.width)
-                        .add(padding_left as isize),
+                        .add(padding_left as isize)
+                        .add(border.left.get() as isize),
                     ((cursor.y + pos.top as isize - top).max(0)
                         * self.render_metrics.cell_size.height)
                         .add(tab_bar_height as isize)
-                        .add(padding_top as isize),
+                        .add(padding_top as isize)
+                        .add(border.top.get() as isize),
                 ),
                 self.render_metrics.cell_size,
             );
-            win.set_text_cursor_position(r);
+            // The input method places its candidate window relative to
+            // this, in points, so a dpi change moves it even when the
+            // pixel position stays the same
+            let current = Some((r, self.dimensions.dpi));
+            if self.last_text_cursor != current {
+                self.last_text_cursor = current;
+                win.set_text_cursor_position(r);
+            }
         }
     }
 
@@ -2490,6 +2923,78 @@ impl TermWindow {
         promise::spawn::spawn(future).detach();
     }
 
+    fn show_workspace_search(&mut self, pattern: &Pattern) {
+        let mux = Mux::get();
+        let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab,
+            None => return,
+        };
+        let pane = match self.get_active_pane_no_overlay() {
+            Some(pane) => pane,
+            None => return,
+        };
+        let workspace = match mux.get_window(self.mux_window_id) {
+            Some(window) => window.get_workspace().to_string(),
+            None => return,
+        };
+
+        let pattern = match self.resolve_search_pattern(pattern.clone(), &pane) {
+            // Text typed at the prompt is matched without regard to case
+            MuxPattern::CaseSensitiveString(s)
+                if s.is_empty() && *pattern == Pattern::CurrentSelectionOrEmptyString =>
+            {
+                MuxPattern::CaseInSensitiveString(s)
+            }
+            pattern => pattern,
+        };
+
+        let mut pane_ids = vec![];
+        for window_id in mux.iter_windows_in_workspace(&workspace) {
+            if let Some(window) = mux.get_window(window_id) {
+                for tab in window.iter() {
+                    for pos in tab.iter_panes_ignoring_zoom() {
+                        pane_ids.push(pos.pane.pane_id());
+                    }
+                }
+            }
+        }
+
+        let window = self.window.clone().unwrap();
+        let (overlay, future) = start_overlay(self, &tab, move |_tab_id, term| {
+            crate::overlay::workspace_search::workspace_search(term, pattern, pane_ids, window)
+        });
+        self.assign_overlay(tab.tab_id(), overlay);
+        promise::spawn::spawn(future).detach();
+    }
+
+    fn show_json_viewer(&mut self, pane: &Arc<dyn Pane>) {
+        let mux = Mux::get();
+        let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab,
+            None => return,
+        };
+
+        let mut text = self.selection_text(pane);
+        if text.trim().is_empty() {
+            text = mux::pane_text::get_last_command_output(&**pane).unwrap_or_default();
+        }
+
+        let value: serde_json::Value = match serde_json::from_str(text.trim()) {
+            Ok(value) => value,
+            Err(err) => {
+                self.show_toast(format!("Not valid JSON: {err}"));
+                return;
+            }
+        };
+
+        let gui_win = GuiWin::new(self);
+        let (overlay, future) = start_overlay(self, &tab, move |_tab_id, term| {
+            crate::overlay::json_viewer::json_viewer(term, value, gui_win)
+        });
+        self.assign_overlay(tab.tab_id(), overlay);
+        promise::spawn::spawn(future).detach();
+    }
+
     fn show_prompt_input_line(&mut self, args: &PromptInputLine) {
         let mux = Mux::get();
         let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
@@ -2514,6 +3019,36 @@ impl TermWindow {
         promise::spawn::spawn(future).detach();
     }
 
+    /// Shows the prompts of a connection as an overlay on the active tab
+    pub fn show_connection_prompt(&mut self, request: PromptRequest) {
+        let PromptRequest {
+            instructions,
+            fields,
+            mut respond,
+        } = request;
+        let mux = Mux::get();
+        let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab,
+            None => {
+                respond.result(Err(anyhow!("window has no active tab")));
+                return;
+            }
+        };
+
+        let (overlay, future) = start_overlay(self, &tab, move |_tab_id, term| {
+            crate::overlay::connection_prompt::show_connection_prompt_overlay(
+                term,
+                instructions,
+                fields,
+            )
+        });
+        self.assign_overlay(tab.tab_id(), overlay);
+        promise::spawn::spawn(async move {
+            respond.result(future.await);
+        })
+        .detach();
+    }
+
     fn show_confirmation(&mut self, args: &Confirmation) {
         let mux = Mux::get();
         let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
@@ -2538,6 +3073,11 @@ impl TermWindow {
         promise::spawn::spawn(future).detach();
     }
 
+    /// Explains which fonts are used to render `text`
+    pub fn font_report(&self, text: &str) -> anyhow::Result<String> {
+        wezterm_font::report::font_report(&self.fonts, text)
+    }
+
     fn show_debug_overlay(&mut self) {
         let mux = Mux::get();
         let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
@@ -2557,6 +3097,75 @@ impl TermWindow {
         promise::spawn::spawn(future).detach();
     }
 
+    fn show_key_debugger(&mut self) {
+        let mux = Mux::get();
+        let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab,
+            None => return,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.key_debugger.replace(tx);
+
+        let config = self.config.clone();
+        let (overlay, future) = start_overlay(self, &tab, move |_tab_id, term| {
+            crate::overlay::key_debugger::show_key_debugger(term, config, rx)
+        });
+        self.assign_overlay(tab.tab_id(), overlay);
+        promise::spawn::spawn(future).detach();
+    }
+
+    fn play_recording(&mut self, path: Option<&str>) {
+        let mux = Mux::get();
+        let tab_id = match mux.get_active_tab_for_window(self.mux_window_id) {
+            Some(tab) => tab.tab_id(),
+            None => return,
+        };
+        let window = match self.window.as_ref() {
+            Some(window) => window.clone(),
+            None => return,
+        };
+
+        let path = path.map(|path| match path.strip_prefix("~/") {
+            Some(rest) => config::HOME_DIR.join(rest),
+            None => PathBuf::from(path),
+        });
+
+        // Finding and parsing the recording touches the filesystem and
+        // can take a while for a long recording, so keep it off the
+        // GUI thread
+        promise::spawn::spawn(async move {
+            let result = promise::spawn::spawn_into_new_thread(move || {
+                let path = path
+                    .or_else(mux::recording::latest_recording)
+                    .ok_or_else(|| anyhow!("There are no recordings to play"))?;
+                mux::recording::read_cast(&path)
+            })
+            .await;
+
+            window.notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                let cast = match result {
+                    Ok(cast) => cast,
+                    Err(err) => {
+                        log::error!("Failed to play recording: {:#}", err);
+                        term_window.show_toast(format!("{:#}", err));
+                        return;
+                    }
+                };
+                let mux = Mux::get();
+                if let Some(tab) = mux.get_tab(tab_id) {
+                    let (overlay, future) =
+                        start_overlay(term_window, &tab, move |_tab_id, term| {
+                            crate::overlay::playback::play_recording(term, cast)
+                        });
+                    term_window.assign_overlay(tab_id, overlay);
+                    promise::spawn::spawn(future).detach();
+                }
+            })));
+        })
+        .detach();
+    }
+
     fn show_tab_navigator(&mut self) {
         let mux = Mux::get();
         let active_tab_idx = match mux.get_window(self.mux_window_id) {
@@ -2624,6 +3233,16 @@ impl TermWindow {
 
         let config = &self.config;
         let alphabet = args.alphabet.unwrap_or(config.launcher_alphabet.clone());
+        // Start listing any entries from Lua right away, so that they
+        // are ready sooner; the launcher shows them once they arrive
+        let augmented = if flags.contains(LauncherFlags::LAUNCH_MENU_ITEMS) {
+            Some(crate::overlay::launcher::augment_launch_menu(
+                GuiWin::new(self),
+                MuxPane(pane_id),
+            ))
+        } else {
+            None
+        };
 
         promise::spawn::spawn(async move {
             let args = LauncherArgs::new(
@@ -2637,6 +3256,10 @@ impl TermWindow {
                 &alphabet,
             )
             .await;
+            let args = match augmented {
+                Some(augmented) => args.with_augmented_launch_menu(augmented),
+                None => args,
+            };
 
             let win = window.clone();
             win.notify(TermWindowNotif::Apply(Box::new(move |term_window| {
@@ -2687,6 +3310,34 @@ impl TermWindow {
         &cache.zones
     }
 
+    fn get_minimap(
+        &mut self,
+        pane: &Arc<dyn Pane>,
+        palette: &ColorPalette,
+        max_thumb_height: usize,
+        bucket_height: usize,
+    ) -> &[Option<SrgbaTuple>] {
+        let dims = pane.get_dimensions();
+        let key = MinimapKey {
+            pane_id: pane.pane_id(),
+            seqno: pane.get_current_seqno(),
+            scrollback_top: dims.scrollback_top,
+            scrollback_rows: dims.scrollback_rows,
+            max_thumb_height,
+            bucket_height,
+        };
+        if self.minimap.as_ref().map(|cache| &cache.key) != Some(&key) {
+            self.minimap = Some(MinimapCache {
+                key,
+                buckets: ScrollHit::minimap(&**pane, palette, max_thumb_height, bucket_height),
+            });
+        }
+        self.minimap
+            .as_ref()
+            .map(|cache| cache.buckets.as_slice())
+            .unwrap_or(&[])
+    }
+
     fn scroll_to_prompt(&mut self, amount: isize, pane: &Arc<dyn Pane>) -> anyhow::Result<()> {
         // Exit peek mode when scroll_to_prompt leaves current viewport
         if pane.is_primary_peek() {
@@ -2716,11 +3367,12 @@ impl TermWindow {
 
     fn scroll_by_page(&mut self, amount: f64, pane: &Arc<dyn Pane>) -> anyhow::Result<()> {
         let dims = pane.get_dimensions();
-        let position = self
+        let old_top = self
             .get_viewport(pane.pane_id())
-            .unwrap_or(dims.physical_top) as f64
-            + (amount * dims.viewport_rows as f64);
+            .unwrap_or(dims.physical_top);
+        let position = old_top as f64 + (amount * dims.viewport_rows as f64);
         self.set_viewport(pane.pane_id(), Some(position as isize), dims);
+        self.animate_scroll(pane.pane_id(), old_top, dims);
         // Exit peek mode when scrolling to bottom
         if pane.is_primary_peek() && self.get_viewport(pane.pane_id()).is_none() {
             pane.set_primary_peek(false);
@@ -2752,12 +3404,13 @@ impl TermWindow {
         }
 
         let dims = pane.get_dimensions();
-        let position = self
+        let old_top = self
             .get_viewport(pane.pane_id())
-            .unwrap_or(dims.physical_top)
-            .saturating_add(amount);
+            .unwrap_or(dims.physical_top);
+        let position = old_top.saturating_add(amount);
 
         self.set_viewport(pane.pane_id(), Some(position), dims);
+        self.animate_scroll(pane.pane_id(), old_top, dims);
 
         // Scroll to bottom → exit peek, return to alt screen
         if pane.is_primary_peek() && self.get_viewport(pane.pane_id()).is_none() {
@@ -2770,6 +3423,32 @@ impl TermWindow {
         Ok(())
     }
 
+    /// With native macOS tabs, each tab is a mux window of its own.
+    /// Tabs that are added to this window by other means than the gui,
+    /// such as `kaku cli spawn` or a Lua `spawn_tab`, are moved into
+    /// a new window that joins this window's native tab group.
+    /// Returns true if `tab_id` was moved.
+    fn move_extra_tab_to_native_tab(&mut self, tab_id: TabId) -> bool {
+        if !self.config.uses_native_macos_tabs() {
+            return false;
+        }
+        let mux = Mux::get();
+        match mux.get_window(self.mux_window_id) {
+            Some(window) if window.len() > 1 => {}
+            _ => return false,
+        }
+        match mux.move_tab_to_window(tab_id, None, None) {
+            Ok(window_id) => {
+                front_end().add_as_native_tab(window_id, self.mux_window_id);
+                true
+            }
+            Err(err) => {
+                log::error!("moving tab {} to a native tab: {:#}", tab_id, err);
+                false
+            }
+        }
+    }
+
     fn move_tab_relative(&mut self, delta: isize) -> anyhow::Result<()> {
         let mux = Mux::get();
         let window = mux
@@ -2836,14 +3515,24 @@ impl TermWindow {
                     prevent_fallback: *prevent_fallback,
                 });
                 self.update_title();
+                if let Some(window) = self.window.as_ref() {
+                    // Shows or hides the hint of the resize_pane table
+                    window.invalidate();
+                }
             }
             PopKeyTable => {
                 self.key_table_state.pop();
                 self.update_title();
+                if let Some(window) = self.window.as_ref() {
+                    window.invalidate();
+                }
             }
             ClearKeyTableStack => {
                 self.key_table_state.clear_stack();
                 self.update_title();
+                if let Some(window) = self.window.as_ref() {
+                    window.invalidate();
+                }
             }
             Multiple(actions) => {
                 for a in actions {
@@ -2919,16 +3608,41 @@ impl TermWindow {
                 let window = self.window.clone().unwrap();
                 window.set_window_level(level.clone());
             }
+            MoveWindowToMonitor(monitor) => self.move_window_to_monitor(*monitor),
             CopyTo(dest) => {
                 let text = self.selection_text(pane);
-                self.copy_to_clipboard(*dest, text);
+                let register = pane
+                    .downcast_ref::<CopyOverlay>()
+                    .and_then(|copy| copy.take_register());
+                match register {
+                    Some(name) => crate::overlay::copy::store_register(name, text),
+                    None => self.copy_to_clipboard(*dest, text),
+                }
             }
             CopyTextTo { text, destination } => {
                 self.copy_to_clipboard(*destination, text.clone());
             }
+            CopySelectionAsTable(format) => {
+                let text = self.selection_text(pane);
+                match table_text::convert_table(&text, *format) {
+                    Some(table) => {
+                        self.copy_to_clipboard(ClipboardCopyDestination::Clipboard, table);
+                        self.show_copy_toast();
+                    }
+                    None => self.show_toast("Selection is not a table".to_string()),
+                }
+            }
+            ViewSelectionAsJson => self.show_json_viewer(pane),
             PasteFrom(source) => {
                 self.paste_from_clipboard(pane, *source);
             }
+            ShowClipboardHistory => self.show_clipboard_history(),
+            PasteFromRegister(name) => self.paste_from_register(pane, name),
+            ActivateTabRelative(n) if self.config.uses_native_macos_tabs() => {
+                if let Some(w) = window.as_ref() {
+                    w.select_native_tab(*n, true);
+                }
+            }
             ActivateTabRelative(n) => {
                 self.activate_tab_relative(*n, true)?;
             }
@@ -2944,6 +3658,11 @@ impl TermWindow {
                     self.reset_font_and_window_size(&w)?
                 }
             }
+            ActivateTab(n) if self.config.uses_native_macos_tabs() => {
+                if let Some(w) = window.as_ref() {
+                    w.select_native_tab(*n, false);
+                }
+            }
             ActivateTab(n) => {
                 self.activate_tab(*n)?;
             }
@@ -2956,14 +3675,26 @@ impl TermWindow {
             ActivateWindowRelativeNoWrap(n) => {
                 self.activate_window_relative(*n, false)?;
             }
-            SendString(s) => pane.writer().write_all(s.as_bytes())?,
+            SendString(s) => {
+                if !self.pane_input_locked(pane, true) {
+                    pane.writer().write_all(s.as_bytes())?;
+                    for target in Mux::get().input_broadcast_targets(pane.pane_id()) {
+                        target.writer().write_all(s.as_bytes()).ok();
+                    }
+                }
+            }
             SendKey(key) => {
                 use keyevent::Key;
                 let mods = key.mods;
                 if let Key::Code(key) = self.win_key_code_to_termwiz_key_code(
                     &key.key.resolve(self.config.key_map_preference),
                 ) {
-                    pane.key_down(key, mods)?;
+                    if !self.pane_input_locked(pane, true) {
+                        pane.key_down(key, mods)?;
+                        for target in Mux::get().input_broadcast_targets(pane.pane_id()) {
+                            target.key_down(key, mods).ok();
+                        }
+                    }
                 }
             }
             Hide => {
@@ -2980,16 +3711,59 @@ impl TermWindow {
             CloseCurrentPane { confirm } => self.close_current_pane(*confirm),
             Nop | DisableDefaultAssignment => {}
             ReloadConfiguration => {}
+            ActivateConfigProfile(name) => config::activate_config_profile(Some(name))?,
+            RestoreSession => crate::session::spawn_restore_previous(),
+            MoveTab(n) if self.config.uses_native_macos_tabs() => {
+                if let Some(w) = window.as_ref() {
+                    w.move_native_tab(*n as isize, false);
+                }
+            }
             MoveTab(n) => self.move_tab(*n)?,
+            MoveTabRelative(n) if self.config.uses_native_macos_tabs() => {
+                if let Some(w) = window.as_ref() {
+                    w.move_native_tab(*n, true);
+                }
+            }
             MoveTabRelative(n) => self.move_tab_relative(*n)?,
+            MoveTabToNewWindow => self.move_tab_to_new_window(None)?,
+            ToggleDropdownWindow => crate::dropdown::toggle(),
             ScrollByPage(n) => self.scroll_by_page(**n, pane)?,
             ScrollByLine(n) => self.scroll_by_line(*n, pane)?,
             ScrollByCurrentEventWheelDelta => self.scroll_by_current_event_wheel_delta(pane)?,
             ScrollToPrompt(n) => self.scroll_to_prompt(*n, pane)?,
+            SelectCommandOutput => self.select_command_output(pane),
+            CopyLastCommandOutput => match mux::pane_text::get_last_command_output(&**pane) {
+                Some(text) => {
+                    self.copy_to_clipboard(
+                        ClipboardCopyDestination::ClipboardAndPrimarySelection,
+                        text,
+                    );
+                    self.show_copy_toast();
+                }
+                None => self.show_toast("No command output to copy".to_string()),
+            },
             ScrollToTop => self.scroll_to_top(pane),
             ScrollToBottom => self.scroll_to_bottom(pane),
+            ToggleViewportSplit(size) => self.toggle_viewport_split(pane, size),
+            ShowTabNavigator if self.config.uses_native_macos_tabs() => {
+                if let Some(w) = window.as_ref() {
+                    w.show_native_tab_overview();
+                }
+            }
             ShowTabNavigator => self.show_tab_navigator(),
             ShowDebugOverlay => self.show_debug_overlay(),
+            ShowKeyDebugger => self.show_key_debugger(),
+            ToggleRenderHud => {
+                self.show_render_hud = !self.show_render_hud;
+                if let Some(window) = self.window.as_ref() {
+                    window.invalidate();
+                }
+            }
+            PreviewEventSound(event) => {
+                if !crate::sound::play_event_sound(&self.config, *event) {
+                    Connection::get().expect("on main thread").beep();
+                }
+            }
             ShowLauncher => self.show_launcher(),
             ShowLauncherArgs(args) => {
                 let title = args.title.clone().unwrap_or("Launcher".to_string());
@@ -3094,6 +3868,61 @@ impl TermWindow {
                 let window = self.window.as_ref().unwrap();
                 window.invalidate();
             }
+            ExportScrollback { format } => {
+                let pane = Arc::clone(pane);
+                let format = *format;
+                promise::spawn::spawn(async move {
+                    match pane.export_scrollback(format).await {
+                        Ok(text) => {
+                            let name = format!(
+                                "kaku-scrollback-{}.{}",
+                                chrono::Local::now().format("%Y%m%d-%H%M%S"),
+                                format.extension()
+                            );
+                            if let Err(err) =
+                                crate::download::save_scrollback_export(&name, text.as_bytes())
+                            {
+                                log::error!("Failed to save the exported scrollback: {:#}", err);
+                            }
+                        }
+                        Err(err) => log::error!("Failed to export the scrollback: {:#}", err),
+                    }
+                })
+                .detach();
+            }
+            TogglePaneRecording => {
+                let pane_id = pane.pane_id();
+                if let Some(path) = mux::recording::stop_recording(pane_id) {
+                    let url = format!("file://{}", path.display());
+                    wezterm_toast_notification::persistent_toast_notification_with_click_to_open_url(
+                        "Recording saved",
+                        &format!("Saved {}", path.display()),
+                        &url,
+                    );
+                } else if let Err(err) = mux::recording::start_recording(&**pane) {
+                    log::error!("Failed to start recording pane {}: {:#}", pane_id, err);
+                }
+            }
+            PlayRecording { path } => self.play_recording(path.as_deref()),
+            SetSerialLineEnding(line_ending) => {
+                let mux = Mux::get();
+                let result = match mux.get_domain(pane.domain_id()) {
+                    Some(domain) => match domain.downcast_ref::<LocalDomain>() {
+                        Some(local) => local.set_serial_line_ending(*line_ending),
+                        None => Err(anyhow!(
+                            "{} is not a serial port domain",
+                            domain.domain_name()
+                        )),
+                    },
+                    None => Err(anyhow!(
+                        "the domain of pane {} was not found",
+                        pane.pane_id()
+                    )),
+                };
+                if let Err(err) = result {
+                    log::error!("SetSerialLineEnding: {:#}", err);
+                }
+            }
             Search(pattern) => {
                 if let Some(pane) = self.get_active_pane_or_overlay() {
                     let mut replace_current = false;
@@ -3131,6 +3960,7 @@ impl TermWindow {
                         });
                 }
             }
+            SearchWorkspace(pattern) => self.show_workspace_search(pattern),
             QuickSelect => {
                 if let Some(pane) = self.get_active_pane_no_overlay() {
                     let qa = QuickSelectOverlay::with_pane(
@@ -3194,6 +4024,20 @@ impl TermWindow {
                     tab.adjust_pane_size(*direction, *amount);
                 }
             }
+            SetPaneSize(size) => {
+                let mux = Mux::get();
+                let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
+                    Some(tab) => tab,
+                    None => return Ok(PerformAssignmentResult::Handled),
+                };
+
+                let tab_id = tab.tab_id();
+
+                if self.tab_state(tab_id).overlay.is_none() {
+                    tab.set_pane_size(pane.pane_id(), size.cols, size.rows);
+                }
+            }
+            ApplyLayout(name) => self.apply_layout(name),
             ActivatePaneByIndex(index) => {
                 let mux = Mux::get();
                 let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
@@ -3223,6 +4067,72 @@ impl TermWindow {
                     tab.activate_pane_direction(*direction);
                 }
             }
+            TogglePaneInputLock => {
+                let mux = Mux::get();
+                let locked = !mux.is_pane_input_locked(pane.pane_id());
+                mux.set_pane_input_locked(pane.pane_id(), locked);
+                self.show_toast(
+                    if locked {
+                        "Pane input locked"
+                    } else {
+                        "Pane input unlocked"
+                    }
+                    .to_string(),
+                );
+            }
+            ToggleBroadcastInput(scope) => {
+                let mux = Mux::get();
+                let broadcast = match scope {
+                    BroadcastInputScope::Tab => {
+                        match mux.get_active_tab_for_window(self.mux_window_id) {
+                            Some(tab) => InputBroadcast::Tab(tab.tab_id()),
+                            None => return Ok(PerformAssignmentResult::Handled),
+                        }
+                    }
+                    BroadcastInputScope::Window => InputBroadcast::Window(self.mux_window_id),
+                    BroadcastInputScope::Workspace => match mux.get_window(self.mux_window_id) {
+                        Some(window) => {
+                            InputBroadcast::Workspace(window.get_workspace().to_string())
+                        }
+                        None => return Ok(PerformAssignmentResult::Handled),
+                    },
+                };
+                let active = mux.toggle_input_broadcast(broadcast);
+                self.show_toast(
+                    if active {
+                        "Broadcasting input"
+                    } else {
+                        "Stopped broadcasting input"
+                    }
+                    .to_string(),
+                );
+            }
+            TogglePaneBroadcastExclusion => {
+                let mux = Mux::get();
+                let excluded = !mux.is_pane_broadcast_excluded(pane.pane_id());
+                mux.set_pane_broadcast_excluded(pane.pane_id(), excluded);
+                self.show_toast(
+                    if excluded {
+                        "Pane excluded from broadcast input"
+                    } else {
+                        "Pane included in broadcast input"
+                    }
+                    .to_string(),
+                );
+            }
+            TogglePaneMonitor(monitor) => {
+                let mux = Mux::get();
+                let pane_id = pane.pane_id();
+                let watching = mux.get_pane_monitor(pane_id) != Some(*monitor);
+                mux.set_pane_monitor(pane_id, watching.then_some(*monitor));
+                self.show_toast(match (watching, monitor) {
+                    (false, _) => "Stopped monitoring pane".to_string(),
+                    (true, PaneMonitor::Activity) => "Monitoring pane for activity".to_string(),
+                    (true, PaneMonitor::Silence(seconds)) => {
+                        format!("Monitoring pane for {seconds}s of silence")
+                    }
+                });
+            }
             TogglePaneZoomState => {
                 let mux = Mux::get();
                 let tab = match mux.get_active_tab_for_window(self.mux_window_id) {
@@ -3423,6 +4333,56 @@ impl TermWindow {
             let window = GuiWin::new(self);
             let pane = MuxPane(pane.pane_id());
 
+            // Only links made by hyperlink_rules can have these, so that
+            // programs can't use OSC 8 to run a handler
+            if link.is_implicit() {
+                if let Some(mods) = link.params().get(hyperlink::RULE_MODS_PARAM) {
+                    let required = Modifiers::try_from(mods.to_string()).unwrap_or(Modifiers::NONE);
+                    let held = self
+                        .current_mouse_event
+                        .as_ref()
+                        .map(|event| event.modifiers.remove_positional_mods())
+                        .unwrap_or(Modifiers::NONE);
+                    if !held.contains(required) {
+                        return;
+                    }
+                }
+
+                if let Some(name) = link.params().get(hyperlink::RULE_HANDLER_PARAM) {
+                    async fn run_handler(
+                        lua: Option<Rc<mlua::Lua>>,
+                        name: String,
+                        window: GuiWin,
+                        pane: MuxPane,
+                        text: String,
+                        link: String,
+                    ) -> anyhow::Result<()> {
+                        if let Some(lua) = lua {
+                            let args = lua.pack_multi((window, pane, text, link))?;
+                            if let Err(err) =
+                                config::lua::emit_event(&lua, (name.clone(), args)).await
+                            {
+                                log::error!("while processing {} event: {:#}", name, err);
+                            }
+                        }
+                        Ok(())
+                    }
+
+                    let name = name.to_string();
+                    let text = link
+                        .params()
+                        .get(hyperlink::RULE_TEXT_PARAM)
+                        .cloned()
+                        .unwrap_or_default();
+                    let uri = link.uri().to_string();
+                    promise::spawn::spawn(config::with_lua_config_on_main_thread(move |lua| {
+                        run_handler(lua, name, window, pane, text, uri)
+                    }))
+                    .detach();
+                    return;
+                }
+            }
+
             async fn open_uri(
                 lua: Option<Rc<mlua::Lua>>,
                 window: GuiWin,
@@ -3594,6 +4554,13 @@ impl TermWindow {
         };
 
         let mut state = self.pane_state(pane_id);
+        // A split viewport keeps its upper portion frozen, even when
+        // scrolled all the way down
+        let pos = if state.viewport_split.is_some() {
+            pos.or(Some(dims.physical_top))
+        } else {
+            pos
+        };
         if pos != state.viewport {
             state.viewport = pos;
 
@@ -3616,6 +4583,44 @@ impl TermWindow {
         }
     }
 
+    /// Starts smoothly scrolling the pane from `old_top` to its
+    /// current viewport, if `scroll_animation_fps` enables it
+    fn animate_scroll(
+        &mut self,
+        pane_id: PaneId,
+        old_top: StableRowIndex,
+        dims: RenderableDimensions,
+    ) {
+        let new_top = self.get_viewport(pane_id).unwrap_or(dims.physical_top);
+        if new_top == old_top {
+            return;
+        }
+        let mut state = self.pane_state(pane_id);
+        if state.viewport_split.is_some() {
+            return;
+        }
+        state.scroll_animation = smoothscroll::ScrollAnimation::new(
+            state.scroll_animation,
+            new_top - old_top,
+            dims.viewport_rows,
+            &self.config,
+            Instant::now(),
+        );
+    }
+
+    /// Returns the offset, in lines, at which the viewport of the pane
+    /// is currently drawn relative to its actual position
+    pub fn scroll_animation_offset(&self, pane_id: PaneId) -> Option<f32> {
+        let mut state = self.pane_state(pane_id);
+        let offset = state
+            .scroll_animation
+            .and_then(|anim| anim.offset(Instant::now()));
+        if offset.is_none() {
+            state.scroll_animation = None;
+        }
+        offset
+    }
+
     fn scroll_to_top(&mut self, pane: &Arc<dyn Pane>) {
         // Exit peek mode when scroll_to_top jumps to scrollback top
         if pane.is_primary_peek() {
@@ -3626,10 +4631,63 @@ impl TermWindow {
     }
 
     fn scroll_to_bottom(&mut self, pane: &Arc<dyn Pane>) {
-        self.pane_state(pane.pane_id()).viewport = None;
+        {
+            let mut state = self.pane_state(pane.pane_id());
+            // The live portion of a split viewport is always at the bottom
+            if state.viewport_split.is_none() {
+                state.viewport = None;
+            }
+        }
         pane.set_primary_peek(false);
     }
 
+    /// Returns the number of rows in the frozen upper portion of the
+    /// pane, if its viewport is split.
+    pub fn viewport_split_rows(
+        &self,
+        pane_id: PaneId,
+        dims: &RenderableDimensions,
+    ) -> Option<usize> {
+        clamp_viewport_split(self.pane_state(pane_id).viewport_split, dims.viewport_rows)
+    }
+
+    /// Maps a row, relative to the top of the pane, to the stable row
+    /// index that is displayed there.
+    pub fn stable_row_for_screen_row(
+        &self,
+        pane_id: PaneId,
+        row: i64,
+        dims: &RenderableDimensions,
+    ) -> StableRowIndex {
+        let viewport = self.get_viewport(pane_id).unwrap_or(dims.physical_top);
+        split_stable_row(
+            viewport,
+            dims.physical_top,
+            self.viewport_split_rows(pane_id, dims),
+            row,
+        )
+    }
+
+    fn toggle_viewport_split(&mut self, pane: &Arc<dyn Pane>, size: &SplitSize) {
+        let pane_id = pane.pane_id();
+        let dims = pane.get_dimensions();
+        let is_split = self.pane_state(pane_id).viewport_split.is_some();
+        if is_split {
+            let mut state = self.pane_state(pane_id);
+            state.viewport_split = None;
+            state.viewport = None;
+        } else {
+            let rows = viewport_split_height(size, dims.viewport_rows);
+            let viewport = self.get_viewport(pane_id);
+            self.pane_state(pane_id).viewport_split.replace(rows);
+            // Freeze whatever is currently displayed in the upper portion
+            self.set_viewport(pane_id, viewport, dims);
+        }
+        if let Some(window) = self.window.as_ref() {
+            window.invalidate();
+        }
+    }
+
     fn get_active_pane_no_overlay(&self) -> Option<Arc<dyn Pane>> {
         let mux = Mux::get();
         mux.get_active_tab_for_window(self.mux_window_id)
@@ -3691,6 +4749,9 @@ impl TermWindow {
             pane_index: pos.index,
             is_active: pos.is_active,
             is_zoomed: pos.is_zoomed,
+            is_input_locked: Mux::get().is_pane_input_locked(pos.pane.pane_id()),
+            is_broadcasting_input: Mux::get().is_pane_broadcasting_input(pos.pane.pane_id()),
+            is_recording: mux::recording::is_recording(pos.pane.pane_id()),
             has_unseen_output: pos.pane.has_unseen_output(),
             left: pos.left,
             top: pos.top,
@@ -3728,6 +4789,9 @@ impl TermWindow {
                         .unwrap_or(false),
                     window_id: self.mux_window_id,
                     tab_title: tab.get_title(),
+                    has_triggered_monitor: panes
+                        .iter()
+                        .any(|p| mux.is_pane_monitor_triggered(p.pane.pane_id())),
                     active_pane: panes
                         .iter()
                         .find(|p| p.is_active)
@@ -3849,6 +4913,92 @@ impl TermWindow {
         self.update_title();
     }
 
+    /// Activates the pane that holds `result`, which was found by
+    /// SearchWorkspace, and selects it in that pane's search UI
+    pub fn jump_to_search_match(
+        &mut self,
+        pane_id: PaneId,
+        pattern: MuxPattern,
+        result: SearchResult,
+    ) -> anyhow::Result<()> {
+        let mux = Mux::get();
+        let pane = mux
+            .get_pane(pane_id)
+            .ok_or_else(|| anyhow!("pane {pane_id} not found"))?;
+        let (_domain_id, window_id, tab_id) = mux
+            .resolve_pane_id(pane_id)
+            .ok_or_else(|| anyhow!("pane {pane_id} is not in a tab"))?;
+
+        if window_id != self.mux_window_id {
+            // The match is in another window; let that one handle it
+            let gui_win = front_end()
+                .gui_window_for_mux_window(window_id)
+                .ok_or_else(|| anyhow!("window {window_id} has no gui window"))?;
+            gui_win.window.focus();
+            gui_win
+                .window
+                .notify(TermWindowNotif::Apply(Box::new(move |term_window| {
+                    if let Err(err) = term_window.jump_to_search_match(pane_id, pattern, result) {
+                        log::error!("jumping to match in pane {pane_id}: {err:#}");
+                    }
+                })));
+            return Ok(());
+        }
+
+        {
+            let mut window = mux
+                .get_window_mut(window_id)
+                .ok_or_else(|| anyhow!("window {window_id} not found"))?;
+            let tab_idx = window
+                .idx_by_id(tab_id)
+                .ok_or_else(|| anyhow!("tab {tab_id} is not in window {window_id}"))?;
+            window.save_and_then_set_active(tab_idx);
+        }
+        let tab = mux
+            .get_tab(tab_id)
+            .ok_or_else(|| anyhow!("tab {tab_id} not found"))?;
+        tab.set_active_pane(&pane);
+
+        let existing = self
+            .pane_state(pane_id)
+            .overlay
+            .as_ref()
+            .map(|overlay| Arc::clone(&overlay.pane))
+            .filter(|overlay| overlay.downcast_ref::<CopyOverlay>().is_some());
+        let replace_current = existing.is_some();
+        let params = CopyModeParams {
+            pattern,
+            editing_search: true,
+        };
+        let search = match existing {
+            Some(search) => {
+                if let Some(copy) = search.downcast_ref::<CopyOverlay>() {
+                    copy.apply_params(params);
+                }
+                search
+            }
+            None => {
+                let search = CopyOverlay::with_pane(self, &pane, params)?;
+                self.assign_overlay_for_pane(pane_id, Arc::clone(&search));
+                search
+            }
+        };
+        if let Some(copy) = search.downcast_ref::<CopyOverlay>() {
+            copy.activate_match_at(result.start_y, result.start_x);
+        }
+        if let Some(overlay) = self.pane_state(pane_id).overlay.as_mut() {
+            overlay.key_table_state.activate(KeyTableArgs {
+                name: "search_mode",
+                timeout_milliseconds: None,
+                replace_current,
+                one_shot: false,
+                until_unknown: false,
+                prevent_fallback: false,
+            });
+        }
+        Ok(())
+    }
+
     fn resolve_search_pattern(&self, pattern: Pattern, pane: &Arc<dyn Pane>) -> MuxPattern {
         match pattern {
             Pattern::CaseSensitiveString(s) => MuxPattern::CaseSensitiveString(s),
@@ -3867,6 +5017,40 @@ impl TermWindow {
     }
 }
 
+/// Returns the number of rows to freeze at the top of a pane that
+/// has `viewport_rows` rows when its viewport is split by `size`
+fn viewport_split_height(size: &SplitSize, viewport_rows: usize) -> usize {
+    let rows = match size {
+        SplitSize::Cells(n) => *n,
+        SplitSize::Percent(p) => viewport_rows * (*p as usize) / 100,
+    };
+    rows.clamp(1, viewport_rows.saturating_sub(1).max(1))
+}
+
+/// Limits the frozen rows of a split viewport so that at least one
+/// row shows the live output, which may leave no room for the split
+/// once the pane has been made smaller
+fn clamp_viewport_split(split: Option<usize>, viewport_rows: usize) -> Option<usize> {
+    split
+        .map(|rows| rows.min(viewport_rows.saturating_sub(1)))
+        .filter(|&rows| rows > 0)
+}
+
+/// Maps `row`, relative to the top of the pane, to the stable row
+/// displayed there: rows above the split show the frozen `viewport`,
+/// while those below it show the live screen
+fn split_stable_row(
+    viewport: StableRowIndex,
+    physical_top: StableRowIndex,
+    split: Option<usize>,
+    row: i64,
+) -> StableRowIndex {
+    match split {
+        Some(split) if row >= split as i64 => physical_top + row as StableRowIndex,
+        _ => viewport + row as StableRowIndex,
+    }
+}
+
 impl Drop for TermWindow {
     fn drop(&mut self) {
         self.clear_all_overlays();
@@ -3877,3 +5061,35 @@ impl Drop for TermWindow {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn viewport_split_heights() {
+        assert_eq!(viewport_split_height(&SplitSize::Percent(50), 24), 12);
+        assert_eq!(viewport_split_height(&SplitSize::Cells(10), 24), 10);
+        // The live output keeps at least one row
+        assert_eq!(viewport_split_height(&SplitSize::Cells(40), 24), 23);
+        assert_eq!(viewport_split_height(&SplitSize::Percent(0), 24), 1);
+        assert_eq!(viewport_split_height(&SplitSize::Percent(50), 1), 1);
+    }
+
+    #[test]
+    fn clamp_split_to_pane() {
+        assert_eq!(clamp_viewport_split(None, 24), None);
+        assert_eq!(clamp_viewport_split(Some(12), 24), Some(12));
+        assert_eq!(clamp_viewport_split(Some(12), 8), Some(7));
+        assert_eq!(clamp_viewport_split(Some(12), 1), None);
+    }
+
+    #[test]
+    fn stable_rows_either_side_of_split() {
+        // Frozen at row 100 while the live screen starts at row 500
+        assert_eq!(split_stable_row(100, 500, Some(10), 0), 100);
+        assert_eq!(split_stable_row(100, 500, Some(10), 9), 109);
+        assert_eq!(split_stable_row(100, 500, Some(10), 10), 510);
+        assert_eq!(split_stable_row(100, 500, None, 10), 110);
+    }
+}
@@ -7,7 +7,7 @@ use ::window::{
     WindowState,
 };
 use config::keyassignment::{KeyAssignment, MouseEventTrigger, SpawnTabDomain};
-use config::MouseEventAltScreen;
+use config::{MouseEventAltScreen, MouseEventZone};
 use mux::pane::{Pane, WithPaneLines};
 use mux::tab::SplitDirection;
 use mux::Mux;
@@ -16,7 +16,7 @@ use std::convert::TryInto;
 use std::ops::Sub;
 use std::rc::Rc;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use termwiz::hyperlink::Hyperlink;
 use termwiz::surface::Line;
 use wezterm_dynamic::ToDynamic;
@@ -24,7 +24,7 @@ use wezterm_term::input::{MouseButton, MouseEventKind as TMEK};
 use wezterm_term::{ClickPosition, LastMouseClick, StableRowIndex};
 
 impl super::TermWindow {
-    fn resolve_ui_item(&self, event: &MouseEvent) -> Option<UIItem> {
+    pub(super) fn resolve_ui_item(&self, event: &MouseEvent) -> Option<UIItem> {
         let x = event.coords.x;
         let y = event.coords.y;
         self.ui_items
@@ -126,6 +126,10 @@ impl super::TermWindow {
             WMEK::Release(ref press) => {
                 self.current_mouse_capture = None;
                 self.current_mouse_buttons.retain(|p| p != press);
+                if press == &MousePress::Left && self.end_tab_drag(&event, context) {
+                    self.is_window_dragging = false;
+                    return;
+                }
                 if press == &MousePress::Left {
                     let was_dragging_window = self.is_window_dragging;
                     self.is_window_dragging = false;
@@ -190,6 +194,9 @@ impl super::TermWindow {
             }
 
             WMEK::Move => {
+                if self.tab_drag_motion(&event, context) {
+                    return;
+                }
                 if let Some(start) = self.window_drag_position.clone() {
                     if event.mouse_buttons != WMB::LEFT {
                         self.window_drag_position = None;
@@ -418,6 +425,73 @@ impl super::TermWindow {
         self.dragging.replace((item, start_event));
     }
 
+    /// Returns the offset from the top of the window and the height
+    /// of the track in which the scroll bar thumb moves
+    fn scroll_thumb_track(&self) -> (usize, usize) {
+        let tab_bar_height = if self.show_tab_bar {
+            self.tab_bar_pixel_height().unwrap_or(0.)
+        } else {
+            0.
+        };
+        let (top_bar_height, bottom_bar_height) = if self.config.tab_bar_at_bottom {
+            (0.0, tab_bar_height)
+        } else {
+            (tab_bar_height, 0.0)
+        };
+
+        let border = self.get_os_border();
+        let y_offset = top_bar_height as usize + border.top.get();
+        let max_thumb_height = self
+            .dimensions
+            .pixel_height
+            .saturating_sub(y_offset + border.bottom.get() + bottom_bar_height as usize);
+        (y_offset, max_thumb_height)
+    }
+
+    /// Moves the scroll bar thumb so that it is centered on the
+    /// position of `event`, then begins dragging it from there
+    fn jump_scroll_thumb(
+        &mut self,
+        item: UIItem,
+        pane: Arc<dyn Pane>,
+        event: MouseEvent,
+        context: &dyn WindowOps,
+    ) {
+        let dims = pane.get_dimensions();
+        let current_viewport = self.get_viewport(pane.pane_id());
+        let (y_offset, max_thumb_height) = self.scroll_thumb_track();
+        let min_thumb_size = self.min_scroll_bar_height() as usize;
+
+        let thumb = ScrollHit::thumb(&*pane, current_viewport, max_thumb_height, min_thumb_size);
+        let thumb_top = event
+            .coords
+            .y
+            .saturating_sub((y_offset + thumb.height / 2) as isize)
+            .max(0) as usize;
+        let thumb_top = thumb_top.min(max_thumb_height.saturating_sub(thumb.height));
+
+        let row = ScrollHit::thumb_top_to_scroll_top(
+            thumb_top,
+            &*pane,
+            current_viewport,
+            max_thumb_height,
+            min_thumb_size,
+        );
+        self.set_viewport(pane.pane_id(), Some(row), dims);
+        context.invalidate();
+
+        self.dragging.replace((
+            UIItem {
+                x: item.x,
+                width: item.width,
+                y: y_offset + thumb_top,
+                height: thumb.height,
+                item_type: UIItemType::ScrollThumb,
+            },
+            event,
+        ));
+    }
+
     fn drag_scroll_thumb(
         &mut self,
         item: UIItem,
@@ -432,20 +506,7 @@ impl super::TermWindow {
 
         let dims = pane.get_dimensions();
         let current_viewport = self.get_viewport(pane.pane_id());
-
-        let tab_bar_height = if self.show_tab_bar {
-            self.tab_bar_pixel_height().unwrap_or(0.)
-        } else {
-            0.
-        };
-        let (top_bar_height, bottom_bar_height) = if self.config.tab_bar_at_bottom {
-            (0.0, tab_bar_height)
-        } else {
-            (tab_bar_height, 0.0)
-        };
-
-        let border = self.get_os_border();
-        let y_offset = top_bar_height + border.top.get() as f32;
+        let (y_offset, max_thumb_height) = self.scroll_thumb_track();
 
         let from_top = start_event.coords.y.saturating_sub(item.y as isize);
         let effective_thumb_top = event
@@ -460,9 +521,7 @@ impl super::TermWindow {
             effective_thumb_top,
             &*pane,
             current_viewport,
-            self.dimensions.pixel_height.saturating_sub(
-                y_offset as usize + border.bottom.get() + bottom_bar_height as usize,
-            ),
+            max_thumb_height,
             self.min_scroll_bar_height() as usize,
         );
         self.set_viewport(pane.pane_id(), Some(row), dims);
@@ -501,6 +560,26 @@ impl super::TermWindow {
         context: &dyn WindowOps,
     ) {
         self.last_ui_item.replace(item.clone());
+        let zone = match &item.item_type {
+            UIItemType::TabBar(
+                TabBarItem::Tab { tab_idx, .. } | TabBarItem::TabRegion { tab_idx, .. },
+            ) => Some((MouseEventZone::Tab, Some(*tab_idx))),
+            UIItemType::TabBar(TabBarItem::NewTabButton { .. }) => {
+                Some((MouseEventZone::NewTabButton, None))
+            }
+            UIItemType::TabBar(
+                TabBarItem::None | TabBarItem::LeftStatus | TabBarItem::RightStatus,
+            ) => Some((MouseEventZone::WindowBackground, None)),
+            UIItemType::Split(_) => Some((MouseEventZone::PaneBorder, None)),
+            _ => None,
+        };
+        if let Some((zone, tab_idx)) = zone {
+            if self.perform_zone_mouse_binding(zone, &event, tab_idx) {
+                context.set_cursor(Some(MouseCursor::Arrow));
+                context.invalidate();
+                return;
+            }
+        }
         match item.item_type {
             UIItemType::TabBar(item) => {
                 self.mouse_event_tab_bar(item, event, context);
@@ -603,10 +682,32 @@ impl super::TermWindow {
                     if !active {
                         self.activate_tab(tab_idx as isize).ok();
                     }
+                    self.begin_tab_drag(tab_idx, &event);
+                }
+                TabBarItem::TabRegion { tab_idx, region } => {
+                    // The action applies to the tab that was clicked,
+                    // so that eg: CloseCurrentTab closes that tab
+                    if let Some(action) = self.tab_bar.region_action(tab_idx, region).cloned() {
+                        self.activate_tab(tab_idx as isize).ok();
+                        if let Some(pane) = self.get_active_pane_or_overlay() {
+                            if let Err(err) = self.perform_key_assignment(&pane, &action) {
+                                log::error!("tab title region {}: {:#}", region, err);
+                            }
+                        }
+                    }
                 }
                 TabBarItem::NewTabButton { .. } => {
                     self.do_new_tab_button_click(MousePress::Left);
                 }
+                TabBarItem::CustomButton(idx) => {
+                    if let Some(button) = self.config.tab_bar_buttons.get(idx).cloned() {
+                        if let Some(pane) = self.get_active_pane_or_overlay() {
+                            if let Err(err) = self.perform_key_assignment(&pane, &button.action) {
+                                log::error!("tab bar button {}: {:#}", idx, err);
+                            }
+                        }
+                    }
+                }
                 TabBarItem::None | TabBarItem::LeftStatus | TabBarItem::RightStatus => {
                     let maximized = self
                         .window_state
@@ -652,7 +753,7 @@ impl super::TermWindow {
                 }
             },
             WMEK::Press(MousePress::Middle) => match item {
-                TabBarItem::Tab { tab_idx, .. } => {
+                TabBarItem::Tab { tab_idx, .. } | TabBarItem::TabRegion { tab_idx, .. } => {
                     self.close_specific_tab(tab_idx, true);
                 }
                 TabBarItem::NewTabButton { .. } => {
@@ -661,10 +762,11 @@ impl super::TermWindow {
                 TabBarItem::None
                 | TabBarItem::LeftStatus
                 | TabBarItem::RightStatus
+                | TabBarItem::CustomButton(_)
                 | TabBarItem::WindowButton(_) => {}
             },
             WMEK::Press(MousePress::Right) => match item {
-                TabBarItem::Tab { .. } => {
+                TabBarItem::Tab { .. } | TabBarItem::TabRegion { .. } => {
                     self.show_tab_navigator();
                 }
                 TabBarItem::NewTabButton { .. } => {
@@ -673,6 +775,7 @@ impl super::TermWindow {
                 TabBarItem::None
                 | TabBarItem::LeftStatus
                 | TabBarItem::RightStatus
+                | TabBarItem::CustomButton(_)
                 | TabBarItem::WindowButton(_) => {}
             },
             WMEK::Move => match item {
@@ -691,6 +794,8 @@ impl super::TermWindow {
                 }
                 TabBarItem::WindowButton(_)
                 | TabBarItem::Tab { .. }
+                | TabBarItem::TabRegion { .. }
+                | TabBarItem::CustomButton(_)
                 | TabBarItem::NewTabButton { .. } => {}
             },
             WMEK::VertWheel(n) => {
@@ -706,12 +811,17 @@ impl super::TermWindow {
 
     pub fn mouse_event_above_scroll_thumb(
         &mut self,
-        _item: UIItem,
+        item: UIItem,
         pane: Arc<dyn Pane>,
         event: MouseEvent,
         context: &dyn WindowOps,
     ) {
         if let WMEK::Press(MousePress::Left) = event.kind {
+            if self.config.scroll_bar_click_to_jump {
+                self.jump_scroll_thumb(item, pane, event, context);
+                context.set_cursor(Some(MouseCursor::Arrow));
+                return;
+            }
             let dims = pane.get_dimensions();
             let current_viewport = self.get_viewport(pane.pane_id());
             // Page up
@@ -731,12 +841,17 @@ impl super::TermWindow {
 
     pub fn mouse_event_below_scroll_thumb(
         &mut self,
-        _item: UIItem,
+        item: UIItem,
         pane: Arc<dyn Pane>,
         event: MouseEvent,
         context: &dyn WindowOps,
     ) {
         if let WMEK::Press(MousePress::Left) = event.kind {
+            if self.config.scroll_bar_click_to_jump {
+                self.jump_scroll_thumb(item, pane, event, context);
+                context.set_cursor(Some(MouseCursor::Arrow));
+                return;
+            }
             let dims = pane.get_dimensions();
             let current_viewport = self.get_viewport(pane.pane_id());
             // Page down
@@ -790,11 +905,117 @@ impl super::TermWindow {
         }
     }
 
+    /// Returns the `mouse_bindings` trigger that corresponds to an event
+    fn mouse_event_trigger(&self, kind: &WMEK) -> Option<MouseEventTrigger> {
+        match kind {
+            WMEK::Press(press) => {
+                let press = mouse_press_to_tmb(press);
+                match self.last_mouse_click.as_ref() {
+                    Some(LastMouseClick { streak, button, .. }) if *button == press => {
+                        Some(MouseEventTrigger::Down {
+                            streak: *streak,
+                            button: press,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            WMEK::Release(press) => {
+                let press = mouse_press_to_tmb(press);
+                match self.last_mouse_click.as_ref() {
+                    Some(LastMouseClick { streak, button, .. }) if *button == press => {
+                        Some(MouseEventTrigger::Up {
+                            streak: *streak,
+                            button: press,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            WMEK::Move => {
+                if !self.current_mouse_buttons.is_empty() {
+                    if let Some(LastMouseClick { streak, button, .. }) =
+                        self.last_mouse_click.as_ref()
+                    {
+                        if Some(*button)
+                            == self.current_mouse_buttons.last().map(mouse_press_to_tmb)
+                        {
+                            Some(MouseEventTrigger::Drag {
+                                streak: *streak,
+                                button: *button,
+                            })
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            WMEK::VertWheel(amount) => Some(match *amount {
+                0 => return None,
+                1.. => MouseEventTrigger::Down {
+                    streak: 1,
+                    button: MouseButton::WheelUp(*amount as usize),
+                },
+                _ => MouseEventTrigger::Down {
+                    streak: 1,
+                    button: MouseButton::WheelDown(-amount as usize),
+                },
+            }),
+            WMEK::HorzWheel(amount) => Some(match *amount {
+                0 => return None,
+                1.. => MouseEventTrigger::Down {
+                    streak: 1,
+                    button: MouseButton::WheelLeft(*amount as usize),
+                },
+                _ => MouseEventTrigger::Down {
+                    streak: 1,
+                    button: MouseButton::WheelRight(-amount as usize),
+                },
+            }),
+        }
+    }
+
+    /// Performs the mouse binding for `zone` that matches `event`, if
+    /// any, returning true if one was performed.  `tab_idx` is the tab
+    /// that was clicked; it is activated first.
+    fn perform_zone_mouse_binding(
+        &mut self,
+        zone: MouseEventZone,
+        event: &MouseEvent,
+        tab_idx: Option<usize>,
+    ) -> bool {
+        let Some(mut trigger) = self.mouse_event_trigger(&event.kind) else {
+            return false;
+        };
+        normalize_wheel_trigger(&mut trigger);
+        let Some(action) = self
+            .input_map
+            .lookup_zone_mouse(zone, trigger.clone(), event.modifiers)
+        else {
+            return false;
+        };
+        if let Some(tab_idx) = tab_idx {
+            self.activate_tab(tab_idx as isize).ok();
+        }
+        let Some(pane) = self.get_active_pane_or_overlay() else {
+            return false;
+        };
+        self.current_event = Some(trigger.to_dynamic());
+        if let Err(err) = self.perform_key_assignment(&pane, &action) {
+            log::error!("mouse binding for {zone:?}: {err:#}");
+        }
+        true
+    }
+
     fn mouse_event_terminal(
         &mut self,
         mut pane: Arc<dyn Pane>,
         position: ClickPosition,
-        event: MouseEvent,
+        mut event: MouseEvent,
         context: &dyn WindowOps,
         capture_mouse: bool,
     ) {
@@ -944,10 +1165,7 @@ impl super::TermWindow {
         );
 
         let dims = pane.get_dimensions();
-        let stable_row = self
-            .get_viewport(pane.pane_id())
-            .unwrap_or(dims.physical_top)
-            + row as StableRowIndex;
+        let stable_row = self.stable_row_for_screen_row(pane.pane_id(), row, &dims);
 
         self.pane_state(pane.pane_id())
             .mouse_terminal_coords
@@ -1014,76 +1232,40 @@ impl super::TermWindow {
             MouseCursor::Text
         }));
 
-        let event_trigger_type = match &event.kind {
-            WMEK::Press(press) => {
-                let press = mouse_press_to_tmb(press);
-                match self.last_mouse_click.as_ref() {
-                    Some(LastMouseClick { streak, button, .. }) if *button == press => {
-                        Some(MouseEventTrigger::Down {
-                            streak: *streak,
-                            button: press,
-                        })
-                    }
-                    _ => None,
-                }
-            }
-            WMEK::Release(press) => {
-                let press = mouse_press_to_tmb(press);
-                match self.last_mouse_click.as_ref() {
-                    Some(LastMouseClick { streak, button, .. }) if *button == press => {
-                        Some(MouseEventTrigger::Up {
-                            streak: *streak,
-                            button: press,
-                        })
-                    }
-                    _ => None,
-                }
-            }
-            WMEK::Move => {
-                if !self.current_mouse_buttons.is_empty() {
-                    if let Some(LastMouseClick { streak, button, .. }) =
-                        self.last_mouse_click.as_ref()
-                    {
-                        if Some(*button)
-                            == self.current_mouse_buttons.last().map(mouse_press_to_tmb)
-                        {
-                            Some(MouseEventTrigger::Drag {
-                                streak: *streak,
-                                button: *button,
-                            })
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+        let mut wheel_reports = 1;
+        if matches!(&event.kind, WMEK::VertWheel(_) | WMEK::HorzWheel(_)) {
+            match self.wheel_scroll.scale(
+                &event.kind,
+                event.precise_scroll,
+                pane.is_alt_screen_active(),
+                &self.config,
+                Instant::now(),
+            ) {
+                Some((kind, reports)) => {
+                    event.kind = kind;
+                    wheel_reports = reports;
+                    self.current_mouse_event.replace(event.clone());
                 }
+                None => return,
             }
-            WMEK::VertWheel(amount) => Some(match *amount {
-                0 => return,
-                1.. => MouseEventTrigger::Down {
-                    streak: 1,
-                    button: MouseButton::WheelUp(*amount as usize),
-                },
-                _ => MouseEventTrigger::Down {
-                    streak: 1,
-                    button: MouseButton::WheelDown(-amount as usize),
-                },
-            }),
-            WMEK::HorzWheel(amount) => Some(match *amount {
-                0 => return,
-                1.. => MouseEventTrigger::Down {
-                    streak: 1,
-                    button: MouseButton::WheelLeft(*amount as usize),
-                },
-                _ => MouseEventTrigger::Down {
-                    streak: 1,
-                    button: MouseButton::WheelRight(-amount as usize),
-                },
-            }),
-        };
+        }
+
+        if matches!(event.kind, WMEK::VertWheel(0) | WMEK::HorzWheel(0)) {
+            return;
+        }
+        let event_trigger_type = self.mouse_event_trigger(&event.kind);
+
+        // A pane whose input is locked doesn't receive mouse reports
+        // either; the mouse acts as though the application hadn't
+        // grabbed it, so that text can still be selected and scrolled
+        let input_locked = Mux::get().is_pane_input_locked(pane.pane_id());
+
+        // Holding the bypass modifiers routes the event to our own
+        // mouse bindings, such as selection, rather than to the application
+        let bypass_mouse_reporting = pane.is_mouse_grabbed()
+            && event
+                .modifiers
+                .contains(self.config.bypass_mouse_reporting_modifiers);
 
         if allow_action {
             if let Some(mut event_trigger_type) = event_trigger_type {
@@ -1092,12 +1274,10 @@ impl super::TermWindow {
 
                 // Since we use shift to force assessing the mouse bindings, pretend
                 // that shift is not one of the mods when the mouse is grabbed.
-                let mut mouse_reporting = pane.is_mouse_grabbed();
-                if mouse_reporting {
-                    if modifiers.contains(self.config.bypass_mouse_reporting_modifiers) {
-                        modifiers.remove(self.config.bypass_mouse_reporting_modifiers);
-                        mouse_reporting = false;
-                    }
+                let mut mouse_reporting = pane.is_mouse_grabbed() && !input_locked;
+                if bypass_mouse_reporting {
+                    modifiers.remove(self.config.bypass_mouse_reporting_modifiers);
+                    mouse_reporting = false;
                 }
 
                 if mouse_reporting {
@@ -1109,38 +1289,7 @@ impl super::TermWindow {
                     self.scroll_to_bottom(&pane);
                 }
 
-                // normalize delta and streak to make mouse assignment
-                // easier to wrangle
-                match event_trigger_type {
-                    MouseEventTrigger::Down {
-                        ref mut streak,
-                        button:
-                            MouseButton::WheelUp(ref mut delta)
-                            | MouseButton::WheelDown(ref mut delta)
-                            | MouseButton::WheelLeft(ref mut delta)
-                            | MouseButton::WheelRight(ref mut delta),
-                    }
-                    | MouseEventTrigger::Up {
-                        ref mut streak,
-                        button:
-                            MouseButton::WheelUp(ref mut delta)
-                            | MouseButton::WheelDown(ref mut delta)
-                            | MouseButton::WheelLeft(ref mut delta)
-                            | MouseButton::WheelRight(ref mut delta),
-                    }
-                    | MouseEventTrigger::Drag {
-                        ref mut streak,
-                        button:
-                            MouseButton::WheelUp(ref mut delta)
-                            | MouseButton::WheelDown(ref mut delta)
-                            | MouseButton::WheelLeft(ref mut delta)
-                            | MouseButton::WheelRight(ref mut delta),
-                    } => {
-                        *streak = 1;
-                        *delta = 1;
-                    }
-                    _ => {}
-                };
+                normalize_wheel_trigger(&mut event_trigger_type);
 
                 let alt_screen = pane.is_alt_screen_active();
                 let mouse_mods = config::MouseEventTriggerMods {
@@ -1202,9 +1351,13 @@ impl super::TermWindow {
         };
 
         if allow_action
+            && !bypass_mouse_reporting
+            && !input_locked
             && !(self.config.swallow_mouse_click_on_pane_focus && is_click_to_focus_pane)
         {
-            pane.mouse_event(mouse_event).ok();
+            for _ in 0..wheel_reports {
+                pane.mouse_event(mouse_event).ok();
+            }
         }
 
         match event.kind {
@@ -1223,3 +1376,38 @@ fn mouse_press_to_tmb(press: &MousePress) -> TMB {
         MousePress::Middle => TMB::Middle,
     }
 }
+
+/// Normalizes the delta and streak of wheel events, to make mouse
+/// assignments easier to wrangle
+fn normalize_wheel_trigger(trigger: &mut MouseEventTrigger) {
+    match *trigger {
+        MouseEventTrigger::Down {
+            ref mut streak,
+            button:
+                MouseButton::WheelUp(ref mut delta)
+                | MouseButton::WheelDown(ref mut delta)
+                | MouseButton::WheelLeft(ref mut delta)
+                | MouseButton::WheelRight(ref mut delta),
+        }
+        | MouseEventTrigger::Up {
+            ref mut streak,
+            button:
+                MouseButton::WheelUp(ref mut delta)
+                | MouseButton::WheelDown(ref mut delta)
+                | MouseButton::WheelLeft(ref mut delta)
+                | MouseButton::WheelRight(ref mut delta),
+        }
+        | MouseEventTrigger::Drag {
+            ref mut streak,
+            button:
+                MouseButton::WheelUp(ref mut delta)
+                | MouseButton::WheelDown(ref mut delta)
+                | MouseButton::WheelLeft(ref mut delta)
+                | MouseButton::WheelRight(ref mut delta),
+        } => {
+            *streak = 1;
+            *delta = 1;
+        }
+        _ => {}
+    }
+}
@@ -1,5 +1,5 @@
 use crate::commands::{CommandDef, ExpandedCommand};
-use crate::overlay::selector::{matcher_pattern, matcher_score};
+use crate::overlay::selector::{matcher_indices, matcher_pattern, matcher_score};
 use crate::termwindow::box_model::*;
 use crate::termwindow::modal::Modal;
 use crate::termwindow::render::corners::{
@@ -13,6 +13,7 @@ use config::Dimension;
 use frecency::Frecency;
 use luahelper::{from_lua_value_dynamic, impl_lua_conversion_dynamic};
 use mux_lua::MuxPane;
+use nucleo_matcher::pattern::Pattern;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -20,8 +21,11 @@ use std::cell::{Ref, RefCell};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::rc::Rc;
 use termwiz::nerdfonts::NERD_FONTS;
+use unicode_segmentation::UnicodeSegmentation;
 use wezterm_dynamic::{FromDynamic, ToDynamic};
+use wezterm_font::LoadedFont;
 use wezterm_term::{KeyCode, KeyModifiers, MouseEvent};
 use window::color::LinearRgba;
 use window::Modifiers;
@@ -29,6 +33,9 @@ use window::Modifiers;
 struct MatchResults {
     selection: String,
     matches: Vec<usize>,
+    /// The positions of the matched graphemes in the label of
+    /// each command, keyed by command index
+    highlights: HashMap<usize, Vec<u32>>,
 }
 
 pub struct CommandPalette {
@@ -39,44 +46,126 @@ pub struct CommandPalette {
     top_row: RefCell<usize>,
     max_rows_on_screen: RefCell<usize>,
     commands: Vec<ExpandedCommand>,
+    /// The frecency score of each of `commands`
+    frecency: Vec<f64>,
 }
 
+/// How much the frecency of a command can raise its match score.
+/// The boost grows logarithmically so that a frequently used command
+/// doesn't outrank a much better textual match.
+const FRECENCY_BOOST: f64 = 16.0;
+
+/// History entries whose score has decayed below this are dropped
+const MIN_FRECENCY: f64 = 0.01;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Recent {
-    brief: String,
+    /// Identifies the action that was performed; see [action_key]
+    action: String,
     frecency: Frecency,
 }
 
-fn recent_file_name() -> PathBuf {
+fn history_file_name() -> PathBuf {
+    config::CACHE_DIR.join("palette_history.json")
+}
+
+/// Where the history was kept before it moved to the cache dir.
+/// Its entries are keyed by the brief description of the command.
+fn legacy_history_file_name() -> PathBuf {
     config::DATA_DIR.join("recent-commands.json")
 }
 
-fn load_recents() -> anyhow::Result<Vec<Recent>> {
-    let file_name = recent_file_name();
+#[derive(Deserialize)]
+struct LegacyRecent {
+    brief: String,
+    frecency: Frecency,
+}
+
+/// Commands are remembered by their action rather than their
+/// brief description, so that renaming an entry doesn't forget it.
+/// The action is described by its config representation, which
+/// is the same as the one used in the lua config and doesn't
+/// change along with the internals of `KeyAssignment`.
+fn action_key(action: &KeyAssignment) -> String {
+    format!("{:?}", action.to_dynamic())
+}
+
+/// Converts the entries of the legacy history to entries keyed
+/// by action.  Entries whose brief no longer matches any of
+/// `commands` are dropped.
+fn migrate_legacy_history(legacy: Vec<LegacyRecent>, commands: &[ExpandedCommand]) -> Vec<Recent> {
+    legacy
+        .into_iter()
+        .filter_map(|r| {
+            let command = commands.iter().find(|cmd| cmd.brief == r.brief)?;
+            Some(Recent {
+                action: action_key(&command.action),
+                frecency: r.frecency,
+            })
+        })
+        .collect()
+}
+
+fn load_history() -> anyhow::Result<Vec<Recent>> {
+    let file_name = history_file_name();
     let f = std::fs::File::open(&file_name)?;
-    let mut recents: Vec<Recent> = serde_json::from_reader(f)?;
-    recents.sort_by(|a, b| b.frecency.score().partial_cmp(&a.frecency.score()).unwrap());
+    let recents: Vec<Recent> = serde_json::from_reader(f)?;
+    Ok(recents)
+}
+
+/// Moves the legacy history, if any, into the cache dir
+fn load_legacy_history(commands: &[ExpandedCommand]) -> anyhow::Result<Vec<Recent>> {
+    let legacy_file_name = legacy_history_file_name();
+    let f = std::fs::File::open(&legacy_file_name)?;
+    let legacy: Vec<LegacyRecent> = serde_json::from_reader(f)?;
+    let recents = migrate_legacy_history(legacy, commands);
+    save_history(&recents)?;
+    if let Err(err) = std::fs::remove_file(&legacy_file_name) {
+        log::warn!("removing {}: {err:#}", legacy_file_name.display());
+    }
     Ok(recents)
 }
 
-fn save_recent(command: &ExpandedCommand) -> anyhow::Result<()> {
-    let mut recents = load_recents().unwrap_or_else(|_| vec![]);
-    if let Some(recent_idx) = recents.iter().position(|r| r.brief == command.brief) {
-        let recent = recents.get_mut(recent_idx).unwrap();
+fn save_history(recents: &[Recent]) -> anyhow::Result<()> {
+    let json = serde_json::to_string(recents)?;
+    config::create_user_owned_dirs(&config::CACHE_DIR)?;
+    std::fs::write(history_file_name(), json)?;
+    Ok(())
+}
+
+/// Returns the frecency score of each of `commands`
+fn history_scores(commands: &[ExpandedCommand]) -> Vec<f64> {
+    let recents = if history_file_name().exists() {
+        load_history()
+    } else {
+        load_legacy_history(commands)
+    };
+    let scores: HashMap<String, f64> = recents
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.action, r.frecency.score()))
+        .collect();
+    commands
+        .iter()
+        .map(|cmd| scores.get(&action_key(&cmd.action)).copied().unwrap_or(0.))
+        .collect()
+}
+
+fn record_invocation(command: &ExpandedCommand) -> anyhow::Result<()> {
+    let mut recents = load_history().unwrap_or_else(|_| vec![]);
+    let key = action_key(&command.action);
+    if let Some(recent) = recents.iter_mut().find(|r| r.action == key) {
         recent.frecency.register_access();
     } else {
         let mut frecency = Frecency::new();
         frecency.register_access();
         recents.push(Recent {
-            brief: command.brief.to_string(),
+            action: key,
             frecency,
         });
     }
-
-    let json = serde_json::to_string(&recents)?;
-    let file_name = recent_file_name();
-    std::fs::write(&file_name, json)?;
-    Ok(())
+    recents.retain(|r| r.frecency.score() >= MIN_FRECENCY);
+    save_history(&recents)
 }
 
 #[derive(Debug, Clone, FromDynamic, ToDynamic)]
@@ -88,11 +177,14 @@ pub struct UserPaletteEntry {
 }
 impl_lua_conversion_dynamic!(UserPaletteEntry);
 
+/// Returns the commands, most frecently used first, along with
+/// the frecency score of each
 fn build_commands(
     gui_window: GuiWin,
     pane: Option<MuxPane>,
     filter_copy_mode: bool,
-) -> Vec<ExpandedCommand> {
+    selection_is_table: bool,
+) -> (Vec<ExpandedCommand>, Vec<f64>) {
     let mut commands = CommandDef::actions_for_palette_and_menubar(&config::configuration());
 
     match config::run_immediate_with_lua_config(|lua| {
@@ -121,7 +213,7 @@ fn build_commands(
                     },
                     action: entry.action,
                     keys: vec![],
-                    menubar: &[],
+                    menubar: vec![],
                     icon: entry.icon.map(Cow::Owned),
                 });
             }
@@ -131,33 +223,21 @@ fn build_commands(
         }
     }
 
-    commands.retain(|cmd| {
-        if filter_copy_mode {
-            !matches!(cmd.action, KeyAssignment::CopyMode(_))
-        } else {
-            true
-        }
+    commands.retain(|cmd| match cmd.action {
+        KeyAssignment::CopyMode(_) => !filter_copy_mode,
+        KeyAssignment::CopySelectionAsTable(_) => selection_is_table,
+        _ => true,
     });
 
-    let mut scores: HashMap<&str, f64> = HashMap::new();
-    let recents = load_recents();
-    if let Ok(recents) = &recents {
-        for r in recents {
-            scores.insert(&r.brief, r.frecency.score());
-        }
-    }
+    let scores = history_scores(&commands);
+    let mut commands: Vec<(ExpandedCommand, f64)> = commands.into_iter().zip(scores).collect();
 
-    commands.sort_by(|a, b| {
-        match (scores.get(&*a.brief), scores.get(&*b.brief)) {
-            // Want descending frecency score, so swap a<->b
-            // for the compare here
-            (Some(a), Some(b)) => match b.partial_cmp(a) {
-                Some(Ordering::Equal) | None => {}
-                Some(ordering) => return ordering,
-            },
-            (Some(_), None) => return Ordering::Less,
-            (None, Some(_)) => return Ordering::Greater,
-            (None, None) => {}
+    commands.sort_by(|(a, a_score), (b, b_score)| {
+        // Want descending frecency score, so swap a<->b
+        // for the compare here
+        match b_score.partial_cmp(a_score) {
+            Some(Ordering::Equal) | None => {}
+            Some(ordering) => return ordering,
         }
 
         match a.menubar.cmp(&b.menubar) {
@@ -166,7 +246,7 @@ fn build_commands(
         }
     });
 
-    commands
+    commands.into_iter().unzip()
 }
 
 #[derive(Debug)]
@@ -176,7 +256,13 @@ struct MatchResult {
 }
 
 impl MatchResult {
-    fn new(row_idx: usize, score: u32, selection: &str, commands: &[ExpandedCommand]) -> Self {
+    fn new(
+        row_idx: usize,
+        score: u32,
+        selection: &str,
+        commands: &[ExpandedCommand],
+        frecency: &[f64],
+    ) -> Self {
         Self {
             row_idx,
             score: if commands[row_idx].brief == selection {
@@ -185,36 +271,130 @@ impl MatchResult {
                 // of candidates with the same score
                 u32::max_value()
             } else {
-                score
+                score.saturating_add(frecency_boost(frecency[row_idx]))
             },
         }
     }
 }
 
-fn compute_matches(selection: &str, commands: &[ExpandedCommand]) -> Vec<usize> {
+fn frecency_boost(score: f64) -> u32 {
+    (score.max(0.).ln_1p() * FRECENCY_BOOST) as u32
+}
+
+/// The text shown for a command, which is also what the
+/// matched characters are highlighted in
+fn command_label(command: &ExpandedCommand) -> String {
+    let group = if command.menubar.is_empty() {
+        String::new()
+    } else {
+        format!("{}: ", command.menubar.join(" | "))
+    };
+
+    // DRY if the brief and doc are the same
+    if command.doc.is_empty()
+        || command.brief.to_ascii_lowercase() == command.doc.to_ascii_lowercase()
+    {
+        format!("{group}{}", command.brief)
+    } else {
+        format!("{group}{}. {}", command.brief, command.doc)
+    }
+}
+
+/// Scores `command` against `pattern`.  The label is matched first,
+/// so that the positions of the matched characters are those of the
+/// text that was scored; failing that, the action is matched, and
+/// there is nothing to highlight.
+fn match_command(pattern: &Pattern, command: &ExpandedCommand) -> Option<(u32, Option<Vec<u32>>)> {
+    let label = command_label(command);
+    if let Some(score) = matcher_score(pattern, &label) {
+        return Some((score, matcher_indices(pattern, &label)));
+    }
+    matcher_score(pattern, &action_key(&command.action)).map(|score| (score, None))
+}
+
+fn compute_matches(
+    selection: &str,
+    commands: &[ExpandedCommand],
+    frecency: &[f64],
+) -> MatchResults {
     if selection.is_empty() {
-        commands.iter().enumerate().map(|(idx, _)| idx).collect()
+        MatchResults {
+            selection: String::new(),
+            matches: commands.iter().enumerate().map(|(idx, _)| idx).collect(),
+            highlights: HashMap::new(),
+        }
     } else {
         let pattern = matcher_pattern(selection);
 
         let start = std::time::Instant::now();
-        let mut scores: Vec<MatchResult> = commands
+        let mut scores: Vec<(MatchResult, Option<Vec<u32>>)> = commands
             .par_iter()
             .enumerate()
             .filter_map(|(row_idx, entry)| {
-                let group = entry.menubar.join(" ");
-                let text = format!("{group}: {}. {} {:?}", entry.brief, entry.doc, entry.action);
-                matcher_score(&pattern, &text)
-                    .map(|score| MatchResult::new(row_idx, score, selection, commands))
+                let (score, highlights) = match_command(&pattern, entry)?;
+                Some((
+                    MatchResult::new(row_idx, score, selection, commands, frecency),
+                    highlights,
+                ))
             })
             .collect();
-        scores.sort_by(|a, b| a.score.cmp(&b.score).reverse());
+        scores.sort_by(|(a, _), (b, _)| a.score.cmp(&b.score).reverse());
         log::trace!("matching took {:?}", start.elapsed());
 
-        scores.iter().map(|result| result.row_idx).collect()
+        let matches: Vec<usize> = scores.iter().map(|(result, _)| result.row_idx).collect();
+        let highlights = scores
+            .into_iter()
+            .filter_map(|(result, highlights)| Some((result.row_idx, highlights?)))
+            .collect();
+
+        MatchResults {
+            selection: selection.to_string(),
+            matches,
+            highlights,
+        }
     }
 }
 
+/// Splits `label` into elements so that the graphemes at
+/// `highlights` are shown in `highlight_color`
+fn highlighted_label(
+    font: &Rc<LoadedFont>,
+    label: &str,
+    highlights: Option<&Vec<u32>>,
+    highlight_color: &InheritableColor,
+) -> Element {
+    let highlights = match highlights {
+        Some(highlights) if !highlights.is_empty() => highlights,
+        _ => return Element::new(font, ElementContent::Text(label.to_string())),
+    };
+
+    let mut runs: Vec<(bool, String)> = vec![];
+    for (idx, grapheme) in label.graphemes(true).enumerate() {
+        let highlighted = highlights.binary_search(&(idx as u32)).is_ok();
+        match runs.last_mut() {
+            Some((prior, text)) if *prior == highlighted => text.push_str(grapheme),
+            _ => runs.push((highlighted, grapheme.to_string())),
+        }
+    }
+
+    let runs = runs
+        .into_iter()
+        .map(|(highlighted, text)| {
+            let element = Element::new(font, ElementContent::Text(text));
+            if highlighted {
+                element.colors(ElementColors {
+                    border: BorderColor::default(),
+                    bg: InheritableColor::Inherited,
+                    text: highlight_color.clone(),
+                })
+            } else {
+                element
+            }
+        })
+        .collect();
+    Element::new(font, ElementContent::Children(runs))
+}
+
 impl CommandPalette {
     pub fn new(term_window: &mut TermWindow) -> Self {
         // Showing the CopyMode actions in the palette is useless
@@ -232,12 +412,28 @@ impl CommandPalette {
             .get_active_pane_or_overlay()
             .map(|pane| MuxPane(pane.pane_id()));
 
-        let commands = build_commands(GuiWin::new(term_window), mux_pane, filter_copy_mode);
+        // Only offer the table conversions when the selection
+        // looks like it spans some columns
+        let selection_is_table = term_window
+            .get_active_pane_or_overlay()
+            .map(|pane| {
+                let text = term_window.selection_text(&pane);
+                crate::termwindow::table_text::parse_table(&text).is_some()
+            })
+            .unwrap_or(false);
+
+        let (commands, frecency) = build_commands(
+            GuiWin::new(term_window),
+            mux_pane,
+            filter_copy_mode,
+            selection_is_table,
+        );
 
         Self {
             element: RefCell::new(None),
             selection: RefCell::new(String::new()),
             commands,
+            frecency,
             matches: RefCell::new(None),
             selected_row: RefCell::new(0),
             top_row: RefCell::new(0),
@@ -284,20 +480,20 @@ impl CommandPalette {
                     .display(DisplayType::Block),
             ];
 
-        for (display_idx, command) in matches
+        let match_color: InheritableColor = term_window
+            .config
+            .command_palette_match_fg_color
+            .to_linear()
+            .into();
+
+        for (display_idx, (command_idx, command)) in matches
             .matches
             .iter()
-            .map(|&idx| &commands[idx])
+            .map(|&idx| (idx, &commands[idx]))
             .enumerate()
             .skip(top_row)
             .take(max_rows_on_screen)
         {
-            let group = if command.menubar.is_empty() {
-                String::new()
-            } else {
-                format!("{}: ", command.menubar.join(" | "))
-            };
-
             let icon = match &command.icon {
                 Some(nf) => NERD_FONTS.get(nf.as_ref()).unwrap_or_else(|| {
                     log::error!("nerdfont {nf} not found in NERD_FONTS");
@@ -329,19 +525,17 @@ impl CommandPalette {
                 (solid_bg_color.clone(), solid_fg_color.clone())
             };
 
-            // DRY if the brief and doc are the same
-            let label = if command.doc.is_empty()
-                || command.brief.to_ascii_lowercase() == command.doc.to_ascii_lowercase()
-            {
-                format!("{group}{}", command.brief)
-            } else {
-                format!("{group}{}. {}", command.brief, command.doc)
-            };
+            let label = highlighted_label(
+                &font,
+                &command_label(command),
+                matches.highlights.get(&command_idx),
+                &match_color,
+            );
 
             let mut row = vec![
                 Element::new(&font, ElementContent::Text(icon.to_string()))
                     .min_width(Some(Dimension::Cells(2.))),
-                Element::new(&font, ElementContent::Text(label)),
+                label,
             ];
 
             if !command.keys.is_empty() {
@@ -630,7 +824,7 @@ impl Modal for CommandPalette {
                     },
                 };
                 let item = &self.commands[alias_idx];
-                if let Err(err) = save_recent(item) {
+                if let Err(err) = record_invocation(item) {
                     log::error!("Error while saving recents: {err:#}");
                 }
                 term_window.cancel_modal();
@@ -676,10 +870,7 @@ impl Modal for CommandPalette {
             .map(|m| m.selection != selection)
             .unwrap_or(true);
         if rebuild_matches {
-            results.replace(MatchResults {
-                selection: selection.to_string(),
-                matches: compute_matches(selection, &self.commands),
-            });
+            results.replace(compute_matches(selection, &self.commands, &self.frecency));
         };
         let matches = results.as_ref().unwrap();
 
@@ -704,3 +895,115 @@ impl Modal for CommandPalette {
         self.element.borrow_mut().take();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn command(brief: &'static str, doc: &'static str, action: KeyAssignment) -> ExpandedCommand {
+        ExpandedCommand {
+            brief: brief.into(),
+            doc: doc.into(),
+            action,
+            keys: vec![],
+            menubar: vec![],
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn action_key_uses_config_representation() {
+        assert_eq!(action_key(&KeyAssignment::Hide), "\"Hide\"");
+        assert_eq!(
+            action_key(&KeyAssignment::ActivateTab(2)),
+            "{\"ActivateTab\": 2}"
+        );
+        assert_ne!(
+            action_key(&KeyAssignment::ActivateTab(1)),
+            action_key(&KeyAssignment::ActivateTab(2))
+        );
+    }
+
+    #[test]
+    fn legacy_history_is_keyed_by_action() {
+        let commands = vec![
+            command("Hide", "", KeyAssignment::Hide),
+            command(
+                "Reload configuration",
+                "",
+                KeyAssignment::ReloadConfiguration,
+            ),
+        ];
+        let legacy = vec![
+            LegacyRecent {
+                brief: "Reload configuration".to_string(),
+                frecency: Frecency::new(),
+            },
+            LegacyRecent {
+                brief: "A command that was since removed".to_string(),
+                frecency: Frecency::new(),
+            },
+        ];
+        let recents = migrate_legacy_history(legacy, &commands);
+        assert_eq!(recents.len(), 1);
+        assert_eq!(
+            recents[0].action,
+            action_key(&KeyAssignment::ReloadConfiguration)
+        );
+    }
+
+    #[test]
+    fn label() {
+        let mut cmd = command("Hide", "hide", KeyAssignment::Hide);
+        assert_eq!(command_label(&cmd), "Hide");
+        cmd.doc = "Hides the window".into();
+        cmd.menubar = vec!["Window".into(), "Misc".into()];
+        assert_eq!(command_label(&cmd), "Window | Misc: Hide. Hides the window");
+    }
+
+    #[test]
+    fn highlights_are_in_the_scored_label() {
+        let mut cmd = command("Reset font size", "", KeyAssignment::ResetFontSize);
+        cmd.menubar = vec!["View".into()];
+
+        let (_, highlights) = match_command(&matcher_pattern("font"), &cmd).unwrap();
+        let label = command_label(&cmd);
+        let highlights = highlights.unwrap();
+        let matched: String = label
+            .graphemes(true)
+            .enumerate()
+            .filter(|(idx, _)| highlights.contains(&(*idx as u32)))
+            .map(|(_, g)| g)
+            .collect();
+        assert_eq!(matched, "font");
+
+        // Matches on the action alone have nothing to highlight
+        let refresh = command("Refresh", "", KeyAssignment::ReloadConfiguration);
+        let (_, highlights) = match_command(&matcher_pattern("reload"), &refresh).unwrap();
+        assert_eq!(highlights, None);
+
+        assert!(match_command(&matcher_pattern("zzz"), &cmd).is_none());
+    }
+
+    #[test]
+    fn frecency_raises_the_rank() {
+        let commands = vec![
+            command("Hide", "", KeyAssignment::Hide),
+            command("Hide too", "", KeyAssignment::ReloadConfiguration),
+        ];
+        let results = compute_matches("hide", &commands, &[0., 0.]);
+        assert_eq!(results.matches, vec![0, 1]);
+        assert_eq!(results.highlights[&1], vec![0, 1, 2, 3]);
+
+        let results = compute_matches("hid", &commands, &[0., 100.]);
+        assert_eq!(results.matches, vec![1, 0]);
+
+        // An exact match on the brief always wins
+        let results = compute_matches("Hide", &commands, &[0., 100.]);
+        assert_eq!(results.matches, vec![0, 1]);
+
+        let results = compute_matches("", &commands, &[0., 100.]);
+        assert_eq!(results.matches, vec![0, 1]);
+        assert!(results.highlights.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Places windows on monitors according to `window_placement_rules`
+//! and the `MoveWindowToMonitor` key assignment.
+use crate::termwindow::TermWindow;
+use config::window::{MonitorSelector, SpawnWindowOn, WindowPlacement, WindowPlacementRule};
+use config::{Dimension, DimensionContext, GeometryOrigin};
+use mux::Mux;
+use window::screen::{ScreenInfo, Screens};
+use window::{Connection, ConnectionOps, RequestedWindowGeometry, WindowOps};
+
+/// Returns the monitors ordered from left to right, and from top
+/// to bottom for monitors that are stacked vertically
+fn monitors_in_order(screens: &Screens) -> Vec<&ScreenInfo> {
+    let mut monitors: Vec<&ScreenInfo> = screens.by_name.values().collect();
+    monitors.sort_by_key(|info| (info.rect.origin.x, info.rect.origin.y));
+    monitors
+}
+
+fn select_monitor<'a>(screens: &'a Screens, selector: &MonitorSelector) -> Option<&'a ScreenInfo> {
+    match selector {
+        MonitorSelector::Index(idx) => monitors_in_order(screens).get(*idx).copied(),
+        MonitorSelector::Name(name) => screens.by_name.get(name),
+    }
+}
+
+/// Computes the (x, y, width, height) of a window of the given size
+/// when placed within a monitor of `monitor_width` x `monitor_height`.
+/// x and y are relative to the top left of the monitor.
+fn placement_rect(
+    placement: WindowPlacement,
+    monitor_width: usize,
+    monitor_height: usize,
+    width: usize,
+    height: usize,
+) -> (usize, usize, usize, usize) {
+    let half_width = monitor_width / 2;
+    let half_height = monitor_height / 2;
+    match placement {
+        WindowPlacement::Centered => {
+            let width = width.min(monitor_width);
+            let height = height.min(monitor_height);
+            (
+                (monitor_width - width) / 2,
+                (monitor_height - height) / 2,
+                width,
+                height,
+            )
+        }
+        WindowPlacement::LeftHalf => (0, 0, half_width, monitor_height),
+        WindowPlacement::RightHalf => (half_width, 0, monitor_width - half_width, monitor_height),
+        WindowPlacement::TopHalf => (0, 0, monitor_width, half_height),
+        WindowPlacement::BottomHalf => {
+            (0, half_height, monitor_width, monitor_height - half_height)
+        }
+        WindowPlacement::Fill => (0, 0, monitor_width, monitor_height),
+    }
+}
+
+/// Returns the geometry for a window of `width` x `height` placed on
+/// the monitor identified by `selector`, or None if there is no such
+/// monitor.
+pub fn geometry_on_monitor(
+    selector: &MonitorSelector,
+    placement: WindowPlacement,
+    width: Dimension,
+    height: Dimension,
+) -> Option<RequestedWindowGeometry> {
+    let conn = Connection::get()?;
+    let screens = conn.screens().ok()?;
+    let monitor = select_monitor(&screens, selector)?;
+
+    let dpi = monitor.effective_dpi.unwrap_or_else(|| conn.default_dpi()) as f32;
+    let monitor_width = monitor.rect.width().max(0) as usize;
+    let monitor_height = monitor.rect.height().max(0) as usize;
+    let width = width.evaluate_as_pixels(DimensionContext {
+        dpi,
+        pixel_max: monitor_width as f32,
+        pixel_cell: monitor_width as f32,
+    });
+    let height = height.evaluate_as_pixels(DimensionContext {
+        dpi,
+        pixel_max: monitor_height as f32,
+        pixel_cell: monitor_height as f32,
+    });
+
+    let (x, y, width, height) = placement_rect(
+        placement,
+        monitor_width,
+        monitor_height,
+        width as usize,
+        height as usize,
+    );
+    Some(RequestedWindowGeometry {
+        width: Dimension::Pixels(width as f32),
+        height: Dimension::Pixels(height as f32),
+        x: Some(Dimension::Pixels(x as f32)),
+        y: Some(Dimension::Pixels(y as f32)),
+        origin: GeometryOrigin::Named(monitor.name.clone()),
+        explicit_size: true,
+        persistence_key: None,
+    })
+}
+
+/// Returns the geometry from the first rule that matches `workspace`
+/// and whose monitor is currently connected
+pub fn geometry_for_rules(
+    rules: &[WindowPlacementRule],
+    workspace: &str,
+    width: Dimension,
+    height: Dimension,
+) -> Option<RequestedWindowGeometry> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches_workspace(workspace))
+        .find_map(|rule| geometry_on_monitor(&rule.monitor, rule.position, width, height))
+}
+
+/// Returns the geometry that centers a window of `width` x `height`
+/// on the monitor selected by `spawn_window_on`, or None to leave its
+/// placement to the system
+pub fn geometry_for_spawn_window_on(
+    spawn_window_on: &SpawnWindowOn,
+    width: Dimension,
+    height: Dimension,
+) -> Option<RequestedWindowGeometry> {
+    let conn = Connection::get()?;
+    let name = match spawn_window_on {
+        SpawnWindowOn::ActiveScreen => return None,
+        SpawnWindowOn::PrimaryScreen => conn.screens().ok()?.main.name,
+        SpawnWindowOn::CursorScreen => {
+            let point = conn.mouse_position()?;
+            let screens = conn.screens().ok()?;
+            let monitor = screens
+                .by_name
+                .values()
+                .find(|info| info.rect.contains(point))?;
+            monitor.name.clone()
+        }
+        SpawnWindowOn::ScreenNamed(name) => name.clone(),
+    };
+    let geometry = geometry_on_monitor(
+        &MonitorSelector::Name(name.clone()),
+        WindowPlacement::Centered,
+        width,
+        height,
+    );
+    if geometry.is_none() {
+        log::warn!("spawn_window_on: there is no monitor named {name}");
+    }
+    geometry
+}
+
+impl TermWindow {
+    fn current_window_size(&self) -> (Dimension, Dimension) {
+        (
+            Dimension::Pixels(self.dimensions.pixel_width as f32),
+            Dimension::Pixels(self.dimensions.pixel_height as f32),
+        )
+    }
+
+    /// Re-applies `window_placement_rules` to this window; called
+    /// when the set of monitors changes
+    pub fn apply_window_placement_rules(&mut self) {
+        if self.config.window_placement_rules.is_empty() {
+            return;
+        }
+        let window = match self.window.as_ref() {
+            Some(window) => window,
+            None => return,
+        };
+        let workspace = match Mux::get().get_window(self.mux_window_id) {
+            Some(mux_window) => mux_window.get_workspace().to_string(),
+            None => return,
+        };
+        let (width, height) = self.current_window_size();
+        if let Some(geometry) = geometry_for_rules(
+            &self.config.window_placement_rules,
+            &workspace,
+            width,
+            height,
+        ) {
+            window.set_window_geometry(geometry);
+        }
+    }
+
+    pub fn move_window_to_monitor(&mut self, monitor: usize) {
+        let window = match self.window.as_ref() {
+            Some(window) => window,
+            None => return,
+        };
+        let (width, height) = self.current_window_size();
+        match geometry_on_monitor(
+            &MonitorSelector::Index(monitor),
+            WindowPlacement::Centered,
+            width,
+            height,
+        ) {
+            Some(geometry) => window.set_window_geometry(geometry),
+            None => log::warn!("MoveWindowToMonitor: there is no monitor {monitor}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placement() {
+        assert_eq!(
+            placement_rect(WindowPlacement::Centered, 1920, 1080, 800, 600),
+            (560, 240, 800, 600)
+        );
+        assert_eq!(
+            placement_rect(WindowPlacement::Centered, 1280, 800, 1600, 600),
+            (0, 100, 1280, 600)
+        );
+        assert_eq!(
+            placement_rect(WindowPlacement::RightHalf, 1921, 1080, 800, 600),
+            (960, 0, 961, 1080)
+        );
+        assert_eq!(
+            placement_rect(WindowPlacement::BottomHalf, 1920, 1080, 800, 600),
+            (0, 540, 1920, 540)
+        );
+    }
+}
@@ -93,7 +93,12 @@ impl crate::TermWindow {
         let tab_padding_h = Dimension::Pixels((0.5 * metrics.cell_size.width as f32) + 4.0);
 
         let item_to_elem = |item: &TabEntry| -> Element {
-            let element = Element::with_line(&font, &item.title, palette);
+            let element = match item.item {
+                TabBarItem::Tab { tab_idx, .. } if !item.regions.is_empty() => {
+                    tab_title_element(&font, item, tab_idx, palette)
+                }
+                _ => Element::with_line(&font, &item.title, palette),
+            };
 
             let bg_color = item
                 .title
@@ -115,7 +120,10 @@ impl crate::TermWindow {
             let active_tab = colors.active_tab();
 
             match item.item {
-                TabBarItem::RightStatus | TabBarItem::LeftStatus | TabBarItem::None => element
+                TabBarItem::RightStatus
+                | TabBarItem::LeftStatus
+                | TabBarItem::None
+                | TabBarItem::TabRegion { .. } => element
                     .item_type(UIItemType::TabBar(TabBarItem::None))
                     .line_height(Some(1.75))
                     .margin(BoxDimension {
@@ -168,6 +176,32 @@ impl crate::TermWindow {
                     bg: new_tab_hover.bg_color.to_linear().into(),
                     text: new_tab_hover.fg_color.to_linear().into(),
                 })),
+                TabBarItem::CustomButton(_) => element
+                    .vertical_align(VerticalAlign::Middle)
+                    .item_type(UIItemType::TabBar(item.item.clone()))
+                    .margin(BoxDimension {
+                        left: Dimension::Cells(0.5),
+                        right: Dimension::Cells(0.),
+                        top: Dimension::Cells(0.2),
+                        bottom: Dimension::Cells(0.),
+                    })
+                    .padding(BoxDimension {
+                        left: Dimension::Cells(0.25),
+                        right: Dimension::Cells(0.25),
+                        top: Dimension::Cells(0.2),
+                        bottom: Dimension::Cells(0.25),
+                    })
+                    .border(BoxDimension::new(Dimension::Pixels(1.)))
+                    .colors(ElementColors {
+                        border: BorderColor::default(),
+                        bg: new_tab.bg_color.to_linear().into(),
+                        text: new_tab.fg_color.to_linear().into(),
+                    })
+                    .hover_colors(Some(ElementColors {
+                        border: BorderColor::default(),
+                        bg: new_tab_hover.bg_color.to_linear().into(),
+                        text: new_tab_hover.fg_color.to_linear().into(),
+                    })),
                 TabBarItem::Tab { active, .. } if active => element
                     .vertical_align(VerticalAlign::Bottom)
                     .item_type(UIItemType::TabBar(item.item.clone()))
@@ -382,7 +416,14 @@ impl crate::TermWindow {
             if self.config.integrated_title_button_style == IntegratedTitleButtonStyle::MacOsNative
             {
                 if !self.window_state.contains(window::WindowState::FULL_SCREEN) {
-                    Dimension::Pixels(70.0)
+                    // The native buttons start 7 points from the left edge
+                    // unless they have been moved
+                    let offset = self
+                        .config
+                        .macos_window_button_position
+                        .map(|position| (position.x as f32 - 7.0).max(0.0))
+                        .unwrap_or(0.0);
+                    Dimension::Pixels(70.0 + offset)
                 } else {
                     Dimension::Cells(0.5)
                 }
@@ -474,6 +515,48 @@ impl crate::TermWindow {
     }
 }
 
+/// Builds the title of a tab that has clickable regions, giving each
+/// region its own element so that it can be hit-tested
+fn tab_title_element(
+    font: &Rc<LoadedFont>,
+    item: &TabEntry,
+    tab_idx: usize,
+    palette: &ColorPalette,
+) -> Element {
+    let len = item.title.len();
+    let mut kids = vec![];
+    let mut x = 0;
+    for (region_idx, region) in item.regions.iter().enumerate() {
+        let start = region.x.min(len);
+        let end = (region.x + region.width).min(len);
+        if start > x {
+            kids.push(Element::with_line(
+                font,
+                &item.title.columns_as_line(x..start),
+                palette,
+            ));
+        }
+        if end > start {
+            kids.push(
+                Element::with_line(font, &item.title.columns_as_line(start..end), palette)
+                    .item_type(UIItemType::TabBar(TabBarItem::TabRegion {
+                        tab_idx,
+                        region: region_idx,
+                    })),
+            );
+        }
+        x = end.max(x);
+    }
+    if x < len {
+        kids.push(Element::with_line(
+            font,
+            &item.title.columns_as_line(x..len),
+            palette,
+        ));
+    }
+    Element::new(font, ElementContent::Children(kids))
+}
+
 fn make_x_button(
     font: &Rc<LoadedFont>,
     metrics: &RenderMetrics,
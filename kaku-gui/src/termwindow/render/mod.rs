@@ -13,8 +13,8 @@ use ::window::bitmaps::{TextureCoord, TextureRect, TextureSize};
 use ::window::{DeadKeyStatus, PointF, RectF, SizeF, WindowOps};
 use anyhow::{anyhow, Context};
 use config::{
-    BoldBrightening, ConfigHandle, DimensionContext, HorizontalWindowContentAlignment, TextStyle,
-    VerticalWindowContentAlignment, VisualBellTarget,
+    BoldBrightening, ConfigHandle, DimensionContext, HorizontalWindowContentAlignment,
+    MissingGlyphStyle, TextStyle, VerticalWindowContentAlignment, VisualBellTarget,
 };
 use euclid::num::Zero;
 use mux::pane::{Pane, PaneId};
@@ -286,6 +286,37 @@ impl crate::TermWindow {
         Ok(quad)
     }
 
+    /// Draws the outline of `rect`, `thickness` pixels wide and lying
+    /// within `rect`, as four filled rectangles
+    pub fn outlined_rectangle(
+        &self,
+        layers: &mut TripleLayerQuadAllocator,
+        layer_num: usize,
+        rect: RectF,
+        thickness: f32,
+        color: LinearRgba,
+    ) -> anyhow::Result<()> {
+        for edge in [
+            euclid::rect(rect.min_x(), rect.min_y(), rect.width(), thickness),
+            euclid::rect(
+                rect.min_x(),
+                rect.max_y() - thickness,
+                rect.width(),
+                thickness,
+            ),
+            euclid::rect(rect.min_x(), rect.min_y(), thickness, rect.height()),
+            euclid::rect(
+                rect.max_x() - thickness,
+                rect.min_y(),
+                thickness,
+                rect.height(),
+            ),
+        ] {
+            self.filled_rectangle(layers, layer_num, edge, color)?;
+        }
+        Ok(())
+    }
+
     pub fn poly_quad<'a>(
         &self,
         layers: &'a mut TripleLayerQuadAllocator,
@@ -742,24 +773,29 @@ impl crate::TermWindow {
         let mut glyphs = Vec::with_capacity(infos.len());
         let mut iter = infos.iter().peekable();
         while let Some(info) = iter.next() {
-            if self.config.custom_block_glyphs {
-                if info.only_char.and_then(BlockKey::from_char).is_some() {
-                    // Don't bother rendering the glyph from the font, as it can
-                    // have incorrect advance metrics.
-                    // Instead, just use our pixel-perfect cell metrics
-                    glyphs.push(Rc::new(CachedGlyph {
-                        brightness_adjust: 1.0,
-                        has_color: false,
-                        texture: None,
-                        x_advance: PixelLength::new(metrics.cell_size.width as f64),
-                        x_offset: PixelLength::zero(),
-                        y_offset: PixelLength::zero(),
-                        bearing_x: PixelLength::zero(),
-                        bearing_y: PixelLength::zero(),
-                        scale: 1.0,
-                    }));
-                    continue;
-                }
+            let style_for_missing = self.config.missing_glyph_style;
+            let is_custom = info
+                .only_char
+                .and_then(|c| BlockKey::for_char(c, &self.config))
+                .is_some()
+                || BlockKey::for_missing_glyph(info, style_for_missing).is_some()
+                || (style_for_missing == MissingGlyphStyle::Blank && BlockKey::is_missing(info));
+            if is_custom {
+                // Don't bother rendering the glyph from the font, as it can
+                // have incorrect advance metrics.
+                // Instead, just use our pixel-perfect cell metrics
+                glyphs.push(Rc::new(CachedGlyph {
+                    brightness_adjust: 1.0,
+                    has_color: false,
+                    texture: None,
+                    x_advance: PixelLength::new(metrics.cell_size.width as f64),
+                    x_offset: PixelLength::zero(),
+                    y_offset: PixelLength::zero(),
+                    bearing_x: PixelLength::zero(),
+                    bearing_y: PixelLength::zero(),
+                    scale: 1.0,
+                }));
+                continue;
             }
 
             let followed_by_space = match iter.peek() {
@@ -804,16 +840,40 @@ impl crate::TermWindow {
                 let window = self.window.as_ref().unwrap().clone();
 
                 let presentation_width = PresentationWidth::with_cluster(&cluster);
+                let disk_cache_size = self.config.persistent_shape_cache_size;
+                let fingerprint = font.shaping_fingerprint();
+                let run_key = crate::diskshapecache::run_key(cluster);
 
-                match font.shape(
+                let shaped = match crate::diskshapecache::lookup(
+                    fingerprint,
+                    run_key,
                     &cluster.text,
-                    move || window.notify(TermWindowNotif::InvalidateShapeCache),
-                    BlockKey::filter_out_synthetic,
-                    Some(cluster.presentation),
-                    cluster.direction,
-                    None, // FIXME: need more paragraph context
-                    Some(&presentation_width),
+                    disk_cache_size,
                 ) {
+                    Some(info) => Ok(info),
+                    None => font
+                        .shape(
+                            &cluster.text,
+                            move || window.notify(TermWindowNotif::InvalidateShapeCache),
+                            BlockKey::filter_out_synthetic,
+                            Some(cluster.presentation),
+                            cluster.direction,
+                            None, // FIXME: need more paragraph context
+                            Some(&presentation_width),
+                        )
+                        .map(|info| {
+                            crate::diskshapecache::store(
+                                fingerprint,
+                                run_key,
+                                &cluster.text,
+                                &info,
+                                disk_cache_size,
+                            );
+                            info
+                        }),
+                };
+
+                match shaped {
                     Ok(info) => {
                         let glyphs = self.glyph_infos_to_glyphs(
                             &style,
@@ -822,7 +882,7 @@ impl crate::TermWindow {
                             &font,
                             metrics,
                         )?;
-                        let shaped = Rc::new(ShapedInfo::process(&info, &glyphs));
+                        let shaped = Rc::new(ShapedInfo::process(&info, &glyphs, &self.config));
 
                         self.shape_cache
                             .borrow_mut()
@@ -1,11 +1,12 @@
 use crate::termwindow::box_model::*;
-use crate::termwindow::{DimensionContext, RenderFrame, TermWindowNotif};
+use crate::termwindow::{DimensionContext, TermWindowNotif};
 use crate::utilsprites::RenderMetrics;
 use ::window::bitmaps::atlas::OutOfTextureSpace;
 use ::window::WindowOps;
 use anyhow::Context;
-use config::Dimension;
+use config::{Dimension, PaneIndexBadges};
 use smol::Timer;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 use wezterm_font::ClearShapeCache;
 use window::color::LinearRgba;
@@ -17,8 +18,27 @@ pub enum AllowImage {
     No,
 }
 
+/// Everything that determines the appearance of a frame other than
+/// its quads.  If neither these nor the quads have changed since the
+/// last frame was drawn, then that frame is still correct and there
+/// is no need to draw another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInputs {
+    dimensions: ::window::Dimensions,
+    config_generation: usize,
+    /// Identifies the texture atlas, which is replaced rather than
+    /// modified when it is cleared or resized
+    atlas: usize,
+}
+
 impl crate::TermWindow {
-    pub fn paint_impl(&mut self, frame: &mut RenderFrame) -> anyhow::Result<()> {
+    /// Builds the quads for the window and then, unless the result
+    /// would be identical to the frame already on screen, calls `draw`
+    /// to render them
+    pub fn paint_impl(
+        &mut self,
+        draw: impl FnOnce(&mut Self) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
         self.num_frames += 1;
         // If nothing on screen needs animating, then we can avoid
         // invalidating as frequently
@@ -27,6 +47,7 @@ impl crate::TermWindow {
         self.allow_images = AllowImage::Yes;
 
         let start = Instant::now();
+        self.last_paint = start;
 
         {
             let diff = start.duration_since(self.last_fps_check_time);
@@ -38,6 +59,11 @@ impl crate::TermWindow {
             }
         }
 
+        self.acknowledge_pane_monitors();
+        // One-shot, until_unknown and expired activations leave the
+        // key table stack without passing through update_title
+        self.check_key_table_stack_changed();
+
         'pass: for pass in 0.. {
             match self.paint_pass() {
                 Ok(_) => match self.render_state.as_mut().unwrap().allocated_more_quads() {
@@ -110,7 +136,24 @@ impl crate::TermWindow {
 
         log::debug!("paint_impl before call_draw elapsed={:?}", start.elapsed());
 
-        self.call_draw(frame)?;
+        let gl_state = self.render_state.as_ref().unwrap();
+        let inputs = FrameInputs {
+            dimensions: self.dimensions,
+            config_generation: self.config.generation(),
+            atlas: Rc::as_ptr(&gl_state.glyph_cache.borrow().atlas.texture()) as *const () as usize,
+        };
+        let quads_changed = gl_state.upload_quads().unwrap_or_else(|err| {
+            log::error!("upload_quads: {:#}", err);
+            true
+        });
+
+        if quads_changed || self.presented_frame.as_ref() != Some(&inputs) {
+            self.presented_frame.take();
+            draw(self)?;
+            self.presented_frame.replace(inputs);
+        } else {
+            metrics::histogram!("gui.paint.skipped.rate").record(1.);
+        }
         self.last_frame_duration = start.elapsed();
         log::debug!(
             "paint_impl elapsed={:?}, fps={}",
@@ -125,6 +168,9 @@ impl crate::TermWindow {
         // invalidate the viewport when the next frame is due
         if self.focused.is_some() {
             if let Some(next_due) = *self.has_animation.borrow() {
+                // Don't schedule frames sooner than the frame rate allows
+                let min_interval = Duration::from_millis(1000 / self.effective_max_fps());
+                let next_due = next_due.max(start + min_interval);
                 let prior = self.scheduled_animation.borrow_mut().take();
                 match prior {
                     Some(prior) if prior <= next_due => {
@@ -391,8 +437,117 @@ impl crate::TermWindow {
         self.paint_window_borders(&mut layers)
             .context("paint_window_borders")?;
         drop(layers);
+        self.paint_pane_index_badges()
+            .context("paint_pane_index_badges")?;
         self.paint_modal().context("paint_modal")?;
         self.paint_toast().context("paint_toast")?;
+        self.paint_resize_mode_hint()
+            .context("paint_resize_mode_hint")?;
+        self.paint_render_hud().context("paint_render_hud")?;
+
+        Ok(())
+    }
+
+    /// Render the statistics overlay toggled by `ToggleRenderHud`
+    /// in the top right corner of the window
+    pub fn paint_render_hud(&mut self) -> anyhow::Result<()> {
+        if !self.show_render_hud {
+            return Ok(());
+        }
+
+        let stats = crate::stats::render_stats();
+        let ratio = |r: Option<f64>| match r {
+            Some(r) => format!("{:.1}%", r * 100.),
+            None => "-".to_string(),
+        };
+        let lines = [
+            format!("fps      {} (+{} skipped)", stats.fps, stats.skipped_fps),
+            format!(
+                "frame    p50 {:.2}ms p95 {:.2}ms",
+                stats.frame_time_p50_ms, stats.frame_time_p95_ms
+            ),
+            format!(
+                "shaping  p50 {:.2}ms p95 {:.2}ms",
+                stats.shaping_time_p50_ms, stats.shaping_time_p95_ms
+            ),
+            format!("glyphs   {} hit", ratio(stats.glyph_cache_hit_ratio)),
+            format!("shapes   {} hit", ratio(stats.shape_cache_hit_ratio)),
+            format!("pty      {} KiB/s", stats.pty_read_bytes_per_sec / 1024),
+        ];
+
+        let font = self.fonts.pane_select_font()?;
+        let metrics = RenderMetrics::with_font_metrics(&font.metrics());
+        let bg_color = LinearRgba(0., 0., 0., 0.75);
+        let text_color = LinearRgba(1., 1., 1., 1.);
+
+        let width_cells = lines.iter().map(|l| l.len()).max().unwrap_or(0) as f32 + 2.;
+        let element = Element::new(
+            &font,
+            ElementContent::Children(
+                lines
+                    .iter()
+                    .map(|line| {
+                        Element::new(&font, ElementContent::Text(line.clone()))
+                            .display(DisplayType::Block)
+                    })
+                    .collect(),
+            ),
+        )
+        .colors(ElementColors {
+            border: BorderColor::new(bg_color.into()),
+            bg: bg_color.into(),
+            text: text_color.into(),
+        })
+        .padding(BoxDimension::new(Dimension::Cells(0.5)))
+        .min_width(Some(Dimension::Cells(width_cells)));
+
+        let dimensions = self.dimensions;
+        let border = self.get_os_border();
+        let tab_bar_height = if self.show_tab_bar && !self.config.tab_bar_at_bottom {
+            self.tab_bar_pixel_height().unwrap_or(0.)
+        } else {
+            0.
+        };
+        let width = width_cells * metrics.cell_size.width as f32;
+        let height = (lines.len() as f32 + 1.) * metrics.cell_size.height as f32;
+        let x = dimensions.pixel_width as f32
+            - width
+            - metrics.cell_size.width as f32
+            - border.right.get() as f32;
+        let y = border.top.get() as f32 + tab_bar_height + metrics.cell_size.height as f32 * 0.5;
+
+        let computed = self.compute_element(
+            &LayoutContext {
+                height: DimensionContext {
+                    dpi: dimensions.dpi as f32,
+                    pixel_max: dimensions.pixel_height as f32,
+                    pixel_cell: metrics.cell_size.height as f32,
+                },
+                width: DimensionContext {
+                    dpi: dimensions.dpi as f32,
+                    pixel_max: dimensions.pixel_width as f32,
+                    pixel_cell: metrics.cell_size.width as f32,
+                },
+                bounds: euclid::rect(x, y, width, height),
+                metrics: &metrics,
+                gl_state: self.render_state.as_ref().unwrap(),
+                zindex: 120,
+            },
+            &element,
+        )?;
+
+        let gl_state = self.render_state.as_ref().unwrap();
+        self.render_element(&computed, gl_state, None)?;
+
+        // Keep the figures current while the HUD is shown
+        let next = Instant::now() + Duration::from_millis(500);
+        let mut anim = self.has_animation.borrow_mut();
+        match *anim {
+            Some(existing) if existing <= next => {}
+            _ => {
+                *anim = Some(next);
+            }
+        }
 
         Ok(())
     }
@@ -489,4 +644,97 @@ impl crate::TermWindow {
 
         Ok(())
     }
+
+    fn should_show_pane_index_badges(&self) -> bool {
+        match self.config.pane_index_badges {
+            PaneIndexBadges::Never => false,
+            PaneIndexBadges::Always => true,
+            PaneIndexBadges::WhileModifiersHeld => {
+                let (mods, _leds) = self.current_modifier_and_leds;
+                let wanted = self.config.pane_index_badge_modifiers;
+                !wanted.is_empty() && mods.remove_positional_mods() == wanted
+            }
+        }
+    }
+
+    /// Renders a small badge with the index of each pane in its top
+    /// right corner, so that the target of `ActivatePaneByIndex` is
+    /// apparent at a glance
+    pub fn paint_pane_index_badges(&mut self) -> anyhow::Result<()> {
+        if !self.should_show_pane_index_badges() || self.get_modal().is_some() {
+            return Ok(());
+        }
+        let panes = self.get_panes_to_render();
+        if panes.len() < 2 {
+            return Ok(());
+        }
+
+        let font = self.fonts.pane_select_font()?;
+        let metrics = RenderMetrics::with_font_metrics(&font.metrics());
+        let top_bar_height = if self.show_tab_bar && !self.config.tab_bar_at_bottom {
+            self.tab_bar_pixel_height()?
+        } else {
+            0.
+        };
+        let (padding_left, padding_top) = self.padding_left_top();
+        let border = self.get_os_border();
+        let top_pixel_y = top_bar_height + padding_top + border.top.get() as f32;
+        let cell_width = self.render_metrics.cell_size.width as f32;
+        let cell_height = self.render_metrics.cell_size.height as f32;
+        let dimensions = self.dimensions;
+
+        for pos in panes {
+            // Show the digit of the key that activates the pane, which
+            // is one-based regardless of tab_and_split_indices_are_zero_based
+            let label = (pos.index + 1).to_string();
+            let element = Element::new(&font, ElementContent::Text(label.clone()))
+                .colors(ElementColors {
+                    border: BorderColor::new(self.config.pane_select_bg_color.to_linear().into()),
+                    bg: self.config.pane_select_bg_color.to_linear().into(),
+                    text: self.config.pane_select_fg_color.to_linear().into(),
+                })
+                .padding(BoxDimension {
+                    left: Dimension::Cells(0.25),
+                    right: Dimension::Cells(0.25),
+                    top: Dimension::Cells(0.),
+                    bottom: Dimension::Cells(0.),
+                })
+                .border(BoxDimension::new(Dimension::Pixels(1.)));
+
+            let badge_width = (label.len() as f32 + 0.5) * metrics.cell_size.width as f32;
+            let badge_height = metrics.cell_size.height as f32;
+            let right_x =
+                padding_left + (pos.left + pos.width) as f32 * cell_width - badge_width - 2.;
+
+            let computed = self.compute_element(
+                &LayoutContext {
+                    height: DimensionContext {
+                        dpi: dimensions.dpi as f32,
+                        pixel_max: dimensions.pixel_height as f32,
+                        pixel_cell: metrics.cell_size.height as f32,
+                    },
+                    width: DimensionContext {
+                        dpi: dimensions.dpi as f32,
+                        pixel_max: dimensions.pixel_width as f32,
+                        pixel_cell: metrics.cell_size.width as f32,
+                    },
+                    bounds: euclid::rect(
+                        right_x.max(padding_left),
+                        top_pixel_y + pos.top as f32 * cell_height + 2.,
+                        badge_width,
+                        badge_height,
+                    ),
+                    metrics: &metrics,
+                    gl_state: self.render_state.as_ref().unwrap(),
+                    zindex: 100,
+                },
+                &element,
+            )?;
+
+            let gl_state = self.render_state.as_ref().unwrap();
+            self.render_element(&computed, gl_state, None)?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
+use crate::overlay::CopyOverlay;
 use crate::quad::{HeapQuadAllocator, QuadTrait, TripleLayerQuadAllocator};
 use crate::selection::SelectionRange;
 use crate::termwindow::box_model::*;
@@ -13,8 +14,9 @@ use config::{DimensionContext, VisualBellTarget};
 use mux::pane::{PaneId, WithPaneLines};
 use mux::renderable::{RenderableDimensions, StableCursorPosition};
 use mux::tab::PositionedPane;
+use mux::Mux;
 use ordered_float::NotNan;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use wezterm_dynamic::Value;
 use wezterm_term::color::{ColorAttribute, ColorPalette};
 use wezterm_term::{Line, StableRowIndex};
@@ -93,8 +95,10 @@ impl crate::TermWindow {
         let white_space = gl_state.util_sprites.white_space.texture_coords();
         let filled_box = gl_state.util_sprites.filled_box.texture_coords();
 
-        let window_is_transparent =
-            !self.window_background.is_empty() || config.window_background_opacity != 1.0;
+        let has_pane_background = self.pane_has_background(pane_id);
+        let window_is_transparent = !self.window_background.is_empty()
+            || has_pane_background
+            || config.window_background_opacity != 1.0;
 
         let default_bg = palette
             .resolve_bg(ColorAttribute::Default)
@@ -192,6 +196,20 @@ impl crate::TermWindow {
             });
         }
 
+        if has_pane_background {
+            // Background layers set on this pane or its tab, drawn over
+            // the window background and clipped to the pane
+            let top = current_viewport.unwrap_or(dims.physical_top);
+            self.render_pane_background(
+                layers,
+                pane_id,
+                palette.background.to_linear(),
+                background_rect,
+                top,
+            )
+            .context("render_pane_background")?;
+        }
+
         {
             // If the bell is ringing, we draw another background layer over the
             // top of this in the configured bell color
@@ -239,6 +257,40 @@ impl crate::TermWindow {
                     Some(config.inactive_pane_hsb)
                 });
             }
+
+            // Or outline just this pane, over the top of its content
+            if let Some(intensity) = self.get_intensity_if_bell_target_ringing(
+                &pos.pane,
+                &config,
+                VisualBellTarget::Border,
+            ) {
+                let LinearRgba(r, g, b, _) = config
+                    .resolved_palette
+                    .visual_bell
+                    .as_deref()
+                    .unwrap_or(&palette.foreground)
+                    .to_linear();
+                let color = LinearRgba::with_components(r, g, b, intensity);
+                let thickness = (cell_width / 4.).round().max(1.);
+                self.outlined_rectangle(layers, 2, background_rect, thickness, color)
+                    .context("outlined_rectangle for bell border")?;
+            }
+        }
+
+        if pos.is_active {
+            self.paint_zoom_indicator(pos, background_rect, &palette, layers)
+                .context("paint_zoom_indicator")?;
+        }
+
+        // Outline the panes that take part in broadcast input, so that
+        // it is apparent where typing will go
+        let mux = Mux::get();
+        let pane_id = pos.pane.pane_id();
+        if mux.is_pane_broadcasting_input(pane_id) && !mux.is_pane_input_locked(pane_id) {
+            let color = palette.colors.0[3].to_linear().mul_alpha(0.6);
+            let thickness = (cell_width / 8.).round().max(1.);
+            self.outlined_rectangle(layers, 2, background_rect, thickness, color)
+                .context("outlined_rectangle for broadcast input")?;
         }
 
         // TODO: we only have a single scrollbar in a single position.
@@ -251,20 +303,21 @@ impl crate::TermWindow {
 
             let min_height = self.min_scroll_bar_height();
 
+            let max_thumb_height = self
+                .dimensions
+                .pixel_height
+                .saturating_sub(thumb_y_offset + border.bottom.get() + bottom_bar_height as usize);
             let info = ScrollHit::thumb(
                 &*pos.pane,
                 current_viewport,
-                self.dimensions.pixel_height.saturating_sub(
-                    thumb_y_offset + border.bottom.get() + bottom_bar_height as usize,
-                ),
+                max_thumb_height,
                 min_height as usize,
             );
             let abs_thumb_top = thumb_y_offset + info.top;
             let thumb_size = info.height;
-            let color = palette.scrollbar_thumb.to_linear();
+            let mut color = palette.scrollbar_thumb.to_linear();
 
             // Adjust the scrollbar thumb position
-            let config = &self.config;
             let padding = self.effective_right_padding(&config) as f32;
 
             let thumb_x = self.dimensions.pixel_width - padding as usize - border.right.get();
@@ -295,6 +348,83 @@ impl crate::TermWindow {
                 item_type: UIItemType::BelowScrollThumb,
             });
 
+            if config.scroll_bar_minimap {
+                let cell_height = self.render_metrics.cell_size.height.max(1) as usize;
+                let bucket_height = (cell_height / 4).max(2);
+                let buckets = self
+                    .get_minimap(&pos.pane, &palette, max_thumb_height, bucket_height)
+                    .to_vec();
+                let num_buckets = buckets.len().max(1) as f32;
+                let bucket_size = max_thumb_height as f32 / num_buckets;
+                for (idx, bucket_color) in buckets.into_iter().enumerate() {
+                    if let Some(bucket_color) = bucket_color {
+                        self.filled_rectangle(
+                            layers,
+                            1,
+                            euclid::rect(
+                                thumb_x as f32,
+                                thumb_y_offset as f32 + idx as f32 * bucket_size,
+                                padding,
+                                bucket_size.ceil(),
+                            ),
+                            bucket_color.to_linear().mul_alpha(0.6),
+                        )
+                        .context("filled_rectangle")?;
+                    }
+                }
+                // Let the minimap show through the thumb
+                color = color.mul_alpha(0.5);
+            }
+
+            if config.scroll_bar_markers {
+                let marker_height = 2.;
+                let mut markers = vec![];
+
+                let prompt_color = config
+                    .resolved_palette
+                    .scrollbar_prompt_marker
+                    .as_deref()
+                    .unwrap_or(&palette.colors.0[4])
+                    .to_linear();
+                for row in self.get_semantic_prompt_zones(&pos.pane).to_vec() {
+                    markers.push((row, prompt_color));
+                }
+
+                if let Some(copy_overlay) = pos.pane.downcast_ref::<CopyOverlay>() {
+                    let search_color = config
+                        .resolved_palette
+                        .scrollbar_search_marker
+                        .as_deref()
+                        .unwrap_or(&palette.colors.0[3])
+                        .to_linear();
+                    for row in copy_overlay.search_result_rows() {
+                        markers.push((row, search_color));
+                    }
+                    // Drawn last, so that it stays visible among the others
+                    if let Some(row) = copy_overlay.active_search_result_row() {
+                        markers.push((row, palette.colors.0[11].to_linear()));
+                    }
+                }
+
+                for (row, marker_color) in markers {
+                    let offset =
+                        ScrollHit::row_to_track_offset(&*pos.pane, row, max_thumb_height) as f32;
+                    self.filled_rectangle(
+                        layers,
+                        2,
+                        euclid::rect(
+                            thumb_x as f32,
+                            thumb_y_offset as f32
+                                + offset.min(max_thumb_height as f32 - marker_height),
+                            padding,
+                            marker_height,
+                        ),
+                        marker_color,
+                    )
+                    .context("filled_rectangle")?;
+                }
+            }
+
             self.filled_rectangle(
                 layers,
                 2,
@@ -323,13 +453,71 @@ impl crate::TermWindow {
             palette.cursor_fg == global_cursor_fg && palette.cursor_bg == global_cursor_bg;
 
         {
-            let stable_range = match current_viewport {
-                Some(top) => top..top + dims.viewport_rows as StableRowIndex,
-                None => dims.physical_top..dims.physical_top + dims.viewport_rows as StableRowIndex,
+            // Each region is a range of stable rows along with the row
+            // of the pane at which it is drawn.  A split viewport has
+            // a frozen region above the live output at the bottom.
+            let viewport_split = self.viewport_split_rows(pane_id, &dims);
+            let live_bottom = dims.physical_top + dims.viewport_rows as StableRowIndex;
+            let regions = match viewport_split {
+                Some(split) => {
+                    let top = current_viewport.unwrap_or(dims.physical_top);
+                    vec![
+                        (top..top + split as StableRowIndex, 0),
+                        (
+                            dims.physical_top + split as StableRowIndex..live_bottom,
+                            split,
+                        ),
+                    ]
+                }
+                None => {
+                    let top = current_viewport.unwrap_or(dims.physical_top);
+                    vec![(top..top + dims.viewport_rows as StableRowIndex, 0)]
+                }
             };
 
-            pos.pane
-                .apply_hyperlinks(stable_range.clone(), &self.config.hyperlink_rules);
+            // While smoothly scrolling, the rows are drawn relative to a
+            // fractional position, and one extra row is needed to fill
+            // the space revealed at the top or bottom of the pane
+            let scroll_origin = match (viewport_split, self.scroll_animation_offset(pane_id)) {
+                (None, Some(offset)) => {
+                    let fps = self.config.scroll_animation_fps.max(1) as u64;
+                    self.update_next_frame_time(Some(
+                        Instant::now() + Duration::from_millis(1000 / fps),
+                    ));
+                    Some(current_viewport.unwrap_or(dims.physical_top) as f32 + offset)
+                }
+                _ => None,
+            };
+            let regions = match scroll_origin {
+                Some(origin) => {
+                    let top = (origin.floor() as StableRowIndex).max(dims.scrollback_top);
+                    vec![(top..top + dims.viewport_rows as StableRowIndex + 1, 0)]
+                }
+                None => regions,
+            };
+
+            for (stable_range, _) in &regions {
+                pos.pane
+                    .apply_hyperlinks(stable_range.clone(), &self.config.hyperlink_rules);
+            }
+
+            if let Some(split) = viewport_split {
+                let split_color = palette.split.to_linear();
+                let line_height = self.render_metrics.underline_height as f32;
+                self.filled_rectangle(
+                    layers,
+                    2,
+                    euclid::rect(
+                        padding_left + border.left.get() as f32 + (pos.left as f32 * cell_width),
+                        top_pixel_y + ((pos.top + split) as f32 * cell_height)
+                            - (line_height / 2.0),
+                        pos.width as f32 * cell_width,
+                        line_height,
+                    ),
+                    split_color,
+                )
+                .context("filled_rectangle")?;
+            }
 
             struct LineRender<'a, 'b> {
                 term_window: &'a mut crate::TermWindow,
@@ -355,6 +543,13 @@ impl crate::TermWindow {
                 filled_box: TextureRect,
                 window_is_transparent: bool,
                 layers: &'a mut TripleLayerQuadAllocator<'b>,
+                /// The row of the pane at which the current region starts
+                row_offset: usize,
+                /// Whether the cursor may be drawn in the current region
+                show_cursor: bool,
+                /// The fractional stable row drawn at the top of the
+                /// pane while smoothly scrolling
+                scroll_origin: Option<f32>,
                 error: Option<anyhow::Error>,
             }
 
@@ -393,6 +588,9 @@ impl crate::TermWindow {
                 filled_box,
                 window_is_transparent,
                 layers,
+                row_offset: 0,
+                show_cursor: true,
+                scroll_origin,
                 error: None,
             };
 
@@ -404,13 +602,30 @@ impl crate::TermWindow {
                     line: &&mut Line,
                 ) -> anyhow::Result<()> {
                     let stable_row = stable_top + line_idx as StableRowIndex;
+                    let cell_height = self.term_window.render_metrics.cell_size.height as f32;
+                    let row_pixel_y = match self.scroll_origin {
+                        Some(origin) => {
+                            // Rows that would be partially outside of the
+                            // pane are not drawn
+                            let y = (stable_row as f32 - origin) * cell_height;
+                            if y < 0.
+                                || y + cell_height > self.dims.viewport_rows as f32 * cell_height
+                            {
+                                return Ok(());
+                            }
+                            y + self.pos.top as f32 * cell_height
+                        }
+                        None => (line_idx + self.row_offset + self.pos.top) as f32 * cell_height,
+                    };
                     let selrange = self
                         .selrange
                         .map_or(0..0, |sel| sel.cols_for_row(stable_row, self.rectangular));
                     // Constrain to the pane width!
                     let selrange = selrange.start..selrange.end.min(self.dims.cols);
 
-                    let (cursor, composing, password_input) = if self.cursor.y == stable_row {
+                    let (cursor, composing, password_input) = if self.show_cursor
+                        && self.cursor.y == stable_row
+                    {
                         (
                             Some(CursorProperties {
                                 position: StableCursorPosition {
@@ -425,11 +640,10 @@ impl crate::TermWindow {
                                 cursor_border_color: self.cursor_border_color,
                                 cursor_is_default_color: self.cursor_is_default_color,
                             }),
-                            match (self.pos.is_active, &self.term_window.dead_key_status) {
-                                (true, DeadKeyStatus::Composing(composing)) => {
-                                    Some(composing.to_string())
-                                }
-                                _ => None,
+                            if self.pos.is_active {
+                                self.term_window.inline_composition().map(str::to_string)
+                            } else {
+                                None
                             },
                             if self.term_window.config.detect_password_input {
                                 match self.pos.pane.get_metadata() {
@@ -463,11 +677,9 @@ impl crate::TermWindow {
                         selection: selrange.clone(),
                         cursor,
                         shape_hash,
-                        top_pixel_y: NotNan::new(self.top_pixel_y).unwrap()
-                            + (line_idx + self.pos.top) as f32
-                                * self.term_window.render_metrics.cell_size.height as f32,
+                        top_pixel_y: NotNan::new(self.top_pixel_y).unwrap() + row_pixel_y,
                         left_pixel_x: NotNan::new(self.left_pixel_x).unwrap(),
-                        phys_line_idx: line_idx,
+                        phys_line_idx: line_idx + self.row_offset,
                         reverse_video: self.dims.reverse_video,
                     };
 
@@ -502,14 +714,13 @@ impl crate::TermWindow {
                     let shape_key = LineToEleShapeCacheKey {
                         shape_hash,
                         shape_generation: quad_key.shape_generation,
-                        composing: if self.cursor.y == stable_row && self.pos.is_active {
-                            if let DeadKeyStatus::Composing(composing) =
-                                &self.term_window.dead_key_status
-                            {
-                                Some((self.cursor.x, composing.to_string()))
-                            } else {
-                                None
-                            }
+                        composing: if self.show_cursor
+                            && self.cursor.y == stable_row
+                            && self.pos.is_active
+                        {
+                            self.term_window
+                                .inline_composition()
+                                .map(|composing| (self.cursor.x, composing.to_string()))
                         } else {
                             None
                         },
@@ -593,9 +804,14 @@ impl crate::TermWindow {
                 }
             }
 
-            pos.pane.with_lines_mut(stable_range.clone(), &mut render);
-            if let Some(error) = render.error.take() {
-                return Err(error).context("error while calling with_lines_mut");
+            for (stable_range, row_offset) in regions {
+                render.row_offset = row_offset;
+                // Only the live region shows the cursor
+                render.show_cursor = viewport_split.is_none() || row_offset > 0;
+                pos.pane.with_lines_mut(stable_range, &mut render);
+                if let Some(error) = render.error.take() {
+                    return Err(error).context("error while calling with_lines_mut");
+                }
             }
         }
 
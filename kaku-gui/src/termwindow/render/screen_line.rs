@@ -5,7 +5,6 @@ use crate::termwindow::render::{
     RenderScreenLineParams, RenderScreenLineResult,
 };
 use crate::termwindow::LineToElementShapeItem;
-use ::window::DeadKeyStatus;
 use anyhow::Context;
 use config::{HsbTransform, TextStyle};
 use std::ops::Range;
@@ -77,11 +76,7 @@ impl crate::TermWindow {
 
         // Referencing the text being composed, but only if it belongs to this pane
         let composing = if cursor_idx.is_some() {
-            if let DeadKeyStatus::Composing(composing) = &self.dead_key_status {
-                Some(composing)
-            } else {
-                None
-            }
+            self.inline_composition()
         } else {
             None
         };
@@ -506,20 +501,18 @@ impl crate::TermWindow {
                             - (glyph.y_offset + glyph.bearing_y).get() as f32)
                             * height_scale;
 
-                    if self.config.custom_block_glyphs {
-                        if let Some(block) = &info.block_key {
-                            texture.replace(
-                                gl_state
-                                    .glyph_cache
-                                    .borrow_mut()
-                                    .cached_block(*block, &params.render_metrics)
-                                    .context("cached_block")?,
-                            );
-                            // Custom glyphs don't have the same offsets as computed
-                            // by the shaper, and are rendered relative to the cell
-                            // top left, rather than the baseline.
-                            top = 0.;
-                        }
+                    if let Some(block) = &info.block_key {
+                        texture.replace(
+                            gl_state
+                                .glyph_cache
+                                .borrow_mut()
+                                .cached_block(*block, &params.render_metrics)
+                                .context("cached_block")?,
+                        );
+                        // Custom glyphs don't have the same offsets as computed
+                        // by the shaper, and are rendered relative to the cell
+                        // top left, rather than the baseline.
+                        top = 0.;
                     }
 
                     if let Some(texture) = texture {
@@ -728,6 +721,20 @@ impl crate::TermWindow {
         })
     }
 
+    /// The attributes of the text being composed by the input method
+    fn preedit_attributes(&self) -> CellAttributes {
+        let palette = &self.config.resolved_palette;
+        let mut attrs = CellAttributes::blank();
+        attrs.set_underline(self.config.ime_preedit_underline);
+        if let Some(fg) = palette.ime_preedit_fg {
+            attrs.set_foreground(ColorAttribute::TrueColorWithDefaultFallback(fg.into()));
+        }
+        if let Some(bg) = palette.ime_preedit_bg {
+            attrs.set_background(ColorAttribute::TrueColorWithDefaultFallback(bg.into()));
+        }
+        attrs
+    }
+
     fn build_line_element_shape(
         &self,
         params: LineToElementParams,
@@ -744,7 +751,12 @@ impl crate::TermWindow {
             // Create an updated line with the composition overlaid
             let mut line = params.line.clone();
             let seqno = line.current_seqno();
-            line.overlay_text_with_attribute(*cursor_x, &composing, CellAttributes::blank(), seqno);
+            line.overlay_text_with_attribute(
+                *cursor_x,
+                &composing,
+                self.preedit_attributes(),
+                seqno,
+            );
             line.cluster(bidi_hint)
         } else {
             params.line.cluster(bidi_hint)
@@ -1,9 +1,13 @@
 use crate::quad::TripleLayerQuadAllocator;
+use crate::tabbar::TabBarItem;
 use crate::termwindow::render::RenderScreenLineParams;
+use crate::termwindow::{UIItem, UIItemType};
 use crate::utilsprites::RenderMetrics;
 use config::ConfigHandle;
 use mux::renderable::RenderableDimensions;
+use mux::Mux;
 use wezterm_term::color::ColorAttribute;
+use wezterm_term::Progress;
 use window::color::LinearRgba;
 
 impl crate::TermWindow {
@@ -15,7 +19,9 @@ impl crate::TermWindow {
                 self.fancy_tab_bar.replace(tab_bar);
             }
 
-            self.ui_items.append(&mut self.paint_fancy_tab_bar()?);
+            let mut ui_items = self.paint_fancy_tab_bar()?;
+            self.paint_tab_progress(&ui_items)?;
+            self.ui_items.append(&mut ui_items);
             return Ok(());
         }
 
@@ -31,11 +37,13 @@ impl crate::TermWindow {
         };
 
         // Register the tab bar location
-        self.ui_items.append(&mut self.tab_bar.compute_ui_items(
+        let mut ui_items = self.tab_bar.compute_ui_items(
             tab_bar_y as usize,
             self.render_metrics.cell_size.height as usize,
             self.render_metrics.cell_size.width as usize,
-        ));
+        );
+        self.paint_tab_progress(&ui_items)?;
+        self.ui_items.append(&mut ui_items);
 
         let window_is_transparent =
             !self.window_background.is_empty() || self.config.window_background_opacity != 1.0;
@@ -126,6 +134,68 @@ impl crate::TermWindow {
         Ok(())
     }
 
+    /// Draws a thin bar along the bottom of each tab whose active
+    /// pane has reported progress with OSC 9;4
+    fn paint_tab_progress(&mut self, ui_items: &[UIItem]) -> anyhow::Result<()> {
+        if !self.config.show_progress_in_tab_bar {
+            return Ok(());
+        }
+        let mux = Mux::get();
+        let window = match mux.get_window(self.mux_window_id) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let palette = self.palette().clone();
+        let ok_color = palette
+            .resolve_fg(ColorAttribute::PaletteIndex(2))
+            .to_linear();
+        let error_color = palette
+            .resolve_fg(ColorAttribute::PaletteIndex(1))
+            .to_linear();
+        let thickness = (self.render_metrics.cell_size.height as f32 / 10.)
+            .round()
+            .max(2.);
+
+        let gl_state = self.render_state.as_ref().unwrap();
+        // Above the fancy tab bar, which is drawn at zindex 10
+        // with its tabs one level above that
+        let layer = gl_state.layer_for_zindex(12)?;
+        let mut layers = layer.quad_allocator();
+
+        for item in ui_items {
+            let tab_idx = match &item.item_type {
+                UIItemType::TabBar(TabBarItem::Tab { tab_idx, .. }) => *tab_idx,
+                _ => continue,
+            };
+            let pane = match window
+                .get_by_idx(tab_idx)
+                .and_then(|tab| tab.get_active_pane())
+            {
+                Some(pane) => pane,
+                None => continue,
+            };
+            let (fraction, color) = match pane.get_progress() {
+                Progress::None => continue,
+                Progress::Percentage(pct) => (pct as f32 / 100., ok_color.mul_alpha(0.8)),
+                Progress::Error(pct) => (pct as f32 / 100., error_color.mul_alpha(0.8)),
+                Progress::Indeterminate => (1., ok_color.mul_alpha(0.4)),
+            };
+            self.filled_rectangle(
+                &mut layers,
+                0,
+                euclid::rect(
+                    item.x as f32,
+                    (item.y + item.height) as f32 - thickness,
+                    item.width as f32 * fraction,
+                    thickness,
+                ),
+                color,
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn tab_bar_pixel_height_impl(
         config: &ConfigHandle,
         fontconfig: &wezterm_font::FontConfiguration,
@@ -2,12 +2,33 @@ use crate::resize_increment_calculator::ResizeIncrementCalculator;
 use crate::utilsprites::RenderMetrics;
 use ::window::{Dimensions, ResizeIncrement, Window, WindowOps, WindowState};
 use config::{ConfigHandle, DimensionContext};
+use mux::tab::TabId;
 use mux::Mux;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
 use wezterm_font::FontConfiguration;
 use wezterm_term::TerminalSize;
 
+lazy_static::lazy_static! {
+    /// The font scale that was in effect when each tab was last active,
+    /// used when `per_tab_font_size` is enabled.  This isn't part of
+    /// the state of a window so that the scales survive tabs moving
+    /// between windows and can be saved along with the session.
+    static ref TAB_FONT_SCALES: Mutex<HashMap<TabId, f64>> = Mutex::new(HashMap::new());
+}
+
+/// Returns the font scale last used in `tab_id`, if any
+pub fn tab_font_scale(tab_id: TabId) -> Option<f64> {
+    TAB_FONT_SCALES.lock().get(&tab_id).copied()
+}
+
+/// Sets the font scale that will be used when `tab_id` is next activated
+pub fn set_tab_font_scale(tab_id: TabId, font_scale: f64) {
+    TAB_FONT_SCALES.lock().insert(tab_id, font_scale);
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct RowsAndCols {
     pub rows: usize,
@@ -61,9 +82,12 @@ impl super::TermWindow {
             webgpu.resize(dimensions);
         }
 
-        // Align fullscreen transition handling with maximize/restore behavior:
-        // keep current dpi for this transition frame so text doesn't pop larger/smaller.
-        if fullscreen_transition && self.dimensions.dpi != dimensions.dpi {
+        if let Some(fit) = self.config.adjust_font_size_to_fit {
+            self.fit_font_scale_to_grid(&dimensions, fit.cols, fit.rows);
+            self.apply_dimensions(&dimensions, None, window);
+        } else if fullscreen_transition && self.dimensions.dpi != dimensions.dpi {
+            // Align fullscreen transition handling with maximize/restore behavior:
+            // keep current dpi for this transition frame so text doesn't pop larger/smaller.
             let mut stabilized = dimensions;
             stabilized.dpi = self.dimensions.dpi;
             self.apply_dimensions(&stabilized, None, window);
@@ -271,8 +295,14 @@ impl super::TermWindow {
                 )
                 .saturating_sub(tab_bar_height as usize);
 
-            let rows = avail_height / self.render_metrics.cell_size.height as usize;
-            let cols = avail_width / self.render_metrics.cell_size.width as usize;
+            let mut rows = avail_height / self.render_metrics.cell_size.height as usize;
+            let mut cols = avail_width / self.render_metrics.cell_size.width as usize;
+            if let Some(fit) = config.adjust_font_size_to_fit {
+                // The font was scaled so that at least this many cells
+                // fit; the remainder is left as padding
+                rows = rows.min(fit.rows);
+                cols = cols.min(fit.cols);
+            }
 
             let size = TerminalSize {
                 rows,
@@ -314,11 +344,13 @@ impl super::TermWindow {
         self.invalidate_fancy_tab_bar();
         self.update_title();
 
-        window.set_resize_increments(if self.config.use_resize_increments {
-            ri_calc.into()
-        } else {
-            ResizeIncrement::disabled()
-        });
+        window.set_resize_increments(
+            if self.config.use_resize_increments && self.config.adjust_font_size_to_fit.is_none() {
+                ri_calc.into()
+            } else {
+                ResizeIncrement::disabled()
+            },
+        );
 
         // Queue up a speculative resize in order to preserve the number of rows+cols
         if let Some(cell_dims) = scale_changed_cells {
@@ -354,6 +386,67 @@ impl super::TermWindow {
         }
     }
 
+    /// Returns the width and height in pixels that are available for
+    /// terminal cells within a window of the given dimensions, after
+    /// accounting for padding, borders and the tab bar
+    fn available_cell_area(&self, dimensions: &Dimensions) -> (usize, usize) {
+        let tab_bar_height = if self.show_tab_bar {
+            self.tab_bar_pixel_height().unwrap_or(0.)
+        } else {
+            0.
+        };
+        let border = self.get_os_border();
+        let h_context = DimensionContext {
+            dpi: dimensions.dpi as f32,
+            pixel_max: dimensions.pixel_width as f32,
+            pixel_cell: self.render_metrics.cell_size.width as f32,
+        };
+        let v_context = DimensionContext {
+            dpi: dimensions.dpi as f32,
+            pixel_max: dimensions.pixel_height as f32,
+            pixel_cell: self.render_metrics.cell_size.height as f32,
+        };
+        let padding = &self.config.window_padding;
+        let padding_width = padding.left.evaluate_as_pixels(h_context) as usize
+            + effective_right_padding(&self.config, h_context);
+        let padding_height = (padding.top.evaluate_as_pixels(v_context)
+            + padding.bottom.evaluate_as_pixels(v_context)) as usize;
+
+        let width = dimensions
+            .pixel_width
+            .saturating_sub(padding_width + (border.left + border.right).get() as usize);
+        let height = dimensions
+            .pixel_height
+            .saturating_sub(padding_height + (border.top + border.bottom).get() as usize)
+            .saturating_sub(tab_bar_height as usize);
+        (width, height)
+    }
+
+    /// Implements `adjust_font_size_to_fit`: changes the font scale to
+    /// the largest at which `cols` x `rows` cells fit in `dimensions`.
+    /// Cell sizes are rounded to whole pixels, so the scale is refined
+    /// over a few passes rather than computed in one step.
+    fn fit_font_scale_to_grid(&mut self, dimensions: &Dimensions, cols: usize, rows: usize) {
+        for _ in 0..6 {
+            let avail = self.available_cell_area(dimensions);
+            let cell = (
+                self.render_metrics.cell_size.width as usize,
+                self.render_metrics.cell_size.height as usize,
+            );
+            let dpi_changed = dimensions.dpi != self.fonts.get_dpi();
+            match next_fit_font_scale(
+                self.fonts.get_font_scale(),
+                avail,
+                cell,
+                (cols, rows),
+                dpi_changed,
+            ) {
+                Some(font_scale) => self.apply_scale_change(dimensions, font_scale),
+                None => break,
+            }
+        }
+    }
+
     pub fn current_cell_dimensions(&self) -> RowsAndCols {
         RowsAndCols {
             rows: self.terminal_size.rows as usize,
@@ -466,6 +559,42 @@ impl super::TermWindow {
         }
 
         persist_current_font_size(&self.config, self.fonts.get_font_scale());
+
+        if self.config.per_tab_font_size {
+            if let Some(tab_id) = self.font_scale_tab {
+                set_tab_font_scale(tab_id, self.fonts.get_font_scale());
+            }
+        }
+    }
+
+    /// When `per_tab_font_size` is enabled, switches to the font scale
+    /// that was last used in `tab_id`.  A tab that has not been seen
+    /// before inherits the current scale.
+    /// The stored scales survive config reloads and are saved with the
+    /// session; the window size is left unchanged.
+    pub fn apply_tab_font_scale(&mut self, tab_id: TabId) {
+        if !self.config.per_tab_font_size || self.font_scale_tab == Some(tab_id) {
+            return;
+        }
+        self.font_scale_tab.replace(tab_id);
+
+        let current = self.fonts.get_font_scale();
+        let font_scale = match tab_font_scale(tab_id) {
+            Some(font_scale) => font_scale,
+            None => {
+                set_tab_font_scale(tab_id, current);
+                current
+            }
+        };
+        if font_scale == current {
+            return;
+        }
+
+        if let Some(window) = self.window.clone() {
+            let dimensions = self.dimensions;
+            self.apply_scale_change(&dimensions, font_scale);
+            self.apply_dimensions(&dimensions, None, &window);
+        }
     }
 
     pub fn decrease_font_size(&mut self) {
@@ -502,7 +631,9 @@ impl super::TermWindow {
             dpi: size.dpi,
         };
 
-        let show_tab_bar = config.enable_tab_bar && !config.hide_tab_bar_if_only_one_tab;
+        let show_tab_bar = config.enable_tab_bar
+            && !config.hide_tab_bar_if_only_one_tab
+            && !config.uses_native_macos_tabs();
         let tab_bar_height = if show_tab_bar {
             self.tab_bar_pixel_height()? as usize
         } else {
@@ -569,6 +700,37 @@ impl super::TermWindow {
     }
 }
 
+/// Returns the font scale to try next so that `grid` cells, which are
+/// `cell` pixels in size at `font_scale`, fit in `avail` pixels, or
+/// None once they fit closely enough.  The scale must be recomputed
+/// after a dpi change even if the grid already fits.
+fn next_fit_font_scale(
+    font_scale: f64,
+    avail: (usize, usize),
+    cell: (usize, usize),
+    grid: (usize, usize),
+    dpi_changed: bool,
+) -> Option<f64> {
+    let (avail_width, avail_height) = avail;
+    let (cell_width, cell_height) = (cell.0.max(1) as f64, cell.1.max(1) as f64);
+    let (cols, rows) = grid;
+    let ratio = (avail_width as f64 / (cols as f64 * cell_width))
+        .min(avail_height as f64 / (rows as f64 * cell_height));
+
+    let fits = ratio >= 1.0;
+    if fits && ratio < 1.05 && !dpi_changed {
+        return None;
+    }
+
+    // Aim slightly low when growing so that rounding up the
+    // cell size doesn't overshoot the available space
+    let target = font_scale * if fits { ratio * 0.99 } else { ratio.min(0.99) };
+    if (target - font_scale).abs() < 0.001 && !dpi_changed {
+        return None;
+    }
+    Some(target)
+}
+
 fn font_size_state_file() -> PathBuf {
     config::CONFIG_DIRS
         .first()
@@ -662,3 +824,36 @@ pub fn effective_right_padding(config: &ConfigHandle, context: DimensionContext)
         config.window_padding.right.evaluate_as_pixels(context) as usize
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fit_font_scale() {
+        // 80x24 cells of 10x20 pixels fill 800x480 exactly
+        assert_eq!(
+            next_fit_font_scale(1.0, (800, 480), (10, 20), (80, 24), false),
+            None
+        );
+        // Slack of less than 5% is left as padding
+        assert_eq!(
+            next_fit_font_scale(1.0, (820, 490), (10, 20), (80, 24), false),
+            None
+        );
+
+        // Twice the room in both directions grows the font, aiming low
+        let scale = next_fit_font_scale(1.0, (1600, 960), (10, 20), (80, 24), false).unwrap();
+        assert!((scale - 1.98).abs() < 1e-9, "{scale}");
+
+        // The tighter dimension decides when shrinking
+        let scale = next_fit_font_scale(2.0, (400, 480), (10, 20), (80, 24), false).unwrap();
+        assert!((scale - 1.0).abs() < 1e-9, "{scale}");
+    }
+
+    #[test]
+    fn fit_font_scale_after_dpi_change() {
+        let scale = next_fit_font_scale(1.0, (800, 480), (10, 20), (80, 24), true).unwrap();
+        assert!((scale - 0.99).abs() < 1e-9, "{scale}");
+    }
+}
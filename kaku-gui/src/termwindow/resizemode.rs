@@ -0,0 +1,137 @@
+//! The built-in `resize_pane` key table, in which hjkl or the arrow
+//! keys resize the active pane until Escape or Enter is pressed, much
+//! like `resize-pane -M` in tmux.  While it is active, a hint listing
+//! its keys is shown at the bottom of the window.
+use crate::termwindow::box_model::*;
+use crate::termwindow::DimensionContext;
+use crate::utilsprites::RenderMetrics;
+use config::keyassignment::{KeyAssignment, KeyTable, KeyTableEntry, PaneDirection};
+use config::{ConfigHandle, Dimension};
+use window::{KeyCode, Modifiers};
+
+pub const RESIZE_PANE_TABLE: &str = "resize_pane";
+
+/// How many times further SHIFT moves the split
+const SHIFT_FACTOR: usize = 5;
+
+const HINT: &str = "RESIZE  hjkl/arrows: move  HJKL: move more  Esc/Enter: done";
+
+/// Returns the action that enters resize mode.  Unbound keys are
+/// swallowed rather than sent to the pane while the mode is active.
+pub fn activate_resize_mode() -> KeyAssignment {
+    KeyAssignment::ActivateKeyTable {
+        name: RESIZE_PANE_TABLE.to_string(),
+        timeout_milliseconds: None,
+        replace_current: false,
+        one_shot: false,
+        until_unknown: false,
+        prevent_fallback: true,
+    }
+}
+
+pub fn resize_pane_key_table(config: &ConfigHandle) -> KeyTable {
+    let step = config.resize_pane_step.max(1);
+    let mut table = KeyTable::default();
+    let mut bind = |key: KeyCode, mods: Modifiers, action: KeyAssignment| {
+        table.insert(
+            (key, mods),
+            KeyTableEntry {
+                action,
+                metadata: Default::default(),
+            },
+        );
+    };
+
+    for (lower, upper, arrow, direction) in [
+        ('h', 'H', KeyCode::LeftArrow, PaneDirection::Left),
+        ('j', 'J', KeyCode::DownArrow, PaneDirection::Down),
+        ('k', 'K', KeyCode::UpArrow, PaneDirection::Up),
+        ('l', 'L', KeyCode::RightArrow, PaneDirection::Right),
+    ] {
+        let small = KeyAssignment::AdjustPaneSize(direction, step);
+        let large = KeyAssignment::AdjustPaneSize(direction, step * SHIFT_FACTOR);
+        bind(KeyCode::Char(lower), Modifiers::NONE, small.clone());
+        bind(arrow.clone(), Modifiers::NONE, small);
+        bind(KeyCode::Char(upper), Modifiers::SHIFT, large.clone());
+        bind(arrow, Modifiers::SHIFT, large);
+    }
+
+    for (key, mods) in [
+        (KeyCode::Char('\x1b'), Modifiers::NONE),
+        (KeyCode::Char('\r'), Modifiers::NONE),
+        (KeyCode::Char('q'), Modifiers::NONE),
+        (KeyCode::Char('c'), Modifiers::CTRL),
+    ] {
+        bind(key, mods, KeyAssignment::PopKeyTable);
+    }
+
+    table
+}
+
+impl crate::TermWindow {
+    /// Render the key hint for the `resize_pane` key table, centered
+    /// near the bottom of the window, while that table is active
+    pub fn paint_resize_mode_hint(&mut self) -> anyhow::Result<()> {
+        if self.key_table_state.current_table() != Some(RESIZE_PANE_TABLE) {
+            return Ok(());
+        }
+
+        let font = self.fonts.pane_select_font()?;
+        let metrics = RenderMetrics::with_font_metrics(&font.metrics());
+        let bg_color = self.config.pane_select_bg_color.to_linear();
+        let element = Element::new(&font, ElementContent::Text(HINT.to_string()))
+            .colors(ElementColors {
+                border: BorderColor::new(bg_color.into()),
+                bg: bg_color.into(),
+                text: self.config.pane_select_fg_color.to_linear().into(),
+            })
+            .padding(BoxDimension {
+                left: Dimension::Cells(0.75),
+                right: Dimension::Cells(0.75),
+                top: Dimension::Cells(0.25),
+                bottom: Dimension::Cells(0.25),
+            })
+            .border(BoxDimension::new(Dimension::Pixels(1.)));
+
+        let dimensions = self.dimensions;
+        let border = self.get_os_border();
+        let tab_bar_height = if self.show_tab_bar && self.config.tab_bar_at_bottom {
+            self.tab_bar_pixel_height().unwrap_or(0.)
+        } else {
+            0.
+        };
+        let width = (HINT.len() as f32 + 1.5) * metrics.cell_size.width as f32;
+        let height = metrics.cell_size.height as f32 * 1.5;
+        let x = ((dimensions.pixel_width as f32 - width) / 2.).max(0.);
+        let y = dimensions.pixel_height as f32
+            - height
+            - metrics.cell_size.height as f32
+            - tab_bar_height
+            - border.bottom.get() as f32;
+
+        let computed = self.compute_element(
+            &LayoutContext {
+                height: DimensionContext {
+                    dpi: dimensions.dpi as f32,
+                    pixel_max: dimensions.pixel_height as f32,
+                    pixel_cell: metrics.cell_size.height as f32,
+                },
+                width: DimensionContext {
+                    dpi: dimensions.dpi as f32,
+                    pixel_max: dimensions.pixel_width as f32,
+                    pixel_cell: metrics.cell_size.width as f32,
+                },
+                bounds: euclid::rect(x, y.max(0.), width, height),
+                metrics: &metrics,
+                gl_state: self.render_state.as_ref().unwrap(),
+                zindex: 120,
+            },
+            &element,
+        )?;
+
+        let gl_state = self.render_state.as_ref().unwrap();
+        self.render_element(&computed, gl_state, None)?;
+
+        Ok(())
+    }
+}
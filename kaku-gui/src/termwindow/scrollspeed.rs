@@ -0,0 +1,180 @@
+//! Applies the scroll speed settings to mouse wheel and trackpad
+//! events before they are matched against mouse bindings or reported
+//! to the pane.
+//!
+//! Scaled amounts are rarely whole numbers, so the fractional part is
+//! carried over to the next event in the same direction.
+use config::Config;
+use std::time::{Duration, Instant};
+use window::MouseEventKind as WMEK;
+
+/// Wheel events closer together than this are part of the same burst
+/// for the purposes of `scroll_acceleration`
+const BURST_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct WheelScroll {
+    last_event: Option<(Instant, bool, i16)>,
+    streak: u32,
+    remainder: f64,
+    report_remainder: f64,
+}
+
+/// Adds `value` to `remainder` and returns the whole part, leaving
+/// the fraction in `remainder`
+fn accumulate(remainder: &mut f64, value: f64) -> i64 {
+    let total = *remainder + value;
+    let whole = total.trunc();
+    *remainder = total - whole;
+    whole as i64
+}
+
+impl WheelScroll {
+    /// Returns the scaled event kind together with the number of
+    /// times the event should be reported to the pane, or None if
+    /// the event was scaled down to nothing.
+    pub fn scale(
+        &mut self,
+        kind: &WMEK,
+        precise: bool,
+        alt_screen: bool,
+        config: &Config,
+        now: Instant,
+    ) -> Option<(WMEK, usize)> {
+        let (vertical, amount) = match kind {
+            WMEK::VertWheel(amount) => (true, *amount),
+            WMEK::HorzWheel(amount) => (false, *amount),
+            _ => return Some((kind.clone(), 1)),
+        };
+        if amount == 0 {
+            return None;
+        }
+        let direction = amount.signum();
+
+        match self.last_event {
+            Some((when, last_vertical, last_direction))
+                if last_vertical == vertical
+                    && last_direction == direction
+                    && now.saturating_duration_since(when) < BURST_INTERVAL =>
+            {
+                self.streak = self.streak.saturating_add(1);
+            }
+            _ => {
+                self.streak = 0;
+                self.remainder = 0.;
+                self.report_remainder = 0.;
+            }
+        }
+        self.last_event = Some((now, vertical, direction));
+
+        let acceleration = config
+            .scroll_acceleration
+            .factor(self.streak, config.scroll_acceleration_max);
+        let multiplier = if alt_screen {
+            config.alternate_screen_scroll_multiplier
+        } else if precise {
+            config.trackpad_scroll_multiplier
+        } else {
+            config.mouse_wheel_scroll_multiplier
+        };
+        let factor = (multiplier * acceleration).max(0.);
+
+        let scaled = accumulate(&mut self.remainder, amount as f64 * factor);
+        let reports = if alt_screen {
+            accumulate(&mut self.report_remainder, factor) as usize
+        } else if scaled == 0 {
+            0
+        } else {
+            1
+        };
+        if scaled == 0 && reports == 0 {
+            return None;
+        }
+
+        // Applications in the alternate screen may receive a report
+        // even when the amount rounds down to zero lines
+        let scaled = if scaled == 0 {
+            direction
+        } else {
+            scaled.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+        };
+        let kind = if vertical {
+            WMEK::VertWheel(scaled)
+        } else {
+            WMEK::HorzWheel(scaled)
+        };
+        Some((kind, reports))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use config::ScrollAcceleration;
+
+    #[test]
+    fn accumulates_fractions() {
+        let mut remainder = 0.;
+        assert_eq!(accumulate(&mut remainder, 0.5), 0);
+        assert_eq!(accumulate(&mut remainder, 0.5), 1);
+        assert_eq!(accumulate(&mut remainder, -2.5), -2);
+        assert_eq!(accumulate(&mut remainder, -0.25), 0);
+        assert_eq!(accumulate(&mut remainder, -0.25), -1);
+    }
+
+    #[test]
+    fn acceleration() {
+        assert_eq!(ScrollAcceleration::None.factor(10, 4.), 1.);
+        assert_eq!(ScrollAcceleration::Linear.factor(5, 4.), 1.5);
+        assert_eq!(ScrollAcceleration::Linear.factor(50, 4.), 4.);
+        assert_eq!(ScrollAcceleration::Quadratic.factor(20, 4.), 4.);
+        assert_eq!(ScrollAcceleration::Quadratic.factor(10, 0.5), 1.);
+    }
+
+    #[test]
+    fn primary_screen_multipliers() {
+        let mut config = Config::default_config();
+        config.mouse_wheel_scroll_multiplier = 3.;
+        config.trackpad_scroll_multiplier = 0.5;
+        config.alternate_screen_scroll_multiplier = 2.;
+        let now = Instant::now();
+
+        let mut wheel = WheelScroll::default();
+        assert_eq!(
+            wheel.scale(&WMEK::VertWheel(1), false, false, &config, now),
+            Some((WMEK::VertWheel(3), 1))
+        );
+
+        let mut trackpad = WheelScroll::default();
+        assert_eq!(
+            trackpad.scale(&WMEK::VertWheel(1), true, false, &config, now),
+            None
+        );
+        assert_eq!(
+            trackpad.scale(&WMEK::VertWheel(1), true, false, &config, now),
+            Some((WMEK::VertWheel(1), 1))
+        );
+    }
+
+    #[test]
+    fn alternate_screen_multiplier() {
+        let mut config = Config::default_config();
+        config.mouse_wheel_scroll_multiplier = 3.;
+        config.trackpad_scroll_multiplier = 0.5;
+        config.alternate_screen_scroll_multiplier = 2.;
+        let now = Instant::now();
+
+        // Both wheels and trackpads use the alternate screen multiplier,
+        // which scales the number of reports
+        let mut wheel = WheelScroll::default();
+        assert_eq!(
+            wheel.scale(&WMEK::VertWheel(1), false, true, &config, now),
+            Some((WMEK::VertWheel(2), 2))
+        );
+        let mut trackpad = WheelScroll::default();
+        assert_eq!(
+            trackpad.scale(&WMEK::HorzWheel(-1), true, true, &config, now),
+            Some((WMEK::HorzWheel(-2), 2))
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use crate::selection::{Selection, SelectionCoordinate, SelectionMode, SelectionRange, SelectionX};
 use ::window::WindowOps;
 use mux::pane::{Pane, PaneId};
+use mux::pane_text::get_text_from_semantic_zone;
 use std::cell::RefMut;
 use std::sync::Arc;
 use termwiz::surface::Line;
-use wezterm_term::StableRowIndex;
+use wezterm_term::{SemanticType, StableRowIndex};
 
 impl super::TermWindow {
     pub fn selection(&self, pane_id: PaneId) -> RefMut<'_, Selection> {
@@ -109,6 +110,49 @@ impl super::TermWindow {
         s
     }
 
+    /// Selects the output of the command at the top of the viewport,
+    /// or of the most recent command when the viewport is at the bottom.
+    /// Commands that produced no output are skipped.
+    pub fn select_command_output(&mut self, pane: &Arc<dyn Pane>) {
+        let viewport = self.get_viewport(pane.pane_id());
+        let zones = pane.get_semantic_zones().unwrap_or_else(|_| vec![]);
+        let mut outputs = zones.into_iter().filter(|zone| {
+            zone.semantic_type == SemanticType::Output
+                && !get_text_from_semantic_zone(&**pane, zone).trim().is_empty()
+        });
+        let zone = match viewport {
+            Some(top) => outputs.find(|zone| zone.end_y >= top),
+            None => outputs.last(),
+        };
+        let zone = match zone {
+            Some(zone) => zone,
+            None => {
+                self.show_toast("No command output to select".to_string());
+                return;
+            }
+        };
+
+        {
+            let mut selection = self.selection(pane.pane_id());
+            let start = SelectionCoordinate::x_y(zone.start_x, zone.start_y);
+            selection.origin = Some(start);
+            selection.range = Some(SelectionRange {
+                start,
+                end: SelectionCoordinate::x_y(zone.end_x, zone.end_y),
+            });
+            selection.rectangular = false;
+            selection.seqno = pane.get_current_seqno();
+        }
+
+        // Bring the start of the output into view
+        let dims = pane.get_dimensions();
+        let top = viewport.unwrap_or(dims.physical_top);
+        if zone.start_y < top {
+            self.set_viewport(pane.pane_id(), Some(zone.start_y), dims);
+        }
+        self.window.as_ref().unwrap().invalidate();
+    }
+
     pub fn clear_selection(&mut self, pane: &Arc<dyn Pane>) {
         let mut selection = self.selection(pane.pane_id());
         selection.clear();
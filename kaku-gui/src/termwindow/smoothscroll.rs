@@ -0,0 +1,78 @@
+//! Smooth scrolling: rather than jumping to a new viewport position,
+//! the viewport is moved immediately but drawn offset by a fraction of
+//! a line that decays to zero over `scroll_animation_duration_ms`.
+use config::{ConfigHandle, EasingFunction};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollAnimation {
+    start: Instant,
+    /// The offset, in lines, of the displayed position relative to
+    /// the viewport when the animation started
+    from: f32,
+    duration: Duration,
+    easing: EasingFunction,
+}
+
+impl ScrollAnimation {
+    /// Begins animating a scroll by `delta` lines, continuing on from
+    /// `prior` if the viewport was already animating
+    pub fn new(
+        prior: Option<ScrollAnimation>,
+        delta: isize,
+        max_lines: usize,
+        config: &ConfigHandle,
+        now: Instant,
+    ) -> Option<Self> {
+        if config.scroll_animation_fps == 0 || config.scroll_animation_duration_ms == 0 {
+            return None;
+        }
+        let remaining = prior.and_then(|a| a.offset(now)).unwrap_or(0.);
+        let max_lines = max_lines as f32;
+        let from = (remaining - delta as f32).clamp(-max_lines, max_lines);
+        if from == 0. {
+            return None;
+        }
+        Some(Self {
+            start: now,
+            from,
+            duration: Duration::from_millis(config.scroll_animation_duration_ms),
+            easing: config.scroll_animation_easing,
+        })
+    }
+
+    /// Returns the offset, in lines, of the displayed position from the
+    /// viewport at `now`, or None once the animation has completed
+    pub fn offset(&self, now: Instant) -> Option<f32> {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let progress = self.easing.evaluate_at_position(progress).clamp(0., 1.);
+        Some(self.from * (1. - progress))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn animation(from: f32) -> ScrollAnimation {
+        ScrollAnimation {
+            start: Instant::now(),
+            from,
+            duration: Duration::from_millis(100),
+            easing: EasingFunction::Linear,
+        }
+    }
+
+    #[test]
+    fn decays_to_nothing() {
+        let anim = animation(-4.);
+        assert_eq!(anim.offset(anim.start), Some(-4.));
+        let halfway = anim.offset(anim.start + Duration::from_millis(50)).unwrap();
+        assert!((halfway + 2.).abs() < 0.001, "{halfway}");
+        assert_eq!(anim.offset(anim.start + Duration::from_millis(100)), None);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::spawn::SpawnWhere;
 use config::keyassignment::{SpawnCommand, SpawnTabDomain};
 use config::TermConfig;
+use mux::Mux;
 use std::sync::Arc;
 
 impl super::TermWindow {
@@ -24,6 +25,34 @@ impl super::TermWindow {
         )
     }
 
+    /// Spawns the layout preset `name` in a new tab
+    pub fn apply_layout(&self, name: &str) {
+        let layout = match self.config.layouts.get(name) {
+            Some(layout) => layout.clone(),
+            None => {
+                log::error!("There is no layout named `{}` in the layouts config", name);
+                return;
+            }
+        };
+        let name = name.to_string();
+        let size = self.terminal_size;
+        let window_id = self.mux_window_id;
+        let term_config = Arc::new(TermConfig::with_config(self.config.clone()));
+
+        promise::spawn::spawn(async move {
+            let workspace = Mux::get().active_workspace();
+            match mux::layout::apply_layout(&layout, Some(window_id), size, workspace).await {
+                Ok((tab, _pane_id, _window_id)) => {
+                    for pos in tab.iter_panes_ignoring_zoom() {
+                        pos.pane.set_config(term_config.clone());
+                    }
+                }
+                Err(err) => log::error!("Failed to apply layout {}: {:#}", name, err),
+            }
+        })
+        .detach();
+    }
+
     pub fn spawn_tab(&mut self, domain: &SpawnTabDomain) {
         self.spawn_command(
             &SpawnCommand {
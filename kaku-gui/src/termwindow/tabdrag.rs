@@ -0,0 +1,159 @@
+//! Dragging tabs around the tab bar with the mouse.  Dragging a tab
+//! over its neighbours reorders the tabs of the window, while dropping
+//! it well away from the tab bar tears it off into a new window of its
+//! own, positioned under the mouse pointer.
+use crate::tabbar::TabBarItem;
+use crate::termwindow::UIItemType;
+use ::window::{MouseButtons as WMB, MouseCursor, MouseEvent, WindowOps};
+use anyhow::{anyhow, ensure};
+use config::{Dimension, GeometryOrigin, GuiPosition};
+use mux::Mux;
+
+/// A tab that is held by the left mouse button
+#[derive(Debug, Clone)]
+pub struct TabDrag {
+    /// The current index of the held tab
+    tab_idx: usize,
+    /// The press that picked up the tab
+    start: MouseEvent,
+    /// Set once the pointer moved far enough to count as a drag
+    /// rather than a click
+    active: bool,
+}
+
+impl crate::TermWindow {
+    /// Called when the left button is pressed over the tab at `tab_idx`
+    pub fn begin_tab_drag(&mut self, tab_idx: usize, event: &MouseEvent) {
+        // The press landed in the tab strip, which would otherwise
+        // start moving the whole window
+        self.is_window_dragging = false;
+        self.tab_drag.replace(TabDrag {
+            tab_idx,
+            start: event.clone(),
+            active: false,
+        });
+    }
+
+    /// Moves the held tab to the position of the tab under the pointer.
+    /// Returns true if the motion belongs to a tab drag.
+    pub fn tab_drag_motion(&mut self, event: &MouseEvent, context: &dyn WindowOps) -> bool {
+        let mut drag = match self.tab_drag.take() {
+            Some(drag) => drag,
+            None => return false,
+        };
+        if !event.mouse_buttons.contains(WMB::LEFT) {
+            // The release happened somewhere we didn't hear about.
+            // Other buttons being pressed along the way don't matter.
+            self.current_mouse_capture = None;
+            return false;
+        }
+
+        if !drag.active {
+            let threshold = self.render_metrics.cell_size.width / 2;
+            let dx = (event.coords.x - drag.start.coords.x).abs();
+            let dy = (event.coords.y - drag.start.coords.y).abs();
+            drag.active = dx > threshold || dy > threshold;
+        }
+
+        if drag.active {
+            context.set_cursor(Some(MouseCursor::Hand));
+            let target = self
+                .resolve_ui_item(event)
+                .and_then(|item| match item.item_type {
+                    UIItemType::TabBar(
+                        TabBarItem::Tab { tab_idx, .. } | TabBarItem::TabRegion { tab_idx, .. },
+                    ) => Some(tab_idx),
+                    _ => None,
+                });
+            if let Some(target) = target {
+                if target != drag.tab_idx {
+                    match self.move_tab(target) {
+                        Ok(()) => drag.tab_idx = target,
+                        Err(err) => log::error!("moving dragged tab: {:#}", err),
+                    }
+                    context.invalidate();
+                }
+            }
+        }
+
+        self.tab_drag.replace(drag);
+        true
+    }
+
+    /// Called when the left button is released.  Tears the held tab off
+    /// into a new window if it was dropped away from the tab bar.
+    /// Returns true if the release completed a tab drag.
+    pub fn end_tab_drag(&mut self, event: &MouseEvent, context: &dyn WindowOps) -> bool {
+        let drag = match self.tab_drag.take() {
+            Some(drag) => drag,
+            None => return false,
+        };
+        if !drag.active {
+            // It was just a click, which already activated the tab
+            return false;
+        }
+        context.set_cursor(Some(MouseCursor::Arrow));
+
+        if self.is_outside_tab_bar(event) {
+            // Keep the pointer at the same spot relative to the new
+            // window as it was relative to this one when the tab was
+            // picked up
+            let position = GuiPosition {
+                x: Dimension::Pixels((event.screen_coords.x - drag.start.coords.x) as f32),
+                y: Dimension::Pixels((event.screen_coords.y - drag.start.coords.y) as f32),
+                origin: GeometryOrigin::ScreenCoordinateSystem,
+            };
+            if let Err(err) = self.move_tab_to_new_window(Some(position)) {
+                log::error!("tearing off tab: {:#}", err);
+            }
+        }
+        context.invalidate();
+        true
+    }
+
+    /// Whether the pointer is far enough from the tab bar that dropping
+    /// a tab there should tear it off
+    fn is_outside_tab_bar(&self, event: &MouseEvent) -> bool {
+        let tab_bar_height = match self.tab_bar_pixel_height() {
+            Ok(height) if self.show_tab_bar => height as isize,
+            _ => return false,
+        };
+        let border = self.get_os_border();
+        let top = if self.config.tab_bar_at_bottom {
+            self.dimensions.pixel_height as isize - tab_bar_height - border.bottom.get() as isize
+        } else {
+            border.top.get() as isize
+        };
+        let margin = self.render_metrics.cell_size.height;
+
+        let (x, y) = (event.coords.x, event.coords.y);
+        x < 0
+            || x >= self.dimensions.pixel_width as isize
+            || y < top - margin
+            || y >= top + tab_bar_height + margin
+    }
+
+    /// Moves the active tab into a new window, optionally placed at
+    /// `position`.  The last tab of a window stays where it is.
+    pub fn move_tab_to_new_window(&mut self, position: Option<GuiPosition>) -> anyhow::Result<()> {
+        let mux = Mux::get();
+        let tab_id = {
+            let window = mux
+                .get_window(self.mux_window_id)
+                .ok_or_else(|| anyhow!("no such window"))?;
+            ensure!(
+                window.len() > 1,
+                "cannot move the only tab of a window to a new window"
+            );
+            window
+                .get_active()
+                .map(|tab| tab.tab_id())
+                .ok_or_else(|| anyhow!("window has no active tab"))?
+        };
+
+        mux.move_tab_to_window(tab_id, None, position)?;
+        self.update_title();
+        self.update_scrollbar();
+        Ok(())
+    }
+}
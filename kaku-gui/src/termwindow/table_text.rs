@@ -0,0 +1,226 @@
+//! Recognizes tabular text, such as a selection spanning the output
+//! of `ls -l` or a CSV file, and re-renders it in another format.
+//!
+//! Delimited text is recognized when every line contains the same
+//! number of tabs or commas.  Otherwise, the text is treated as
+//! aligned columns when the lines share at least one column position
+//! that is blank in all of them.
+use config::keyassignment::TableFormat;
+
+/// Parses `text` into rows of cells, or returns None if it doesn't
+/// look like a table of at least two rows and two columns.
+pub fn parse_table(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let rows = split_delimited(&lines, '\t')
+        .or_else(|| split_csv(&lines))
+        .or_else(|| split_aligned(&lines))?;
+
+    if rows.iter().all(|row| row.len() >= 2) {
+        Some(rows)
+    } else {
+        None
+    }
+}
+
+fn split_delimited(lines: &[&str], delimiter: char) -> Option<Vec<Vec<String>>> {
+    let count = lines[0].matches(delimiter).count();
+    if count == 0
+        || lines
+            .iter()
+            .any(|line| line.matches(delimiter).count() != count)
+    {
+        return None;
+    }
+    Some(
+        lines
+            .iter()
+            .map(|line| {
+                line.split(delimiter)
+                    .map(|cell| cell.trim().to_string())
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// Splits a line of CSV, honoring double quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                cells.push(cell.trim().to_string());
+                cell.clear();
+            }
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn split_csv(lines: &[&str]) -> Option<Vec<Vec<String>>> {
+    let rows: Vec<Vec<String>> = lines.iter().map(|line| split_csv_line(line)).collect();
+    let columns = rows[0].len();
+    if columns < 2 || rows.iter().any(|row| row.len() != columns) {
+        return None;
+    }
+    Some(rows)
+}
+
+fn split_aligned(lines: &[&str]) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+    let width = lines.iter().map(|line| line.len()).max()?;
+
+    // A position is a gap if it is blank in every line
+    let is_gap: Vec<bool> = (0..width)
+        .map(|idx| {
+            lines
+                .iter()
+                .all(|line| line.get(idx).map(|c| c.is_whitespace()).unwrap_or(true))
+        })
+        .collect();
+
+    // Columns start wherever text follows a gap
+    let starts: Vec<usize> = (0..width)
+        .filter(|&idx| !is_gap[idx] && (idx == 0 || is_gap[idx - 1]))
+        .collect();
+    if starts.len() < 2 {
+        return None;
+    }
+
+    Some(
+        lines
+            .iter()
+            .map(|line| {
+                starts
+                    .iter()
+                    .enumerate()
+                    .map(|(col, &start)| {
+                        let end = starts.get(col + 1).copied().unwrap_or(width);
+                        let start = start.min(line.len());
+                        let end = end.min(line.len());
+                        line[start..end]
+                            .iter()
+                            .collect::<String>()
+                            .trim()
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Renders rows of cells in the requested format.  For Markdown,
+/// the first row is used as the header.
+pub fn format_table(rows: &[Vec<String>], format: TableFormat) -> String {
+    match format {
+        TableFormat::Tsv => rows
+            .iter()
+            .map(|row| row.join("\t"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        TableFormat::Csv => rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| csv_field(cell))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        TableFormat::Markdown => {
+            let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+            let render_row = |row: &[String]| {
+                let cells: Vec<String> = (0..columns)
+                    .map(|idx| {
+                        row.get(idx)
+                            .map(|cell| cell.replace('|', "\\|"))
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                format!("| {} |", cells.join(" | "))
+            };
+            let mut lines = vec![];
+            if let Some((header, body)) = rows.split_first() {
+                lines.push(render_row(header));
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+                for row in body {
+                    lines.push(render_row(row));
+                }
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+/// Converts tabular `text` to `format`, or returns None if the text
+/// doesn't look like a table
+pub fn convert_table(text: &str, format: TableFormat) -> Option<String> {
+    parse_table(text).map(|rows| format_table(&rows, format))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aligned_columns() {
+        let text = "NAME     READY   STATUS\n\
+                    web-1    1/1     Running\n\
+                    db-0     0/1     Pending\n";
+        assert_eq!(
+            convert_table(text, TableFormat::Tsv).unwrap(),
+            "NAME\tREADY\tSTATUS\nweb-1\t1/1\tRunning\ndb-0\t0/1\tPending"
+        );
+        assert_eq!(
+            convert_table(text, TableFormat::Markdown).unwrap(),
+            "| NAME | READY | STATUS |\n| --- | --- | --- |\n\
+             | web-1 | 1/1 | Running |\n| db-0 | 0/1 | Pending |"
+        );
+    }
+
+    #[test]
+    fn delimited() {
+        assert_eq!(
+            convert_table("a,\"b, c\"\n1,\"say \"\"hi\"\"\"", TableFormat::Tsv).unwrap(),
+            "a\tb, c\n1\tsay \"hi\""
+        );
+        assert_eq!(
+            convert_table("a\tb\n1\t2,3", TableFormat::Csv).unwrap(),
+            "a,b\n1,\"2,3\""
+        );
+    }
+
+    #[test]
+    fn not_a_table() {
+        assert!(parse_table("just one line").is_none());
+        assert!(parse_table("hello world\nthis is prose").is_none());
+    }
+}
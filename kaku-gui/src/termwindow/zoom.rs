@@ -0,0 +1,105 @@
+//! Makes a zoomed pane easy to recognize, so that the panes it hides
+//! aren't forgotten: the zoomed pane is outlined, and when the zoom
+//! state changes the outline glides between the prior and the new
+//! extent of the pane over `zoom_animation_duration_ms`.
+use crate::colorease::ColorEase;
+use crate::quad::TripleLayerQuadAllocator;
+use ::window::RectF;
+use anyhow::Context;
+use config::EasingFunction;
+use mux::pane::PaneId;
+use mux::tab::PositionedPane;
+use std::time::Instant;
+use wezterm_term::color::ColorPalette;
+
+#[derive(Debug, Clone, Copy)]
+struct ZoomAnimation {
+    start: Instant,
+    /// Where the pane was drawn before the zoom state changed
+    from: RectF,
+}
+
+/// The zoom state of the active pane as of the last frame
+#[derive(Debug, Default)]
+pub struct ZoomIndicator {
+    last: Option<(PaneId, bool, RectF)>,
+    animation: Option<ZoomAnimation>,
+}
+
+fn lerp(from: f32, to: f32, progress: f32) -> f32 {
+    from + (to - from) * progress
+}
+
+impl crate::TermWindow {
+    /// Called when painting the active pane, whose background fills `rect`
+    pub fn paint_zoom_indicator(
+        &self,
+        pos: &PositionedPane,
+        rect: RectF,
+        palette: &ColorPalette,
+        layers: &mut TripleLayerQuadAllocator,
+    ) -> anyhow::Result<()> {
+        let pane_id = pos.pane.pane_id();
+        let duration_ms = self.config.zoom_animation_duration_ms;
+
+        let mut indicator = self.zoom_indicator.borrow_mut();
+        if let Some((last_pane, was_zoomed, from)) =
+            indicator.last.replace((pane_id, pos.is_zoomed, rect))
+        {
+            if last_pane == pane_id && was_zoomed != pos.is_zoomed && duration_ms > 0 {
+                indicator.animation = Some(ZoomAnimation {
+                    start: Instant::now(),
+                    from,
+                });
+            }
+        }
+
+        let mut outline = if pos.is_zoomed && self.config.show_zoom_indicator {
+            Some((rect, 1.0))
+        } else {
+            None
+        };
+
+        if let Some(animation) = indicator.animation {
+            let mut ease = ColorEase::new(
+                duration_ms,
+                EasingFunction::EaseOut,
+                1,
+                EasingFunction::Linear,
+                Some(animation.start),
+            );
+            match ease.intensity_one_shot() {
+                Some((progress, next)) => {
+                    self.update_next_frame_time(Some(next));
+                    let from = animation.from;
+                    let glide = euclid::rect(
+                        lerp(from.min_x(), rect.min_x(), progress),
+                        lerp(from.min_y(), rect.min_y(), progress),
+                        lerp(from.width(), rect.width(), progress),
+                        lerp(from.height(), rect.height(), progress),
+                    );
+                    // Fade out while zooming out, as nothing remains
+                    // outlined afterwards
+                    let alpha = if pos.is_zoomed { 1.0 } else { 1.0 - progress };
+                    outline = Some((glide, alpha));
+                }
+                None => {
+                    indicator.animation.take();
+                }
+            }
+        }
+        drop(indicator);
+
+        let (rect, alpha) = match outline {
+            Some(outline) => outline,
+            None => return Ok(()),
+        };
+        let color = palette.split.to_linear().mul_alpha(alpha);
+        let thickness = (self.render_metrics.cell_size.width as f32 / 6.)
+            .round()
+            .max(1.);
+        self.outlined_rectangle(layers, 2, rect, thickness, color)
+            .context("outlined_rectangle for zoom indicator")?;
+        Ok(())
+    }
+}
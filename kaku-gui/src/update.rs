@@ -1,10 +1,11 @@
 use anyhow::anyhow;
-use config::{configuration, wezterm_version};
+use config::{configuration, wezterm_version, ProxyServer};
 use http_req::request::{HttpVersion, Request};
 use http_req::uri::Uri;
 use serde::*;
 use std::cmp::Ordering as CmpOrdering;
 use std::convert::TryFrom;
+use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use wezterm_toast_notification::*;
@@ -27,10 +28,13 @@ pub struct Asset {
 }
 
 fn get_github_release_info(uri: &str) -> anyhow::Result<Release> {
-    let uri = Uri::try_from(uri)?;
+    let parsed = Uri::try_from(uri)?;
+    if let Some(proxy) = configuration().proxy_for_host(parsed.host().unwrap_or("")) {
+        return get_github_release_info_via_proxy(uri, &proxy);
+    }
 
     let mut latest = Vec::new();
-    let _res = Request::new(&uri)
+    let _res = Request::new(&parsed)
         .version(HttpVersion::Http10)
         .header("User-Agent", &format!("kaku/{}", wezterm_version()))
         .send(&mut latest)
@@ -45,6 +49,34 @@ fn get_github_release_info(uri: &str) -> anyhow::Result<Release> {
     Ok(latest)
 }
 
+/// http_req cannot use a proxy, so requests that need one are
+/// made with curl instead
+fn get_github_release_info_via_proxy(uri: &str, proxy: &ProxyServer) -> anyhow::Result<Release> {
+    let output = Command::new("/usr/bin/curl")
+        .arg("--fail")
+        .arg("--location")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--connect-timeout")
+        .arg("15")
+        .arg("--user-agent")
+        .arg(format!("kaku/{}", wezterm_version()))
+        .arg("--proxy")
+        .arg(proxy.url())
+        .arg(uri)
+        .output()
+        .map_err(|e| anyhow!("failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "failed to query github releases via proxy {}: {}",
+            proxy.address(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let latest: Release = serde_json::from_slice(&output.stdout)?;
+    Ok(latest)
+}
+
 pub fn get_latest_release_info() -> anyhow::Result<Release> {
     get_github_release_info("https://api.github.com/repos/tw93/Kaku/releases/latest")
 }
@@ -1,16 +1,15 @@
 use anyhow::Context;
-use chrono::serde::ts_seconds_option;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use clap::Parser;
 use config::ConfigHandle;
 use filedescriptor::FileDescriptor;
+use mux::asciicast::{read_cast, Event, Header, Theme};
 use portable_pty::{native_pty_system, PtySize};
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
-use std::sync::mpsc::channel;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::time::{Duration, Instant};
 use termwiz::escape::parser::Parser as TWParser;
 use termwiz::escape::Action;
@@ -20,115 +19,56 @@ use wezterm_term::color::ColorPalette;
 #[cfg(windows)]
 use win::WinTty as Tty;
 
-/// See <https://github.com/asciinema/asciinema/blob/develop/doc/asciicast-v2.md>
-/// for file format specification
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
-pub struct Header {
-    /// Must be 2 or higher
-    pub version: u32,
-    /// Initial terminal width (number of columns)
-    pub width: u32,
-    /// Initial terminal height (number of columns)
-    pub height: u32,
-    /// Unix timestamp of starting time of session
-    #[serde(
-        default,
-        with = "ts_seconds_option",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub timestamp: Option<DateTime<Utc>>,
-    /// Duration of the whole recording in seconds
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub duration: Option<f32>,
-    /// Used to reduce terminal inactivity (delays between frames)
-    /// to a maximum of this amount.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub idle_time_limit: Option<f32>,
-    /// Command that was recorded
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub command: Option<String>,
-    /// Title of the asciicast
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub title: Option<String>,
-    /// Map of captured environment variables
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub env: HashMap<String, String>,
-    /// Color theme of the recorded terminal
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub theme: Option<Theme>,
-}
-
-impl Header {
-    fn new(config: &ConfigHandle, size: PtySize, prog: &[&OsStr]) -> Self {
-        let mut env = HashMap::new();
-        env.insert("TERM".to_string(), config.term.to_string());
-        env.insert(
-            "WEZTERM_VERSION".to_string(),
-            config::wezterm_version().to_string(),
-        );
-        env.insert(
-            "WEZTERM_TARGET_TRIPLE".to_string(),
-            config::wezterm_target_triple().to_string(),
-        );
-        if let Ok(shell) = std::env::var("SHELL") {
-            env.insert("SHELL".to_string(), shell);
-        }
-        if let Ok(lang) = std::env::var("LANG") {
-            env.insert("LANG".to_string(), lang);
-        }
+/// The header of a recording of `prog` in a terminal of `size`
+fn new_header(config: &ConfigHandle, size: PtySize, prog: &[&OsStr]) -> Header {
+    let mut env = HashMap::new();
+    env.insert("TERM".to_string(), config.term.to_string());
+    env.insert(
+        "WEZTERM_VERSION".to_string(),
+        config::wezterm_version().to_string(),
+    );
+    env.insert(
+        "WEZTERM_TARGET_TRIPLE".to_string(),
+        config::wezterm_target_triple().to_string(),
+    );
+    if let Ok(shell) = std::env::var("SHELL") {
+        env.insert("SHELL".to_string(), shell);
+    }
+    if let Ok(lang) = std::env::var("LANG") {
+        env.insert("LANG".to_string(), lang);
+    }
 
-        let palette: ColorPalette = config.resolved_palette.clone().into();
-        let ansi_colors: Vec<String> = palette.colors.0[0..16]
+    let palette: ColorPalette = config.resolved_palette.clone().into();
+    let ansi_colors: Vec<String> = palette.colors.0[0..16]
+        .iter()
+        .map(|c| c.to_rgb_string())
+        .collect();
+
+    let theme = Theme {
+        fg: palette.foreground.to_rgb_string(),
+        bg: palette.background.to_rgb_string(),
+        palette: ansi_colors.join(":"),
+    };
+
+    let command = if prog.is_empty() {
+        None
+    } else {
+        let args: Vec<String> = prog
             .iter()
-            .map(|c| c.to_rgb_string())
+            .map(|s| s.to_string_lossy().to_string())
             .collect();
-
-        let theme = Theme {
-            fg: palette.foreground.to_rgb_string(),
-            bg: palette.background.to_rgb_string(),
-            palette: ansi_colors.join(":"),
-        };
-
-        let command = if prog.is_empty() {
-            None
-        } else {
-            let args: Vec<String> = prog
-                .iter()
-                .map(|s| s.to_string_lossy().to_string())
-                .collect();
-            Some(shell_words::join(&args))
-        };
-
-        Header {
-            version: 2,
-            height: size.rows.into(),
-            width: size.cols.into(),
-            timestamp: Some(Utc::now()),
-            env,
-            command,
-            theme: Some(theme),
-            ..Default::default()
-        }
-    }
-}
-
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
-pub struct Theme {
-    /// Normal text color
-    pub fg: String,
-    /// Normal background color
-    pub bg: String,
-    /// List of 8 or 16 colors separated by a colon character
-    pub palette: String,
-}
-
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
-pub struct Event(pub f32, pub String, pub String);
-
-impl Event {
-    fn log_output<W: Write>(mut w: W, elapsed: f32, output: &str) -> std::io::Result<()> {
-        let event = Event(elapsed, "o".to_string(), output.to_string());
-        writeln!(w, "{}", serde_json::to_string(&event)?)
+        Some(shell_words::join(&args))
+    };
+
+    Header {
+        version: 2,
+        height: size.rows.into(),
+        width: size.cols.into(),
+        timestamp: Some(Utc::now()),
+        env,
+        command,
+        theme: Some(theme),
+        ..Default::default()
     }
 }
 
@@ -360,7 +300,7 @@ impl RecordCommand {
         let mut tty = Tty::new()?;
         let size = tty.get_size()?;
 
-        let header = Header::new(&config, size, &prog);
+        let header = new_header(&config, size, &prog);
 
         let (cast_file, cast_file_name) = match self.outfile.as_ref() {
             Some(outfile) => (
@@ -499,6 +439,184 @@ impl RecordCommand {
     }
 }
 
+/// ANSI art is traditionally paced as though it were arriving over
+/// a modem; this is roughly the rate of a 28.8k connection
+const ANSI_ART_BYTES_PER_SECOND: f32 = 3600.;
+/// ANSI art is drawn for an 80 column screen
+const ANSI_ART_WIDTH: u32 = 80;
+const ANSI_ART_CHUNK_SIZE: usize = 64;
+
+/// The characters of IBM PC code page 437 for bytes 0x80 through
+/// 0xff, which is the encoding used by most ANSI art
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+                          ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+                          αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// Decodes an ANSI art file into text that can be written to a tty.
+/// Everything from the first ^Z is dropped, as that marks the start
+/// of the SAUCE metadata record.  Files that aren't valid UTF-8 are
+/// decoded as CP437, and bare line feeds are expanded to CRLF as the
+/// tty is in raw mode during playback.
+fn decode_ansi_art(data: &[u8]) -> String {
+    let data = match data.iter().position(|&b| b == 0x1a) {
+        Some(end) => &data[..end],
+        None => data,
+    };
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => {
+            let high: Vec<char> = CP437_HIGH.chars().collect();
+            data.iter()
+                .map(|&b| {
+                    if b < 0x80 {
+                        b as char
+                    } else {
+                        high[(b - 0x80) as usize]
+                    }
+                })
+                .collect()
+        }
+    };
+
+    let mut result = String::with_capacity(text.len());
+    let mut prior = None;
+    for c in text.chars() {
+        if c == '\n' && prior != Some('\r') {
+            result.push('\r');
+        }
+        result.push(c);
+        prior = Some(c);
+    }
+    result.push_str("\x1b[0m");
+    result
+}
+
+/// Returns a header and output events that replay an ANSI art file
+/// at modem speed
+fn ansi_art_recording(data: &[u8]) -> (Header, Vec<Event>) {
+    let text = decode_ansi_art(data);
+    let chars: Vec<char> = text.chars().collect();
+    let mut events = vec![];
+    let mut sent = 0;
+    for chunk in chars.chunks(ANSI_ART_CHUNK_SIZE) {
+        let chunk: String = chunk.iter().collect();
+        events.push(Event(
+            sent as f32 / ANSI_ART_BYTES_PER_SECOND,
+            "o".to_string(),
+            chunk.clone(),
+        ));
+        sent += chunk.len();
+    }
+    let header = Header {
+        version: 2,
+        width: ANSI_ART_WIDTH,
+        ..Default::default()
+    };
+    (header, events)
+}
+
+fn is_ansi_art(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("ans"))
+        .unwrap_or(false)
+}
+
+/// Loads an asciicast or ANSI art file, returning the header and
+/// its output events
+fn load_recording(path: &Path) -> anyhow::Result<(Header, Vec<Event>)> {
+    if is_ansi_art(path) {
+        let data = std::fs::read(path)
+            .with_context(|| format!("reading ANSI art file {}", path.display()))?;
+        return Ok(ansi_art_recording(&data));
+    }
+
+    let cast_file = BufReader::new(
+        std::fs::File::open(path)
+            .with_context(|| format!("reading cast file {}", path.display()))?,
+    );
+    read_cast(cast_file).with_context(|| format!("reading cast file {}", path.display()))
+}
+
+/// Caps the delay between consecutive events to `limit` seconds
+fn apply_idle_time_limit(events: &mut [Event], limit: f32) {
+    let mut prior = 0.;
+    let mut adjusted = 0.;
+    for event in events {
+        adjusted += (event.0 - prior).min(limit);
+        prior = event.0;
+        event.0 = adjusted;
+    }
+}
+
+const MIN_SPEED: f32 = 1. / 16.;
+const MAX_SPEED: f32 = 64.;
+
+/// Tracks the position within a recording as it is played back,
+/// taking the playback speed and pauses into account
+struct PlaybackClock {
+    position: f32,
+    speed: f32,
+    paused: bool,
+    last_tick: Instant,
+}
+
+impl PlaybackClock {
+    fn new(speed: f32) -> Self {
+        Self {
+            position: 0.,
+            speed: speed.max(MIN_SPEED).min(MAX_SPEED),
+            paused: false,
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Brings the position up to date; must be called before
+    /// changing the speed or pause state
+    fn advance(&mut self) {
+        let now = Instant::now();
+        if !self.paused {
+            self.position += (now - self.last_tick).as_secs_f32() * self.speed;
+        }
+        self.last_tick = now;
+    }
+
+    /// How long to wait, in real time, until `target` is reached.
+    /// Returns None while paused.
+    fn wait_until(&self, target: f32) -> Option<Duration> {
+        if self.paused {
+            None
+        } else {
+            Some(Duration::from_secs_f32(
+                (target - self.position).max(0.) / self.speed,
+            ))
+        }
+    }
+}
+
+/// What the user asked for via the keyboard during playback
+enum PlaybackControl {
+    Continue,
+    /// Jump to this position in the recording
+    Seek(f32),
+    Quit,
+}
+
+/// How far the arrow keys seek, in seconds of the recording
+const SEEK_STEP: f32 = 5.;
+
+/// Returns the range of `events` to write to move the display from
+/// just before `events[next]` to `target`, and whether the screen must
+/// be reset first because the seek goes backwards
+fn seek_range(events: &[Event], next: usize, target: f32) -> (std::ops::Range<usize>, bool) {
+    let end = events.partition_point(|event| event.0 <= target);
+    if end < next {
+        (0..end, true)
+    } else {
+        (next..end, false)
+    }
+}
+
 #[derive(Debug, Parser, Clone)]
 pub struct PlayCommand {
     /// Explain what is being sent/received
@@ -513,46 +631,85 @@ pub struct PlayCommand {
     #[arg(long, conflicts_with = "explain")]
     cat: bool,
 
+    /// Playback speed multiplier.  While playing, press space to
+    /// pause or resume, `+` and `-` to double or halve the speed,
+    /// the left and right arrows to seek back or forward 5 seconds,
+    /// `0` to `9` to seek to that tenth of the recording, and `q`
+    /// to stop.
+    #[arg(long, default_value = "1.0")]
+    speed: f32,
+
+    /// Limit the delay between frames to this many seconds,
+    /// overriding the idle_time_limit of the recording
+    #[arg(long)]
+    idle_time_limit: Option<f32>,
+
+    /// Wait for a key press after playback completes, rather than
+    /// exiting immediately
+    #[arg(long)]
+    hold: bool,
+
+    /// The asciicast recording, or ANSI art file with a `.ans`
+    /// extension, to play
     cast_file: PathBuf,
 }
 
 impl PlayCommand {
-    pub fn run(&self) -> anyhow::Result<()> {
-        let mut cast_file = BufReader::new(
-            std::fs::File::open(&self.cast_file)
-                .with_context(|| format!("reading cast file {}", self.cast_file.display()))?,
-        );
-        let mut header_line = String::new();
-        cast_file
-            .read_line(&mut header_line)
-            .context("reading Header line")?;
+    /// Handles keys pressed during playback.  Escape sequences other
+    /// than the arrow keys, such as answerbacks to queries made by the
+    /// recording, are ignored.
+    fn handle_input(
+        &self,
+        data: &[u8],
+        clock: &mut PlaybackClock,
+        duration: f32,
+    ) -> PlaybackControl {
+        clock.advance();
+        match data {
+            b"\x1b[C" | b"\x1bOC" => {
+                return PlaybackControl::Seek((clock.position + SEEK_STEP).min(duration))
+            }
+            b"\x1b[D" | b"\x1bOD" => {
+                return PlaybackControl::Seek((clock.position - SEEK_STEP).max(0.))
+            }
+            _ if data.first() == Some(&0x1b) => return PlaybackControl::Continue,
+            _ => {}
+        }
+        for &b in data {
+            match b {
+                b' ' => clock.paused = !clock.paused,
+                b'+' | b'=' => clock.speed = (clock.speed * 2.).min(MAX_SPEED),
+                b'-' | b'_' => clock.speed = (clock.speed / 2.).max(MIN_SPEED),
+                b'0'..=b'9' => return PlaybackControl::Seek(duration * f32::from(b - b'0') / 10.),
+                b'q' | b'Q' | 0x03 => return PlaybackControl::Quit,
+                _ => {}
+            }
+        }
+        PlaybackControl::Continue
+    }
 
-        let header: Header = serde_json::from_str(&header_line).context("parsing Header")?;
+    pub fn run(&self) -> anyhow::Result<()> {
+        let (header, mut events) = load_recording(&self.cast_file)?;
 
         if self.cat {
-            for line in cast_file.lines() {
-                let line = line?;
-                let event: Event = serde_json::from_str(&line)?;
-                if event.1 != "o" {
-                    continue;
-                }
+            for event in &events {
                 std::io::stdout().write_all(&event.2.as_bytes())?;
             }
 
             return Ok(());
         }
 
+        if let Some(limit) = self.idle_time_limit.or(header.idle_time_limit) {
+            apply_idle_time_limit(&mut events, limit);
+        }
+
         let (tx, rx) = channel();
         let mut sent_parser = TWParser::new();
         let mut sent_actions = vec![];
+        let mut received = vec![];
 
         if self.explain_only {
-            for line in cast_file.lines() {
-                let line = line?;
-                let event: Event = serde_json::from_str(&line)?;
-                if event.1 != "o" {
-                    continue;
-                }
+            for event in &events {
                 sent_parser.parse(&event.2.as_bytes(), |act| sent_actions.push(act));
             }
             drop(tx);
@@ -590,24 +747,78 @@ impl PlayCommand {
                 });
             }
 
-            let start = Instant::now();
+            let mut clock = PlaybackClock::new(self.speed);
+            let mut input_open = true;
+            let duration = events.last().map(|event| event.0).unwrap_or(0.);
+            let mut next = 0;
 
-            for line in cast_file.lines() {
-                let line = line?;
-                let event: Event = serde_json::from_str(&line)?;
-                if event.1 != "o" {
-                    continue;
+            'events: while let Some(event) = events.get(next) {
+                loop {
+                    clock.advance();
+                    if clock.position >= event.0 {
+                        break;
+                    }
+                    let wait = clock.wait_until(event.0);
+                    if !input_open {
+                        std::thread::sleep(wait.unwrap_or_default());
+                        continue;
+                    }
+                    let msg = match wait {
+                        Some(duration) => rx.recv_timeout(duration),
+                        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    };
+                    match msg {
+                        Ok(Message::Stdin(data)) => {
+                            let control = self.handle_input(&data, &mut clock, duration);
+                            received.push(data);
+                            match control {
+                                PlaybackControl::Continue => {}
+                                PlaybackControl::Quit => break 'events,
+                                PlaybackControl::Seek(target) => {
+                                    let (range, reset) = seek_range(&events, next, target);
+                                    if reset {
+                                        // Replay from the start onto a fresh screen
+                                        tty.write_all(b"\x1bc")?;
+                                    }
+                                    next = range.end;
+                                    for event in &events[range] {
+                                        tty.write_all(event.2.as_bytes())?;
+                                        sent_parser.parse(event.2.as_bytes(), |act| {
+                                            sent_actions.push(act)
+                                        });
+                                    }
+                                    clock.position = target;
+                                    continue 'events;
+                                }
+                            }
+                        }
+                        Ok(_) => unreachable!(),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => {
+                            input_open = false;
+                            clock.advance();
+                            clock.paused = false;
+                        }
+                    }
                 }
-                let target = start + Duration::from_secs_f32(event.0);
-                let duration = target.saturating_duration_since(Instant::now());
-                std::thread::sleep(duration);
 
                 tty.write_all(&event.2.as_bytes())?;
                 sent_parser.parse(&event.2.as_bytes(), |act| sent_actions.push(act));
+                next += 1;
             }
 
             std::thread::sleep(Duration::from_millis(100));
 
+            if self.hold && input_open {
+                while let Ok(Message::Stdin(data)) = rx.recv() {
+                    let is_key = data.first() != Some(&0x1b);
+                    received.push(data);
+                    if is_key {
+                        break;
+                    }
+                }
+            }
+
             tty.set_cooked()?;
         }
 
@@ -622,21 +833,22 @@ impl PlayCommand {
             if self.explain {
                 println!("< RECV");
             }
-            let mut parser = TWParser::new();
             while let Ok(msg) = rx.try_recv() {
                 match msg {
-                    Message::Stdin(data) => {
-                        if self.explain {
-                            let answer_back = String::from_utf8_lossy(&data);
-                            println!("\t{:?}", answer_back);
-                            parser.parse(&data, |action| {
-                                println!("\t{:?}", action);
-                            });
-                        }
-                    }
+                    Message::Stdin(data) => received.push(data),
                     _ => unreachable!(),
                 }
             }
+            let mut parser = TWParser::new();
+            for data in received {
+                if self.explain {
+                    let answer_back = String::from_utf8_lossy(&data);
+                    println!("\t{:?}", answer_back);
+                    parser.parse(&data, |action| {
+                        println!("\t{:?}", action);
+                    });
+                }
+            }
         }
 
         Ok(())
@@ -670,3 +882,41 @@ fn summarize(actions: Vec<Action>) -> Vec<Summarized> {
     }
     res
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ansi_art() {
+        let data = b"\x1b[1;34m\xdb\xb0 hi\r\nthere\n\x1aSAUCE00 title";
+        assert_eq!(
+            decode_ansi_art(data),
+            "\x1b[1;34m\u{2588}\u{2591} hi\r\nthere\r\n\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn idle_time_limit() {
+        let mut events = vec![
+            Event(0.5, "o".to_string(), "a".to_string()),
+            Event(10.5, "o".to_string(), "b".to_string()),
+            Event(11., "o".to_string(), "c".to_string()),
+        ];
+        apply_idle_time_limit(&mut events, 2.);
+        let times: Vec<f32> = events.iter().map(|e| e.0).collect();
+        assert_eq!(times, vec![0.5, 2.5, 3.]);
+    }
+
+    #[test]
+    fn seek() {
+        let events: Vec<Event> = [1., 2., 3., 4.]
+            .iter()
+            .map(|&t| Event(t, "o".to_string(), String::new()))
+            .collect();
+        assert_eq!(seek_range(&events, 1, 3.5), (1..3, false));
+        assert_eq!(seek_range(&events, 3, 1.5), (0..1, true));
+        assert_eq!(seek_range(&events, 2, 2.), (2..2, false));
+        assert_eq!(seek_range(&events, 0, 10.), (0..4, false));
+    }
+}
@@ -0,0 +1,17 @@
+use clap::Parser;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ActivateProfile {
+    /// The name of the profile
+    name: String,
+}
+
+impl ActivateProfile {
+    pub async fn run(self, client: Client) -> anyhow::Result<()> {
+        client
+            .activate_config_profile(codec::ActivateConfigProfile { name: self.name })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::cli::window_for_pane;
+use clap::Parser;
+use config::ConfigHandle;
+use mux::pane::PaneId;
+use mux::window::WindowId;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ApplyLayout {
+    /// Specify the current pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    /// The pane is used to determine the window to add the
+    /// layout to.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// Specify the window into which to spawn the layout.
+    /// If omitted, the window associated with the current
+    /// pane is used.
+    /// Cannot be used with `--workspace` or `--new-window`.
+    #[arg(long, conflicts_with_all=&["workspace", "new_window"])]
+    window_id: Option<WindowId>,
+
+    /// Spawn the layout into a new window, rather than a new tab.
+    #[arg(long)]
+    new_window: bool,
+
+    /// When creating a new window, override the default workspace name
+    /// with the provided name.  The default name is "default".
+    /// Requires `--new-window`.
+    #[arg(long, requires = "new_window")]
+    workspace: Option<String>,
+
+    /// The name of the layout in the `layouts` config
+    name: String,
+}
+
+impl ApplyLayout {
+    pub async fn run(self, client: Client, config: &ConfigHandle) -> anyhow::Result<()> {
+        let window_id = if self.new_window {
+            None
+        } else {
+            match self.window_id {
+                Some(w) => Some(w),
+                None => {
+                    let pane_id = client.resolve_pane_id(self.pane_id).await?;
+                    window_for_pane(&client, pane_id).await?
+                }
+            }
+        };
+
+        let workspace = self
+            .workspace
+            .as_deref()
+            .unwrap_or(
+                config
+                    .default_workspace
+                    .as_deref()
+                    .unwrap_or(mux::DEFAULT_WORKSPACE),
+            )
+            .to_string();
+
+        let spawned = client
+            .apply_layout(codec::ApplyLayout {
+                name: self.name,
+                window_id,
+                size: config.initial_size(0, None),
+                workspace,
+            })
+            .await?;
+
+        log::debug!("{:?}", spawned);
+        println!("{}", spawned.pane_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use anyhow::Context;
+use clap::Parser;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ClearShapeCache {}
+
+impl ClearShapeCache {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let dir = config::shape_cache_dir();
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err).with_context(|| format!("removing {}", dir.display())),
+        }
+        println!("Cleared {}", dir.display());
+        Ok(())
+    }
+}
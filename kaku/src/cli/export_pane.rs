@@ -0,0 +1,58 @@
+use anyhow::{bail, Context};
+use clap::Parser;
+use config::keyassignment::ScrollbackExportFormat;
+use mux::pane::PaneId;
+use std::io::Write;
+use std::path::PathBuf;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ExportPane {
+    /// Specify the target pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// The format to write: "text" for plain text, "ansi" for text
+    /// with the escape sequences that reproduce its colors and styling,
+    /// or "html" for an HTML document using the pane's color palette.
+    #[arg(long, default_value = "text", value_parser = parse_format)]
+    format: ScrollbackExportFormat,
+
+    /// The file to write.  The default is to write to stdout.
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    path: Option<PathBuf>,
+}
+
+impl ExportPane {
+    pub async fn run(self, client: Client) -> anyhow::Result<()> {
+        let pane_id = client.resolve_pane_id(self.pane_id).await?;
+        let response = client
+            .export_scrollback(codec::ExportScrollback {
+                pane_id,
+                format: self.format,
+            })
+            .await?;
+
+        match &self.path {
+            Some(path) => std::fs::write(path, response.text)
+                .with_context(|| format!("writing {}", path.display()))?,
+            None => {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(response.text.as_bytes())?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_format(s: &str) -> anyhow::Result<ScrollbackExportFormat> {
+    Ok(match s {
+        "text" | "plain" => ScrollbackExportFormat::PlainText,
+        "ansi" => ScrollbackExportFormat::Ansi,
+        "html" => ScrollbackExportFormat::Html,
+        _ => bail!("unknown format `{s}`; possible values are text, ansi and html"),
+    })
+}
@@ -0,0 +1,121 @@
+use anyhow::{anyhow, bail};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// A `FIELD=VALUE` condition passed to `--filter`.
+/// FIELD is the name of a field in the JSON output; nested fields
+/// are named with dots, eg: `size.cols`.
+/// A `cwd` matches the given directory or any directory beneath it,
+/// and a leading `~` in its value refers to the home directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    field: String,
+    value: String,
+}
+
+impl std::str::FromStr for Filter {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Filter, Self::Err> {
+        let (field, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected FIELD=VALUE, got `{s}`"))?;
+        if field.is_empty() {
+            bail!("expected FIELD=VALUE, got `{s}`");
+        }
+        Ok(Filter {
+            field: field.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Filter {
+    fn matches(&self, item: &Value) -> anyhow::Result<bool> {
+        let pointer = format!("/{}", self.field.replace('.', "/"));
+        let actual = item
+            .pointer(&pointer)
+            .ok_or_else(|| anyhow!("cannot filter on unknown field `{}`", self.field))?;
+        Ok(match actual {
+            Value::Null => self.value.is_empty(),
+            Value::String(s) if self.field == "cwd" => cwd_matches(s, &self.value),
+            Value::String(s) => *s == self.value,
+            other => other.to_string() == self.value,
+        })
+    }
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            config::HOME_DIR.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// `cwd` is reported as a `file://` URL
+fn cwd_matches(cwd: &str, wanted: &str) -> bool {
+    let path = match url::Url::parse(cwd) {
+        Ok(url) => PathBuf::from(
+            percent_decode_str(url.path())
+                .decode_utf8_lossy()
+                .into_owned(),
+        ),
+        Err(_) => PathBuf::from(cwd),
+    };
+    path.starts_with(Path::new(&expand_tilde(wanted)))
+}
+
+/// Returns true if `item` satisfies all of `filters`
+pub fn matches_all<T: Serialize>(item: &T, filters: &[Filter]) -> anyhow::Result<bool> {
+    if filters.is_empty() {
+        return Ok(true);
+    }
+    let value = serde_json::to_value(item)?;
+    for filter in filters {
+        if !filter.matches(&value)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the items that satisfy all of `filters`
+pub fn apply_filters<T: Serialize>(items: Vec<T>, filters: &[Filter]) -> anyhow::Result<Vec<T>> {
+    let mut result = vec![];
+    for item in items {
+        if matches_all(&item, filters)? {
+            result.push(item);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let item = serde_json::json!({
+            "pane_id": 3,
+            "title": "vim",
+            "cwd": "file://host/home/me/project/src",
+            "size": {"rows": 24, "cols": 80},
+            "tty_name": null,
+        });
+        let check = |filter: &str| filter.parse::<Filter>().unwrap().matches(&item).unwrap();
+
+        assert!(check("pane_id=3"));
+        assert!(!check("pane_id=4"));
+        assert!(check("title=vim"));
+        assert!(check("size.cols=80"));
+        assert!(check("cwd=/home/me/project"));
+        assert!(!check("cwd=/home/me/proj"));
+        assert!(check("tty_name="));
+        assert!("nope=1".parse::<Filter>().unwrap().matches(&item).is_err());
+        assert!("title".parse::<Filter>().is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use clap::Parser;
+use config::ConfigHandle;
+use wezterm_font::FontConfiguration;
+
+/// The dpi at which fonts are loaded when the config doesn't specify one
+const DEFAULT_DPI: f64 = 96.;
+
+#[derive(Debug, Parser, Clone)]
+pub struct FontReport {
+    /// The text to explain
+    text: String,
+}
+
+impl FontReport {
+    pub fn run(&self, config: ConfigHandle) -> anyhow::Result<()> {
+        let dpi = config.dpi.unwrap_or(DEFAULT_DPI) as usize;
+        let fonts = FontConfiguration::new(Some(config), dpi)?;
+        print!("{}", wezterm_font::report::font_report(&fonts, &self.text)?);
+        Ok(())
+    }
+}
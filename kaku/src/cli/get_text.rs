@@ -17,9 +17,14 @@ pub struct GetText {
     /// Negative numbers proceed backwards into the scrollback.
     /// The default value is unspecified is 0, the first line of
     /// the terminal screen.
-    #[arg(long, allow_hyphen_values = true)]
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "scrollback")]
     start_line: Option<ScrollbackOrVisibleRowIndex>,
 
+    /// Start from the oldest line of the scrollback rather than
+    /// the first line of the terminal screen.
+    #[arg(long)]
+    scrollback: bool,
+
     /// The ending line number.
     /// 0 is the first line of terminal screen.
     /// Negative numbers proceed backwards into the scrollback.
@@ -32,6 +37,12 @@ pub struct GetText {
     /// If omitted, unattributed text will be returned.
     #[arg(long)]
     escapes: bool,
+
+    /// Remove whitespace from the end of each line, and omit
+    /// blank lines from the end of the output.
+    /// Ignored when `--escapes` is used.
+    #[arg(long)]
+    trim: bool,
 }
 
 impl GetText {
@@ -43,6 +54,7 @@ impl GetText {
             .await?;
 
         let start_line = match self.start_line {
+            None if self.scrollback => info.dimensions.scrollback_top,
             None => info.dimensions.physical_top,
             Some(n) if n >= 0 => info.dimensions.physical_top + n as StableRowIndex,
             Some(n) => {
@@ -85,6 +97,15 @@ impl GetText {
 
         if self.escapes {
             println!("{}", lines_to_escapes(lines)?);
+        } else if self.trim {
+            let mut text: Vec<String> = lines
+                .iter()
+                .map(|line| line.as_str().trim_end().to_string())
+                .collect();
+            while text.last().map_or(false, |line| line.is_empty()) {
+                text.pop();
+            }
+            text.iter().for_each(|line| println!("{}", line));
         } else {
             lines.iter().for_each(|line| println!("{}", line.as_str()));
         }
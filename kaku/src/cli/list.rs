@@ -1,3 +1,4 @@
+use crate::cli::filter::{apply_filters, Filter};
 use crate::cli::CliOutputFormatKind;
 use clap::Parser;
 use serde::Serializer as _;
@@ -5,12 +6,19 @@ use tabout::{tabulate_output, Alignment, Column};
 use wezterm_client::client::Client;
 use wezterm_term::TerminalSize;
 
-#[derive(Debug, Parser, Clone, Copy)]
+#[derive(Debug, Parser, Clone)]
 pub struct ListCommand {
     /// Controls the output format.
     /// "table" and "json" are possible formats.
     #[arg(long = "format", default_value = "table")]
     format: CliOutputFormatKind,
+
+    /// Only output the entries whose FIELD matches VALUE.
+    /// FIELD is the name of a field in the JSON output.
+    /// May be repeated, in which case all filters must match.
+    /// A `cwd` filter matches that directory and those beneath it.
+    #[arg(long = "filter", value_name = "FIELD=VALUE")]
+    filter: Vec<Filter>,
 }
 
 impl ListCommand {
@@ -42,6 +50,7 @@ impl ListCommand {
                 }
             }
         }
+        let output_items = apply_filters(output_items, &self.filter)?;
         match self.format {
             CliOutputFormatKind::Json => {
                 let mut writer = serde_json::Serializer::pretty(out.lock());
@@ -77,6 +86,14 @@ impl ListCommand {
                         name: "CWD".to_string(),
                         alignment: Alignment::Left,
                     },
+                    Column {
+                        name: "PID".to_string(),
+                        alignment: Alignment::Right,
+                    },
+                    Column {
+                        name: "LOCKED".to_string(),
+                        alignment: Alignment::Left,
+                    },
                 ];
                 let data = output_items
                     .iter()
@@ -89,6 +106,16 @@ impl ListCommand {
                             format!("{}x{}", output_item.size.cols, output_item.size.rows),
                             output_item.title.to_string(),
                             output_item.cwd.to_string(),
+                            output_item
+                                .pid
+                                .map(|pid| pid.to_string())
+                                .unwrap_or_default(),
+                            if output_item.is_input_locked {
+                                "yes"
+                            } else {
+                                ""
+                            }
+                            .to_string(),
                         ]
                     })
                     .collect::<Vec<_>>();
@@ -139,6 +166,10 @@ struct CliListResultItem {
     is_active: bool,
     is_zoomed: bool,
     tty_name: Option<String>,
+    /// The id of the process spawned into the pane, if it is known
+    pid: Option<u32>,
+    /// true if the pane is read-only because of TogglePaneInputLock
+    is_input_locked: bool,
 }
 
 impl CliListResultItem {
@@ -157,6 +188,8 @@ impl CliListResultItem {
             is_active_pane,
             is_zoomed_pane,
             tty_name,
+            pid,
+            is_input_locked,
             size:
                 TerminalSize {
                     rows,
@@ -197,6 +230,8 @@ impl CliListResultItem {
             is_active: is_active_pane,
             is_zoomed: is_zoomed_pane,
             tty_name,
+            pid,
+            is_input_locked,
         }
     }
 }
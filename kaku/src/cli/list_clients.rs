@@ -1,3 +1,4 @@
+use crate::cli::filter::{matches_all, Filter};
 use crate::cli::CliOutputFormatKind;
 use chrono::{DateTime, Utc};
 use clap::Parser;
@@ -5,25 +6,33 @@ use serde::Serializer as _;
 use tabout::{tabulate_output, Alignment, Column};
 use wezterm_client::client::Client;
 
-#[derive(Debug, Parser, Clone, Copy)]
+#[derive(Debug, Parser, Clone)]
 pub struct ListClientsCommand {
     /// Controls the output format.
     /// "table" and "json" are possible formats.
     #[arg(long = "format", default_value = "table")]
     format: CliOutputFormatKind,
+
+    /// Only output the entries whose FIELD matches VALUE.
+    /// FIELD is the name of a field in the JSON output.
+    /// May be repeated, in which case all filters must match.
+    /// A `cwd` filter matches that directory and those beneath it.
+    #[arg(long = "filter", value_name = "FIELD=VALUE")]
+    filter: Vec<Filter>,
 }
 
 impl ListClientsCommand {
     pub async fn run(&self, client: Client) -> anyhow::Result<()> {
         let out = std::io::stdout();
-        let clients = client.list_clients().await?;
+        let mut clients = vec![];
+        for info in client.list_clients().await?.clients {
+            if matches_all(&CliListClientsResultItem::from(info.clone()), &self.filter)? {
+                clients.push(info);
+            }
+        }
         match self.format {
             CliOutputFormatKind::Json => {
-                let clients = clients
-                    .clients
-                    .iter()
-                    .cloned()
-                    .map(CliListClientsResultItem::from);
+                let clients = clients.into_iter().map(CliListClientsResultItem::from);
                 let mut writer = serde_json::Serializer::pretty(out.lock());
                 writer.collect_seq(clients)?;
             }
@@ -86,7 +95,7 @@ impl ListClientsCommand {
                     }
                 }
 
-                for info in clients.clients {
+                for info in clients {
                     let connected = now - info.connected_at;
                     let idle = now - info.last_input;
                     data.push(vec![
@@ -0,0 +1,126 @@
+use crate::cli::filter::{matches_all, Filter};
+use crate::cli::CliOutputFormatKind;
+use clap::Parser;
+use serde::Serializer as _;
+use std::time::Duration;
+use tabout::{tabulate_output, Alignment, Column};
+use wezterm_client::known_servers::pinned_fingerprint;
+use wezterm_client::mdns::{browse, DiscoveredServer};
+
+#[derive(Debug, Parser, Clone)]
+pub struct ListDiscoveredCommand {
+    /// Controls the output format.
+    /// "table" and "json" are possible formats.
+    #[arg(long = "format", default_value = "table")]
+    format: CliOutputFormatKind,
+
+    /// Only output the entries whose FIELD matches VALUE.
+    /// FIELD is the name of a field in the JSON output.
+    /// May be repeated, in which case all filters must match.
+    #[arg(long = "filter", value_name = "FIELD=VALUE")]
+    filter: Vec<Filter>,
+
+    /// How many milliseconds to wait for servers to answer
+    #[arg(long, default_value = "2000")]
+    timeout_ms: u64,
+}
+
+impl ListDiscoveredCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let out = std::io::stdout();
+        let mut servers = vec![];
+        for server in browse(Duration::from_millis(self.timeout_ms))? {
+            let item = CliListDiscoveredResultItem::from(server);
+            if matches_all(&item, &self.filter)? {
+                servers.push(item);
+            }
+        }
+        match self.format {
+            CliOutputFormatKind::Json => {
+                let mut writer = serde_json::Serializer::pretty(out.lock());
+                writer.collect_seq(servers)?;
+            }
+            CliOutputFormatKind::Table => {
+                let cols = vec![
+                    Column {
+                        name: "DOMAIN".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                    Column {
+                        name: "HOST".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                    Column {
+                        name: "ADDRESS".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                    Column {
+                        name: "PINNED".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                ];
+                let data: Vec<Vec<String>> = servers
+                    .into_iter()
+                    .map(|server| {
+                        vec![
+                            server.domain,
+                            server.host,
+                            server.remote_address,
+                            if server.pinned_fingerprint.is_some() {
+                                "*"
+                            } else {
+                                ""
+                            }
+                            .to_string(),
+                        ]
+                    })
+                    .collect();
+
+                tabulate_output(&cols, &data, &mut out.lock())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// This will be serialized to JSON via the 'ListDiscovered' command.
+// As such it is intended to be a stable output format,
+// Thus we need to be careful about the stability of the fields and types
+// herein as they are directly reflected in the output.
+#[derive(serde::Serialize)]
+struct CliListDiscoveredResultItem {
+    name: String,
+    domain: String,
+    host: String,
+    port: u16,
+    addresses: Vec<String>,
+    remote_address: String,
+    properties: std::collections::BTreeMap<String, String>,
+    pinned_fingerprint: Option<String>,
+}
+
+impl From<DiscoveredServer> for CliListDiscoveredResultItem {
+    fn from(server: DiscoveredServer) -> CliListDiscoveredResultItem {
+        let domain = server.domain_name();
+        let remote_address = server.remote_address();
+        let pinned_fingerprint = pinned_fingerprint(&domain);
+        let DiscoveredServer {
+            name,
+            host,
+            port,
+            addresses,
+            properties,
+        } = server;
+
+        CliListDiscoveredResultItem {
+            name,
+            domain,
+            host,
+            port,
+            addresses: addresses.iter().map(|addr| addr.to_string()).collect(),
+            remote_address,
+            properties,
+            pinned_fingerprint,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::cli::filter::{matches_all, Filter};
+use crate::cli::CliOutputFormatKind;
+use clap::Parser;
+use serde::Serializer as _;
+use tabout::{tabulate_output, Alignment, Column};
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ListDomainsCommand {
+    /// Controls the output format.
+    /// "table" and "json" are possible formats.
+    #[arg(long = "format", default_value = "table")]
+    format: CliOutputFormatKind,
+
+    /// Only output the entries whose FIELD matches VALUE.
+    /// FIELD is the name of a field in the JSON output.
+    /// May be repeated, in which case all filters must match.
+    #[arg(long = "filter", value_name = "FIELD=VALUE")]
+    filter: Vec<Filter>,
+}
+
+impl ListDomainsCommand {
+    pub async fn run(&self, client: Client) -> anyhow::Result<()> {
+        let out = std::io::stdout();
+        let mut domains = vec![];
+        for info in client.list_domains().await?.domains {
+            let item = CliListDomainsResultItem::from(info);
+            if matches_all(&item, &self.filter)? {
+                domains.push(item);
+            }
+        }
+        match self.format {
+            CliOutputFormatKind::Json => {
+                let mut writer = serde_json::Serializer::pretty(out.lock());
+                writer.collect_seq(domains)?;
+            }
+            CliOutputFormatKind::Table => {
+                let cols = vec![
+                    Column {
+                        name: "ID".to_string(),
+                        alignment: Alignment::Right,
+                    },
+                    Column {
+                        name: "NAME".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                    Column {
+                        name: "STATE".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                    Column {
+                        name: "DEFAULT".to_string(),
+                        alignment: Alignment::Left,
+                    },
+                ];
+                let data: Vec<Vec<String>> = domains
+                    .into_iter()
+                    .map(|domain| {
+                        vec![
+                            domain.domain_id.to_string(),
+                            domain.name,
+                            if domain.attached {
+                                "attached"
+                            } else {
+                                "detached"
+                            }
+                            .to_string(),
+                            if domain.is_default { "*" } else { "" }.to_string(),
+                        ]
+                    })
+                    .collect();
+
+                tabulate_output(&cols, &data, &mut out.lock())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// This will be serialized to JSON via the 'ListDomains' command.
+// As such it is intended to be a stable output format,
+// Thus we need to be careful about the stability of the fields and types
+// herein as they are directly reflected in the output.
+#[derive(serde::Serialize)]
+struct CliListDomainsResultItem {
+    domain_id: mux::domain::DomainId,
+    name: String,
+    attached: bool,
+    detachable: bool,
+    is_default: bool,
+}
+
+impl From<codec::DomainInfo> for CliListDomainsResultItem {
+    fn from(info: codec::DomainInfo) -> CliListDomainsResultItem {
+        let codec::DomainInfo {
+            domain_id,
+            name,
+            attached,
+            detachable,
+            is_default,
+        } = info;
+
+        CliListDomainsResultItem {
+            domain_id,
+            name,
+            attached,
+            detachable,
+            is_default,
+        }
+    }
+}
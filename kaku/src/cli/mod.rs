@@ -1,26 +1,42 @@
 use anyhow::anyhow;
 use clap::Parser;
+use mux::pane::PaneId;
+use mux::window::WindowId;
 use std::ffi::OsString;
 use wezterm_client::client::Client;
 
 mod activate_pane;
 mod activate_pane_direction;
+mod activate_profile;
 mod activate_tab;
 mod adjust_pane_size;
+mod apply_layout;
+mod clear_shape_cache;
+mod export_pane;
+mod filter;
+mod font_report;
 mod get_pane_direction;
 mod get_text;
 mod kill_pane;
 mod list;
 mod list_clients;
+mod list_discovered;
+mod list_domains;
 mod move_pane_to_new_tab;
+mod play;
 mod proxy;
+mod record_pane;
 mod rename_workspace;
+mod resize_pane;
+mod selftest;
+mod send_key;
 mod send_text;
 mod set_tab_title;
 mod set_window_title;
 mod spawn_command;
 mod split_pane;
 mod tls_creds;
+mod wait_for_text;
 mod zoom_pane;
 
 #[derive(Debug, Parser, Clone, Copy)]
@@ -75,12 +91,26 @@ pub struct CliCommand {
 
 #[derive(Debug, Parser, Clone)]
 enum CliSubCommand {
-    #[command(name = "list", about = "list windows, tabs and panes")]
+    #[command(
+        name = "list",
+        visible_alias = "list-panes",
+        about = "list windows, tabs and panes"
+    )]
     List(list::ListCommand),
 
     #[command(name = "list-clients", about = "list clients")]
     ListClients(list_clients::ListClientsCommand),
 
+    #[command(name = "list-domains", about = "list domains")]
+    ListDomains(list_domains::ListDomainsCommand),
+
+    /// List the mux servers that advertise themselves on the local
+    /// network via mDNS.  Connect to one with
+    /// `kaku start --domain DOMAIN`, which pins the certificate
+    /// that the server presents the first time.
+    #[command(name = "list-discovered", rename_all = "kebab")]
+    ListDiscovered(list_discovered::ListDiscoveredCommand),
+
     #[command(name = "proxy", about = "start rpc proxy pipe")]
     Proxy(proxy::ProxyCommand),
 
@@ -111,16 +141,44 @@ Outputs the pane-id for the newly created pane on success"
     )]
     SpawnCommand(spawn_command::SpawnCommand),
 
+    #[command(
+        name = "play",
+        about = "Replay an asciicast recording or ANSI art file in a new tab, window or pane.
+Outputs the pane-id for the newly created pane on success"
+    )]
+    Play(play::PlayCommand),
+
     /// Send text to a pane as though it were pasted.
     /// If bracketed paste mode is enabled in the pane, then the
     /// text will be sent as a bracketed paste.
     #[command(name = "send-text", rename_all = "kebab")]
     SendText(send_text::SendText),
 
+    /// Send key presses to a pane, encoded according to the
+    /// keyboard modes that are active in that pane.
+    #[command(name = "send-key", rename_all = "kebab")]
+    SendKey(send_key::SendKey),
+
     /// Retrieves the textual content of a pane and output it to stdout
     #[command(name = "get-text", rename_all = "kebab")]
     GetText(get_text::GetText),
 
+    /// Write the scrollback of a pane to a file as plain text,
+    /// text with escape sequences, or HTML
+    #[command(name = "export-pane", rename_all = "kebab")]
+    ExportPane(export_pane::ExportPane),
+
+    /// Start or stop recording the output of a pane to an asciinema
+    /// `.cast` file in the recordings folder, and print its path
+    #[command(name = "record", rename_all = "kebab")]
+    Record(record_pane::RecordPane),
+
+    /// Wait for text matching a regular expression to appear in
+    /// the viewport of a pane, then print the matched text.
+    /// Exits with an error if the timeout elapses first.
+    #[command(name = "wait-for-text", rename_all = "kebab")]
+    WaitForText(wait_for_text::WaitForText),
+
     /// Activate an adjacent pane in the specified direction.
     #[command(name = "activate-pane-direction", rename_all = "kebab")]
     ActivatePaneDirection(activate_pane_direction::ActivatePaneDirection),
@@ -144,6 +202,16 @@ Outputs the pane-id for the newly created pane on success"
     #[command(name = "adjust-pane-size", rename_all = "kebab")]
     AdjustPaneSize(adjust_pane_size::CliAdjustPaneSize),
 
+    /// Resize a pane to an exact size in cells, by moving the split
+    /// dividers around it
+    #[command(name = "resize-pane", rename_all = "kebab")]
+    ResizePane(resize_pane::ResizePane),
+
+    /// Spawn a layout preset from the `layouts` config into a new tab,
+    /// and print the pane-id of its active pane
+    #[command(name = "apply-layout", rename_all = "kebab")]
+    ApplyLayout(apply_layout::ApplyLayout),
+
     /// Activate a tab
     #[command(name = "activate-tab", rename_all = "kebab")]
     ActivateTab(activate_tab::ActivateTab),
@@ -160,12 +228,48 @@ Outputs the pane-id for the newly created pane on success"
     #[command(name = "rename-workspace", rename_all = "kebab")]
     RenameWorkspace(rename_workspace::RenameWorkspace),
 
+    /// Activate an entry of the `profiles` config table, or
+    /// `default` to return to the config without a profile.
+    /// The choice is remembered across restarts.
+    #[command(name = "activate-profile", rename_all = "kebab")]
+    ActivateProfile(activate_profile::ActivateProfile),
+
     /// Zoom, unzoom, or toggle zoom state
     #[command(name = "zoom-pane", rename_all = "kebab")]
     ZoomPane(zoom_pane::ZoomPane),
+
+    /// Run a scripted scenario against an in-process mux to check
+    /// that spawning, splitting, input, output and resizing work
+    #[command(name = "selftest", rename_all = "kebab")]
+    SelfTest(selftest::SelfTest),
+
+    /// Explain which font supplies each glyph of some text, how the
+    /// text was shaped, and whether fallback fonts were needed
+    #[command(name = "font-report", rename_all = "kebab")]
+    FontReport(font_report::FontReport),
+
+    /// Remove the text shaping results that are persisted between
+    /// runs; they are rebuilt as text is displayed.  Running windows
+    /// may write some of them back until they are closed.
+    #[command(name = "clear-shape-cache", rename_all = "kebab")]
+    ClearShapeCache(clear_shape_cache::ClearShapeCache),
 }
 
 async fn run_cli_async(opts: &crate::Opt, cli: CliCommand) -> anyhow::Result<()> {
+    // These are self-contained and don't need a mux server
+    if let CliSubCommand::SelfTest(cmd) = &cli.sub {
+        return cmd.run().await;
+    }
+    if let CliSubCommand::ClearShapeCache(cmd) = &cli.sub {
+        return cmd.run();
+    }
+    if let CliSubCommand::ListDiscovered(cmd) = &cli.sub {
+        return cmd.run();
+    }
+    if let CliSubCommand::FontReport(cmd) = &cli.sub {
+        return cmd.run(crate::init_config(opts)?);
+    }
+
     let mut ui = mux::connui::ConnectionUI::new_headless();
     let initial = true;
 
@@ -181,12 +285,18 @@ async fn run_cli_async(opts: &crate::Opt, cli: CliCommand) -> anyhow::Result<()>
 
     match cli.sub {
         CliSubCommand::ListClients(cmd) => cmd.run(client).await,
+        CliSubCommand::ListDomains(cmd) => cmd.run(client).await,
         CliSubCommand::List(cmd) => cmd.run(client).await,
         CliSubCommand::MovePaneToNewTab(cmd) => cmd.run(client).await,
         CliSubCommand::SplitPane(cmd) => cmd.run(client).await,
         CliSubCommand::SendText(cmd) => cmd.run(client).await,
+        CliSubCommand::SendKey(cmd) => cmd.run(client).await,
         CliSubCommand::GetText(cmd) => cmd.run(client).await,
+        CliSubCommand::ExportPane(cmd) => cmd.run(client).await,
+        CliSubCommand::Record(cmd) => cmd.run(client).await,
+        CliSubCommand::WaitForText(cmd) => cmd.run(client).await,
         CliSubCommand::SpawnCommand(cmd) => cmd.run(client, &crate::init_config(opts)?).await,
+        CliSubCommand::Play(cmd) => cmd.run(client, &crate::init_config(opts)?).await,
         CliSubCommand::Proxy(cmd) => cmd.run(client, &crate::init_config(opts)?).await,
         CliSubCommand::TlsCreds(cmd) => cmd.run(client).await,
         CliSubCommand::ActivatePaneDirection(cmd) => cmd.run(client).await,
@@ -194,11 +304,18 @@ async fn run_cli_async(opts: &crate::Opt, cli: CliCommand) -> anyhow::Result<()>
         CliSubCommand::KillPane(cmd) => cmd.run(client).await,
         CliSubCommand::ActivatePane(cmd) => cmd.run(client).await,
         CliSubCommand::AdjustPaneSize(cmd) => cmd.run(client).await,
+        CliSubCommand::ResizePane(cmd) => cmd.run(client).await,
+        CliSubCommand::ApplyLayout(cmd) => cmd.run(client, &crate::init_config(opts)?).await,
         CliSubCommand::ActivateTab(cmd) => cmd.run(client).await,
         CliSubCommand::SetTabTitle(cmd) => cmd.run(client).await,
         CliSubCommand::SetWindowTitle(cmd) => cmd.run(client).await,
         CliSubCommand::RenameWorkspace(cmd) => cmd.run(client).await,
+        CliSubCommand::ActivateProfile(cmd) => cmd.run(client).await,
         CliSubCommand::ZoomPane(cmd) => cmd.run(client).await,
+        CliSubCommand::SelfTest(_)
+        | CliSubCommand::ClearShapeCache(_)
+        | CliSubCommand::ListDiscovered(_)
+        | CliSubCommand::FontReport(_) => unreachable!("handled above"),
     }
 }
 
@@ -210,6 +327,27 @@ pub fn run_cli(opts: &crate::Opt, cli: CliCommand) -> anyhow::Result<()> {
     }
 }
 
+/// Returns the id of the window that contains `pane_id`
+pub async fn window_for_pane(client: &Client, pane_id: PaneId) -> anyhow::Result<Option<WindowId>> {
+    let panes = client.list_panes().await?;
+    for tabroot in panes.tabs {
+        let mut cursor = tabroot.into_tree().cursor();
+
+        loop {
+            if let Some(entry) = cursor.leaf_mut() {
+                if entry.pane_id == pane_id {
+                    return Ok(Some(entry.window_id));
+                }
+            }
+            match cursor.preorder_next() {
+                Ok(c) => cursor = c,
+                Err(_) => break,
+            }
+        }
+    }
+    Ok(None)
+}
+
 pub fn resolve_relative_cwd(cwd: Option<OsString>) -> anyhow::Result<Option<String>> {
     match cwd {
         None => Ok(None),
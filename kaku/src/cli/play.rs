@@ -0,0 +1,109 @@
+use crate::cli::window_for_pane;
+use clap::{Parser, ValueHint};
+use config::keyassignment::SpawnTabDomain;
+use config::ConfigHandle;
+use mux::pane::PaneId;
+use mux::tab::{SplitDirection, SplitRequest, SplitSize};
+use portable_pty::cmdbuilder::CommandBuilder;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct PlayCommand {
+    /// Specify the current pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    /// The new tab is added to the window of this pane, or
+    /// this pane is split when `--split` is used.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// Play in a new window, rather than a new tab
+    #[arg(long, conflicts_with = "split")]
+    new_window: bool,
+
+    /// Play in a new pane alongside the current pane, rather than
+    /// in a new tab
+    #[arg(long)]
+    split: bool,
+
+    /// Playback speed multiplier.  While playing, press space to
+    /// pause or resume, `+` and `-` to double or halve the speed,
+    /// the left and right arrows to seek back or forward 5 seconds,
+    /// `0` to `9` to seek to that tenth of the recording, and `q`
+    /// to stop.
+    #[arg(long, default_value = "1.0")]
+    speed: f32,
+
+    /// The asciicast recording, or ANSI art file with a `.ans`
+    /// extension, to play
+    #[arg(value_hint=ValueHint::FilePath)]
+    file: PathBuf,
+}
+
+impl PlayCommand {
+    /// Returns the command that replays the file in the new pane
+    fn replay_command(&self) -> anyhow::Result<CommandBuilder> {
+        let file = std::env::current_dir()?.join(&self.file);
+        anyhow::ensure!(file.exists(), "{} does not exist", file.display());
+        let argv: Vec<OsString> = vec![
+            std::env::current_exe()?.into(),
+            "replay".into(),
+            "--hold".into(),
+            "--speed".into(),
+            self.speed.to_string().into(),
+            file.into(),
+        ];
+        Ok(CommandBuilder::from_argv(argv))
+    }
+
+    pub async fn run(self, client: Client, config: &ConfigHandle) -> anyhow::Result<()> {
+        let command = self.replay_command()?;
+
+        if self.split {
+            let pane_id = client.resolve_pane_id(self.pane_id).await?;
+            let spawned = client
+                .split_pane(codec::SplitPane {
+                    pane_id,
+                    split_request: SplitRequest {
+                        direction: SplitDirection::Horizontal,
+                        target_is_second: true,
+                        size: SplitSize::Percent(50),
+                        top_level: false,
+                    },
+                    domain: SpawnTabDomain::DefaultDomain,
+                    command: Some(command),
+                    command_dir: None,
+                    move_pane_id: None,
+                })
+                .await?;
+            println!("{}", spawned.pane_id);
+            return Ok(());
+        }
+
+        let window_id = if self.new_window {
+            None
+        } else {
+            let pane_id = client.resolve_pane_id(self.pane_id).await?;
+            window_for_pane(&client, pane_id).await?
+        };
+
+        let spawned = client
+            .spawn_v2(codec::SpawnV2 {
+                domain: SpawnTabDomain::DefaultDomain,
+                window_id,
+                command: Some(command),
+                command_dir: None,
+                size: config.initial_size(0, None),
+                workspace: config
+                    .default_workspace
+                    .as_deref()
+                    .unwrap_or(mux::DEFAULT_WORKSPACE)
+                    .to_string(),
+            })
+            .await?;
+        println!("{}", spawned.pane_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use clap::Parser;
+use mux::pane::PaneId;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct RecordPane {
+    /// Specify the target pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// Stop recording, rather than start it
+    #[arg(long)]
+    stop: bool,
+}
+
+impl RecordPane {
+    pub async fn run(self, client: Client) -> anyhow::Result<()> {
+        let pane_id = client.resolve_pane_id(self.pane_id).await?;
+        let response = client
+            .set_pane_recording(codec::SetPaneRecording {
+                pane_id,
+                recording: !self.stop,
+            })
+            .await?;
+        match response.path {
+            Some(path) => println!("{}", path.display()),
+            None => anyhow::bail!("pane {pane_id} is not being recorded"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use codec::SetPaneSize;
+use mux::pane::PaneId;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct ResizePane {
+    /// Specify the target pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// The size to make the pane, as COLSxROWS, eg: `100x30`.
+    /// Either dimension may be omitted to leave it unchanged,
+    /// eg: `100x` or `x30`.
+    /// The size is clamped so that the other panes in the tab
+    /// remain visible.
+    #[arg(long, value_parser = parse_cells)]
+    cells: (Option<usize>, Option<usize>),
+}
+
+impl ResizePane {
+    pub async fn run(&self, client: Client) -> anyhow::Result<()> {
+        let pane_id = client.resolve_pane_id(self.pane_id).await?;
+        let (cols, rows) = self.cells;
+        client
+            .set_pane_size(SetPaneSize {
+                pane_id,
+                cols,
+                rows,
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+fn parse_cells(s: &str) -> anyhow::Result<(Option<usize>, Option<usize>)> {
+    let (cols, rows) = s
+        .split_once('x')
+        .ok_or_else(|| anyhow!("expected COLSxROWS, eg: 100x30"))?;
+    let parse = |n: &str| -> anyhow::Result<Option<usize>> {
+        if n.is_empty() {
+            return Ok(None);
+        }
+        let n: usize = n.parse().with_context(|| format!("invalid size `{n}`"))?;
+        if n == 0 {
+            anyhow::bail!("the size must be at least 1 cell");
+        }
+        Ok(Some(n))
+    };
+    Ok((parse(cols)?, parse(rows)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        assert_eq!(parse_cells("100x30").unwrap(), (Some(100), Some(30)));
+        assert_eq!(parse_cells("100x").unwrap(), (Some(100), None));
+        assert_eq!(parse_cells("x30").unwrap(), (None, Some(30)));
+        assert!(parse_cells("100").is_err());
+        assert!(parse_cells("0x30").is_err());
+        assert!(parse_cells("ax30").is_err());
+    }
+}
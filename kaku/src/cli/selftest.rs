@@ -0,0 +1,338 @@
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use config::keyassignment::SpawnTabDomain;
+use mux::domain::{Domain, LocalDomain, SplitSource};
+use mux::pane::Pane;
+use mux::tab::{SplitDirection, SplitRequest, SplitSize};
+use mux::Mux;
+use portable_pty::CommandBuilder;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wezterm_term::TerminalSize;
+
+const DEFAULT_SCENARIO: &str = r#"
+# Each line is a step; see `kaku cli selftest --help`
+spawn
+type echo kaku-$((6*7))\r
+expect kaku-42
+resize 100x30
+type stty size\r
+expect 30 100
+split
+type echo right-$((2+3))\r
+expect right-5
+type stty size\r
+expect 30 50
+select 0
+type exit\r
+select 1
+type exit\r
+"#;
+
+/// Runs a scripted scenario against a mux and local domain hosted
+/// in-process, without connecting to a mux server or opening any
+/// windows, and reports the outcome of each step.
+/// This exercises spawning, splitting, resizing and the terminal
+/// model of the panes; nothing is rendered.
+///
+/// A scenario is a list of steps, one per line:
+///
+///   spawn              start a shell in a new tab and select its pane
+///   split              split the selected pane, selecting the new right half
+///   select N           select the Nth pane to be spawned (starting at 0)
+///   type TEXT          send TEXT to the pane; \r, \n, \t and \\ are recognized
+///   expect TEXT        wait for TEXT to appear on the screen of the pane
+///   resize COLSxROWS   resize the tab of the selected pane
+///
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Parser, Clone)]
+pub struct SelfTest {
+    /// Run the scenario from this file rather than the built-in one
+    #[arg(long, value_hint=clap::ValueHint::FilePath)]
+    script: Option<PathBuf>,
+
+    /// How many seconds to wait for an expectation to be met
+    #[arg(long, default_value = "10")]
+    timeout: u64,
+
+    /// The shell to run in each pane
+    #[arg(long, default_value = "/bin/sh")]
+    shell: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Spawn,
+    Split,
+    Select(usize),
+    Type(String),
+    Expect(String),
+    Resize { cols: usize, rows: usize },
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => result.push('\r'),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn parse_size(size: &str) -> anyhow::Result<(usize, usize)> {
+    let (cols, rows) = size
+        .split_once('x')
+        .ok_or_else(|| anyhow!("expected COLSxROWS, got {size:?}"))?;
+    Ok((cols.parse()?, rows.parse()?))
+}
+
+fn parse_step(line: &str) -> anyhow::Result<Step> {
+    let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+    Ok(match command {
+        "spawn" => Step::Spawn,
+        "split" => Step::Split,
+        "select" => Step::Select(
+            arg.parse()
+                .with_context(|| format!("expected a pane index, got {arg:?}"))?,
+        ),
+        "type" => Step::Type(unescape(arg)),
+        "expect" => Step::Expect(arg.to_string()),
+        "resize" => {
+            let (cols, rows) = parse_size(arg)?;
+            Step::Resize { cols, rows }
+        }
+        _ => bail!("unknown step {command:?}"),
+    })
+}
+
+/// Parses a whole scenario up front, so that a typo is reported
+/// before any shells are started.  Each step is returned along with
+/// its line number and text.
+fn parse_scenario(script: &str) -> anyhow::Result<Vec<(usize, &str, Step)>> {
+    let mut steps = vec![];
+    for (idx, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let step = parse_step(line).with_context(|| format!("line {}", idx + 1))?;
+        steps.push((idx + 1, line, step));
+    }
+    Ok(steps)
+}
+
+fn screen_text(pane: &dyn Pane) -> String {
+    let dims = pane.get_dimensions();
+    let (_first_row, lines) =
+        pane.get_lines(dims.physical_top..dims.physical_top + dims.viewport_rows as isize);
+    lines
+        .iter()
+        .map(|line| line.as_str().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Scenario {
+    mux: Arc<Mux>,
+    panes: Vec<Arc<dyn Pane>>,
+    current: usize,
+    shell: String,
+    timeout: Duration,
+}
+
+impl Scenario {
+    fn current(&self) -> anyhow::Result<&Arc<dyn Pane>> {
+        self.panes
+            .get(self.current)
+            .ok_or_else(|| anyhow!("no pane has been spawned"))
+    }
+
+    fn command(&self) -> CommandBuilder {
+        let mut cmd = CommandBuilder::new(&self.shell);
+        cmd.env("PS1", "$ ");
+        cmd
+    }
+
+    async fn wait_for(&self, text: &str) -> anyhow::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let screen = screen_text(&**self.current()?);
+            if screen.contains(text) {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                bail!("timed out waiting for {text:?}; screen contents:\n{screen}");
+            }
+            // Yield to the executor, which delivers the mux notifications
+            smol::Timer::after(Duration::from_millis(20)).await;
+        }
+    }
+
+    async fn step(&mut self, step: &Step) -> anyhow::Result<()> {
+        match step {
+            Step::Spawn => {
+                let (_tab, pane, _window_id) = self
+                    .mux
+                    .spawn_tab_or_window(
+                        None,
+                        SpawnTabDomain::DefaultDomain,
+                        Some(self.command()),
+                        None,
+                        TerminalSize::default(),
+                        None,
+                        mux::DEFAULT_WORKSPACE.to_string(),
+                        None,
+                    )
+                    .await?;
+                self.panes.push(pane);
+                self.current = self.panes.len() - 1;
+            }
+            Step::Split => {
+                let pane_id = self.current()?.pane_id();
+                let (pane, _size) = self
+                    .mux
+                    .split_pane(
+                        pane_id,
+                        SplitRequest {
+                            direction: SplitDirection::Horizontal,
+                            target_is_second: true,
+                            top_level: false,
+                            size: SplitSize::Percent(50),
+                        },
+                        SplitSource::Spawn {
+                            command: Some(self.command()),
+                            command_dir: None,
+                        },
+                        SpawnTabDomain::CurrentPaneDomain,
+                    )
+                    .await?;
+                self.panes.push(pane);
+                self.current = self.panes.len() - 1;
+            }
+            Step::Select(idx) => {
+                if *idx >= self.panes.len() {
+                    bail!("there are only {} panes", self.panes.len());
+                }
+                self.current = *idx;
+            }
+            Step::Type(text) => {
+                let pane = self.current()?;
+                let mut writer = pane.writer();
+                writer.write_all(text.as_bytes())?;
+                writer.flush()?;
+            }
+            Step::Expect(text) => self.wait_for(text).await?,
+            Step::Resize { cols, rows } => {
+                let pane_id = self.current()?.pane_id();
+                let (_domain_id, _window_id, tab_id) = self
+                    .mux
+                    .resolve_pane_id(pane_id)
+                    .ok_or_else(|| anyhow!("pane {pane_id} is not in a tab"))?;
+                let tab = self
+                    .mux
+                    .get_tab(tab_id)
+                    .ok_or_else(|| anyhow!("no such tab {tab_id}"))?;
+                tab.resize(TerminalSize {
+                    rows: *rows,
+                    cols: *cols,
+                    ..tab.get_size()
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Scenario {
+    fn drop(&mut self) {
+        for pane in &self.panes {
+            pane.kill();
+        }
+    }
+}
+
+impl SelfTest {
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let script = match &self.script {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?,
+            None => DEFAULT_SCENARIO.to_string(),
+        };
+        let steps = parse_scenario(&script)?;
+
+        let domain: Arc<dyn Domain> = Arc::new(LocalDomain::new("local")?);
+        let mux = Arc::new(Mux::new(Some(domain)));
+        Mux::set_mux(&mux);
+
+        let mut scenario = Scenario {
+            mux,
+            panes: vec![],
+            current: 0,
+            shell: self.shell.clone(),
+            timeout: Duration::from_secs(self.timeout),
+        };
+
+        let start = Instant::now();
+        for (line_number, line, step) in &steps {
+            let step_start = Instant::now();
+            match scenario.step(step).await {
+                Ok(()) => println!("ok   {line} ({:?})", step_start.elapsed()),
+                Err(err) => {
+                    println!("FAIL {line}");
+                    bail!("step on line {} failed: {:#}", line_number, err);
+                }
+            }
+        }
+
+        println!("{} steps passed in {:?}", steps.len(), start.elapsed());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_default_scenario() {
+        let steps = parse_scenario(DEFAULT_SCENARIO).unwrap();
+        assert_eq!(steps.len(), 15);
+        assert_eq!(steps[0], (3, "spawn", Step::Spawn));
+        assert_eq!(steps[1].2, Step::Type("echo kaku-$((6*7))\r".to_string()));
+        assert_eq!(
+            steps[3].2,
+            Step::Resize {
+                cols: 100,
+                rows: 30
+            }
+        );
+        assert_eq!(steps[5].2, Step::Expect("30 100".to_string()));
+        assert_eq!(steps[10].2, Step::Expect("30 50".to_string()));
+        assert_eq!(steps[11].2, Step::Select(0));
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let err = parse_scenario("spawn\n\nfrobnicate\n").unwrap_err();
+        assert_eq!(format!("{err:#}"), "line 3: unknown step \"frobnicate\"");
+        assert!(parse_scenario("resize 80by24").is_err());
+        assert!(parse_scenario("select one").is_err());
+    }
+
+    #[test]
+    fn unescape_sequences() {
+        assert_eq!(unescape(r"a\r\n\t\\b\"), "a\r\n\t\\b\\");
+    }
+}
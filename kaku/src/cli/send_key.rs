@@ -0,0 +1,101 @@
+use anyhow::{anyhow, bail};
+use clap::Parser;
+use codec::InputSerial;
+use mux::pane::PaneId;
+use std::convert::TryFrom;
+use termwiz::input::{KeyCode, KeyEvent, Modifiers};
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct SendKey {
+    /// Specify the target pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// The keys to press, in order.
+    /// Each is a key name, such as `Enter`, `UpArrow`, `F5` or `c`,
+    /// optionally preceded by modifiers in the same form used by
+    /// key bindings, eg: `CTRL|c` or `CTRL|SHIFT|Tab`.
+    #[arg(required = true, value_parser = parse_key)]
+    keys: Vec<KeyEvent>,
+}
+
+impl SendKey {
+    pub async fn run(self, client: Client) -> anyhow::Result<()> {
+        let pane_id = client.resolve_pane_id(self.pane_id).await?;
+        for event in self.keys {
+            client
+                .key_down(codec::SendKeyDown {
+                    pane_id,
+                    event,
+                    input_serial: InputSerial::now(),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_key(s: &str) -> anyhow::Result<KeyEvent> {
+    let (modifiers, key) = match s.rsplit_once('|') {
+        // Allow the `|` key itself to be named without modifiers
+        Some((mods, key)) if !key.is_empty() => (
+            Modifiers::try_from(mods.to_string()).map_err(|err| anyhow!("{err}"))?,
+            key,
+        ),
+        _ => (Modifiers::NONE, s),
+    };
+
+    let key = match key {
+        "Enter" | "Return" => KeyCode::Enter,
+        "Tab" => KeyCode::Tab,
+        "Backspace" => KeyCode::Backspace,
+        "Escape" => KeyCode::Escape,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        "UpArrow" => KeyCode::UpArrow,
+        "DownArrow" => KeyCode::DownArrow,
+        "LeftArrow" => KeyCode::LeftArrow,
+        "RightArrow" => KeyCode::RightArrow,
+        "Space" => KeyCode::Char(' '),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => match key.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::Function(n),
+                    _ => bail!("unknown key `{key}`"),
+                },
+            }
+        }
+    };
+    Ok(KeyEvent { key, modifiers })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            parse_key("CTRL|c").unwrap(),
+            KeyEvent {
+                key: KeyCode::Char('c'),
+                modifiers: Modifiers::CTRL,
+            }
+        );
+        assert_eq!(parse_key("F12").unwrap().key, KeyCode::Function(12));
+        assert_eq!(parse_key("|").unwrap().key, KeyCode::Char('|'));
+        assert_eq!(parse_key("SHIFT|Tab").unwrap().modifiers, Modifiers::SHIFT);
+        assert!(parse_key("F25").is_err());
+        assert!(parse_key("Bogus").is_err());
+        assert!(parse_key("HYPO|a").is_err());
+    }
+}
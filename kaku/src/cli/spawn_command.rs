@@ -1,4 +1,4 @@
-use crate::cli::resolve_relative_cwd;
+use crate::cli::{resolve_relative_cwd, window_for_pane};
 use clap::{Parser, ValueHint};
 use config::keyassignment::SpawnTabDomain;
 use config::ConfigHandle;
@@ -59,26 +59,7 @@ impl SpawnCommand {
                 Some(w) => Some(w),
                 None => {
                     let pane_id = client.resolve_pane_id(self.pane_id).await?;
-
-                    let panes = client.list_panes().await?;
-                    let mut window_id = None;
-                    'outer: for tabroot in panes.tabs {
-                        let mut cursor = tabroot.into_tree().cursor();
-
-                        loop {
-                            if let Some(entry) = cursor.leaf_mut() {
-                                if entry.pane_id == pane_id {
-                                    window_id.replace(entry.window_id);
-                                    break 'outer;
-                                }
-                            }
-                            match cursor.preorder_next() {
-                                Ok(c) => cursor = c,
-                                Err(_) => break,
-                            }
-                        }
-                    }
-                    window_id
+                    window_for_pane(&client, pane_id).await?
                 }
             }
         };
@@ -0,0 +1,44 @@
+use anyhow::bail;
+use clap::Parser;
+use mux::pane::PaneId;
+use wezterm_client::client::Client;
+
+#[derive(Debug, Parser, Clone)]
+pub struct WaitForText {
+    /// Specify the target pane.
+    /// The default is to use the current pane based on the
+    /// environment variable WEZTERM_PANE.
+    #[arg(long)]
+    pane_id: Option<PaneId>,
+
+    /// How many milliseconds to wait before giving up
+    #[arg(long, default_value = "5000")]
+    timeout_ms: u64,
+
+    /// The regular expression to wait for
+    regex: String,
+}
+
+impl WaitForText {
+    pub async fn run(self, client: Client) -> anyhow::Result<()> {
+        let pane_id = client.resolve_pane_id(self.pane_id).await?;
+        let response = client
+            .wait_for_pane_text(codec::WaitForPaneText {
+                pane_id,
+                regex: self.regex.clone(),
+                timeout_ms: self.timeout_ms,
+            })
+            .await?;
+        match response.matched {
+            Some(text) => {
+                println!("{text}");
+                Ok(())
+            }
+            None => bail!(
+                "timed out after {}ms waiting for {:?}",
+                self.timeout_ms,
+                self.regex
+            ),
+        }
+    }
+}
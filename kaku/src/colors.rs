@@ -0,0 +1,94 @@
+use clap::Parser;
+use tabout::{tabulate_output, Alignment, Column};
+
+#[derive(Debug, Parser, Clone)]
+pub struct ColorsCommand {
+    /// Only list schemes with a dark background
+    #[arg(long, conflicts_with = "light")]
+    dark: bool,
+
+    /// Only list schemes with a light background
+    #[arg(long)]
+    light: bool,
+
+    /// Only list schemes with this tag; may be repeated, in which
+    /// case schemes must have all of the tags
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+
+    /// Only list schemes whose name or author contains this text,
+    /// ignoring case
+    #[arg(long)]
+    search: Option<String>,
+
+    /// Output the metadata as JSON rather than a table
+    #[arg(long)]
+    json: bool,
+}
+
+impl ColorsCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let search = self.search.as_ref().map(|s| s.to_lowercase());
+        let schemes = config::builtin_scheme_metadata()
+            .iter()
+            .filter(|meta| !self.dark || meta.is_dark)
+            .filter(|meta| !self.light || !meta.is_dark)
+            .filter(|meta| self.tags.iter().all(|tag| meta.has_tag(tag)))
+            .filter(|meta| match &search {
+                Some(search) => {
+                    meta.name.to_lowercase().contains(search)
+                        || meta
+                            .author
+                            .as_ref()
+                            .map_or(false, |author| author.to_lowercase().contains(search))
+                }
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        if self.json {
+            let items = schemes
+                .iter()
+                .map(|meta| {
+                    serde_json::json!({
+                        "name": meta.name,
+                        "author": meta.author,
+                        "origin_url": meta.origin_url,
+                        "aliases": meta.aliases,
+                        "is_dark": meta.is_dark,
+                        "tags": meta.tags,
+                    })
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&items)?);
+            return Ok(());
+        }
+
+        let cols = vec![
+            Column {
+                name: "NAME".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "KIND".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "AUTHOR".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+        let data = schemes
+            .iter()
+            .map(|meta| {
+                vec![
+                    meta.name.clone(),
+                    if meta.is_dark { "dark" } else { "light" }.to_string(),
+                    meta.author.clone().unwrap_or_default(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        tabulate_output(&cols, &data, &mut std::io::stdout().lock())?;
+        Ok(())
+    }
+}
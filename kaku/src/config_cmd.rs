@@ -1,17 +1,76 @@
 use anyhow::{anyhow, bail, Context};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use config::meta::{ConfigContainer, ConfigMeta, ConfigOption};
+use config::Config;
+use serde::Serialize;
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use wezterm_dynamic::Value;
 
 #[derive(Debug, Parser, Clone, Default)]
 pub struct ConfigCommand {
     /// Ensure ~/.config/kaku/kaku.lua exists, but do not open it.
     #[arg(long)]
     ensure_only: bool,
+
+    #[command(subcommand)]
+    sub: Option<ConfigSubCommand>,
+}
+
+#[derive(Debug, Parser, Clone)]
+enum ConfigSubCommand {
+    /// Print a description of every config option, for use by
+    /// editors and language servers
+    #[command(name = "schema")]
+    Schema(SchemaCommand),
+
+    /// Load the config without applying it, and report any
+    /// errors or warnings
+    #[command(name = "validate")]
+    Validate(ValidateCommand),
+}
+
+#[derive(Debug, Parser, Clone)]
+struct SchemaCommand {
+    #[arg(long, value_enum, default_value_t = SchemaFormat::JsonSchema)]
+    format: SchemaFormat,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum SchemaFormat {
+    /// A JSON Schema document describing the config table
+    JsonSchema,
+    /// Lua language server annotations for the config table
+    Lua,
+}
+
+#[derive(Debug, Parser, Clone)]
+struct ValidateCommand {
+    #[arg(long, value_enum, default_value_t = DiagnosticFormat::Text)]
+    format: DiagnosticFormat,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum DiagnosticFormat {
+    /// One line per diagnostic
+    Text,
+    /// A JSON array of diagnostics
+    Json,
 }
 
 impl ConfigCommand {
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(
+        &self,
+        config_file: Option<&OsString>,
+        overrides: &[(String, String)],
+    ) -> anyhow::Result<()> {
+        match &self.sub {
+            Some(ConfigSubCommand::Schema(cmd)) => return cmd.run(),
+            Some(ConfigSubCommand::Validate(cmd)) => return cmd.run(config_file, overrides),
+            None => {}
+        }
+
         let config_path = resolve_user_config_path();
         ensure_config_exists(&config_path)?;
         if self.ensure_only {
@@ -25,7 +84,331 @@ impl ConfigCommand {
     }
 }
 
-fn resolve_user_config_path() -> PathBuf {
+impl SchemaCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let options = Config::default_config().get_config_options();
+        match self.format {
+            SchemaFormat::JsonSchema => {
+                let schema = json_schema(options);
+                println!("{}", serde_json::to_string_pretty(&schema)?);
+            }
+            SchemaFormat::Lua => print!("{}", lua_annotations(options)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    severity: Severity,
+    message: String,
+    file: Option<PathBuf>,
+    line: Option<usize>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, message: String, config_file: Option<&Path>) -> Self {
+        let (file, line) = match lua_error_location(&message, config_file) {
+            Some((file, line)) => (Some(file), Some(line)),
+            None => (config_file.map(Path::to_path_buf), None),
+        };
+        Self {
+            severity,
+            message,
+            file,
+            line,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.severity {
+            Severity::Error => write!(fmt, "error: ")?,
+            Severity::Warning => write!(fmt, "warning: ")?,
+        }
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(fmt, "{}:{line}: ", file.display())?,
+            (Some(file), None) => write!(fmt, "{}: ", file.display())?,
+            _ => {}
+        }
+        write!(fmt, "{}", self.message)
+    }
+}
+
+impl ValidateCommand {
+    fn run(
+        &self,
+        config_file: Option<&OsString>,
+        overrides: &[(String, String)],
+    ) -> anyhow::Result<()> {
+        if let Some(config_file) = config_file {
+            config::set_config_file_override(Path::new(config_file));
+        }
+        config::set_config_overrides(overrides)?;
+
+        // This is the same load that a reload performs, but the result
+        // is only inspected, never made the active configuration
+        let loaded = Config::load();
+        let file_name = loaded.file_name.as_deref();
+        let mut diagnostics: Vec<Diagnostic> = loaded
+            .warnings
+            .into_iter()
+            .map(|warning| Diagnostic::new(Severity::Warning, warning, file_name))
+            .collect();
+        if let Err(err) = &loaded.config {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                format!("{err:#}"),
+                file_name,
+            ));
+        }
+
+        match self.format {
+            DiagnosticFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+            }
+            DiagnosticFormat::Text => {
+                for diagnostic in &diagnostics {
+                    println!("{diagnostic}");
+                }
+                if diagnostics.is_empty() {
+                    match file_name {
+                        Some(file_name) => println!("{} is valid", file_name.display()),
+                        None => println!("No config file found; the defaults are valid"),
+                    }
+                }
+            }
+        }
+
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            bail!("the configuration is invalid");
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the file and line from the position that Lua prefixes to
+/// its error messages, which looks like `[string "/path/kaku.lua"]:12:`
+/// for the config file itself and `/path/module.lua:12:` for modules
+/// loaded via `require`.
+fn lua_error_location(message: &str, config_file: Option<&Path>) -> Option<(PathBuf, usize)> {
+    const CHUNK_PREFIX: &str = "[string \"";
+    for (idx, _) in message.match_indices(':') {
+        let rest = &message[idx + 1..];
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 || !rest[digits..].starts_with(':') {
+            continue;
+        }
+        let line = match rest[..digits].parse() {
+            Ok(line) => line,
+            Err(_) => continue,
+        };
+
+        let before = &message[..idx];
+        if let Some(chunk) = before.strip_suffix("\"]") {
+            let name = match chunk.rfind(CHUNK_PREFIX) {
+                Some(start) => &chunk[start + CHUNK_PREFIX.len()..],
+                None => continue,
+            };
+            // Lua abbreviates long chunk names with a trailing "...",
+            // but the only chunk loaded by name is the config file
+            if name.ends_with("...") {
+                if let Some(config_file) = config_file {
+                    return Some((config_file.to_path_buf(), line));
+                }
+                continue;
+            }
+            return Some((PathBuf::from(name), line));
+        }
+
+        let start = before.rfind(char::is_whitespace).map_or(0, |idx| idx + 1);
+        let name = &before[start..];
+        if name.ends_with(".lua") {
+            return Some((PathBuf::from(name), line));
+        }
+    }
+    None
+}
+
+/// The shape of a config option, derived from the type information
+/// in its `ConfigOption`
+#[derive(Debug, PartialEq)]
+enum FieldType {
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array(Box<FieldType>),
+    /// A table keyed by strings
+    Map(Box<FieldType>),
+    Optional(Box<FieldType>),
+    /// Anything we can't describe more precisely, such as an enum
+    /// or a nested struct
+    Other,
+}
+
+impl FieldType {
+    fn scalar(type_name: &str) -> Self {
+        match type_name {
+            "bool" => Self::Boolean,
+            "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" => {
+                Self::Integer
+            }
+            "f32" | "f64" => Self::Number,
+            "String" | "PathBuf" | "char" => Self::String,
+            _ => Self::Other,
+        }
+    }
+
+    fn from_option(option: &ConfigOption) -> Self {
+        let scalar = Box::new(Self::scalar(option.type_name));
+        match option.container {
+            ConfigContainer::None => *scalar,
+            ConfigContainer::Option => Self::Optional(scalar),
+            ConfigContainer::Vec => Self::Array(scalar),
+            ConfigContainer::Map => Self::Map(scalar),
+        }
+    }
+
+    fn json_schema(&self) -> serde_json::Value {
+        use serde_json::json;
+        match self {
+            Self::Boolean => json!({"type": "boolean"}),
+            Self::Integer => json!({"type": "integer"}),
+            Self::Number => json!({"type": "number"}),
+            Self::String => json!({"type": "string"}),
+            Self::Array(element) => json!({"type": "array", "items": element.json_schema()}),
+            Self::Map(value) => {
+                json!({"type": "object", "additionalProperties": value.json_schema()})
+            }
+            Self::Optional(inner) => match inner.as_ref() {
+                Self::Other => json!({}),
+                inner => json!({"anyOf": [inner.json_schema(), {"type": "null"}]}),
+            },
+            Self::Other => json!({}),
+        }
+    }
+
+    fn lua_type(&self) -> String {
+        match self {
+            Self::Boolean => "boolean".to_string(),
+            Self::Integer => "integer".to_string(),
+            Self::Number => "number".to_string(),
+            Self::String => "string".to_string(),
+            Self::Array(element) => match element.as_ref() {
+                Self::Optional(_) => format!("({})[]", element.lua_type()),
+                element => format!("{}[]", element.lua_type()),
+            },
+            Self::Map(value) => format!("table<string, {}>", value.lua_type()),
+            Self::Optional(inner) => format!("{}?", inner.lua_type()),
+            Self::Other => "any".to_string(),
+        }
+    }
+}
+
+fn dynamic_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(b) => (*b).into(),
+        Value::String(s) => s.as_str().into(),
+        Value::Array(array) => array.iter().map(dynamic_to_json).collect(),
+        Value::Object(obj) => obj
+            .iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    Value::String(s) => s.to_string(),
+                    other => dynamic_to_json(other).to_string(),
+                };
+                (key, dynamic_to_json(value))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Value::U64(u) => (*u).into(),
+        Value::I64(i) => (*i).into(),
+        Value::F64(f) => f.into_inner().into(),
+    }
+}
+
+/// The doc comment of `option`, without the indentation
+/// that follows each `///`
+fn option_doc(option: &ConfigOption) -> String {
+    option
+        .doc
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn json_schema(options: &[ConfigOption]) -> serde_json::Value {
+    let properties = options
+        .iter()
+        .map(|option| {
+            let mut schema = FieldType::from_option(option).json_schema();
+            let property = schema.as_object_mut().expect("schemas are objects");
+            let mut description = option_doc(option);
+            if let Some(reason) = option.deprecated {
+                property.insert("deprecated".to_string(), true.into());
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&format!("Deprecated: {reason}"));
+            }
+            if !description.is_empty() {
+                property.insert("description".to_string(), description.into());
+            }
+            if let Some(default_value) = option.default_value {
+                property.insert("default".to_string(), dynamic_to_json(&default_value()));
+            }
+            (option.name.to_string(), schema)
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Kaku configuration",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+fn lua_annotations(options: &[ConfigOption]) -> String {
+    let mut lua = String::from("---@meta\n\n---@class KakuConfig\n");
+    for option in options {
+        let ty = match FieldType::from_option(option) {
+            FieldType::Optional(inner) => *inner,
+            ty => ty,
+        };
+        let optional = if option.default_value.is_some() {
+            "?"
+        } else {
+            ""
+        };
+        let summary = match option.deprecated {
+            Some(reason) => format!("Deprecated: {reason}"),
+            None => option_doc(option).lines().next().unwrap_or("").to_string(),
+        };
+        lua.push_str(&format!(
+            "---@field {}{optional} {} {summary}",
+            option.name,
+            ty.lua_type()
+        ));
+        lua.truncate(lua.trim_end().len());
+        lua.push('\n');
+    }
+    lua
+}
+
+pub(crate) fn resolve_user_config_path() -> PathBuf {
     config::CONFIG_DIRS
         .first()
         .cloned()
@@ -33,7 +416,7 @@ fn resolve_user_config_path() -> PathBuf {
         .join("kaku.lua")
 }
 
-fn ensure_config_exists(config_path: &Path) -> anyhow::Result<()> {
+pub(crate) fn ensure_config_exists(config_path: &Path) -> anyhow::Result<()> {
     if config_path.exists() {
         return Ok(());
     }
@@ -172,3 +555,49 @@ fn open_with_editor(config_path: &Path) -> anyhow::Result<bool> {
 
     Ok(status.success())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn error_location() {
+        let config = Path::new("/home/me/.config/kaku/kaku.lua");
+        assert_eq!(
+            lua_error_location(
+                "runtime error: [string \"/home/me/.config/kaku/kaku.lua\"]:12: attempt to call a nil value",
+                None
+            ),
+            Some((config.to_path_buf(), 12))
+        );
+        assert_eq!(
+            lua_error_location(
+                "syntax error: [string \"...g/kaku/kaku.lua\"]:3: unexpected symbol",
+                Some(config)
+            ),
+            Some((config.to_path_buf(), 3))
+        );
+        assert_eq!(
+            lua_error_location(
+                "error: /home/me/.config/kaku/keys.lua:7: bad key",
+                Some(config)
+            ),
+            Some((PathBuf::from("/home/me/.config/kaku/keys.lua"), 7))
+        );
+        assert_eq!(
+            lua_error_location("font_size: expected number at 12:30", Some(config)),
+            None
+        );
+    }
+
+    #[test]
+    fn schema_covers_config() {
+        let options = Config::default_config().get_config_options();
+        let schema = json_schema(options);
+        let properties = schema["properties"].as_object().unwrap();
+        assert_eq!(properties.len(), options.len());
+        assert_eq!(properties["font_size"]["type"], "number");
+        assert_eq!(properties["keys"]["type"], "array");
+        assert!(lua_annotations(options).contains("---@field font_size? number"));
+    }
+}
@@ -19,9 +19,12 @@ use wezterm_gui_subcommands::*;
 
 mod asciicast;
 mod cli;
+mod colors;
 mod config_cmd;
 mod init;
 mod reset;
+mod ssh_agent_relay;
+mod theme;
 mod update;
 
 #[derive(Debug, Parser)]
@@ -137,6 +140,18 @@ enum SubCommand {
     )]
     Update(update::UpdateCommand),
 
+    #[command(
+        name = "colors",
+        about = "List the built-in color schemes, optionally filtered by their metadata"
+    )]
+    Colors(colors::ColorsCommand),
+
+    #[command(
+        name = "theme",
+        about = "Browse the built-in color schemes and set one in kaku.lua"
+    )]
+    Theme(theme::ThemeCommand),
+
     #[command(name = "config", about = "Open and edit user kaku.lua configuration")]
     Config(config_cmd::ConfigCommand),
 
@@ -148,6 +163,11 @@ enum SubCommand {
         about = "Reset Kaku shell integration and managed defaults"
     )]
     Reset(reset::ResetCommand),
+
+    /// Used by WSL domains with `forward_ssh_agent` enabled to reach
+    /// the Windows OpenSSH agent
+    #[command(name = "ssh-agent-relay", hide = true)]
+    SshAgentRelay(ssh_agent_relay::SshAgentRelayCommand),
 }
 
 use termwiz::escape::osc::{
@@ -195,6 +215,13 @@ struct ImgCatCommand {
     #[arg(long, value_parser)]
     tmux_passthru: Option<TmuxPassthru>,
 
+    /// Send the image as a series of smaller escape sequences using
+    /// the iTerm2 multipart file protocol.  This is useful with
+    /// multiplexers, such as tmux, that limit the length of the
+    /// sequences that they pass through.
+    #[arg(long)]
+    multipart: bool,
+
     /// Set the maximum number of pixels per image frame.
     /// Images will be scaled down so that they do not exceed this size,
     /// unless `--no-resample` is also used.
@@ -565,23 +592,40 @@ impl ImgCatCommand {
             }])?;
         }
 
-        let osc = OperatingSystemCommand::ITermProprietary(ITermProprietary::File(Box::new(
-            ITermFileData {
-                name: None,
-                size: Some(data.len()),
-                width: self.width.unwrap_or_default(),
-                height: self.height.unwrap_or_default(),
-                preserve_aspect_ratio: !self.no_preserve_aspect_ratio,
-                inline: true,
-                do_not_move_cursor: self.no_move_cursor,
-                data,
-            },
-        )));
-        let encoded = self
-            .tmux_passthru
-            .unwrap_or_default()
-            .encode(osc.to_string());
-        println!("{encoded}");
+        let file = ITermFileData {
+            name: None,
+            size: Some(data.len()),
+            width: self.width.unwrap_or_default(),
+            height: self.height.unwrap_or_default(),
+            preserve_aspect_ratio: !self.no_preserve_aspect_ratio,
+            inline: true,
+            do_not_move_cursor: self.no_move_cursor,
+            data,
+        };
+        let passthru = self.tmux_passthru.unwrap_or_default();
+        if self.multipart {
+            // Keep each FilePart well within the limits of the multiplexers
+            const CHUNK_SIZE: usize = 64 * 1024;
+            let parts: Vec<Vec<u8>> = file.data.chunks(CHUNK_SIZE).map(<[u8]>::to_vec).collect();
+            let begin = OperatingSystemCommand::ITermProprietary(ITermProprietary::MultipartFile(
+                Box::new(ITermFileData {
+                    data: vec![],
+                    ..file
+                }),
+            ));
+            print!("{}", passthru.encode(begin.to_string()));
+            for part in parts {
+                let osc =
+                    OperatingSystemCommand::ITermProprietary(ITermProprietary::FilePart(part));
+                print!("{}", passthru.encode(osc.to_string()));
+            }
+            let end = OperatingSystemCommand::ITermProprietary(ITermProprietary::FileEnd);
+            println!("{}", passthru.encode(end.to_string()));
+        } else {
+            let osc =
+                OperatingSystemCommand::ITermProprietary(ITermProprietary::File(Box::new(file)));
+            println!("{}", passthru.encode(osc.to_string()));
+        }
 
         if let ((_cursor_x, cursor_y), true) = (image_dims, needs_force_cursor_move) {
             // tell the terminal that doesn't fully understand the image sequence
@@ -767,10 +811,17 @@ fn run() -> anyhow::Result<()> {
             generate_completion(shell, &mut cmd, name, &mut std::io::stdout());
             Ok(())
         }
-        SubCommand::Update(cmd) => cmd.run(),
-        SubCommand::Config(cmd) => cmd.run(),
+        SubCommand::Update(cmd) => {
+            // Loaded for the proxy settings
+            init_config(&opts)?;
+            cmd.run()
+        }
+        SubCommand::Colors(cmd) => cmd.run(),
+        SubCommand::Theme(cmd) => cmd.run(),
+        SubCommand::Config(cmd) => cmd.run(opts.config_file.as_ref(), &opts.config_override),
         SubCommand::Init(cmd) => cmd.run(),
         SubCommand::Reset(cmd) => cmd.run(),
+        SubCommand::SshAgentRelay(cmd) => cmd.run(),
     }
 }
 
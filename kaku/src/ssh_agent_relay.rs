@@ -0,0 +1,68 @@
+use clap::Parser;
+
+/// Relays the ssh-agent protocol between stdio and the named pipe
+/// of the Windows OpenSSH agent.  This is run from inside WSL panes
+/// whose domain has `forward_ssh_agent` enabled.
+#[derive(Debug, Parser, Clone)]
+pub struct SshAgentRelayCommand {
+    /// The named pipe on which the agent is listening
+    #[arg(long, default_value = r"\\.\pipe\openssh-ssh-agent")]
+    pipe: String,
+}
+
+impl SshAgentRelayCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        imp::run(&self.pipe)
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    pub fn run(_pipe: &str) -> anyhow::Result<()> {
+        anyhow::bail!("`kaku ssh-agent-relay` is only supported on Windows")
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use anyhow::Context;
+    use std::io::{Read, Write};
+
+    /// Reads one length-prefixed agent message, returning None
+    /// at end of file
+    fn read_message(r: &mut impl Read) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut len = [0u8; 4];
+        match r.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let mut message = len.to_vec();
+        message.resize(4 + u32::from_be_bytes(len) as usize, 0);
+        r.read_exact(&mut message[4..])?;
+        Ok(Some(message))
+    }
+
+    pub fn run(pipe: &str) -> anyhow::Result<()> {
+        // The pipe is opened for synchronous I/O, which Windows
+        // serializes per handle, so rather than copying in both
+        // directions at once we rely on the protocol being strictly
+        // request/response.
+        let mut agent = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(pipe)
+            .with_context(|| format!("opening {pipe}"))?;
+        let mut stdin = std::io::stdin().lock();
+        let mut stdout = std::io::stdout().lock();
+
+        while let Some(request) = read_message(&mut stdin)? {
+            agent.write_all(&request)?;
+            let response = read_message(&mut agent)?
+                .ok_or_else(|| anyhow::anyhow!("agent closed the connection"))?;
+            stdout.write_all(&response)?;
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+use crate::config_cmd::{ensure_config_exists, resolve_user_config_path};
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use config::lua::mlua::Lua;
+use config::RgbaColor;
+use std::io::Write;
+use std::path::Path;
+use termwiz::caps::Capabilities;
+use termwiz::cell::AttributeChange;
+use termwiz::color::ColorAttribute;
+use termwiz::input::{InputEvent, KeyCode, KeyEvent, Modifiers};
+use termwiz::surface::{Change, CursorVisibility, Position};
+use termwiz::terminal::buffered::BufferedTerminal;
+use termwiz::terminal::{new_terminal, Terminal};
+
+const HELP: &str = "Type to filter, Up/Down to move, Enter to apply, Esc to quit";
+const SAMPLE: &str = " kaku ~/src $ cargo build ";
+
+#[derive(Debug, Parser, Clone, Default)]
+pub struct ThemeCommand {}
+
+impl ThemeCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let config_path = resolve_user_config_path();
+        let mut browser = Browser::new(config::COLOR_SCHEMES.scheme_names());
+
+        let caps = Capabilities::new_from_env()?;
+        let mut term = new_terminal(caps)?;
+        term.set_raw_mode()?;
+        term.enter_alternate_screen()?;
+        let mut buf = BufferedTerminal::new(term)?;
+        buf.add_change(Change::CursorVisibility(CursorVisibility::Hidden));
+
+        let result = browser.run(&mut buf, &config_path);
+
+        buf.add_change(Change::CursorVisibility(CursorVisibility::Visible));
+        buf.flush()?;
+        let term = buf.terminal();
+        term.exit_alternate_screen()?;
+        term.set_cooked_mode()?;
+
+        if let Some(scheme) = result? {
+            println!(
+                "Set color_scheme to '{}' in {}",
+                scheme,
+                config_path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+struct Browser {
+    names: Vec<String>,
+    filter: String,
+    /// Indices into `names` that match `filter`
+    visible: Vec<usize>,
+    selected: usize,
+    top: usize,
+    status: String,
+}
+
+impl Browser {
+    fn new(mut names: Vec<String>) -> Self {
+        names.sort_by_key(|name| name.to_lowercase());
+        let mut browser = Self {
+            names,
+            filter: String::new(),
+            visible: vec![],
+            selected: 0,
+            top: 0,
+            status: HELP.to_string(),
+        };
+        browser.apply_filter();
+        browser
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = self
+            .names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.to_lowercase().contains(&filter))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.selected = 0;
+        self.top = 0;
+    }
+
+    fn selected_name(&self) -> Option<&str> {
+        self.visible
+            .get(self.selected)
+            .map(|&idx| self.names[idx].as_str())
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let max = self.visible.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, max) as usize;
+    }
+
+    /// Returns the name of the scheme that was applied, if any
+    fn run<T: Terminal>(
+        &mut self,
+        buf: &mut BufferedTerminal<T>,
+        config_path: &Path,
+    ) -> anyhow::Result<Option<String>> {
+        let mut applied = None;
+        loop {
+            buf.check_for_resize()?;
+            self.render(buf);
+            buf.flush()?;
+
+            let event = match buf.terminal().poll_input(None)? {
+                Some(event) => event,
+                None => continue,
+            };
+            let (_, rows) = buf.dimensions();
+            let page = rows.saturating_sub(3).max(1) as isize;
+            if let InputEvent::Key(KeyEvent { key, modifiers }) = event {
+                match (key, modifiers) {
+                    (KeyCode::Escape, _) | (KeyCode::Char('c'), Modifiers::CTRL) => {
+                        return Ok(applied)
+                    }
+                    (KeyCode::UpArrow, _) | (KeyCode::Char('p'), Modifiers::CTRL) => {
+                        self.move_by(-1)
+                    }
+                    (KeyCode::DownArrow, _) | (KeyCode::Char('n'), Modifiers::CTRL) => {
+                        self.move_by(1)
+                    }
+                    (KeyCode::PageUp, _) => self.move_by(-page),
+                    (KeyCode::PageDown, _) => self.move_by(page),
+                    (KeyCode::Home, _) => self.move_by(-(self.visible.len() as isize)),
+                    (KeyCode::End, _) => self.move_by(self.visible.len() as isize),
+                    (KeyCode::Backspace, _) => {
+                        self.filter.pop();
+                        self.apply_filter();
+                    }
+                    (KeyCode::Enter, _) => {
+                        if let Some(name) = self.selected_name().map(str::to_string) {
+                            self.status = match apply_color_scheme(config_path, &name) {
+                                Ok(()) => {
+                                    let status = format!("Applied '{name}'. Esc to quit");
+                                    applied = Some(name);
+                                    status
+                                }
+                                Err(err) => format!("Failed to apply '{name}': {err:#}"),
+                            };
+                        }
+                    }
+                    (KeyCode::Char(c), Modifiers::NONE | Modifiers::SHIFT) => {
+                        self.filter.push(c);
+                        self.apply_filter();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn render<T: Terminal>(&mut self, buf: &mut BufferedTerminal<T>) {
+        let (cols, rows) = buf.dimensions();
+        let list_rows = rows.saturating_sub(3).max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + list_rows {
+            self.top = self.selected + 1 - list_rows;
+        }
+        let list_width = (cols / 2).clamp(1, 40);
+
+        buf.add_change(Change::ClearScreen(ColorAttribute::Default));
+        put_text(buf, 0, 0, &format!("Filter: {}", self.filter), cols, false);
+        for (row, &idx) in self
+            .visible
+            .iter()
+            .skip(self.top)
+            .take(list_rows)
+            .enumerate()
+        {
+            let selected = self.top + row == self.selected;
+            put_text(buf, 0, row + 2, &self.names[idx], list_width, selected);
+        }
+        put_text(buf, 0, rows.saturating_sub(1), &self.status, cols, false);
+
+        if let Some(name) = self.selected_name() {
+            let x = list_width + 2;
+            if x < cols {
+                render_preview(buf, name, x, cols - x);
+            }
+        }
+    }
+}
+
+fn put_text<T: Terminal>(
+    buf: &mut BufferedTerminal<T>,
+    x: usize,
+    y: usize,
+    text: &str,
+    width: usize,
+    reverse: bool,
+) {
+    let text: String = text.chars().take(width).collect();
+    buf.add_changes(vec![
+        Change::CursorPosition {
+            x: Position::Absolute(x),
+            y: Position::Absolute(y),
+        },
+        AttributeChange::Reverse(reverse).into(),
+        Change::Text(text),
+        AttributeChange::Reverse(false).into(),
+    ]);
+}
+
+fn color(c: RgbaColor) -> ColorAttribute {
+    ColorAttribute::TrueColorWithDefaultFallback(c.into())
+}
+
+fn render_preview<T: Terminal>(buf: &mut BufferedTerminal<T>, name: &str, x: usize, width: usize) {
+    put_text(buf, x, 2, name, width, false);
+    let palette = match config::COLOR_SCHEMES.get(name) {
+        Some(palette) => palette,
+        None => {
+            put_text(buf, x, 4, "(failed to load this scheme)", width, false);
+            return;
+        }
+    };
+
+    let fg = palette.foreground.map(color).unwrap_or_default();
+    let bg = palette.background.map(color).unwrap_or_default();
+    let sample: String = format!("{:width$}", SAMPLE, width = width)
+        .chars()
+        .take(width)
+        .collect();
+    buf.add_changes(vec![
+        Change::CursorPosition {
+            x: Position::Absolute(x),
+            y: Position::Absolute(4),
+        },
+        AttributeChange::Foreground(fg).into(),
+        AttributeChange::Background(bg).into(),
+        Change::Text(sample),
+        Change::AllAttributes(Default::default()),
+    ]);
+
+    for (row, label, colors) in [
+        (6, "normal ", palette.ansi),
+        (7, "bright ", palette.brights),
+    ] {
+        let colors = match colors {
+            Some(colors) => colors,
+            None => continue,
+        };
+        put_text(buf, x, row, label, width, false);
+        for (idx, c) in colors.iter().enumerate() {
+            let swatch_x = x + label.len() + idx * 3;
+            if swatch_x + 3 > x + width {
+                break;
+            }
+            buf.add_changes(vec![
+                Change::CursorPosition {
+                    x: Position::Absolute(swatch_x),
+                    y: Position::Absolute(row),
+                },
+                AttributeChange::Background(color(*c)).into(),
+                Change::Text("   ".to_string()),
+                Change::AllAttributes(Default::default()),
+            ]);
+        }
+    }
+}
+
+/// Sets `config.color_scheme` in the user's kaku.lua.  The new content
+/// is written to a temporary file alongside the config, which then
+/// replaces it, so that a running Kaku never reloads a partial file.
+/// The file is left alone if the change can't be made safely.
+fn apply_color_scheme(config_path: &Path, scheme: &str) -> anyhow::Result<()> {
+    ensure_config_exists(config_path)?;
+    // Write next to the real file so that a symlinked config stays a symlink
+    let config_path = std::fs::canonicalize(config_path)
+        .with_context(|| format!("resolve {}", config_path.display()))?;
+    let source = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("read {}", config_path.display()))?;
+    let updated = set_color_scheme(&source, scheme)?;
+    check_lua_syntax(&updated).context("the updated config doesn't compile; not saving it")?;
+
+    let dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow!("invalid config path: {}", config_path.display()))?;
+    let mut temp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("create temporary file in {}", dir.display()))?;
+    temp.write_all(updated.as_bytes())
+        .context("write temporary config")?;
+    let permissions = std::fs::metadata(&config_path)?.permissions();
+    std::fs::set_permissions(temp.path(), permissions)?;
+    temp.persist(&config_path)
+        .with_context(|| format!("replace {}", config_path.display()))?;
+    Ok(())
+}
+
+/// Compiles `source` without running it
+fn check_lua_syntax(source: &str) -> anyhow::Result<()> {
+    let lua = Lua::new();
+    lua.load(source).set_name("kaku.lua").into_function()?;
+    Ok(())
+}
+
+/// Returns the name of the table that the config file returns, taken
+/// from its final `return NAME` line
+fn returned_config_name(lines: &[String]) -> Option<(usize, &str)> {
+    let (idx, line) = lines
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, line)| line.split_whitespace().next() == Some("return"))?;
+    let name = line.trim().strip_prefix("return")?.trim();
+    let is_identifier = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    is_identifier.then_some((idx, name))
+}
+
+fn is_color_scheme_assignment(line: &str, name: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix(".color_scheme"))
+        .map_or(false, |rest| rest.trim_start().starts_with('='))
+}
+
+/// Returns `source` with each `config.color_scheme = ...` line replaced
+/// by one that selects `scheme`, where `config` is whatever name the
+/// file returns.  If there is no such line, one is inserted before the
+/// final `return config`.  Files that don't end by returning a named
+/// table, such as `return { ... }`, are refused rather than guessed at.
+fn set_color_scheme(source: &str, scheme: &str) -> anyhow::Result<String> {
+    let quoted = scheme.replace('\\', "\\\\").replace('\'', "\\'");
+
+    let mut lines: Vec<String> = source.lines().map(|line| line.to_string()).collect();
+    let (return_idx, name) = returned_config_name(&lines)
+        .map(|(idx, name)| (idx, name.to_string()))
+        .ok_or_else(|| {
+            anyhow!(
+                "kaku.lua doesn't end with `return config`; \
+                 set color_scheme = '{quoted}' in it yourself"
+            )
+        })?;
+    let assignment = format!("{name}.color_scheme = '{quoted}'");
+
+    let mut replaced = false;
+    for line in lines.iter_mut() {
+        if is_color_scheme_assignment(line, &name) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            *line = format!("{indent}{assignment}");
+            replaced = true;
+        }
+    }
+    if !replaced {
+        lines.insert(return_idx, assignment);
+        lines.insert(return_idx + 1, String::new());
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn color_scheme_assignment() {
+        assert_eq!(
+            set_color_scheme(
+                "local config = {}\n-- config.color_scheme = 'Old'\nreturn config\n",
+                "Builtin Solarized Dark"
+            )
+            .unwrap(),
+            "local config = {}\n-- config.color_scheme = 'Old'\n\
+             config.color_scheme = 'Builtin Solarized Dark'\n\nreturn config\n"
+        );
+        assert_eq!(
+            set_color_scheme("  config.color_scheme = 'Old'\nreturn config", "It's").unwrap(),
+            "  config.color_scheme = 'It\\'s'\nreturn config\n"
+        );
+    }
+
+    #[test]
+    fn color_scheme_uses_returned_name() {
+        assert_eq!(
+            set_color_scheme(
+                "local c = wezterm.config_builder()\nc.color_scheme = 'Old'\nreturn c\n",
+                "New"
+            )
+            .unwrap(),
+            "local c = wezterm.config_builder()\nc.color_scheme = 'New'\nreturn c\n"
+        );
+    }
+
+    #[test]
+    fn color_scheme_refuses_unnamed_config() {
+        assert!(set_color_scheme("return {\n  font_size = 14,\n}\n", "New").is_err());
+        assert!(set_color_scheme("local config = {}\n", "New").is_err());
+    }
+
+    #[test]
+    fn lua_syntax() {
+        assert!(check_lua_syntax("local config = {}\nreturn config\n").is_ok());
+        assert!(check_lua_syntax("return config\nconfig.color_scheme = 'x'\n").is_err());
+    }
+}
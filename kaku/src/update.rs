@@ -422,7 +422,8 @@ mod imp {
                 .arg("%{url_effective}")
                 .arg("--output")
                 .arg("/dev/null")
-                .arg(RELEASE_LATEST_URL),
+                .arg(RELEASE_LATEST_URL)
+                .args(proxy_args(RELEASE_LATEST_URL)),
             "resolve latest release tag via redirect",
         )?;
         let effective_url = String::from_utf8(output)
@@ -464,6 +465,22 @@ mod imp {
             .as_secs()
     }
 
+    /// The curl arguments that route a request for `url` through the
+    /// proxy configured for its host, if any
+    fn proxy_args(url: &str) -> Vec<String> {
+        let host = url
+            .split("://")
+            .nth(1)
+            .unwrap_or(url)
+            .split(|c| c == '/' || c == ':')
+            .next()
+            .unwrap_or("");
+        match config::configuration().proxy_for_host(host) {
+            Some(proxy) => vec!["--proxy".to_string(), proxy.url()],
+            None => vec![],
+        }
+    }
+
     fn curl_get_text(url: &str, current_version: &str) -> anyhow::Result<String> {
         let output = run_output(
             Command::new("/usr/bin/curl")
@@ -477,7 +494,8 @@ mod imp {
                 .arg("15")
                 .arg("--user-agent")
                 .arg(format!("kaku/{}", current_version))
-                .arg(url),
+                .arg(url)
+                .args(proxy_args(url)),
             "request update metadata",
         )?;
         String::from_utf8(output).context("curl returned non-utf8 response")
@@ -502,7 +520,8 @@ mod imp {
                 .arg(format!("kaku/{}", current_version))
                 .arg("--output")
                 .arg(output_path)
-                .arg(url),
+                .arg(url)
+                .args(proxy_args(url)),
             "download update package",
         )
     }
@@ -3,6 +3,7 @@ use crate::schemes::sexy::Sexy;
 use config::lua::mlua::{self, Lua, MetaMethod, UserData, UserDataMethods, UserDataRef};
 use config::lua::{get_or_create_module, get_or_create_sub_module};
 use config::{ColorSchemeFile, ColorSchemeMetaData, Gradient, Palette, RgbaColor, SrgbaTuple};
+use std::collections::HashMap;
 
 mod image_colors;
 pub mod schemes;
@@ -178,6 +179,15 @@ pub fn register(lua: &Lua) -> anyhow::Result<()> {
         "get_builtin_schemes",
         lua.create_function(|_, ()| Ok(config::COLOR_SCHEMES.clone()))?,
     )?;
+    color.set(
+        "get_builtin_schemes_metadata",
+        lua.create_function(|_, ()| {
+            Ok(config::builtin_scheme_metadata()
+                .iter()
+                .map(|meta| (meta.name.clone(), meta.clone()))
+                .collect::<HashMap<_, _>>())
+        })?,
+    )?;
 
     Ok(())
 }
@@ -2,6 +2,7 @@ use config::keyassignment::SpawnTabDomain;
 use config::lua::mlua::{self, Lua, UserData, UserDataMethods, Value as LuaValue};
 use config::lua::{get_or_create_module, get_or_create_sub_module};
 use luahelper::impl_lua_conversion_dynamic;
+use luahelper::mlua::LuaSerdeExt;
 use mlua::UserDataRef;
 use mux::domain::{DomainId, SplitSource};
 use mux::pane::{Pane, PaneId};
@@ -119,6 +120,14 @@ pub fn register(lua: &Lua) -> anyhow::Result<()> {
         })?,
     )?;
 
+    mux_mod.set(
+        "get_overall_progress",
+        lua.create_function(|lua, _: ()| {
+            let mux = get_mux()?;
+            lua.to_value(&mux.overall_progress())
+        })?,
+    )?;
+
     mux_mod.set(
         "get_domain",
         lua.create_function(|_, domain: LuaValue| {
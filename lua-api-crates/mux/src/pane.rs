@@ -1,8 +1,9 @@
 use super::*;
+use config::keyassignment::{PaneMonitor, Pattern};
 use luahelper::mlua::LuaSerdeExt;
 use luahelper::{dynamic_to_lua_value, from_lua, to_lua};
 use mlua::Value;
-use mux::pane::CachePolicy;
+use mux::pane::{CachePolicy, Pattern as SearchPattern};
 use std::cmp::Ordering;
 use std::sync::Arc;
 use termwiz::cell::SemanticType;
@@ -22,65 +23,7 @@ impl MuxPane {
     fn get_text_from_semantic_zone(&self, zone: SemanticZone) -> mlua::Result<String> {
         let mux = get_mux()?;
         let pane = self.resolve(&mux)?;
-
-        let mut last_was_wrapped = false;
-        let first_row = zone.start_y;
-        let last_row = zone.end_y;
-
-        fn cols_for_row(zone: &SemanticZone, row: StableRowIndex) -> std::ops::Range<usize> {
-            if row < zone.start_y || row > zone.end_y {
-                0..0
-            } else if zone.start_y == zone.end_y {
-                // A single line zone
-                if zone.start_x <= zone.end_x {
-                    zone.start_x..zone.end_x.saturating_add(1)
-                } else {
-                    zone.end_x..zone.start_x.saturating_add(1)
-                }
-            } else if row == zone.end_y {
-                // last line of multi-line
-                0..zone.end_x.saturating_add(1)
-            } else if row == zone.start_y {
-                // first line of multi-line
-                zone.start_x..usize::max_value()
-            } else {
-                // some "middle" line of multi-line
-                0..usize::max_value()
-            }
-        }
-
-        let mut s = String::new();
-        for line in pane.get_logical_lines(zone.start_y..zone.end_y + 1) {
-            if !s.is_empty() && !last_was_wrapped {
-                s.push('\n');
-            }
-            let last_idx = line.physical_lines.len().saturating_sub(1);
-            for (idx, phys) in line.physical_lines.iter().enumerate() {
-                let this_row = line.first_row + idx as StableRowIndex;
-                if this_row >= first_row && this_row <= last_row {
-                    let last_phys_idx = phys.len().saturating_sub(1);
-
-                    let cols = cols_for_row(&zone, this_row);
-                    let last_col_idx = cols.end.saturating_sub(1).min(last_phys_idx);
-                    let col_span = phys.columns_as_str(cols);
-                    // Only trim trailing whitespace if we are the last line
-                    // in a wrapped sequence
-                    if idx == last_idx {
-                        s.push_str(col_span.trim_end());
-                    } else {
-                        s.push_str(&col_span);
-                    }
-
-                    last_was_wrapped = last_col_idx == last_phys_idx
-                        && phys
-                            .get_cell(last_col_idx)
-                            .map(|c| c.attrs().wrapped())
-                            .unwrap_or(false);
-                }
-            }
-        }
-
-        Ok(s)
+        Ok(mux::pane_text::get_text_from_semantic_zone(&*pane, &zone))
     }
 }
 
@@ -146,6 +89,59 @@ impl UserData for MuxPane {
             Ok(pane.get_title())
         });
 
+        methods.add_method("is_input_locked", |_, this, _: ()| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            Ok(mux.is_pane_input_locked(pane.pane_id()))
+        });
+
+        methods.add_method("set_input_locked", |_, this, locked: bool| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            mux.set_pane_input_locked(pane.pane_id(), locked);
+            Ok(())
+        });
+
+        methods.add_method("set_background", |_, this, layers: Value| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            let layers: Option<Vec<config::BackgroundLayer>> = from_lua(layers)?;
+            mux.set_pane_background(pane.pane_id(), layers.unwrap_or_default());
+            Ok(())
+        });
+
+        methods.add_method("is_broadcast_excluded", |_, this, _: ()| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            Ok(mux.is_pane_broadcast_excluded(pane.pane_id()))
+        });
+
+        methods.add_method("set_broadcast_excluded", |_, this, excluded: bool| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            mux.set_pane_broadcast_excluded(pane.pane_id(), excluded);
+            Ok(())
+        });
+
+        methods.add_method("get_monitor", |_, this, _: ()| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            Ok(mux.get_pane_monitor(pane.pane_id()))
+        });
+
+        methods.add_method("set_monitor", |_, this, monitor: Option<PaneMonitor>| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            mux.set_pane_monitor(pane.pane_id(), monitor);
+            Ok(())
+        });
+
+        methods.add_method("is_monitor_triggered", |_, this, _: ()| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            Ok(mux.is_pane_monitor_triggered(pane.pane_id()))
+        });
+
         methods.add_method("get_progress", |lua, this, _: ()| {
             let mux = get_mux()?;
             let pane = this.resolve(&mux)?;
@@ -378,6 +374,73 @@ impl UserData for MuxPane {
             this.get_text_from_semantic_zone(zone)
         });
 
+        methods.add_method("get_semantic_text", |lua, this, of_type: Option<Value>| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            let of_type: Option<SemanticType> = match of_type {
+                Some(v) => Some(from_lua(v)?),
+                None => None,
+            };
+            let zones = mux::pane_text::get_semantic_text(&*pane, of_type)
+                .map_err(|e| mlua::Error::external(format!("{:#}", e)))?;
+            to_lua(lua, zones)
+        });
+
+        methods.add_method(
+            "get_region",
+            |_lua, this, (x, y, width, height): (usize, usize, usize, usize)| {
+                let mux = get_mux()?;
+                let pane = this.resolve(&mux)?;
+                Ok(mux::pane_text::get_region_text(&*pane, x, y, width, height))
+            },
+        );
+
+        methods.add_async_method(
+            "wait_for_text",
+            |_lua, this, (pattern, timeout_ms): (String, Option<u64>)| async move {
+                let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(5000));
+                mux::pane_text::wait_for_text(this.0, &pattern, timeout)
+                    .await
+                    .map_err(|e| mlua::Error::external(format!("{:#}", e)))
+            },
+        );
+
+        methods.add_async_method("search", |lua, this, pattern: Value| async move {
+            // A plain string is matched case-sensitively; otherwise the
+            // pattern takes the same form as for the `Search` action
+            let pattern = match pattern {
+                Value::String(s) => SearchPattern::CaseSensitiveString(s.to_str()?.to_string()),
+                value => match from_lua::<Pattern>(value)? {
+                    Pattern::CaseSensitiveString(s) => SearchPattern::CaseSensitiveString(s),
+                    Pattern::CaseInSensitiveString(s) => SearchPattern::CaseInSensitiveString(s),
+                    Pattern::Regex(s) => SearchPattern::Regex(s),
+                    Pattern::CurrentSelectionOrEmptyString => {
+                        return Err(mlua::Error::external(
+                            "CurrentSelectionOrEmptyString cannot be used with pane:search",
+                        ))
+                    }
+                },
+            };
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            let dims = pane.get_dimensions();
+            let range =
+                dims.scrollback_top..dims.scrollback_top + dims.scrollback_rows as StableRowIndex;
+            let mut results = pane
+                .search(pattern, range, None)
+                .await
+                .map_err(|e| mlua::Error::external(format!("{:#}", e)))?;
+            results.sort();
+            lua.to_value(&results)
+        });
+
+        methods.add_method("share_read_only", |_lua, this, ()| {
+            let mux = get_mux()?;
+            let pane = this.resolve(&mux)?;
+            mux::share::share_pane(pane.pane_id())
+                .map_err(|e| mlua::Error::external(format!("{:#}", e)))
+        });
+
         methods.add_async_method("move_to_new_tab", |_lua, this, ()| async move {
             let mux = Mux::get();
             let (_domain, window_id, _tab) = mux
@@ -46,6 +46,13 @@ impl UserData for MuxTab {
             let tab = this.resolve(&mux)?;
             Ok(tab.set_title(&title))
         });
+        methods.add_method("set_background", |_, this, layers: Value| {
+            let mux = get_mux()?;
+            let tab = this.resolve(&mux)?;
+            let layers: Option<Vec<config::BackgroundLayer>> = from_lua(layers)?;
+            mux.set_tab_background(tab.tab_id(), layers.unwrap_or_default());
+            Ok(())
+        });
         methods.add_method("active_pane", |_, this, _: ()| {
             let mux = get_mux()?;
             let tab = this.resolve(&mux)?;
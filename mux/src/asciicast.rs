@@ -0,0 +1,132 @@
+//! The asciinema v2 `.cast` format, shared by `kaku record` and
+//! `kaku play` and by the recordings made with `TogglePaneRecording`.
+use anyhow::Context;
+use chrono::serde::ts_seconds_option;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// See <https://github.com/asciinema/asciinema/blob/develop/doc/asciicast-v2.md>
+/// for file format specification
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Header {
+    /// Always 2 for this format
+    pub version: u32,
+    /// Initial terminal width (number of columns)
+    pub width: u32,
+    /// Initial terminal height (number of columns)
+    pub height: u32,
+    /// Unix timestamp of starting time of session
+    #[serde(
+        default,
+        with = "ts_seconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Duration of the whole recording in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
+    /// Used to reduce terminal inactivity (delays between frames)
+    /// to a maximum of this amount.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_time_limit: Option<f32>,
+    /// Command that was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Title of the asciicast
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Map of captured environment variables
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Color theme of the recorded terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Theme {
+    /// Normal text color
+    pub fg: String,
+    /// Normal background color
+    pub bg: String,
+    /// List of 8 or 16 colors separated by a colon character
+    pub palette: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Event(pub f32, pub String, pub String);
+
+impl Event {
+    pub fn log_output<W: Write>(mut w: W, elapsed: f32, output: &str) -> std::io::Result<()> {
+        let event = Event(elapsed, "o".to_string(), output.to_string());
+        writeln!(w, "{}", serde_json::to_string(&event)?)
+    }
+}
+
+/// Reads an asciicast v2 recording, returning its header and its
+/// output events in time order.  Events other than output, such as
+/// input and markers, are skipped.
+pub fn read_cast<R: BufRead>(reader: R) -> anyhow::Result<(Header, Vec<Event>)> {
+    let mut lines = reader.lines().enumerate();
+    let header: Header = match lines.next() {
+        Some((_, line)) => serde_json::from_str(&line?).context("parsing the header")?,
+        None => anyhow::bail!("the recording is empty"),
+    };
+    anyhow::ensure!(
+        header.version == 2,
+        "unsupported asciicast version {}",
+        header.version
+    );
+
+    let mut events = vec![];
+    for (idx, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: Event =
+            serde_json::from_str(&line).with_context(|| format!("parsing line {}", idx + 1))?;
+        if event.1 == "o" {
+            events.push(event);
+        }
+    }
+    // Tolerate recordings that were concatenated or edited by hand
+    events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    Ok((header, events))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let (header, events) = read_cast(
+            "{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": 1}\n\
+             [0.5, \"o\", \"hello\"]\n\
+             [0.7, \"i\", \"x\"]\n\
+             \n\
+             [0.25, \"o\", \"\\u001b[H\"]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!((header.width, header.height), (80, 24));
+        assert_eq!(
+            events
+                .iter()
+                .map(|Event(time, _, data)| (*time, data.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0.25, "\x1b[H"), (0.5, "hello")]
+        );
+
+        assert!(read_cast("".as_bytes()).is_err());
+        assert!(read_cast("{\"version\": 1, \"width\": 80, \"height\": 24}".as_bytes()).is_err());
+        let err =
+            read_cast("{\"version\": 2, \"width\": 80, \"height\": 24}\n[1, \"o\"\n".as_bytes())
+                .unwrap_err();
+        assert!(format!("{err:#}").starts_with("parsing line 2"));
+    }
+}
@@ -73,3 +73,35 @@ impl ClientInfo {
         self.focused_pane_id.replace(pane_id);
     }
 }
+
+/// Whether a `MuxNotification::ClipboardSynced` copy made by `origin`
+/// should be relayed to the client identified by `recipient`.
+/// Copies are never returned to the client that made them, and copies
+/// without an origin came from a remote mux server; relaying those
+/// would bounce them straight back to where they came from.
+pub fn should_relay_clipboard(
+    origin: Option<&Arc<ClientId>>,
+    recipient: Option<&Arc<ClientId>>,
+) -> bool {
+    match origin {
+        Some(origin) => recipient != Some(origin),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clipboard_relay() {
+        let a = Arc::new(ClientId::new());
+        let b = Arc::new(ClientId::new());
+
+        assert!(should_relay_clipboard(Some(&a), Some(&b)));
+        assert!(should_relay_clipboard(Some(&a), None));
+        assert!(!should_relay_clipboard(Some(&a), Some(&a)));
+        assert!(!should_relay_clipboard(None, Some(&b)));
+        assert!(!should_relay_clipboard(None, None));
+    }
+}
@@ -12,8 +12,9 @@ use termwiz::surface::{Change, Position};
 use termwiz::terminal::*;
 use wezterm_term::TerminalSize;
 
+/// A line editor host that obscures the characters that are typed
 #[derive(Default)]
-struct PasswordPromptHost {
+pub struct PasswordPromptHost {
     history: BasicHistory,
 }
 impl LineEditorHost for PasswordPromptHost {
@@ -55,6 +56,36 @@ pub enum UIRequest {
     Close,
 }
 
+/// A question to put to the user, such as a password or the one-time
+/// code of keyboard-interactive authentication
+#[derive(Clone, Debug)]
+pub struct PromptField {
+    pub prompt: String,
+    /// If false, the answer is obscured as it is typed
+    pub echo: bool,
+}
+
+/// A set of questions that are answered together, in the order of
+/// `fields`, by the front end registered with `set_prompt_handler`
+pub struct PromptRequest {
+    pub instructions: String,
+    pub fields: Vec<PromptField>,
+    pub respond: Promise<Vec<String>>,
+}
+
+type PromptHandler = Box<dyn Fn(PromptRequest) + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref PROMPT_HANDLER: Mutex<Option<PromptHandler>> = Mutex::new(None);
+}
+
+/// Registers a function that is called from a background thread to
+/// present prompts in place of the connection window or the console.
+/// It must eventually complete the `respond` promise of each request.
+pub fn set_prompt_handler<F: Fn(PromptRequest) + Send + Sync + 'static>(handler: F) {
+    PROMPT_HANDLER.lock().unwrap().replace(Box::new(handler));
+}
+
 struct ConnectionUIImpl {
     term: termwiztermtab::TermWizTerminal,
     rx: Receiver<UIRequest>,
@@ -369,27 +400,47 @@ impl ConnectionUI {
         }
     }
 
-    pub fn input(&self, prompt: &str) -> anyhow::Result<String> {
+    /// Asks the user to answer each of `fields`, showing `instructions`
+    /// above them.  The front end registered with `set_prompt_handler`
+    /// is preferred; otherwise each field is read in turn from this UI.
+    pub fn prompt(
+        &self,
+        instructions: &str,
+        fields: Vec<PromptField>,
+    ) -> anyhow::Result<Vec<String>> {
         let mut promise = Promise::new();
         let future = promise.get_future().unwrap();
-
-        let (preamble, prompt) = Self::split_multi_line_prompt(prompt);
-        if let Some(preamble) = preamble {
-            self.output(vec![Change::Text(preamble)]);
+        let request = PromptRequest {
+            instructions: instructions.to_string(),
+            fields,
+            respond: promise,
+        };
+
+        // The handler is called with the lock held, but the lock must
+        // be released before waiting for the answers
+        let unhandled = match PROMPT_HANDLER.lock().unwrap().as_ref() {
+            Some(handler) => {
+                handler(request);
+                None
+            }
+            None => Some(request),
+        };
+        let request = match unhandled {
+            Some(request) => request,
+            None => return block_on(future),
+        };
+
+        if !request.instructions.is_empty() {
+            self.output_str(&format!("{}\n", request.instructions));
         }
-
-        self.tx
-            .send(UIRequest::Input {
-                prompt,
-                echo: true,
-                respond: promise,
-            })
-            .context("send to ConnectionUI failed")?;
-
-        block_on(future)
+        request
+            .fields
+            .iter()
+            .map(|field| self.read_line(&field.prompt, field.echo))
+            .collect()
     }
 
-    pub fn password(&self, prompt: &str) -> anyhow::Result<String> {
+    fn read_line(&self, prompt: &str, echo: bool) -> anyhow::Result<String> {
         let mut promise = Promise::new();
         let future = promise.get_future().unwrap();
 
@@ -401,7 +452,7 @@ impl ConnectionUI {
         self.tx
             .send(UIRequest::Input {
                 prompt,
-                echo: false,
+                echo,
                 respond: promise,
             })
             .context("send to ConnectionUI failed")?;
@@ -409,6 +460,24 @@ impl ConnectionUI {
         block_on(future)
     }
 
+    fn prompt_one(&self, prompt: &str, echo: bool) -> anyhow::Result<String> {
+        let field = PromptField {
+            prompt: prompt.to_string(),
+            echo,
+        };
+        self.prompt("", vec![field])?
+            .pop()
+            .ok_or_else(|| anyhow!("prompt was not answered"))
+    }
+
+    pub fn input(&self, prompt: &str) -> anyhow::Result<String> {
+        self.prompt_one(prompt, true)
+    }
+
+    pub fn password(&self, prompt: &str) -> anyhow::Result<String> {
+        self.prompt_one(prompt, false)
+    }
+
     pub fn close(&self) {
         self.tx.send(UIRequest::Close).ok();
     }
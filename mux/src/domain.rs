@@ -13,11 +13,16 @@ use crate::Mux;
 use anyhow::{bail, Context, Error};
 use async_trait::async_trait;
 use config::keyassignment::{SpawnCommand, SpawnTabDomain};
-use config::{configuration, ExecDomain, SerialDomain, ValueOrFunc, WslDomain};
+use config::{
+    configuration, ExecDomain, SerialDomain, SerialFlowControl, SerialLineEnding, SerialParity,
+    SerialStopBits, ValueOrFunc, WslDomain,
+};
 use downcast_rs::{impl_downcast, Downcast};
 use parking_lot::Mutex;
+use portable_pty::serial::{LineEnding, SerialTty};
 use portable_pty::{native_pty_system, CommandBuilder, ExitStatus, MasterPty, PtySize, PtySystem};
-use std::collections::HashMap;
+use serial2::{FlowControl, Parity, StopBits};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -56,8 +61,9 @@ pub trait Domain: Downcast + Send + Sync {
         command_dir: Option<String>,
         window: WindowId,
     ) -> anyhow::Result<Arc<Tab>> {
+        let workspace = Mux::get().workspace_of_window(window);
         let pane = self
-            .spawn_pane(size, command, command_dir)
+            .spawn_pane(size, command, command_dir, &workspace)
             .await
             .context("spawn")?;
 
@@ -103,7 +109,11 @@ pub trait Domain: Downcast + Send + Sync {
                 command,
                 command_dir,
             } => {
-                self.spawn_pane(split_size.second, command, command_dir)
+                let workspace = match mux.window_containing_tab(tab.tab_id()) {
+                    Some(window) => mux.workspace_of_window(window),
+                    None => mux.active_workspace(),
+                };
+                self.spawn_pane(split_size.second, command, command_dir, &workspace)
                     .await?
             }
             SplitSource::MovePane(src_pane_id) => {
@@ -141,11 +151,13 @@ pub trait Domain: Downcast + Send + Sync {
         Ok(pane)
     }
 
+    /// Spawn a pane for a tab in `workspace`
     async fn spawn_pane(
         &self,
         size: TerminalSize,
         command: Option<CommandBuilder>,
         command_dir: Option<String>,
+        workspace: &str,
     ) -> anyhow::Result<Arc<dyn Pane>>;
 
     /// The mux will call this method on the domain of the pane that
@@ -199,6 +211,14 @@ pub trait Domain: Downcast + Send + Sync {
 }
 impl_downcast!(Domain);
 
+fn serial_line_ending(line_ending: SerialLineEnding) -> LineEnding {
+    match line_ending {
+        SerialLineEnding::CR => LineEnding::Cr,
+        SerialLineEnding::LF => LineEnding::Lf,
+        SerialLineEnding::CRLF => LineEnding::CrLf,
+    }
+}
+
 pub struct LocalDomain {
     pty_system: Mutex<Box<dyn PtySystem + Send>>,
     id: DomainId,
@@ -243,16 +263,75 @@ impl LocalDomain {
         Self::new(&exec_domain.name)
     }
 
+    /// Maps `cwd`, as reported by a process running in this exec domain,
+    /// to the equivalent path on the host by calling the `map_cwd`
+    /// function of the domain.  Returns None if this is not an exec
+    /// domain, it has no such function, or the function returned nil.
+    pub fn map_exec_domain_cwd(&self, cwd: &str) -> Option<String> {
+        let event_name = self.resolve_exec_domain()?.map_cwd?;
+        let result = config::run_immediate_with_lua_config(|lua| {
+            let lua = match lua {
+                Some(lua) => lua,
+                None => return Ok(None),
+            };
+            let value = config::lua::emit_sync_callback(
+                &*lua,
+                (event_name, (cwd.to_string(), self.name.clone())),
+            )?;
+            match value {
+                mlua::Value::Nil => Ok(None),
+                value => Ok(Some(luahelper::from_lua_value_dynamic::<String>(value)?)),
+            }
+        });
+        match result {
+            Ok(cwd) => cwd,
+            Err(err) => {
+                log::error!(
+                    "Error while calling map_cwd function for ExecDomain `{}`: {err:#}",
+                    self.name
+                );
+                None
+            }
+        }
+    }
+
     pub fn new_serial_domain(serial_domain: SerialDomain) -> anyhow::Result<Self> {
         let port = serial_domain.port.as_ref().unwrap_or(&serial_domain.name);
-        let mut serial = portable_pty::serial::SerialTty::new(&port);
+        let mut serial = SerialTty::new(&port);
         if let Some(baud) = serial_domain.baud {
             serial.set_baud_rate(baud as u32);
         }
+        serial.set_flow_control(match serial_domain.flow_control {
+            SerialFlowControl::None => FlowControl::None,
+            SerialFlowControl::XonXoff => FlowControl::XonXoff,
+            SerialFlowControl::RtsCts => FlowControl::RtsCts,
+        });
+        serial.set_parity(match serial_domain.parity {
+            SerialParity::None => Parity::None,
+            SerialParity::Odd => Parity::Odd,
+            SerialParity::Even => Parity::Even,
+        });
+        serial.set_stop_bits(match serial_domain.stop_bits {
+            SerialStopBits::One => StopBits::One,
+            SerialStopBits::Two => StopBits::Two,
+        });
+        serial.set_line_ending(serial_line_ending(serial_domain.line_ending));
         let pty_system = Box::new(serial);
         Ok(Self::with_pty_system(&serial_domain.name, pty_system))
     }
 
+    /// Changes what the Enter key sends for a serial port domain,
+    /// including to panes that are already connected
+    pub fn set_serial_line_ending(&self, line_ending: SerialLineEnding) -> anyhow::Result<()> {
+        let pty_system = self.pty_system.lock();
+        let pty_system: &dyn PtySystem = &**pty_system;
+        let serial = pty_system
+            .downcast_ref::<SerialTty>()
+            .ok_or_else(|| anyhow::anyhow!("{} is not a serial port domain", self.name))?;
+        serial.set_line_ending(serial_line_ending(line_ending));
+        Ok(())
+    }
+
     #[cfg(unix)]
     fn is_conpty(&self) -> bool {
         false
@@ -279,6 +358,12 @@ impl LocalDomain {
                 }
             }
 
+            if wsl.forward_ssh_agent {
+                args = crate::wsl_agent::wrap_command_for_agent(&wsl, args)?;
+            } else if wsl.share_ssh_auth_sock {
+                crate::wsl_agent::share_auth_sock(cmd);
+            }
+
             let mut argv: Vec<OsString> = vec![
                 "wsl.exe".into(),
                 "--distribution".into(),
@@ -311,30 +396,7 @@ impl LocalDomain {
             cmd.clear_cwd();
             *cmd.get_argv_mut() = argv;
         } else if let Some(ed) = self.resolve_exec_domain() {
-            let mut args = vec![];
-            let mut set_environment_variables = HashMap::new();
-            for arg in cmd.get_argv() {
-                args.push(
-                    arg.to_str()
-                        .ok_or_else(|| anyhow::anyhow!("command argument is not utf8"))?
-                        .to_string(),
-                );
-            }
-            for (k, v) in cmd.iter_full_env_as_str() {
-                set_environment_variables.insert(k.to_string(), v.to_string());
-            }
-            let cwd = match cmd.get_cwd() {
-                Some(cwd) => Some(PathBuf::from(cwd)),
-                None => None,
-            };
-            let spawn_command = SpawnCommand {
-                label: None,
-                domain: SpawnTabDomain::DomainName(ed.name.clone()),
-                args: if args.is_empty() { None } else { Some(args) },
-                set_environment_variables,
-                cwd,
-                position: None,
-            };
+            let spawn_command = command_to_spawn_command(cmd, &ed.name)?;
 
             let spawn_command = config::with_lua_config_on_main_thread(|lua| async {
                 let lua = lua.ok_or_else(|| anyhow::anyhow!("missing lua context"))?;
@@ -355,22 +417,7 @@ impl LocalDomain {
             .await
             .with_context(|| format!("calling ExecDomain {} function", ed.name))?;
 
-            // Reinterpret the SpawnCommand into the builder
-
-            cmd.get_argv_mut().clear();
-            if let Some(args) = &spawn_command.args {
-                for arg in args {
-                    cmd.get_argv_mut().push(arg.into());
-                }
-            }
-            cmd.env_clear();
-            for (k, v) in &spawn_command.set_environment_variables {
-                cmd.env(k, v);
-            }
-            cmd.clear_cwd();
-            if let Some(cwd) = &spawn_command.cwd {
-                cmd.cwd(cwd);
-            }
+            apply_spawn_command(cmd, &spawn_command);
         } else if Path::new("/.flatpak-info").exists() {
             // We're running inside a flatpak sandbox.
             // Run the command outside the sandbox via flatpak-spawn
@@ -451,9 +498,20 @@ impl LocalDomain {
         command: Option<CommandBuilder>,
         command_dir: Option<String>,
         pane_id: PaneId,
+        workspace: &str,
     ) -> anyhow::Result<CommandBuilder> {
         let config = configuration();
 
+        // Variables set explicitly by the spawn win over those of the workspace
+        let explicit_env: HashSet<String> = command
+            .as_ref()
+            .map(|cmd| {
+                cmd.iter_extra_env_as_str()
+                    .map(|(k, _)| k.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let wsl = self.resolve_wsl_domain();
         let default_prog = wsl
             .as_ref()
@@ -476,6 +534,9 @@ impl LocalDomain {
         if let Some(dir) = command_dir {
             cmd.cwd(dir);
         }
+        if let Some(settings) = config.workspace_settings(workspace) {
+            apply_workspace_env(&mut cmd, &settings.env, &explicit_env);
+        }
         if let Ok(sock) = std::env::var("WEZTERM_UNIX_SOCKET") {
             cmd.env("WEZTERM_UNIX_SOCKET", sock);
         }
@@ -483,9 +544,114 @@ impl LocalDomain {
         if let Some(agent) = Mux::get().agent.as_ref() {
             cmd.env("SSH_AUTH_SOCK", agent.path());
         }
+        self.augment_command_environment(&mut cmd, workspace)
+            .await
+            .context("augment-command-environment")?;
         self.fixup_command(&mut cmd).await?;
         Ok(cmd)
     }
+
+    /// Gives the `augment-command-environment` event a chance to adjust
+    /// the environment, cwd and arguments of a command before it is
+    /// spawned into this domain.  The handler is passed the command as
+    /// a SpawnCommand along with the domain and workspace names, and
+    /// may return a modified SpawnCommand, or nil to leave it as-is.
+    async fn augment_command_environment(
+        &self,
+        cmd: &mut CommandBuilder,
+        workspace: &str,
+    ) -> anyhow::Result<()> {
+        let spawn_command = command_to_spawn_command(cmd, &self.name)?;
+        let domain_name = self.name.clone();
+        let workspace = workspace.to_string();
+
+        let spawn_command = config::with_lua_config_on_main_thread(|lua| async {
+            let lua = match lua {
+                Some(lua) => lua,
+                None => return Ok(None),
+            };
+            let value = config::lua::emit_async_callback(
+                &*lua,
+                (
+                    "augment-command-environment".to_string(),
+                    (spawn_command, domain_name, workspace),
+                ),
+            )
+            .await?;
+            if let mlua::Value::Nil = value {
+                return Ok(None);
+            }
+            let cmd: SpawnCommand = luahelper::from_lua_value_dynamic(value)
+                .context("interpreting SpawnCommand result")?;
+            Ok(Some(cmd))
+        })
+        .await?;
+
+        if let Some(spawn_command) = spawn_command {
+            apply_spawn_command(cmd, &spawn_command);
+        }
+        Ok(())
+    }
+}
+
+/// Describes `cmd` as a SpawnCommand, for the Lua functions that may
+/// adjust a command before it is spawned into `domain_name`
+fn command_to_spawn_command(
+    cmd: &CommandBuilder,
+    domain_name: &str,
+) -> anyhow::Result<SpawnCommand> {
+    let mut args = vec![];
+    for arg in cmd.get_argv() {
+        args.push(
+            arg.to_str()
+                .ok_or_else(|| anyhow::anyhow!("command argument is not utf8"))?
+                .to_string(),
+        );
+    }
+    Ok(SpawnCommand {
+        label: None,
+        domain: SpawnTabDomain::DomainName(domain_name.to_string()),
+        args: if args.is_empty() { None } else { Some(args) },
+        set_environment_variables: cmd
+            .iter_full_env_as_str()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        cwd: cmd.get_cwd().map(PathBuf::from),
+        position: None,
+    })
+}
+
+/// Reinterprets the SpawnCommand returned by one of those functions
+/// into `cmd`.  Without any arguments, the default program is spawned.
+fn apply_spawn_command(cmd: &mut CommandBuilder, spawn_command: &SpawnCommand) {
+    cmd.get_argv_mut().clear();
+    if let Some(args) = &spawn_command.args {
+        for arg in args {
+            cmd.get_argv_mut().push(arg.into());
+        }
+    }
+    cmd.env_clear();
+    for (k, v) in &spawn_command.set_environment_variables {
+        cmd.env(k, v);
+    }
+    cmd.clear_cwd();
+    if let Some(cwd) = &spawn_command.cwd {
+        cmd.cwd(cwd);
+    }
+}
+
+/// Sets the `env` of the active workspace on `cmd`, except for the
+/// variables in `explicit_env`, which were set by the spawn itself
+fn apply_workspace_env(
+    cmd: &mut CommandBuilder,
+    env: &HashMap<String, String>,
+    explicit_env: &HashSet<String>,
+) {
+    for (k, v) in env {
+        if !explicit_env.contains(k) {
+            cmd.env(k, v);
+        }
+    }
 }
 
 /// Allows sharing the writer between the Pane and the Terminal.
@@ -592,10 +758,11 @@ impl Domain for LocalDomain {
         size: TerminalSize,
         command: Option<CommandBuilder>,
         command_dir: Option<String>,
+        workspace: &str,
     ) -> anyhow::Result<Arc<dyn Pane>> {
         let pane_id = alloc_pane_id();
         let cmd = self
-            .build_command(command, command_dir, pane_id)
+            .build_command(command, command_dir, pane_id, workspace)
             .await
             .context("build_command")?;
         let pair = self
@@ -730,3 +897,58 @@ impl Domain for LocalDomain {
         DomainState::Attached
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn explicit_env_wins_over_workspace_env() {
+        let mut cmd = CommandBuilder::new("true");
+        cmd.env("FOO", "spawn");
+        let explicit_env: HashSet<String> = cmd
+            .iter_extra_env_as_str()
+            .map(|(k, _)| k.to_string())
+            .collect();
+
+        let mut workspace_env = HashMap::new();
+        workspace_env.insert("FOO".to_string(), "workspace".to_string());
+        workspace_env.insert("BAR".to_string(), "workspace".to_string());
+        apply_workspace_env(&mut cmd, &workspace_env, &explicit_env);
+
+        assert_eq!(cmd.get_env("FOO"), Some(OsStr::new("spawn")));
+        assert_eq!(cmd.get_env("BAR"), Some(OsStr::new("workspace")));
+    }
+
+    #[test]
+    fn spawn_command_round_trip() {
+        let mut cmd = CommandBuilder::new("vim");
+        cmd.arg("notes.txt");
+        cmd.env("FOO", "bar");
+        cmd.cwd("/tmp");
+
+        let mut spawn_command = command_to_spawn_command(&cmd, "local").unwrap();
+        assert_eq!(
+            spawn_command.args,
+            Some(vec!["vim".to_string(), "notes.txt".to_string()])
+        );
+        assert_eq!(
+            spawn_command.set_environment_variables.get("FOO"),
+            Some(&"bar".to_string())
+        );
+        assert_eq!(spawn_command.cwd, Some(PathBuf::from("/tmp")));
+
+        spawn_command.args = None;
+        spawn_command.cwd = None;
+        spawn_command
+            .set_environment_variables
+            .insert("BAZ".to_string(), "qux".to_string());
+        apply_spawn_command(&mut cmd, &spawn_command);
+
+        assert!(cmd.is_default_prog());
+        assert_eq!(cmd.get_cwd(), None);
+        assert_eq!(cmd.get_env("FOO"), Some(OsStr::new("bar")));
+        assert_eq!(cmd.get_env("BAZ"), Some(OsStr::new("qux")));
+    }
+}
@@ -0,0 +1,190 @@
+//! Collects the exit status and resource usage of the program that
+//! was spawned into a pane, for the summary line shown when
+//! `pane_exit_summary` is enabled and for the `pane-exited` event.
+use crate::pane::PaneId;
+use luahelper::impl_lua_conversion_dynamic;
+use portable_pty::{Child, ExitStatus};
+use std::io::Result as IoResult;
+use std::time::Duration;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    /// User plus system time
+    pub cpu_time: Duration,
+    pub max_rss_bytes: u64,
+}
+
+#[cfg(unix)]
+impl ResourceUsage {
+    fn from_rusage(usage: &libc::rusage) -> Self {
+        fn timeval(tv: &libc::timeval) -> Duration {
+            Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+        }
+
+        // Linux reports ru_maxrss in kilobytes, macOS in bytes
+        #[cfg(target_os = "macos")]
+        let max_rss_bytes = usage.ru_maxrss as u64;
+        #[cfg(not(target_os = "macos"))]
+        let max_rss_bytes = usage.ru_maxrss as u64 * 1024;
+
+        Self {
+            cpu_time: timeval(&usage.ru_utime) + timeval(&usage.ru_stime),
+            max_rss_bytes,
+        }
+    }
+}
+
+/// Waits for `process` to terminate.  On unix systems a process that
+/// was spawned directly by this one is reaped via wait4, so that its
+/// resource usage can be reported too.  The pid is taken from the
+/// `std::process::Child` handle itself, rather than from `process_id`,
+/// as other kinds of child, such as those of ssh or tmux domains, may
+/// report the pid of a process that isn't ours to reap.  Otherwise the
+/// wait is delegated to `process`.
+pub(crate) fn wait_for_child(
+    process: &mut Box<dyn Child>,
+) -> IoResult<(ExitStatus, Option<ResourceUsage>)> {
+    #[cfg(unix)]
+    {
+        let pid = process
+            .downcast_ref::<std::process::Child>()
+            .map(|child| child.id());
+        if let Some(pid) = pid {
+            use std::os::unix::process::ExitStatusExt;
+            let mut status = 0;
+            let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+            loop {
+                let res = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) };
+                if res == pid as libc::pid_t {
+                    let status = std::process::ExitStatus::from_raw(status);
+                    return Ok((status.into(), Some(ResourceUsage::from_rusage(&usage))));
+                }
+                if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+                    break;
+                }
+            }
+        }
+    }
+    process.wait().map(|status| (status, None))
+}
+
+/// Describes how the program spawned into a pane exited
+#[derive(Debug, Clone, PartialEq, FromDynamic, ToDynamic)]
+pub struct PaneExitSummary {
+    pub pane_id: PaneId,
+    pub command: String,
+    /// true if the exit code is 0 or one of `clean_exit_codes`
+    pub success: bool,
+    pub exit_code: u32,
+    pub signal: Option<String>,
+    /// Seconds elapsed between spawning and exiting
+    pub wall_time: f64,
+    /// Seconds of user plus system CPU time, where available
+    pub cpu_time: Option<f64>,
+    /// Peak resident set size, where available
+    pub max_rss_bytes: Option<u64>,
+}
+impl_lua_conversion_dynamic!(PaneExitSummary);
+
+impl PaneExitSummary {
+    pub fn new(
+        pane_id: PaneId,
+        command: &str,
+        status: &ExitStatus,
+        success: bool,
+        wall_time: Duration,
+        usage: Option<ResourceUsage>,
+    ) -> Self {
+        Self {
+            pane_id,
+            command: command.to_string(),
+            success,
+            exit_code: status.exit_code(),
+            signal: status.signal().map(|s| s.to_string()),
+            wall_time: wall_time.as_secs_f64(),
+            cpu_time: usage.map(|u| u.cpu_time.as_secs_f64()),
+            max_rss_bytes: usage.map(|u| u.max_rss_bytes),
+        }
+    }
+
+    /// Returns a compact one line description, such as
+    /// `exit 0, 3.21s, cpu 1.10s, max rss 12.3 MiB`
+    pub fn summary_line(&self) -> String {
+        let mut parts = vec![match &self.signal {
+            Some(signal) => signal.to_string(),
+            None => format!("exit {}", self.exit_code),
+        }];
+        parts.push(format_seconds(self.wall_time));
+        if let Some(cpu) = self.cpu_time {
+            parts.push(format!("cpu {}", format_seconds(cpu)));
+        }
+        if let Some(rss) = self.max_rss_bytes {
+            parts.push(format!("max rss {}", format_bytes(rss)));
+        }
+        parts.join(", ")
+    }
+}
+
+fn format_seconds(secs: f64) -> String {
+    if secs < 60. {
+        return format!("{secs:.2}s");
+    }
+    let secs = secs.round() as u64;
+    if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for u in UNITS {
+        if value < 1024. {
+            break;
+        }
+        value /= 1024.;
+        unit = u;
+    }
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary_line() {
+        let summary = PaneExitSummary::new(
+            1,
+            "make",
+            &ExitStatus::with_exit_code(2),
+            false,
+            Duration::from_millis(3210),
+            Some(ResourceUsage {
+                cpu_time: Duration::from_millis(1100),
+                max_rss_bytes: 12_900_000,
+            }),
+        );
+        assert_eq!(
+            summary.summary_line(),
+            "exit 2, 3.21s, cpu 1.10s, max rss 12.3 MiB"
+        );
+
+        let summary = PaneExitSummary::new(
+            1,
+            "sleep",
+            &ExitStatus::with_signal("Terminated"),
+            false,
+            Duration::from_secs(3725),
+            None,
+        );
+        assert_eq!(summary.summary_line(), "Terminated, 1h02m");
+    }
+}
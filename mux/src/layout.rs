@@ -0,0 +1,251 @@
+//! Builds the panes described by a layout preset from the `layouts`
+//! config table, for `ApplyLayout` and `kaku cli apply-layout`.
+use crate::domain::SplitSource;
+use crate::pane::PaneId;
+use crate::tab::{SplitDirection, SplitRequest, SplitSize, Tab};
+use crate::window::WindowId;
+use crate::Mux;
+use anyhow::{anyhow, Context};
+use config::keyassignment::{self, PaneDirection};
+use config::LayoutPane;
+use portable_pty::CommandBuilder;
+use std::sync::Arc;
+use wezterm_term::TerminalSize;
+
+/// Returns the layout preset named `name`
+pub fn get_layout(name: &str) -> anyhow::Result<LayoutPane> {
+    config::configuration()
+        .layouts
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("there is no layout named `{}` in the layouts config", name))
+}
+
+/// Returns the command to spawn for `layout`, or None to spawn the
+/// default program
+fn command_for(layout: &LayoutPane) -> Option<CommandBuilder> {
+    let spawn = &layout.command;
+    if spawn.args.is_none() && spawn.set_environment_variables.is_empty() {
+        return None;
+    }
+    let mut builder = spawn
+        .args
+        .as_ref()
+        .map(|args| CommandBuilder::from_argv(args.iter().map(Into::into).collect()))
+        .unwrap_or_else(CommandBuilder::new_default_prog);
+    for (k, v) in spawn.set_environment_variables.iter() {
+        builder.env(k, v);
+    }
+    Some(builder)
+}
+
+/// The cwd of `layout`, falling back to the cwd of its parent
+fn cwd_for(layout: &LayoutPane, parent_cwd: Option<&String>) -> anyhow::Result<Option<String>> {
+    match &layout.command.cwd {
+        Some(cwd) => cwd
+            .to_str()
+            .map(|cwd| Some(cwd.to_string()))
+            .ok_or_else(|| anyhow!("the cwd {:?} must be unicode", cwd)),
+        None => Ok(parent_cwd.cloned()),
+    }
+}
+
+fn split_request(layout: &LayoutPane) -> anyhow::Result<SplitRequest> {
+    let (direction, target_is_second) = match layout.direction {
+        PaneDirection::Right => (SplitDirection::Horizontal, true),
+        PaneDirection::Left => (SplitDirection::Horizontal, false),
+        PaneDirection::Down => (SplitDirection::Vertical, true),
+        PaneDirection::Up => (SplitDirection::Vertical, false),
+        PaneDirection::Next | PaneDirection::Prev => {
+            anyhow::bail!("invalid direction {:?} for a layout pane", layout.direction)
+        }
+    };
+    Ok(SplitRequest {
+        direction,
+        target_is_second,
+        top_level: false,
+        size: match layout.size {
+            keyassignment::SplitSize::Percent(n) => SplitSize::Percent(n),
+            keyassignment::SplitSize::Cells(n) => SplitSize::Cells(n),
+        },
+    })
+}
+
+/// Checks the directions and cwds of `layout` and its splits, so that
+/// a mistake in the config is reported before anything is spawned
+fn check_layout(layout: &LayoutPane) -> anyhow::Result<()> {
+    cwd_for(layout, None)?;
+    for split in &layout.splits {
+        split_request(split)?;
+        check_layout(split)?;
+    }
+    Ok(())
+}
+
+/// Spawns the panes of `layout` in a new tab of `window_id`, or of a
+/// new window in `workspace` if `window_id` is None.
+/// Returns the new tab, its active pane and the window it is in.
+/// If any of the panes fails to spawn, the tab is removed again.
+pub async fn apply_layout(
+    layout: &LayoutPane,
+    window_id: Option<WindowId>,
+    size: TerminalSize,
+    workspace: String,
+) -> anyhow::Result<(Arc<Tab>, PaneId, WindowId)> {
+    check_layout(layout)?;
+
+    let mux = Mux::get();
+    let cwd = cwd_for(layout, None)?;
+    let (tab, pane, window_id) = mux
+        .spawn_tab_or_window(
+            window_id,
+            layout.command.domain.clone(),
+            command_for(layout),
+            cwd.clone(),
+            size,
+            None,
+            workspace,
+            layout.command.position.clone(),
+        )
+        .await
+        .context("spawning the first pane of the layout")?;
+
+    let active = match split_layout(&mux, layout, pane.pane_id(), cwd).await {
+        Ok(active) => active,
+        Err(err) => {
+            // Don't leave a partial layout behind
+            mux.remove_tab(tab.tab_id());
+            return Err(err);
+        }
+    };
+
+    if let Some(pane) = mux.get_pane(active) {
+        tab.set_active_pane(&pane);
+    }
+    Ok((tab, active, window_id))
+}
+
+/// Spawns the splits of `layout` off from `pane_id`, which is the
+/// pane spawned for `layout` itself.  Returns the pane to activate.
+async fn split_layout(
+    mux: &Mux,
+    layout: &LayoutPane,
+    pane_id: PaneId,
+    cwd: Option<String>,
+) -> anyhow::Result<PaneId> {
+    let mut active = pane_id;
+    let mut pending = vec![(layout, pane_id, cwd)];
+    while let Some((layout, pane_id, cwd)) = pending.pop() {
+        if layout.active {
+            active = pane_id;
+        }
+        let mut children = vec![];
+        for split in &layout.splits {
+            let split_cwd = cwd_for(split, cwd.as_ref())?;
+            let (new_pane, _size) = mux
+                .split_pane(
+                    pane_id,
+                    split_request(split)?,
+                    SplitSource::Spawn {
+                        command: command_for(split),
+                        command_dir: split_cwd.clone(),
+                    },
+                    split.command.domain.clone(),
+                )
+                .await
+                .context("splitting a pane of the layout")?;
+            children.push((split, new_pane.pane_id(), split_cwd));
+        }
+        // Visit the children in order
+        pending.extend(children.into_iter().rev());
+    }
+    Ok(active)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use config::keyassignment::SpawnCommand;
+
+    fn pane(direction: PaneDirection, splits: Vec<LayoutPane>) -> LayoutPane {
+        LayoutPane {
+            command: SpawnCommand::default(),
+            direction,
+            size: keyassignment::SplitSize::Percent(30),
+            splits,
+            active: false,
+        }
+    }
+
+    #[test]
+    fn split_requests() {
+        let request = split_request(&pane(PaneDirection::Left, vec![])).unwrap();
+        assert_eq!(
+            request,
+            SplitRequest {
+                direction: SplitDirection::Horizontal,
+                target_is_second: false,
+                top_level: false,
+                size: SplitSize::Percent(30),
+            }
+        );
+        let request = split_request(&pane(PaneDirection::Down, vec![])).unwrap();
+        assert_eq!(request.direction, SplitDirection::Vertical);
+        assert!(request.target_is_second);
+        assert!(split_request(&pane(PaneDirection::Next, vec![])).is_err());
+    }
+
+    #[test]
+    fn commands_and_cwds() {
+        let mut layout = pane(PaneDirection::Right, vec![]);
+        assert!(command_for(&layout).is_none());
+        assert_eq!(
+            cwd_for(&layout, Some(&"/src".to_string())).unwrap(),
+            Some("/src".to_string())
+        );
+
+        layout.command.args = Some(vec!["tail".to_string(), "-f".to_string()]);
+        layout.command.cwd = Some("/var/log".into());
+        let argv: Vec<String> = command_for(&layout)
+            .unwrap()
+            .get_argv()
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(argv, vec!["tail", "-f"]);
+        assert_eq!(
+            cwd_for(&layout, Some(&"/src".to_string())).unwrap(),
+            Some("/var/log".to_string())
+        );
+
+        // Environment variables alone still need a command builder
+        let mut layout = pane(PaneDirection::Right, vec![]);
+        layout
+            .command
+            .set_environment_variables
+            .insert("RUST_LOG".to_string(), "debug".to_string());
+        assert!(command_for(&layout).unwrap().is_default_prog());
+    }
+
+    #[test]
+    fn check_nested_directions() {
+        let layout = pane(
+            PaneDirection::Next,
+            vec![pane(
+                PaneDirection::Right,
+                vec![pane(PaneDirection::Down, vec![])],
+            )],
+        );
+        // The direction of the top level pane is ignored
+        assert!(check_layout(&layout).is_ok());
+
+        let layout = pane(
+            PaneDirection::Right,
+            vec![pane(
+                PaneDirection::Right,
+                vec![pane(PaneDirection::Prev, vec![])],
+            )],
+        );
+        assert!(check_layout(&layout).is_err());
+    }
+}
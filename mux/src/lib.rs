@@ -1,12 +1,13 @@
 use crate::client::{ClientId, ClientInfo};
+use crate::output_filter::OutputFilters;
 use crate::pane::{CachePolicy, Pane, PaneId};
 use crate::ssh_agent::AgentProxy;
 use crate::tab::{SplitRequest, Tab, TabId};
 use crate::window::{Window, WindowId};
 use anyhow::{anyhow, Context, Error};
-use config::keyassignment::SpawnTabDomain;
-use config::{configuration, ExitBehavior, GuiPosition};
-use domain::{Domain, DomainId, DomainState, SplitSource};
+use config::keyassignment::{PaneMonitor, SpawnTabDomain};
+use config::{configuration, BackgroundLayer, ExitBehavior, GuiPosition, WorkspaceSpawnDefaults};
+use domain::{Domain, DomainId, DomainState, LocalDomain, SplitSource};
 use filedescriptor::{poll, pollfd, socketpair, AsRawSocketDescriptor, FileDescriptor, POLLIN};
 #[cfg(unix)]
 use libc::{c_int, SOL_SOCKET, SO_RCVBUF, SO_SNDBUF};
@@ -29,17 +30,26 @@ use std::time::{Duration, Instant};
 use termwiz::escape::csi::{DecPrivateMode, DecPrivateModeCode, Device, Mode};
 use termwiz::escape::{Action, CSI};
 use thiserror::*;
-use wezterm_term::{Clipboard, ClipboardSelection, DownloadHandler, TerminalSize};
+use wezterm_term::{Clipboard, ClipboardSelection, DownloadHandler, Progress, TerminalSize};
 #[cfg(windows)]
 use winapi::um::winsock2::{SOL_SOCKET, SO_RCVBUF, SO_SNDBUF};
 
 pub mod activity;
+pub mod asciicast;
 pub mod client;
 pub mod connui;
 pub mod domain;
+pub mod exit_summary;
+pub mod layout;
 pub mod localpane;
+mod monitor;
+pub mod output_filter;
 pub mod pane;
+pub mod pane_text;
+pub mod recording;
 pub mod renderable;
+pub mod scrollback_export;
+pub mod share;
 pub mod ssh;
 pub mod ssh_agent;
 pub mod tab;
@@ -48,6 +58,7 @@ pub mod tmux;
 pub mod tmux_commands;
 mod tmux_pty;
 pub mod window;
+pub mod wsl_agent;
 
 use crate::activity::Activity;
 
@@ -95,10 +106,62 @@ pub enum MuxNotification {
         old_workspace: String,
         new_workspace: String,
     },
+    ClientAttached(Arc<ClientId>),
+    ClientDetached(Arc<ClientId>),
+    PaneProcessExited {
+        pane_id: PaneId,
+        status: ExitStatus,
+    },
+    /// Text was copied in a client attached to the mux server.
+    /// `origin` identifies that client, so that the text isn't sent
+    /// back to it; it is `None` for text that was received from a
+    /// remote mux server, which must not be relayed any further.
+    /// See `client::should_relay_clipboard`.
+    ClipboardSynced {
+        origin: Option<Arc<ClientId>>,
+        selection: ClipboardSelection,
+        clipboard: String,
+    },
+    /// A pane that is being watched with `Mux::set_pane_monitor`
+    /// saw the activity, or silence, that it was watched for
+    PaneMonitorTriggered {
+        pane_id: PaneId,
+        monitor: PaneMonitor,
+    },
+    /// The client certificate of a TLS domain is close to expiry.
+    /// `days_remaining` is negative once it has expired.
+    TlsCertificateExpiring {
+        domain_name: String,
+        days_remaining: i64,
+    },
 }
 
 static SUB_ID: AtomicUsize = AtomicUsize::new(0);
 
+/// Converts the `file://` URL reported as the working directory
+/// of a pane into a path that can be used to spawn a command
+pub fn cwd_url_to_path(url: &url::Url) -> Option<String> {
+    let path = percent_decode_str(url.path()).decode_utf8().ok()?;
+    // On Windows the file URI can produce a path like:
+    // `/C:\Users` which is valid in a file URI, but the leading slash
+    // is not liked by the windows file APIs, so we strip it off here.
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        Some(path[1..].to_owned())
+    } else {
+        Some(path.into_owned())
+    }
+}
+
+/// A group of panes within which input is mirrored,
+/// see `Mux::toggle_input_broadcast`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputBroadcast {
+    Tab(TabId),
+    Window(WindowId),
+    Workspace(String),
+}
+
 pub struct Mux {
     tabs: RwLock<HashMap<TabId, Arc<Tab>>>,
     panes: RwLock<HashMap<PaneId, Arc<dyn Pane>>>,
@@ -111,6 +174,13 @@ pub struct Mux {
     clients: RwLock<HashMap<ClientId, ClientInfo>>,
     identity: RwLock<Option<Arc<ClientId>>>,
     num_panes_by_workspace: RwLock<HashMap<String, usize>>,
+    input_locked_panes: RwLock<HashSet<PaneId>>,
+    input_broadcasts: RwLock<HashSet<InputBroadcast>>,
+    broadcast_excluded_panes: RwLock<HashSet<PaneId>>,
+    pane_monitors: RwLock<HashMap<PaneId, monitor::MonitorState>>,
+    silence_checker_running: AtomicBool,
+    pane_backgrounds: RwLock<HashMap<PaneId, Arc<Vec<BackgroundLayer>>>>,
+    tab_backgrounds: RwLock<HashMap<TabId, Arc<Vec<BackgroundLayer>>>>,
     main_thread_id: std::thread::ThreadId,
     agent: Option<AgentProxy>,
 }
@@ -125,6 +195,9 @@ fn send_actions_to_mux(pane: &Weak<dyn Pane>, dead: &Arc<AtomicBool>, actions: V
         Some(pane) => {
             pane.perform_actions(actions);
             histogram!("send_actions_to_mux.perform_actions.latency").record(start.elapsed());
+            if let Some(mux) = Mux::try_get() {
+                mux.record_pane_monitor_output(pane.pane_id());
+            }
             Mux::notify_from_any_thread(MuxNotification::PaneOutput(pane.pane_id()));
         }
         None => {
@@ -145,6 +218,7 @@ fn parse_buffered_data(pane: Weak<dyn Pane>, dead: &Arc<AtomicBool>, mut rx: Fil
     let mut action_size = 0;
     let mut delay = Duration::from_millis(configuration().mux_output_parser_coalesce_delay_ms);
     let mut deadline = None;
+    let mut filters = OutputFilters::new();
 
     loop {
         match rx.read(&mut buf) {
@@ -167,7 +241,11 @@ fn parse_buffered_data(pane: Weak<dyn Pane>, dead: &Arc<AtomicBool>, mut rx: Fil
 
                             // Flush prior actions
                             if !actions.is_empty() {
-                                send_actions_to_mux(&pane, &dead, std::mem::take(&mut actions));
+                                send_actions_to_mux(
+                                    &pane,
+                                    &dead,
+                                    filters.apply(std::mem::take(&mut actions)),
+                                );
                                 action_size = 0;
                             }
                         }
@@ -186,7 +264,11 @@ fn parse_buffered_data(pane: Weak<dyn Pane>, dead: &Arc<AtomicBool>, mut rx: Fil
                     action.append_to(&mut actions);
 
                     if flush && !actions.is_empty() {
-                        send_actions_to_mux(&pane, &dead, std::mem::take(&mut actions));
+                        send_actions_to_mux(
+                            &pane,
+                            &dead,
+                            filters.apply(std::mem::take(&mut actions)),
+                        );
                         action_size = 0;
                     }
                 });
@@ -221,13 +303,14 @@ fn parse_buffered_data(pane: Weak<dyn Pane>, dead: &Arc<AtomicBool>, mut rx: Fil
                         }
                     }
 
-                    send_actions_to_mux(&pane, &dead, std::mem::take(&mut actions));
+                    send_actions_to_mux(&pane, &dead, filters.apply(std::mem::take(&mut actions)));
                     deadline = None;
                     action_size = 0;
                 }
 
                 let config = configuration();
                 buf.resize(config.mux_output_parser_buffer_size, 0);
+                filters.update_config();
                 delay = Duration::from_millis(config.mux_output_parser_coalesce_delay_ms);
             }
         }
@@ -238,7 +321,7 @@ fn parse_buffered_data(pane: Weak<dyn Pane>, dead: &Arc<AtomicBool>, mut rx: Fil
     // for very short lived commands so that we don't forget to
     // display what they displayed.
     if !actions.is_empty() {
-        send_actions_to_mux(&pane, &dead, std::mem::take(&mut actions));
+        send_actions_to_mux(&pane, &dead, filters.apply(std::mem::take(&mut actions)));
     }
 }
 
@@ -329,6 +412,7 @@ fn read_from_pane_pty(
             Ok(size) => {
                 histogram!("read_from_pane_pty.bytes.rate").record(size as f64);
                 log::trace!("read_pty pane {pane_id} read {size} bytes");
+                recording::record_output(pane_id, &buf[..size]);
                 if let Err(err) = tx.write_all(&buf[..size]) {
                     error!(
                         "read_pty failed to write to parser: pane {} {:?}",
@@ -446,6 +530,13 @@ impl Mux {
             clients: RwLock::new(HashMap::new()),
             identity: RwLock::new(None),
             num_panes_by_workspace: RwLock::new(HashMap::new()),
+            input_locked_panes: RwLock::new(HashSet::new()),
+            input_broadcasts: RwLock::new(HashSet::new()),
+            broadcast_excluded_panes: RwLock::new(HashSet::new()),
+            pane_monitors: RwLock::new(HashMap::new()),
+            silence_checker_running: AtomicBool::new(false),
+            pane_backgrounds: RwLock::new(HashMap::new()),
+            tab_backgrounds: RwLock::new(HashMap::new()),
             main_thread_id: std::thread::current().id(),
             agent,
         }
@@ -567,7 +658,8 @@ impl Mux {
     pub fn register_client(&self, client_id: Arc<ClientId>) {
         self.clients
             .write()
-            .insert((*client_id).clone(), ClientInfo::new(client_id));
+            .insert((*client_id).clone(), ClientInfo::new(client_id.clone()));
+        self.notify(MuxNotification::ClientAttached(client_id));
     }
 
     pub fn iter_clients(&self) -> Vec<ClientInfo> {
@@ -686,7 +778,10 @@ impl Mux {
     }
 
     pub fn unregister_client(&self, client_id: &ClientId) {
-        self.clients.write().remove(client_id);
+        let removed = self.clients.write().remove(client_id);
+        if let Some(info) = removed {
+            self.notify(MuxNotification::ClientDetached(info.client_id));
+        }
     }
 
     pub fn subscribe<F>(&self, subscriber: F)
@@ -767,6 +862,154 @@ impl Mux {
         self.panes.read().get(&pane_id).map(Arc::clone)
     }
 
+    /// Locks or unlocks input to a pane.  While locked, the pane is
+    /// read-only: keystrokes and pastes from the GUI are discarded.
+    pub fn set_pane_input_locked(&self, pane_id: PaneId, locked: bool) {
+        let changed = if locked {
+            self.input_locked_panes.write().insert(pane_id)
+        } else {
+            self.input_locked_panes.write().remove(&pane_id)
+        };
+        if changed {
+            if let Some((_domain_id, window_id, _tab_id)) = self.resolve_pane_id(pane_id) {
+                self.notify(MuxNotification::WindowInvalidated(window_id));
+            }
+        }
+    }
+
+    pub fn is_pane_input_locked(&self, pane_id: PaneId) -> bool {
+        self.input_locked_panes.read().contains(&pane_id)
+    }
+
+    /// Starts or stops mirroring the input typed into any pane in
+    /// `broadcast` to all of the other panes in it.
+    /// Returns true if the broadcast is now active.
+    pub fn toggle_input_broadcast(&self, broadcast: InputBroadcast) -> bool {
+        let active = {
+            let mut broadcasts = self.input_broadcasts.write();
+            if broadcasts.remove(&broadcast) {
+                false
+            } else {
+                broadcasts.insert(broadcast);
+                true
+            }
+        };
+        for window_id in self.iter_windows() {
+            self.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+        active
+    }
+
+    /// Excludes a pane from sending and receiving broadcast input
+    pub fn set_pane_broadcast_excluded(&self, pane_id: PaneId, excluded: bool) {
+        let changed = if excluded {
+            self.broadcast_excluded_panes.write().insert(pane_id)
+        } else {
+            self.broadcast_excluded_panes.write().remove(&pane_id)
+        };
+        if changed {
+            if let Some((_domain_id, window_id, _tab_id)) = self.resolve_pane_id(pane_id) {
+                self.notify(MuxNotification::WindowInvalidated(window_id));
+            }
+        }
+    }
+
+    pub fn is_pane_broadcast_excluded(&self, pane_id: PaneId) -> bool {
+        self.broadcast_excluded_panes.read().contains(&pane_id)
+    }
+
+    /// Returns the active input broadcast that includes `pane_id`, if any
+    fn input_broadcast_for_pane(&self, pane_id: PaneId) -> Option<InputBroadcast> {
+        if self.input_broadcasts.read().is_empty() || self.is_pane_broadcast_excluded(pane_id) {
+            return None;
+        }
+        let (_domain_id, window_id, tab_id) = self.resolve_pane_id(pane_id)?;
+        let workspace = self.get_window(window_id)?.get_workspace().to_string();
+        self.input_broadcasts
+            .read()
+            .iter()
+            .find(|broadcast| match broadcast {
+                InputBroadcast::Tab(id) => *id == tab_id,
+                InputBroadcast::Window(id) => *id == window_id,
+                InputBroadcast::Workspace(name) => *name == workspace,
+            })
+            .cloned()
+    }
+
+    /// Returns true if input typed into `pane_id` is being mirrored
+    /// to other panes, and vice versa
+    pub fn is_pane_broadcasting_input(&self, pane_id: PaneId) -> bool {
+        self.input_broadcast_for_pane(pane_id).is_some()
+    }
+
+    /// Returns the panes to which input typed into `pane_id` should
+    /// be mirrored.  Panes that are excluded or input locked are
+    /// omitted.
+    pub fn input_broadcast_targets(&self, pane_id: PaneId) -> Vec<Arc<dyn Pane>> {
+        let tabs = match self.input_broadcast_for_pane(pane_id) {
+            None => return vec![],
+            Some(InputBroadcast::Tab(tab_id)) => self.get_tab(tab_id).into_iter().collect(),
+            Some(InputBroadcast::Window(window_id)) => self
+                .get_window(window_id)
+                .map(|window| window.iter().cloned().collect())
+                .unwrap_or_default(),
+            Some(InputBroadcast::Workspace(workspace)) => self
+                .iter_windows_in_workspace(&workspace)
+                .into_iter()
+                .filter_map(|window_id| self.get_window(window_id))
+                .flat_map(|window| window.iter().cloned().collect::<Vec<_>>())
+                .collect::<Vec<Arc<Tab>>>(),
+        };
+        tabs.iter()
+            .flat_map(|tab| tab.iter_panes_ignoring_zoom())
+            .map(|pos| pos.pane)
+            .filter(|pane| {
+                let id = pane.pane_id();
+                id != pane_id
+                    && !self.is_pane_broadcast_excluded(id)
+                    && !self.is_pane_input_locked(id)
+            })
+            .collect()
+    }
+
+    /// Sets the background layers drawn behind a pane, in place of the
+    /// window background.  An empty list removes them.
+    pub fn set_pane_background(&self, pane_id: PaneId, layers: Vec<BackgroundLayer>) {
+        if layers.is_empty() {
+            self.pane_backgrounds.write().remove(&pane_id);
+        } else {
+            self.pane_backgrounds
+                .write()
+                .insert(pane_id, Arc::new(layers));
+        }
+        if let Some((_domain_id, window_id, _tab_id)) = self.resolve_pane_id(pane_id) {
+            self.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+    }
+
+    pub fn get_pane_background(&self, pane_id: PaneId) -> Option<Arc<Vec<BackgroundLayer>>> {
+        self.pane_backgrounds.read().get(&pane_id).map(Arc::clone)
+    }
+
+    /// Sets the background layers drawn behind the panes of a tab that
+    /// don't have a background of their own.  An empty list removes them.
+    pub fn set_tab_background(&self, tab_id: TabId, layers: Vec<BackgroundLayer>) {
+        if layers.is_empty() {
+            self.tab_backgrounds.write().remove(&tab_id);
+        } else {
+            self.tab_backgrounds
+                .write()
+                .insert(tab_id, Arc::new(layers));
+        }
+        if let Some(window_id) = self.window_containing_tab(tab_id) {
+            self.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+    }
+
+    pub fn get_tab_background(&self, tab_id: TabId) -> Option<Arc<Vec<BackgroundLayer>>> {
+        self.tab_backgrounds.read().get(&tab_id).map(Arc::clone)
+    }
+
     pub fn get_tab(&self, tab_id: TabId) -> Option<Arc<Tab>> {
         self.tabs.read().get(&tab_id).map(Arc::clone)
     }
@@ -815,6 +1058,11 @@ impl Mux {
         if let Some(pane) = self.panes.write().remove(&pane_id).clone() {
             log::debug!("killing pane {}", pane_id);
             pane.kill();
+            self.input_locked_panes.write().remove(&pane_id);
+            self.broadcast_excluded_panes.write().remove(&pane_id);
+            self.pane_monitors.write().remove(&pane_id);
+            self.pane_backgrounds.write().remove(&pane_id);
+            recording::stop_recording(pane_id);
             self.notify(MuxNotification::PaneRemoved(pane_id));
             changed = true;
         }
@@ -828,6 +1076,10 @@ impl Mux {
         log::debug!("remove_tab_internal tab {}", tab_id);
 
         let tab = self.tabs.write().remove(&tab_id)?;
+        self.tab_backgrounds.write().remove(&tab_id);
+        self.input_broadcasts
+            .write()
+            .remove(&InputBroadcast::Tab(tab_id));
 
         if let Some(mut windows) = self.windows.try_write() {
             for w in windows.values_mut() {
@@ -852,6 +1104,9 @@ impl Mux {
         log::debug!("remove_window_internal {}", window_id);
 
         let window = self.windows.write().remove(&window_id);
+        self.input_broadcasts
+            .write()
+            .remove(&InputBroadcast::Window(window_id));
         if let Some(window) = window {
             // Gather all the domains referenced by this window
             let mut domains_of_window = HashSet::new();
@@ -1016,6 +1271,16 @@ impl Mux {
         None
     }
 
+    /// Returns the workspace of `window_id`, which is the one that
+    /// things spawned into that window belong to.  Falls back to the
+    /// active workspace if there is no such window.
+    pub fn workspace_of_window(&self, window_id: WindowId) -> String {
+        match self.windows.read().get(&window_id) {
+            Some(window) => window.get_workspace().to_string(),
+            None => self.active_workspace(),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.panes.read().is_empty()
     }
@@ -1042,6 +1307,44 @@ impl Mux {
             .collect()
     }
 
+    /// Combines the progress reported by all of the panes: the mean
+    /// of their percentages, which is an error if any of them is
+    /// in an error state, or indeterminate if none of them has
+    /// reported a percentage
+    pub fn overall_progress(&self) -> Progress {
+        let mut total = 0usize;
+        let mut count = 0usize;
+        let mut error = false;
+        let mut busy = false;
+        for pane in self.iter_panes() {
+            match pane.get_progress() {
+                Progress::None => {}
+                Progress::Percentage(pct) => {
+                    total += pct as usize;
+                    count += 1;
+                }
+                Progress::Error(pct) => {
+                    total += pct as usize;
+                    count += 1;
+                    error = true;
+                }
+                Progress::Indeterminate => busy = true,
+            }
+        }
+        if count > 0 {
+            let pct = (total / count) as u8;
+            if error {
+                Progress::Error(pct)
+            } else {
+                Progress::Percentage(pct)
+            }
+        } else if busy {
+            Progress::Indeterminate
+        } else {
+            Progress::None
+        }
+    }
+
     pub fn iter_windows_in_workspace(&self, workspace: &str) -> Vec<WindowId> {
         let mut windows: Vec<WindowId> = self
             .windows
@@ -1151,6 +1454,23 @@ impl Mux {
         Ok(domain)
     }
 
+    /// Applies the `workspaces` settings configured for `workspace`
+    /// to a spawn that didn't explicitly pick a domain or a command.
+    /// The workspace `env` is added by the domain when it builds the
+    /// command.
+    /// Returns the cwd to fall back to when the spawn and the current
+    /// pane don't provide one.
+    fn apply_workspace_spawn_defaults(
+        workspace: &str,
+        pane_id: Option<PaneId>,
+        domain: &mut SpawnTabDomain,
+        command: &mut Option<CommandBuilder>,
+    ) -> Option<String> {
+        let config = configuration();
+        let defaults = config.workspace_settings(workspace)?;
+        apply_spawn_defaults(defaults, pane_id, domain, command)
+    }
+
     fn resolve_cwd(
         &self,
         command_dir: Option<String>,
@@ -1158,29 +1478,22 @@ impl Mux {
         target_domain: DomainId,
         policy: CachePolicy,
     ) -> Option<String> {
-        command_dir.or_else(|| {
-            match pane {
-                Some(pane) if pane.domain_id() == target_domain => pane
+        command_dir.or_else(|| match pane {
+            Some(pane) if pane.domain_id() == target_domain => pane
+                .get_current_working_dir(policy)
+                .and_then(|url| cwd_url_to_path(&url)),
+            Some(pane) => {
+                // A pane in an exec domain may be able to map the cwd
+                // inside of its container to one that makes sense
+                // elsewhere
+                let domain = self.get_domain(pane.domain_id())?;
+                let domain = domain.downcast_ref::<LocalDomain>()?;
+                let cwd = pane
                     .get_current_working_dir(policy)
-                    .and_then(|url| {
-                        percent_decode_str(url.path())
-                            .decode_utf8()
-                            .ok()
-                            .map(|path| path.into_owned())
-                    })
-                    .map(|path| {
-                        // On Windows the file URI can produce a path like:
-                        // `/C:\Users` which is valid in a file URI, but the leading slash
-                        // is not liked by the windows file APIs, so we strip it off here.
-                        let bytes = path.as_bytes();
-                        if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
-                            path[1..].to_owned()
-                        } else {
-                            path
-                        }
-                    }),
-                _ => None,
+                    .and_then(|url| cwd_url_to_path(&url))?;
+                domain.map_exec_domain_cwd(&cwd)
             }
+            None => None,
         })
     }
 
@@ -1196,6 +1509,33 @@ impl Mux {
             .resolve_pane_id(pane_id)
             .ok_or_else(|| anyhow!("pane_id {} invalid", pane_id))?;
 
+        let workspace = self
+            .get_window(window_id)
+            .map(|w| w.get_workspace().to_string())
+            .unwrap_or_else(|| self.active_workspace());
+        let mut domain = domain;
+        let (source, workspace_cwd) = match source {
+            SplitSource::Spawn {
+                mut command,
+                command_dir,
+            } => {
+                let workspace_cwd = Self::apply_workspace_spawn_defaults(
+                    &workspace,
+                    Some(pane_id),
+                    &mut domain,
+                    &mut command,
+                );
+                (
+                    SplitSource::Spawn {
+                        command,
+                        command_dir,
+                    },
+                    workspace_cwd,
+                )
+            }
+            other => (other, None),
+        };
+
         let domain = self
             .resolve_spawn_tab_domain(Some(pane_id), &domain)
             .context("resolve_spawn_tab_domain")?;
@@ -1215,12 +1555,14 @@ impl Mux {
                 command_dir,
             } => SplitSource::Spawn {
                 command,
-                command_dir: self.resolve_cwd(
-                    command_dir,
-                    Some(Arc::clone(&current_pane)),
-                    domain.domain_id(),
-                    CachePolicy::FetchImmediate,
-                ),
+                command_dir: self
+                    .resolve_cwd(
+                        command_dir,
+                        Some(Arc::clone(&current_pane)),
+                        domain.domain_id(),
+                        CachePolicy::FetchImmediate,
+                    )
+                    .or(workspace_cwd),
             },
             other => other,
         };
@@ -1304,6 +1646,83 @@ impl Mux {
         Ok((tab, window_id))
     }
 
+    /// Moves a tab out of its window, appending it to `window_id`,
+    /// or to a new window in the same workspace placed at `position`
+    /// if `window_id` is None.  A source window left without tabs
+    /// is pruned.
+    /// Only tabs whose panes all belong to local domains can be moved;
+    /// the layout of the tabs of a remote domain belongs to its server,
+    /// which would undo the move.
+    pub fn move_tab_to_window(
+        &self,
+        tab_id: TabId,
+        window_id: Option<WindowId>,
+        position: Option<GuiPosition>,
+    ) -> anyhow::Result<WindowId> {
+        let src_window_id = self
+            .window_containing_tab(tab_id)
+            .ok_or_else(|| anyhow!("tab {} is not in any window", tab_id))?;
+        if window_id == Some(src_window_id) {
+            return Ok(src_window_id);
+        }
+
+        let src_tab = self
+            .get_tab(tab_id)
+            .ok_or_else(|| anyhow!("tab {} not found", tab_id))?;
+        for pos in src_tab.iter_panes_ignoring_zoom() {
+            let domain_id = pos.pane.domain_id();
+            let is_local = self.get_domain(domain_id).map_or(false, |domain| {
+                domain.downcast_ref::<LocalDomain>().is_some()
+            });
+            if !is_local {
+                anyhow::bail!(
+                    "tab {} has panes in domain {}, which is not local",
+                    tab_id,
+                    domain_id
+                );
+            }
+        }
+
+        let window_builder;
+        let window_id = match window_id {
+            Some(window_id) => {
+                if self.get_window(window_id).is_none() {
+                    anyhow::bail!("window_id {} not found on this server", window_id);
+                }
+                window_id
+            }
+            None => {
+                let workspace = self
+                    .get_window(src_window_id)
+                    .map(|w| w.get_workspace().to_string());
+                window_builder = self.new_empty_window(workspace, position);
+                *window_builder
+            }
+        };
+
+        let (tab, idx) = {
+            let mut window = self
+                .get_window_mut(src_window_id)
+                .ok_or_else(|| anyhow!("window_id {} not found", src_window_id))?;
+            let idx = window
+                .idx_by_id(tab_id)
+                .ok_or_else(|| anyhow!("tab {} wasn't in its window!?", tab_id))?;
+            (window.remove_by_idx(idx), idx)
+        };
+
+        if let Err(err) = self.add_tab_to_window(&tab, window_id) {
+            // Put the tab back where it came from rather than losing it
+            if let Some(mut window) = self.get_window_mut(src_window_id) {
+                window.insert(idx, &tab);
+            }
+            return Err(err);
+        }
+        self.notify(MuxNotification::WindowInvalidated(src_window_id));
+        self.prune_dead_windows();
+
+        Ok(window_id)
+    }
+
     pub async fn spawn_tab_or_window(
         &self,
         window_id: Option<WindowId>,
@@ -1315,6 +1734,19 @@ impl Mux {
         workspace_for_new_window: String,
         window_position: Option<GuiPosition>,
     ) -> anyhow::Result<(Arc<Tab>, Arc<dyn Pane>, WindowId)> {
+        let workspace = window_id
+            .and_then(|id| self.get_window(id))
+            .map(|w| w.get_workspace().to_string())
+            .unwrap_or_else(|| workspace_for_new_window.clone());
+        let mut domain = domain;
+        let mut command = command;
+        let workspace_cwd = Self::apply_workspace_spawn_defaults(
+            &workspace,
+            current_pane_id,
+            &mut domain,
+            &mut command,
+        );
+
         let domain = self
             .resolve_spawn_tab_domain(current_pane_id, &domain)
             .context("resolve_spawn_tab_domain")?;
@@ -1370,6 +1802,7 @@ impl Mux {
             domain.domain_id(),
             CachePolicy::FetchImmediate,
         );
+        let cwd = cwd.or(workspace_cwd);
 
         let tab = domain
             .spawn(size, command.clone(), cwd.clone(), window_id)
@@ -1467,3 +1900,228 @@ impl wezterm_term::DownloadHandler for MuxDownloader {
         }
     }
 }
+
+/// Applies `defaults` to a spawn that didn't explicitly pick a domain
+/// or a command, returning the cwd that `defaults` falls back to
+fn apply_spawn_defaults(
+    defaults: &WorkspaceSpawnDefaults,
+    pane_id: Option<PaneId>,
+    domain: &mut SpawnTabDomain,
+    command: &mut Option<CommandBuilder>,
+) -> Option<String> {
+    let uses_default_domain = match domain {
+        SpawnTabDomain::DefaultDomain => true,
+        SpawnTabDomain::CurrentPaneDomain => pane_id.is_none(),
+        _ => false,
+    };
+    if uses_default_domain {
+        if let Some(name) = &defaults.default_domain {
+            *domain = SpawnTabDomain::DomainName(name.clone());
+        }
+    }
+
+    if let Some(prog) = &defaults.default_prog {
+        match command {
+            Some(cmd) if cmd.is_default_prog() => cmd.replace_default_prog(prog),
+            Some(_) => {}
+            None => {
+                *command = Some(CommandBuilder::from_argv(
+                    prog.iter().map(Into::into).collect(),
+                ))
+            }
+        }
+    }
+
+    defaults
+        .default_cwd
+        .as_ref()
+        .and_then(|cwd| cwd.to_str())
+        .map(|cwd| cwd.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tab::test::FakePane;
+
+    fn size() -> TerminalSize {
+        TerminalSize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 800,
+            pixel_height: 600,
+            dpi: 96,
+        }
+    }
+
+    fn add_window(mux: &Mux, workspace: &str) -> WindowId {
+        let window = Window::new(Some(workspace.to_string()), None);
+        let window_id = window.window_id();
+        mux.windows.write().insert(window_id, window);
+        window_id
+    }
+
+    /// Adds a tab holding a pane for each of `pane_ids` to `window_id`
+    fn add_tab(mux: &Mux, window_id: WindowId, pane_ids: &[PaneId]) -> TabId {
+        let tab = Arc::new(Tab::new(&size()));
+        tab.assign_pane(&FakePane::new(pane_ids[0], size()));
+        for &pane_id in &pane_ids[1..] {
+            tab.split_and_insert(0, SplitRequest::default(), FakePane::new(pane_id, size()))
+                .unwrap();
+        }
+        mux.add_tab_no_panes(&tab);
+        for pos in tab.iter_panes_ignoring_zoom() {
+            mux.add_pane(&pos.pane).unwrap();
+        }
+        mux.add_tab_to_window(&tab, window_id).unwrap();
+        tab.tab_id()
+    }
+
+    fn target_ids(mux: &Mux, pane_id: PaneId) -> Vec<PaneId> {
+        let mut ids: Vec<PaneId> = mux
+            .input_broadcast_targets(pane_id)
+            .iter()
+            .map(|pane| pane.pane_id())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn input_broadcast_targets() {
+        config::use_test_configuration();
+        let mux = Mux::new(None);
+        let work = add_window(&mux, "work");
+        let work_tab = add_tab(&mux, work, &[1001, 1002, 1003]);
+        add_tab(&mux, work, &[1004]);
+        let other = add_window(&mux, "work");
+        add_tab(&mux, other, &[1005]);
+        let home = add_window(&mux, "home");
+        add_tab(&mux, home, &[1006]);
+
+        assert_eq!(target_ids(&mux, 1001), Vec::<PaneId>::new());
+
+        mux.toggle_input_broadcast(InputBroadcast::Tab(work_tab));
+        assert_eq!(target_ids(&mux, 1001), vec![1002, 1003]);
+        // Panes outside of the tab neither send nor receive
+        assert_eq!(target_ids(&mux, 1004), Vec::<PaneId>::new());
+        mux.toggle_input_broadcast(InputBroadcast::Tab(work_tab));
+        assert_eq!(target_ids(&mux, 1001), Vec::<PaneId>::new());
+
+        mux.toggle_input_broadcast(InputBroadcast::Window(work));
+        assert_eq!(target_ids(&mux, 1004), vec![1001, 1002, 1003]);
+        mux.toggle_input_broadcast(InputBroadcast::Window(work));
+
+        mux.toggle_input_broadcast(InputBroadcast::Workspace("work".to_string()));
+        assert_eq!(target_ids(&mux, 1005), vec![1001, 1002, 1003, 1004]);
+        assert_eq!(target_ids(&mux, 1006), Vec::<PaneId>::new());
+
+        // Excluded panes neither send nor receive, and locked
+        // panes don't receive
+        mux.set_pane_broadcast_excluded(1002, true);
+        mux.set_pane_input_locked(1003, true);
+        assert_eq!(target_ids(&mux, 1005), vec![1001, 1004]);
+        assert_eq!(target_ids(&mux, 1002), Vec::<PaneId>::new());
+        assert!(!mux.is_pane_broadcasting_input(1002));
+        assert!(mux.is_pane_broadcasting_input(1005));
+    }
+
+    #[test]
+    fn workspace_of_inactive_window() {
+        config::use_test_configuration();
+        let mux = Mux::new(None);
+        let work = add_window(&mux, "work");
+        let work_tab = add_tab(&mux, work, &[1001]);
+        assert_eq!(mux.active_workspace(), DEFAULT_WORKSPACE);
+
+        // A spawn into a window of another workspace belongs to
+        // that workspace, not the active one
+        assert_eq!(mux.workspace_of_window(work), "work");
+        let window = mux.window_containing_tab(work_tab).unwrap();
+        assert_eq!(mux.workspace_of_window(window), "work");
+        assert_eq!(mux.workspace_of_window(work + 100), DEFAULT_WORKSPACE);
+    }
+
+    fn workspace_defaults() -> WorkspaceSpawnDefaults {
+        WorkspaceSpawnDefaults {
+            default_domain: Some("SSHMUX:build".to_string()),
+            default_prog: Some(vec!["zsh".to_string(), "-l".to_string()]),
+            default_cwd: Some("/srv/work".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spawn_defaults_domain() {
+        let defaults = workspace_defaults();
+        let build = SpawnTabDomain::DomainName("SSHMUX:build".to_string());
+
+        let mut domain = SpawnTabDomain::DefaultDomain;
+        apply_spawn_defaults(&defaults, Some(1), &mut domain, &mut None);
+        assert_eq!(domain, build);
+
+        // A new window has no current pane to take the domain from
+        let mut domain = SpawnTabDomain::CurrentPaneDomain;
+        apply_spawn_defaults(&defaults, None, &mut domain, &mut None);
+        assert_eq!(domain, build);
+
+        let mut domain = SpawnTabDomain::CurrentPaneDomain;
+        apply_spawn_defaults(&defaults, Some(1), &mut domain, &mut None);
+        assert_eq!(domain, SpawnTabDomain::CurrentPaneDomain);
+
+        let mut domain = SpawnTabDomain::DomainName("local".to_string());
+        apply_spawn_defaults(&defaults, None, &mut domain, &mut None);
+        assert_eq!(domain, SpawnTabDomain::DomainName("local".to_string()));
+    }
+
+    #[test]
+    fn spawn_defaults_prog_and_cwd() {
+        let defaults = workspace_defaults();
+        let argv = |command: &Option<CommandBuilder>| -> Vec<String> {
+            command
+                .as_ref()
+                .unwrap()
+                .get_argv()
+                .iter()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+
+        let mut command = None;
+        let cwd = apply_spawn_defaults(
+            &defaults,
+            None,
+            &mut SpawnTabDomain::DefaultDomain,
+            &mut command,
+        );
+        assert_eq!(argv(&command), vec!["zsh", "-l"]);
+        assert_eq!(cwd.as_deref(), Some("/srv/work"));
+
+        let mut command = Some(CommandBuilder::new_default_prog());
+        apply_spawn_defaults(
+            &defaults,
+            None,
+            &mut SpawnTabDomain::DefaultDomain,
+            &mut command,
+        );
+        assert_eq!(argv(&command), vec!["zsh", "-l"]);
+
+        // An explicit command is left alone
+        let mut command = Some(CommandBuilder::new("htop"));
+        apply_spawn_defaults(
+            &defaults,
+            None,
+            &mut SpawnTabDomain::DefaultDomain,
+            &mut command,
+        );
+        assert_eq!(argv(&command), vec!["htop"]);
+
+        let cwd = apply_spawn_defaults(
+            &WorkspaceSpawnDefaults::default(),
+            None,
+            &mut SpawnTabDomain::DefaultDomain,
+            &mut None,
+        );
+        assert_eq!(cwd, None);
+    }
+}
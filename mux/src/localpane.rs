@@ -1,4 +1,5 @@
 use crate::domain::DomainId;
+use crate::exit_summary::{wait_for_child, PaneExitSummary, ResourceUsage};
 use crate::pane::{
     CachePolicy, CloseReason, ForEachPaneLogicalLine, LogicalLine, Pane, PaneId, Pattern,
     SearchResult, WithPaneLines,
@@ -21,6 +22,7 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
 use std::io::{Result as IoResult, Write};
 use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use termwiz::escape::csi::{Sgr, CSI};
@@ -36,12 +38,15 @@ use wezterm_term::{
 };
 
 const PROC_INFO_CACHE_TTL: Duration = Duration::from_millis(300);
+/// How many lines of scrollback to rewrap at a time after a resize
+const REFLOW_BATCH_LINES: usize = 2000;
 
 #[derive(Debug)]
 enum ProcessState {
     Running {
-        child_waiter: Receiver<IoResult<ExitStatus>>,
+        child_waiter: Receiver<IoResult<(ExitStatus, Option<ResourceUsage>)>>,
         pid: Option<u32>,
+        started: Instant,
         signaller: Box<dyn ChildKiller + Sync>,
         // Whether we've explicitly killed the child
         killed: bool,
@@ -58,6 +63,25 @@ struct CachedProcInfo {
     foreground: LocalProcessInfo,
 }
 
+/// Emits the `pane-exited` event so that the config can log or
+/// otherwise react to the exit summary
+fn emit_pane_exited_event(summary: PaneExitSummary) {
+    promise::spawn::spawn_into_main_thread(async move {
+        let result = config::with_lua_config_on_main_thread(move |lua| async move {
+            if let Some(lua) = lua {
+                let args = lua.pack_multi(summary)?;
+                config::lua::emit_event(&lua, ("pane-exited".to_string(), args)).await?;
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(err) = result {
+            log::error!("while processing pane-exited event: {err:#}");
+        }
+    })
+    .detach();
+}
+
 /// This is a bit horrible; it can take 700us to tcgetpgrp, so if we have
 /// 10 tabs open and run the mouse over them, hovering them each in turn,
 /// we can spend 7ms per evaluation of the tab bar state on fetching those
@@ -133,6 +157,12 @@ pub struct LocalPane {
     #[cfg(unix)]
     leader: Arc<Mutex<Option<CachedLeaderInfo>>>,
     command_description: String,
+    /// true while a task is rewrapping scrollback after a resize
+    reflow_scheduled: AtomicBool,
+    /// The `kill(2)` targets stopped by `pause_processes`, in the
+    /// order in which they were stopped
+    #[cfg(unix)]
+    paused: Mutex<Vec<libc::pid_t>>,
 }
 
 #[async_trait(?Send)]
@@ -273,27 +303,39 @@ impl Pane for LocalPane {
         let mut terse = String::new();
         let mut brief = String::new();
         let mut trailer = String::new();
+        let mut exited = None;
+        let mut summary = None;
         let cmd = &self.command_description;
 
         match &mut *proc {
             ProcessState::Running {
                 child_waiter,
                 killed,
+                started,
                 ..
             } => {
                 let status = match child_waiter.try_recv() {
                     Ok(Ok(s)) => Some(s),
                     Err(TryRecvError::Empty) => None,
-                    _ => Some(ExitStatus::with_exit_code(1)),
+                    _ => Some((ExitStatus::with_exit_code(1), None)),
                 };
 
-                if let Some(status) = status {
+                if let Some((status, usage)) = status {
+                    exited.replace(status.clone());
                     let success = match status.success() {
                         true => true,
                         false => configuration()
                             .clean_exit_codes
                             .contains(&status.exit_code()),
                     };
+                    summary.replace(PaneExitSummary::new(
+                        self.pane_id,
+                        cmd,
+                        &status,
+                        success,
+                        started.elapsed(),
+                        usage,
+                    ));
 
                     match (
                         self.exit_behavior()
@@ -360,15 +402,38 @@ impl Pane for LocalPane {
             }
         }
 
+        if let Some(summary) = &summary {
+            if configuration().pane_exit_summary {
+                let line = format!("\r\n[{}]", summary.summary_line());
+                notify = Some(match notify {
+                    Some(notify) => format!("{notify}{line}"),
+                    None => line,
+                });
+            }
+        }
+
         if let Some(notify) = notify {
             emit_output_for_pane(self.pane_id, &notify);
         }
 
-        match &*proc {
+        let dead = match &*proc {
             ProcessState::Running { .. } => false,
             ProcessState::DeadPendingClose { .. } => false,
             ProcessState::Dead => true,
+        };
+        drop(proc);
+
+        if let Some(status) = exited {
+            Mux::notify_from_any_thread(MuxNotification::PaneProcessExited {
+                pane_id: self.pane_id,
+                status,
+            });
+        }
+        if let Some(summary) = summary {
+            emit_pane_exited_event(summary);
         }
+
+        dead
     }
 
     fn set_clipboard(&self, clipboard: &Arc<dyn Clipboard>) {
@@ -422,11 +487,13 @@ impl Pane for LocalPane {
             pixel_height: size.pixel_height.try_into()?,
         })?;
         self.terminal.lock().resize(size);
+        self.schedule_reflow();
         Ok(())
     }
 
     fn resize_visual(&self, size: TerminalSize) -> Result<(), Error> {
         self.terminal.lock().resize(size);
+        self.schedule_reflow();
         Ok(())
     }
 
@@ -514,6 +581,14 @@ impl Pane for LocalPane {
         }
     }
 
+    fn is_bracketed_paste_enabled(&self) -> Option<bool> {
+        if self.tmux_domain.lock().is_some() {
+            None
+        } else {
+            Some(self.terminal.lock().bracketed_paste_enabled())
+        }
+    }
+
     fn is_primary_peek(&self) -> bool {
         self.terminal.lock().is_primary_peek()
     }
@@ -543,6 +618,13 @@ impl Pane for LocalPane {
         }
     }
 
+    fn process_id(&self) -> Option<u32> {
+        match &*self.process.lock() {
+            ProcessState::Running { pid, .. } => *pid,
+            _ => None,
+        }
+    }
+
     fn get_foreground_process_info(&self, policy: CachePolicy) -> Option<LocalProcessInfo> {
         #[cfg(unix)]
         if let Some(pid) = self.pty.lock().process_group_leader() {
@@ -975,7 +1057,7 @@ impl AlertHandler for LocalPaneNotifHandler {
 fn split_child(
     mut process: Box<dyn Child>,
 ) -> (
-    Receiver<IoResult<ExitStatus>>,
+    Receiver<IoResult<(ExitStatus, Option<ResourceUsage>)>>,
     Box<dyn ChildKiller + Sync>,
     Option<u32>,
 ) {
@@ -985,7 +1067,7 @@ fn split_child(
     let (tx, rx) = bounded(1);
 
     std::thread::spawn(move || {
-        let status = process.wait();
+        let status = wait_for_child(&mut process);
         tx.try_send(status).ok();
         promise::spawn::spawn_into_main_thread(async move {
             let mux = Mux::get();
@@ -1021,6 +1103,7 @@ impl LocalPane {
             process: Mutex::new(ProcessState::Running {
                 child_waiter: process,
                 pid,
+                started: Instant::now(),
                 signaller,
                 killed: false,
             }),
@@ -1032,9 +1115,93 @@ impl LocalPane {
             #[cfg(unix)]
             leader: Arc::new(Mutex::new(None)),
             command_description,
+            reflow_scheduled: AtomicBool::new(false),
+            #[cfg(unix)]
+            paused: Mutex::new(vec![]),
         }
     }
 
+    /// Stops the shell and the foreground process group of this pane
+    /// until `resume_processes` is called.
+    #[cfg(unix)]
+    pub fn pause_processes(&self) {
+        let mut paused = self.paused.lock();
+        if !paused.is_empty() {
+            return;
+        }
+        let shell = match &*self.process.lock() {
+            ProcessState::Running { pid: Some(pid), .. } => Some(*pid as libc::pid_t),
+            _ => None,
+        };
+        let group = self.pty.lock().process_group_leader();
+
+        for target in pause_targets(shell, group) {
+            if unsafe { libc::kill(target, libc::SIGSTOP) } == 0 {
+                paused.push(target);
+            } else {
+                log::warn!(
+                    "pane {}: failed to stop {}: {}",
+                    self.pane_id,
+                    target,
+                    std::io::Error::last_os_error()
+                );
+            }
+        }
+    }
+
+    /// Continues the processes stopped by `pause_processes`
+    #[cfg(unix)]
+    pub fn resume_processes(&self) {
+        let mut paused = self.paused.lock();
+        // Continue the foreground job before the shell, for the same
+        // reason that the shell was stopped first
+        for target in paused.drain(..).rev() {
+            unsafe {
+                libc::kill(target, libc::SIGCONT);
+            }
+        }
+    }
+
+    /// Resizing a pane with a large scrollback only rewraps the lines
+    /// near the viewport; this arranges for the remainder to be
+    /// rewrapped in small batches on the main thread, so that neither
+    /// the resize nor the rendering of the pane stalls.
+    fn schedule_reflow(&self) {
+        if !self.terminal.lock().primary_screen().has_pending_reflow()
+            || self.reflow_scheduled.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        let pane_id = self.pane_id;
+        promise::spawn::spawn_into_main_thread(async move {
+            loop {
+                let more = match Mux::get().get_pane(pane_id) {
+                    Some(pane) => match pane.downcast_ref::<LocalPane>() {
+                        Some(local) => {
+                            let more = local
+                                .terminal
+                                .lock()
+                                .reflow_pending_lines(REFLOW_BATCH_LINES);
+                            if !more {
+                                local.reflow_scheduled.store(false, Ordering::SeqCst);
+                            }
+                            more
+                        }
+                        None => false,
+                    },
+                    None => false,
+                };
+                Mux::get().notify(MuxNotification::PaneOutput(pane_id));
+                if !more {
+                    break;
+                }
+                // Yield so that input and rendering can proceed
+                smol::Timer::after(Duration::from_millis(1)).await;
+            }
+        })
+        .detach();
+    }
+
     #[cfg(unix)]
     fn get_leader(&self, policy: CachePolicy) -> CachedLeaderInfo {
         let mut leader = self.leader.lock();
@@ -1156,3 +1323,33 @@ impl Drop for LocalPane {
         }
     }
 }
+
+/// Returns the `kill(2)` targets that stop the processes of a pane:
+/// the `shell` comes first, so that it cannot observe its foreground
+/// job being stopped and take back the terminal, followed by the
+/// foreground process `group` unless that is led by the shell itself
+#[cfg(unix)]
+fn pause_targets(shell: Option<libc::pid_t>, group: Option<libc::pid_t>) -> Vec<libc::pid_t> {
+    let mut targets = vec![];
+    targets.extend(shell);
+    if let Some(group) = group {
+        if Some(group) != shell {
+            targets.push(-group);
+        }
+    }
+    targets
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pause_shell_before_foreground_job() {
+        assert_eq!(pause_targets(Some(100), Some(200)), vec![100, -200]);
+        // Only the shell is running
+        assert_eq!(pause_targets(Some(100), Some(100)), vec![100]);
+        assert_eq!(pause_targets(None, Some(200)), vec![-200]);
+        assert_eq!(pause_targets(Some(100), None), vec![100]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Watches panes for output activity, or for a lack of it, so that
+//! the user can be alerted when a long running command makes
+//! progress or goes quiet; see `TogglePaneMonitor`
+use crate::pane::PaneId;
+use crate::{Mux, MuxNotification};
+use config::keyassignment::PaneMonitor;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+pub(crate) struct MonitorState {
+    monitor: PaneMonitor,
+    last_output: Instant,
+    /// Set when the monitor fires, and cleared once the user has
+    /// looked at the pane again.  The monitor won't fire again
+    /// until then.
+    triggered: bool,
+}
+
+impl MonitorState {
+    fn new(monitor: PaneMonitor, now: Instant) -> Self {
+        Self {
+            monitor,
+            last_output: now,
+            triggered: false,
+        }
+    }
+
+    /// Records output at `now`, returning true if that fires the monitor
+    fn record_output(&mut self, now: Instant) -> bool {
+        self.last_output = now;
+        match self.monitor {
+            PaneMonitor::Activity if !self.triggered => {
+                self.triggered = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if a silence monitor fires at `now`
+    fn check_silence(&mut self, now: Instant) -> bool {
+        match self.monitor {
+            PaneMonitor::Silence(seconds)
+                if !self.triggered
+                    && now.duration_since(self.last_output) >= Duration::from_secs(seconds) =>
+            {
+                self.triggered = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Re-arms the monitor once the user has seen the pane at `now`
+    fn acknowledge(&mut self, now: Instant) {
+        self.triggered = false;
+        self.last_output = now;
+    }
+}
+
+impl Mux {
+    /// Starts watching `pane_id`, replacing any existing monitor,
+    /// or stops watching it when `monitor` is None
+    pub fn set_pane_monitor(&self, pane_id: PaneId, monitor: Option<PaneMonitor>) {
+        let start_checker = {
+            let mut monitors = self.pane_monitors.write();
+            match monitor {
+                Some(monitor) => {
+                    monitors.insert(pane_id, MonitorState::new(monitor, Instant::now()));
+                }
+                None => {
+                    monitors.remove(&pane_id);
+                }
+            }
+            // The flag is only cleared by the checker while it holds
+            // the lock, so this can't race with it shutting down
+            matches!(monitor, Some(PaneMonitor::Silence(_)))
+                && !self.silence_checker_running.swap(true, Ordering::SeqCst)
+        };
+        if start_checker {
+            std::thread::spawn(check_for_silence);
+        }
+        if let Some((_domain_id, window_id, _tab_id)) = self.resolve_pane_id(pane_id) {
+            self.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+    }
+
+    pub fn get_pane_monitor(&self, pane_id: PaneId) -> Option<PaneMonitor> {
+        self.pane_monitors
+            .read()
+            .get(&pane_id)
+            .map(|state| state.monitor)
+    }
+
+    /// Returns true if the monitor on `pane_id` has fired and the
+    /// pane hasn't been seen since
+    pub fn is_pane_monitor_triggered(&self, pane_id: PaneId) -> bool {
+        self.pane_monitors
+            .read()
+            .get(&pane_id)
+            .map(|state| state.triggered)
+            .unwrap_or(false)
+    }
+
+    /// Clears the triggered state of the monitor on `pane_id`;
+    /// called when the pane is seen by the user
+    pub fn acknowledge_pane_monitor(&self, pane_id: PaneId) {
+        if !self.is_pane_monitor_triggered(pane_id) {
+            return;
+        }
+        if let Some(state) = self.pane_monitors.write().get_mut(&pane_id) {
+            state.acknowledge(Instant::now());
+        }
+        if let Some((_domain_id, window_id, _tab_id)) = self.resolve_pane_id(pane_id) {
+            self.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+    }
+
+    /// Called each time that a pane produces output: from the output
+    /// processing thread of a local pane, or as changes to a remote
+    /// pane are received from the server
+    pub fn record_pane_monitor_output(&self, pane_id: PaneId) {
+        if !self.pane_monitors.read().contains_key(&pane_id) {
+            return;
+        }
+        let fired = {
+            let mut monitors = self.pane_monitors.write();
+            let Some(state) = monitors.get_mut(&pane_id) else {
+                return;
+            };
+            state.record_output(Instant::now()).then_some(state.monitor)
+        };
+        if let Some(monitor) = fired {
+            Mux::notify_from_any_thread(MuxNotification::PaneMonitorTriggered { pane_id, monitor });
+        }
+    }
+}
+
+/// Runs on a background thread while any pane is being watched
+/// for silence
+fn check_for_silence() {
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+        let Some(mux) = Mux::try_get() else {
+            return;
+        };
+
+        let mut fired = vec![];
+        {
+            let mut monitors = mux.pane_monitors.write();
+            let mut watching = false;
+            let now = Instant::now();
+            for (pane_id, state) in monitors.iter_mut() {
+                if let PaneMonitor::Silence(_) = state.monitor {
+                    watching = true;
+                    if state.check_silence(now) {
+                        fired.push((*pane_id, state.monitor));
+                    }
+                }
+            }
+            if !watching {
+                mux.silence_checker_running.store(false, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        for (pane_id, monitor) in fired {
+            Mux::notify_from_any_thread(MuxNotification::PaneMonitorTriggered { pane_id, monitor });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn activity_fires_once_until_acknowledged() {
+        let start = Instant::now();
+        let mut state = MonitorState::new(PaneMonitor::Activity, start);
+        assert!(!state.check_silence(start + Duration::from_secs(3600)));
+        assert!(state.record_output(start));
+        assert!(!state.record_output(start + Duration::from_secs(1)));
+
+        state.acknowledge(start + Duration::from_secs(2));
+        assert!(state.record_output(start + Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn silence_fires_after_quiet_period() {
+        let start = Instant::now();
+        let mut state = MonitorState::new(PaneMonitor::Silence(10), start);
+        assert!(!state.record_output(start + Duration::from_secs(5)));
+        // Output restarts the quiet period
+        assert!(!state.check_silence(start + Duration::from_secs(12)));
+        assert!(state.check_silence(start + Duration::from_secs(15)));
+        assert!(!state.check_silence(start + Duration::from_secs(30)));
+
+        state.acknowledge(start + Duration::from_secs(30));
+        assert!(!state.check_silence(start + Duration::from_secs(35)));
+        assert!(state.check_silence(start + Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn monitors_are_per_pane() {
+        let mux = Mux::new(None);
+        assert_eq!(mux.get_pane_monitor(1), None);
+        mux.set_pane_monitor(1, Some(PaneMonitor::Activity));
+        assert_eq!(mux.get_pane_monitor(1), Some(PaneMonitor::Activity));
+        assert_eq!(mux.get_pane_monitor(2), None);
+        assert!(!mux.is_pane_monitor_triggered(1));
+
+        // Output from an unwatched pane is ignored
+        mux.record_pane_monitor_output(2);
+        assert!(!mux.is_pane_monitor_triggered(2));
+
+        mux.set_pane_monitor(1, None);
+        assert_eq!(mux.get_pane_monitor(1), None);
+    }
+}
@@ -0,0 +1,415 @@
+//! Applies the configured `output_filters` to the actions parsed
+//! from a pane's output, before they reach the terminal model.
+//! Filters run on the pane's reader thread, so they operate on
+//! whatever has been read so far and never hold output back
+//! waiting for more data.  Text is matched in runs of at most
+//! `output_filter_max_text_bytes`, and filtering is disabled for a
+//! pane if a batch takes longer than `output_filter_time_budget_ms`.
+//! Only the built-in filters are available: the Lua state lives on
+//! the main thread, so calling into it for each read would stall
+//! the reader.
+use anyhow::bail;
+use chrono::format::{Item, StrftimeItems};
+use config::OutputFilter;
+use fancy_regex::Regex;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use termwiz::escape::osc::Progress;
+use termwiz::escape::{Action, ControlCode, OperatingSystemCommand};
+
+enum Filter {
+    StripEscapes,
+    Mask {
+        regex: Regex,
+        replacement: String,
+        max_text: usize,
+    },
+    Timestamp {
+        format: String,
+        at_line_start: bool,
+    },
+    Progress {
+        regex: Regex,
+        last: Option<u8>,
+        max_text: usize,
+    },
+}
+
+/// Appends the text of `action` to `text`, returning false if
+/// `action` isn't text
+fn push_text(text: &mut String, action: &Action) -> bool {
+    match action {
+        Action::Print(c) => text.push(*c),
+        Action::PrintString(s) => text.push_str(s),
+        _ => return false,
+    }
+    true
+}
+
+/// Extracts a percentage from the captures of an
+/// `OutputFilter::Progress` regex
+fn captured_percentage(caps: &fancy_regex::Captures) -> Option<u8> {
+    let number = |m: Option<fancy_regex::Match>| m?.as_str().parse::<f64>().ok();
+    let pct = match (number(caps.name("done")), number(caps.name("total"))) {
+        (Some(done), Some(total)) if total > 0. => done * 100. / total,
+        _ => number(caps.get(1))?,
+    };
+    Some(pct.clamp(0., 100.) as u8)
+}
+
+impl Filter {
+    /// `max_text` bounds the amount of text that is matched at once
+    fn new(filter: &OutputFilter, max_text: usize) -> anyhow::Result<Self> {
+        Ok(match filter {
+            OutputFilter::StripEscapes => Self::StripEscapes,
+            OutputFilter::Mask { regex, replacement } => Self::Mask {
+                regex: Regex::new(regex)?,
+                replacement: replacement.clone(),
+                max_text,
+            },
+            OutputFilter::Timestamp { format } => {
+                // Formatting with an invalid item fails, which would
+                // otherwise panic the reader thread
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    bail!("invalid strftime format {:?}", format);
+                }
+                Self::Timestamp {
+                    format: format.clone(),
+                    at_line_start: true,
+                }
+            }
+            OutputFilter::Progress { regex } => Self::Progress {
+                regex: Regex::new(regex)?,
+                last: None,
+                max_text,
+            },
+        })
+    }
+
+    fn apply(&mut self, actions: Vec<Action>) -> Vec<Action> {
+        match self {
+            Self::StripEscapes => actions
+                .into_iter()
+                .filter(|action| match action {
+                    Action::Print(_) | Action::PrintString(_) => true,
+                    Action::Control(code) => matches!(
+                        code,
+                        ControlCode::LineFeed
+                            | ControlCode::CarriageReturn
+                            | ControlCode::HorizontalTab
+                            | ControlCode::Backspace
+                    ),
+                    _ => false,
+                })
+                .collect(),
+            Self::Mask {
+                regex,
+                replacement,
+                max_text,
+            } => {
+                let mut result = Vec::with_capacity(actions.len());
+                let mut text = String::new();
+
+                let flush = |text: &mut String, result: &mut Vec<Action>| {
+                    if !text.is_empty() {
+                        let masked = regex.replace_all(text, replacement.as_str());
+                        result.push(Action::PrintString(masked.into_owned()));
+                        text.clear();
+                    }
+                };
+
+                for action in actions {
+                    if push_text(&mut text, &action) {
+                        if text.len() >= *max_text {
+                            flush(&mut text, &mut result);
+                        }
+                    } else {
+                        flush(&mut text, &mut result);
+                        result.push(action);
+                    }
+                }
+                flush(&mut text, &mut result);
+                result
+            }
+            Self::Timestamp {
+                format,
+                at_line_start,
+            } => {
+                let mut result = Vec::with_capacity(actions.len());
+                for action in actions {
+                    match &action {
+                        Action::Print(_) | Action::PrintString(_) if *at_line_start => {
+                            let mut stamp = String::new();
+                            write!(stamp, "{}", chrono::Local::now().format(format)).ok();
+                            result.push(Action::PrintString(stamp));
+                            *at_line_start = false;
+                        }
+                        Action::Control(ControlCode::LineFeed) => *at_line_start = true,
+                        _ => {}
+                    }
+                    result.push(action);
+                }
+                result
+            }
+            Self::Progress {
+                regex,
+                last,
+                max_text,
+            } => {
+                let mut result = Vec::with_capacity(actions.len());
+                let mut text = String::new();
+
+                let mut scan = |text: &mut String, result: &mut Vec<Action>| {
+                    let pct = regex
+                        .captures_iter(text)
+                        .filter_map(|caps| caps.ok())
+                        .filter_map(|caps| captured_percentage(&caps))
+                        .last();
+                    text.clear();
+                    if let Some(pct) = pct {
+                        if *last != Some(pct) {
+                            *last = Some(pct);
+                            let progress = if pct >= 100 {
+                                Progress::None
+                            } else {
+                                Progress::SetPercentage(pct)
+                            };
+                            result.push(Action::OperatingSystemCommand(Box::new(
+                                OperatingSystemCommand::ConEmuProgress(progress),
+                            )));
+                        }
+                    }
+                };
+
+                for action in actions {
+                    if !push_text(&mut text, &action) || text.len() >= *max_text {
+                        scan(&mut text, &mut result);
+                    }
+                    result.push(action);
+                }
+                scan(&mut text, &mut result);
+                result
+            }
+        }
+    }
+}
+
+/// The chain of filters for a single pane
+pub struct OutputFilters {
+    filters: Vec<Filter>,
+    /// The config that `filters` was built from
+    config: Vec<OutputFilter>,
+    generation: usize,
+    budget: Duration,
+    disabled: bool,
+}
+
+impl OutputFilters {
+    pub fn new() -> Self {
+        let config = config::configuration();
+        let filters = config
+            .output_filters
+            .iter()
+            .filter_map(
+                |filter| match Filter::new(filter, config.output_filter_max_text_bytes) {
+                    Ok(filter) => Some(filter),
+                    Err(err) => {
+                        log::error!("ignoring output filter {:?}: {:#}", filter, err);
+                        None
+                    }
+                },
+            )
+            .collect();
+        Self {
+            filters,
+            config: config.output_filters.clone(),
+            generation: config.generation(),
+            budget: Duration::from_millis(config.output_filter_time_budget_ms),
+            disabled: false,
+        }
+    }
+
+    /// Rebuild the chain if the configuration has been reloaded.
+    /// Filters that were disabled for being too slow stay disabled
+    /// unless `output_filters` itself has changed.
+    pub fn update_config(&mut self) {
+        if config::configuration().generation() != self.generation {
+            let disabled = self.disabled;
+            let config = std::mem::take(&mut self.config);
+            *self = Self::new();
+            self.disabled = disabled && self.config == config;
+        }
+    }
+
+    pub fn apply(&mut self, mut actions: Vec<Action>) -> Vec<Action> {
+        if self.disabled || self.filters.is_empty() {
+            return actions;
+        }
+        let start = Instant::now();
+        for filter in &mut self.filters {
+            actions = filter.apply(actions);
+        }
+        let elapsed = start.elapsed();
+        if elapsed > self.budget {
+            log::warn!(
+                "output filters took {:?}, which exceeds output_filter_time_budget_ms; \
+                 disabling them for this pane",
+                elapsed
+            );
+            self.disabled = true;
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(actions: &[Action]) -> String {
+        let mut s = String::new();
+        for action in actions {
+            match action {
+                Action::Print(c) => s.push(*c),
+                Action::PrintString(p) => s.push_str(p),
+                Action::Control(ControlCode::LineFeed) => s.push('\n'),
+                _ => s.push('~'),
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn mask_coalesces_text() {
+        let mut filter = Filter::new(
+            &OutputFilter::Mask {
+                regex: "token=\\w+".to_string(),
+                replacement: "token=***".to_string(),
+            },
+            1024,
+        )
+        .unwrap();
+        let actions = vec![
+            Action::PrintString("tok".to_string()),
+            Action::Print('e'),
+            Action::PrintString("n=hunter2 ok".to_string()),
+            Action::Control(ControlCode::LineFeed),
+        ];
+        assert_eq!(text(&filter.apply(actions)), "token=*** ok\n");
+    }
+
+    #[test]
+    fn mask_splits_long_text() {
+        let mut filter = Filter::new(
+            &OutputFilter::Mask {
+                regex: "secret".to_string(),
+                replacement: "***".to_string(),
+            },
+            4,
+        )
+        .unwrap();
+        let actions = vec![
+            Action::PrintString("ab".to_string()),
+            Action::PrintString("cd".to_string()),
+            Action::PrintString("secret".to_string()),
+        ];
+        let actions = filter.apply(actions);
+        assert_eq!(text(&actions), "abcd***");
+        assert_eq!(actions.len(), 2);
+    }
+
+    #[test]
+    fn invalid_timestamp_format() {
+        assert!(Filter::new(
+            &OutputFilter::Timestamp {
+                format: "%Q".to_string(),
+            },
+            1024,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn strip_escapes() {
+        let mut filter = Filter::new(&OutputFilter::StripEscapes, 1024).unwrap();
+        let actions = vec![
+            Action::Control(ControlCode::Bell),
+            Action::PrintString("hello".to_string()),
+            Action::Control(ControlCode::LineFeed),
+        ];
+        assert_eq!(text(&filter.apply(actions)), "hello\n");
+    }
+
+    #[test]
+    fn timestamp_spans_batches() {
+        let mut filter = Filter::new(
+            &OutputFilter::Timestamp {
+                format: "@".to_string(),
+            },
+            1024,
+        )
+        .unwrap();
+        let first = vec![
+            Action::PrintString("a".to_string()),
+            Action::Control(ControlCode::LineFeed),
+        ];
+        assert_eq!(text(&filter.apply(first)), "@a\n");
+        let second = vec![Action::PrintString("b".to_string())];
+        assert_eq!(text(&filter.apply(second)), "@b");
+        let third = vec![Action::PrintString("c".to_string())];
+        assert_eq!(text(&filter.apply(third)), "c");
+    }
+
+    fn progress(actions: &[Action]) -> Vec<Progress> {
+        actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::OperatingSystemCommand(osc) => match &**osc {
+                    OperatingSystemCommand::ConEmuProgress(progress) => Some(progress.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn progress_from_ratio() {
+        let mut filter = Filter::new(
+            &OutputFilter::Progress {
+                regex: "^\\[(?P<done>\\d+)/(?P<total>\\d+)\\]".to_string(),
+            },
+            1024,
+        )
+        .unwrap();
+        let actions = vec![
+            Action::PrintString("[3/12] Building foo.o".to_string()),
+            Action::Control(ControlCode::CarriageReturn),
+        ];
+        let actions = filter.apply(actions);
+        assert_eq!(text(&actions), "[3/12] Building foo.o~~");
+        assert_eq!(progress(&actions), vec![Progress::SetPercentage(25)]);
+
+        let actions = vec![Action::PrintString("[12/12] Linking".to_string())];
+        assert_eq!(progress(&filter.apply(actions)), vec![Progress::None]);
+    }
+
+    #[test]
+    fn progress_from_percentage() {
+        let mut filter = Filter::new(
+            &OutputFilter::Progress {
+                regex: "(\\d+)%".to_string(),
+            },
+            1024,
+        )
+        .unwrap();
+        let actions = vec![Action::PrintString("downloading 42% ".to_string())];
+        assert_eq!(
+            progress(&filter.apply(actions)),
+            vec![Progress::SetPercentage(42)]
+        );
+        // Unchanged progress isn't reported again
+        let actions = vec![Action::PrintString("still 42%".to_string())];
+        assert_eq!(progress(&filter.apply(actions)), vec![]);
+    }
+}
@@ -2,7 +2,7 @@ use crate::domain::DomainId;
 use crate::renderable::*;
 use crate::ExitBehavior;
 use async_trait::async_trait;
-use config::keyassignment::{KeyAssignment, ScrollbackEraseMode};
+use config::keyassignment::{KeyAssignment, ScrollbackEraseMode, ScrollbackExportFormat};
 use downcast_rs::{impl_downcast, Downcast};
 use parking_lot::MappedMutexGuard;
 use rangeset::RangeSet;
@@ -306,6 +306,12 @@ pub trait Pane: Downcast + Send + Sync {
         Ok(vec![])
     }
 
+    /// Returns the scrollback and viewport rendered in `format`,
+    /// for `ExportScrollback` and `kaku cli export-pane`
+    async fn export_scrollback(&self, format: ScrollbackExportFormat) -> anyhow::Result<String> {
+        crate::scrollback_export::export_scrollback(self, format).await
+    }
+
     /// Retrieve the set of semantic zones
     fn get_semantic_zones(&self) -> anyhow::Result<Vec<SemanticZone>> {
         Ok(vec![])
@@ -318,6 +324,13 @@ pub trait Pane: Downcast + Send + Sync {
     fn is_mouse_grabbed(&self) -> bool;
     fn is_alt_screen_active(&self) -> bool;
 
+    /// Returns whether the application in the pane has enabled
+    /// bracketed paste mode, or None if that isn't known, such as
+    /// for panes whose terminal state lives on a remote server
+    fn is_bracketed_paste_enabled(&self) -> Option<bool> {
+        None
+    }
+
     /// Primary Screen Peek: view primary screen history while in alt screen
     fn is_primary_peek(&self) -> bool {
         false
@@ -346,6 +359,12 @@ pub trait Pane: Downcast + Send + Sync {
         None
     }
 
+    /// Returns the id of the process that was spawned into this pane,
+    /// if it is running on the local machine
+    fn process_id(&self) -> Option<u32> {
+        None
+    }
+
     fn exit_behavior(&self) -> Option<ExitBehavior> {
         None
     }
@@ -0,0 +1,195 @@
+//! Helpers for extracting text from panes, shared by the Lua API
+//! and the mux protocol so that automation sees the same results
+//! whether it runs in-process or via `kaku cli`.
+use crate::pane::{Pane, PaneId};
+use crate::Mux;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use termwiz::cell::SemanticType;
+use wezterm_dynamic::{FromDynamic, ToDynamic};
+use wezterm_term::{Line, SemanticZone, StableRowIndex};
+
+/// A semantic zone along with the text that it contains
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromDynamic, ToDynamic)]
+pub struct SemanticText {
+    pub zone: SemanticZone,
+    pub text: String,
+}
+
+/// Returns the text contained in `zone`, joining wrapped lines.
+pub fn get_text_from_semantic_zone(pane: &dyn Pane, zone: &SemanticZone) -> String {
+    let mut last_was_wrapped = false;
+    let first_row = zone.start_y;
+    let last_row = zone.end_y;
+
+    fn cols_for_row(zone: &SemanticZone, row: StableRowIndex) -> std::ops::Range<usize> {
+        if row < zone.start_y || row > zone.end_y {
+            0..0
+        } else if zone.start_y == zone.end_y {
+            // A single line zone
+            if zone.start_x <= zone.end_x {
+                zone.start_x..zone.end_x.saturating_add(1)
+            } else {
+                zone.end_x..zone.start_x.saturating_add(1)
+            }
+        } else if row == zone.end_y {
+            // last line of multi-line
+            0..zone.end_x.saturating_add(1)
+        } else if row == zone.start_y {
+            // first line of multi-line
+            zone.start_x..usize::max_value()
+        } else {
+            // some "middle" line of multi-line
+            0..usize::max_value()
+        }
+    }
+
+    let mut s = String::new();
+    for line in pane.get_logical_lines(zone.start_y..zone.end_y + 1) {
+        if !s.is_empty() && !last_was_wrapped {
+            s.push('\n');
+        }
+        let last_idx = line.physical_lines.len().saturating_sub(1);
+        for (idx, phys) in line.physical_lines.iter().enumerate() {
+            let this_row = line.first_row + idx as StableRowIndex;
+            if this_row >= first_row && this_row <= last_row {
+                let last_phys_idx = phys.len().saturating_sub(1);
+
+                let cols = cols_for_row(zone, this_row);
+                let last_col_idx = cols.end.saturating_sub(1).min(last_phys_idx);
+                let col_span = phys.columns_as_str(cols);
+                // Only trim trailing whitespace if we are the last line
+                // in a wrapped sequence
+                if idx == last_idx {
+                    s.push_str(col_span.trim_end());
+                } else {
+                    s.push_str(&col_span);
+                }
+
+                last_was_wrapped = last_col_idx == last_phys_idx
+                    && phys
+                        .get_cell(last_col_idx)
+                        .map(|c| c.attrs().wrapped())
+                        .unwrap_or(false);
+            }
+        }
+    }
+
+    s
+}
+
+/// Returns the semantic zones of the pane, optionally restricted
+/// to those of type `of_type`, together with their text.
+pub fn get_semantic_text(
+    pane: &dyn Pane,
+    of_type: Option<SemanticType>,
+) -> anyhow::Result<Vec<SemanticText>> {
+    let mut zones = pane.get_semantic_zones()?;
+    if let Some(of_type) = of_type {
+        zones.retain(|zone| zone.semantic_type == of_type);
+    }
+    Ok(zones
+        .into_iter()
+        .map(|zone| SemanticText {
+            text: get_text_from_semantic_zone(pane, &zone),
+            zone,
+        })
+        .collect())
+}
+
+/// Returns the text of the output of the most recent command that
+/// produced any, according to the semantic zones of the pane
+pub fn get_last_command_output(pane: &dyn Pane) -> Option<String> {
+    get_semantic_text(pane, Some(SemanticType::Output))
+        .ok()?
+        .into_iter()
+        .rev()
+        .find(|zone| !zone.text.trim().is_empty())
+        .map(|zone| zone.text)
+}
+
+/// Returns the text in the rectangle of `width` x `height` cells
+/// whose top left corner is at column `x` and row `y` of the
+/// viewport, with one line per row and trailing whitespace removed.
+/// The rectangle is clipped to the viewport.
+pub fn get_region_text(pane: &dyn Pane, x: usize, y: usize, width: usize, height: usize) -> String {
+    let dims = pane.get_dimensions();
+    let y = y.min(dims.viewport_rows);
+    let bottom = y.saturating_add(height).min(dims.viewport_rows);
+    let top = dims.physical_top + y as StableRowIndex;
+    let (_first_row, lines) = pane.get_lines(top..top + (bottom - y) as StableRowIndex);
+    lines
+        .iter()
+        .map(|line| line_columns_text(line, x, width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the text of `width` columns of `line` starting at `x`,
+/// clipped to the width of the line
+fn line_columns_text(line: &Line, x: usize, width: usize) -> String {
+    let end = x.saturating_add(width).min(line.len());
+    if x >= end {
+        return String::new();
+    }
+    line.columns_as_str(x..end).trim_end().to_string()
+}
+
+/// Returns the text of the viewport, joining wrapped lines
+pub fn get_viewport_text(pane: &dyn Pane) -> String {
+    let dims = pane.get_dimensions();
+    let top = dims.physical_top;
+    pane.get_logical_lines(top..top + dims.viewport_rows as StableRowIndex)
+        .iter()
+        .map(|line| line.logical.as_str().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Waits until the text in the viewport of the pane matches the
+/// regular expression `pattern`, returning the matched text, or
+/// None if `timeout` elapses first.
+/// Must be awaited on the main thread.
+pub async fn wait_for_text(
+    pane_id: PaneId,
+    pattern: &str,
+    timeout: Duration,
+) -> anyhow::Result<Option<String>> {
+    let regex =
+        fancy_regex::Regex::new(pattern).with_context(|| format!("invalid regex {pattern:?}"))?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pane = Mux::get()
+            .get_pane(pane_id)
+            .ok_or_else(|| anyhow!("pane {pane_id} not found"))?;
+        let text = get_viewport_text(&*pane);
+        if let Some(found) = regex.find(&text)? {
+            return Ok(Some(found.as_str().to_string()));
+        }
+        drop(pane);
+
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        smol::Timer::after(Duration::from_millis(50)).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::cell::CellAttributes;
+    use termwiz::surface::SEQ_ZERO;
+
+    #[test]
+    fn line_columns_are_clipped() {
+        let line = Line::from_text("hello world", &CellAttributes::default(), SEQ_ZERO, None);
+        assert_eq!(line_columns_text(&line, 0, 5), "hello");
+        assert_eq!(line_columns_text(&line, 6, 100), "world");
+        assert_eq!(line_columns_text(&line, 6, usize::MAX), "world");
+        assert_eq!(line_columns_text(&line, 4, 3), "o w");
+        assert_eq!(line_columns_text(&line, 20, 5), "");
+        assert_eq!(line_columns_text(&line, usize::MAX, usize::MAX), "");
+    }
+}
@@ -0,0 +1,236 @@
+//! Records the output of panes, with its timing, to asciinema v2
+//! `.cast` files under `DATA_DIR/recordings/`, for
+//! `TogglePaneRecording` and `kaku cli record`, and reads them back
+//! for `PlayRecording`.
+use crate::asciicast::{self, Event, Header};
+use crate::domain::LocalDomain;
+use crate::pane::{Pane, PaneId};
+use crate::{Mux, MuxNotification};
+use anyhow::Context;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+lazy_static::lazy_static! {
+    static ref RECORDINGS: Mutex<HashMap<PaneId, Recording>> = Mutex::new(HashMap::new());
+}
+
+struct Recording {
+    file: BufWriter<File>,
+    path: PathBuf,
+    start: Instant,
+    /// The start of a UTF-8 sequence that was split across reads
+    pending: Vec<u8>,
+}
+
+impl Recording {
+    fn write_event(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.pending.extend_from_slice(data);
+        let text = take_text(&mut self.pending);
+        if text.is_empty() {
+            return Ok(());
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        serde_json::to_writer(&mut self.file, &(elapsed, "o", text))?;
+        self.file.write_all(b"\n")?;
+        // Flush so that the file is usable while the recording is
+        // in progress, and nothing is lost if we crash
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Removes and returns the longest valid UTF-8 prefix of `pending`.
+/// An incomplete sequence at the end is left in place to be completed
+/// by the next read; invalid sequences are replaced.
+fn take_text(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(_) => String::from_utf8(std::mem::take(pending)).expect("to be valid"),
+        Err(err) if err.error_len().is_none() => {
+            let rest = pending.split_off(err.valid_up_to());
+            String::from_utf8(std::mem::replace(pending, rest)).expect("to be valid")
+        }
+        Err(_) => String::from_utf8_lossy(&std::mem::take(pending)).into_owned(),
+    }
+}
+
+pub fn recordings_dir() -> PathBuf {
+    config::DATA_DIR.join("recordings")
+}
+
+pub fn is_recording(pane_id: PaneId) -> bool {
+    RECORDINGS.lock().contains_key(&pane_id)
+}
+
+/// Returns the file that `pane_id` is being recorded to, if any
+pub fn recording_path(pane_id: PaneId) -> Option<PathBuf> {
+    RECORDINGS.lock().get(&pane_id).map(|rec| rec.path.clone())
+}
+
+fn invalidate_window_of_pane(pane_id: PaneId) {
+    if let Some(mux) = Mux::try_get() {
+        if let Some((_domain_id, window_id, _tab_id)) = mux.resolve_pane_id(pane_id) {
+            mux.notify(MuxNotification::WindowInvalidated(window_id));
+        }
+    }
+}
+
+/// Starts recording the output of `pane` to a new file, which is
+/// returned.  If the pane is already being recorded, returns the
+/// file it is being recorded to.
+/// Only panes in a local domain, whose pty is read by this process,
+/// can be recorded; the output of a pane in a remote mux domain never
+/// passes through `record_output` here, so it has to be recorded by
+/// its server, for example with `kaku cli record`.
+pub fn start_recording(pane: &dyn Pane) -> anyhow::Result<PathBuf> {
+    let pane_id = pane.pane_id();
+    if let Some(path) = recording_path(pane_id) {
+        return Ok(path);
+    }
+    let is_local = Mux::get()
+        .get_domain(pane.domain_id())
+        .map_or(false, |domain| {
+            domain.downcast_ref::<LocalDomain>().is_some()
+        });
+    if !is_local {
+        anyhow::bail!(
+            "pane {} belongs to a remote domain; record it on its mux server instead",
+            pane_id
+        );
+    }
+
+    let dir = recordings_dir();
+    config::create_user_owned_dirs(&dir)?;
+    let path = dir.join(format!(
+        "kaku-pane-{}-{}.cast",
+        pane_id,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let mut file = BufWriter::new(
+        File::create(&path).with_context(|| format!("creating {}", path.display()))?,
+    );
+
+    let dims = pane.get_dimensions();
+    let mut env = HashMap::new();
+    env.insert("TERM".to_string(), config::configuration().term.clone());
+    if let Ok(shell) = std::env::var("SHELL") {
+        env.insert("SHELL".to_string(), shell);
+    }
+    let header = Header {
+        version: 2,
+        width: dims.cols as u32,
+        height: dims.viewport_rows as u32,
+        timestamp: Some(chrono::Utc::now()),
+        env,
+        ..Default::default()
+    };
+    serde_json::to_writer(&mut file, &header)?;
+    file.write_all(b"\n")?;
+    file.flush()?;
+
+    RECORDINGS.lock().insert(
+        pane_id,
+        Recording {
+            file,
+            path: path.clone(),
+            start: Instant::now(),
+            pending: vec![],
+        },
+    );
+    log::info!("Recording pane {} to {}", pane_id, path.display());
+    invalidate_window_of_pane(pane_id);
+    Ok(path)
+}
+
+/// Stops recording `pane_id`, returning the file it was recorded to,
+/// or None if it wasn't being recorded
+pub fn stop_recording(pane_id: PaneId) -> Option<PathBuf> {
+    let mut recording = RECORDINGS.lock().remove(&pane_id)?;
+    // Don't lose an incomplete sequence at the end of the output
+    let pending = std::mem::take(&mut recording.pending);
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        recording.write_event(text.as_bytes()).ok();
+    }
+    log::info!(
+        "Stopped recording pane {} to {}",
+        pane_id,
+        recording.path.display()
+    );
+    invalidate_window_of_pane(pane_id);
+    Some(recording.path)
+}
+
+/// A recording that has been read back from a `.cast` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastFile {
+    pub width: usize,
+    pub height: usize,
+    /// The output of the recording, as seconds since its start
+    /// and the text written at that time, in order
+    pub output: Vec<(f32, String)>,
+}
+
+/// Reads a recording for playback
+pub fn read_cast(path: &Path) -> anyhow::Result<CastFile> {
+    let file = File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    let (header, events) = asciicast::read_cast(BufReader::new(file))
+        .with_context(|| format!("reading {}", path.display()))?;
+    Ok(CastFile {
+        width: header.width as usize,
+        height: header.height as usize,
+        output: events
+            .into_iter()
+            .map(|Event(time, _, data)| (time, data))
+            .collect(),
+    })
+}
+
+/// Returns the most recently modified recording in `recordings_dir`
+pub fn latest_recording() -> Option<PathBuf> {
+    std::fs::read_dir(recordings_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "cast"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Called with the raw output of a pane as it is read from the pty
+pub(crate) fn record_output(pane_id: PaneId, data: &[u8]) {
+    let mut recordings = RECORDINGS.lock();
+    if let Some(recording) = recordings.get_mut(&pane_id) {
+        if let Err(err) = recording.write_event(data) {
+            log::error!(
+                "Failed to record pane {} to {}: {:#}",
+                pane_id,
+                recording.path.display(),
+                err
+            );
+            recordings.remove(&pane_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_utf8() {
+        let mut pending = b"a\xe2\x94".to_vec();
+        assert_eq!(take_text(&mut pending), "a");
+        assert_eq!(pending, b"\xe2\x94");
+        pending.push(0x80);
+        assert_eq!(take_text(&mut pending), "\u{2500}");
+        assert!(pending.is_empty());
+
+        let mut pending = b"\xffb".to_vec();
+        assert_eq!(take_text(&mut pending), "\u{fffd}b");
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+//! Renders the scrollback of a pane as plain text, text with escape
+//! sequences or HTML, for `ExportScrollback` and `kaku cli export-pane`.
+use crate::pane::Pane;
+use config::keyassignment::ScrollbackExportFormat;
+use std::fmt::Write;
+use termwiz::cell::{CellAttributes, Intensity, Underline};
+use termwiz::surface::Line;
+use wezterm_term::color::ColorPalette;
+use wezterm_term::StableRowIndex;
+
+/// Returns the scrollback and viewport of `pane` in `format`.
+/// Blank lines at the bottom of the viewport are omitted.
+/// The lines are copied from the pane on the calling thread and
+/// then rendered on a background thread, as rendering a long
+/// scrollback can take a while.
+/// This is the default implementation of `Pane::export_scrollback`.
+pub async fn export_scrollback<P: Pane + ?Sized>(
+    pane: &P,
+    format: ScrollbackExportFormat,
+) -> anyhow::Result<String> {
+    let lines = scrollback_lines(pane);
+    let palette = pane.palette();
+    promise::spawn::spawn_into_new_thread(move || export_lines(lines, format, &palette)).await
+}
+
+/// Returns the lines of the scrollback and viewport of `pane`,
+/// without the blank lines at the bottom of the viewport
+fn scrollback_lines<P: Pane + ?Sized>(pane: &P) -> Vec<Line> {
+    let dims = pane.get_dimensions();
+    let end = dims.physical_top + dims.viewport_rows as StableRowIndex;
+    let (_first_row, mut lines) = pane.get_lines(dims.scrollback_top..end);
+    while lines.last().map_or(false, |line| line.is_whitespace()) {
+        lines.pop();
+    }
+    lines
+}
+
+pub fn export_lines(
+    lines: Vec<Line>,
+    format: ScrollbackExportFormat,
+    palette: &ColorPalette,
+) -> anyhow::Result<String> {
+    match format {
+        ScrollbackExportFormat::PlainText => Ok(lines_to_text(&lines)),
+        ScrollbackExportFormat::Ansi => termwiz_funcs::lines_to_escapes(lines),
+        ScrollbackExportFormat::Html => Ok(lines_to_html(&lines, palette)),
+    }
+}
+
+/// Joins wrapped lines back together, so that the text can be
+/// re-flowed by whatever displays it
+fn lines_to_text(lines: &[Line]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        if line.last_cell_was_wrapped() {
+            text.push_str(&line.as_str());
+        } else {
+            text.push_str(line.as_str().trim_end());
+            text.push('\n');
+        }
+    }
+    text
+}
+
+pub(crate) fn html_escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Returns the inline style for text with `attrs`, or None if it
+/// looks the same as unattributed text
+pub(crate) fn css_for_attrs(attrs: &CellAttributes, palette: &ColorPalette) -> Option<String> {
+    let mut fg = palette.resolve_fg(attrs.foreground());
+    let mut bg = palette.resolve_bg(attrs.background());
+    if attrs.reverse() {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    if attrs.invisible() {
+        fg = bg;
+    }
+
+    let mut css = String::new();
+    if fg != palette.foreground {
+        write!(css, "color:{};", fg.to_rgb_string()).ok();
+    }
+    if bg != palette.background {
+        write!(css, "background-color:{};", bg.to_rgb_string()).ok();
+    }
+    match attrs.intensity() {
+        Intensity::Bold => css.push_str("font-weight:bold;"),
+        Intensity::Half => css.push_str("opacity:0.5;"),
+        Intensity::Normal => {}
+    }
+    if attrs.italic() {
+        css.push_str("font-style:italic;");
+    }
+    let mut decorations = vec![];
+    if attrs.underline() != Underline::None {
+        decorations.push("underline");
+    }
+    if attrs.strikethrough() {
+        decorations.push("line-through");
+    }
+    if attrs.overline() {
+        decorations.push("overline");
+    }
+    if !decorations.is_empty() {
+        write!(css, "text-decoration:{};", decorations.join(" ")).ok();
+    }
+
+    if css.is_empty() {
+        None
+    } else {
+        Some(css)
+    }
+}
+
+fn lines_to_html(lines: &[Line], palette: &ColorPalette) -> String {
+    let mut html = String::new();
+    write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Kaku scrollback</title>\n</head>\n\
+         <body style=\"margin:0;background-color:{bg}\">\n\
+         <pre style=\"margin:0;padding:1em;font-family:monospace;color:{fg};background-color:{bg}\">",
+        fg = palette.foreground.to_rgb_string(),
+        bg = palette.background.to_rgb_string(),
+    )
+    .ok();
+
+    for line in lines {
+        let mut clusters = line.cluster(None);
+        if !line.last_cell_was_wrapped() {
+            // Drop the trailing blanks, unless they are colored
+            while let Some(last) = clusters.last_mut() {
+                if css_for_attrs(&last.attrs, palette).is_some() {
+                    break;
+                }
+                let trimmed = last.text.trim_end().len();
+                if trimmed > 0 {
+                    last.text.truncate(trimmed);
+                    break;
+                }
+                clusters.pop();
+            }
+        }
+
+        for cluster in &clusters {
+            let link = cluster.attrs.hyperlink();
+            if let Some(link) = link {
+                html.push_str("<a href=\"");
+                html_escape(link.uri(), &mut html);
+                html.push_str("\">");
+            }
+            match css_for_attrs(&cluster.attrs, palette) {
+                Some(css) => {
+                    write!(html, "<span style=\"{}\">", css).ok();
+                    html_escape(&cluster.text, &mut html);
+                    html.push_str("</span>");
+                }
+                None => html_escape(&cluster.text, &mut html),
+            }
+            if link.is_some() {
+                html.push_str("</a>");
+            }
+        }
+
+        if !line.last_cell_was_wrapped() {
+            html.push('\n');
+        }
+    }
+
+    html.push_str("</pre>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::color::ColorAttribute;
+
+    #[test]
+    fn export() {
+        let palette = ColorPalette::default();
+        let mut red = CellAttributes::default();
+        red.set_foreground(ColorAttribute::PaletteIndex(1));
+
+        let mut line = Line::from_text("a<b ", &CellAttributes::default(), 0, None);
+        line.append_line(Line::from_text("red", &red, 0, None), 0);
+        let lines = vec![
+            line,
+            Line::from_text("next  ", &Default::default(), 0, None),
+        ];
+
+        assert_eq!(lines_to_text(&lines), "a<b red\nnext\n");
+
+        let html = lines_to_html(&lines, &palette);
+        assert!(html.contains(&format!(
+            "a&lt;b <span style=\"color:{};\">red</span>\nnext\n</pre>",
+            palette.colors.0[1].to_rgb_string()
+        )));
+    }
+}
@@ -0,0 +1,507 @@
+//! Serves a read-only, live view of a pane to a web browser.
+//!
+//! Sharing is strictly opt-in: nothing listens until
+//! `pane_share_bind_address` is set in the config and a pane is
+//! explicitly shared.  Each share produces a URL with a one-time
+//! token; the first browser to load it consumes the token and
+//! receives a self-contained page that shows the pane, fed by a
+//! server-sent event stream of screen repaints.  The repaints are
+//! rendered to HTML on the server, with the colors and attributes of
+//! each run of cells as styled spans and the cursor drawn in the
+//! cursor colors, so the page needs no terminal emulator of its own.
+//! Serving HTTPS is out of scope: the server speaks plain HTTP only,
+//! so put it behind a TLS terminating proxy if it needs to leave the
+//! local machine.
+use crate::pane::{Pane, PaneId};
+use crate::scrollback_export::{css_for_attrs, html_escape};
+use crate::Mux;
+use anyhow::{anyhow, Context};
+use base64::Engine;
+use config::configuration;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use termwiz::surface::{CursorVisibility, Line};
+use wezterm_term::color::ColorPalette;
+
+/// How long an unused share link remains valid
+const TOKEN_LIFETIME: Duration = Duration::from_secs(300);
+/// How often the stream checks the pane for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often to send a comment to detect disconnected viewers
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// How long to wait for a client to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many connections, including open streams, are served at once
+const MAX_CONNECTIONS: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref SERVER: Mutex<Option<Arc<ShareServer>>> = Mutex::new(None);
+}
+
+struct PendingShare {
+    pane_id: PaneId,
+    created: Instant,
+}
+
+struct ShareServer {
+    addr: SocketAddr,
+    /// Tokens embedded in share links, not yet visited
+    links: Mutex<HashMap<String, PendingShare>>,
+    /// Tokens handed to a page that has not yet opened its stream
+    streams: Mutex<HashMap<String, PendingShare>>,
+    connections: Arc<AtomicUsize>,
+}
+
+/// Counts a connection towards `MAX_CONNECTIONS` for as long as it
+/// is alive
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < MAX_CONNECTIONS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ShareServer {
+    fn start(bind_address: &str) -> anyhow::Result<Arc<Self>> {
+        let listener = TcpListener::bind(bind_address)
+            .with_context(|| format!("binding pane share server to {bind_address}"))?;
+        let addr = listener.local_addr()?;
+        let server = Arc::new(Self {
+            addr,
+            links: Mutex::new(HashMap::new()),
+            streams: Mutex::new(HashMap::new()),
+            connections: Arc::new(AtomicUsize::new(0)),
+        });
+
+        let accept_server = Arc::clone(&server);
+        std::thread::Builder::new()
+            .name("pane-share".into())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let slot = match ConnectionSlot::acquire(&accept_server.connections) {
+                                Some(slot) => slot,
+                                None => {
+                                    log::warn!("pane share: too many connections");
+                                    let _ = respond(
+                                        stream,
+                                        "503 Service Unavailable",
+                                        "text/plain",
+                                        "",
+                                    );
+                                    continue;
+                                }
+                            };
+                            let server = Arc::clone(&accept_server);
+                            std::thread::spawn(move || {
+                                if let Err(err) = server.handle(stream) {
+                                    log::trace!("pane share connection: {:#}", err);
+                                }
+                                drop(slot);
+                            });
+                        }
+                        Err(err) => log::error!("pane share accept failed: {:#}", err),
+                    }
+                }
+            })
+            .context("spawning pane-share thread")?;
+
+        log::info!("pane share server listening on {}", addr);
+        Ok(server)
+    }
+
+    fn url_for(&self, token: &str) -> String {
+        let host = if self.addr.ip().is_unspecified() {
+            hostname::get()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "localhost".to_string())
+        } else {
+            match self.addr {
+                SocketAddr::V4(a) => a.ip().to_string(),
+                SocketAddr::V6(a) => format!("[{}]", a.ip()),
+            }
+        };
+        format!("http://{}:{}/s/{}", host, self.addr.port(), token)
+    }
+
+    /// Removes and returns the pane for `token`, provided that it
+    /// has not expired.
+    fn take(map: &Mutex<HashMap<String, PendingShare>>, token: &str) -> Option<PaneId> {
+        let mut map = map.lock();
+        map.retain(|_, pending| pending.created.elapsed() < TOKEN_LIFETIME);
+        map.remove(token).map(|pending| pending.pane_id)
+    }
+
+    fn handle(&self, stream: TcpStream) -> anyhow::Result<()> {
+        // Don't let a client that never finishes its request hold
+        // on to a connection slot
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Drain the headers; we don't need any of them
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut fields = request_line.split_whitespace();
+        let method = fields.next().unwrap_or("");
+        let path = fields.next().unwrap_or("");
+
+        if method != "GET" {
+            return respond(stream, "405 Method Not Allowed", "text/plain", "");
+        }
+
+        if let Some(token) = path.strip_prefix("/s/") {
+            return match Self::take(&self.links, token) {
+                Some(pane_id) => {
+                    let stream_token = new_token()?;
+                    self.streams.lock().insert(
+                        stream_token.clone(),
+                        PendingShare {
+                            pane_id,
+                            created: Instant::now(),
+                        },
+                    );
+                    let page = VIEWER_PAGE.replace("@STREAM@", &stream_token);
+                    respond(stream, "200 OK", "text/html; charset=utf-8", &page)
+                }
+                None => respond(
+                    stream,
+                    "404 Not Found",
+                    "text/plain",
+                    "This share link is invalid, expired or has already been used.\n",
+                ),
+            };
+        }
+
+        if let Some(token) = path.strip_prefix("/stream/") {
+            return match Self::take(&self.streams, token) {
+                Some(pane_id) => stream_pane(stream, pane_id),
+                None => respond(stream, "404 Not Found", "text/plain", ""),
+            };
+        }
+
+        respond(stream, "404 Not Found", "text/plain", "")
+    }
+}
+
+fn respond(
+    mut stream: TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-store\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn new_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow!("generating share token: {err}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Appends the cells of `line` to `out` as HTML, merging runs of
+/// cells with the same attributes into a single span.  The cell at
+/// `cursor_x`, if any, is drawn in the cursor colors of the palette.
+fn line_to_html(line: &Line, cursor_x: Option<usize>, palette: &ColorPalette, out: &mut String) {
+    let mut run_css = None;
+    let mut run_text = String::new();
+    let mut end_col = 0;
+    let cursor_open = format!(
+        "<span style=\"color:{};background-color:{}\">",
+        palette.cursor_fg.to_rgb_string(),
+        palette.cursor_bg.to_rgb_string()
+    );
+
+    fn flush(css: &Option<String>, text: &mut String, out: &mut String) {
+        if text.is_empty() {
+            return;
+        }
+        match css {
+            Some(css) => {
+                out.push_str(&format!("<span style=\"{css}\">"));
+                html_escape(text, out);
+                out.push_str("</span>");
+            }
+            None => html_escape(text, out),
+        }
+        text.clear();
+    }
+
+    for cell in line.visible_cells() {
+        let css = css_for_attrs(cell.attrs(), palette);
+        if Some(cell.cell_index()) == cursor_x {
+            flush(&run_css, &mut run_text, out);
+            out.push_str(&cursor_open);
+            html_escape(cell.str(), out);
+            out.push_str("</span>");
+        } else {
+            if css != run_css {
+                flush(&run_css, &mut run_text, out);
+                run_css = css;
+            }
+            run_text.push_str(cell.str());
+        }
+        end_col = cell.cell_index() + cell.width();
+    }
+    flush(&run_css, &mut run_text, out);
+
+    // The cursor can sit past the last cell of the line
+    if let Some(x) = cursor_x.filter(|&x| x >= end_col) {
+        out.push_str(&" ".repeat(x - end_col));
+        out.push_str(&cursor_open);
+        out.push_str(" </span>");
+    }
+}
+
+/// Renders the visible portion of the pane, and the cursor, as the
+/// HTML content of the viewer's `<pre>` element
+fn render_viewport(pane: &Arc<dyn Pane>) -> String {
+    let dims = pane.get_dimensions();
+    let top = dims.physical_top;
+    let (_first_row, lines) = pane.get_lines(top..top + dims.viewport_rows as isize);
+    let palette = pane.palette();
+    let cursor = pane.get_cursor_position();
+
+    let mut html = format!(
+        "<div style=\"color:{};background-color:{}\">",
+        palette.foreground.to_rgb_string(),
+        palette.background.to_rgb_string()
+    );
+    for (idx, line) in lines.iter().enumerate() {
+        let cursor_x = (cursor.visibility == CursorVisibility::Visible
+            && cursor.y == top + idx as isize)
+            .then_some(cursor.x);
+        line_to_html(line, cursor_x, &palette, &mut html);
+        html.push('\n');
+    }
+    html.push_str("</div>");
+    html
+}
+
+fn stream_pane(mut stream: TcpStream, pane_id: PaneId) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/event-stream\r\n\
+         Cache-Control: no-store\r\n\
+         Connection: close\r\n\r\n"
+    )?;
+
+    let mut last_seqno = None;
+    let mut last_write = Instant::now();
+    loop {
+        let pane = match Mux::try_get().and_then(|mux| mux.get_pane(pane_id)) {
+            Some(pane) => pane,
+            None => {
+                write!(stream, "event: closed\ndata:\n\n")?;
+                return Ok(());
+            }
+        };
+
+        let seqno = pane.get_current_seqno();
+        if last_seqno != Some(seqno) {
+            let html = render_viewport(&pane);
+            let base64 = &base64::engine::general_purpose::STANDARD;
+            write!(stream, "data: {}\n\n", base64.encode(html))?;
+            stream.flush()?;
+            last_seqno.replace(seqno);
+            last_write = Instant::now();
+        } else if last_write.elapsed() >= KEEPALIVE_INTERVAL {
+            write!(stream, ":\n\n")?;
+            stream.flush()?;
+            last_write = Instant::now();
+        }
+
+        drop(pane);
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Creates a one-time link that presents a read-only live view of
+/// the specified pane.  The share server is started on first use,
+/// bound to `pane_share_bind_address`.
+pub fn share_pane(pane_id: PaneId) -> anyhow::Result<String> {
+    let server = {
+        let mut server = SERVER.lock();
+        match server.as_ref() {
+            Some(server) => Arc::clone(server),
+            None => {
+                let bind_address = configuration().pane_share_bind_address.clone().context(
+                    "pane sharing is disabled; set pane_share_bind_address to enable it",
+                )?;
+                let started = ShareServer::start(&bind_address)?;
+                server.replace(Arc::clone(&started));
+                started
+            }
+        }
+    };
+
+    let token = new_token()?;
+    server.links.lock().insert(
+        token.clone(),
+        PendingShare {
+            pane_id,
+            created: Instant::now(),
+        },
+    );
+    Ok(server.url_for(&token))
+}
+
+/// The viewer is self-contained, rather than loading a terminal
+/// renderer from a CDN, so that sharing works without internet access
+/// and the page doesn't run third party code.
+const VIEWER_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Kaku shared pane</title>
+<style>
+html, body { margin: 0; background: #000; color: #ddd; }
+pre { margin: 0; font: 14px/1.2 monospace; }
+pre > div { padding: 4px; min-height: 100vh; box-sizing: border-box; }
+</style>
+</head>
+<body>
+<pre id="terminal"></pre>
+<script>
+const screen = document.getElementById("terminal");
+const decoder = new TextDecoder();
+const decode = (data) => decoder.decode(Uint8Array.from(atob(data), (c) => c.charCodeAt(0)));
+const events = new EventSource("/stream/@STREAM@");
+events.onmessage = (e) => {
+  // The server renders the screen to escaped, styled spans
+  screen.innerHTML = decode(e.data);
+};
+events.addEventListener("closed", () => {
+  events.close();
+  screen.firstElementChild?.append("[pane closed]");
+});
+events.onerror = () => events.close();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termwiz::cell::CellAttributes;
+    use termwiz::color::ColorAttribute;
+
+    fn pending(pane_id: PaneId, age: Duration) -> PendingShare {
+        PendingShare {
+            pane_id,
+            created: Instant::now() - age,
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let a = new_token().unwrap();
+        let b = new_token().unwrap();
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn tokens_are_single_use() {
+        let map = Mutex::new(HashMap::new());
+        map.lock()
+            .insert("abc".to_string(), pending(1, Duration::ZERO));
+        assert_eq!(ShareServer::take(&map, "nope"), None);
+        assert_eq!(ShareServer::take(&map, "abc"), Some(1));
+        assert_eq!(ShareServer::take(&map, "abc"), None);
+    }
+
+    #[test]
+    fn tokens_expire() {
+        let map = Mutex::new(HashMap::new());
+        map.lock()
+            .insert("old".to_string(), pending(1, TOKEN_LIFETIME));
+        map.lock()
+            .insert("new".to_string(), pending(2, Duration::ZERO));
+        assert_eq!(ShareServer::take(&map, "old"), None);
+        // Expired tokens are pruned along the way
+        assert_eq!(map.lock().len(), 1);
+        assert_eq!(ShareServer::take(&map, "new"), Some(2));
+    }
+
+    #[test]
+    fn connection_cap() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| ConnectionSlot::acquire(&connections).unwrap())
+            .collect();
+        assert!(ConnectionSlot::acquire(&connections).is_none());
+        drop(slots);
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+        assert!(ConnectionSlot::acquire(&connections).is_some());
+    }
+
+    #[test]
+    fn viewport_html() {
+        let palette = ColorPalette::default();
+        let mut red = CellAttributes::default();
+        red.set_foreground(ColorAttribute::PaletteIndex(1));
+        let mut line = Line::from_text("a<b ", &CellAttributes::default(), 0, None);
+        line.append_line(Line::from_text("red", &red, 0, None), 0);
+        let cursor = format!(
+            "<span style=\"color:{};background-color:{}\">",
+            palette.cursor_fg.to_rgb_string(),
+            palette.cursor_bg.to_rgb_string()
+        );
+        let red_css = format!("color:{};", palette.colors.0[1].to_rgb_string());
+
+        let mut html = String::new();
+        line_to_html(&line, None, &palette, &mut html);
+        assert_eq!(html, format!("a&lt;b <span style=\"{red_css}\">red</span>"));
+
+        let mut html = String::new();
+        line_to_html(&line, Some(5), &palette, &mut html);
+        assert_eq!(
+            html,
+            format!(
+                "a&lt;b <span style=\"{red_css}\">r</span>{cursor}e</span>\
+                 <span style=\"{red_css}\">d</span>"
+            )
+        );
+
+        let mut html = String::new();
+        line_to_html(&line, Some(9), &palette, &mut html);
+        assert!(html.ends_with(&format!("red</span>  {cursor} </span>")));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::connui::ConnectionUI;
+use crate::connui::{ConnectionUI, PromptField};
 use crate::domain::{alloc_domain_id, Domain, DomainId, DomainState, WriterWrapper};
 use crate::localpane::LocalPane;
 use crate::pane::{alloc_pane_id, Pane, PaneId};
@@ -13,6 +13,7 @@ use smol::channel::{bounded, Receiver as AsyncReceiver};
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
 use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -57,8 +58,12 @@ impl LineEditorHost for PasswordPromptHost {
     }
 }
 
+/// Connects and authenticates, prompting via `ui`.
+/// `problems` are those found by `ssh_domain_problems`; they are shown
+/// before connecting and again if the connection fails.
 pub fn ssh_connect_with_ui(
     ssh_config: wezterm_ssh::ConfigMap,
+    problems: &[String],
     ui: &mut ConnectionUI,
 ) -> anyhow::Result<Session> {
     let cloned_ui = ui.clone();
@@ -66,6 +71,9 @@ pub fn ssh_connect_with_ui(
         let remote_address = ssh_config
             .get("hostname")
             .expect("ssh config to always set hostname");
+        for problem in problems {
+            ui.output_str(&format!("Warning: {}\n", problem));
+        }
         ui.output_str(&format!("Connecting to {} using SSH\n", remote_address));
         let (session, events) = Session::connect(ssh_config.clone())?;
 
@@ -77,8 +85,12 @@ pub fn ssh_connect_with_ui(
                     }
                 }
                 SessionEvent::HostVerify(verify) => {
-                    ui.output_str(&format!("{}\n", verify.message));
-                    let ok = if let Ok(line) = ui.input("Enter [y/n]> ") {
+                    let field = PromptField {
+                        prompt: "Enter [y/n]> ".to_string(),
+                        echo: true,
+                    };
+                    let answer = ui.prompt(&verify.message, vec![field]);
+                    let ok = if let Some(line) = answer.ok().and_then(|mut lines| lines.pop()) {
                         match line.as_ref() {
                             "y" | "Y" | "yes" | "YES" => true,
                             "n" | "N" | "no" | "NO" | _ => false,
@@ -89,30 +101,24 @@ pub fn ssh_connect_with_ui(
                     smol::block_on(verify.answer(ok)).context("send verify response")?;
                 }
                 SessionEvent::Authenticate(auth) => {
+                    let mut instructions = vec![];
                     if !auth.username.is_empty() {
-                        ui.output_str(&format!("Authentication for {}\n", auth.username));
+                        instructions.push(format!("Authentication for {}", auth.username));
                     }
                     if !auth.instructions.is_empty() {
-                        ui.output_str(&format!("{}\n", auth.instructions));
-                    }
-                    let mut answers = vec![];
-                    for prompt in &auth.prompts {
-                        let mut prompt_lines = prompt.prompt.split('\n').collect::<Vec<_>>();
-                        let editor_prompt = prompt_lines.pop().unwrap();
-                        for line in &prompt_lines {
-                            ui.output_str(&format!("{}\n", line));
-                        }
-                        let res = if prompt.echo {
-                            ui.input(editor_prompt)
-                        } else {
-                            ui.password(editor_prompt)
-                        };
-                        if let Ok(line) = res {
-                            answers.push(line);
-                        } else {
-                            anyhow::bail!("Authentication was cancelled");
-                        }
+                        instructions.push(auth.instructions.clone());
                     }
+                    let fields = auth
+                        .prompts
+                        .iter()
+                        .map(|prompt| PromptField {
+                            prompt: prompt.prompt.clone(),
+                            echo: prompt.echo,
+                        })
+                        .collect();
+                    let answers = ui
+                        .prompt(&instructions.join("\n"), fields)
+                        .map_err(|_| anyhow!("Authentication was cancelled"))?;
                     smol::block_on(auth.answer(answers))?;
                 }
                 SessionEvent::HostVerificationFailed(failed) => {
@@ -121,12 +127,15 @@ pub fn ssh_connect_with_ui(
                     anyhow::bail!("Host key verification failed");
                 }
                 SessionEvent::Error(err) => {
-                    anyhow::bail!("Error: {}", err);
+                    anyhow::bail!("{}", explain_ssh_error(&err, problems));
                 }
                 SessionEvent::Authenticated => return Ok(session),
             }
         }
-        bail!("unable to authenticate session");
+        bail!(
+            "{}",
+            explain_ssh_error("unable to authenticate session", problems)
+        );
     })
 }
 
@@ -223,12 +232,115 @@ pub fn ssh_domain_to_ssh_config(ssh_dom: &SshDomain) -> anyhow::Result<ConfigMap
     if ssh_dom.no_agent_auth {
         ssh_config.insert("identitiesonly".to_string(), "yes".to_string());
     }
+    if ssh_dom.forward_agent {
+        ssh_config.insert("forwardagent".to_string(), "yes".to_string());
+    }
+    if !ssh_dom.identity_files.is_empty() {
+        let files: Vec<_> = identity_files(ssh_dom)
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        ssh_config.insert(
+            "identityfile".to_string(),
+            wezterm_ssh::join_identity_files(&files),
+        );
+    }
+
+    // A ProxyCommand or ProxyJump from the ssh config takes precedence
+    // over the configured proxy
+    if !ssh_config.contains_key("proxycommand") && !ssh_config.contains_key("proxyjump") {
+        let host = ssh_config
+            .get("hostname")
+            .map(|s| s.as_str())
+            .unwrap_or(remote_host_name)
+            .to_string();
+        if let Some(proxy) = config::configuration().proxy_for_host(&host) {
+            let port = match ssh_config.get("port") {
+                Some(port) => port.parse::<u16>()?,
+                None => 22,
+            };
+            ssh_config.insert(
+                "proxycommand".to_string(),
+                proxy.netcat_proxy_command(&host, port),
+            );
+        }
+    }
+
     if let Some("true") = ssh_config.get("wezterm_ssh_verbose").map(|s| s.as_str()) {
         log::info!("Using ssh config: {ssh_config:#?}");
     }
     Ok(ssh_config)
 }
 
+/// Returns the identity_files of `ssh_dom`, with a leading `~`
+/// expanded to the home directory
+fn identity_files(ssh_dom: &SshDomain) -> Vec<PathBuf> {
+    ssh_dom
+        .identity_files
+        .iter()
+        .map(|path| match path.strip_prefix("~") {
+            Ok(rest) => config::HOME_DIR.join(rest),
+            Err(_) => path.clone(),
+        })
+        .collect()
+}
+
+/// Checks the parts of `ssh_dom` that would otherwise only show up as
+/// an authentication failure once connected, such as a missing identity
+/// file, and returns a description of each problem found
+pub fn ssh_domain_problems(ssh_dom: &SshDomain, ssh_config: &ConfigMap) -> Vec<String> {
+    let mut problems = vec![];
+    for path in identity_files(ssh_dom) {
+        if !path.exists() {
+            problems.push(format!(
+                "Identity file {} configured for {} does not exist",
+                path.display(),
+                ssh_dom.name
+            ));
+        }
+    }
+
+    if ssh_dom.forward_agent
+        && ssh_config.get("wezterm_ssh_backend").map(String::as_str) == Some("ssh2")
+    {
+        problems.push(format!(
+            "forward_agent is enabled for {}, but agent forwarding requires \
+             the LibSsh ssh_backend",
+            ssh_dom.name
+        ));
+    } else if ssh_dom.forward_agent {
+        match ssh_config
+            .get("identityagent")
+            .cloned()
+            .or_else(|| std::env::var("SSH_AUTH_SOCK").ok())
+        {
+            None => problems.push(format!(
+                "forward_agent is enabled for {}, but no ssh agent is available: \
+                 SSH_AUTH_SOCK is not set",
+                ssh_dom.name
+            )),
+            #[cfg(unix)]
+            Some(sock) if !std::path::Path::new(&sock).exists() => problems.push(format!(
+                "forward_agent is enabled for {}, but the ssh agent socket {} does not exist",
+                ssh_dom.name, sock
+            )),
+            Some(_) => {}
+        }
+    }
+
+    problems
+}
+
+/// Adds the problems found by `ssh_domain_problems` to an error
+/// reported by the ssh session, as they are the likely cause
+fn explain_ssh_error(err: &str, problems: &[String]) -> String {
+    let mut message = format!("Error: {}", err);
+    for problem in problems {
+        message.push_str(&format!("\n{}", problem));
+    }
+    message
+}
+
 impl RemoteSshDomain {
     pub fn with_ssh_domain(dom: &SshDomain) -> anyhow::Result<Self> {
         let id = alloc_domain_id();
@@ -330,8 +442,9 @@ impl RemoteSshDomain {
         env: HashMap<String, String>,
         size: TerminalSize,
     ) -> anyhow::Result<StartNewSessionResult> {
-        let (session, events) = Session::connect(self.ssh_config().context("obtain ssh config")?)
-            .context("connect to ssh server")?;
+        let ssh_config = self.ssh_config().context("obtain ssh config")?;
+        let problems = ssh_domain_problems(&self.dom, &ssh_config);
+        let (session, events) = Session::connect(ssh_config).context("connect to ssh server")?;
         self.session.lock().unwrap().replace(session.clone());
 
         // We get to establish the session!
@@ -400,6 +513,7 @@ impl RemoteSshDomain {
                 size,
                 command_line,
                 env,
+                problems,
             ) {
                 let _ = write!(stdout_write, "{:#}", err);
                 log::error!("Failed to connect ssh: {:#}", err);
@@ -430,6 +544,7 @@ fn connect_ssh_session(
     size: Arc<Mutex<TerminalSize>>,
     command_line: Option<String>,
     env: HashMap<String, String>,
+    problems: Vec<String>,
 ) -> anyhow::Result<()> {
     struct StdoutShim<'a> {
         size: Arc<Mutex<TerminalSize>>,
@@ -589,6 +704,10 @@ fn connect_ssh_session(
         }
     }
 
+    for problem in &problems {
+        shim.output_line(&format!("Warning: {}", problem))?;
+    }
+
     // Process authentication related events
     while let Ok(event) = smol::block_on(events.recv()) {
         match event {
@@ -640,7 +759,7 @@ fn connect_ssh_session(
                 smol::block_on(auth.answer(answers))?;
             }
             SessionEvent::Error(err) => {
-                shim.output_line(&format!("Error: {}", err))?;
+                shim.output_line(&explain_ssh_error(&err, &problems))?;
             }
             SessionEvent::HostVerificationFailed(failed) => {
                 let message = format_host_verification_for_terminal(failed);
@@ -704,6 +823,7 @@ impl Domain for RemoteSshDomain {
         size: TerminalSize,
         command: Option<CommandBuilder>,
         command_dir: Option<String>,
+        _workspace: &str,
     ) -> anyhow::Result<Arc<dyn Pane>> {
         let pane_id = alloc_pane_id();
 
@@ -1146,3 +1266,71 @@ impl std::io::Read for PtyReader {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn domain() -> SshDomain {
+        SshDomain {
+            name: "SSH:test".to_string(),
+            remote_address: "test".to_string(),
+            ..SshDomain::default()
+        }
+    }
+
+    #[test]
+    fn identity_files_expand_tilde() {
+        let dom = SshDomain {
+            identity_files: vec!["~/.ssh/id_work".into(), "/etc/ssh/id_shared".into()],
+            ..domain()
+        };
+        assert_eq!(
+            identity_files(&dom),
+            vec![
+                config::HOME_DIR.join(".ssh/id_work"),
+                PathBuf::from("/etc/ssh/id_shared")
+            ]
+        );
+    }
+
+    #[test]
+    fn problems() {
+        let dom = SshDomain {
+            identity_files: vec!["/nonexistent/kaku/id_rsa".into()],
+            forward_agent: true,
+            ..domain()
+        };
+        let mut ssh_config = ConfigMap::new();
+        ssh_config.insert("wezterm_ssh_backend".to_string(), "ssh2".to_string());
+        assert_eq!(
+            ssh_domain_problems(&dom, &ssh_config),
+            vec![
+                "Identity file /nonexistent/kaku/id_rsa configured for SSH:test does not exist"
+                    .to_string(),
+                "forward_agent is enabled for SSH:test, but agent forwarding requires \
+                 the LibSsh ssh_backend"
+                    .to_string(),
+            ]
+        );
+
+        ssh_config.insert("wezterm_ssh_backend".to_string(), "libssh".to_string());
+        ssh_config.insert(
+            "identityagent".to_string(),
+            "/nonexistent/kaku/agent.sock".to_string(),
+        );
+        let dom = SshDomain {
+            identity_files: vec![],
+            ..dom
+        };
+        #[cfg(unix)]
+        assert_eq!(
+            ssh_domain_problems(&dom, &ssh_config),
+            vec![
+                "forward_agent is enabled for SSH:test, but the ssh agent socket \
+                 /nonexistent/kaku/agent.sock does not exist"
+                    .to_string()
+            ]
+        );
+    }
+}
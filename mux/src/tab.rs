@@ -291,6 +291,10 @@ fn pane_tree(
                 left_col,
                 top_row,
                 tty_name: pane.tty_name(),
+                pid: pane.process_id(),
+                is_input_locked: Mux::try_get()
+                    .map(|mux| mux.is_pane_input_locked(pane.pane_id()))
+                    .unwrap_or(false),
             })
         }
     }
@@ -691,6 +695,13 @@ impl Tab {
         self.inner.lock().adjust_pane_size(direction, amount)
     }
 
+    /// Resizes the splits around the specified pane so that it is
+    /// `cols` wide and `rows` tall, as far as the tab allows.
+    /// A dimension that is None is left unchanged.
+    pub fn set_pane_size(&self, pane_id: PaneId, cols: Option<usize>, rows: Option<usize>) {
+        self.inner.lock().set_pane_size(pane_id, cols, rows)
+    }
+
     /// Activate an adjacent pane in the specified direction.
     /// In cases where there are multiple adjacent panes in the
     /// intended direction, we take the pane that has the largest
@@ -1657,6 +1668,88 @@ impl TabInner {
         }
     }
 
+    fn set_pane_size(&mut self, pane_id: PaneId, cols: Option<usize>, rows: Option<usize>) {
+        if self.zoomed.is_some() {
+            return;
+        }
+        let pane_index = match self
+            .iter_panes_ignoring_zoom()
+            .iter()
+            .find(|pos| pos.pane.pane_id() == pane_id)
+        {
+            Some(pos) => pos.index,
+            None => return,
+        };
+        if let Some(cols) = cols {
+            self.set_pane_size_in_direction(pane_index, SplitDirection::Horizontal, cols);
+        }
+        if let Some(rows) = rows {
+            self.set_pane_size_in_direction(pane_index, SplitDirection::Vertical, rows);
+        }
+    }
+
+    fn set_pane_size_in_direction(
+        &mut self,
+        pane_index: usize,
+        split_direction: SplitDirection,
+        size: usize,
+    ) {
+        let mut cursor = self.pane.take().unwrap().cursor();
+        let mut index = 0;
+
+        // Position cursor on the leaf
+        loop {
+            if cursor.is_leaf() {
+                if index == pane_index {
+                    break;
+                }
+                index += 1;
+            }
+            match cursor.preorder_next() {
+                Ok(c) => cursor = c,
+                Err(c) => {
+                    self.pane.replace(c.tree());
+                    return;
+                }
+            }
+        }
+
+        // Any splits between the pane and the nearest split in the
+        // desired direction are perpendicular to it, so the pane is
+        // as big as the side of that split that contains it
+        loop {
+            let branch = cursor.path_to_root().next().map(|(branch, _)| branch);
+            match cursor.go_up() {
+                Ok(mut c) => {
+                    if let Ok(Some(node)) = c.node_mut() {
+                        if node.direction == split_direction {
+                            let (side, sign) = if branch == Some(PathBranch::IsRight) {
+                                (node.second, -1)
+                            } else {
+                                (node.first, 1)
+                            };
+                            let current = match split_direction {
+                                SplitDirection::Horizontal => side.cols,
+                                SplitDirection::Vertical => side.rows,
+                            };
+                            let delta = (size as isize - current as isize) * sign;
+                            self.adjust_node_at_cursor(&mut c, delta);
+                            self.cascade_size_from_cursor(c);
+                            return;
+                        }
+                    }
+
+                    cursor = c;
+                }
+
+                Err(c) => {
+                    self.pane.replace(c.tree());
+                    return;
+                }
+            }
+        }
+    }
+
     fn activate_pane_direction(&mut self, direction: PaneDirection) {
         if self.zoomed.is_some() {
             if !configuration().unzoom_on_switch_pane {
@@ -2371,6 +2464,8 @@ pub struct PaneEntry {
     pub top_row: usize,
     pub left_col: usize,
     pub tty_name: Option<String>,
+    pub pid: Option<u32>,
+    pub is_input_locked: bool,
 }
 
 #[derive(Deserialize, Clone, Serialize, PartialEq, Debug)]
@@ -2406,7 +2501,7 @@ impl Into<String> for SerdeUrl {
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use crate::renderable::*;
     use parking_lot::{MappedMutexGuard, Mutex};
@@ -2417,13 +2512,13 @@ mod test {
     use wezterm_term::color::ColorPalette;
     use wezterm_term::{KeyCode, KeyModifiers, Line, MouseEvent, StableRowIndex};
 
-    struct FakePane {
+    pub(crate) struct FakePane {
         id: PaneId,
         size: Mutex<TerminalSize>,
     }
 
     impl FakePane {
-        fn new(id: PaneId, size: TerminalSize) -> Arc<dyn Pane> {
+        pub(crate) fn new(id: PaneId, size: TerminalSize) -> Arc<dyn Pane> {
             Arc::new(Self {
                 id,
                 size: Mutex::new(size),
@@ -2727,6 +2822,58 @@ mod test {
         assert_eq!(600, panes[2].pixel_height);
     }
 
+    #[test]
+    fn set_pane_size() {
+        let size = TerminalSize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 800,
+            pixel_height: 600,
+            dpi: 96,
+        };
+
+        // 1 on the left; 2 above 3 on the right
+        let tab = Tab::new(&size);
+        tab.assign_pane(&FakePane::new(1, size));
+        tab.split_and_insert(0, SplitRequest::default(), FakePane::new(2, size))
+            .unwrap();
+        tab.split_and_insert(
+            1,
+            SplitRequest {
+                direction: SplitDirection::Vertical,
+                ..Default::default()
+            },
+            FakePane::new(3, size),
+        )
+        .unwrap();
+
+        let sizes = || -> Vec<(usize, usize)> {
+            tab.iter_panes()
+                .iter()
+                .map(|pos| (pos.width, pos.height))
+                .collect()
+        };
+        assert_eq!(sizes(), vec![(39, 24), (40, 11), (40, 12)]);
+
+        tab.set_pane_size(1, Some(50), None);
+        assert_eq!(sizes(), vec![(50, 24), (29, 11), (29, 12)]);
+
+        // The vertical split between 2 and 3 is skipped over to find
+        // the split that sets the width of 3
+        tab.set_pane_size(3, Some(20), None);
+        assert_eq!(sizes(), vec![(59, 24), (20, 11), (20, 12)]);
+
+        tab.set_pane_size(2, None, Some(5));
+        assert_eq!(sizes(), vec![(59, 24), (20, 5), (20, 18)]);
+        tab.set_pane_size(3, Some(30), Some(8));
+        assert_eq!(sizes(), vec![(49, 24), (30, 15), (30, 8)]);
+
+        // Pane 1 spans the height of the tab, and there is no pane 4
+        tab.set_pane_size(1, None, Some(10));
+        tab.set_pane_size(4, Some(10), Some(10));
+        assert_eq!(sizes(), vec![(49, 24), (30, 15), (30, 8)]);
+    }
+
     fn is_send_and_sync<T: Send + Sync>() -> bool {
         true
     }
@@ -0,0 +1,115 @@
+//! Makes the Windows OpenSSH agent reachable from WSL panes.
+//!
+//! The agent listens on a named pipe, which processes in a WSL2
+//! distro cannot open.  To bridge the gap, the pane's command is
+//! wrapped in a small shell script that starts `socat` listening
+//! on a unix socket inside the distro; each connection runs
+//! `kaku ssh-agent-relay` through WSL interop, which forwards the
+//! agent protocol between its stdio and the named pipe.
+use config::WslDomain;
+use portable_pty::CommandBuilder;
+use std::ffi::OsString;
+use std::path::Path;
+
+pub const DEFAULT_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
+const WRAPPER_SCRIPT: &str = r#"
+sock="${XDG_RUNTIME_DIR:-/tmp}/kaku-ssh-agent-$(id -u).sock"
+if command -v socat >/dev/null 2>&1; then
+  SSH_AUTH_SOCK="$sock" ssh-add -l >/dev/null 2>&1
+  rc=$?
+  if [ $rc -ne 0 ] && [ $rc -ne 1 ]; then
+    rm -f "$sock"
+    export KAKU_RELAY KAKU_AGENT_PIPE
+    (setsid socat UNIX-LISTEN:"$sock",fork,umask=077 \
+      SYSTEM:'exec "$KAKU_RELAY" ssh-agent-relay --pipe "$KAKU_AGENT_PIPE"' \
+      >/dev/null 2>&1 &)
+  fi
+  export SSH_AUTH_SOCK="$sock"
+else
+  echo "kaku: install socat in this distro to use forward_ssh_agent" >&2
+fi
+if [ $# -eq 0 ]; then
+  exec "${SHELL:-/bin/sh}" -l
+fi
+exec "$@"
+"#;
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Translates a Windows path such as `C:\Program Files\Kaku\kaku.exe`
+/// into the path at which it is visible in WSL.
+fn windows_path_to_wsl(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    let mut chars = path.chars();
+    let drive = chars.next()?;
+    if !drive.is_ascii_alphabetic() || chars.next()? != ':' {
+        return None;
+    }
+    Some(format!(
+        "/mnt/{}{}",
+        drive.to_ascii_lowercase(),
+        chars.as_str().replace('\\', "/")
+    ))
+}
+
+/// Returns the path of the `kaku` cli executable, as seen from WSL
+fn relay_executable() -> anyhow::Result<String> {
+    let exe = std::env::current_exe()?;
+    let cli = exe.with_file_name("kaku.exe");
+    windows_path_to_wsl(&cli)
+        .ok_or_else(|| anyhow::anyhow!("cannot translate {} for use in WSL", cli.display()))
+}
+
+/// Rewrites `args`, the command to run in the distro (which may be
+/// empty to run the default shell), so that it runs with
+/// SSH_AUTH_SOCK pointing at a relay to the Windows agent.
+pub fn wrap_command_for_agent(
+    wsl: &WslDomain,
+    args: Vec<OsString>,
+) -> anyhow::Result<Vec<OsString>> {
+    let pipe = wsl.ssh_agent_pipe.as_deref().unwrap_or(DEFAULT_AGENT_PIPE);
+    let script = format!(
+        "KAKU_RELAY={}\nKAKU_AGENT_PIPE={}\n{}",
+        shell_quote(&relay_executable()?),
+        shell_quote(pipe),
+        WRAPPER_SCRIPT
+    );
+    let mut argv: Vec<OsString> = vec!["sh".into(), "-c".into(), script.into(), "sh".into()];
+    argv.extend(args);
+    Ok(argv)
+}
+
+/// Arranges for the SSH_AUTH_SOCK of the Windows environment to be
+/// passed through to the distro via WSLENV
+pub fn share_auth_sock(cmd: &mut CommandBuilder) {
+    if cmd.get_env("SSH_AUTH_SOCK").is_none() {
+        return;
+    }
+    let wslenv = match cmd.get_env("WSLENV").and_then(|v| v.to_str()) {
+        Some(existing) if !existing.is_empty() => format!("{existing}:SSH_AUTH_SOCK/p"),
+        _ => "SSH_AUTH_SOCK/p".to_string(),
+    };
+    cmd.env("WSLENV", wslenv);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn translate_paths() {
+        assert_eq!(
+            windows_path_to_wsl(Path::new(r"C:\Program Files\Kaku\kaku.exe")).as_deref(),
+            Some("/mnt/c/Program Files/Kaku/kaku.exe")
+        );
+        assert_eq!(windows_path_to_wsl(Path::new("/usr/bin/kaku")), None);
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(shell_quote(r"\\.\pipe\it's"), r"'\\.\pipe\it'\''s'");
+    }
+}
@@ -12,6 +12,19 @@ pub enum NewlineCanon {
     CarriageReturnAndLineFeed,
 }
 
+/// The finest granularity of mouse coordinates that are reported
+/// to applications that request mouse reporting
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MouseReportingGranularity {
+    /// Applications may enable SGR-Pixels (1016) reporting to receive
+    /// the pointer position in pixels
+    #[default]
+    Pixel,
+    /// Requests for SGR-Pixels (1016) reporting are declined, so
+    /// that the pointer position is only ever reported in cells
+    Cell,
+}
+
 impl NewlineCanon {
     fn target(self) -> Option<&'static str> {
         match self {
@@ -223,6 +236,10 @@ pub trait TerminalConfiguration: Downcast + std::fmt::Debug + Send + Sync {
     fn log_unknown_escape_sequences(&self) -> bool {
         false
     }
+
+    fn mouse_reporting_granularity(&self) -> MouseReportingGranularity {
+        MouseReportingGranularity::default()
+    }
 }
 impl_downcast!(TerminalConfiguration);
 
@@ -44,8 +44,17 @@ pub struct Screen {
     pub dpi: u32,
 
     pub(crate) saved_cursor: Option<SavedCursor>,
+
+    /// Lines before this index may not have been rewrapped to
+    /// `physical_cols` yet; see `reflow_pending_lines`
+    pending_reflow: usize,
 }
 
+/// When there are more lines than this, resizing only rewraps the
+/// lines around the viewport and leaves the rest of the scrollback
+/// to be rewrapped incrementally via `reflow_pending_lines`
+const SYNC_REWRAP_LINES: usize = 10_000;
+
 fn scrollback_size(config: &Arc<dyn TerminalConfiguration>, allow_scrollback: bool) -> usize {
     if allow_scrollback {
         config.scrollback_size()
@@ -86,6 +95,7 @@ impl Screen {
             dpi: size.dpi,
             keyboard_stack: vec![],
             saved_cursor: None,
+            pending_reflow: 0,
         }
     }
 
@@ -97,20 +107,23 @@ impl Screen {
         scrollback_size(&self.config, self.allow_scrollback)
     }
 
-    fn rewrap_lines(
+    /// Joins wrapped physical `lines` into logical lines and splits
+    /// them again at `physical_cols`, appending the results to
+    /// `self.lines`.  `cursor` is the x position and index within
+    /// `lines` of the cursor, if it is among them, and the adjusted
+    /// position and index into `self.lines` is returned.
+    fn rewrap_into(
         &mut self,
+        lines: Vec<Line>,
         physical_cols: usize,
-        physical_rows: usize,
-        cursor_x: usize,
-        cursor_y: PhysRowIndex,
+        cursor: Option<(usize, usize)>,
         seqno: SequenceNo,
-    ) -> (usize, PhysRowIndex) {
-        let mut rewrapped = VecDeque::new();
+    ) -> Option<(usize, PhysRowIndex)> {
         let mut logical_line: Option<Line> = None;
         let mut logical_cursor_x: Option<usize> = None;
-        let mut adjusted_cursor = (cursor_x, cursor_y);
+        let mut adjusted_cursor = None;
 
-        for (phys_idx, mut line) in self.lines.drain(..).enumerate() {
+        for (idx, mut line) in lines.into_iter().enumerate() {
             line.update_last_change_seqno(seqno);
             let was_wrapped = line.last_cell_was_wrapped();
 
@@ -118,15 +131,16 @@ impl Screen {
                 line.set_last_cell_was_wrapped(false, seqno);
             }
 
+            let cursor_x = cursor.filter(|&(_, y)| y == idx).map(|(x, _)| x);
             let line = match logical_line.take() {
                 None => {
-                    if phys_idx == cursor_y {
+                    if let Some(cursor_x) = cursor_x {
                         logical_cursor_x = Some(cursor_x);
                     }
                     line
                 }
                 Some(mut prior) => {
-                    if phys_idx == cursor_y {
+                    if let Some(cursor_x) = cursor_x {
                         logical_cursor_x = Some(cursor_x + prior.len());
                     }
                     prior.append_line(line, seqno);
@@ -142,37 +156,72 @@ impl Screen {
             if let Some(x) = logical_cursor_x.take() {
                 let num_lines = x / physical_cols;
                 let last_x = x - (num_lines * physical_cols);
-                adjusted_cursor = (last_x, rewrapped.len() + num_lines);
+                let mut adjusted = (last_x, self.lines.len() + num_lines);
 
                 // Special case: if the cursor lands in column zero, we'll
                 // lose track of its logical association with the wrapped
                 // line and it won't resize with the line correctly.
                 // Put it back on the prior line. The cursor is now
                 // technically outside of the viewport width.
-                if adjusted_cursor.0 == 0 && adjusted_cursor.1 > 0 {
+                if adjusted.0 == 0 && adjusted.1 > 0 {
                     if physical_cols < self.physical_cols {
                         // getting smaller: preserve its original position
                         // on the prior line
-                        adjusted_cursor.0 = cursor_x;
+                        adjusted.0 = cursor.map(|(x, _)| x).unwrap_or(0);
                     } else {
                         // getting larger; we were most likely in column 1
                         // or somewhere close. Jump to the end of the
                         // prior line.
-                        adjusted_cursor.0 = physical_cols;
+                        adjusted.0 = physical_cols;
                     }
-                    adjusted_cursor.1 -= 1;
+                    adjusted.1 -= 1;
                 }
+                adjusted_cursor = Some(adjusted);
             }
 
             if line.len() <= physical_cols {
-                rewrapped.push_back(line);
+                self.lines.push_back(line);
             } else {
                 for line in line.wrap(physical_cols, seqno) {
-                    rewrapped.push_back(line);
+                    self.lines.push_back(line);
                 }
             }
         }
-        self.lines = rewrapped;
+
+        adjusted_cursor
+    }
+
+    /// Returns the index of the first line that needs to be rewrapped
+    /// immediately when resizing; a couple of screenfuls above the
+    /// viewport, but always including the cursor, and adjusted to
+    /// fall at the start of a logical line.
+    fn incremental_rewrap_start(&self, physical_rows: usize, cursor_y: PhysRowIndex) -> usize {
+        if self.lines.len() <= SYNC_REWRAP_LINES {
+            return 0;
+        }
+        let rows = physical_rows.max(self.physical_rows);
+        let mut start = self.lines.len().saturating_sub(rows * 3).min(cursor_y);
+        while start > 0 && self.lines[start - 1].last_cell_was_wrapped() {
+            start -= 1;
+        }
+        start
+    }
+
+    fn rewrap_lines(
+        &mut self,
+        physical_cols: usize,
+        physical_rows: usize,
+        cursor_x: usize,
+        cursor_y: PhysRowIndex,
+        seqno: SequenceNo,
+    ) -> (usize, PhysRowIndex) {
+        let start = self.incremental_rewrap_start(physical_rows, cursor_y);
+        let lines: Vec<Line> = self.lines.drain(start..).collect();
+        let cursor = cursor_y.checked_sub(start).map(|y| (cursor_x, y));
+        let adjusted_cursor = self
+            .rewrap_into(lines, physical_cols, cursor, seqno)
+            .unwrap_or((cursor_x, cursor_y));
+        self.pending_reflow = start;
 
         // If we resized narrower and generated additional lines,
         // we may need to scroll the lines to make room.  However,
@@ -189,6 +238,49 @@ impl Screen {
         adjusted_cursor
     }
 
+    /// Returns true if some of the scrollback was left at its prior
+    /// width by a resize and still needs to be rewrapped
+    pub fn has_pending_reflow(&self) -> bool {
+        self.pending_reflow > 0
+    }
+
+    /// Rewraps up to around `max_lines` of the scrollback that a
+    /// resize left at its prior width, working upwards from the
+    /// viewport so that the most recent history is fixed up first.
+    /// Returns true if there is more work remaining.
+    pub fn reflow_pending_lines(&mut self, max_lines: usize, seqno: SequenceNo) -> bool {
+        let mut end = self.pending_reflow.min(self.lines.len());
+        if end == 0 {
+            self.pending_reflow = 0;
+            return false;
+        }
+        while end < self.lines.len() && self.lines[end - 1].last_cell_was_wrapped() {
+            end += 1;
+        }
+        let mut start = end.saturating_sub(max_lines.max(1));
+        while start > 0 && self.lines[start - 1].last_cell_was_wrapped() {
+            start -= 1;
+        }
+
+        let tail = self.lines.split_off(end);
+        let lines: Vec<Line> = self.lines.drain(start..).collect();
+        let num_lines = lines.len();
+        self.rewrap_into(lines, self.physical_cols, None, seqno);
+
+        // If the number of lines changed then the content of all of
+        // the subsequent StableRowIndex values has moved
+        let moved = self.lines.len() != start + num_lines;
+        for mut line in tail {
+            if moved {
+                line.update_last_change_seqno(seqno);
+            }
+            self.lines.push_back(line);
+        }
+
+        self.pending_reflow = start;
+        start > 0
+    }
+
     /// Resize the physical, viewable portion of the screen
     pub fn resize(
         &mut self,
@@ -731,6 +823,7 @@ impl Screen {
 
         if remove_idx == 0 && scrollback_ok {
             self.stable_row_index_offset += lines_removed;
+            self.pending_reflow = self.pending_reflow.saturating_sub(lines_removed);
         }
 
         for _ in 0..to_add {
@@ -762,6 +855,7 @@ impl Screen {
     pub fn erase_scrollback(&mut self) {
         let len = self.lines.len();
         let to_clear = len - self.physical_rows;
+        self.pending_reflow = self.pending_reflow.saturating_sub(to_clear);
         for _ in 0..to_clear {
             self.lines.pop_front();
             if self.allow_scrollback {
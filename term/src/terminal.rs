@@ -71,6 +71,11 @@ pub enum Alert {
     OutputSinceFocusLost,
     /// A change to the progress bar state
     Progress(Progress),
+    /// The shell integration reported that a command finished,
+    /// with the given exit status
+    CommandComplete {
+        status: i32,
+    },
 }
 
 pub trait AlertHandler: Send + Sync {
@@ -6,7 +6,45 @@ use log::error;
 use wezterm_cell::image::ImageDataType;
 use wezterm_escape_parser::osc::ITermFileData;
 
+/// Limits the memory that an application can make us hold on to
+/// while sending a file in parts
+const MAX_MULTIPART_FILE_SIZE: usize = 128 * 1024 * 1024;
+
 impl TerminalState {
+    /// Starts receiving a file whose data will arrive via FilePart
+    pub(crate) fn begin_multipart_image(&mut self, image: ITermFileData) {
+        if self.iterm_multipart_file.is_some() {
+            log::warn!("MultipartFile received before the previous FileEnd; discarding it");
+        }
+        self.iterm_multipart_file.replace(image);
+    }
+
+    pub(crate) fn add_multipart_image_data(&mut self, data: Vec<u8>) {
+        let image = match self.iterm_multipart_file.as_mut() {
+            Some(image) => image,
+            None => {
+                log::warn!("Ignoring FilePart received without a MultipartFile");
+                return;
+            }
+        };
+        if image.data.len() + data.len() > MAX_MULTIPART_FILE_SIZE {
+            error!(
+                "Discarding multipart file name={:?} as it exceeds {} bytes",
+                image.name, MAX_MULTIPART_FILE_SIZE
+            );
+            self.iterm_multipart_file.take();
+            return;
+        }
+        image.data.extend_from_slice(&data);
+    }
+
+    pub(crate) fn end_multipart_image(&mut self) {
+        match self.iterm_multipart_file.take() {
+            Some(image) => self.set_image(image),
+            None => log::warn!("Ignoring FileEnd received without a MultipartFile"),
+        }
+    }
+
     pub(crate) fn set_image(&mut self, image: ITermFileData) {
         if !image.inline {
             if let Some(handler) = &self.download_handler {
@@ -261,12 +261,92 @@ impl TerminalState {
                     self.kitty_img.remove_data_for_id(image_id);
                 }
             }
+            KittyImage::Delete {
+                what:
+                    KittyImageDelete::ByImageNumber {
+                        image_number,
+                        placement_id,
+                        delete,
+                    },
+                verbosity,
+            } => {
+                let image_id = match self.kitty_img.number_to_id.get(&image_number) {
+                    Some(id) => *id,
+                    None => {
+                        self.kitty_send_response(
+                            verbosity,
+                            false,
+                            None,
+                            Some(image_number),
+                            "ENOENT".to_string(),
+                        );
+                        return Ok(());
+                    }
+                };
+
+                self.kitty_remove_placement(image_id, placement_id);
+
+                if delete {
+                    self.kitty_img.remove_data_for_id(image_id);
+                    self.kitty_img.number_to_id.remove(&image_number);
+                }
+            }
             KittyImage::Delete {
                 what: KittyImageDelete::All { delete },
                 verbosity: _,
             } => {
                 self.kitty_remove_all_placements(delete);
             }
+            KittyImage::Delete {
+                what: KittyImageDelete::AtCursorPosition { delete },
+                verbosity: _,
+            } => {
+                let row = self.screen().visible_row_to_stable_row(self.cursor.y);
+                let col = self.cursor.x;
+                self.kitty_remove_placements_where(delete, |info| {
+                    info.contains_row(row) && info.contains_col(col)
+                });
+            }
+            KittyImage::Delete {
+                what: KittyImageDelete::DeleteAt { x, y, delete },
+                verbosity: _,
+            } => {
+                let row = self.kitty_cell_y_to_stable_row(y);
+                let col = kitty_cell_x_to_col(x);
+                self.kitty_remove_placements_where(delete, |info| {
+                    info.contains_row(row) && info.contains_col(col)
+                });
+            }
+            KittyImage::Delete {
+                what: KittyImageDelete::DeleteAtZ { x, y, z, delete },
+                verbosity: _,
+            } => {
+                let row = self.kitty_cell_y_to_stable_row(y);
+                let col = kitty_cell_x_to_col(x);
+                self.kitty_remove_placements_where(delete, |info| {
+                    info.z_index == z && info.contains_row(row) && info.contains_col(col)
+                });
+            }
+            KittyImage::Delete {
+                what: KittyImageDelete::DeleteColumn { x, delete },
+                verbosity: _,
+            } => {
+                let col = kitty_cell_x_to_col(x);
+                self.kitty_remove_placements_where(delete, |info| info.contains_col(col));
+            }
+            KittyImage::Delete {
+                what: KittyImageDelete::DeleteRow { y, delete },
+                verbosity: _,
+            } => {
+                let row = self.kitty_cell_y_to_stable_row(y);
+                self.kitty_remove_placements_where(delete, |info| info.contains_row(row));
+            }
+            KittyImage::Delete {
+                what: KittyImageDelete::DeleteZ { z, delete },
+                verbosity: _,
+            } => {
+                self.kitty_remove_placements_where(delete, |info| info.z_index == z);
+            }
             KittyImage::Delete { what, verbosity } => {
                 log::warn!("unhandled KittyImage::Delete {:?} {:?}", what, verbosity);
             }
@@ -337,6 +417,53 @@ impl TerminalState {
         );
     }
 
+    /// Removes the placements for which `pred` returns true.
+    /// If `delete` is true, the data for the images that no longer
+    /// have any placements is also released.
+    fn kitty_remove_placements_where<F: Fn(&PlacementInfo) -> bool>(
+        &mut self,
+        delete: bool,
+        pred: F,
+    ) {
+        let matched: Vec<(u32, Option<u32>)> = self
+            .kitty_img
+            .placements
+            .iter()
+            .filter(|(_, info)| pred(info))
+            .map(|(key, _)| *key)
+            .collect();
+
+        for (image_id, placement_id) in &matched {
+            if let Some(info) = self
+                .kitty_img
+                .placements
+                .remove(&(*image_id, *placement_id))
+            {
+                self.kitty_remove_placement_from_model(*image_id, *placement_id, info);
+            }
+        }
+
+        if delete {
+            for (image_id, _) in matched {
+                let referenced = self
+                    .kitty_img
+                    .placements
+                    .keys()
+                    .any(|(id, _)| *id == image_id);
+                if !referenced {
+                    self.kitty_img.remove_data_for_id(image_id);
+                }
+            }
+        }
+    }
+
+    /// Kitty expresses cell coordinates as 1-based positions
+    /// on the visible screen
+    fn kitty_cell_y_to_stable_row(&self, y: u32) -> StableRowIndex {
+        self.screen()
+            .visible_row_to_stable_row(y.saturating_sub(1) as i64)
+    }
+
     pub(crate) fn kitty_remove_all_placements(&mut self, delete: bool) {
         for ((image_id, p), info) in std::mem::take(&mut self.kitty_img.placements).into_iter() {
             self.kitty_remove_placement_from_model(image_id, p, info);
@@ -596,10 +723,12 @@ impl TerminalState {
         let mut anim = anim.data();
         let x = frame.x.unwrap_or(0);
         let y = frame.y.unwrap_or(0);
-        let frame_gap = Duration::from_millis(match frame.duration_ms {
-            None | Some(0) => 40,
-            Some(n) => n.into(),
-        });
+        let frame_gap = match frame.duration_ms {
+            None | Some(0) => Duration::from_millis(40),
+            // A gapless frame
+            Some(n) if n < 0 => Duration::ZERO,
+            Some(n) => Duration::from_millis(n as u64),
+        };
 
         match &mut *anim {
             ImageDataType::EncodedLease(_) | ImageDataType::EncodedFile(_) => {
@@ -733,6 +862,9 @@ impl TerminalState {
 
                     drop(anim_img);
                     hashes[frame_no - 1] = ImageDataType::hash_bytes(&frames[frame_no - 1]);
+                    if frame.duration_ms.is_some() {
+                        durations[frame_no - 1] = frame_gap;
+                    }
                 }
             }
         }
@@ -924,6 +1056,10 @@ impl TerminalState {
     }
 }
 
+fn kitty_cell_x_to_col(x: u32) -> usize {
+    x.saturating_sub(1) as usize
+}
+
 /// Make a copy of the source region.
 /// Ideally we wouldn't need this, but Rust's mutability rules
 /// make it very awkward to mutably reference a frame while
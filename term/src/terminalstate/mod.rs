@@ -3,7 +3,7 @@
 #![allow(clippy::range_plus_one)]
 use super::*;
 use crate::color::{ColorPalette, RgbColor};
-use crate::config::{BidiMode, NewlineCanon};
+use crate::config::{BidiMode, MouseReportingGranularity, NewlineCanon};
 use log::debug;
 use num_traits::ToPrimitive;
 use std::collections::HashMap;
@@ -22,6 +22,7 @@ use wezterm_escape_parser::csi::{
     EraseInLine, Mode, Sgr, TabulationClear, TerminalMode, TerminalModeCode, Window, XtSmGraphics,
     XtSmGraphicsAction, XtSmGraphicsItem, XtSmGraphicsStatus, XtermKeyModifierResource,
 };
+use wezterm_escape_parser::osc::ITermFileData;
 use wezterm_escape_parser::{OneBased, OperatingSystemCommand, CSI};
 use wezterm_surface::{CursorShape, CursorVisibility, SequenceNo};
 
@@ -370,6 +371,9 @@ pub struct TerminalState {
 
     image_cache: lru::LruCache<[u8; 32], Arc<ImageData>>,
     sixel_scrolls_right: bool,
+    /// The iTerm2 file whose data is being received via
+    /// MultipartFile/FilePart sequences
+    iterm_multipart_file: Option<ITermFileData>,
 
     user_vars: HashMap<String, String>,
 
@@ -580,6 +584,7 @@ impl TerminalState {
             term_version: term_version.to_string(),
             writer,
             image_cache: lru::LruCache::new(NonZeroUsize::new(16).unwrap()),
+            iterm_multipart_file: None,
             user_vars: HashMap::new(),
             kitty_img: Default::default(),
             seqno,
@@ -758,6 +763,19 @@ impl TerminalState {
         self.screen_mut().erase_scrollback();
     }
 
+    /// Rewraps up to around `max_lines` of the scrollback that was
+    /// left at its prior width by a resize of a terminal with a large
+    /// scrollback.  Returns true if there is more work remaining, in
+    /// which case the caller should call this again later.
+    pub fn reflow_pending_lines(&mut self, max_lines: usize) -> bool {
+        if !self.screen.screen.has_pending_reflow() {
+            return false;
+        }
+        self.increment_seqno();
+        let seqno = self.seqno;
+        self.screen.screen.reflow_pending_lines(max_lines, seqno)
+    }
+
     /// Returns true if the associated application has enabled any of the
     /// supported mouse reporting modes.
     /// This is useful for the hosting GUI application to decide how best
@@ -1870,8 +1888,12 @@ impl TerminalState {
                 );
             }
             Mode::SetDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::SGRPixelsMouse)) => {
-                self.mouse_encoding = MouseEncoding::SgrPixels;
-                self.last_mouse_move.take();
+                if self.config.mouse_reporting_granularity() == MouseReportingGranularity::Cell {
+                    log::debug!("ignoring SGR-Pixels mouse request; granularity is Cell");
+                } else {
+                    self.mouse_encoding = MouseEncoding::SgrPixels;
+                    self.last_mouse_move.take();
+                }
             }
             Mode::ResetDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::SGRPixelsMouse)) => {
                 self.mouse_encoding = MouseEncoding::X10;
@@ -1880,7 +1902,7 @@ impl TerminalState {
             Mode::QueryDecPrivateMode(DecPrivateMode::Code(DecPrivateModeCode::SGRPixelsMouse)) => {
                 self.decqrm_response(
                     mode,
-                    true,
+                    self.config.mouse_reporting_granularity() != MouseReportingGranularity::Cell,
                     match self.mouse_encoding {
                         MouseEncoding::SgrPixels => true,
                         _ => false,
@@ -715,6 +715,7 @@ impl<'a> Performer<'a> {
                 self.unicode_version_stack.clear();
                 self.suppress_initial_title_change = false;
                 self.accumulating_title.take();
+                self.iterm_multipart_file.take();
                 self.progress = Progress::default();
 
                 self.screen.full_reset();
@@ -826,6 +827,9 @@ impl<'a> Performer<'a> {
                     self.writer.flush().ok();
                 }
                 ITermProprietary::File(image) => self.set_image(*image),
+                ITermProprietary::MultipartFile(image) => self.begin_multipart_image(*image),
+                ITermProprietary::FilePart(data) => self.add_multipart_image_data(data),
+                ITermProprietary::FileEnd => self.end_multipart_image(),
                 ITermProprietary::SetUserVar { name, value } => {
                     self.user_vars.insert(name.clone(), value.clone());
                     if let Some(handler) = self.alert_handler.as_mut() {
@@ -898,8 +902,12 @@ impl<'a> Performer<'a> {
             }
 
             OperatingSystemCommand::FinalTermSemanticPrompt(
-                FinalTermSemanticPrompt::CommandStatus { .. },
-            ) => {}
+                FinalTermSemanticPrompt::CommandStatus { status, .. },
+            ) => {
+                if let Some(handler) = self.alert_handler.as_mut() {
+                    handler.alert(Alert::CommandComplete { status });
+                }
+            }
 
             OperatingSystemCommand::SystemNotification(message) => {
                 if let Some(handler) = self.alert_handler.as_mut() {
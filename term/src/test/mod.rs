@@ -45,12 +45,17 @@ struct TestTerm {
 #[derive(Debug)]
 struct TestTermConfig {
     scrollback: usize,
+    kitty_graphics: bool,
 }
 impl TerminalConfiguration for TestTermConfig {
     fn scrollback_size(&self) -> usize {
         self.scrollback
     }
 
+    fn enable_kitty_graphics(&self) -> bool {
+        self.kitty_graphics
+    }
+
     fn color_palette(&self) -> ColorPalette {
         ColorPalette::default()
     }
@@ -58,6 +63,17 @@ impl TerminalConfiguration for TestTermConfig {
 
 impl TestTerm {
     fn new(height: usize, width: usize, scrollback: usize) -> Self {
+        Self::with_config(
+            height,
+            width,
+            TestTermConfig {
+                scrollback,
+                kitty_graphics: false,
+            },
+        )
+    }
+
+    fn with_config(height: usize, width: usize, config: TestTermConfig) -> Self {
         let _ = env_logger::Builder::new()
             .is_test(true)
             .filter_level(log::LevelFilter::Trace)
@@ -71,7 +87,7 @@ impl TestTerm {
                 pixel_height: height * 16,
                 dpi: 0,
             },
-            Arc::new(TestTermConfig { scrollback }),
+            Arc::new(config),
             "WezTerm",
             "O_o",
             Box::new(Vec::new()),
@@ -346,6 +362,52 @@ fn test_semantic_1539() {
     );
 }
 
+struct CollectAlerts(Arc<Mutex<Vec<Alert>>>);
+
+impl AlertHandler for CollectAlerts {
+    fn alert(&mut self, alert: Alert) {
+        self.0.lock().unwrap().push(alert);
+    }
+}
+
+#[test]
+fn test_command_status_alert() {
+    use wezterm_escape_parser::osc::FinalTermSemanticPrompt;
+    let alerts = Arc::new(Mutex::new(vec![]));
+    let mut term = TestTerm::new(5, 10, 0);
+    term.set_notification_handler(Box::new(CollectAlerts(Arc::clone(&alerts))));
+    let command_complete = |alerts: &Mutex<Vec<Alert>>| -> Vec<Alert> {
+        alerts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|alert| matches!(alert, Alert::CommandComplete { .. }))
+            .cloned()
+            .collect()
+    };
+
+    term.print(format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(
+            FinalTermSemanticPrompt::MarkEndOfInputAndStartOfOutput { aid: None }
+        )
+    ));
+    term.print("output\r\n");
+    assert_eq!(command_complete(&alerts), vec![]);
+
+    term.print(format!(
+        "{}",
+        OperatingSystemCommand::FinalTermSemanticPrompt(FinalTermSemanticPrompt::CommandStatus {
+            status: 2,
+            aid: None
+        })
+    ));
+    assert_eq!(
+        command_complete(&alerts),
+        vec![Alert::CommandComplete { status: 2 }]
+    );
+}
+
 #[test]
 fn test_semantic() {
     use wezterm_escape_parser::osc::FinalTermSemanticPrompt;
@@ -1022,6 +1084,35 @@ fn test_resize_wrap() {
     );
 }
 
+/// With a large scrollback, only the lines around the viewport
+/// are rewrapped by the resize and the rest are done incrementally.
+#[test]
+fn test_resize_incremental_reflow() {
+    let mut term = TestTerm::new(4, 4, 20_000);
+    for _ in 0..6000 {
+        term.print("aaaaaa\r\n");
+    }
+    term.resize(TerminalSize {
+        rows: 4,
+        cols: 8,
+        ..Default::default()
+    });
+    assert!(term.screen().has_pending_reflow());
+    assert_visible_contents(&term, file!(), line!(), &["aaaaaa", "aaaaaa", "aaaaaa", ""]);
+
+    while term.reflow_pending_lines(1000) {}
+    assert!(!term.screen().has_pending_reflow());
+    let screen = term.screen();
+    let lines = screen.lines_in_phys_range(0..screen.scrollback_rows());
+    assert_eq!(
+        lines.iter().filter(|l| l.as_str() == "aaaaaa").count(),
+        6000
+    );
+    assert!(lines
+        .iter()
+        .all(|l| l.as_str() == "aaaaaa" || l.is_whitespace()));
+}
+
 #[test]
 fn test_resize_wrap_issue_971() {
     const LINES: usize = 4;
@@ -1506,3 +1597,128 @@ fn test_primary_peek_no_leak_across_sessions() {
         "peek should not remain after multiple rounds"
     );
 }
+
+#[derive(Default)]
+struct RecordingDownloads {
+    saved: Mutex<Vec<(Option<String>, Vec<u8>)>>,
+}
+
+impl DownloadHandler for RecordingDownloads {
+    fn save_to_downloads(&self, name: Option<String>, data: Vec<u8>) {
+        self.saved.lock().unwrap().push((name, data));
+    }
+}
+
+#[test]
+fn test_iterm_multipart_file_discarded_on_full_reset() {
+    let mut term = TestTerm::new(5, 10, 0);
+    let downloads = Arc::new(RecordingDownloads::default());
+    let handler: Arc<dyn DownloadHandler> = downloads.clone();
+    term.set_download_handler(&handler);
+
+    // "a.txt" is YS50eHQ=, "abc" is YWJj and "def" is ZGVm
+    term.print("\x1b]1337;MultipartFile=name=YS50eHQ=;size=6\x07");
+    term.print("\x1b]1337;FilePart=YWJj\x07");
+    term.print("\x1bc");
+    term.print("\x1b]1337;FilePart=ZGVm\x07");
+    term.print("\x1b]1337;FileEnd\x07");
+    assert!(downloads.saved.lock().unwrap().is_empty());
+
+    term.print("\x1b]1337;MultipartFile=name=YS50eHQ=;size=6\x07");
+    term.print("\x1b]1337;FilePart=YWJj\x07");
+    term.print("\x1b]1337;FilePart=ZGVm\x07");
+    term.print("\x1b]1337;FileEnd\x07");
+    assert_eq!(
+        *downloads.saved.lock().unwrap(),
+        vec![(Some("a.txt".to_string()), b"abcdef".to_vec())]
+    );
+}
+
+/// Returns the ids of the kitty images shown on the screen
+fn kitty_image_ids(term: &Terminal) -> Vec<u32> {
+    let mut ids = std::collections::BTreeSet::new();
+    for line in term.screen().visible_lines() {
+        for cell in line.visible_cells() {
+            for image in cell.attrs().images().unwrap_or_default() {
+                ids.extend(image.image_id());
+            }
+        }
+    }
+    ids.into_iter().collect()
+}
+
+/// Places three 2x2 cell images on a 10x20 screen: image 1 at the top
+/// left, image 2 with z=5 at rows 5-6 and columns 10-11, and image
+/// number 7, which is assigned id 3, with z=-1 at rows 8-9 and
+/// columns 1-2.  Positions are 1-based, as they are in the delete
+/// commands.
+fn kitty_images_term() -> TestTerm {
+    let mut term = TestTerm::with_config(
+        10,
+        20,
+        TestTermConfig {
+            scrollback: 0,
+            kitty_graphics: true,
+        },
+    );
+    // Each image is 2x1 RGB pixels of zeroes, scaled to 2x2 cells
+    term.print("\x1b[1;1H\x1b_Ga=T,f=24,s=2,v=1,i=1,c=2,r=2,C=1,q=2;AAAAAAAA\x1b\\");
+    term.print("\x1b[5;10H\x1b_Ga=T,f=24,s=2,v=1,i=2,c=2,r=2,z=5,C=1,q=2;AAAAAAAA\x1b\\");
+    term.print("\x1b[8;1H\x1b_Ga=T,f=24,s=2,v=1,I=7,c=2,r=2,z=-1,C=1,q=2;AAAAAAAA\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2, 3]);
+    term
+}
+
+#[test]
+fn test_kitty_delete_at_cursor() {
+    let mut term = kitty_images_term();
+    term.print("\x1b[2;2H\x1b_Ga=d,d=c,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![2, 3]);
+    // The cursor is no longer over an image
+    term.print("\x1b_Ga=d,d=c,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![2, 3]);
+}
+
+#[test]
+fn test_kitty_delete_at_position() {
+    let mut term = kitty_images_term();
+    term.print("\x1b_Ga=d,d=p,x=11,y=6,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 3]);
+
+    // Only placements with the matching z-index are deleted
+    term.print("\x1b_Ga=d,d=q,x=1,y=1,z=5,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 3]);
+    term.print("\x1b_Ga=d,d=q,x=1,y=1,z=0,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![3]);
+}
+
+#[test]
+fn test_kitty_delete_column_row_and_z() {
+    let mut term = kitty_images_term();
+    term.print("\x1b_Ga=d,d=x,x=2,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![2]);
+
+    let mut term = kitty_images_term();
+    term.print("\x1b_Ga=d,d=y,y=9,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2]);
+
+    let mut term = kitty_images_term();
+    term.print("\x1b_Ga=d,d=z,z=5,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 3]);
+}
+
+#[test]
+fn test_kitty_delete_by_image_number() {
+    let mut term = kitty_images_term();
+    // Lowercase keeps the image data, so it can be placed again
+    term.print("\x1b_Ga=d,d=n,I=7,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2]);
+    term.print("\x1b[8;1H\x1b_Ga=p,I=7,c=2,r=2,C=1,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2, 3]);
+
+    // Uppercase releases the data along with the image number
+    term.print("\x1b_Ga=d,d=N,I=7,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2]);
+    term.print("\x1b[8;1H\x1b_Ga=p,I=7,c=2,r=2,C=1,q=2\x1b\\");
+    assert_eq!(kitty_image_ids(&term), vec![1, 2]);
+}
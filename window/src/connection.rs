@@ -1,9 +1,13 @@
 use crate::screen::Screens;
-use crate::{Appearance, Connection, GeometryOrigin, RequestedWindowGeometry, ResolvedGeometry};
+use crate::{
+    Appearance, Connection, GeometryOrigin, Modifiers, PhysKeyCode, RequestedWindowGeometry,
+    ResolvedGeometry, ScreenPoint,
+};
 use anyhow::Result as Fallible;
 use config::keyassignment::KeyAssignment;
 use config::DimensionContext;
 use std::cell::RefCell;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Mutex;
 
@@ -26,6 +30,17 @@ pub enum ApplicationEvent {
     /// The system wants to open a command in a new tab when possible
     OpenCommandScriptInTab(String),
     PerformKeyAssignment(KeyAssignment),
+    /// Monitors were connected, disconnected or rearranged
+    ScreensChanged,
+    /// The system is about to sleep
+    WillSleep,
+    /// The system woke up after a prior `WillSleep`
+    DidWake,
+    /// The user is logging out, or the system is shutting down
+    /// or restarting
+    WillPowerOff,
+    /// The hotkey registered via `set_global_hotkey` was pressed
+    GlobalHotKeyPressed,
 }
 
 pub trait ConnectionOps {
@@ -97,11 +112,39 @@ pub trait ConnectionOps {
     /// Perform the system beep/notification sound
     fn beep(&self) {}
 
+    /// Show `label` as a badge on the application icon, such as in
+    /// the macOS dock, or remove the badge when it is None
+    fn set_dock_badge(&self, _label: Option<&str>) {}
+
+    /// Start playing the sound file at `path`, with `volume` in the
+    /// range 0.0 to 1.0.  Decoding and playback happen in the
+    /// background; this returns as soon as playback has started.
+    fn play_sound(&self, _path: &Path, _volume: f32) -> anyhow::Result<()> {
+        anyhow::bail!("playing sounds is not supported on this platform");
+    }
+
     /// Returns information about the screens
     fn screens(&self) -> anyhow::Result<Screens> {
         anyhow::bail!("Unable to query screen information");
     }
 
+    /// Registers a hotkey that is reported via
+    /// `ApplicationEvent::GlobalHotKeyPressed` even while another
+    /// application has focus, replacing any prior hotkey.
+    /// None removes the hotkey.
+    fn set_global_hotkey(&self, hotkey: Option<(PhysKeyCode, Modifiers)>) -> anyhow::Result<()> {
+        match hotkey {
+            Some(_) => anyhow::bail!("global hotkeys are not supported on this platform"),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the position of the mouse pointer, in the same
+    /// coordinate system as the rects returned by `screens`
+    fn mouse_position(&self) -> Option<ScreenPoint> {
+        None
+    }
+
     fn resolve_geometry(&self, geometry: RequestedWindowGeometry) -> ResolvedGeometry {
         let bounds = match self.screens() {
             Ok(screens) => {
@@ -95,6 +95,13 @@ pub enum Appearance {
     DarkHighContrast,
 }
 
+impl Appearance {
+    /// Returns true for the dark and dark high contrast appearances
+    pub fn is_dark(self) -> bool {
+        matches!(self, Self::Dark | Self::DarkHighContrast)
+    }
+}
+
 impl std::string::ToString for Appearance {
     fn to_string(&self) -> String {
         match self {
@@ -185,6 +192,13 @@ pub enum WindowEvent {
     /// be repainted
     NeedRepaint,
 
+    /// Called when what was previously presented in the window may
+    /// no longer be shown, such as when the window becomes visible
+    /// again after being covered or minimized.  The next repaint
+    /// must draw everything rather than assume the last frame is
+    /// still on screen.
+    Exposed,
+
     /// Called when the window gains/loses focus
     FocusChanged(bool),
 
@@ -314,6 +328,13 @@ pub trait WindowOps {
     /// windows to move themselves (not Wayland).
     fn set_window_position(&self, _coords: ScreenPoint) {}
 
+    /// Changes the size and location of the window, evaluating
+    /// the geometry relative to the screen selected by its origin.
+    ///
+    /// This is only implemented on backends that allow
+    /// windows to move themselves (not Wayland).
+    fn set_window_geometry(&self, _geometry: RequestedWindowGeometry) {}
+
     /// inform the windowing system of the current textual
     /// cursor input location.  This is used primarily for
     /// the platform specific input method editor
@@ -341,6 +362,43 @@ pub trait WindowOps {
 
     fn toggle_fullscreen(&self) {}
 
+    /// Adds `tab` to the native window tab group of this window,
+    /// placing it just after this window's tab.
+    /// Only supported on macOS.
+    fn add_native_tab(&self, _tab: &Self)
+    where
+        Self: Sized,
+    {
+    }
+
+    /// Selects a tab in the native window tab group of this window.
+    /// With `relative`, `n` is relative to the current tab and wraps
+    /// around; otherwise it is an index, where negative values count
+    /// back from the last tab.
+    /// Only supported on macOS.
+    fn select_native_tab(&self, _n: isize, _relative: bool) {}
+
+    /// Moves this window's tab within its native window tab group.
+    /// With `relative`, `n` is relative to the current position and
+    /// is clamped to the group; otherwise it is an index.
+    /// Only supported on macOS.
+    fn move_native_tab(&self, _n: isize, _relative: bool) {}
+
+    /// Shows the overview of the tabs in the native window tab group
+    /// of this window.
+    /// Only supported on macOS.
+    fn show_native_tab_overview(&self) {}
+
+    /// Shows the window sliding down into `frame`, which is given in
+    /// the coordinate system of `Connection::screens`, above all other
+    /// windows and on every space.
+    /// Only supported on macOS.
+    fn show_dropdown(&self, _frame: ScreenRect, _duration: std::time::Duration) {}
+
+    /// Slides the window up out of view and then hides it.
+    /// Only supported on macOS.
+    fn hide_dropdown(&self, _duration: std::time::Duration) {}
+
     fn config_did_change(&self, _config: &config::ConfigHandle) {}
 
     /// Check if the window is in a zoom (maximize/restore) animation.
@@ -375,6 +433,14 @@ pub struct RequestedWindowGeometry {
     /// Specifies basis for evaluating x/y coords.
     /// Also applies to width/height when computing % based dimensions
     pub origin: GeometryOrigin,
+    /// true if width/height were explicitly requested by the user,
+    /// rather than derived from the configured rows and columns,
+    /// in which case a remembered window size should not be restored
+    pub explicit_size: bool,
+    /// When set, the size and position of the window are remembered
+    /// under this key, and a new window with the same key opens with
+    /// them.  Only honored on macOS.
+    pub persistence_key: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -4,7 +4,7 @@ use crate::macos::{nsstring, nsstring_to_str};
 use crate::menu::{Menu, MenuItem};
 use crate::{ApplicationEvent, Connection};
 use cocoa::appkit::{NSApp, NSApplicationTerminateReply, NSFilenamesPboardType, NSStringPboardType};
-use cocoa::base::id;
+use cocoa::base::{id, nil};
 use cocoa::foundation::NSInteger;
 use config::keyassignment::KeyAssignment;
 use config::WindowCloseConfirmation;
@@ -100,6 +100,69 @@ extern "C" fn application_did_finish_launching(this: &mut Object, _sel: Sel, _no
     unsafe {
         let () = msg_send![NSApp(), setServicesProvider: this as *mut Object];
         (*this).set_ivar("launched", YES);
+        observe_workspace_notifications(this);
+    }
+}
+
+/// Power and session notifications are posted to the workspace
+/// notification center rather than the default one
+unsafe fn observe_workspace_notifications(this: &mut Object) {
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    let center: id = msg_send![workspace, notificationCenter];
+    for (selector, name) in [
+        (
+            sel!(workspaceWillSleep:),
+            "NSWorkspaceWillSleepNotification",
+        ),
+        (sel!(workspaceDidWake:), "NSWorkspaceDidWakeNotification"),
+        (
+            sel!(workspaceWillPowerOff:),
+            "NSWorkspaceWillPowerOffNotification",
+        ),
+    ] {
+        let () = msg_send![center,
+            addObserver: this as *mut Object
+            selector: selector
+            name: *nsstring(name)
+            object: nil];
+    }
+}
+
+extern "C" fn workspace_will_sleep(_self: &mut Object, _sel: Sel, _notif: *mut Object) {
+    log::info!("system will sleep");
+    if config::configuration().checkpoint_state_on_sleep {
+        super::window::persist_key_window_state();
+    }
+    if let Some(conn) = Connection::get() {
+        conn.dispatch_app_event(ApplicationEvent::WillSleep);
+    }
+}
+
+extern "C" fn workspace_did_wake(_self: &mut Object, _sel: Sel, _notif: *mut Object) {
+    log::info!("system did wake");
+    if let Some(conn) = Connection::get() {
+        conn.dispatch_app_event(ApplicationEvent::DidWake);
+    }
+}
+
+extern "C" fn workspace_will_power_off(_self: &mut Object, _sel: Sel, _notif: *mut Object) {
+    log::info!("session is ending");
+    if config::configuration().checkpoint_state_on_sleep {
+        super::window::persist_key_window_state();
+    }
+    if let Some(conn) = Connection::get() {
+        conn.dispatch_app_event(ApplicationEvent::WillPowerOff);
+    }
+}
+
+extern "C" fn application_did_change_screen_parameters(
+    _self: &mut Object,
+    _sel: Sel,
+    _notif: *mut Object,
+) {
+    log::debug!("application_did_change_screen_parameters");
+    if let Some(conn) = Connection::get() {
+        conn.dispatch_app_event(ApplicationEvent::ScreensChanged);
     }
 }
 
@@ -408,6 +471,23 @@ fn get_class() -> &'static Class {
                 sel!(showPreferencesWindow:),
                 show_settings_window as extern "C" fn(&mut Object, Sel, *mut Object),
             );
+            cls.add_method(
+                sel!(applicationDidChangeScreenParameters:),
+                application_did_change_screen_parameters
+                    as extern "C" fn(&mut Object, Sel, *mut Object),
+            );
+            cls.add_method(
+                sel!(workspaceWillSleep:),
+                workspace_will_sleep as extern "C" fn(&mut Object, Sel, *mut Object),
+            );
+            cls.add_method(
+                sel!(workspaceDidWake:),
+                workspace_did_wake as extern "C" fn(&mut Object, Sel, *mut Object),
+            );
+            cls.add_method(
+                sel!(workspaceWillPowerOff:),
+                workspace_will_power_off as extern "C" fn(&mut Object, Sel, *mut Object),
+            );
             cls.add_method(
                 sel!(applicationOpenUntitledFile:),
                 application_open_untitled_file
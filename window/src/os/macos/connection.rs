@@ -2,22 +2,24 @@
 #![allow(clippy::let_unit_value)]
 
 use super::nsstring_to_str;
-use super::window::WindowInner;
+use super::window::{cartesian_to_screen_point, WindowInner};
 use crate::connection::ConnectionOps;
 use crate::os::macos::app::{create_app_delegate, flush_pending_service_opens};
 use crate::screen::{ScreenInfo, Screens};
 use crate::spawn::*;
-use crate::Appearance;
+use crate::{Appearance, Modifiers, PhysKeyCode, ScreenPoint};
 use cocoa::appkit::{NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSScreen};
 use cocoa::base::{id, nil};
-use cocoa::foundation::{NSArray, NSInteger};
+use cocoa::foundation::{NSArray, NSInteger, NSPoint, NSRect};
 use core_foundation::base::TCFType;
 use core_foundation::string::{CFString, CFStringRef};
-use objc::runtime::{Object, BOOL, YES};
+use objc::rc::StrongPtr;
+use objc::runtime::{Object, BOOL, NO, YES};
 use objc::*;
 use serde::Deserialize;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::atomic::AtomicUsize;
 
@@ -26,6 +28,9 @@ pub struct Connection {
     pub(crate) windows: RefCell<HashMap<usize, Rc<RefCell<WindowInner>>>>,
     pub(crate) next_window_id: AtomicUsize,
     pub(crate) gl_connection: RefCell<Option<Rc<crate::egl::GlConnection>>>,
+    /// NSSound instances keyed by path, so that each file is only
+    /// loaded once and stays alive while it is playing
+    sounds: RefCell<HashMap<PathBuf, StrongPtr>>,
 }
 
 impl Connection {
@@ -46,6 +51,7 @@ impl Connection {
                 windows: RefCell::new(HashMap::new()),
                 next_window_id: AtomicUsize::new(1),
                 gl_connection: RefCell::new(None),
+                sounds: RefCell::new(HashMap::new()),
             };
             Ok(conn)
         }
@@ -203,6 +209,53 @@ impl ConnectionOps for Connection {
         }
     }
 
+    fn set_dock_badge(&self, label: Option<&str>) {
+        unsafe {
+            let dock_tile: id = msg_send![self.ns_app, dockTile];
+            match label {
+                Some(label) => {
+                    let () = msg_send![dock_tile, setBadgeLabel: *super::nsstring(label)];
+                }
+                None => {
+                    let () = msg_send![dock_tile, setBadgeLabel: nil];
+                }
+            }
+        }
+    }
+
+    fn play_sound(&self, path: &Path, volume: f32) -> anyhow::Result<()> {
+        let mut sounds = self.sounds.borrow_mut();
+        if !sounds.contains_key(path) {
+            let path_str = path
+                .to_str()
+                .ok_or_else(|| anyhow::anyhow!("{} is not valid UTF-8", path.display()))?;
+            unsafe {
+                // byReference:YES defers reading and decoding the file
+                // until the sound is first played
+                let sound: id = msg_send![class!(NSSound), alloc];
+                let sound: id = msg_send![sound,
+                    initWithContentsOfFile: *super::nsstring(path_str)
+                    byReference: YES];
+                if sound == nil {
+                    anyhow::bail!("unable to load sound from {}", path.display());
+                }
+                sounds.insert(path.to_path_buf(), StrongPtr::new(sound));
+            }
+        }
+        let sound = &sounds[path];
+        unsafe {
+            // Restart the sound if it is still playing from a
+            // previous event
+            let _: BOOL = msg_send![**sound, stop];
+            let () = msg_send![**sound, setVolume: volume.clamp(0., 1.)];
+            let started: BOOL = msg_send![**sound, play];
+            if started == NO {
+                anyhow::bail!("unable to play {}", path.display());
+            }
+        }
+        Ok(())
+    }
+
     fn alert(&self, title: &str, message: &str) {
         unsafe {
             let alert: id = msg_send![class!(NSAlert), alloc];
@@ -279,6 +332,15 @@ impl ConnectionOps for Connection {
             virtual_rect,
         })
     }
+
+    fn set_global_hotkey(&self, hotkey: Option<(PhysKeyCode, Modifiers)>) -> anyhow::Result<()> {
+        super::hotkey::set_global_hotkey(hotkey)
+    }
+
+    fn mouse_position(&self) -> Option<ScreenPoint> {
+        let location: NSPoint = unsafe { msg_send![class!(NSEvent), mouseLocation] };
+        Some(cartesian_to_screen_point(location))
+    }
 }
 
 // NOTE: LSSetDefaultRoleHandlerForContentType is deprecated since macOS 12 (Monterey).
@@ -335,9 +397,26 @@ pub fn nsscreen_to_screen_info(screen: *mut Object) -> ScreenInfo {
         Some(crate::DEFAULT_DPI * scale)
     };
 
+    let visible_frame: NSRect = unsafe { msg_send![screen, visibleFrame] };
+    let top_left = cartesian_to_screen_point(NSPoint::new(
+        visible_frame.origin.x,
+        visible_frame.origin.y + visible_frame.size.height,
+    ));
+    let bottom_right = cartesian_to_screen_point(NSPoint::new(
+        visible_frame.origin.x + visible_frame.size.width,
+        visible_frame.origin.y,
+    ));
+    let visible_rect = euclid::rect(
+        top_left.x,
+        top_left.y,
+        bottom_right.x - top_left.x,
+        bottom_right.y - top_left.y,
+    );
+
     ScreenInfo {
         name,
         rect,
+        visible_rect,
         scale,
         max_fps,
         effective_dpi,
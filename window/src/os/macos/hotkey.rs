@@ -0,0 +1,187 @@
+//! Registers a system wide hotkey with the Carbon event manager.
+//! Unlike an NSEvent global monitor, this needs no accessibility
+//! permission, consumes the key press and works while the application
+//! is inactive.
+use super::keycodes::phys_to_vkey;
+use crate::connection::ConnectionOps;
+use crate::{ApplicationEvent, Connection, Modifiers};
+use anyhow::{anyhow, ensure};
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::ptr::null_mut;
+use wezterm_input_types::PhysKeyCode;
+
+type OSStatus = i32;
+type EventTargetRef = *mut c_void;
+type EventHandlerRef = *mut c_void;
+type EventHandlerCallRef = *mut c_void;
+type EventRef = *mut c_void;
+type EventHotKeyRef = *mut c_void;
+type EventHandlerProcPtr = extern "C" fn(EventHandlerCallRef, EventRef, *mut c_void) -> OSStatus;
+
+#[repr(C)]
+struct EventTypeSpec {
+    event_class: u32,
+    event_kind: u32,
+}
+
+#[repr(C)]
+struct EventHotKeyID {
+    signature: u32,
+    id: u32,
+}
+
+const NO_ERR: OSStatus = 0;
+const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+const EVENT_HOT_KEY_PRESSED: u32 = 5;
+const HOT_KEY_SIGNATURE: u32 = u32::from_be_bytes(*b"kaku");
+
+// Carbon modifier key masks
+const CMD_KEY: u32 = 1 << 8;
+const SHIFT_KEY: u32 = 1 << 9;
+const OPTION_KEY: u32 = 1 << 11;
+const CONTROL_KEY: u32 = 1 << 12;
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn GetApplicationEventTarget() -> EventTargetRef;
+    fn InstallEventHandler(
+        target: EventTargetRef,
+        handler: EventHandlerProcPtr,
+        num_types: u32,
+        list: *const EventTypeSpec,
+        user_data: *mut c_void,
+        out_ref: *mut EventHandlerRef,
+    ) -> OSStatus;
+    fn RegisterEventHotKey(
+        key_code: u32,
+        modifiers: u32,
+        hotkey_id: EventHotKeyID,
+        target: EventTargetRef,
+        options: u32,
+        out_ref: *mut EventHotKeyRef,
+    ) -> OSStatus;
+    fn UnregisterEventHotKey(hotkey: EventHotKeyRef) -> OSStatus;
+}
+
+thread_local! {
+    static HANDLER: RefCell<Option<EventHandlerRef>> = RefCell::new(None);
+    static HOT_KEY: RefCell<Option<EventHotKeyRef>> = RefCell::new(None);
+}
+
+extern "C" fn hot_key_pressed(
+    _next: EventHandlerCallRef,
+    _event: EventRef,
+    _user_data: *mut c_void,
+) -> OSStatus {
+    if let Some(conn) = Connection::get() {
+        conn.dispatch_app_event(ApplicationEvent::GlobalHotKeyPressed);
+    }
+    NO_ERR
+}
+
+fn carbon_modifiers(mods: Modifiers) -> u32 {
+    let mut result = 0;
+    if mods.contains(Modifiers::SUPER) {
+        result |= CMD_KEY;
+    }
+    if mods.contains(Modifiers::SHIFT) {
+        result |= SHIFT_KEY;
+    }
+    if mods.contains(Modifiers::ALT) {
+        result |= OPTION_KEY;
+    }
+    if mods.contains(Modifiers::CTRL) {
+        result |= CONTROL_KEY;
+    }
+    result
+}
+
+fn install_handler(target: EventTargetRef) -> anyhow::Result<()> {
+    HANDLER.with(|handler| {
+        let mut handler = handler.borrow_mut();
+        if handler.is_some() {
+            return Ok(());
+        }
+        let spec = EventTypeSpec {
+            event_class: EVENT_CLASS_KEYBOARD,
+            event_kind: EVENT_HOT_KEY_PRESSED,
+        };
+        let mut handler_ref = null_mut();
+        let status = unsafe {
+            InstallEventHandler(
+                target,
+                hot_key_pressed,
+                1,
+                &spec,
+                null_mut(),
+                &mut handler_ref,
+            )
+        };
+        ensure!(status == NO_ERR, "InstallEventHandler failed: {status}");
+        handler.replace(handler_ref);
+        Ok(())
+    })
+}
+
+/// Replaces the registered hotkey with `hotkey`, or removes it
+pub fn set_global_hotkey(hotkey: Option<(PhysKeyCode, Modifiers)>) -> anyhow::Result<()> {
+    HOT_KEY.with(|prior| {
+        if let Some(prior) = prior.borrow_mut().take() {
+            unsafe {
+                UnregisterEventHotKey(prior);
+            }
+        }
+    });
+
+    let (key, mods) = match hotkey {
+        Some(hotkey) => hotkey,
+        None => return Ok(()),
+    };
+    let vkey = phys_to_vkey(key)
+        .ok_or_else(|| anyhow!("{key:?} cannot be registered as a global hotkey"))?;
+
+    let target = unsafe { GetApplicationEventTarget() };
+    install_handler(target)?;
+
+    let mut hot_key_ref = null_mut();
+    let status = unsafe {
+        RegisterEventHotKey(
+            vkey as u32,
+            carbon_modifiers(mods),
+            EventHotKeyID {
+                signature: HOT_KEY_SIGNATURE,
+                id: 1,
+            },
+            target,
+            0,
+            &mut hot_key_ref,
+        )
+    };
+    ensure!(
+        status == NO_ERR,
+        "RegisterEventHotKey failed: {status}; the key may be in use by another application"
+    );
+    HOT_KEY.with(|hot_key| hot_key.borrow_mut().replace(hot_key_ref));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn modifiers() {
+        assert_eq!(carbon_modifiers(Modifiers::NONE), 0);
+        assert_eq!(carbon_modifiers(Modifiers::SUPER), CMD_KEY);
+        assert_eq!(
+            carbon_modifiers(Modifiers::CTRL | Modifiers::ALT | Modifiers::SHIFT),
+            CONTROL_KEY | OPTION_KEY | SHIFT_KEY
+        );
+        // The positional bits have no Carbon equivalent
+        assert_eq!(
+            carbon_modifiers(Modifiers::SUPER | Modifiers::LEFT_ALT | Modifiers::ALT),
+            CMD_KEY | OPTION_KEY
+        );
+    }
+}
@@ -132,6 +132,11 @@ pub fn vkey_to_phys(vkey: u16) -> Option<PhysKeyCode> {
     MAP.get(&vkey).copied()
 }
 
+pub fn phys_to_vkey(phys: PhysKeyCode) -> Option<u16> {
+    MAP.iter()
+        .find_map(|(&vkey, &p)| if p == phys { Some(vkey) } else { None })
+}
+
 pub const kVK_ANSI_A: u16 = 0x00;
 pub const kVK_ANSI_S: u16 = 0x01;
 pub const kVK_ANSI_D: u16 = 0x02;
@@ -246,3 +251,19 @@ pub const kVK_LeftArrow: u16 = 0x7B;
 pub const kVK_RightArrow: u16 = 0x7C;
 pub const kVK_DownArrow: u16 = 0x7D;
 pub const kVK_UpArrow: u16 = 0x7E;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn phys_to_vkey_inverts_vkey_to_phys() {
+        assert_eq!(phys_to_vkey(PhysKeyCode::A), Some(kVK_ANSI_A));
+        assert_eq!(phys_to_vkey(PhysKeyCode::Grave), Some(kVK_ANSI_Grave));
+        assert_eq!(phys_to_vkey(PhysKeyCode::F1), Some(kVK_F1));
+        for phys in build_map().into_values() {
+            let vkey = phys_to_vkey(phys).unwrap();
+            assert_eq!(vkey_to_phys(vkey), Some(phys));
+        }
+    }
+}
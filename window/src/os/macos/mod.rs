@@ -9,6 +9,7 @@ mod app;
 pub mod bitmap;
 pub mod clipboard;
 pub mod connection;
+mod hotkey;
 pub mod menu;
 pub mod window;
 
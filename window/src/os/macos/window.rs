@@ -11,7 +11,8 @@ use crate::{
     Clipboard, ClipboardData, Connection, DeadKeyStatus, Dimensions, Handled, KeyCode, KeyEvent,
     Modifiers, MouseButtons, MouseCursor, MouseEvent, MouseEventKind, MousePress, Point,
     RawKeyEvent, Rect, RequestedWindowGeometry, ResizeIncrement, ResolvedGeometry, ScreenPoint,
-    Size, ULength, WindowDecorations, WindowEvent, WindowEventSender, WindowOps, WindowState,
+    ScreenRect, Size, ULength, WindowDecorations, WindowEvent, WindowEventSender, WindowOps,
+    WindowState,
 };
 use anyhow::{anyhow, bail, ensure};
 use async_trait::async_trait;
@@ -26,8 +27,8 @@ use cocoa::foundation::{
     NSArray, NSAutoreleasePool, NSFastEnumeration, NSInteger, NSNotFound, NSPoint, NSRect, NSSize,
     NSString, NSUInteger,
 };
-use config::window::WindowLevel;
-use config::{ConfigHandle, RgbaColor, SrgbaTuple};
+use config::window::{MacOsWindowButtonPosition, WindowLevel};
+use config::{ConfigHandle, MacOsVibrancy, RgbaColor, SrgbaTuple};
 use core_foundation::base::{CFTypeID, TCFType};
 use core_foundation::bundle::{CFBundleGetBundleWithIdentifier, CFBundleGetFunctionPointerForName};
 use core_foundation::data::{CFData, CFDataGetBytePtr, CFDataRef};
@@ -45,6 +46,7 @@ use raw_window_handle::{
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::ffi::{c_void, CStr};
 use std::path::PathBuf;
 use std::ptr::NonNull;
@@ -61,6 +63,14 @@ static APP_TERMINATING: AtomicBool = AtomicBool::new(false);
 const NSViewLayerContentsPlacementTopLeft: NSInteger = 11;
 #[allow(non_upper_case_globals)]
 const NSViewLayerContentsRedrawDuringViewResize: NSInteger = 2;
+#[allow(non_upper_case_globals)]
+const NSVisualEffectBlendingModeBehindWindow: NSInteger = 0;
+#[allow(non_upper_case_globals)]
+const NSVisualEffectStateActive: NSInteger = 1;
+#[allow(non_upper_case_globals)]
+const NSWindowAbove: NSInteger = 1;
+#[allow(non_upper_case_globals)]
+const NSWindowOcclusionStateVisible: NSUInteger = 1 << 1;
 const FULLSCREEN_ENTER_HIDE_CONTENT_MS: u64 = 30;
 const FULLSCREEN_EXIT_HIDE_CONTENT_MS: u64 = 20;
 const ZOOM_HIDE_CONTENT_MS: u64 = 20;
@@ -384,6 +394,18 @@ pub(crate) struct WindowInner {
     view: StrongPtr,
     window: StrongPtr,
     config: ConfigHandle,
+    /// Shows `macos_window_vibrancy` behind `view`
+    vibrancy: Option<Vibrancy>,
+}
+
+/// While vibrancy is enabled, the content view of the window is a
+/// container that holds an NSVisualEffectView with the WindowView
+/// layered above it.  AppKit doesn't support adding views directly
+/// to the frame view of the window.
+struct Vibrancy {
+    material: MacOsVibrancy,
+    container: StrongPtr,
+    effect_view: StrongPtr,
 }
 
 fn function_key_to_keycode(function_key: char) -> KeyCode {
@@ -446,9 +468,15 @@ thread_local! {
     static LAST_CLOSED_WINDOW_POSITION: RefCell<Option<ScreenPoint>> = RefCell::new(None);
     // Sync drag flag: set by request_drag_move(), checked by mouse_down to execute performWindowDragWithEvent:
     static PENDING_DRAG_MOVE: Cell<bool> = Cell::new(false);
+    /// State that is waiting to be written to the state file
+    static PENDING_STATE: RefCell<Option<PersistedState>> = RefCell::new(None);
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// How long to wait before writing the state file, so that a burst of
+/// moves and resizes is written once
+const PERSIST_STATE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 struct PersistedWindowSize {
     width: usize,
     height: usize,
@@ -462,20 +490,46 @@ struct PersistedWindowPosition {
     screen_id: Option<u32>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct PersistedState {
-    #[serde(default)]
-    config_version: Option<u64>,
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersistedWindowState {
     #[serde(default)]
     window_geometry: Option<PersistedWindowSize>,
     #[serde(default)]
     window_position: Option<PersistedWindowPosition>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersistedState {
+    #[serde(default)]
+    config_version: Option<u64>,
+    /// The geometry of the last window without a persistence key
+    #[serde(flatten)]
+    window: PersistedWindowState,
+    /// The geometry of windows by their persistence key
+    #[serde(default)]
+    keyed_windows: HashMap<String, PersistedWindowState>,
+}
+
+impl PersistedState {
+    fn window(&self, key: Option<&str>) -> Option<&PersistedWindowState> {
+        match key {
+            Some(key) => self.keyed_windows.get(key),
+            None => Some(&self.window),
+        }
+    }
+
+    fn window_mut(&mut self, key: Option<&str>) -> &mut PersistedWindowState {
+        match key {
+            Some(key) => self.keyed_windows.entry(key.to_string()).or_default(),
+            None => &mut self.window,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
 struct PersistedRestore {
     position: Option<ScreenPoint>,
-    skip_persisted_size: bool,
+    size: Option<PersistedWindowSize>,
 }
 
 fn config_dir_file(name: &str) -> PathBuf {
@@ -603,6 +657,30 @@ fn screen_identifier_for_window(window: *mut Object) -> Option<u32> {
     }
 }
 
+/// Returns the size of the largest visible frame of the connected
+/// displays, which bounds the size of a restored window
+fn largest_screen_size() -> Option<PersistedWindowSize> {
+    let mut largest: Option<PersistedWindowSize> = None;
+    unsafe {
+        let screens = NSScreen::screens(nil);
+        let count = screens.count();
+        for idx in 0..count {
+            let screen = screens.objectAtIndex(idx);
+            let frame: NSRect = msg_send![screen, visibleFrame];
+            let width = frame.size.width.max(1.0) as usize;
+            let height = frame.size.height.max(1.0) as usize;
+            largest = Some(match largest {
+                Some(size) => PersistedWindowSize {
+                    width: size.width.max(width),
+                    height: size.height.max(height),
+                },
+                None => PersistedWindowSize { width, height },
+            });
+        }
+    }
+    largest
+}
+
 fn has_screen_identifier(screen_id: u32) -> bool {
     unsafe {
         let screens = NSScreen::screens(nil);
@@ -639,26 +717,25 @@ fn migrate_legacy_window_state() -> Option<PersistedState> {
     let size = parse_legacy_window_size(&contents)?;
 
     let state = PersistedState {
-        config_version: None,
-        window_geometry: Some(size),
-        window_position: None,
+        window: PersistedWindowState {
+            window_geometry: Some(size),
+            window_position: None,
+        },
+        ..PersistedState::default()
     };
 
-    let state_file_name = state_file();
-    if let Some(parent) = state_file_name.parent() {
-        let _ = config::create_user_owned_dirs(parent);
-    }
-
-    if let Ok(encoded) = serde_json::to_string_pretty(&state) {
-        let _ = std::fs::write(&state_file_name, format!("{}\n", encoded));
+    if write_persisted_state(&state) {
+        let _ = std::fs::remove_file(&file_name);
     }
 
-    let _ = std::fs::remove_file(&file_name);
-
     Some(state)
 }
 
 fn load_persisted_state() -> Option<PersistedState> {
+    if let Some(state) = PENDING_STATE.with(|pending| pending.borrow().clone()) {
+        return Some(state);
+    }
+
     let file_name = state_file();
     if let Ok(contents) = std::fs::read_to_string(file_name) {
         if let Ok(state) = serde_json::from_str(&contents) {
@@ -669,40 +746,111 @@ fn load_persisted_state() -> Option<PersistedState> {
     migrate_legacy_window_state()
 }
 
-fn load_persisted_window_size() -> Option<PersistedWindowSize> {
-    load_persisted_state()?.window_geometry
+fn write_persisted_state(state: &PersistedState) -> bool {
+    let file_name = state_file();
+    if let Some(parent) = file_name.parent() {
+        if config::create_user_owned_dirs(parent).is_err() {
+            return false;
+        }
+    }
+
+    let encoded = match serde_json::to_string_pretty(state) {
+        Ok(value) => value,
+        Err(_) => return false,
+    };
+
+    std::fs::write(&file_name, format!("{}\n", encoded)).is_ok()
 }
 
-fn load_persisted_restore() -> PersistedRestore {
-    let mut restore = PersistedRestore::default();
+/// Arranges for `state` to be written once no further changes have
+/// been made for PERSIST_STATE_DELAY
+fn schedule_persisted_state(state: PersistedState) {
+    let already_scheduled =
+        PENDING_STATE.with(|pending| pending.borrow_mut().replace(state).is_some());
+    if !already_scheduled {
+        promise::spawn::spawn(async move {
+            async_io::Timer::after(PERSIST_STATE_DELAY).await;
+            flush_persisted_state();
+        })
+        .detach();
+    }
+}
 
-    let state = match load_persisted_state() {
-        Some(state) => state,
-        None => return restore,
-    };
+/// Writes the state that is waiting for its delay to elapse, if any
+fn flush_persisted_state() {
+    if let Some(state) = PENDING_STATE.with(|pending| pending.borrow_mut().take()) {
+        if !write_persisted_state(&state) {
+            log::warn!("failed to write {}", state_file().display());
+        }
+    }
+}
 
-    if let Some(pos) = state.window_position {
+/// Decides how much of the `saved` geometry to restore.
+/// When the display that the window was on is no longer connected,
+/// neither its position nor its size is restored, so that the window
+/// opens centered at its default size.  Otherwise the position is kept
+/// if it is still visible, and the size if it isn't too small; the
+/// size is limited to `max_size`.
+fn restore_for(
+    saved: &PersistedWindowState,
+    is_connected: impl Fn(u32) -> bool,
+    is_visible: impl Fn(ScreenPoint) -> bool,
+    max_size: Option<PersistedWindowSize>,
+) -> PersistedRestore {
+    let mut restore = PersistedRestore::default();
+
+    if let Some(pos) = saved.window_position {
         if let Some(screen_id) = pos.screen_id {
-            if !has_screen_identifier(screen_id) {
-                // The previously used display is disconnected.
-                // Fall back to centered default startup behavior.
-                restore.skip_persisted_size = true;
+            if !is_connected(screen_id) {
                 return restore;
             }
         }
 
-        restore.position = restorable_window_position(ScreenPoint::new(pos.x, pos.y));
+        let pos = ScreenPoint::new(pos.x, pos.y);
+        if is_visible(pos) {
+            restore.position = Some(pos);
+        }
     }
 
+    restore.size = saved
+        .window_geometry
+        .filter(|size| size.width >= MIN_RESTORE_WIDTH && size.height >= MIN_RESTORE_HEIGHT)
+        .map(|size| match max_size {
+            Some(max) => PersistedWindowSize {
+                width: size.width.min(max.width),
+                height: size.height.min(max.height),
+            },
+            None => size,
+        });
+
     restore
 }
 
+/// Returns the geometry persisted for windows with `key`, or for
+/// the last window without a key
+fn load_persisted_restore(key: Option<&str>) -> PersistedRestore {
+    let state = match load_persisted_state() {
+        Some(state) => state,
+        None => return PersistedRestore::default(),
+    };
+    match state.window(key) {
+        Some(saved) => restore_for(
+            saved,
+            has_screen_identifier,
+            |pos| restorable_window_position(pos).is_some(),
+            largest_screen_size(),
+        ),
+        None => PersistedRestore::default(),
+    }
+}
+
 fn persist_window_state(window: *mut Object, persist_position: bool) -> bool {
     if window.is_null() {
         return false;
     }
 
-    let content_view: id = unsafe { msg_send![window, contentView] };
+    let mut key = None;
+    let content_view = window_view_of(window);
     if !content_view.is_null() {
         if let Some(window_view) = unsafe { WindowView::get_this(&*content_view) } {
             if window_view.simple_fullscreen_active.get()
@@ -711,6 +859,10 @@ fn persist_window_state(window: *mut Object, persist_position: bool) -> bool {
             {
                 return false;
             }
+            key = match window_view.inner.try_borrow() {
+                Ok(inner) => inner.persistence_key.clone(),
+                Err(_) => return false,
+            };
         }
     }
 
@@ -719,31 +871,20 @@ fn persist_window_state(window: *mut Object, persist_position: bool) -> bool {
         return false;
     }
 
-    let file_name = state_file();
-    let size = window_size(window);
-    if let Some(parent) = file_name.parent() {
-        if config::create_user_owned_dirs(parent).is_err() {
-            return false;
-        }
-    }
-
     let mut state = load_persisted_state().unwrap_or_default();
-    state.window_geometry = Some(size);
+    let saved = state.window_mut(key.as_deref());
+    saved.window_geometry = Some(window_size(window));
     if persist_position {
         let screen_id = screen_identifier_for_window(window);
-        state.window_position = window_position(window).map(|pos| PersistedWindowPosition {
+        saved.window_position = window_position(window).map(|pos| PersistedWindowPosition {
             x: pos.x,
             y: pos.y,
             screen_id,
         });
     }
 
-    let encoded = match serde_json::to_string_pretty(&state) {
-        Ok(value) => value,
-        Err(_) => return false,
-    };
-
-    std::fs::write(&file_name, format!("{}\n", encoded)).is_ok()
+    schedule_persisted_state(state);
+    true
 }
 
 fn persist_window_size_and_position(window: *mut Object) -> bool {
@@ -754,6 +895,12 @@ fn persist_window_size_and_position(window: *mut Object) -> bool {
 /// Persists size and position from tracked terminal windows before the event loop stops.
 pub(crate) fn on_app_terminating() {
     APP_TERMINATING.store(true, Ordering::Relaxed);
+    persist_key_window_state();
+}
+
+/// Persists size and position of the key (or otherwise most relevant)
+/// terminal window so that it can be restored on the next launch.
+pub(crate) fn persist_key_window_state() {
     if let Some(conn) = Connection::get() {
         let mut windows = vec![];
         for window_inner in conn.windows.borrow().values() {
@@ -789,10 +936,11 @@ pub(crate) fn on_app_terminating() {
 
         for window in candidates {
             if persist_window_size_and_position(window) {
-                return;
+                break;
             }
         }
     }
+    flush_persisted_state();
 }
 
 impl Window {
@@ -813,6 +961,8 @@ impl Window {
         };
 
         let conn = Connection::get().expect("new_window called on gui thread");
+        let explicit_size = geometry.explicit_size;
+        let persistence_key = geometry.persistence_key.clone();
         let ResolvedGeometry {
             width,
             height,
@@ -830,29 +980,26 @@ impl Window {
             (Some(x), Some(y)) => Some(ScreenPoint::new(x as isize, y as isize)),
             _ => None,
         };
+        // A window with a persistence key opens with the geometry last
+        // persisted for that key; otherwise only the first window
+        // restores the geometry of the last window.
         let is_first_window = conn.windows.borrow().is_empty();
-        let remembered_initial_pos = if explicit_initial_pos.is_none() && is_first_window {
-            last_closed_window_position()
-        } else {
-            None
-        };
-        let persisted_restore = if explicit_initial_pos.is_none()
-            && is_first_window
-            && remembered_initial_pos.is_none()
-        {
-            load_persisted_restore()
-        } else {
-            PersistedRestore::default()
-        };
-        if explicit_initial_pos.is_none()
-            && is_first_window
-            && !persisted_restore.skip_persisted_size
-        {
-            if let Some(size) = load_persisted_window_size() {
-                if size.width >= MIN_RESTORE_WIDTH && size.height >= MIN_RESTORE_HEIGHT {
-                    width = size.width;
-                    height = size.height;
-                }
+        let remembered_initial_pos =
+            if explicit_initial_pos.is_none() && is_first_window && persistence_key.is_none() {
+                last_closed_window_position()
+            } else {
+                None
+            };
+        let persisted_restore =
+            if explicit_initial_pos.is_none() && (is_first_window || persistence_key.is_some()) {
+                load_persisted_restore(persistence_key.as_deref())
+            } else {
+                PersistedRestore::default()
+            };
+        if !explicit_size {
+            if let Some(size) = persisted_restore.size {
+                width = size.width;
+                height = size.height;
             }
         }
 
@@ -895,6 +1042,7 @@ impl Window {
                 last_reported_dpi: None,
                 last_reported_window_state: WindowState::default(),
                 ime_text: String::new(),
+                persistence_key,
             }));
 
             let window: id = msg_send![get_window_class(), alloc];
@@ -911,10 +1059,10 @@ impl Window {
                 config.window_decorations,
                 config.integrated_title_button_style,
                 config.native_macos_fullscreen_mode,
+                config.macos_window_button_position,
             );
 
-            // Prevent Cocoa native tabs from being used
-            let _: () = msg_send![*window, setTabbingMode:2 /* NSWindowTabbingModeDisallowed */];
+            apply_tabbing_mode(*window, &config);
             let _: () = msg_send![*window, setRestorable: NO];
 
             window.setReleasedWhenClosed_(NO);
@@ -954,11 +1102,12 @@ impl Window {
                 setLayerContentsPlacement: NSViewLayerContentsPlacementTopLeft
             ];
 
-            if config.macos_window_background_blur > 0 {
+            let blur_radius = effective_background_blur(&config);
+            if blur_radius > 0 {
                 CGSSetWindowBackgroundBlurRadius(
                     CGSMainConnectionID(),
                     window.windowNumber(),
-                    config.macos_window_background_blur,
+                    blur_radius,
                 );
             }
             window.setContentView_(*view);
@@ -996,6 +1145,7 @@ impl Window {
                 window,
                 view,
                 config: config.clone(),
+                vibrancy: None,
             }));
             inner.borrow_mut().window.replace(weak_window);
             conn.windows
@@ -1180,6 +1330,13 @@ impl WindowOps for Window {
         });
     }
 
+    fn set_window_geometry(&self, geometry: RequestedWindowGeometry) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.set_window_geometry(geometry);
+            Ok(())
+        });
+    }
+
     fn set_text_cursor_position(&self, cursor: Rect) {
         Connection::with_window_inner(self.id, move |inner| {
             inner.set_text_cursor_position(cursor);
@@ -1214,6 +1371,54 @@ impl WindowOps for Window {
         });
     }
 
+    fn add_native_tab(&self, tab: &Self) {
+        let tab_id = tab.id;
+        Connection::with_window_inner(self.id, move |inner| {
+            let tab = Connection::get()
+                .unwrap()
+                .window_by_id(tab_id)
+                .ok_or_else(|| anyhow!("no such window {}", tab_id))?;
+            let tab_window = *tab.borrow().window;
+            inner.add_native_tab(tab_window);
+            Ok(())
+        });
+    }
+
+    fn select_native_tab(&self, n: isize, relative: bool) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.select_native_tab(n, relative);
+            Ok(())
+        });
+    }
+
+    fn move_native_tab(&self, n: isize, relative: bool) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.move_native_tab(n, relative);
+            Ok(())
+        });
+    }
+
+    fn show_native_tab_overview(&self) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.show_native_tab_overview();
+            Ok(())
+        });
+    }
+
+    fn show_dropdown(&self, frame: ScreenRect, duration: Duration) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.show_dropdown(frame, duration);
+            Ok(())
+        });
+    }
+
+    fn hide_dropdown(&self, duration: Duration) {
+        Connection::with_window_inner(self.id, move |inner| {
+            inner.hide_dropdown(duration);
+            Ok(())
+        });
+    }
+
     fn maximize(&self) {
         Connection::with_window_inner(self.id, move |inner| {
             inner.maximize();
@@ -1353,7 +1558,7 @@ impl WindowOps for Window {
 
 /// Convert from a macOS screen coordinate with the origin in the bottom left
 /// to a pixel coordinate with its origin in the top left
-fn cartesian_to_screen_point(cartesian: NSPoint) -> ScreenPoint {
+pub(crate) fn cartesian_to_screen_point(cartesian: NSPoint) -> ScreenPoint {
     unsafe {
         let screens = NSScreen::screens(nil);
         let primary = screens.objectAtIndex(0);
@@ -1383,6 +1588,33 @@ fn screen_point_to_cartesian(point: ScreenPoint) -> NSPoint {
     }
 }
 
+/// Convert a rect in pixel coordinates with its origin in the top left
+/// to a macOS screen rect with its origin in the bottom left
+fn screen_rect_to_cartesian(rect: ScreenRect) -> NSRect {
+    let bottom_left = screen_point_to_cartesian(ScreenPoint::new(rect.min_x(), rect.max_y()));
+    let top_right = screen_point_to_cartesian(ScreenPoint::new(rect.max_x(), rect.min_y()));
+    NSRect::new(
+        bottom_left,
+        NSSize::new(top_right.x - bottom_left.x, top_right.y - bottom_left.y),
+    )
+}
+
+/// Moves `window` to `frame`, animating the change over `duration`
+fn animate_window_frame(window: id, frame: NSRect, duration: Duration) {
+    unsafe {
+        if duration.is_zero() {
+            let () = msg_send![window, setFrame: frame display: YES];
+            return;
+        }
+        let () = msg_send![class!(NSAnimationContext), beginGrouping];
+        let context: id = msg_send![class!(NSAnimationContext), currentContext];
+        let () = msg_send![context, setDuration: duration.as_secs_f64()];
+        let animator: id = msg_send![window, animator];
+        let () = msg_send![animator, setFrame: frame display: YES];
+        let () = msg_send![class!(NSAnimationContext), endGrouping];
+    }
+}
+
 impl WindowInner {
     fn arm_transition_content_hide(&mut self, duration_ms: u64, _reason: &str, sync_now: bool) {
         if let Some(window_view) = WindowView::get_this(unsafe { &**self.view }) {
@@ -1455,6 +1687,7 @@ impl WindowInner {
                 self.config.window_decorations,
                 self.config.integrated_title_button_style,
                 self.config.native_macos_fullscreen_mode,
+                self.config.macos_window_button_position,
             );
         }
     }
@@ -1523,6 +1756,7 @@ impl WindowInner {
                         self.config.window_decorations,
                         self.config.integrated_title_button_style,
                         self.config.native_macos_fullscreen_mode,
+                        self.config.macos_window_button_position,
                     );
                     self.window.setFrame_display_(saved_rect, YES);
                     let clear: id = msg_send![class!(NSColor), clearColor];
@@ -1668,10 +1902,134 @@ impl WindowInner {
             CGSSetWindowBackgroundBlurRadius(
                 CGSMainConnectionID(),
                 self.window.windowNumber(),
-                self.config.macos_window_background_blur,
+                effective_background_blur(&self.config),
             );
         }
     }
+
+    /// Places an NSVisualEffectView with the configured material
+    /// beneath the WindowView, so that it shows through wherever
+    /// the terminal background is translucent.  Both are hosted in a
+    /// plain container view that becomes the content view, as AppKit
+    /// doesn't support adding subviews to the window frame view.
+    fn update_window_vibrancy(&mut self) {
+        let material = self.config.macos_window_vibrancy;
+        if self.vibrancy.as_ref().map(|v| v.material) == material {
+            return;
+        }
+        unsafe {
+            let Some(material) = material else {
+                if let Some(vibrancy) = self.vibrancy.take() {
+                    // Make the view the content view again
+                    let () = msg_send![*self.view, removeFromSuperview];
+                    let () = msg_send![*vibrancy.container, removeFromSuperview];
+                    self.window.setContentView_(*self.view);
+                    let _: BOOL = msg_send![*self.window, makeFirstResponder: *self.view];
+                }
+                return;
+            };
+
+            if let Some(vibrancy) = self.vibrancy.as_mut() {
+                let () = msg_send![*vibrancy.effect_view, setMaterial: vibrancy_material(material)];
+                vibrancy.material = material;
+                return;
+            }
+
+            let frame = NSView::frame(*self.view);
+            let container: id = msg_send![class!(NSView), alloc];
+            let container = StrongPtr::new(msg_send![container, initWithFrame: frame]);
+            container.setAutoresizingMask_(NSViewHeightSizable | NSViewWidthSizable);
+            container.setWantsLayer(YES);
+
+            let bounds = NSView::bounds(*container);
+            let effect_view: id = msg_send![class!(NSVisualEffectView), alloc];
+            let effect_view = StrongPtr::new(msg_send![effect_view, initWithFrame: bounds]);
+            let () = msg_send![*effect_view, setMaterial: vibrancy_material(material)];
+            let () = msg_send![
+                *effect_view,
+                setBlendingMode: NSVisualEffectBlendingModeBehindWindow
+            ];
+            // Keep the material when the window loses focus, as the
+            // terminal content doesn't change appearance either
+            let () = msg_send![*effect_view, setState: NSVisualEffectStateActive];
+            effect_view.setAutoresizingMask_(NSViewHeightSizable | NSViewWidthSizable);
+            let () = msg_send![*container, addSubview: *effect_view];
+
+            // Replacing the content view detaches the view, which is
+            // kept alive by self.view, so that it can join the container
+            self.window.setContentView_(*container);
+            let () = msg_send![*self.view, setFrame: bounds];
+            self.view
+                .setAutoresizingMask_(NSViewHeightSizable | NSViewWidthSizable);
+            let () = msg_send![
+                *container,
+                addSubview: *self.view
+                positioned: NSWindowAbove
+                relativeTo: *effect_view
+            ];
+            let _: BOOL = msg_send![*self.window, makeFirstResponder: *self.view];
+
+            self.vibrancy.replace(Vibrancy {
+                material,
+                container,
+                effect_view,
+            });
+        }
+    }
+}
+
+/// Returns the WindowView of `window`, which is its content view
+/// unless the view has been placed in the vibrancy container
+fn window_view_of(window: id) -> id {
+    unsafe {
+        let content_view: id = msg_send![window, contentView];
+        if content_view.is_null() {
+            return nil;
+        }
+        let cls = WindowView::get_class();
+        let is_window_view: BOOL = msg_send![content_view, isKindOfClass: cls];
+        if is_window_view == YES {
+            return content_view;
+        }
+        let subviews: id = msg_send![content_view, subviews];
+        for idx in 0..subviews.count() {
+            let view = subviews.objectAtIndex(idx);
+            let is_window_view: BOOL = msg_send![view, isKindOfClass: cls];
+            if is_window_view == YES {
+                return view;
+            }
+        }
+        nil
+    }
+}
+
+/// The blur radius to apply to the window background; the vibrancy
+/// material blurs by itself, so the blur is skipped when it is shown
+fn effective_background_blur(config: &ConfigHandle) -> i64 {
+    if config.macos_window_vibrancy.is_some() {
+        0
+    } else {
+        config.macos_window_background_blur
+    }
+}
+
+fn vibrancy_material(material: MacOsVibrancy) -> NSInteger {
+    match material {
+        MacOsVibrancy::Titlebar => 3,
+        MacOsVibrancy::Selection => 4,
+        MacOsVibrancy::Menu => 5,
+        MacOsVibrancy::Popover => 6,
+        MacOsVibrancy::Sidebar => 7,
+        MacOsVibrancy::HeaderView => 10,
+        MacOsVibrancy::Sheet => 11,
+        MacOsVibrancy::WindowBackground => 12,
+        MacOsVibrancy::HudWindow => 13,
+        MacOsVibrancy::FullScreenUI => 15,
+        MacOsVibrancy::ToolTip => 17,
+        MacOsVibrancy::ContentBackground => 18,
+        MacOsVibrancy::UnderWindowBackground => 21,
+        MacOsVibrancy::UnderPageBackground => 22,
+    }
 }
 
 impl WindowInner {
@@ -1692,6 +2050,7 @@ impl WindowInner {
                 self.config.window_decorations,
                 self.config.integrated_title_button_style,
                 self.config.native_macos_fullscreen_mode,
+                self.config.macos_window_button_position,
             );
 
             self.update_titlebar_background();
@@ -1712,6 +2071,113 @@ impl WindowInner {
         }
     }
 
+    fn show_dropdown(&mut self, frame: ScreenRect, duration: Duration) {
+        let target = screen_rect_to_cartesian(frame);
+        unsafe {
+            // A toggle during the hide animation must not be undone
+            // by its pending orderOut:
+            let () = msg_send![
+                class!(NSObject),
+                cancelPreviousPerformRequestsWithTarget: *self.window
+                selector: sel!(orderOut:)
+                object: nil
+            ];
+
+            let mut start = target;
+            start.origin.y += target.size.height;
+            let () = msg_send![*self.window, setFrame: start display: NO];
+        }
+
+        self.show();
+
+        unsafe {
+            // show() resets the collection behavior along with the
+            // decorations, so this has to come afterwards
+            let mut behavior = self.window.collectionBehavior();
+            behavior.insert(
+                appkit::NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
+                    | appkit::NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary,
+            );
+            self.window.setCollectionBehavior_(behavior);
+        }
+
+        animate_window_frame(*self.window, target, duration);
+    }
+
+    fn hide_dropdown(&mut self, duration: Duration) {
+        unsafe {
+            let mut frame = NSWindow::frame(*self.window);
+            frame.origin.y += frame.size.height;
+            animate_window_frame(*self.window, frame, duration);
+            let () = msg_send![
+                *self.window,
+                performSelector: sel!(orderOut:)
+                withObject: nil
+                afterDelay: duration.as_secs_f64()
+            ];
+        }
+    }
+
+    fn add_native_tab(&mut self, tab: id) {
+        unsafe {
+            let ordered: NSInteger = 1; // NSWindowAbove
+            let () = msg_send![*self.window, addTabbedWindow: tab ordered: ordered];
+        }
+    }
+
+    fn select_native_tab(&mut self, n: isize, relative: bool) {
+        unsafe {
+            // nil unless the window is part of a tab group
+            let windows: id = msg_send![*self.window, tabbedWindows];
+            if windows.is_null() {
+                return;
+            }
+            let count = windows.count() as isize;
+            let current: NSUInteger = msg_send![windows, indexOfObject: *self.window];
+            let target = if relative {
+                (current as isize + n).rem_euclid(count)
+            } else if n < 0 {
+                count + n
+            } else {
+                n
+            };
+            if target < 0 || target >= count {
+                return;
+            }
+            let target = windows.objectAtIndex(target as NSUInteger);
+            let () = msg_send![target, makeKeyAndOrderFront: nil];
+        }
+    }
+
+    fn move_native_tab(&mut self, n: isize, relative: bool) {
+        unsafe {
+            let windows: id = msg_send![*self.window, tabbedWindows];
+            if windows.is_null() {
+                return;
+            }
+            let count = windows.count() as isize;
+            let current: NSUInteger = msg_send![windows, indexOfObject: *self.window];
+            let target = if relative {
+                (current as isize + n).clamp(0, count - 1)
+            } else {
+                n
+            };
+            if target < 0 || target >= count || target == current as isize {
+                return;
+            }
+            let group: id = msg_send![*self.window, tabGroup];
+            let () = msg_send![group, removeWindow: *self.window];
+            let () = msg_send![group, insertWindow: *self.window atIndex: target as NSInteger];
+            let () = msg_send![group, setSelectedWindow: *self.window];
+        }
+    }
+
+    fn show_native_tab_overview(&mut self) {
+        unsafe {
+            let () = msg_send![*self.window, toggleTabOverview: nil];
+        }
+    }
+
     fn hide(&mut self) {
         unsafe {
             NSWindow::miniaturize_(*self.window, *self.window);
@@ -1787,6 +2253,36 @@ impl WindowInner {
         set_window_position(*self.window, coords);
     }
 
+    fn set_window_geometry(&mut self, geometry: RequestedWindowGeometry) {
+        let conn = Connection::get().expect("set_window_geometry called on gui thread");
+        let ResolvedGeometry {
+            width,
+            height,
+            x,
+            y,
+        } = conn.resolve_geometry(geometry);
+
+        // Use the same scaling as new_window so that the result
+        // matches the placement of a newly created window
+        let scale_factor = (conn.default_dpi() / crate::DEFAULT_DPI) as usize;
+        unsafe {
+            NSWindow::setContentSize_(
+                *self.window,
+                NSSize::new(
+                    (width / scale_factor) as f64,
+                    (height / scale_factor) as f64,
+                ),
+            );
+        }
+        if let (Some(x), Some(y)) = (x, y) {
+            self.set_window_position(ScreenPoint::new(
+                (x / scale_factor as i32) as isize,
+                (y / scale_factor as i32) as isize,
+            ));
+        }
+        self.invalidate();
+    }
+
     // request_drag_move moved to mouse_down for synchronous execution to avoid
     // modal drag loop swallowing subsequent events
 
@@ -1881,8 +2377,23 @@ impl WindowInner {
         }
         self.update_window_shadow();
         self.update_window_background_blur();
+        self.update_window_vibrancy();
         self.update_titlebar_background();
         self.apply_decorations();
+        apply_tabbing_mode(*self.window, config);
+    }
+}
+
+/// Native window tabs are only allowed when `use_native_macos_tabs`
+/// is enabled; otherwise tabs are drawn by the application.
+fn apply_tabbing_mode(window: id, config: &ConfigHandle) {
+    unsafe {
+        if config.uses_native_macos_tabs() {
+            let _: () = msg_send![window, setTabbingMode:0 /* NSWindowTabbingModeAutomatic */];
+            let _: () = msg_send![window, setTabbingIdentifier: *nsstring("kaku")];
+        } else {
+            let _: () = msg_send![window, setTabbingMode:2 /* NSWindowTabbingModeDisallowed */];
+        }
     }
 }
 
@@ -1901,6 +2412,7 @@ fn apply_decorations_to_window(
     decorations: WindowDecorations,
     integrated_title_button_style: IntegratedTitleButtonStyle,
     native_macos_fullscreen_mode: bool,
+    window_button_position: Option<MacOsWindowButtonPosition>,
 ) {
     let mask = decoration_to_mask(decorations, integrated_title_button_style);
     let decorations = effective_decorations(decorations, integrated_title_button_style);
@@ -1940,6 +2452,12 @@ fn apply_decorations_to_window(
             let _: () = msg_send![button, setHidden: hidden];
         }
 
+        if decorations.contains(WindowDecorations::INTEGRATED_BUTTONS) {
+            if let Some(position) = window_button_position {
+                position_window_buttons(window, position);
+            }
+        }
+
         window.setTitleVisibility_(if decorations.contains(WindowDecorations::TITLE) {
             appkit::NSWindowTitleVisibility::NSWindowTitleVisible
         } else {
@@ -1956,6 +2474,49 @@ fn apply_decorations_to_window(
     }
 }
 
+/// Moves the standard window buttons so that the top left of the
+/// close button is at `position`, measured in points from the top
+/// left of the window.  AppKit lays the buttons out again whenever
+/// the window is resized, so this needs to be re-applied from
+/// windowDidResize.
+fn position_window_buttons(window: &StrongPtr, position: MacOsWindowButtonPosition) {
+    unsafe {
+        let close = window.standardWindowButton_(appkit::NSWindowButton::NSWindowCloseButton);
+        let miniaturize =
+            window.standardWindowButton_(appkit::NSWindowButton::NSWindowMiniaturizeButton);
+        let zoom = window.standardWindowButton_(appkit::NSWindowButton::NSWindowZoomButton);
+        if close.is_null() || miniaturize.is_null() || zoom.is_null() {
+            return;
+        }
+
+        // The buttons live in a titlebar container view that spans
+        // the top of the window; resize it so that the buttons sit
+        // `position.y` points below the top edge.
+        let superview: id = msg_send![close, superview];
+        if superview.is_null() {
+            return;
+        }
+        let container: id = msg_send![superview, superview];
+        if container.is_null() {
+            return;
+        }
+
+        let close_frame = NSView::frame(close);
+        let spacing = NSView::frame(miniaturize).origin.x - close_frame.origin.x;
+        let window_frame = NSWindow::frame(**window);
+
+        let mut container_frame = NSView::frame(container);
+        container_frame.size.height = close_frame.size.height + position.y as CGFloat;
+        container_frame.origin.y = window_frame.size.height - container_frame.size.height;
+        let _: () = msg_send![container, setFrame: container_frame];
+
+        for (idx, button) in [close, miniaturize, zoom].iter().enumerate() {
+            let origin = NSPoint::new(position.x as CGFloat + idx as CGFloat * spacing, 0.);
+            let _: () = msg_send![*button, setFrameOrigin: origin];
+        }
+    }
+}
+
 fn decoration_to_mask(
     decorations: WindowDecorations,
     integrated_title_button_style: IntegratedTitleButtonStyle,
@@ -2122,6 +2683,8 @@ struct Inner {
     last_reported_dpi: Option<usize>,
     /// Last window state dispatched to the gui layer.
     last_reported_window_state: WindowState,
+    /// The key under which the geometry of the window is persisted
+    persistence_key: Option<String>,
 
     ime_text: String,
 }
@@ -2583,6 +3146,86 @@ mod tests {
             );
         }
     }
+
+    fn saved(x: isize, screen_id: u32, width: usize) -> PersistedWindowState {
+        PersistedWindowState {
+            window_geometry: Some(PersistedWindowSize { width, height: 600 }),
+            window_position: Some(PersistedWindowPosition {
+                x,
+                y: 100,
+                screen_id: Some(screen_id),
+            }),
+        }
+    }
+
+    #[test]
+    fn restore_on_connected_screen() {
+        let restore = restore_for(&saved(100, 1, 800), |id| id == 1, |_| true, None);
+        assert_eq!(
+            restore,
+            PersistedRestore {
+                position: Some(ScreenPoint::new(100, 100)),
+                size: Some(PersistedWindowSize {
+                    width: 800,
+                    height: 600
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn restore_skips_disconnected_screen() {
+        let restore = restore_for(&saved(100, 2, 800), |id| id == 1, |_| true, None);
+        assert_eq!(restore, PersistedRestore::default());
+    }
+
+    #[test]
+    fn restore_drops_hidden_position_and_clamps_size() {
+        let max = PersistedWindowSize {
+            width: 640,
+            height: 480,
+        };
+        let restore = restore_for(&saved(-5000, 1, 800), |_| true, |pos| pos.x >= 0, Some(max));
+        assert_eq!(restore.position, None);
+        assert_eq!(restore.size, Some(max));
+
+        let tiny = restore_for(&saved(100, 1, 50), |_| true, |_| true, Some(max));
+        assert_eq!(tiny.size, None);
+    }
+
+    #[test]
+    fn state_is_keyed_by_persistence_key() {
+        let mut state: PersistedState = serde_json::from_str(
+            r#"{"window_geometry": {"width": 800, "height": 600}, "window_position": null}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            state.window(None).unwrap().window_geometry,
+            Some(PersistedWindowSize {
+                width: 800,
+                height: 600
+            })
+        );
+        assert!(state.window(Some("kaku/default")).is_none());
+
+        state.window_mut(Some("kaku/default")).window_geometry = Some(PersistedWindowSize {
+            width: 1000,
+            height: 700,
+        });
+        let state: PersistedState =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(
+            state.window(Some("kaku/default")).unwrap().window_geometry,
+            Some(PersistedWindowSize {
+                width: 1000,
+                height: 700
+            })
+        );
+        assert_eq!(
+            state.window(None).unwrap().window_geometry.unwrap().width,
+            800
+        );
+    }
 }
 
 /// We register our own subclass of NSWindow so that we can override
@@ -2599,7 +3242,7 @@ fn get_window_class() -> &'static Class {
         }
 
         extern "C" fn redirect_toggle_fullscreen(this: &mut Object, _sel: Sel, sender: id) {
-            let content_view: id = unsafe { msg_send![this, contentView] };
+            let content_view = window_view_of(this);
             if !content_view.is_null() {
                 if let Some(window_view) = unsafe { WindowView::get_this(&*content_view) } {
                     let (window_id, use_native) = {
@@ -3140,12 +3783,17 @@ impl WindowView {
             modifiers = key_modifiers(nsevent.modifierFlags());
             screen_coords = NSEvent::mouseLocation(nsevent);
         }
+        let precise_scroll = matches!(
+            kind,
+            MouseEventKind::VertWheel(_) | MouseEventKind::HorzWheel(_)
+        ) && unsafe { nsevent.hasPreciseScrollingDeltas() } == YES;
         let event = MouseEvent {
             kind,
             coords: Point::new(coords.x as isize, coords.y as isize),
             screen_coords: cartesian_to_screen_point(screen_coords),
             mouse_buttons,
             modifiers,
+            precise_scroll,
         };
 
         if let Some(myself) = Self::get_this(this) {
@@ -3729,6 +4377,17 @@ impl WindowView {
         }
     }
 
+    extern "C" fn did_change_occlusion_state(this: &mut Object, _sel: Sel, _notification: id) {
+        if let Some(this) = Self::get_this(this) {
+            let mut inner = this.inner.borrow_mut();
+            let state: NSUInteger = unsafe { msg_send![*inner.window, occlusionState] };
+            if state & NSWindowOcclusionStateVisible != 0 {
+                inner.events.dispatch(WindowEvent::Exposed);
+                inner.events.dispatch(WindowEvent::NeedRepaint);
+            }
+        }
+    }
+
     extern "C" fn will_start_live_resize(this: &mut Object, _sel: Sel, _notification: id) {
         if let Some(this) = Self::get_this(this) {
             let mut inner = this.inner.borrow_mut();
@@ -3936,6 +4595,22 @@ impl WindowView {
 
             let live_resizing = inner.live_resizing;
 
+            if !is_full_screen
+                && inner
+                    .config
+                    .window_decorations
+                    .contains(WindowDecorations::INTEGRATED_BUTTONS)
+                && inner.config.integrated_title_button_style
+                    == IntegratedTitleButtonStyle::MacOsNative
+            {
+                if let (Some(position), Some(window)) = (
+                    inner.config.macos_window_button_position,
+                    inner.window.as_ref(),
+                ) {
+                    position_window_buttons(&window.load(), position);
+                }
+            }
+
             // Note: isZoomed can falsely return YES in situations such as
             // the current screen changing. We cannot detect that case here.
             // There is some logic to compensate for this in
@@ -4399,6 +5074,10 @@ impl WindowView {
                 sel!(windowDidChangeScreen:),
                 Self::did_change_screen as extern "C" fn(&mut Object, Sel, id),
             );
+            cls.add_method(
+                sel!(windowDidChangeOcclusionState:),
+                Self::did_change_occlusion_state as extern "C" fn(&mut Object, Sel, id),
+            );
             cls.add_method(
                 sel!(kakuPersistWindowStateAfterMove:),
                 Self::persist_window_state_after_move as extern "C" fn(&mut Object, Sel, id),
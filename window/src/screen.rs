@@ -13,6 +13,10 @@ pub struct Screens {
 pub struct ScreenInfo {
     pub name: String,
     pub rect: ScreenRect,
+    /// The part of the screen that isn't taken up by the menu bar,
+    /// the camera housing or the dock, in pixels, with the origin at
+    /// the top left of the primary screen
+    pub visible_rect: ScreenRect,
     pub scale: f64,
     pub max_fps: Option<usize>,
     pub effective_dpi: Option<f64>,